@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use prost::Message;
+use prost::{Message, VecPool};
 use std::error::Error;
 
 pub mod benchmarks {
@@ -39,13 +39,21 @@ where
 {
     let mut group = criterion.benchmark_group(&format!("dataset/{}", name));
 
+    group.bench_function("decode", move |b| {
+        let dataset = load_dataset(dataset).unwrap();
+        b.iter(|| {
+            for buf in &dataset.payload {
+                criterion::black_box(M::decode(buf.as_slice()).unwrap());
+            }
+        });
+    });
+
     group.bench_function("merge", move |b| {
         let dataset = load_dataset(dataset).unwrap();
         let mut message = M::default();
         b.iter(|| {
             for buf in &dataset.payload {
-                message.clear();
-                message.merge(buf.as_slice()).unwrap();
+                message.clear_and_merge(buf.as_slice()).unwrap();
                 criterion::black_box(&message);
             }
         });
@@ -70,6 +78,39 @@ where
         });
     });
 
+    group.bench_function("encode_to_vec", move |b| {
+        let messages = load_dataset(dataset)
+            .unwrap()
+            .payload
+            .iter()
+            .map(Vec::as_slice)
+            .map(M::decode)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        b.iter(|| {
+            for message in &messages {
+                criterion::black_box(message.encode_to_vec());
+            }
+        });
+    });
+
+    group.bench_function("encode_to_vec_in", move |b| {
+        let messages = load_dataset(dataset)
+            .unwrap()
+            .payload
+            .iter()
+            .map(Vec::as_slice)
+            .map(M::decode)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let pool = VecPool::new(1);
+        b.iter(|| {
+            for message in &messages {
+                criterion::black_box(message.encode_to_vec_in(&pool));
+            }
+        });
+    });
+
     group.bench_function("encoded_len", move |b| {
         let messages = load_dataset(dataset)
             .unwrap()
@@ -0,0 +1,29 @@
+use std::env;
+use std::path::PathBuf;
+
+/// The schemas of the `tests` crate which are checked against the C++ implementation.
+const SCHEMAS: &[&str] = &[
+    "groups.proto",
+    "nesting.proto",
+    "proto3_presence.proto",
+    "recursive_oneof.proto",
+    "well_known_types.proto",
+];
+
+fn main() {
+    let out_dir =
+        PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR environment variable not set"));
+    let src = PathBuf::from("../tests/src");
+    let protos: Vec<PathBuf> = SCHEMAS.iter().map(|schema| src.join(schema)).collect();
+
+    // Generate BTreeMap fields for all messages, so that re-encoding a message is deterministic
+    // and the outputs of both implementations can be compared byte for byte.
+    prost_build::Config::new()
+        .protoc_executable(protobuf::protoc())
+        .btree_map(["."])
+        .enable_type_names()
+        .file_descriptor_set_path(out_dir.join("schemas.bin"))
+        .include_file("schemas.rs")
+        .compile_protos(&protos, &[src])
+        .unwrap();
+}
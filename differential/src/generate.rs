@@ -0,0 +1,257 @@
+//! Generation of random wire-format inputs from message descriptors.
+
+use std::collections::HashMap;
+
+use prost::encoding::{encode_key, encode_varint, WireType};
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+use rand::distributions::{Distribution, Standard};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// The maximum nesting depth of generated messages.
+const MAX_DEPTH: u32 = 3;
+
+/// Characters from which strings are generated, including multi-byte characters.
+const CHARS: &[char] = &[
+    'a', 'z', '0', ' ', '"', '\\', '\n', '\0', 'é', 'ß', '€', '𝄞',
+];
+
+/// Generates random inputs of the messages of a file descriptor set.
+pub struct Generator<'a> {
+    /// The messages by fully-qualified name, with a leading dot.
+    messages: HashMap<String, &'a DescriptorProto>,
+    /// The values of the enums by fully-qualified name, with a leading dot.
+    enums: HashMap<String, Vec<i32>>,
+}
+
+impl<'a> Generator<'a> {
+    pub fn new(fds: &'a FileDescriptorSet) -> Generator<'a> {
+        let mut generator = Generator {
+            messages: HashMap::new(),
+            enums: HashMap::new(),
+        };
+        for file in &fds.file {
+            let scope = match file.package() {
+                "" => String::new(),
+                package => format!(".{}", package),
+            };
+            for message in &file.message_type {
+                generator.insert(&scope, message);
+            }
+            for enumeration in &file.enum_type {
+                generator.enums.insert(
+                    format!("{}.{}", scope, enumeration.name()),
+                    enumeration
+                        .value
+                        .iter()
+                        .map(|value| value.number())
+                        .collect(),
+                );
+            }
+        }
+        generator
+    }
+
+    fn insert(&mut self, scope: &str, message: &'a DescriptorProto) {
+        let name = format!("{}.{}", scope, message.name());
+        for nested in &message.nested_type {
+            self.insert(&name, nested);
+        }
+        for enumeration in &message.enum_type {
+            self.enums.insert(
+                format!("{}.{}", name, enumeration.name()),
+                enumeration
+                    .value
+                    .iter()
+                    .map(|value| value.number())
+                    .collect(),
+            );
+        }
+        self.messages.insert(name, message);
+    }
+
+    /// Generates an input of the message with the given full name.
+    pub fn generate(&self, message: &str, rng: &mut impl Rng) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.message(&format!(".{}", message), MAX_DEPTH, rng, &mut buf);
+        buf
+    }
+
+    /// Appends the fields of a random message to the buffer.
+    ///
+    /// Each field is present with even odds. The occurrences of all fields are shuffled, so that
+    /// inputs also exercise out-of-order fields, interleaved repeated fields, and singular fields
+    /// which occur more than once.
+    fn message(&self, name: &str, depth: u32, rng: &mut impl Rng, buf: &mut Vec<u8>) {
+        let descriptor = self.messages[name];
+        let mut occurrences = Vec::new();
+        for field in &descriptor.field {
+            let nested = matches!(field.r#type(), Type::Message | Type::Group);
+            if (nested && depth == 0) || !rng.gen_bool(0.5) {
+                continue;
+            }
+            let count = match field.label() {
+                Label::Repeated => rng.gen_range(1..=3),
+                _ if rng.gen_ratio(1, 8) => 2,
+                _ => 1,
+            };
+            if field.label() == Label::Repeated && packable(field.r#type()) && rng.gen() {
+                let mut values = Vec::new();
+                for _ in 0..count {
+                    self.value(field, depth, rng, &mut values);
+                }
+                let mut occurrence = Vec::new();
+                encode_key(
+                    field.number() as u32,
+                    WireType::LengthDelimited,
+                    &mut occurrence,
+                );
+                encode_varint(values.len() as u64, &mut occurrence);
+                occurrence.extend(values);
+                occurrences.push(occurrence);
+            } else {
+                for _ in 0..count {
+                    let mut occurrence = Vec::new();
+                    self.field(field, depth, rng, &mut occurrence);
+                    occurrences.push(occurrence);
+                }
+            }
+        }
+        occurrences.shuffle(rng);
+        buf.extend(occurrences.concat());
+    }
+
+    /// Appends a random occurrence of the field to the buffer, including its key.
+    fn field(
+        &self,
+        field: &FieldDescriptorProto,
+        depth: u32,
+        rng: &mut impl Rng,
+        buf: &mut Vec<u8>,
+    ) {
+        let tag = field.number() as u32;
+        let wire_type = match field.r#type() {
+            Type::Int32
+            | Type::Int64
+            | Type::Uint32
+            | Type::Uint64
+            | Type::Sint32
+            | Type::Sint64
+            | Type::Bool
+            | Type::Enum => WireType::Varint,
+            Type::Fixed64 | Type::Sfixed64 | Type::Double => WireType::SixtyFourBit,
+            Type::Fixed32 | Type::Sfixed32 | Type::Float => WireType::ThirtyTwoBit,
+            Type::String | Type::Bytes | Type::Message => WireType::LengthDelimited,
+            Type::Group => {
+                encode_key(tag, WireType::StartGroup, buf);
+                self.message(field.type_name(), depth - 1, rng, buf);
+                encode_key(tag, WireType::EndGroup, buf);
+                return;
+            }
+        };
+        encode_key(tag, wire_type, buf);
+        self.value(field, depth, rng, buf);
+    }
+
+    /// Appends a random value of the field to the buffer.
+    fn value(
+        &self,
+        field: &FieldDescriptorProto,
+        depth: u32,
+        rng: &mut impl Rng,
+        buf: &mut Vec<u8>,
+    ) {
+        match field.r#type() {
+            Type::Int32 => {
+                encode_varint(int::<i32>(rng, &[0, 1, -1, i32::MIN, i32::MAX]) as u64, buf)
+            }
+            Type::Int64 => {
+                encode_varint(int::<i64>(rng, &[0, 1, -1, i64::MIN, i64::MAX]) as u64, buf)
+            }
+            Type::Uint32 => encode_varint(int::<u32>(rng, &[0, 1, u32::MAX]).into(), buf),
+            Type::Uint64 => encode_varint(int::<u64>(rng, &[0, 1, u64::MAX]), buf),
+            Type::Sint32 => {
+                let value = int::<i32>(rng, &[0, 1, -1, i32::MIN, i32::MAX]);
+                encode_varint(((value << 1) ^ (value >> 31)) as u32 as u64, buf)
+            }
+            Type::Sint64 => {
+                let value = int::<i64>(rng, &[0, 1, -1, i64::MIN, i64::MAX]);
+                encode_varint(((value << 1) ^ (value >> 63)) as u64, buf)
+            }
+            Type::Bool => encode_varint(rng.gen_bool(0.5).into(), buf),
+            Type::Enum => {
+                // Only declared values are generated, since C++ keeps undeclared values of closed
+                // enums as unknown fields, which prost skips.
+                let value = self.enums[field.type_name()]
+                    .choose(rng)
+                    .copied()
+                    .unwrap_or(0);
+                encode_varint(value as u64, buf)
+            }
+            Type::Fixed32 => buf.extend(int::<u32>(rng, &[0, u32::MAX]).to_le_bytes()),
+            Type::Sfixed32 => buf.extend(int::<i32>(rng, &[0, i32::MIN, i32::MAX]).to_le_bytes()),
+            Type::Fixed64 => buf.extend(int::<u64>(rng, &[0, u64::MAX]).to_le_bytes()),
+            Type::Sfixed64 => buf.extend(int::<i64>(rng, &[0, i64::MIN, i64::MAX]).to_le_bytes()),
+            Type::Float => {
+                let value = special(
+                    rng,
+                    &[0.0, -0.0, f32::INFINITY, f32::NEG_INFINITY, f32::NAN],
+                )
+                .unwrap_or_else(|| f32::from_bits(rng.gen()));
+                // NaN payloads are not preserved by the text format used to drive C++.
+                let value = if value.is_nan() { f32::NAN } else { value };
+                buf.extend(value.to_le_bytes())
+            }
+            Type::Double => {
+                let value = special(
+                    rng,
+                    &[0.0, -0.0, f64::INFINITY, f64::NEG_INFINITY, f64::NAN],
+                )
+                .unwrap_or_else(|| f64::from_bits(rng.gen()));
+                let value = if value.is_nan() { f64::NAN } else { value };
+                buf.extend(value.to_le_bytes())
+            }
+            Type::String => {
+                let len = rng.gen_range(0..8);
+                let value: String = (0..len).map(|_| *CHARS.choose(rng).unwrap()).collect();
+                encode_varint(value.len() as u64, buf);
+                buf.extend(value.as_bytes());
+            }
+            Type::Bytes => {
+                let len = rng.gen_range(0..8);
+                encode_varint(len as u64, buf);
+                buf.extend((0..len).map(|_| rng.gen::<u8>()));
+            }
+            Type::Message => {
+                let mut message = Vec::new();
+                self.message(field.type_name(), depth - 1, rng, &mut message);
+                encode_varint(message.len() as u64, buf);
+                buf.extend(message);
+            }
+            Type::Group => unreachable!("groups are not values"),
+        }
+    }
+}
+
+/// Returns whether repeated fields of the type may be packed.
+fn packable(ty: Type) -> bool {
+    !matches!(ty, Type::String | Type::Bytes | Type::Message | Type::Group)
+}
+
+/// Returns one of the special values with odds of one in four.
+fn special<T: Copy>(rng: &mut impl Rng, values: &[T]) -> Option<T> {
+    if rng.gen_ratio(1, 4) {
+        values.choose(rng).copied()
+    } else {
+        None
+    }
+}
+
+/// Returns a random integer, which is one of the special values with odds of one in four.
+fn int<T: Copy>(rng: &mut impl Rng, values: &[T]) -> T
+where
+    Standard: Distribution<T>,
+{
+    special(rng, values).unwrap_or_else(|| rng.gen())
+}
@@ -0,0 +1,153 @@
+//! Differential testing of prost against the C++ protobuf implementation.
+//!
+//! Random inputs are generated in the wire format from the descriptors of schemas in this
+//! repository, and decoded by both implementations. The C++ implementation is driven through the
+//! `protoc` built from the vendored protobuf sources, which decodes and re-encodes messages with
+//! `--decode` and `--encode`. Both interpretations of an input are compared after re-encoding
+//! them with prost, so that any disagreement on the wire format shows up as different bytes.
+
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use prost::{Message, Name};
+use prost_types::FileDescriptorSet;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+mod generate;
+
+use crate::generate::Generator;
+
+#[allow(clippy::large_enum_variant)]
+pub mod schemas {
+    include!(concat!(env!("OUT_DIR"), "/schemas.rs"));
+}
+
+/// The path of the file descriptor set of the schemas.
+const FILE_DESCRIPTOR_SET_PATH: &str = concat!(env!("OUT_DIR"), "/schemas.bin");
+
+/// An input on which prost and the C++ implementation disagree.
+#[derive(Debug)]
+pub struct Mismatch {
+    /// The full name of the message type.
+    pub message: String,
+    /// The seed which generates the input.
+    pub seed: u64,
+    /// The generated input.
+    pub input: Vec<u8>,
+    description: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (message: {}, seed: {}, input: {:02x?})",
+            self.description, self.message, self.seed, self.input
+        )
+    }
+}
+
+impl Error for Mismatch {}
+
+/// Checks that prost and the C++ implementation agree on `cases` random inputs of the message
+/// type `M`, generated from the seeds `seed..seed + cases`.
+pub fn check<M>(seed: u64, cases: u64) -> Result<(), Mismatch>
+where
+    M: Name + Default,
+{
+    let fds =
+        FileDescriptorSet::decode(&include_bytes!(concat!(env!("OUT_DIR"), "/schemas.bin"))[..])
+            .expect("failed to decode the file descriptor set");
+    let generator = Generator::new(&fds);
+    let message = M::full_name();
+
+    for seed in seed..seed + cases {
+        let input = generator.generate(&message, &mut StdRng::seed_from_u64(seed));
+        if let Err(description) = check_input::<M>(&message, &input) {
+            return Err(Mismatch {
+                message,
+                seed,
+                input,
+                description,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Decodes the input with both implementations, and compares their re-encoded messages.
+fn check_input<M>(message: &str, input: &[u8]) -> Result<(), String>
+where
+    M: Message + Default,
+{
+    let expected = M::decode(input)
+        .map_err(|error| format!("prost failed to decode the input: {}", error))?
+        .encode_to_vec();
+
+    // Both implementations must interpret the input alike.
+    let actual = cpp_roundtrip::<M>(message, input)?;
+    if actual != expected {
+        return Err(format!(
+            "the implementations decode different messages: prost: {:02x?}, C++: {:02x?}",
+            expected, actual
+        ));
+    }
+
+    // The C++ implementation must interpret prost's encoding of the message as prost does.
+    let actual = cpp_roundtrip::<M>(message, &expected)?;
+    if actual != expected {
+        return Err(format!(
+            "C++ decodes the encoding of prost differently: prost: {:02x?}, C++: {:02x?}",
+            expected, actual
+        ));
+    }
+    Ok(())
+}
+
+/// Decodes the input with the C++ implementation, and returns its encoding as re-encoded by
+/// prost.
+fn cpp_roundtrip<M>(message: &str, input: &[u8]) -> Result<Vec<u8>, String>
+where
+    M: Message + Default,
+{
+    let text = protoc("--decode", message, input)
+        .map_err(|error| format!("C++ failed to decode the input: {}", error))?;
+    let output = protoc("--encode", message, &text)
+        .map_err(|error| format!("C++ failed to encode the message: {}", error))?;
+    M::decode(output.as_slice())
+        .map(|message| message.encode_to_vec())
+        .map_err(|error| format!("prost failed to decode the encoding of C++: {}", error))
+}
+
+/// Runs `protoc` in the given mode on the input, and returns its output.
+fn protoc(mode: &str, message: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(protobuf::protoc())
+        .arg(format!("--descriptor_set_in={}", FILE_DESCRIPTOR_SET_PATH))
+        .arg(format!("{}={}", mode, message))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("failed to run protoc: {}", error))?;
+
+    // Write the input from another thread, so that `protoc` can't block on a full stdout pipe.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+
+    let output = child
+        .wait_with_output()
+        .map_err(|error| format!("failed to run protoc: {}", error))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+    writer
+        .join()
+        .expect("failed to write to protoc")
+        .map_err(|error| format!("failed to write to protoc: {}", error))?;
+    Ok(output.stdout)
+}
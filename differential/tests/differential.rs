@@ -0,0 +1,63 @@
+#![cfg(not(target_os = "windows"))]
+
+use std::env;
+
+use differential::check;
+use differential::schemas::{groups, nesting, proto3, recursive_oneof, well_known_types};
+use prost::Name;
+
+/// The number of random inputs checked for each message type.
+const CASES: u64 = 64;
+
+/// Checks random inputs of the message type against the C++ implementation.
+///
+/// The first seed is taken from the `DIFFERENTIAL_SEED` environment variable, so that other inputs
+/// can be explored, and a reported mismatch can be reproduced.
+fn differential<M: Name + Default>() {
+    let seed = env::var("DIFFERENTIAL_SEED")
+        .ok()
+        .map(|seed| seed.parse().expect("invalid DIFFERENTIAL_SEED"))
+        .unwrap_or(0);
+    if let Err(mismatch) = check::<M>(seed, CASES) {
+        panic!(
+            "{}\n\nreproduce with DIFFERENTIAL_SEED={}",
+            mismatch, mismatch.seed
+        );
+    }
+}
+
+#[test]
+fn test_groups() {
+    differential::<groups::Test1>();
+    differential::<groups::Test2>();
+    differential::<groups::OneofGroup>();
+    // `NestedGroup` is left out, since its required recursive group can't be defaulted.
+    differential::<groups::NestedGroup2>();
+}
+
+#[test]
+fn test_nesting() {
+    differential::<nesting::A>();
+    differential::<nesting::B>();
+    differential::<nesting::C>();
+    differential::<nesting::D>();
+}
+
+#[test]
+fn test_proto3_presence() {
+    differential::<proto3::presence::A>();
+}
+
+#[test]
+fn test_recursive_oneof() {
+    differential::<recursive_oneof::A>();
+    differential::<recursive_oneof::B>();
+    differential::<recursive_oneof::C>();
+}
+
+#[test]
+fn test_well_known_types() {
+    differential::<well_known_types::Foo>();
+    differential::<well_known_types::Test>();
+    differential::<well_known_types::Bar>();
+}
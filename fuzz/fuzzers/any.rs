@@ -0,0 +1,54 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prost::{Message, Name};
+use prost_types::{Any, Duration, Timestamp};
+
+// Run with the type URL dictionary: `cargo fuzz run any -- -dict=dictionaries/any.dict`.
+fuzz_target!(|data: &[u8]| test_any(data));
+
+fn test_any(data: &[u8]) {
+    let Ok(any) = Any::decode(data) else {
+        return;
+    };
+    unpack(&any);
+}
+
+/// Unpacks the message with the types known to the target, as a type registry would.
+fn unpack(any: &Any) {
+    match any.type_name() {
+        Some("google.protobuf.Any") => {
+            if let Some(inner) = check::<Any>(any) {
+                unpack(&inner);
+            }
+        }
+        Some("google.protobuf.Duration") => {
+            check::<Duration>(any);
+        }
+        Some("google.protobuf.Timestamp") => {
+            check::<Timestamp>(any);
+        }
+        _ => {
+            assert!(!any.is::<Any>() && !any.is::<Duration>() && !any.is::<Timestamp>());
+            assert!(any.to_msg::<Duration>().is_err());
+        }
+    }
+}
+
+/// Checks that a message of type `M` which can be unpacked is packed again with the same type.
+fn check<M>(any: &Any) -> Option<M>
+where
+    M: Message + Name + Default,
+{
+    assert!(any.is::<M>());
+    let message = any.to_msg::<M>().ok()?;
+
+    let (domain, _) = any.type_url.rsplit_once('/').unwrap();
+    let repacked = Any::from_msg_with_domain(domain, &message).unwrap();
+    assert_eq!(repacked.type_name(), any.type_name());
+    assert_eq!(
+        repacked.to_msg::<M>().unwrap().encode_to_vec(),
+        message.encode_to_vec()
+    );
+    Some(message)
+}
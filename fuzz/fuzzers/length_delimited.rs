@@ -0,0 +1,54 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prost::Message;
+use protobuf::test_messages::proto3::TestAllTypesProto3;
+
+fuzz_target!(|data: &[u8]| test_length_delimited(data));
+
+fn test_length_delimited(data: &[u8]) {
+    // Decode the input as a stream of length-delimited messages, up to the first invalid one.
+    let mut buf = data;
+    let mut messages = Vec::new();
+    while !buf.is_empty() {
+        let start = buf;
+        let mut delimiter = buf;
+        let Ok(len) = prost::decode_length_delimiter(&mut delimiter) else {
+            break;
+        };
+        let Ok(message) = TestAllTypesProto3::decode_length_delimited(&mut buf) else {
+            break;
+        };
+        // The delimiter, which may be a non-minimal varint, is followed by exactly `len` bytes.
+        assert_eq!(start.len() - buf.len(), start.len() - delimiter.len() + len);
+        messages.push(message);
+    }
+
+    // Encoding the messages again produces a stream which decodes to the same messages, whether
+    // it is decoded from a buffer or from a reader.
+    let stream = encode_stream(&messages);
+
+    let mut buf = stream.as_slice();
+    let mut decoded = Vec::new();
+    while !buf.is_empty() {
+        decoded.push(TestAllTypesProto3::decode_length_delimited(&mut buf).unwrap());
+    }
+    assert_eq!(encode_stream(&decoded), stream);
+
+    let mut reader = stream.as_slice();
+    let mut read = Vec::new();
+    while let Some(message) =
+        TestAllTypesProto3::decode_length_delimited_from_reader(&mut reader, usize::MAX).unwrap()
+    {
+        read.push(message);
+    }
+    assert_eq!(encode_stream(&read), stream);
+}
+
+fn encode_stream(messages: &[TestAllTypesProto3]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for message in messages {
+        message.encode_length_delimited(&mut buf).unwrap();
+    }
+    buf
+}
@@ -30,6 +30,7 @@ sed -i -E "s~html_root_url = \"https://docs\.rs/${PROST_CRATE_MATCHER}/$VERSION_
   "$DIR/prost/src/lib.rs" \
   "$DIR/prost-derive/src/lib.rs" \
   "$DIR/prost-build/src/lib.rs" \
+  "$DIR/prost-build-test-utils/src/lib.rs" \
   "$DIR/prost-types/src/lib.rs"
 
 # Update Cargo.toml version fields.
@@ -41,6 +42,7 @@ sed -i -E "s/^${PROST_CRATE_MATCHER} = \{ version = \"${VERSION_MATCHER}\"/\1 =
   "$DIR/prost/Cargo.toml" \
   "$DIR/prost-derive/Cargo.toml" \
   "$DIR/prost-build/Cargo.toml" \
+  "$DIR/prost-build-test-utils/Cargo.toml" \
   "$DIR/prost-types/Cargo.toml"
 
 git commit -a -m "release ${VERSION}"
@@ -0,0 +1,145 @@
+#![doc(html_root_url = "https://docs.rs/prost-build-test-utils/0.13.4")]
+
+//! Helpers to compare the code generated with `prost-build` against fixture files.
+//!
+//! These are the helpers `prost-build` tests its own output with, for tools built on top of it,
+//! such as [`ServiceGenerator`](prost_build::ServiceGenerator) implementations: a
+//! [`TestCompiler`] generates code into a temporary directory, and
+//! [`assert_eq_fixture_file!`] compares a generated file with the expected one.
+//!
+//! When the generated code does not match, the fixture file is overwritten with the generated
+//! code before the assertion fails, so that expected output can be updated by running the tests
+//! again and reviewing the diff.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use prost_build_test_utils::{assert_eq_fixture_file, TestCompiler};
+//!
+//! let compiler = TestCompiler::new().unwrap();
+//! compiler
+//!     .compile(
+//!         prost_build::Config::new().btree_map(["."]),
+//!         &["tests/fixtures/hello.proto"],
+//!         &["tests/fixtures"],
+//!     )
+//!     .unwrap();
+//!
+//! assert_eq_fixture_file!(
+//!     "tests/fixtures/_expected_hello.rs",
+//!     compiler.out_dir().join("hello.rs")
+//! );
+//! ```
+
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use prost_build::Config;
+use tempfile::TempDir;
+
+/// Compiles `.proto` files into a temporary directory, removed when the compiler is dropped.
+#[derive(Debug)]
+pub struct TestCompiler {
+    out_dir: TempDir,
+}
+
+impl TestCompiler {
+    /// Creates a compiler with a new temporary output directory.
+    pub fn new() -> Result<TestCompiler> {
+        Ok(TestCompiler {
+            out_dir: tempfile::tempdir()?,
+        })
+    }
+
+    /// Returns the directory the generated files are written to.
+    pub fn out_dir(&self) -> &Path {
+        self.out_dir.path()
+    }
+
+    /// Compiles `protos` with the given configuration into the output directory, overriding the
+    /// output directory of the configuration.
+    pub fn compile(
+        &self,
+        config: &mut Config,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<()> {
+        config
+            .out_dir(self.out_dir())
+            .compile_protos(protos, includes)
+    }
+
+    /// Returns the path of a generated file, relative to the output directory.
+    pub fn generated_path(&self, file_name: impl AsRef<Path>) -> PathBuf {
+        self.out_dir().join(file_name)
+    }
+
+    /// Returns the contents of a generated file, with Windows line endings normalized.
+    pub fn generated(&self, file_name: impl AsRef<Path>) -> Result<String> {
+        read_normalized(self.generated_path(file_name))
+    }
+}
+
+/// Compares the contents of a generated file with a fixture file.
+///
+/// See [`assert_eq_fixture_contents()`] for details.
+#[track_caller]
+pub fn assert_eq_fixture_file(expected_path: impl AsRef<Path>, actual_path: impl AsRef<Path>) {
+    let actual_path = actual_path.as_ref();
+    let actual = read_normalized(actual_path).unwrap_or_else(|error| {
+        panic!("failed to read {}: {}", actual_path.display(), error);
+    });
+    assert_eq_fixture_contents(expected_path, &actual);
+}
+
+/// Compares generated code with the contents of a fixture file, ignoring the differences
+/// between Windows and Linux line endings.
+///
+/// If they differ, the fixture file is overwritten with the generated code, and the assertion
+/// fails. A missing fixture file is created.
+#[track_caller]
+pub fn assert_eq_fixture_contents(expected_path: impl AsRef<Path>, actual: &str) {
+    let expected_path = expected_path.as_ref();
+    let expected = read_normalized(expected_path).unwrap_or_default();
+
+    if expected != actual {
+        fs::write(expected_path, actual).unwrap_or_else(|error| {
+            panic!("failed to write {}: {}", expected_path.display(), error);
+        });
+    }
+
+    assert_eq!(
+        expected,
+        actual,
+        "generated code does not match {}",
+        expected_path.display()
+    );
+}
+
+/// Asserts that a generated file matches a fixture file.
+///
+/// Takes the path of the fixture file and the path of the generated file. See
+/// [`assert_eq_fixture_contents()`] for details.
+#[macro_export]
+macro_rules! assert_eq_fixture_file {
+    ($expected_path:expr, $actual_path:expr) => {
+        $crate::assert_eq_fixture_file($expected_path, $actual_path)
+    };
+}
+
+/// Asserts that generated code matches a fixture file.
+///
+/// Takes the path of the fixture file and the generated code. See
+/// [`assert_eq_fixture_contents()`] for details.
+#[macro_export]
+macro_rules! assert_eq_fixture_contents {
+    ($expected_path:expr, $actual:expr) => {
+        $crate::assert_eq_fixture_contents($expected_path, &$actual)
+    };
+}
+
+fn read_normalized(path: impl AsRef<Path>) -> Result<String> {
+    // Normalizes windows and Linux-style EOL
+    Ok(fs::read_to_string(path)?.replace("\r\n", "\n"))
+}
@@ -0,0 +1,9 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Greeting {
+    #[prost(string, tag="1")]
+    pub name: ::prost::alloc::string::String,
+}
+pub trait Greeter {
+    fn greet(&self, _: Greeting) -> Greeting;
+}
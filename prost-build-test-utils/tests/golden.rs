@@ -0,0 +1,56 @@
+use prost_build::{Config, Service, ServiceGenerator};
+use prost_build_test_utils::{assert_eq_fixture_contents, assert_eq_fixture_file, TestCompiler};
+
+/// Generates a trait with a method for each method of the service.
+struct TraitGenerator;
+
+impl ServiceGenerator for TraitGenerator {
+    fn generate(&mut self, service: Service, buf: &mut String) {
+        buf.push_str(&format!("pub trait {} {{\n", service.name));
+        for method in service.methods {
+            buf.push_str(&format!(
+                "    fn {}(&self, _: {}) -> {};\n",
+                method.name, method.input_type, method.output_type
+            ));
+        }
+        buf.push_str("}\n");
+    }
+}
+
+#[test]
+fn generated_code_matches_fixture() {
+    let compiler = TestCompiler::new().unwrap();
+    compiler
+        .compile(
+            Config::new().service_generator(Box::new(TraitGenerator)),
+            &["tests/fixtures/greeter.proto"],
+            &["tests/fixtures"],
+        )
+        .unwrap();
+
+    let expected = if cfg!(feature = "format") {
+        "tests/fixtures/_expected_greeter_formatted.rs"
+    } else {
+        "tests/fixtures/_expected_greeter.rs"
+    };
+    assert_eq_fixture_file!(expected, compiler.generated_path("greeter.rs"));
+    assert_eq_fixture_contents!(expected, compiler.generated("greeter.rs").unwrap());
+}
+
+#[test]
+fn mismatch_updates_fixture() {
+    let fixtures = tempfile::tempdir().unwrap();
+    let expected = fixtures.path().join("expected.rs");
+    std::fs::write(&expected, "pub struct Old;\r\n").unwrap();
+
+    let result = std::panic::catch_unwind(|| {
+        assert_eq_fixture_contents!(&expected, "pub struct New;\n");
+    });
+    assert!(result.is_err());
+    assert_eq!(
+        std::fs::read_to_string(&expected).unwrap(),
+        "pub struct New;\n"
+    );
+
+    assert_eq_fixture_contents!(&expected, "pub struct New;\n");
+}
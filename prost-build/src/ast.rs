@@ -1,9 +1,10 @@
 use once_cell::sync::Lazy;
-use prost_types::source_code_info::Location;
 #[cfg(feature = "cleanup-markdown")]
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
 use regex::Regex;
 
+use crate::source_info::Location;
+
 /// Comments on a Protobuf item.
 #[derive(Debug, Default, Clone)]
 pub struct Comments {
@@ -85,6 +86,21 @@ impl Comments {
         }
     }
 
+    /// Appends the leading comments to a buffer as inner doc comments (`//!`), documenting the
+    /// enclosing module.
+    ///
+    /// Each level of indentation corresponds to four space (' ') characters.
+    pub(crate) fn append_inner_with_indent(&self, indent_level: u8, buf: &mut String) {
+        for line in &self.leading {
+            for _ in 0..indent_level {
+                buf.push_str("    ");
+            }
+            buf.push_str("//!");
+            buf.push_str(&Self::sanitize_line(line));
+            buf.push('\n');
+        }
+    }
+
     /// Checks whether a RustDoc line should be indented.
     ///
     /// Lines should be indented if:
@@ -159,12 +175,174 @@ pub struct Method {
     pub output_proto_type: String,
     /// The method options.
     pub options: prost_types::MethodOptions,
+    /// Whether the method is marked as deprecated in its options.
+    pub deprecated: bool,
+    /// The encoded method options, including custom options.
+    ///
+    /// Custom options are extensions of `MethodOptions`, which are not kept by
+    /// [`options`](Self::options). They are only available when the file descriptor set is
+    /// loaded by the same [`Config`](crate::Config), with [`Config::compile_protos`] or
+    /// [`Config::load_fds`]; otherwise this is the encoding of [`options`](Self::options).
+    ///
+    /// [`Config::compile_protos`]: crate::Config::compile_protos
+    /// [`Config::load_fds`]: crate::Config::load_fds
+    pub options_bytes: Vec<u8>,
+    /// The path of the method in its `FileDescriptorProto`, as used by `SourceCodeInfo`.
+    pub source_path: Vec<i32>,
+    /// The span of the method definition in its .proto file, as the start line, start column,
+    /// end line (omitted if it is the start line) and end column, all zero-based.
+    ///
+    /// This is empty if source info is not available.
+    pub span: Vec<i32>,
     /// Identifies if client streams multiple client messages.
     pub client_streaming: bool,
     /// Identifies if server streams multiple server messages.
     pub server_streaming: bool,
 }
 
+/// The version of the [`ServiceDescriptorExt`] interface.
+///
+/// It is incremented when the meaning of an existing field changes, so that RPC code generators
+/// can detect a `prost-build` they are not compatible with. Fields added by later releases do not
+/// change the version.
+pub const SERVICE_DESCRIPTOR_EXT_VERSION: u32 = 1;
+
+/// The metadata of a service resolved by `prost-build`, for RPC code generators.
+///
+/// It is passed to [`ServiceGenerator::generate_with_descriptor`] along with the [`Service`], so
+/// that generators do not have to derive it from the Protobuf names or the generated code.
+///
+/// [`ServiceGenerator::generate_with_descriptor`]: crate::ServiceGenerator::generate_with_descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ServiceDescriptorExt {
+    /// The version of the interface, [`SERVICE_DESCRIPTOR_EXT_VERSION`].
+    pub version: u32,
+    /// The fully-qualified Protobuf name of the service, such as `helloworld.Greeter`.
+    pub full_name: String,
+    /// The path of the `prost` crate in the generated code, configured with
+    /// [`Config::prost_path`](crate::Config::prost_path).
+    ///
+    /// The request and response messages implement its `Message` trait, so that codecs should be
+    /// bounded by it.
+    pub prost_path: String,
+    /// The metadata of the methods, in the order of [`Service::methods`].
+    pub methods: Vec<MethodDescriptorExt>,
+}
+
+/// The metadata of a service method resolved by `prost-build`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MethodDescriptorExt {
+    /// The fully-qualified Protobuf name of the method, such as `helloworld.Greeter.SayHello`.
+    pub full_name: String,
+    /// The gRPC path of the method, such as `/helloworld.Greeter/SayHello`.
+    pub path: String,
+    /// The request message.
+    pub input: MessageTypeExt,
+    /// The response message.
+    pub output: MessageTypeExt,
+    /// The attributes configured for the method with
+    /// [`Config::method_attribute`](crate::Config::method_attribute).
+    pub attributes: Vec<String>,
+}
+
+/// The request or response message of a service method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MessageTypeExt {
+    /// The fully-qualified Protobuf name of the message, such as `.helloworld.HelloRequest`.
+    pub proto_name: String,
+    /// The Rust path of the message, relative to the module of the service.
+    pub rust_path: String,
+    /// Whether the message is an existing Rust type rather than a generated one, such as a type
+    /// mapped with [`Config::extern_path`](crate::Config::extern_path) or a well-known type of
+    /// `prost-types`.
+    pub is_extern: bool,
+    /// The type URL of the message, with the domain configured with
+    /// [`Config::type_name_domain`](crate::Config::type_name_domain).
+    pub type_url: String,
+}
+
+impl Service {
+    /// Appends the attributes derived from the service definition to a buffer with indentation.
+    ///
+    /// This is a `#[deprecated]` attribute if the service is deprecated, and a `#[doc(alias)]`
+    /// attribute with the Protobuf name if it differs from the Rust name by more than case.
+    ///
+    /// Each level of indentation corresponds to four space (' ') characters.
+    pub fn append_attributes(&self, indent_level: u8, buf: &mut String) {
+        append_attributes(
+            self.options.deprecated(),
+            &self.proto_name,
+            &self.name,
+            indent_level,
+            buf,
+        );
+    }
+}
+
+impl Method {
+    /// Appends the attributes derived from the method definition to a buffer with indentation.
+    ///
+    /// This is a `#[deprecated]` attribute if the method is deprecated, and a `#[doc(alias)]`
+    /// attribute with the Protobuf name if it differs from the Rust name by more than case.
+    ///
+    /// Each level of indentation corresponds to four space (' ') characters.
+    pub fn append_attributes(&self, indent_level: u8, buf: &mut String) {
+        append_attributes(
+            self.deprecated,
+            &self.proto_name,
+            &self.name,
+            indent_level,
+            buf,
+        );
+    }
+}
+
+fn append_attributes(
+    deprecated: bool,
+    proto_name: &str,
+    rust_name: &str,
+    indent_level: u8,
+    buf: &mut String,
+) {
+    append_deprecated(deprecated, indent_level, buf);
+    append_doc_alias(proto_name, rust_name, indent_level, buf);
+}
+
+/// Appends a `#[deprecated]` attribute with indentation if the item is deprecated in the
+/// Protobuf definition.
+pub(crate) fn append_deprecated(deprecated: bool, indent_level: u8, buf: &mut String) {
+    if deprecated {
+        push_indent(indent_level, buf);
+        buf.push_str("#[deprecated(note = \"marked as deprecated in the Protobuf definition\")]\n");
+    }
+}
+
+/// Appends a `#[doc(alias)]` attribute with indentation, so that the item can be found in
+/// rustdoc by its Protobuf name, if that differs from the Rust name by more than case.
+pub(crate) fn append_doc_alias(
+    proto_name: &str,
+    rust_name: &str,
+    indent_level: u8,
+    buf: &mut String,
+) {
+    if !rust_name
+        .trim_start_matches("r#")
+        .eq_ignore_ascii_case(proto_name)
+    {
+        push_indent(indent_level, buf);
+        buf.push_str(&format!("#[doc(alias = \"{}\")]\n", proto_name));
+    }
+}
+
+fn push_indent(indent_level: u8, buf: &mut String) {
+    for _ in 0..indent_level {
+        buf.push_str("    ");
+    }
+}
+
 #[cfg(not(feature = "cleanup-markdown"))]
 fn get_lines<S>(comments: S) -> Vec<String>
 where
@@ -1,24 +1,29 @@
 use std::ascii;
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::iter;
+use std::mem;
 
 use itertools::{Either, Itertools};
 use log::debug;
 use multimap::MultiMap;
+use prost::Message;
+use prost_types::descriptor_proto::ReservedRange;
 use prost_types::field_descriptor_proto::{Label, Type};
-use prost_types::source_code_info::Location;
 use prost_types::{
     DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
     FieldOptions, FileDescriptorProto, OneofDescriptorProto, ServiceDescriptorProto,
-    SourceCodeInfo,
 };
 
-use crate::ast::{Comments, Method, Service};
+use crate::ast::{
+    self, Comments, MessageTypeExt, Method, MethodDescriptorExt, Service, ServiceDescriptorExt,
+    SERVICE_DESCRIPTOR_EXT_VERSION,
+};
 use crate::extern_paths::ExternPaths;
-use crate::ident::{strip_enum_prefix, to_snake, to_upper_camel};
+use crate::ident::{to_json_name, to_snake_with, to_upper_camel_with, IdentPolicy};
 use crate::message_graph::MessageGraph;
-use crate::Config;
+use crate::source_info::Location;
+use crate::{ArchiveDerive, Config, CustomType, EnumVariantNaming, MapType};
 
 mod c_escaping;
 use c_escaping::unescape_c_escape_string;
@@ -30,13 +35,17 @@ pub struct CodeGenerator<'a> {
     config: &'a mut Config,
     package: String,
     type_path: Vec<String>,
-    source_info: Option<SourceCodeInfo>,
+    source_info: Option<Vec<Location>>,
     syntax: Syntax,
     message_graph: &'a MessageGraph,
     extern_paths: &'a ExternPaths,
+    duplicates: &'a HashSet<String>,
     depth: u8,
     path: Vec<i32>,
+    /// The configuration predicate of the type whose items are being generated.
+    item_cfg: Option<String>,
     buf: &'a mut String,
+    newtypes: &'a mut BTreeMap<String, String>,
 }
 
 fn push_indent(buf: &mut String, depth: u8) {
@@ -49,6 +58,73 @@ fn prost_path(config: &Config) -> &str {
     config.prost_path.as_deref().unwrap_or("::prost")
 }
 
+/// Appends the definitions of the newtypes used by a package's fields.
+pub fn append_newtypes(config: &Config, newtypes: &BTreeMap<String, String>, buf: &mut String) {
+    let string_type = format!("{}::alloc::string::String", prost_path(config));
+    for (name, ty) in newtypes {
+        buf.push_str(&format!(
+            "#[derive(Clone, {}PartialEq, Eq, Hash, PartialOrd, Ord, Default, Debug)]\n",
+            if *ty == string_type { "" } else { "Copy, " },
+        ));
+        buf.push_str(&format!("pub struct {}(pub {});\n", name, ty));
+        buf.push_str(&format!(
+            "impl ::core::convert::From<{ty}> for {name} {{\n    fn from(value: {ty}) -> Self {{\n        {name}(value)\n    }}\n}}\n",
+            name = name,
+            ty = ty,
+        ));
+        buf.push_str(&format!(
+            "impl ::core::convert::From<{name}> for {ty} {{\n    fn from(value: {name}) -> Self {{\n        value.0\n    }}\n}}\n",
+            name = name,
+            ty = ty,
+        ));
+    }
+}
+
+/// Appends the constants describing the Protobuf package and files a module is generated from.
+///
+/// `files` holds the name and the syntax of each file, in generation order.
+pub fn append_package_constants(package: &str, files: &[(String, String)], buf: &mut String) {
+    let names = files
+        .iter()
+        .map(|(name, _)| format!("{:?}", name))
+        .join(", ");
+    let syntax = files
+        .iter()
+        .map(|(_, syntax)| syntax.as_str())
+        .sorted()
+        .dedup()
+        .join(", ");
+
+    buf.push_str("/// The Protobuf package this module is generated from.\n");
+    buf.push_str(&format!("pub const PACKAGE: &str = {:?};\n", package));
+    buf.push_str("/// The Protobuf files this module is generated from.\n");
+    buf.push_str(&format!("pub const PROTO_FILES: &[&str] = &[{}];\n", names));
+    buf.push_str("/// The syntax of the Protobuf files this module is generated from.\n");
+    buf.push_str(&format!("pub const SYNTAX: &str = {:?};\n", syntax));
+}
+
+/// Returns the comments documenting the package of a file, which are the leading comments of its
+/// `package` statement.
+pub fn package_comments(config: &Config, file: &FileDescriptorProto) -> Option<Comments> {
+    // The field number of `package` in `FileDescriptorProto`.
+    const PACKAGE_PATH: &[i32] = &[2];
+
+    let fq_package_name = format!(".{}", file.package());
+    if config
+        .disable_comments
+        .get(&fq_package_name)
+        .next()
+        .is_some()
+    {
+        return None;
+    }
+    let location = Location::of_file(file)?
+        .into_iter()
+        .find(|location| location.path == PACKAGE_PATH)?;
+    let comments = Comments::from_location(&location);
+    (!comments.leading.is_empty()).then_some(comments)
+}
+
 struct Field {
     descriptor: FieldDescriptorProto,
     path_index: i32,
@@ -62,8 +138,8 @@ impl Field {
         }
     }
 
-    fn rust_name(&self) -> String {
-        to_snake(self.descriptor.name())
+    fn rust_name(&self, policy: &dyn IdentPolicy) -> String {
+        to_snake_with(policy, self.descriptor.name())
     }
 }
 
@@ -82,8 +158,8 @@ impl OneofField {
         }
     }
 
-    fn rust_name(&self) -> String {
-        to_snake(self.descriptor.name())
+    fn rust_name(&self, policy: &dyn IdentPolicy) -> String {
+        to_snake_with(policy, self.descriptor.name())
     }
 }
 
@@ -92,16 +168,18 @@ impl CodeGenerator<'_> {
         config: &mut Config,
         message_graph: &MessageGraph,
         extern_paths: &ExternPaths,
+        duplicates: &HashSet<String>,
         file: FileDescriptorProto,
         buf: &mut String,
+        newtypes: &mut BTreeMap<String, String>,
     ) {
-        let source_info = file.source_code_info.map(|mut s| {
-            s.location.retain(|loc| {
+        let source_info = Location::of_file(&file).map(|mut locations| {
+            locations.retain(|loc| {
                 let len = loc.path.len();
                 len > 0 && len % 2 == 0
             });
-            s.location.sort_by(|a, b| a.path.cmp(&b.path));
-            s
+            locations.sort_by(|a, b| a.path.cmp(&b.path));
+            locations
         });
 
         let mut code_gen = CodeGenerator {
@@ -112,9 +190,12 @@ impl CodeGenerator<'_> {
             syntax: file.syntax.as_deref().into(),
             message_graph,
             extern_paths,
+            duplicates,
             depth: 0,
+            item_cfg: None,
             path: Vec::new(),
             buf,
+            newtypes,
         };
 
         debug!(
@@ -139,7 +220,7 @@ impl CodeGenerator<'_> {
         }
         code_gen.path.pop();
 
-        if code_gen.config.service_generator.is_some() {
+        if code_gen.config.service_generator.is_some() || code_gen.config.service_descriptors {
             code_gen.path.push(6);
             for (idx, service) in file.service.into_iter().enumerate() {
                 code_gen.path.push(idx as i32);
@@ -161,8 +242,10 @@ impl CodeGenerator<'_> {
         let message_name = message.name().to_string();
         let fq_message_name = self.fq_name(&message_name);
 
-        // Skip external types.
-        if self.extern_paths.resolve_ident(&fq_message_name).is_some() {
+        // Skip external types, and types already generated by a merged package.
+        if self.extern_paths.resolve_ident(&fq_message_name).is_some()
+            || self.duplicates.contains(&fq_message_name)
+        {
             return;
         }
 
@@ -224,7 +307,41 @@ impl CodeGenerator<'_> {
             })
             .collect();
 
+        let presence_bits = self.presence_bits(&fq_message_name, &fields);
+        if !presence_bits.is_empty()
+            && (self.archive_derive(&fq_message_name, ArchiveDerive::Rkyv)
+                || self.archive_derive(&fq_message_name, ArchiveDerive::Zerocopy))
+        {
+            panic!(
+                "presence bitsets can not be used for message {}, which derives archive traits",
+                fq_message_name
+            );
+        }
+
+        let json_unknown_fields = self
+            .config
+            .json_unknown_fields
+            .get(&fq_message_name)
+            .next()
+            .is_some();
+        if json_unknown_fields
+            && (self.archive_derive(&fq_message_name, ArchiveDerive::Rkyv)
+                || self.archive_derive(&fq_message_name, ArchiveDerive::Zerocopy))
+        {
+            panic!(
+                "unknown JSON fields can not be retained by message {}, which derives archive traits",
+                fq_message_name
+            );
+        }
+
+        let type_cfg = self.type_cfg(&fq_message_name);
+        let outer_cfg = mem::replace(&mut self.item_cfg, type_cfg);
+
+        let deprecated = message.options.as_ref().is_some_and(|o| o.deprecated());
         self.append_doc(&fq_message_name, None);
+        self.append_deprecated(deprecated);
+        self.append_doc_alias(&message_name, &self.to_upper_camel(&message_name));
+        self.append_cfg(self.item_cfg.clone());
         self.append_type_attributes(&fq_message_name);
         self.append_message_attributes(&fq_message_name);
         self.push_indent();
@@ -237,10 +354,22 @@ impl CodeGenerator<'_> {
             },
             prost_path(self.config)
         ));
+        self.append_archive_derives(&fq_message_name, &fields, &map_types, &oneof_fields);
         self.append_skip_debug(&fq_message_name);
+        self.append_field_values(&fq_message_name);
+        if message
+            .options
+            .as_ref()
+            .is_some_and(|options| options.message_set_wire_format())
+        {
+            self.push_indent();
+            self.buf.push_str("#[prost(message_set_wire_format)]\n");
+        }
+        self.append_reserved(&message.reserved_range);
         self.push_indent();
-        self.buf.push_str("pub struct ");
-        self.buf.push_str(&to_upper_camel(&message_name));
+        self.buf.push_str(&self.type_visibility(&fq_message_name));
+        self.buf.push_str("struct ");
+        self.buf.push_str(&self.to_upper_camel(&message_name));
         self.buf.push_str(" {\n");
 
         self.depth += 1;
@@ -254,7 +383,11 @@ impl CodeGenerator<'_> {
                 .and_then(|type_name| map_types.get(type_name))
             {
                 Some((key, value)) => self.append_map_field(&fq_message_name, field, key, value),
-                None => self.append_field(&fq_message_name, field),
+                None => self.append_field(
+                    &fq_message_name,
+                    field,
+                    presence_bits.get(&field.descriptor.number()).copied(),
+                ),
             }
             self.path.pop();
         }
@@ -268,10 +401,91 @@ impl CodeGenerator<'_> {
         }
         self.path.pop();
 
+        if !presence_bits.is_empty() {
+            self.push_indent();
+            self.buf.push_str(
+                "#[prost(presence)]
+",
+            );
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "_presence: {}::PresenceBits<{}>,\n",
+                prost_path(self.config),
+                (presence_bits.len() + 31) / 32
+            ));
+        }
+
+        if json_unknown_fields {
+            self.push_indent();
+            self.buf.push_str(
+                "/// Members of the JSON object which don't name a field of the message.\n",
+            );
+            self.push_indent();
+            self.buf.push_str("#[prost(skip)]\n");
+            self.push_indent();
+            self.buf.push_str("#[serde(flatten)]\n");
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "pub json_unknown_fields: ::serde_json::Map<{}::alloc::string::String, ::serde_json::Value>,\n",
+                prost_path(self.config)
+            ));
+        }
+
         self.depth -= 1;
         self.push_indent();
         self.buf.push_str("}\n");
 
+        let oneof_from_impls = self
+            .config
+            .oneof_from_impls
+            .get(&fq_message_name)
+            .next()
+            .is_some();
+        if oneof_from_impls {
+            self.append_message_from_oneofs(
+                &message_name,
+                &fq_message_name,
+                &oneof_fields,
+                fields.len() + oneof_fields.len() > 1,
+            );
+        }
+
+        if self
+            .config
+            .serde_json_value_conversions
+            .get(&fq_message_name)
+            .next()
+            .is_some()
+        {
+            self.append_serde_json_value_conversions(&message_name, deprecated);
+        }
+
+        if self.config.convenience_conversions {
+            self.append_convenience_conversions(&message_name, deprecated);
+        }
+
+        if self
+            .config
+            .send_sync_assertions
+            .get(&fq_message_name)
+            .next()
+            .is_some()
+        {
+            self.append_send_sync_assertion(&message_name, deprecated);
+        }
+
+        if let Some(display_field) = self.config.display_fields.get_first(&fq_message_name) {
+            let display_field = display_field.clone();
+            self.append_display_from_field(
+                &message_name,
+                &fq_message_name,
+                &fields,
+                &display_field,
+                &presence_bits,
+                deprecated,
+            );
+        }
+
         if !message.enum_type.is_empty() || !nested_types.is_empty() || !oneof_fields.is_empty() {
             self.push_mod(&message_name);
             self.path.push(3);
@@ -292,21 +506,30 @@ impl CodeGenerator<'_> {
 
             for oneof in &oneof_fields {
                 self.append_oneof(&fq_message_name, oneof);
+                if oneof_from_impls {
+                    self.append_oneof_from_variants(&fq_message_name, oneof);
+                }
             }
 
             self.pop_mod();
         }
 
         if self.config.enable_type_names {
+            if deprecated {
+                self.buf.push_str("#[allow(deprecated)]\n");
+            }
             self.append_type_name(&message_name, &fq_message_name);
         }
+
+        self.item_cfg = outer_cfg;
     }
 
     fn append_type_name(&mut self, message_name: &str, fq_message_name: &str) {
+        self.append_cfg(self.item_cfg.clone());
         self.buf.push_str(&format!(
             "impl {}::Name for {} {{\n",
             self.config.prost_path.as_deref().unwrap_or("::prost"),
-            to_upper_camel(message_name)
+            self.to_upper_camel(message_name)
         ));
         self.depth += 1;
 
@@ -370,6 +593,32 @@ impl CodeGenerator<'_> {
         self.config.skip_debug.get(fq_message_name).next().is_some()
     }
 
+    fn append_reserved(&mut self, reserved_ranges: &[ReservedRange]) {
+        if !self.config.enforce_reserved || reserved_ranges.is_empty() {
+            return;
+        }
+        let ranges = reserved_ranges
+            .iter()
+            .map(|range| {
+                // Reserved ranges in the descriptor are exclusive of the end.
+                let (start, end) = (range.start(), range.end() - 1);
+                if start == end {
+                    start.to_string()
+                } else {
+                    format!("{}..={}", start, end)
+                }
+            })
+            .join(", ");
+        self.push_indent();
+        self.buf
+            .push_str(&format!("#[prost(reserved = \"{}\")]\n", ranges));
+        if let Some(callback) = &self.config.reserved_field_callback {
+            push_indent(self.buf, self.depth);
+            self.buf
+                .push_str(&format!("#[prost(reserved_callback = \"{}\")]\n", callback));
+        }
+    }
+
     fn append_skip_debug(&mut self, fq_message_name: &str) {
         if self.should_skip_debug(fq_message_name) {
             push_indent(self.buf, self.depth);
@@ -378,6 +627,238 @@ impl CodeGenerator<'_> {
         }
     }
 
+    fn append_field_values(&mut self, fq_message_name: &str) {
+        assert_eq!(b'.', fq_message_name.as_bytes()[0]);
+        if self
+            .config
+            .field_values
+            .get(fq_message_name)
+            .next()
+            .is_some()
+        {
+            push_indent(self.buf, self.depth);
+            self.buf.push_str("#[prost(field_values)]\n");
+        }
+    }
+
+    /// Returns whether the traits of the given framework are derived for a message.
+    fn archive_derive(&self, fq_message_name: &str, derive: ArchiveDerive) -> bool {
+        self.config
+            .archive_derives
+            .get(fq_message_name)
+            .any(|configured| *configured == derive)
+    }
+
+    /// Appends an attribute of a zero-copy serialization framework, only enabled with the
+    /// configured feature.
+    fn push_archive_attribute(&mut self, attribute: &str) {
+        self.push_indent();
+        match &self.config.archive_derive_feature {
+            Some(feature) => self.buf.push_str(&format!(
+                "#[cfg_attr(feature = \"{}\", {})]\n",
+                feature.escape_default(),
+                attribute
+            )),
+            None => self.buf.push_str(&format!("#[{}]\n", attribute)),
+        }
+    }
+
+    /// Returns whether a field of a message archived with rkyv refers back to the message, and
+    /// must not require the traits of its type to break the cycle.
+    fn rkyv_omits_bounds(&self, fq_message_name: &str, field: &FieldDescriptorProto) -> bool {
+        matches!(field.r#type(), Type::Message | Type::Group)
+            && self.std_time_type(field).is_none()
+            && self
+                .message_graph
+                .is_nested(field.type_name(), fq_message_name)
+    }
+
+    /// Panics if rkyv can not archive a field of a message.
+    fn check_rkyv_field(&self, fq_message_name: &str, field: &FieldDescriptorProto) {
+        let unsupported = if self
+            .config
+            .newtypes
+            .get_first_field(fq_message_name, field.name())
+            .is_some()
+        {
+            Some("newtype fields are not supported".to_string())
+        } else if let Some((_, ty)) = self.std_time_type(field) {
+            (ty == "::std::time::SystemTime").then(|| format!("{} is not supported", ty))
+        } else if matches!(field.r#type(), Type::Message | Type::Group)
+            && self.extern_paths.resolve_ident(field.type_name()).is_none()
+            && !self.archive_derive(field.type_name(), ArchiveDerive::Rkyv)
+        {
+            Some(format!(
+                "message {} does not derive the rkyv traits",
+                field.type_name()
+            ))
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            panic!(
+                "rkyv traits can not be derived for field {}.{}: {}",
+                fq_message_name,
+                field.name(),
+                unsupported
+            );
+        }
+    }
+
+    /// Panics if zerocopy can not map the fields of a message, or if its struct would have
+    /// padding.
+    fn check_zerocopy_fields(&self, fq_message_name: &str, fields: &[Field]) {
+        let mut size = 0;
+        let mut align = 1;
+        for field in fields {
+            let descriptor = &field.descriptor;
+            let field_size = match descriptor.r#type() {
+                Type::Int32
+                | Type::Uint32
+                | Type::Sint32
+                | Type::Fixed32
+                | Type::Sfixed32
+                | Type::Float
+                | Type::Enum => 4,
+                Type::Int64
+                | Type::Uint64
+                | Type::Sint64
+                | Type::Fixed64
+                | Type::Sfixed64
+                | Type::Double => 8,
+                _ => 0,
+            };
+            let newtype = self
+                .config
+                .newtypes
+                .get_first_field(fq_message_name, descriptor.name())
+                .is_some();
+            if field_size == 0
+                || descriptor.label() == Label::Repeated
+                || self.optional(descriptor)
+                || newtype
+            {
+                panic!(
+                    "zerocopy traits can not be derived for field {}.{}: only singular, \
+                     non-optional fixed-width numbers and enums are supported",
+                    fq_message_name,
+                    descriptor.name()
+                );
+            }
+            if size % field_size != 0 {
+                panic!(
+                    "zerocopy traits can not be derived for message {}: field {} would be \
+                     preceded by padding",
+                    fq_message_name,
+                    descriptor.name()
+                );
+            }
+            size += field_size;
+            align = align.max(field_size);
+        }
+        if size % align != 0 {
+            panic!(
+                "zerocopy traits can not be derived for message {}: its fields would be \
+                 followed by padding",
+                fq_message_name
+            );
+        }
+    }
+
+    /// Appends the derives configured with `Config::archive_derive` for a message, after
+    /// checking that the framework supports its fields.
+    fn append_archive_derives(
+        &mut self,
+        fq_message_name: &str,
+        fields: &[Field],
+        map_types: &HashMap<String, (FieldDescriptorProto, FieldDescriptorProto)>,
+        oneofs: &[OneofField],
+    ) {
+        if self.archive_derive(fq_message_name, ArchiveDerive::Rkyv) {
+            let mut omits_bounds = false;
+            for field in fields {
+                let descriptors = match field
+                    .descriptor
+                    .type_name
+                    .as_ref()
+                    .and_then(|type_name| map_types.get(type_name))
+                {
+                    Some((key, value)) => vec![key, value],
+                    None => vec![&field.descriptor],
+                };
+                for descriptor in descriptors {
+                    self.check_rkyv_field(fq_message_name, descriptor);
+                    omits_bounds |= self.rkyv_omits_bounds(fq_message_name, descriptor);
+                }
+            }
+            for field in oneofs.iter().flat_map(|oneof| &oneof.fields) {
+                self.check_rkyv_field(fq_message_name, &field.descriptor);
+            }
+            self.append_rkyv_derives(omits_bounds);
+        }
+
+        if self.archive_derive(fq_message_name, ArchiveDerive::Zerocopy) {
+            self.check_zerocopy_fields(fq_message_name, fields);
+            if let Some(oneof) = oneofs.first() {
+                panic!(
+                    "zerocopy traits can not be derived for field {}.{}: oneofs are not supported",
+                    fq_message_name,
+                    oneof.descriptor.name()
+                );
+            }
+            self.push_archive_attribute(
+                "derive(::zerocopy::FromBytes, ::zerocopy::IntoBytes, ::zerocopy::Immutable, \
+                 ::zerocopy::KnownLayout)",
+            );
+            self.push_archive_attribute("repr(C)");
+        }
+    }
+
+    /// Appends the rkyv derives of a oneof whose message is archived with rkyv.
+    fn append_oneof_archive_derives(&mut self, fq_message_name: &str, oneof: &OneofField) {
+        if self.archive_derive(fq_message_name, ArchiveDerive::Rkyv) {
+            let omits_bounds = oneof
+                .fields
+                .iter()
+                .any(|field| self.rkyv_omits_bounds(fq_message_name, &field.descriptor));
+            self.append_rkyv_derives(omits_bounds);
+        }
+    }
+
+    /// Appends the rkyv derives, with the bounds required by types with recursive fields. The
+    /// bounds are written without leading `::`, which would be printed as `:::` once formatted.
+    fn append_rkyv_derives(&mut self, omits_bounds: bool) {
+        self.push_archive_attribute(
+            "derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)",
+        );
+        if omits_bounds {
+            self.push_archive_attribute(
+                "rkyv(serialize_bounds(__S: rkyv::ser::Writer + rkyv::ser::Allocator, \
+                 __S::Error: rkyv::rancor::Source))",
+            );
+            self.push_archive_attribute(
+                "rkyv(deserialize_bounds(__D::Error: rkyv::rancor::Source))",
+            );
+            self.push_archive_attribute(
+                "rkyv(bytecheck(bounds(__C: rkyv::validation::ArchiveContext, \
+                 __C::Error: rkyv::rancor::Source)))",
+            );
+        }
+    }
+
+    /// Appends `#[rkyv(omit_bounds)]` to the recursive fields of messages archived with rkyv.
+    fn append_archive_field_attributes(
+        &mut self,
+        fq_message_name: &str,
+        field: &FieldDescriptorProto,
+    ) {
+        if self.archive_derive(fq_message_name, ArchiveDerive::Rkyv)
+            && self.rkyv_omits_bounds(fq_message_name, field)
+        {
+            self.push_archive_attribute("rkyv(omit_bounds)");
+        }
+    }
+
     fn append_enum_attributes(&mut self, fq_message_name: &str) {
         assert_eq!(b'.', fq_message_name.as_bytes()[0]);
         for attribute in self.config.enum_attributes.get(fq_message_name) {
@@ -387,6 +868,15 @@ impl CodeGenerator<'_> {
         }
     }
 
+    fn append_oneof_attributes(&mut self, fq_oneof_name: &str) {
+        assert_eq!(b'.', fq_oneof_name.as_bytes()[0]);
+        for attribute in self.config.oneof_attributes.get(fq_oneof_name) {
+            push_indent(self.buf, self.depth);
+            self.buf.push_str(attribute);
+            self.buf.push('\n');
+        }
+    }
+
     fn append_field_attributes(&mut self, fq_message_name: &str, field_name: &str) {
         assert_eq!(b'.', fq_message_name.as_bytes()[0]);
         for attribute in self
@@ -400,13 +890,110 @@ impl CodeGenerator<'_> {
         }
     }
 
-    fn append_field(&mut self, fq_message_name: &str, field: &Field) {
+    /// Returns the visibility of a message, enum or oneof, followed by a space unless private.
+    fn type_visibility(&self, fq_type_name: &str) -> String {
+        visibility(self.config.type_visibilities.get_first(fq_type_name))
+    }
+
+    /// Returns the visibility of a field or oneof, followed by a space unless private.
+    fn field_visibility(&self, fq_message_name: &str, field_name: &str) -> String {
+        visibility(
+            self.config
+                .field_visibilities
+                .get_first_field(fq_message_name, field_name),
+        )
+    }
+
+    /// Returns the configuration predicate under which a message or enum is compiled.
+    fn type_cfg(&self, fq_type_name: &str) -> Option<String> {
+        let cfgs = self.config.type_cfgs.get(fq_type_name).collect::<Vec<_>>();
+        match cfgs.as_slice() {
+            [] => None,
+            [cfg] => Some(cfg.to_string()),
+            cfgs => Some(format!("all({})", cfgs.iter().join(", "))),
+        }
+    }
+
+    /// Returns the configuration predicate under which a field or oneof variant is compiled.
+    fn field_cfg(&self, fq_message_name: &str, field_name: &str) -> Option<String> {
+        let cfgs = self
+            .config
+            .field_cfgs
+            .get_field(fq_message_name, field_name)
+            .collect::<Vec<_>>();
+        match cfgs.as_slice() {
+            [] => None,
+            [cfg] => Some(cfg.to_string()),
+            cfgs => Some(format!("all({})", cfgs.iter().join(", "))),
+        }
+    }
+
+    fn append_cfg(&mut self, cfg: Option<String>) {
+        if let Some(cfg) = cfg {
+            self.push_indent();
+            self.buf.push_str(&format!("#[cfg({})]\n", cfg));
+        }
+    }
+
+    /// Appends a `#[serde(with)]` attribute if the field has a custom type serialized with a
+    /// module.
+    fn append_serde_with(&mut self, field: &FieldDescriptorProto) {
+        let module = match self
+            .custom_type(field)
+            .and_then(CustomType::serde_with_module)
+        {
+            Some(module) => module.escape_default().to_string(),
+            None => return,
+        };
+        self.push_indent();
+        self.buf
+            .push_str(&format!("#[serde(with = \"{}\")]\n", module));
+    }
+
+    /// Appends a `#[serde(rename)]` attribute with the field's JSON name, if configured.
+    fn append_json_name(&mut self, fq_message_name: &str, field: &FieldDescriptorProto) {
+        if self
+            .config
+            .serde_json_names
+            .get_first_field(fq_message_name, field.name())
+            .is_none()
+        {
+            return;
+        }
+
+        let json_name = if self
+            .config
+            .json_use_proto_names
+            .get_first_field(fq_message_name, field.name())
+            .is_some()
+        {
+            field.name().to_string()
+        } else {
+            field
+                .json_name
+                .clone()
+                .unwrap_or_else(|| to_json_name(field.name()))
+        };
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "#[serde(rename = \"{}\")]\n",
+            json_name.escape_default()
+        ));
+    }
+
+    fn append_field(&mut self, fq_message_name: &str, field: &Field, presence_bit: Option<u32>) {
         let type_ = field.descriptor.r#type();
         let repeated = field.descriptor.label == Some(Label::Repeated as i32);
         let deprecated = self.deprecated(&field.descriptor);
-        let optional = self.optional(&field.descriptor);
+        // Fields whose presence is tracked by the bitset are stored inline.
+        let optional = self.optional(&field.descriptor) && presence_bit.is_none();
         let boxed = self.boxed(&field.descriptor, fq_message_name, None);
-        let ty = self.resolve_type(&field.descriptor, fq_message_name);
+        let mut ty = self.resolve_field_type(&field.descriptor, fq_message_name);
+        let newtype = self.newtype(fq_message_name, &field.descriptor, &ty);
+        if let Some(ref newtype) = newtype {
+            ty = format!("{}{}", "super::".repeat(self.type_path.len()), newtype);
+        }
 
         debug!(
             "    field: {:?}, type: {:?}, boxed: {}",
@@ -416,11 +1003,12 @@ impl CodeGenerator<'_> {
         );
 
         self.append_doc(fq_message_name, Some(field.descriptor.name()));
-
-        if deprecated {
-            self.push_indent();
-            self.buf.push_str("#[deprecated]\n");
-        }
+        self.append_deprecated(deprecated);
+        self.append_allow_deprecated_type(&field.descriptor);
+        self.append_doc_alias(
+            field.descriptor.name(),
+            &field.rust_name(self.ident_policy()),
+        );
 
         self.push_indent();
         self.buf.push_str("#[prost(");
@@ -442,6 +1030,9 @@ impl CodeGenerator<'_> {
             Label::Optional => {
                 if optional {
                     self.buf.push_str(", optional");
+                } else if let Some(bit) = presence_bit {
+                    self.buf
+                        .push_str(&format!(", optional, presence=\"{}\"", bit));
                 }
             }
             Label::Required => self.buf.push_str(", required"),
@@ -459,9 +1050,15 @@ impl CodeGenerator<'_> {
             }
         }
 
+        if self.closed_enum(fq_message_name, &field.descriptor) {
+            self.buf.push_str(", closed");
+        }
         if boxed {
             self.buf.push_str(", boxed");
         }
+        if newtype.is_some() {
+            self.buf.push_str(", newtype");
+        }
         self.buf.push_str(", tag=\"");
         self.buf.push_str(&field.descriptor.number().to_string());
 
@@ -476,20 +1073,9 @@ impl CodeGenerator<'_> {
                 }
                 self.buf.push_str("\\\"");
             } else if type_ == Type::Enum {
-                let mut enum_value = to_upper_camel(default);
-                if self.config.strip_enum_prefix {
-                    // Field types are fully qualified, so we extract
-                    // the last segment and strip it from the left
-                    // side of the default value.
-                    let enum_type = field
-                        .descriptor
-                        .type_name
-                        .as_ref()
-                        .and_then(|ty| ty.split('.').last())
-                        .unwrap();
-
-                    enum_value = strip_enum_prefix(&to_upper_camel(enum_type), &enum_value)
-                }
+                let enum_value = self
+                    .config
+                    .enum_variant_name(field.descriptor.type_name(), default);
                 self.buf.push_str(&enum_value);
             } else {
                 self.buf.push_str(&default.escape_default().to_string());
@@ -497,10 +1083,18 @@ impl CodeGenerator<'_> {
         }
 
         self.buf.push_str("\")]\n");
+        self.append_json_name(fq_message_name, &field.descriptor);
+        self.append_serde_with(&field.descriptor);
+        self.append_archive_field_attributes(fq_message_name, &field.descriptor);
         self.append_field_attributes(fq_message_name, field.descriptor.name());
+        self.append_cfg(self.field_cfg(fq_message_name, field.descriptor.name()));
         self.push_indent();
-        self.buf.push_str("pub ");
-        self.buf.push_str(&field.rust_name());
+        // The value and the presence bit are kept consistent by the generated accessors.
+        if presence_bit.is_none() {
+            self.buf
+                .push_str(&self.field_visibility(fq_message_name, field.descriptor.name()));
+        }
+        self.buf.push_str(&field.rust_name(self.ident_policy()));
         self.buf.push_str(": ");
 
         let prost_path = prost_path(self.config);
@@ -525,6 +1119,58 @@ impl CodeGenerator<'_> {
         self.buf.push_str(",\n");
     }
 
+    /// Returns the presence bits of the optional proto2 scalar fields of a message configured with
+    /// `Config::presence_bitsets`, keyed by field number and assigned in declaration order.
+    fn presence_bits(&self, fq_message_name: &str, fields: &[Field]) -> HashMap<i32, u32> {
+        if self.syntax != Syntax::Proto2
+            || self
+                .config
+                .presence_bitsets
+                .get(fq_message_name)
+                .next()
+                .is_none()
+        {
+            return HashMap::new();
+        }
+        fields
+            .iter()
+            .map(|field| &field.descriptor)
+            .filter(|field| {
+                field.label() == Label::Optional
+                    && !field.proto3_optional()
+                    && !matches!(
+                        field.r#type(),
+                        Type::String | Type::Bytes | Type::Message | Type::Group
+                    )
+                    && self
+                        .config
+                        .newtypes
+                        .get_first_field(fq_message_name, field.name())
+                        .is_none()
+            })
+            .zip(0..)
+            .map(|(field, bit)| (field.number(), bit))
+            .collect()
+    }
+
+    /// Returns the name of the newtype wrapping the field, if one is configured, and records
+    /// the newtype so its definition is generated alongside the package. The wrapped fields are
+    /// checked by `Config::generate` beforehand.
+    fn newtype(
+        &mut self,
+        fq_message_name: &str,
+        field: &FieldDescriptorProto,
+        ty: &str,
+    ) -> Option<String> {
+        let name = self
+            .config
+            .newtypes
+            .get_first_field(fq_message_name, field.name())?
+            .clone();
+        self.newtypes.insert(name.clone(), ty.to_string());
+        Some(name)
+    }
+
     fn append_map_field(
         &mut self,
         fq_message_name: &str,
@@ -543,6 +1189,12 @@ impl CodeGenerator<'_> {
         );
 
         self.append_doc(fq_message_name, Some(field.descriptor.name()));
+        self.append_deprecated(self.deprecated(&field.descriptor));
+        self.append_allow_deprecated_type(value);
+        self.append_doc_alias(
+            field.descriptor.name(),
+            &field.rust_name(self.ident_policy()),
+        );
         self.push_indent();
 
         let map_type = self
@@ -550,22 +1202,35 @@ impl CodeGenerator<'_> {
             .map_type
             .get_first_field(fq_message_name, field.descriptor.name())
             .copied()
-            .unwrap_or_default();
+            .unwrap_or(if self.config.no_std {
+                MapType::BTreeMap
+            } else {
+                MapType::HashMap
+            });
         let key_tag = self.field_type_tag(key);
         let value_tag = self.map_value_type_tag(value);
 
         self.buf.push_str(&format!(
-            "#[prost({}=\"{}, {}\", tag=\"{}\")]\n",
+            "#[prost({}=\"{}, {}\", tag=\"{}\"{})]\n",
             map_type.annotation(),
             key_tag,
             value_tag,
-            field.descriptor.number()
+            field.descriptor.number(),
+            if self.config.encode_default_map_values {
+                ", encode_default_values"
+            } else {
+                ""
+            },
         ));
+        self.append_json_name(fq_message_name, &field.descriptor);
+        self.append_archive_field_attributes(fq_message_name, value);
         self.append_field_attributes(fq_message_name, field.descriptor.name());
+        self.append_cfg(self.field_cfg(fq_message_name, field.descriptor.name()));
         self.push_indent();
         self.buf.push_str(&format!(
-            "pub {}: {}<{}, {}>,\n",
-            field.rust_name(),
+            "{}{}: {}<{}, {}>,\n",
+            self.field_visibility(fq_message_name, field.descriptor.name()),
+            field.rust_name(self.ident_policy()),
             map_type.rust_type(),
             key_ty,
             value_ty
@@ -580,29 +1245,71 @@ impl CodeGenerator<'_> {
     ) {
         let type_name = format!(
             "{}::{}",
-            to_snake(message_name),
-            to_upper_camel(oneof.descriptor.name())
+            self.to_snake(message_name),
+            self.to_upper_camel(oneof.descriptor.name())
         );
         self.append_doc(fq_message_name, None);
+        self.append_doc_alias(
+            oneof.descriptor.name(),
+            &oneof.rust_name(self.ident_policy()),
+        );
+        // The tags of conditional variants are only declared when the variants exist.
+        let oneof_name = format!("{}.{}", fq_message_name, oneof.descriptor.name());
+        let (unconditional, conditional): (Vec<_>, Vec<_>) = oneof
+            .fields
+            .iter()
+            .map(|field| {
+                (
+                    field.descriptor.number(),
+                    self.field_cfg(&oneof_name, field.descriptor.name()),
+                )
+            })
+            .partition(|(_, cfg)| cfg.is_none());
         self.push_indent();
-        self.buf.push_str(&format!(
-            "#[prost(oneof=\"{}\", tags=\"{}\")]\n",
-            type_name,
-            oneof
-                .fields
-                .iter()
-                .map(|field| field.descriptor.number())
-                .join(", "),
-        ));
-        self.append_field_attributes(fq_message_name, oneof.descriptor.name());
-        self.push_indent();
-        self.buf.push_str(&format!(
-            "pub {}: ::core::option::Option<{}>,\n",
-            oneof.rust_name(),
-            type_name
-        ));
-    }
-
+        if unconditional.is_empty() {
+            self.buf
+                .push_str(&format!("#[prost(oneof=\"{}\")]\n", type_name));
+        } else {
+            self.buf.push_str(&format!(
+                "#[prost(oneof=\"{}\", tags=\"{}\")]\n",
+                type_name,
+                unconditional.iter().map(|(tag, _)| tag).join(", "),
+            ));
+        }
+        for (tag, cfg) in &conditional {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "#[cfg_attr({}, prost(tags=\"{}\"))]\n",
+                cfg.as_deref().unwrap(),
+                tag
+            ));
+        }
+        self.append_field_attributes(fq_message_name, oneof.descriptor.name());
+        // A oneof whose variants are all conditional only exists along with one of them.
+        let mut cfg = self.field_cfg(fq_message_name, oneof.descriptor.name());
+        if unconditional.is_empty() && !conditional.is_empty() {
+            let any = format!(
+                "any({})",
+                conditional
+                    .iter()
+                    .map(|(_, cfg)| cfg.as_deref().unwrap())
+                    .join(", ")
+            );
+            cfg = Some(match cfg {
+                Some(cfg) => format!("all({}, {})", cfg, any),
+                None => any,
+            });
+        }
+        self.append_cfg(cfg);
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "{}{}: ::core::option::Option<{}>,\n",
+            self.field_visibility(fq_message_name, oneof.descriptor.name()),
+            oneof.rust_name(self.ident_policy()),
+            type_name
+        ));
+    }
+
     fn append_oneof(&mut self, fq_message_name: &str, oneof: &OneofField) {
         self.path.push(8);
         self.path.push(oneof.path_index);
@@ -611,8 +1318,13 @@ impl CodeGenerator<'_> {
         self.path.pop();
 
         let oneof_name = format!("{}.{}", fq_message_name, oneof.descriptor.name());
+        self.append_doc_alias(
+            oneof.descriptor.name(),
+            &self.to_upper_camel(oneof.descriptor.name()),
+        );
         self.append_type_attributes(&oneof_name);
         self.append_enum_attributes(&oneof_name);
+        self.append_oneof_attributes(&oneof_name);
         self.push_indent();
 
         let can_oneof_derive_copy = oneof.fields.iter().all(|field| {
@@ -624,10 +1336,14 @@ impl CodeGenerator<'_> {
             if can_oneof_derive_copy { "Copy, " } else { "" },
             prost_path(self.config)
         ));
+        self.append_oneof_archive_derives(fq_message_name, oneof);
         self.append_skip_debug(fq_message_name);
+        self.append_field_values(fq_message_name);
         self.push_indent();
-        self.buf.push_str("pub enum ");
-        self.buf.push_str(&to_upper_camel(oneof.descriptor.name()));
+        self.buf.push_str(&self.type_visibility(&oneof_name));
+        self.buf.push_str("enum ");
+        self.buf
+            .push_str(&self.to_upper_camel(oneof.descriptor.name()));
         self.buf.push_str(" {\n");
 
         self.path.push(2);
@@ -636,18 +1352,33 @@ impl CodeGenerator<'_> {
             self.path.push(field.path_index);
             self.append_doc(fq_message_name, Some(field.descriptor.name()));
             self.path.pop();
+            self.append_deprecated(self.deprecated(&field.descriptor));
+            self.append_allow_deprecated_type(&field.descriptor);
+            self.append_doc_alias(
+                field.descriptor.name(),
+                &self.to_upper_camel(field.descriptor.name()),
+            );
 
             self.push_indent();
             let ty_tag = self.field_type_tag(&field.descriptor);
             self.buf.push_str(&format!(
-                "#[prost({}, tag=\"{}\")]\n",
+                "#[prost({}{}, tag=\"{}\")]\n",
                 ty_tag,
+                if self.closed_enum(fq_message_name, &field.descriptor) {
+                    ", closed"
+                } else {
+                    ""
+                },
                 field.descriptor.number()
             ));
+            self.append_json_name(fq_message_name, &field.descriptor);
+            self.append_serde_with(&field.descriptor);
+            self.append_archive_field_attributes(fq_message_name, &field.descriptor);
             self.append_field_attributes(&oneof_name, field.descriptor.name());
+            self.append_cfg(self.field_cfg(&oneof_name, field.descriptor.name()));
 
             self.push_indent();
-            let ty = self.resolve_type(&field.descriptor, fq_message_name);
+            let ty = self.resolve_field_type(&field.descriptor, fq_message_name);
 
             let boxed = self.boxed(
                 &field.descriptor,
@@ -665,13 +1396,13 @@ impl CodeGenerator<'_> {
             if boxed {
                 self.buf.push_str(&format!(
                     "{}(::prost::alloc::boxed::Box<{}>),\n",
-                    to_upper_camel(field.descriptor.name()),
+                    self.to_upper_camel(field.descriptor.name()),
                     ty
                 ));
             } else {
                 self.buf.push_str(&format!(
                     "{}({}),\n",
-                    to_upper_camel(field.descriptor.name()),
+                    self.to_upper_camel(field.descriptor.name()),
                     ty
                 ));
             }
@@ -683,13 +1414,326 @@ impl CodeGenerator<'_> {
         self.buf.push_str("}\n");
     }
 
+    /// Returns the source type of the `From` conversion for each variant of the oneof, with the
+    /// variant name and the expression converting a `value` to the variant's inner type.
+    /// Deprecated and conditional variants are left out, but their types still count towards
+    /// ambiguity.
+    fn oneof_variant_sources(
+        &self,
+        fq_message_name: &str,
+        oneof: &OneofField,
+    ) -> Vec<(String, Option<(String, String)>)> {
+        let oneof_name = format!("{}.{}", fq_message_name, oneof.descriptor.name());
+        oneof
+            .fields
+            .iter()
+            .map(|field| {
+                let left_out = self.deprecated(&field.descriptor)
+                    || self
+                        .field_cfg(&oneof_name, field.descriptor.name())
+                        .is_some();
+                let variant = self.to_upper_camel(field.descriptor.name());
+                if field.descriptor.r#type() == Type::Enum {
+                    let ty = self.resolve_ident(field.descriptor.type_name());
+                    let conversion = (variant, "value as i32".to_string());
+                    return (ty, (!left_out).then_some(conversion));
+                }
+
+                let ty = self.resolve_field_type(&field.descriptor, fq_message_name);
+                let boxed = self.boxed(
+                    &field.descriptor,
+                    fq_message_name,
+                    Some(oneof.descriptor.name()),
+                );
+                let value = if boxed {
+                    format!("{}::alloc::boxed::Box::new(value)", prost_path(self.config))
+                } else {
+                    "value".to_string()
+                };
+                (ty, (!left_out).then_some((variant, value)))
+            })
+            .collect()
+    }
+
+    fn append_from_impl(&mut self, source: &str, target: &str, body: &str) {
+        self.append_cfg(self.item_cfg.clone());
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl ::core::convert::From<{}> for {} {{\n",
+            source, target
+        ));
+        self.depth += 1;
+        self.push_indent();
+        self.buf
+            .push_str(&format!("fn from(value: {}) -> Self {{\n", source));
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str(body);
+        self.buf.push('\n');
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+    }
+
+    fn append_serde_json_value_conversions(&mut self, message_name: &str, deprecated: bool) {
+        let rust_message_name = self.to_upper_camel(message_name);
+        if deprecated {
+            self.push_indent();
+            self.buf.push_str("#[allow(deprecated)]\n");
+        }
+        self.append_cfg(self.item_cfg.clone());
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl ::core::convert::TryFrom<::serde_json::Value> for {} {{\n",
+            rust_message_name
+        ));
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str("type Error = ::serde_json::Error;\n");
+        self.push_indent();
+        self.buf.push_str(
+            "fn try_from(value: ::serde_json::Value) -> ::core::result::Result<Self, Self::Error> {\n",
+        );
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str("::serde_json::from_value(value)\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        if deprecated {
+            self.push_indent();
+            self.buf.push_str("#[allow(deprecated)]\n");
+        }
+        // Messages only have string, integer and boolean map keys, which serialize to JSON.
+        self.append_from_impl(
+            &rust_message_name,
+            "::serde_json::Value",
+            &format!(
+                "::serde_json::to_value(value).expect(\"failed to convert {} to JSON\")",
+                rust_message_name
+            ),
+        );
+    }
+
+    /// Appends conversions decoding the message from a byte slice and encoding it to a vector.
+    fn append_convenience_conversions(&mut self, message_name: &str, deprecated: bool) {
+        let rust_message_name = self.to_upper_camel(message_name);
+        let prost_path = prost_path(self.config).to_string();
+        if deprecated {
+            self.push_indent();
+            self.buf.push_str("#[allow(deprecated)]\n");
+        }
+        self.append_cfg(self.item_cfg.clone());
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl<'a> ::core::convert::TryFrom<&'a [u8]> for {} {{\n",
+            rust_message_name
+        ));
+        self.depth += 1;
+        self.push_indent();
+        self.buf
+            .push_str(&format!("type Error = {}::DecodeError;\n", prost_path));
+        self.push_indent();
+        self.buf.push_str(
+            "fn try_from(buf: &'a [u8]) -> ::core::result::Result<Self, Self::Error> {\n",
+        );
+        self.depth += 1;
+        self.push_indent();
+        self.buf
+            .push_str(&format!("{}::Message::decode(buf)\n", prost_path));
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        if deprecated {
+            self.push_indent();
+            self.buf.push_str("#[allow(deprecated)]\n");
+        }
+        self.append_from_impl(
+            &format!("&{}", rust_message_name),
+            &format!("{}::alloc::vec::Vec<u8>", prost_path),
+            &format!("{}::Message::encode_to_vec(value)", prost_path),
+        );
+    }
+
+    /// Appends a constant which only compiles if the message is `Send`, `Sync` and `Unpin`.
+    fn append_send_sync_assertion(&mut self, message_name: &str, deprecated: bool) {
+        if deprecated {
+            self.push_indent();
+            self.buf.push_str("#[allow(deprecated)]\n");
+        }
+        self.append_cfg(self.item_cfg.clone());
+        self.push_indent();
+        self.buf.push_str("const _: fn() = || {\n");
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str(
+            "fn assert_send_sync_unpin<T: ::core::marker::Send + ::core::marker::Sync + ::core::marker::Unpin>() {}\n",
+        );
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "assert_send_sync_unpin::<{}>();\n",
+            self.to_upper_camel(message_name)
+        ));
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("};\n");
+    }
+
+    /// Appends a `Display` implementation formatting the value of the field configured with
+    /// `Config::display_from_field`.
+    fn append_display_from_field(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        fields: &[Field],
+        display_field: &str,
+        presence_bits: &HashMap<i32, u32>,
+        deprecated: bool,
+    ) {
+        let field = fields
+            .iter()
+            .find(|field| field.descriptor.name() == display_field)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Display can not be generated for message {}: it has no field {} outside of \
+                     a oneof",
+                    fq_message_name, display_field
+                )
+            });
+        let formattable = !matches!(
+            field.descriptor.r#type(),
+            Type::Bytes | Type::Enum | Type::Message | Type::Group
+        );
+        if !formattable
+            || field.descriptor.label() == Label::Repeated
+            || self
+                .config
+                .newtypes
+                .get_first_field(fq_message_name, display_field)
+                .is_some()
+        {
+            panic!(
+                "Display can not be generated for message {} from field {}: only singular \
+                 string, numeric and bool fields are supported",
+                fq_message_name, display_field
+            );
+        }
+
+        // Optional fields are formatted through their getter, which returns the default value
+        // if they are unset.
+        let rust_name = field.rust_name(self.ident_policy());
+        let value = if self.optional(&field.descriptor)
+            || presence_bits.contains_key(&field.descriptor.number())
+        {
+            format!("&self.{}()", rust_name)
+        } else {
+            format!("&self.{}", rust_name)
+        };
+
+        if deprecated {
+            self.push_indent();
+            self.buf.push_str("#[allow(deprecated)]\n");
+        }
+        self.append_cfg(self.item_cfg.clone());
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl ::core::fmt::Display for {} {{\n",
+            self.to_upper_camel(message_name)
+        ));
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str(
+            "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
+        );
+        self.depth += 1;
+        self.push_indent();
+        self.buf
+            .push_str(&format!("::core::fmt::Display::fmt({}, f)\n", value));
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+    }
+
+    fn append_oneof_from_variants(&mut self, fq_message_name: &str, oneof: &OneofField) {
+        let oneof_name = self.to_upper_camel(oneof.descriptor.name());
+        let sources = self.oneof_variant_sources(fq_message_name, oneof);
+        for (ty, conversion) in &sources {
+            let Some((variant, value)) = conversion else {
+                continue;
+            };
+            if sources.iter().filter(|(other, _)| other == ty).count() > 1 {
+                continue;
+            }
+            self.append_from_impl(ty, &oneof_name, &format!("Self::{}({})", variant, value));
+        }
+    }
+
+    fn append_message_from_oneofs(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        oneof_fields: &[OneofField],
+        has_other_fields: bool,
+    ) {
+        let rust_message_name = self.to_upper_camel(message_name);
+        let sources = oneof_fields
+            .iter()
+            .flat_map(|oneof| {
+                self.oneof_variant_sources(fq_message_name, oneof)
+                    .into_iter()
+                    .map(move |source| (oneof, source))
+            })
+            .collect::<Vec<_>>();
+        for (oneof, (ty, conversion)) in &sources {
+            let Some((variant, value)) = conversion else {
+                continue;
+            };
+            // `From<T> for T` is already implemented for recursive messages.
+            if *ty == rust_message_name
+                || self
+                    .field_cfg(fq_message_name, oneof.descriptor.name())
+                    .is_some()
+                || sources.iter().filter(|(_, (other, _))| other == ty).count() > 1
+            {
+                continue;
+            }
+            let body = format!(
+                "Self {{ {}: ::core::option::Option::Some({}::{}::{}({})){} }}",
+                oneof.rust_name(self.ident_policy()),
+                self.to_snake(message_name),
+                self.to_upper_camel(oneof.descriptor.name()),
+                variant,
+                value,
+                if has_other_fields {
+                    ", ..::core::default::Default::default()"
+                } else {
+                    ""
+                },
+            );
+            self.append_from_impl(ty, &rust_message_name, &body);
+        }
+    }
+
     fn location(&self) -> Option<&Location> {
         let source_info = self.source_info.as_ref()?;
         let idx = source_info
-            .location
             .binary_search_by_key(&&self.path[..], |location| &location.path[..])
             .unwrap();
-        Some(&source_info.location[idx])
+        Some(&source_info[idx])
     }
 
     fn append_doc(&mut self, fq_name: &str, field_name: Option<&str>) {
@@ -712,7 +1756,8 @@ impl CodeGenerator<'_> {
         debug!("  enum: {:?}", desc.name());
 
         let proto_enum_name = desc.name();
-        let enum_name = to_upper_camel(proto_enum_name);
+        let enum_name = self.to_upper_camel(proto_enum_name);
+        let deprecated = desc.options.as_ref().is_some_and(|o| o.deprecated());
 
         let enum_values = &desc.value;
         let fq_proto_enum_name = self.fq_name(proto_enum_name);
@@ -721,13 +1766,25 @@ impl CodeGenerator<'_> {
             .extern_paths
             .resolve_ident(&fq_proto_enum_name)
             .is_some()
+            || self.duplicates.contains(&fq_proto_enum_name)
         {
             return;
         }
 
+        let type_cfg = self.type_cfg(&fq_proto_enum_name);
+
         self.append_doc(&fq_proto_enum_name, None);
+        self.append_deprecated(deprecated);
+        self.append_doc_alias(proto_enum_name, &enum_name);
+        self.append_cfg(type_cfg.clone());
         self.append_type_attributes(&fq_proto_enum_name);
         self.append_enum_attributes(&fq_proto_enum_name);
+        if self.config.enum_variant_renamer.is_none()
+            && self.config.enum_variant_naming == EnumVariantNaming::Original
+        {
+            self.push_indent();
+            self.buf.push_str("#[allow(non_camel_case_types)]\n");
+        }
         self.push_indent();
         let dbg = if self.should_skip_debug(&fq_proto_enum_name) {
             ""
@@ -742,12 +1799,16 @@ impl CodeGenerator<'_> {
         self.push_indent();
         self.buf.push_str("#[repr(i32)]\n");
         self.push_indent();
-        self.buf.push_str("pub enum ");
+        self.buf
+            .push_str(&self.type_visibility(&fq_proto_enum_name));
+        self.buf.push_str("enum ");
         self.buf.push_str(&enum_name);
         self.buf.push_str(" {\n");
 
-        let variant_mappings =
-            build_enum_value_mappings(&enum_name, self.config.strip_enum_prefix, enum_values);
+        let variant_mappings = build_enum_value_mappings(enum_values, |value_name| {
+            self.config
+                .enum_variant_name(&fq_proto_enum_name, value_name)
+        });
 
         self.depth += 1;
         self.path.push(2);
@@ -755,6 +1816,8 @@ impl CodeGenerator<'_> {
             self.path.push(variant.path_idx as i32);
 
             self.append_doc(&fq_proto_enum_name, Some(variant.proto_name));
+            self.append_deprecated(variant.deprecated);
+            self.append_doc_alias(variant.proto_name, &variant.generated_variant_name);
             self.append_field_attributes(&fq_proto_enum_name, variant.proto_name);
             self.push_indent();
             self.buf.push_str(&variant.generated_variant_name);
@@ -771,6 +1834,11 @@ impl CodeGenerator<'_> {
         self.push_indent();
         self.buf.push_str("}\n");
 
+        if deprecated || variant_mappings.iter().any(|variant| variant.deprecated) {
+            self.push_indent();
+            self.buf.push_str("#[allow(deprecated)]\n");
+        }
+        self.append_cfg(type_cfg);
         self.push_indent();
         self.buf.push_str("impl ");
         self.buf.push_str(&enum_name);
@@ -865,6 +1933,11 @@ impl CodeGenerator<'_> {
             .map(Comments::from_location)
             .unwrap_or_default();
 
+        let fq_service_name = match self.package.as_str() {
+            "" => format!(".{}", name),
+            package => format!(".{}.{}", package, name),
+        };
+
         self.path.push(2);
         let methods = service
             .method
@@ -878,9 +1951,22 @@ impl CodeGenerator<'_> {
                     .location()
                     .map(Comments::from_location)
                     .unwrap_or_default();
+                let span = self
+                    .location()
+                    .map(|location| location.span.clone())
+                    .unwrap_or_default();
+                let source_path = self.path.clone();
                 self.path.pop();
 
                 let name = method.name.take().unwrap();
+                let options = method.options.take().unwrap_or_default();
+                let fq_method_name = format!("{}.{}", fq_service_name, name);
+                let options_bytes = self
+                    .config
+                    .raw_method_options
+                    .get(&fq_method_name)
+                    .cloned()
+                    .unwrap_or_else(|| options.encode_to_vec());
                 let input_proto_type = method.input_type.take().unwrap();
                 let output_proto_type = method.output_type.take().unwrap();
                 let input_type = self.resolve_ident(&input_proto_type);
@@ -889,14 +1975,18 @@ impl CodeGenerator<'_> {
                 let server_streaming = method.server_streaming();
 
                 Method {
-                    name: to_snake(&name),
+                    name: self.to_snake(&name),
                     proto_name: name,
                     comments,
                     input_type,
                     output_type,
                     input_proto_type,
                     output_proto_type,
-                    options: method.options.unwrap_or_default(),
+                    deprecated: options.deprecated(),
+                    options,
+                    options_bytes,
+                    source_path,
+                    span,
                     client_streaming,
                     server_streaming,
                 }
@@ -905,7 +1995,7 @@ impl CodeGenerator<'_> {
         self.path.pop();
 
         let service = Service {
-            name: to_upper_camel(&name),
+            name: self.to_upper_camel(&name),
             proto_name: name,
             package: self.package.clone(),
             comments,
@@ -913,9 +2003,202 @@ impl CodeGenerator<'_> {
             options: service.options.unwrap_or_default(),
         };
 
-        if let Some(service_generator) = self.config.service_generator.as_mut() {
-            service_generator.generate(service, self.buf)
+        if self.config.service_descriptors {
+            self.append_service_descriptor(&service);
+        }
+
+        if self.config.service_generator.is_some() {
+            let descriptor = self.service_descriptor_ext(&service);
+            if let Some(service_generator) = self.config.service_generator.as_mut() {
+                service_generator.generate_with_descriptor(service, &descriptor, self.buf)
+            }
+        }
+    }
+
+    /// Returns the metadata of a service for the service generator.
+    fn service_descriptor_ext(&self, service: &Service) -> ServiceDescriptorExt {
+        let full_name = if service.package.is_empty() {
+            service.proto_name.clone()
+        } else {
+            format!("{}.{}", service.package, service.proto_name)
+        };
+        let message_type = |proto_name: &str, rust_path: &str| MessageTypeExt {
+            proto_name: proto_name.to_string(),
+            rust_path: rust_path.to_string(),
+            is_extern: self.extern_paths.resolve_ident(proto_name).is_some(),
+            type_url: self.type_url(proto_name),
+        };
+        let methods = service
+            .methods
+            .iter()
+            .map(|method| {
+                let fq_method_name = format!(".{}.{}", full_name, method.proto_name);
+                MethodDescriptorExt {
+                    full_name: fq_method_name[1..].to_string(),
+                    path: format!("/{}/{}", full_name, method.proto_name),
+                    input: message_type(&method.input_proto_type, &method.input_type),
+                    output: message_type(&method.output_proto_type, &method.output_type),
+                    attributes: self
+                        .config
+                        .method_attributes
+                        .get(&fq_method_name)
+                        .cloned()
+                        .collect(),
+                }
+            })
+            .collect();
+
+        ServiceDescriptorExt {
+            version: SERVICE_DESCRIPTOR_EXT_VERSION,
+            full_name,
+            prost_path: prost_path(self.config).to_string(),
+            methods,
+        }
+    }
+
+    /// Appends a module with the constants describing a service.
+    fn append_service_descriptor(&mut self, service: &Service) {
+        let full_name = if service.package.is_empty() {
+            service.proto_name.clone()
+        } else {
+            format!("{}.{}", service.package, service.proto_name)
+        };
+        let path = |method: &Method| format!("/{}/{}", full_name, method.proto_name);
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "/// Constants describing the `{}` service.\n",
+            full_name
+        ));
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub mod {} {{\n",
+            self.to_snake(&format!("{}Descriptor", service.proto_name))
+        ));
+        self.depth += 1;
+
+        self.push_indent();
+        self.buf
+            .push_str("/// The fully-qualified name of the service.\n");
+        self.push_indent();
+        self.buf
+            .push_str(&format!("pub const NAME: &str = {:?};\n", full_name));
+        self.push_indent();
+        self.buf
+            .push_str("/// The names of the methods of the service.\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const METHOD_NAMES: &[&str] = &[{}];\n",
+            service
+                .methods
+                .iter()
+                .map(|method| format!("{:?}", method.proto_name))
+                .join(", ")
+        ));
+        self.push_indent();
+        self.buf
+            .push_str("/// The gRPC paths of the methods of the service.\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const METHOD_PATHS: &[&str] = &[{}];\n",
+            service
+                .methods
+                .iter()
+                .map(|method| format!("{:?}", path(method)))
+                .join(", ")
+        ));
+
+        for method in &service.methods {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "/// Constants describing the `{}` method.\n",
+                method.proto_name
+            ));
+            self.push_indent();
+            self.buf.push_str(&format!("pub mod {} {{\n", method.name));
+            self.depth += 1;
+
+            let constants = [
+                (
+                    "The name of the method.",
+                    "NAME",
+                    "&str",
+                    format!("{:?}", method.proto_name),
+                ),
+                (
+                    "The gRPC path of the method.",
+                    "PATH",
+                    "&str",
+                    format!("{:?}", path(method)),
+                ),
+                (
+                    "The type URL of the request message.",
+                    "INPUT_TYPE_URL",
+                    "&str",
+                    format!("{:?}", self.type_url(&method.input_proto_type)),
+                ),
+                (
+                    "The type URL of the response message.",
+                    "OUTPUT_TYPE_URL",
+                    "&str",
+                    format!("{:?}", self.type_url(&method.output_proto_type)),
+                ),
+                (
+                    "Whether the client sends a stream of requests.",
+                    "CLIENT_STREAMING",
+                    "bool",
+                    method.client_streaming.to_string(),
+                ),
+                (
+                    "Whether the server sends a stream of responses.",
+                    "SERVER_STREAMING",
+                    "bool",
+                    method.server_streaming.to_string(),
+                ),
+            ];
+            for (doc, name, ty, value) in constants {
+                self.push_indent();
+                self.buf.push_str(&format!("/// {}\n", doc));
+                self.push_indent();
+                self.buf
+                    .push_str(&format!("pub const {}: {} = {};\n", name, ty, value));
+            }
+
+            self.depth -= 1;
+            self.push_indent();
+            self.buf.push_str("}\n");
         }
+
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+    }
+
+    /// Returns the type URL of a message with the given fully-qualified name, with the domain
+    /// configured with `type_name_domain`.
+    fn type_url(&self, fq_message_name: &str) -> String {
+        let domain_name = self
+            .config
+            .type_name_domains
+            .get_first(fq_message_name)
+            .map_or("", |name| name.as_str());
+        format!(
+            "{}/{}",
+            domain_name,
+            fq_message_name.trim_start_matches('.')
+        )
+    }
+
+    fn ident_policy(&self) -> &dyn IdentPolicy {
+        self.config.ident_policy.as_ref()
+    }
+
+    fn to_snake(&self, name: &str) -> String {
+        to_snake_with(self.ident_policy(), name)
+    }
+
+    fn to_upper_camel(&self, name: &str) -> String {
+        to_upper_camel_with(self.ident_policy(), name)
     }
 
     fn push_indent(&mut self) {
@@ -928,9 +2211,10 @@ impl CodeGenerator<'_> {
         self.buf.push_str(module);
         self.buf.push_str("`.\n");
 
+        self.append_cfg(self.item_cfg.clone());
         self.push_indent();
         self.buf.push_str("pub mod ");
-        self.buf.push_str(&to_snake(module));
+        self.buf.push_str(&self.to_snake(module));
         self.buf.push_str(" {\n");
 
         self.type_path.push(module.into());
@@ -969,6 +2253,39 @@ impl CodeGenerator<'_> {
         }
     }
 
+    /// Resolves the Rust type of a message or oneof field, which unlike the key and value of a map
+    /// field can be an interned string.
+    fn resolve_field_type(&self, field: &FieldDescriptorProto, fq_message_name: &str) -> String {
+        if let Some(custom_type) = self.custom_type(field) {
+            return custom_type.rust_type().to_string();
+        }
+        if let Some((_, ty)) = self.std_time_type(field) {
+            return ty.to_string();
+        }
+        let interned = field.r#type() == Type::String
+            && self
+                .config
+                .interned_strings
+                .get_first_field(fq_message_name, field.name())
+                .is_some();
+        if !interned {
+            return self.resolve_type(field, fq_message_name);
+        }
+        if self
+            .config
+            .newtypes
+            .get_first_field(fq_message_name, field.name())
+            .is_some()
+        {
+            panic!(
+                "field {}.{} can not be both interned and wrapped in a newtype",
+                fq_message_name,
+                field.name()
+            );
+        }
+        format!("{}::alloc::sync::Arc<str>", prost_path(self.config))
+    }
+
     fn resolve_ident(&self, pb_ident: &str) -> String {
         // protoc should always give fully qualified identifiers.
         assert_eq!(".", &pb_ident[..1]);
@@ -977,8 +2294,11 @@ impl CodeGenerator<'_> {
             return proto_ident;
         }
 
-        let mut local_path = self
-            .package
+        // Types of merged packages are generated in the module of their target package.
+        let merged_packages = &self.config.merged_packages;
+        let pb_ident = merged_packages.resolve(pb_ident);
+        let mut local_path = merged_packages
+            .target(&self.package)
             .split('.')
             .chain(self.type_path.iter().map(String::as_str))
             .peekable();
@@ -1002,8 +2322,8 @@ impl CodeGenerator<'_> {
 
         local_path
             .map(|_| "super".to_string())
-            .chain(ident_path.map(to_snake))
-            .chain(iter::once(to_upper_camel(ident_type)))
+            .chain(ident_path.map(|segment| self.to_snake(segment)))
+            .chain(iter::once(self.to_upper_camel(ident_type)))
             .join("::")
     }
 
@@ -1025,7 +2345,14 @@ impl CodeGenerator<'_> {
             Type::String => Cow::Borrowed("string"),
             Type::Bytes => Cow::Borrowed("bytes"),
             Type::Group => Cow::Borrowed("group"),
-            Type::Message => Cow::Borrowed("message"),
+            Type::Message if self.custom_type(field).is_some() => Cow::Owned(format!(
+                "message, proxy={:?}",
+                self.resolve_ident(field.type_name())
+            )),
+            Type::Message => match self.std_time_type(field) {
+                Some((tag, _)) => Cow::Owned(self.config.time_range_policy.annotation(tag)),
+                None => Cow::Borrowed("message"),
+            },
             Type::Enum => Cow::Owned(format!(
                 "enumeration={:?}",
                 self.resolve_ident(field.type_name())
@@ -1033,16 +2360,58 @@ impl CodeGenerator<'_> {
         }
     }
 
+    /// Returns the `prost-derive` type and the Rust type of a `google.protobuf.Timestamp` or
+    /// `google.protobuf.Duration` field generated as a std type with `Config::wkt_as_std`.
+    ///
+    /// With `Config::no_std`, timestamps are not generated as `SystemTime`, which requires `std`.
+    fn std_time_type(&self, field: &FieldDescriptorProto) -> Option<(&'static str, &'static str)> {
+        if !self.config.wkt_as_std
+            || field.r#type() != Type::Message
+            || self.custom_type(field).is_some()
+        {
+            return None;
+        }
+        match field.type_name() {
+            ".google.protobuf.Timestamp" if !self.config.no_std => {
+                Some(("timestamp", "::std::time::SystemTime"))
+            }
+            ".google.protobuf.Duration" => Some(("duration", "::core::time::Duration")),
+            _ => None,
+        }
+    }
+
+    /// Returns the custom type configured with `Config::custom_type` for a message field.
+    fn custom_type(&self, field: &FieldDescriptorProto) -> Option<&CustomType> {
+        if field.r#type() != Type::Message {
+            return None;
+        }
+        self.config.custom_types.get(field.type_name())
+    }
+
     fn map_value_type_tag(&self, field: &FieldDescriptorProto) -> Cow<'static, str> {
         match field.r#type() {
             Type::Enum => Cow::Owned(format!(
                 "enumeration({})",
                 self.resolve_ident(field.type_name())
             )),
+            // Map values are never generated as std time types.
+            Type::Message => Cow::Borrowed("message"),
             _ => self.field_type_tag(field),
         }
     }
 
+    /// Returns `true` if the field is of a closed enum type, whose undeclared values are skipped
+    /// when decoding, unless configured otherwise with `Config::accept_unknown_enum_values`.
+    fn closed_enum(&self, fq_message_name: &str, field: &FieldDescriptorProto) -> bool {
+        field.r#type() == Type::Enum
+            && self.message_graph.is_closed_enum(field.type_name())
+            && self
+                .config
+                .unknown_enum_values
+                .get_first_field(fq_message_name, field.name())
+                .is_none()
+    }
+
     fn optional(&self, field: &FieldDescriptorProto) -> bool {
         if field.proto3_optional.unwrap_or(false) {
             return true;
@@ -1069,6 +2438,9 @@ impl CodeGenerator<'_> {
         fq_message_name: &str,
         oneof: Option<&str>,
     ) -> bool {
+        if self.std_time_type(field).is_some() || self.custom_type(field).is_some() {
+            return false;
+        }
         let repeated = field.label == Some(Label::Repeated as i32);
         let fd_type = field.r#type();
         if !repeated
@@ -1109,6 +2481,32 @@ impl CodeGenerator<'_> {
             .map_or(false, FieldOptions::deprecated)
     }
 
+    /// Appends a `#[deprecated]` attribute if the item is deprecated in the Protobuf definition.
+    fn append_deprecated(&mut self, deprecated: bool) {
+        ast::append_deprecated(deprecated, self.depth, self.buf);
+    }
+
+    /// Appends an `#[allow(deprecated)]` attribute if the field's type is a message which is
+    /// deprecated in the Protobuf definition, so the generated code itself does not warn.
+    fn append_allow_deprecated_type(&mut self, field: &FieldDescriptorProto) {
+        let deprecated_type = matches!(field.r#type(), Type::Message | Type::Group)
+            && self
+                .message_graph
+                .get_message(field.type_name())
+                .and_then(|message| message.options.as_ref())
+                .is_some_and(|options| options.deprecated());
+        if deprecated_type {
+            self.push_indent();
+            self.buf.push_str("#[allow(deprecated)]\n");
+        }
+    }
+
+    /// Appends a `#[doc(alias)]` attribute so the item can be found in rustdoc by its Protobuf
+    /// name, if that differs from the generated Rust name by more than case.
+    fn append_doc_alias(&mut self, proto_name: &str, rust_name: &str) {
+        ast::append_doc_alias(proto_name, rust_name, self.depth, self.buf);
+    }
+
     /// Returns the fully-qualified name, starting with a dot
     fn fq_name(&self, message_name: &str) -> String {
         format!(
@@ -1122,6 +2520,16 @@ impl CodeGenerator<'_> {
     }
 }
 
+/// Returns the configured visibility followed by a space, `pub ` by default, or nothing for
+/// private items.
+fn visibility(configured: Option<&String>) -> String {
+    match configured {
+        None => "pub ".to_string(),
+        Some(visibility) if visibility.is_empty() => String::new(),
+        Some(visibility) => format!("{} ", visibility),
+    }
+}
+
 /// Returns `true` if the repeated field type can be packed.
 fn can_pack(field: &FieldDescriptorProto) -> bool {
     matches!(
@@ -1148,12 +2556,12 @@ struct EnumVariantMapping<'a> {
     proto_name: &'a str,
     proto_number: i32,
     generated_variant_name: String,
+    deprecated: bool,
 }
 
 fn build_enum_value_mappings<'a>(
-    generated_enum_name: &str,
-    do_strip_enum_prefix: bool,
     enum_values: &'a [EnumValueDescriptorProto],
+    variant_name: impl Fn(&str) -> String,
 ) -> Vec<EnumVariantMapping<'a>> {
     let mut numbers = HashSet::new();
     let mut generated_names = HashMap::new();
@@ -1166,11 +2574,7 @@ fn build_enum_value_mappings<'a>(
             continue;
         }
 
-        let mut generated_variant_name = to_upper_camel(value.name());
-        if do_strip_enum_prefix {
-            generated_variant_name =
-                strip_enum_prefix(generated_enum_name, &generated_variant_name);
-        }
+        let generated_variant_name = variant_name(value.name());
 
         if let Some(old_v) = generated_names.insert(generated_variant_name.to_owned(), value.name())
         {
@@ -1183,6 +2587,7 @@ fn build_enum_value_mappings<'a>(
             proto_name: value.name(),
             proto_number: value.number(),
             generated_variant_name,
+            deprecated: value.options.as_ref().is_some_and(|o| o.deprecated()),
         })
     }
     mappings
@@ -1,12 +1,15 @@
 /// The map collection type to output for Protobuf `map` fields.
 #[non_exhaustive]
 #[derive(Default, Clone, Copy, Debug, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum MapType {
     /// The [`std::collections::HashMap`] type.
     #[default]
     HashMap,
     /// The [`std::collections::BTreeMap`] type.
     BTreeMap,
+    /// The `indexmap::IndexMap` type, re-exported by `prost`.
+    IndexMap,
 }
 
 /// The bytes collection type to output for Protobuf `bytes` fields.
@@ -26,6 +29,7 @@ impl MapType {
         match self {
             MapType::HashMap => "map",
             MapType::BTreeMap => "btree_map",
+            MapType::IndexMap => "index_map",
         }
     }
 
@@ -34,6 +38,7 @@ impl MapType {
         match self {
             MapType::HashMap => "::std::collections::HashMap",
             MapType::BTreeMap => "::prost::alloc::collections::BTreeMap",
+            MapType::IndexMap => "::prost::indexmap::IndexMap",
         }
     }
 }
@@ -1,55 +1,321 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::default;
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::fs;
 use std::io::{Error, ErrorKind, Result, Write};
+use std::iter;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output};
 
+use itertools::Itertools;
 use log::debug;
 use log::trace;
 
-use prost::Message;
-use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use prost::{FieldIter, Message};
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
 
-use crate::code_generator::CodeGenerator;
+use crate::ast::Comments;
+use crate::code_generator::{
+    append_newtypes, append_package_constants, package_comments, CodeGenerator,
+};
+use crate::diagnostics::SchemaReport;
 use crate::extern_paths::ExternPaths;
+use crate::ident::{
+    strip_enum_prefix, to_snake, to_upper_camel_with, DefaultIdentPolicy, IdentPolicy,
+};
+use crate::merged_packages::MergedPackages;
 use crate::message_graph::MessageGraph;
 use crate::path::PathMap;
+use crate::protoc_version::ProtocVersion;
 use crate::BytesType;
 use crate::MapType;
 use crate::Module;
 use crate::ServiceGenerator;
 
+type FdsTransform = Box<dyn FnMut(&mut FileDescriptorSet)>;
+
+/// The name of the file written by [`Config::generate_prelude`].
+const PRELUDE_FILE_NAME: &str = "prelude.rs";
+
+/// How `google.protobuf.Timestamp` and `google.protobuf.Duration` values which can not be
+/// represented by the std types generated with [`Config::wkt_as_std`] are decoded.
+#[non_exhaustive]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeRangePolicy {
+    /// Fail to decode the message.
+    #[default]
+    Reject,
+    /// Decode the closest representable value: negative durations become zero, and timestamps
+    /// are clamped to the range of `SystemTime` on the target platform.
+    Saturate,
+}
+
+/// A zero-copy serialization framework whose traits can be derived for generated messages.
+///
+/// See [`Config::archive_derive`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveDerive {
+    /// Derives `rkyv::Archive`, `rkyv::Serialize` and `rkyv::Deserialize`, for rkyv 0.8.
+    ///
+    /// Messages and oneofs are supported, as long as the messages of their fields derive the
+    /// traits as well. Recursive fields get the `#[rkyv(omit_bounds)]` attribute, and their
+    /// message the bounds rkyv requires for recursive types. `Timestamp` fields generated as
+    /// `SystemTime` and newtype fields are not supported. `bytes::Bytes` fields require the
+    /// `bytes-1` feature of rkyv.
+    Rkyv,
+    /// Derives `zerocopy::FromBytes`, `zerocopy::IntoBytes`, `zerocopy::Immutable` and
+    /// `zerocopy::KnownLayout`, and adds `#[repr(C)]`, for zerocopy 0.8.
+    ///
+    /// Only messages whose fields are all singular, non-optional numbers or enums of fixed
+    /// width are supported, and their fields must be ordered so that the struct has no padding.
+    Zerocopy,
+}
+
+/// How the variants of generated enums are named.
+///
+/// See [`Config::enum_variant_naming`].
+#[non_exhaustive]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnumVariantNaming {
+    /// Converts value names to `UpperCamelCase` and strips the enum name from their start, so
+    /// that the `COLOR_RED` value of the `Color` enum becomes `Red`.
+    #[default]
+    StripEnumPrefix,
+    /// Converts value names to `UpperCamelCase`, so that `COLOR_RED` becomes `ColorRed`.
+    UpperCamel,
+    /// Keeps the value names of the Protobuf definition, so that `COLOR_RED` stays `COLOR_RED`.
+    /// The enums are annotated with `#[allow(non_camel_case_types)]`.
+    Original,
+}
+
+type EnumVariantRenamer = Box<dyn Fn(&str, &str) -> String>;
+
+type ModuleAttributes = Box<dyn Fn(&Module) -> Vec<String>>;
+
+/// A Rust type generated for the fields of a Protobuf message type, instead of the message.
+///
+/// See [`Config::custom_type`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomType {
+    rust_type: String,
+    serde_with: Option<String>,
+}
+
+impl CustomType {
+    /// Creates a custom type generating fields of the given Rust type, such as
+    /// `::rust_decimal::Decimal`.
+    pub fn new(rust_type: impl Into<String>) -> Self {
+        CustomType {
+            rust_type: rust_type.into(),
+            serde_with: None,
+        }
+    }
+
+    /// Sets the module serializing fields of the type with serde, through a
+    /// `#[serde(with = "...")]` attribute.
+    ///
+    /// The module is used for the whole field, so it must handle the `Option` of optional
+    /// fields and the `Vec` of repeated fields.
+    pub fn serde_with(mut self, module: impl Into<String>) -> Self {
+        self.serde_with = Some(module.into());
+        self
+    }
+
+    pub(crate) fn rust_type(&self) -> &str {
+        &self.rust_type
+    }
+
+    pub(crate) fn serde_with_module(&self) -> Option<&str> {
+        self.serde_with.as_deref()
+    }
+}
+
+/// An external crate providing the generated code of a Protobuf package and its nested packages.
+///
+/// See [`Config::crate_remap`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrateRemap {
+    rust_path: String,
+    modules: Vec<(String, String)>,
+    feature: Option<String>,
+}
+
+impl CrateRemap {
+    /// Creates a remap to the crate or module at the given Rust path, such as `::acme_protos`,
+    /// whose modules follow the package hierarchy.
+    pub fn new(rust_path: impl Into<String>) -> Self {
+        CrateRemap {
+            rust_path: rust_path.into(),
+            modules: Vec::new(),
+            feature: None,
+        }
+    }
+
+    /// Sets the module of a nested package, relative to the Rust path of the crate, for crates
+    /// whose modules don't follow the package hierarchy.
+    ///
+    /// `package` is a fully-qualified Protobuf package nested in the remapped package. Packages
+    /// nested in it are in the matching submodules of `module`.
+    pub fn module(mut self, package: impl Into<String>, module: impl Into<String>) -> Self {
+        self.modules.push((package.into(), module.into()));
+        self
+    }
+
+    /// Gates the re-exports of the include file on a feature of the crate including it, for
+    /// crates which are optional dependencies.
+    pub fn feature(mut self, feature: impl Into<String>) -> Self {
+        self.feature = Some(feature.into());
+        self
+    }
+
+    /// Returns the extern paths of the remapped package, and of its nested packages with a
+    /// module.
+    fn extern_paths(&self, package: &str) -> std::result::Result<Vec<(String, String)>, String> {
+        iter::once(Ok((package.to_string(), self.rust_path.clone())))
+            .chain(self.modules.iter().map(|(nested, module)| {
+                if !is_nested_package(package, nested) || nested == package {
+                    return Err(format!(
+                        "the module package {} of the crate remap of {} is not nested in it",
+                        nested, package
+                    ));
+                }
+                Ok((nested.clone(), format!("{}::{}", self.rust_path, module)))
+            }))
+            .collect()
+    }
+
+    /// Returns the Rust path of the module of `package`, if it is `remapped` or nested in it.
+    fn package_path(&self, remapped: &str, package: &str) -> Option<String> {
+        let (base, path) = self
+            .modules
+            .iter()
+            .filter(|(nested, _)| is_nested_package(nested, package))
+            .max_by_key(|(nested, _)| nested.len())
+            .map(|(nested, module)| (nested.as_str(), format!("{}::{}", self.rust_path, module)))
+            .or_else(|| {
+                is_nested_package(remapped, package).then(|| (remapped, self.rust_path.clone()))
+            })?;
+        Some(
+            iter::once(path)
+                .chain(
+                    package[base.len()..]
+                        .split('.')
+                        .filter(|part| !part.is_empty())
+                        .map(to_snake),
+                )
+                .join("::"),
+        )
+    }
+}
+
+impl From<&str> for CrateRemap {
+    fn from(rust_path: &str) -> Self {
+        CrateRemap::new(rust_path)
+    }
+}
+
+impl From<String> for CrateRemap {
+    fn from(rust_path: String) -> Self {
+        CrateRemap::new(rust_path)
+    }
+}
+
+/// Returns `true` if `nested` is `package`, or a package nested in it.
+fn is_nested_package(package: &str, nested: &str) -> bool {
+    nested
+        .strip_prefix(package)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+impl TimeRangePolicy {
+    /// The `prost-derive` annotation of a field of the given time type.
+    pub(crate) fn annotation(&self, ty: &str) -> String {
+        match self {
+            TimeRangePolicy::Reject => ty.to_string(),
+            TimeRangePolicy::Saturate => format!("{}=\"saturate\"", ty),
+        }
+    }
+}
+
 /// Configuration options for Protobuf code generation.
 ///
 /// This configuration builder can be used to set non-default code generation options.
 pub struct Config {
     pub(crate) file_descriptor_set_path: Option<PathBuf>,
     pub(crate) service_generator: Option<Box<dyn ServiceGenerator>>,
+    pub(crate) fds_transforms: Vec<FdsTransform>,
     pub(crate) map_type: PathMap<MapType>,
+    pub(crate) encode_default_map_values: bool,
+    pub(crate) convenience_conversions: bool,
     pub(crate) bytes_type: PathMap<BytesType>,
+    pub(crate) interned_strings: PathMap<()>,
+    pub(crate) wkt_as_std: bool,
+    pub(crate) no_std: bool,
+    pub(crate) time_range_policy: TimeRangePolicy,
     pub(crate) type_attributes: PathMap<String>,
     pub(crate) message_attributes: PathMap<String>,
     pub(crate) enum_attributes: PathMap<String>,
+    pub(crate) oneof_attributes: PathMap<String>,
+    pub(crate) method_attributes: PathMap<String>,
     pub(crate) field_attributes: PathMap<String>,
+    pub(crate) field_cfgs: PathMap<String>,
+    pub(crate) type_cfgs: PathMap<String>,
+    pub(crate) type_visibilities: PathMap<String>,
+    pub(crate) field_visibilities: PathMap<String>,
     pub(crate) boxed: PathMap<()>,
+    pub(crate) newtypes: PathMap<String>,
+    pub(crate) unknown_enum_values: PathMap<()>,
     pub(crate) prost_types: bool,
-    pub(crate) strip_enum_prefix: bool,
+    pub(crate) unwrap_well_known_wrappers: bool,
+    pub(crate) custom_types: HashMap<String, CustomType>,
+    pub(crate) enum_variant_naming: EnumVariantNaming,
+    pub(crate) enum_variant_renamer: Option<EnumVariantRenamer>,
+    pub(crate) ident_policy: Box<dyn IdentPolicy>,
     pub(crate) out_dir: Option<PathBuf>,
     pub(crate) extern_paths: Vec<(String, String)>,
+    pub(crate) crate_remaps: Vec<(String, CrateRemap)>,
+    pub(crate) merged_packages: MergedPackages,
+    pub(crate) archive_derives: PathMap<ArchiveDerive>,
+    pub(crate) archive_derive_feature: Option<String>,
     pub(crate) default_package_filename: String,
     pub(crate) enable_type_names: bool,
     pub(crate) type_name_domains: PathMap<String>,
     pub(crate) protoc_args: Vec<OsString>,
-    pub(crate) protoc_executable: PathBuf,
+    pub(crate) experimental_editions: bool,
+    pub(crate) fatal_warnings: bool,
+    pub(crate) protoc_executable: Option<PathBuf>,
     pub(crate) disable_comments: PathMap<()>,
     pub(crate) skip_debug: PathMap<()>,
+    pub(crate) serde_json_names: PathMap<()>,
+    pub(crate) json_use_proto_names: PathMap<()>,
+    pub(crate) oneof_from_impls: PathMap<()>,
+    pub(crate) serde_json_value_conversions: PathMap<()>,
+    pub(crate) json_unknown_fields: PathMap<()>,
+    pub(crate) field_values: PathMap<()>,
+    pub(crate) send_sync_assertions: PathMap<()>,
+    pub(crate) presence_bitsets: PathMap<()>,
+    pub(crate) display_fields: PathMap<String>,
+    pub(crate) enforce_reserved: bool,
+    pub(crate) inner_package_docs: bool,
+    pub(crate) package_constants: bool,
+    pub(crate) service_descriptors: bool,
+    pub(crate) reserved_field_callback: Option<String>,
     pub(crate) skip_protoc_run: bool,
+    pub(crate) hermetic: bool,
+    pub(crate) allowed_env_vars: Vec<String>,
     pub(crate) skip_source_info: bool,
+    pub(crate) descriptor_set_from_stdout: bool,
+    pub(crate) raw_method_options: HashMap<String, Vec<u8>>,
     pub(crate) include_file: Option<PathBuf>,
+    pub(crate) include_module_attributes: Option<ModuleAttributes>,
+    pub(crate) generate_prelude: bool,
+    pub(crate) schema_warnings: bool,
+    pub(crate) schema_report: Option<PathBuf>,
     pub(crate) prost_path: Option<String>,
     #[cfg(feature = "format")]
     pub(crate) fmt: bool,
@@ -114,7 +380,8 @@ impl Config {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        self.map_type.clear();
+        self.map_type
+            .retain(|map_type| *map_type != MapType::BTreeMap);
         for matcher in paths {
             self.map_type
                 .insert(matcher.as_ref().to_string(), MapType::BTreeMap);
@@ -122,6 +389,90 @@ impl Config {
         self
     }
 
+    /// Configure the code generator to generate Rust [`IndexMap`][1] fields for Protobuf
+    /// [`map`][2] type fields.
+    ///
+    /// `IndexMap` fields keep their entries in insertion order, and in the order in which they
+    /// were decoded. The generated code uses the `indexmap` crate re-exported by `prost`, which
+    /// requires its `indexmap` feature.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should use a Rust
+    /// `IndexMap` for Protobuf `map` fields. Paths are matched as with [`Config::btree_map`].
+    /// A field matched by both is generated with the type of the most specific path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Keep the order of the entries of all map fields in the 'settings' package.
+    /// config.index_map(&[".settings"]);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/indexmap/latest/indexmap/map/struct.IndexMap.html
+    /// [2]: https://developers.google.com/protocol-buffers/docs/proto3#maps
+    pub fn index_map<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.map_type
+            .retain(|map_type| *map_type != MapType::IndexMap);
+        for matcher in paths {
+            self.map_type
+                .insert(matcher.as_ref().to_string(), MapType::IndexMap);
+        }
+        self
+    }
+
+    /// Configure whether map entries encode their default values.
+    ///
+    /// Each entry of a map field is encoded as a message with the key as field 1 and the value
+    /// as field 2. By default, keys and values equal to the default value of their type, such
+    /// as `0`, `""` or an empty message, are omitted from the entries, as in proto3 messages.
+    /// Decoders treat a missing key or value as the default value, so that both encodings
+    /// decode to the same map.
+    ///
+    /// Some decoders require the value of every entry to be present, for example for message
+    /// values. When enabled, the values of all entries are encoded, even when they are equal
+    /// to their default value. Default keys are still omitted.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.encode_default_map_values(true);
+    /// ```
+    pub fn encode_default_map_values(&mut self, enabled: bool) -> &mut Self {
+        self.encode_default_map_values = enabled;
+        self
+    }
+
+    /// Configure the code generator to generate conversions between messages and their
+    /// encoding.
+    ///
+    /// When enabled, each message gets a `TryFrom<&[u8]>` implementation decoding it, failing
+    /// with a [`DecodeError`](prost::DecodeError), and `Vec<u8>` gets a `From<&Message>`
+    /// implementation encoding it. Application code can then decode with `?` and
+    /// `try_into()`, and encode with `into()`, without importing the `Message` trait.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Allows `let user = User::try_from(bytes)?` and `let bytes: Vec<u8> = (&user).into()`.
+    /// config.convenience_conversions(true);
+    /// ```
+    pub fn convenience_conversions(&mut self, enabled: bool) -> &mut Self {
+        self.convenience_conversions = enabled;
+        self
+    }
+
     /// Configure the code generator to generate Rust [`bytes::Bytes`](prost::bytes::Bytes) fields for Protobuf
     /// [`bytes`][2] type fields.
     ///
@@ -182,6 +533,116 @@ impl Config {
         self
     }
 
+    /// Configure the code generator to generate `Arc<str>` fields for Protobuf `string` fields,
+    /// deduplicated through the [`Interner`](prost::Interner) of the decode context, if any.
+    ///
+    /// Decoding repeated identical strings, such as the labels of log events, then allocates each
+    /// distinct value only once. Set an interner with [`prost::Extensions::set_interner`] and
+    /// decode with [`prost::Message::decode_with_context`]; other decodes allocate every string as
+    /// usual.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should use `Arc<str>`
+    /// for Protobuf `string` fields. For details about matching fields see
+    /// [`btree_map`](#method.btree_map). Map fields always use `String`, and fields wrapped in a
+    /// [`newtype`](#method.newtype) can not be interned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Intern the strings of all fields of a message type.
+    /// config.interned_strings(&[".my_messages.LogEvent"]);
+    /// ```
+    pub fn interned_strings<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.interned_strings.clear();
+        for matcher in paths {
+            self.interned_strings
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configure the code generator to generate `std::time::SystemTime` fields for
+    /// `google.protobuf.Timestamp` fields, and `std::time::Duration` fields for
+    /// `google.protobuf.Duration` fields, instead of the `prost_types` messages.
+    ///
+    /// Singular fields are generated as an `Option`, since the messages have presence. Map
+    /// values keep the `prost_types` messages. `SystemTime` fields require the `std` feature of
+    /// `prost`, see [`no_std`](#method.no_std) to keep timestamps as `prost_types` messages.
+    ///
+    /// Negative durations and timestamps outside of the range of `SystemTime` can not be
+    /// represented, and fail to decode by default. See
+    /// [`time_range_policy`](#method.time_range_policy) to saturate them instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.wkt_as_std(true);
+    /// ```
+    pub fn wkt_as_std(&mut self, enable: bool) -> &mut Self {
+        self.wkt_as_std = enable;
+        self
+    }
+
+    /// Configure the code generator to generate code which compiles without the standard
+    /// library, in `no_std` crates and with `prost` and `prost-types` built without their `std`
+    /// feature.
+    ///
+    /// The generated code then only uses `core` and the `alloc` crate re-exported by `prost`:
+    ///
+    /// - Map fields which are not matched by [`btree_map`](#method.btree_map) or
+    ///   [`index_map`](#method.index_map) are generated as `BTreeMap` rather than `HashMap`.
+    /// - With [`wkt_as_std`](#method.wkt_as_std), `google.protobuf.Timestamp` fields keep the
+    ///   `prost_types::Timestamp` type, since `SystemTime` is only part of `std`.
+    ///   `google.protobuf.Duration` fields are still generated as `core::time::Duration`.
+    /// - `IndexMap` fields require the `std` feature of `prost`, so configuring any with
+    ///   [`index_map`](#method.index_map) is an error.
+    ///
+    /// Serde derives added with [`type_attribute`](#method.type_attribute), and the renames of
+    /// [`serde_json_names`](#method.serde_json_names), compile with `serde` built without its
+    /// `std` feature, as long as its `alloc` feature is enabled. Attributes and
+    /// [`custom_type`](#method.custom_type) paths are not checked, and must not refer to `std`
+    /// either.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config
+    ///     .no_std(true)
+    ///     .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+    ///     .serde_json_names(&["."]);
+    /// ```
+    pub fn no_std(&mut self, enable: bool) -> &mut Self {
+        self.no_std = enable;
+        self
+    }
+
+    /// Configure how the fields generated with [`wkt_as_std`](#method.wkt_as_std) decode values
+    /// which can not be represented by the std types.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use prost_build::TimeRangePolicy;
+    /// # let mut config = prost_build::Config::new();
+    /// // Decode negative durations as zero instead of failing.
+    /// config
+    ///     .wkt_as_std(true)
+    ///     .time_range_policy(TimeRangePolicy::Saturate);
+    /// ```
+    pub fn time_range_policy(&mut self, policy: TimeRangePolicy) -> &mut Self {
+        self.time_range_policy = policy;
+        self
+    }
+
     /// Add additional attribute to matched fields.
     ///
     /// # Arguments
@@ -215,6 +676,141 @@ impl Config {
         self
     }
 
+    /// Compile matched fields only when a configuration predicate holds.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields, oneofs or oneof variants. These fields
+    /// get the predicate. For details about matching fields see
+    /// [`btree_map`](#method.btree_map). Oneof variants are matched as the fields of their
+    /// oneof, for example `.my.pkg.Msg.my_oneof.internal_variant`.
+    ///
+    /// **`cfg`** - a configuration predicate, as written inside `#[cfg(...)]`.
+    ///
+    /// Matched fields and oneof variants are generated with a `#[cfg(...)]` attribute, so that a
+    /// single `.proto` file can generate a reduced public API, for example without the fields
+    /// used internally. When the predicate does not hold, the fields are decoded as unknown
+    /// fields and skipped, as if they were not part of the message.
+    ///
+    /// The calls to this method are cumulative: a field matched by several calls is only
+    /// compiled when all their predicates hold.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.field_cfg(".my_messages.Account.audit_log", "feature = \"internal\"");
+    /// ```
+    pub fn field_cfg<P, C>(&mut self, path: P, cfg: C) -> &mut Self
+    where
+        P: AsRef<str>,
+        C: AsRef<str>,
+    {
+        self.field_cfgs
+            .insert(path.as_ref().to_string(), cfg.as_ref().to_string());
+        self
+    }
+
+    /// Compile matched messages and enums only when a configuration predicate holds.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of messages and enums. It works the same way as
+    /// in [`btree_map`](#method.btree_map), just with the field name omitted. The nested types of
+    /// matched messages are matched too.
+    ///
+    /// **`cfg`** - a configuration predicate, as written inside `#[cfg(...)]`.
+    ///
+    /// Matched types are generated with a `#[cfg(...)]` attribute, as are the module of their
+    /// nested types and the implementations generated for them, so that rarely used types can be
+    /// left out of a build. Fields having a matched type must be made conditional with
+    /// [`field_cfg`](#method.field_cfg) as well.
+    ///
+    /// The calls to this method are cumulative: a type matched by several calls is only
+    /// compiled when all their predicates hold.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config
+    ///     .type_cfg(".my_messages.AuditLog", "feature = \"audit\"")
+    ///     .field_cfg(".my_messages.Account.audit_log", "feature = \"audit\"");
+    /// ```
+    pub fn type_cfg<P, C>(&mut self, path: P, cfg: C) -> &mut Self
+    where
+        P: AsRef<str>,
+        C: AsRef<str>,
+    {
+        self.type_cfgs
+            .insert(path.as_ref().to_string(), cfg.as_ref().to_string());
+        self
+    }
+
+    /// Set the visibility of matched messages, enums and oneofs, instead of `pub`.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of types. It works the same way as in
+    /// [`btree_map`](#method.btree_map), just with the field name omitted. Oneofs are matched by
+    /// their field name, as with [`type_attribute`](#method.type_attribute).
+    ///
+    /// **`visibility`** - a visibility such as `pub(crate)` or `pub(super)`, or an empty string
+    /// for private types. It is not checked.
+    ///
+    /// Library crates can use this to keep the generated types out of their public API. The
+    /// generated code is placed in a module for each package, so `pub(super)` types are visible
+    /// in the parent of the package module. The fields of the types stay `pub`, unless matched
+    /// by [`field_visibility`](#method.field_visibility). Public types whose fields have a
+    /// restricted type trigger the `private_interfaces` lint.
+    ///
+    /// If a type is matched by multiple calls, the most specific path takes precedence.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.type_visibility(".my_messages", "pub(crate)");
+    /// ```
+    pub fn type_visibility<P, V>(&mut self, path: P, visibility: V) -> &mut Self
+    where
+        P: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.type_visibilities
+            .insert(path.as_ref().to_string(), visibility.as_ref().to_string());
+        self
+    }
+
+    /// Set the visibility of matched fields, instead of `pub`.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields or oneofs. For details about matching
+    /// fields see [`btree_map`](#method.btree_map).
+    ///
+    /// **`visibility`** - a visibility such as `pub(crate)` or `pub(super)`, or an empty string
+    /// for private fields. It is not checked.
+    ///
+    /// If a field is matched by multiple calls, the most specific path takes precedence.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // The account is only created through an API checking the balance.
+    /// config.field_visibility(".my_messages.Account.balance", "pub(crate)");
+    /// ```
+    pub fn field_visibility<P, V>(&mut self, path: P, visibility: V) -> &mut Self
+    where
+        P: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.field_visibilities
+            .insert(path.as_ref().to_string(), visibility.as_ref().to_string());
+        self
+    }
+
     /// Add additional attribute to matched messages, enums and one-ofs.
     ///
     /// # Arguments
@@ -342,6 +938,10 @@ impl Config {
     ///
     /// In other words, to place an attribute on the `enum` implementing the `oneof`, the match
     /// would look like `my_messages.MyNestedMessageType.oneofname`.
+    ///
+    /// Since a path matches the types nested below it as well, a path like `.my_messages` adds the
+    /// attribute to both the enums and the oneofs of the package. Use
+    /// [`oneof_attribute`](#method.oneof_attribute) to add attributes to oneofs only.
     pub fn enum_attribute<P, A>(&mut self, path: P, attribute: A) -> &mut Self
     where
         P: AsRef<str>,
@@ -352,41 +952,252 @@ impl Config {
         self
     }
 
-    /// Wrap matched fields in a `Box`.
+    /// Add additional attribute to the enums of matched one-ofs.
+    ///
+    /// Unlike [`enum_attribute`](#method.enum_attribute), the attribute is never added to enums
+    /// generated for Protobuf `enum` types, even if the path matches them as well.
     ///
     /// # Arguments
     ///
-    /// **`path`** - a path matching any number of fields. These fields get the attribute.
-    /// For details about matching fields see [`btree_map`](#method.btree_map).
+    /// **`paths`** - a path matching any number of one-ofs. A one-of is matched by the path of
+    /// its message followed by its name, like `my_messages.MyMessageType.oneofname`; the paths
+    /// work the same way as in [`btree_map`](#method.btree_map) otherwise.
+    ///
+    /// **`attribute`** - an arbitrary string to be placed before the `enum` of each matched
+    /// one-of. The expected usage are additional attributes, but anything is allowed.
+    ///
+    /// The calls to this method are cumulative. They don't overwrite previous calls and if a
+    /// one-of is matched by multiple calls of the method, all relevant attributes are added to
+    /// it.
     ///
     /// # Examples
     ///
     /// ```rust
     /// # let mut config = prost_build::Config::new();
-    /// config.boxed(".my_messages.MyMessageType.my_field");
+    /// // Only the oneofs of a package are converted from their variants.
+    /// config.oneof_attribute(".my_messages", "#[derive(derive_more::From)]");
+    /// // A single oneof is serialized without a tag.
+    /// config.oneof_attribute("my_messages.MyMessageType.oneofname", "#[serde(untagged)]");
     /// ```
-    pub fn boxed<P>(&mut self, path: P) -> &mut Self
+    pub fn oneof_attribute<P, A>(&mut self, path: P, attribute: A) -> &mut Self
     where
         P: AsRef<str>,
+        A: AsRef<str>,
     {
-        self.boxed.insert(path.as_ref().to_string(), ());
-        self
-    }
-
-    /// Configures the code generator to use the provided service generator.
-    pub fn service_generator(&mut self, service_generator: Box<dyn ServiceGenerator>) -> &mut Self {
-        self.service_generator = Some(service_generator);
-        self
-    }
-
-    /// Configures the code generator to not use the `prost_types` crate for Protobuf well-known
-    /// types, and instead generate Protobuf well-known types from their `.proto` definitions.
-    pub fn compile_well_known_types(&mut self) -> &mut Self {
-        self.prost_types = false;
+        self.oneof_attributes
+            .insert(path.as_ref().to_string(), attribute.as_ref().to_string());
         self
     }
 
-    /// Configures the code generator to omit documentation comments on generated Protobuf types.
+    /// Add additional attribute to the matched service methods.
+    ///
+    /// The attributes are not added to generated code by `prost-build` itself. They are passed to
+    /// the [`ServiceGenerator`] in the [`MethodDescriptorExt::attributes`] of the method, so that
+    /// RPC code generators can place them on the code generated for the method.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of methods. A method is matched by the path of its
+    /// service followed by its name, like `my_services.MyService.MyMethod`; the paths work the
+    /// same way as in [`btree_map`](#method.btree_map) otherwise.
+    ///
+    /// **`attribute`** - an arbitrary string for the service generator.
+    ///
+    /// The calls to this method are cumulative. They don't overwrite previous calls and if a
+    /// method is matched by multiple calls of the method, all relevant attributes are passed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // All the methods of a service are instrumented.
+    /// config.method_attribute(".my_services.MyService", "#[tracing::instrument]");
+    /// ```
+    ///
+    /// [`MethodDescriptorExt::attributes`]: crate::MethodDescriptorExt::attributes
+    pub fn method_attribute<P, A>(&mut self, path: P, attribute: A) -> &mut Self
+    where
+        P: AsRef<str>,
+        A: AsRef<str>,
+    {
+        self.method_attributes
+            .insert(path.as_ref().to_string(), attribute.as_ref().to_string());
+        self
+    }
+
+    /// Wrap matched fields in a `Box`.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. These fields get the attribute.
+    /// For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.boxed(".my_messages.MyMessageType.my_field");
+    /// ```
+    pub fn boxed<P>(&mut self, path: P) -> &mut Self
+    where
+        P: AsRef<str>,
+    {
+        self.boxed.insert(path.as_ref().to_string(), ());
+        self
+    }
+
+    /// Wrap matched fields in a generated newtype.
+    ///
+    /// A tuple struct with the given name is generated at the top level of the module of the
+    /// package containing the field, next to the package's messages, wrapping the field's scalar
+    /// Rust type. This allows identifier-like fields to be strongly typed instead of being bare
+    /// `String` or integer values. The wire format is unchanged.
+    ///
+    /// Only singular `string` and integer fields may be wrapped, and fields sharing a newtype name
+    /// within a module must have the same underlying type. Otherwise, code generation fails with
+    /// an [`InvalidInput`](std::io::ErrorKind::InvalidInput) error. Fields of oneofs are not
+    /// wrapped.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. These fields are wrapped in the newtype.
+    /// For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// **`name`** - the name of the generated newtype.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Generate `pub struct UserId(pub String);` and use it for the `User.id` field.
+    /// config.newtype(".my_messages.User.id", "UserId");
+    /// ```
+    pub fn newtype<P, N>(&mut self, path: P, name: N) -> &mut Self
+    where
+        P: AsRef<str>,
+        N: AsRef<str>,
+    {
+        self.newtypes
+            .insert(path.as_ref().to_string(), name.as_ref().to_string());
+        self
+    }
+
+    /// Configures the code generator to store values not declared by their enum in matched fields
+    /// of `proto2` enums.
+    ///
+    /// The enums of `proto2` files are closed: a value which is not declared by the enum is
+    /// treated as an unknown field, and skipped, instead of being stored in the field. Matched
+    /// fields accept all values instead, as the fields of `proto3` enums do, which was the
+    /// behavior of previous versions.
+    ///
+    /// Map fields always accept all values.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should accept all
+    /// values. For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Keep the previous behavior for all fields.
+    /// config.accept_unknown_enum_values(&["."]);
+    /// ```
+    pub fn accept_unknown_enum_values<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.unknown_enum_values.clear();
+        for matcher in paths {
+            self.unknown_enum_values
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to use the provided service generator.
+    pub fn service_generator(&mut self, service_generator: Box<dyn ServiceGenerator>) -> &mut Self {
+        self.service_generator = Some(service_generator);
+        self
+    }
+
+    /// Configures the code generator to not use the `prost_types` crate for Protobuf well-known
+    /// types, and instead generate Protobuf well-known types from their `.proto` definitions.
+    pub fn compile_well_known_types(&mut self) -> &mut Self {
+        self.prost_types = false;
+        self
+    }
+
+    /// Configures the code generator to generate the Rust type of the wrapped value for fields
+    /// of the `google.protobuf.*Value` wrapper types, such as `Option<i32>` for an
+    /// `google.protobuf.Int32Value` field, instead of the wrapper message.
+    ///
+    /// The field keeps the presence of the wrapper on the wire: `None` is not encoded, while
+    /// `Some(0)` is encoded as an empty wrapper message.
+    ///
+    /// Wrapper fields are always unwrapped when the well-known types come from `prost_types`.
+    /// This option also unwraps them when the well-known types are generated with
+    /// [`compile_well_known_types`](#method.compile_well_known_types), or mapped to another crate
+    /// with [`extern_path`](#method.extern_path). Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config
+    ///     .extern_path(".google.protobuf", "::pbjson_types")
+    ///     .unwrap_well_known_wrappers(true);
+    /// ```
+    pub fn unwrap_well_known_wrappers(&mut self, enable: bool) -> &mut Self {
+        self.unwrap_well_known_wrappers = enable;
+        self
+    }
+
+    /// Configures the code generator to generate fields of a Protobuf message type with a custom
+    /// Rust type, as it does for well-known types with [`wkt_as_std`](#method.wkt_as_std).
+    ///
+    /// Optional, required and repeated message fields and oneof variants of the message type
+    /// hold the Rust type, and are encoded as the message they convert to and from: the message
+    /// must implement `for<'a> From<&'a T>`, and the Rust type `T` must implement `TryFrom` the
+    /// message, whose error fails the decode. The message itself is still generated, unless it
+    /// is mapped with [`extern_path`](#method.extern_path), and is used for the values of map
+    /// fields. The Rust type must implement `Debug` and `PartialEq`, and `Default` for required
+    /// fields and oneof variants.
+    ///
+    /// A custom type takes precedence over the std type generated by `wkt_as_std`.
+    ///
+    /// # Arguments
+    ///
+    /// **`proto_type`** - the fully-qualified name of the message, such as `.my.company.Decimal`.
+    ///
+    /// **`custom_type`** - the Rust type of the fields, and how they are serialized with serde.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use prost_build::CustomType;
+    /// # let mut config = prost_build::Config::new();
+    /// config.custom_type(
+    ///     ".my.company.Decimal",
+    ///     CustomType::new("::rust_decimal::Decimal").serde_with("crate::serde_decimal"),
+    /// );
+    /// ```
+    pub fn custom_type(
+        &mut self,
+        proto_type: impl Into<String>,
+        custom_type: CustomType,
+    ) -> &mut Self {
+        let mut proto_type = proto_type.into();
+        if !proto_type.starts_with('.') {
+            proto_type.insert(0, '.');
+        }
+        self.custom_types.insert(proto_type, custom_type);
+        self
+    }
+
+    /// Configures the code generator to omit documentation comments on generated Protobuf types.
     ///
     /// # Example
     ///
@@ -435,6 +1246,379 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to derive the traits of a zero-copy serialization framework
+    /// for messages, instead of maintaining the derives with
+    /// [`type_attribute`](#method.type_attribute).
+    ///
+    /// The fields of matched messages are checked while generating code, and the generation
+    /// panics with the name of the first field the framework does not support. See
+    /// [`ArchiveDerive`] for the supported fields. The derives of both frameworks can be
+    /// configured for the same message.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages whose messages should derive the
+    /// traits. For details about matching paths see [`btree_map`](#method.btree_map).
+    ///
+    /// **`derive`** - the framework whose traits should be derived.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use prost_build::ArchiveDerive;
+    /// # let mut config = prost_build::Config::new();
+    /// // Archive all messages of the `telemetry` package with rkyv.
+    /// config.archive_derive([".telemetry"], ArchiveDerive::Rkyv);
+    /// // Map the `telemetry.Sample` message directly from bytes.
+    /// config.archive_derive([".telemetry.Sample"], ArchiveDerive::Zerocopy);
+    /// ```
+    pub fn archive_derive<I, S>(&mut self, paths: I, derive: ArchiveDerive) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for matcher in paths {
+            self.archive_derives
+                .insert(matcher.as_ref().to_string(), derive);
+        }
+        self
+    }
+
+    /// Configures the code generator to only derive the traits configured with
+    /// [`archive_derive`](#method.archive_derive) when the given feature of the crate including
+    /// the generated code is enabled, through `#[cfg_attr(feature = "...", ...)]` attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.archive_derive_feature("rkyv");
+    /// ```
+    pub fn archive_derive_feature(&mut self, feature: impl Into<String>) -> &mut Self {
+        self.archive_derive_feature = Some(feature.into());
+        self
+    }
+
+    /// Configures the code generator to name fields after their Protobuf JSON name when
+    /// serialized through serde.
+    ///
+    /// Matched fields get a `#[serde(rename = "...")]` attribute with the `json_name` from the
+    /// field descriptor, which is either the `json_name` option from the `.proto` definition or
+    /// the lowerCamelCase form of the field name. The serde derives themselves are not
+    /// generated, and must be added with [`type_attribute`](#method.type_attribute).
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should be renamed.
+    /// For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]");
+    /// config.serde_json_names(&["."]);
+    /// ```
+    pub fn serde_json_names<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.serde_json_names.clear();
+        for matcher in paths {
+            self.serde_json_names
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to use the original Protobuf field name instead of the
+    /// `json_name` for fields matched by [`serde_json_names`](#method.serde_json_names).
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should keep their
+    /// Protobuf names. For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.serde_json_names(&["."]);
+    /// // Serialize `my_messages.Legacy.user_id` as `user_id` rather than `userId`.
+    /// config.json_use_proto_names(&[".my_messages.Legacy.user_id"]);
+    /// ```
+    pub fn json_use_proto_names<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.json_use_proto_names.clear();
+        for matcher in paths {
+            self.json_use_proto_names
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to generate conversions between messages and
+    /// `serde_json::Value`.
+    ///
+    /// Matched messages get a `TryFrom<serde_json::Value>` implementation, failing with a
+    /// `serde_json::Error` if the value doesn't deserialize to the message, and
+    /// `serde_json::Value` gets a `From` implementation for them, so that messages convert to
+    /// dynamic JSON with `into()`. Conversions go through the serde implementations of the
+    /// messages, which are not generated and must be derived with
+    /// [`type_attribute`](#method.type_attribute), typically along with
+    /// [`serde_json_names`](#method.serde_json_names). The crate including the generated code
+    /// must depend on `serde_json`.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should get the conversions.
+    /// For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]");
+    /// config.serde_json_names(&["."]);
+    /// // Allows `let value: serde_json::Value = settings.into()` and `Settings::try_from(value)`.
+    /// config.serde_json_value_conversions(&[".my_messages.Settings"]);
+    /// ```
+    pub fn serde_json_value_conversions<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.serde_json_value_conversions.clear();
+        for matcher in paths {
+            self.serde_json_value_conversions
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to retain the unknown fields of JSON objects deserialized
+    /// into messages through serde, and serialize them back.
+    ///
+    /// Matched messages get a `json_unknown_fields` field holding the members of the JSON object
+    /// which don't name a field of the message, as a `serde_json::Map` flattened into the
+    /// message by serde. Their values are kept verbatim, so that a gateway translating JSON
+    /// doesn't drop data of a newer schema, including nested objects such as `Any` values with
+    /// their `@type` member. The field is not part of the Protobuf encoding, and is skipped by
+    /// the `Message` derive. Messages which are nested in matched messages only retain their own
+    /// unknown fields if they are matched too.
+    ///
+    /// The serde derives are not generated, and must be added with
+    /// [`type_attribute`](#method.type_attribute). The crate including the generated code must
+    /// depend on `serde_json`.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should retain unknown JSON
+    /// fields. For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]");
+    /// config.serde_json_names(&["."]);
+    /// config.json_unknown_fields(&[".gateway"]);
+    /// ```
+    pub fn json_unknown_fields<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.json_unknown_fields.clear();
+        for matcher in paths {
+            self.json_unknown_fields
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to implement `prost::MessageFields` for messages, which
+    /// iterates over their fields.
+    ///
+    /// Each field which holds a value is yielded with a static `prost::FieldDescriptorMeta`, its
+    /// name and number, and a `prost::FieldValueRef` referencing the value, so that generic
+    /// algorithms such as masking, redaction or size accounting can traverse messages without
+    /// reflection or serde. Nested message fields are yielded as `&dyn MessageFields`, so their
+    /// types must be matched as well. The messages of `prost-types` and the well-known wrapper
+    /// types implemented by `prost` implement `MessageFields`.
+    ///
+    /// Messages with fields of a [`custom_type`](#method.custom_type) are not supported.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages whose messages should implement
+    /// `MessageFields`. For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.field_values(&["."]);
+    /// ```
+    pub fn field_values<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.field_values.clear();
+        for matcher in paths {
+            self.field_values.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to assert at compile time that messages are `Send`, `Sync`
+    /// and `Unpin`.
+    ///
+    /// Generated messages usually are, but a field type chosen with
+    /// [`custom_type`](#method.custom_type) or [`extern_path`](#method.extern_path), such as a type
+    /// holding an `Rc`, can make a message lose these traits. Without the assertion, this is only
+    /// discovered where the message is used, such as in the future of an async service, with an
+    /// error far from the cause. With it, the generated code fails to compile next to the
+    /// message, naming the type which is missing the trait.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should be checked. For details
+    /// about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Check all messages.
+    /// config.send_sync_assertions(&["."]);
+    /// ```
+    pub fn send_sync_assertions<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.send_sync_assertions.clear();
+        for matcher in paths {
+            self.send_sync_assertions
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures the code generator to track the presence of optional proto2 scalar fields with
+    /// a bitset, rather than wrapping each of them in an `Option`.
+    ///
+    /// An `Option<f64>` takes twice the space of the `f64`, so messages with many optional
+    /// scalar fields grow large. With a bitset, the values are stored inline, and a single
+    /// `_presence` field of type `prost::PresenceBits` holds a bit per field. The fields become
+    /// private, and are accessed through generated methods: for a field `foo`, `foo()` returns
+    /// the value or the default, `has_foo()` whether it is set, and `set_foo()` and `clear_foo()`
+    /// set and unset it.
+    ///
+    /// Only optional fields of numeric, `bool` and enum types use the bitset, so message fields,
+    /// including those with a [`custom_type`](#method.custom_type), keep their `Option`. So do
+    /// fields wrapped in a [`newtype`](#method.newtype).
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages whose messages should use presence
+    /// bitsets. For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Use presence bitsets for the messages of the `telemetry` package.
+    /// config.presence_bitsets(&[".telemetry"]);
+    /// ```
+    pub fn presence_bitsets<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.presence_bitsets.clear();
+        for matcher in paths {
+            self.presence_bitsets
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate a `Display` implementation for matched messages, formatting the value of one of
+    /// their fields.
+    ///
+    /// The field must be a singular string, numeric or `bool` field, outside of any oneof. Unset
+    /// optional fields are formatted as their default value. This allows logging messages such as
+    /// users by their name, rather than with the whole `Debug` output.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of messages. For details about matching messages
+    /// see [`btree_map`](#method.btree_map). If multiple paths match a message, the first one
+    /// configured is used.
+    ///
+    /// **`field`** - the name of the field of the matched messages, as in the `.proto` file.
+    ///
+    /// # Panics
+    ///
+    /// The generation panics if a matched message has no such field, or if the field can not be
+    /// formatted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // `format!("{}", user)` returns the name of the user.
+    /// config.display_from_field(".my_app.User", "name");
+    /// ```
+    pub fn display_from_field<P, F>(&mut self, path: P, field: F) -> &mut Self
+    where
+        P: AsRef<str>,
+        F: AsRef<str>,
+    {
+        self.display_fields
+            .insert(path.as_ref().to_string(), field.as_ref().to_string());
+        self
+    }
+
+    /// Configures the code generator to generate `From` implementations for oneof variants.
+    ///
+    /// For every variant of a oneof in a matched message, `From<Variant>` is implemented for the
+    /// oneof enum, and for the message itself by setting the oneof and defaulting all other
+    /// fields. Enumeration variants are converted from the Rust enum type. Conversions are
+    /// skipped for variant types which are not unique, since they would be ambiguous.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should get the
+    /// implementations. For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Allows writing `msg.kind = Some(circle.into())` or `Shape::from(circle)`.
+    /// config.oneof_from_impls(&[".my_messages.Shape"]);
+    /// ```
+    pub fn oneof_from_impls<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.oneof_from_impls.clear();
+        for matcher in paths {
+            self.oneof_from_impls
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
     /// Declare an externally provided Protobuf package or type.
     ///
     /// `extern_path` allows `prost` types in external crates to be referenced in generated code.
@@ -547,6 +1731,114 @@ impl Config {
         self
     }
 
+    /// Declare a Protobuf package and all its nested packages as provided by an external crate.
+    ///
+    /// This is a superset of [`extern_path`](#method.extern_path) for crates generated from a
+    /// hierarchy of packages: the types of `proto_package` and of the packages nested in it are
+    /// referenced in the matching modules of the crate instead of being generated. Nested packages
+    /// whose module doesn't follow the package hierarchy are declared with
+    /// [`CrateRemap::module`], rather than with an extern path each.
+    ///
+    /// With an [`include_file`](#method.include_file), the modules of the remapped packages which
+    /// are compiled or imported re-export the contents of the crate modules with a glob import,
+    /// so that the include file still exposes the complete tree of packages. The re-exports can
+    /// be gated on a feature with [`CrateRemap::feature`].
+    ///
+    /// # Arguments
+    ///
+    /// **`proto_package`** - a fully-qualified Protobuf package, such as `.acme`.
+    ///
+    /// **`remap`** - the Rust path of the crate or module providing the package, or a
+    /// [`CrateRemap`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use prost_build::CrateRemap;
+    /// # let mut config = prost_build::Config::new();
+    /// // `.google.protobuf.Timestamp` is `::prost_types::Timestamp`.
+    /// config.crate_remap(".google.protobuf", "::prost_types");
+    ///
+    /// // `.acme.billing.v1.Invoice` is `::acme_protos::billing::v1::Invoice`, but
+    /// // `.acme.identity.v2.User` is `::acme_protos::identity::User`, and the include file
+    /// // re-exports the packages only with the `acme` feature.
+    /// config.crate_remap(
+    ///     ".acme",
+    ///     CrateRemap::new("::acme_protos")
+    ///         .module(".acme.identity.v2", "identity")
+    ///         .feature("acme"),
+    /// );
+    /// ```
+    pub fn crate_remap<P, R>(&mut self, proto_package: P, remap: R) -> &mut Self
+    where
+        P: Into<String>,
+        R: Into<CrateRemap>,
+    {
+        self.crate_remaps.push((proto_package.into(), remap.into()));
+        self
+    }
+
+    /// Generates several Protobuf packages into the Rust module of a single package.
+    ///
+    /// This is useful when a package is split in versions, such as `foo.v1` and `foo.v1beta`,
+    /// whose types should be used interchangeably. The types of the merged packages are
+    /// generated in the module of the `module` package, and references between them are
+    /// resolved to that module.
+    ///
+    /// Top-level messages and enums defined identically by several merged packages, after
+    /// resolving the package of their field types, are generated once. Generation fails with an
+    /// error if merged packages define different types with the same name. Generated `Name`
+    /// implementations and services keep the package they are defined in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Generate the types of `foo.v1` and `foo.v1beta` in the `foo` module.
+    /// config.merge_packages(["foo.v1", "foo.v1beta"], "foo");
+    /// ```
+    pub fn merge_packages<I, S>(&mut self, packages: I, module: impl AsRef<str>) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for package in packages {
+            self.merged_packages
+                .insert(package.as_ref(), module.as_ref());
+        }
+        self
+    }
+
+    /// Registers a transformation of the `FileDescriptorSet`, applied before generating code.
+    ///
+    /// Transformations run in registration order on the descriptors passed to
+    /// [`Self::compile_fds()`], including those loaded by [`Self::compile_protos()`], and can be
+    /// used to strip source info, rename packages or add options. The file written to
+    /// [`Self::file_descriptor_set_path()`] is not affected.
+    ///
+    /// # Example `build.rs`
+    ///
+    /// ```rust,no_run
+    /// # use std::io::Result;
+    /// fn main() -> Result<()> {
+    ///   prost_build::Config::new()
+    ///       .transform_fds(|fds| {
+    ///           for file in &mut fds.file {
+    ///               file.source_code_info = None;
+    ///           }
+    ///       })
+    ///       .compile_protos(&["src/frontend.proto", "src/backend.proto"], &["src"])?;
+    ///   Ok(())
+    /// }
+    /// ```
+    pub fn transform_fds<F>(&mut self, transform: F) -> &mut Self
+    where
+        F: FnMut(&mut FileDescriptorSet) + 'static,
+    {
+        self.fds_transforms.push(Box::new(transform));
+        self
+    }
+
     /// When set, the `FileDescriptorSet` generated by `protoc` is written to the provided
     /// filesystem path.
     ///
@@ -619,36 +1911,229 @@ impl Config {
         self
     }
 
+    /// Configures `protoc` to write the file descriptor set to its standard output rather than to
+    /// a temporary file.
+    ///
+    /// By default, unless [`Self::file_descriptor_set_path()`] is set, `protoc` writes the file
+    /// descriptor set to a file in a temporary directory, which fails in sandboxes without a
+    /// writable temporary directory, such as some Bazel and Nix builds. With this option, the
+    /// file descriptor set is read from a pipe to `protoc`, through `/dev/stdout`.
+    ///
+    /// On platforms without `/dev/stdout`, such as Windows, a temporary file is still used.
+    ///
+    /// In `build.rs`:
+    ///
+    /// ```rust,no_run
+    /// # let mut config = prost_build::Config::new();
+    /// config.descriptor_set_from_stdout()
+    ///     .compile_protos(&["src/items.proto"], &["src/"]);
+    /// ```
+    pub fn descriptor_set_from_stdout(&mut self) -> &mut Self {
+        self.descriptor_set_from_stdout = true;
+        self
+    }
+
     /// Configures the code generator to not strip the enum name from variant names.
     ///
-    /// Protobuf enum definitions commonly include the enum name as a prefix of every variant name.
-    /// This style is non-idiomatic in Rust, so by default `prost` strips the enum name prefix from
-    /// variants which include it. Configuring this option prevents `prost` from stripping the
-    /// prefix.
-    pub fn retain_enum_prefix(&mut self) -> &mut Self {
-        self.strip_enum_prefix = false;
+    /// Protobuf enum definitions commonly include the enum name as a prefix of every variant name.
+    /// This style is non-idiomatic in Rust, so by default `prost` strips the enum name prefix from
+    /// variants which include it. Configuring this option prevents `prost` from stripping the
+    /// prefix.
+    ///
+    /// This is a shorthand for `enum_variant_naming(EnumVariantNaming::UpperCamel)`.
+    pub fn retain_enum_prefix(&mut self) -> &mut Self {
+        self.enum_variant_naming(EnumVariantNaming::UpperCamel)
+    }
+
+    /// Configures how the variants of generated enums are named.
+    ///
+    /// By default, the enum name is stripped from the start of the variant names, see
+    /// [`EnumVariantNaming`] for the alternatives. The names are checked for collisions, and the
+    /// generation panics if two values of an enum get the same variant name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use prost_build::EnumVariantNaming;
+    /// # let mut config = prost_build::Config::new();
+    /// // Generate `Color::COLOR_RED` for the `COLOR_RED` value of the `Color` enum.
+    /// config.enum_variant_naming(EnumVariantNaming::Original);
+    /// ```
+    pub fn enum_variant_naming(&mut self, naming: EnumVariantNaming) -> &mut Self {
+        self.enum_variant_naming = naming;
+        self
+    }
+
+    /// Configures a function naming the variants of generated enums, overriding
+    /// [`enum_variant_naming`](#method.enum_variant_naming).
+    ///
+    /// The function is called with the fully-qualified Protobuf name of the enum, such as
+    /// `.my_package.Color`, and the name of the value in the Protobuf definition. Names which are
+    /// Rust keywords are escaped, and the generation panics if two values of an enum get the
+    /// same name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Strip the `k` prefix of values such as `kColorRed`.
+    /// config.enum_variant_renamer(|_enum_name, value_name| {
+    ///     value_name.strip_prefix('k').unwrap_or(value_name).to_string()
+    /// });
+    /// ```
+    pub fn enum_variant_renamer<F>(&mut self, renamer: F) -> &mut Self
+    where
+        F: Fn(&str, &str) -> String + 'static,
+    {
+        self.enum_variant_renamer = Some(Box::new(renamer));
+        self
+    }
+
+    /// Configures how the names of the Protobuf definitions are converted to Rust identifiers.
+    ///
+    /// By default, names are converted with [`DefaultIdentPolicy`], which uses the `heck` crate
+    /// and, for example, converts a `HTTPServer2` message to a `HttpServer2` struct. Projects
+    /// with naming conventions the default conversion handles poorly, such as acronyms or
+    /// numbers, can keep control of the generated names, and avoid collisions between them,
+    /// with their own [`IdentPolicy`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use prost_build::IdentPolicy;
+    /// # let mut config = prost_build::Config::new();
+    /// struct KeepAcronyms;
+    ///
+    /// impl IdentPolicy for KeepAcronyms {
+    ///     fn to_upper_camel(&self, name: &str) -> String {
+    ///         name.to_string()
+    ///     }
+    /// }
+    ///
+    /// config.ident_policy(KeepAcronyms);
+    /// ```
+    pub fn ident_policy<P>(&mut self, policy: P) -> &mut Self
+    where
+        P: IdentPolicy + 'static,
+    {
+        self.ident_policy = Box::new(policy);
+        self
+    }
+
+    /// Configures the output directory where generated Rust files will be written.
+    ///
+    /// If unset, defaults to the `OUT_DIR` environment variable. `OUT_DIR` is set by Cargo when
+    /// executing build scripts, so `out_dir` typically does not need to be configured.
+    pub fn out_dir<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.out_dir = Some(path.into());
+        self
+    }
+
+    /// Configures what filename protobufs with no package definition are written to.
+    /// The filename will be appended with the `.rs` extension.
+    pub fn default_package_filename<S>(&mut self, filename: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.default_package_filename = filename.into();
+        self
+    }
+
+    /// Configures the code generator to document the module of each package at the top of its
+    /// generated file, with `//!` inner doc comments.
+    ///
+    /// The documentation of a package is taken from the comments preceding its `package`
+    /// statement. It is always added to the module tree of the
+    /// [`include_file`](#method.include_file).
+    ///
+    /// Inner doc comments are not supported in files included with the `include!` macro, so this
+    /// should only be enabled if the generated files are used as module sources, for example with
+    /// `#[path = "..."] mod my_package;`. Disabled by default.
+    pub fn inner_package_docs(&mut self, enable: bool) -> &mut Self {
+        self.inner_package_docs = enable;
+        self
+    }
+
+    /// Configures the code generator to add constants describing the Protobuf package and files
+    /// each module is generated from, so that applications can report the provenance of their
+    /// schema at runtime.
+    ///
+    /// The generated constants are:
+    ///
+    /// - `PACKAGE: &str`, the name of the package, such as `"foo.bar"`. The package merged into
+    ///   with [`merge_packages`](#method.merge_packages) is used for merged packages.
+    /// - `PROTO_FILES: &[&str]`, the names of the `.proto` files of the package, relative to their
+    ///   include directory.
+    /// - `SYNTAX: &str`, the syntax of the files, such as `"proto3"`. Files without a `syntax`
+    ///   statement are `"proto2"`. If the files of a package use different syntaxes, they are
+    ///   all listed, separated by commas.
+    ///
+    /// Disabled by default.
+    pub fn package_constants(&mut self, enable: bool) -> &mut Self {
+        self.package_constants = enable;
+        self
+    }
+
+    /// Configures the code generator to add constants describing each service, so that clients
+    /// building raw gRPC requests or routing tables don't have to hardcode them. The constants
+    /// are generated with or without a [`ServiceGenerator`].
+    ///
+    /// The constants of a service `Greeter` in the `helloworld` package are generated in a
+    /// `greeter_descriptor` module:
+    ///
+    /// - `NAME: &str`, the fully-qualified name of the service, such as `"helloworld.Greeter"`.
+    /// - `METHOD_NAMES: &[&str]`, the names of the methods, such as `["SayHello"]`.
+    /// - `METHOD_PATHS: &[&str]`, the gRPC paths of the methods, such as
+    ///   `["/helloworld.Greeter/SayHello"]`.
+    ///
+    /// and in a module for each method, such as `greeter_descriptor::say_hello`:
+    ///
+    /// - `NAME: &str` and `PATH: &str`, the name and the gRPC path of the method.
+    /// - `INPUT_TYPE_URL: &str` and `OUTPUT_TYPE_URL: &str`, the type URLs of the request and
+    ///   response messages, with the domains configured with
+    ///   [`type_name_domain`](#method.type_name_domain).
+    /// - `CLIENT_STREAMING: bool` and `SERVER_STREAMING: bool`.
+    ///
+    /// Disabled by default.
+    pub fn service_descriptors(&mut self, enable: bool) -> &mut Self {
+        self.service_descriptors = enable;
         self
     }
 
-    /// Configures the output directory where generated Rust files will be written.
+    /// Configures the code generator to check for reserved field numbers when decoding.
     ///
-    /// If unset, defaults to the `OUT_DIR` environment variable. `OUT_DIR` is set by Cargo when
-    /// executing build scripts, so `out_dir` typically does not need to be configured.
-    pub fn out_dir<P>(&mut self, path: P) -> &mut Self
-    where
-        P: Into<PathBuf>,
-    {
-        self.out_dir = Some(path.into());
+    /// When enabled, decoding a message fails with a `DecodeError` if it contains a field whose
+    /// number is declared `reserved` in the message definition, instead of skipping it as an
+    /// unknown field. This helps detecting old clients writing to reclaimed field numbers.
+    ///
+    /// See [`reserved_field_callback`](#method.reserved_field_callback) to observe such fields
+    /// without failing.
+    pub fn enforce_reserved(&mut self, enforce: bool) -> &mut Self {
+        self.enforce_reserved = enforce;
         self
     }
 
-    /// Configures what filename protobufs with no package definition are written to.
-    /// The filename will be appended with the `.rs` extension.
-    pub fn default_package_filename<S>(&mut self, filename: S) -> &mut Self
+    /// Configures a function to call when a reserved field number is decoded.
+    ///
+    /// The function is called with the message name and the field number, and must have the
+    /// signature `fn(&'static str, u32)`. The field is skipped afterwards rather than failing the
+    /// decode. Enables [`enforce_reserved`](#method.enforce_reserved).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.reserved_field_callback("crate::metrics::count_reserved_field");
+    /// ```
+    pub fn reserved_field_callback<P>(&mut self, path: P) -> &mut Self
     where
-        S: Into<String>,
+        P: AsRef<str>,
     {
-        self.default_package_filename = filename.into();
+        self.enforce_reserved = true;
+        self.reserved_field_callback = Some(path.as_ref().to_string());
         self
     }
 
@@ -705,6 +2190,13 @@ impl Config {
 
     /// Add an argument to the `protoc` protobuf compilation invocation.
     ///
+    /// Arguments are passed after the include paths and before the `.proto` files, in the order
+    /// they were added. They must be flags: `.proto` files are passed to
+    /// [`Self::compile_protos()`], and the file descriptor set output is configured with
+    /// [`Self::file_descriptor_set_path()`]. Compilation fails with an `InvalidInput` error if an
+    /// argument is not a flag, or is a flag which overrides the output or the mode of `protoc`,
+    /// such as `-o`, `--descriptor_set_out`, `--decode` or `--version`.
+    ///
     /// # Example `build.rs`
     ///
     /// ```rust,no_run
@@ -725,6 +2217,50 @@ impl Config {
         self
     }
 
+    /// Add arguments to the `protoc` protobuf compilation invocation.
+    ///
+    /// See [`Self::protoc_arg()`] for the arguments which are accepted.
+    ///
+    /// # Example `build.rs`
+    ///
+    /// ```rust,no_run
+    /// # use std::io::Result;
+    /// fn main() -> Result<()> {
+    ///   let mut prost_build = prost_build::Config::new();
+    ///   prost_build.protoc_args(["--experimental_allow_proto3_optional", "-Ithird_party"]);
+    ///   prost_build.compile_protos(&["src/frontend.proto", "src/backend.proto"], &["src"])?;
+    ///   Ok(())
+    /// }
+    /// ```
+    pub fn protoc_args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.protoc_args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self
+    }
+
+    /// Allows `protoc` to compile `.proto` files which use Protobuf editions, by passing the
+    /// `--experimental_editions` flag.
+    ///
+    /// The flag is required by `protoc` versions which support editions only experimentally, and
+    /// for editions which are not released yet. Older versions of `protoc` reject it.
+    pub fn experimental_editions(&mut self) -> &mut Self {
+        self.experimental_editions = true;
+        self
+    }
+
+    /// Makes `protoc` fail on warnings, such as unused imports, by passing the `--fatal_warnings`
+    /// flag.
+    ///
+    /// Older versions of `protoc` reject the flag.
+    pub fn fatal_warnings(&mut self) -> &mut Self {
+        self.fatal_warnings = true;
+        self
+    }
+
     /// Set the path to `protoc` executable to be used by `prost-build`
     ///
     /// Use the provided path to find `protoc`. This can either be a file name which is
@@ -745,10 +2281,64 @@ impl Config {
     where
         S: Into<PathBuf>,
     {
-        self.protoc_executable = executable.into();
+        self.protoc_executable = Some(executable.into());
+        self
+    }
+
+    /// Stops `prost-build` from reading environment variables.
+    ///
+    /// By default, `prost-build` reads `PROTOC` to find the `protoc` executable, `PROTOC_INCLUDE`
+    /// for the Protobuf include directory, and `OUT_DIR` for the output directory. In hermetic
+    /// mode these are ignored, unless allowed with [`Self::allow_env_var()`], so the executable
+    /// must be set with [`Self::protoc_executable()`] (or [`Self::skip_protoc_run()`] used) and the
+    /// output directory with [`Self::out_dir()`].
+    ///
+    /// Each allowed variable which is read is reported to Cargo with a
+    /// `cargo:rerun-if-env-changed` directive. Note that Cargo no longer reruns the build script
+    /// whenever a file in the package changes once any `rerun-if` directive is emitted, so the
+    /// build script should emit `rerun-if-changed` directives for its `.proto` files as well.
+    ///
+    /// # Example `build.rs`
+    ///
+    /// ```rust,no_run
+    /// # use std::io::Result;
+    /// fn main() -> Result<()> {
+    ///   prost_build::Config::new()
+    ///       .hermetic()
+    ///       .allow_env_var("OUT_DIR")
+    ///       .protoc_executable("/nix/store/protobuf/bin/protoc")
+    ///       .compile_protos(&["src/frontend.proto", "src/backend.proto"], &["src"])?;
+    ///   println!("cargo:rerun-if-changed=src");
+    ///   Ok(())
+    /// }
+    /// ```
+    pub fn hermetic(&mut self) -> &mut Self {
+        self.hermetic = true;
+        self
+    }
+
+    /// Allows reading an environment variable in [hermetic mode](Self::hermetic()).
+    ///
+    /// The variables read by `prost-build` are `PROTOC`, `PROTOC_INCLUDE` and `OUT_DIR`.
+    pub fn allow_env_var<S>(&mut self, name: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.allowed_env_vars.push(name.into());
         self
     }
 
+    /// Reads an environment variable through `read`, unless disallowed by hermetic mode.
+    fn read_env<T>(&self, name: &str, read: impl FnOnce() -> T) -> Option<T> {
+        if self.hermetic {
+            if !self.allowed_env_vars.iter().any(|allowed| allowed == name) {
+                return None;
+            }
+            println!("cargo:rerun-if-env-changed={}", name);
+        }
+        Some(read())
+    }
+
     /// Configures the optional module filename for easy inclusion of all generated Rust files
     ///
     /// If set, generates a file (inside the `OUT_DIR` or `out_dir()` as appropriate) which contains
@@ -782,9 +2372,127 @@ impl Config {
         self
     }
 
+    /// Configures a function returning the lines written before each module declared by the
+    /// [`include_file`](#method.include_file), such as attributes and doc comments.
+    ///
+    /// The function is called with each module of the include file, including the parent modules
+    /// which only contain other modules, such as `foo` for a `foo.bar` package. This allows
+    /// compiling some packages only with a feature, or allowing lints in them, without
+    /// post-processing the include file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config
+    ///     .include_file("_includes.rs")
+    ///     .include_module_attributes(|module| {
+    ///         if module.to_rust_path() == "admin" {
+    ///             vec![
+    ///                 "/// Definitions of the administration API.".to_string(),
+    ///                 "#[cfg(feature = \"admin\")]".to_string(),
+    ///             ]
+    ///         } else {
+    ///             vec!["#[allow(clippy::all)]".to_string()]
+    ///         }
+    ///     });
+    /// ```
+    pub fn include_module_attributes<F>(&mut self, attributes: F) -> &mut Self
+    where
+        F: Fn(&Module) -> Vec<String> + 'static,
+    {
+        self.include_module_attributes = Some(Box::new(attributes));
+        self
+    }
+
+    /// Configures the code generator to write a `prelude.rs` file, which re-exports the top-level
+    /// messages and enums of all packages under one flat namespace.
+    ///
+    /// The prelude is made of `pub use super::...;` items, so it must be included in a module
+    /// next to the package modules. With an [`include_file`](#method.include_file), the include
+    /// file declares a `prelude` module for it:
+    ///
+    /// ```rust,no_run,ignore
+    /// include!(concat!(env!("OUT_DIR"), "/_includes.rs"));
+    ///
+    /// use prelude::*;
+    /// ```
+    ///
+    /// Otherwise, it is included by hand:
+    ///
+    /// ```rust,no_run,ignore
+    /// pub mod prelude {
+    ///     include!(concat!(env!("OUT_DIR"), "/prelude.rs"));
+    /// }
+    /// ```
+    ///
+    /// A name defined by several packages is ambiguous, so it is left out of the prelude, and the
+    /// colliding types are listed in a comment instead. Types mapped to external paths with
+    /// [`extern_path`](#method.extern_path) are not re-exported either.
+    ///
+    /// Disabled by default.
+    pub fn generate_prelude(&mut self, enable: bool) -> &mut Self {
+        self.generate_prelude = enable;
+        self
+    }
+
+    /// Configures the code generator to emit a `cargo:warning` for each suspicious construct of
+    /// the compiled `.proto` files.
+    ///
+    /// These constructs are valid Protobuf, but often surface as confusing compile errors or
+    /// surprising code in the generated code:
+    ///
+    /// - oneofs with so many variants that their generated enum is large,
+    /// - message fields which are boxed automatically because their message is recursive,
+    /// - fields using the deprecated group syntax, and deprecated weak imports,
+    /// - files without package declaration,
+    /// - names which are converted to the same Rust identifier in the same scope, such as the
+    ///   fields `fooBar` and `foo_bar` of a message.
+    ///
+    /// The warnings are emitted before the code is generated, so that they are also shown when
+    /// the generation fails.
+    ///
+    /// Disabled by default.
+    pub fn schema_warnings(&mut self, enable: bool) -> &mut Self {
+        self.schema_warnings = enable;
+        self
+    }
+
+    /// Configures the code generator to write a report about the compiled `.proto` files to the
+    /// given path in the output directory.
+    ///
+    /// The report lists the number of files, messages, enums, fields, oneofs and services, and
+    /// the suspicious constructs described in [`schema_warnings`](#method.schema_warnings), one
+    /// `name: value` line each. It allows tracking the size of the schema, or failing CI on new
+    /// warnings.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use std::io::Result;
+    /// fn main() -> Result<()> {
+    ///   prost_build::Config::new()
+    ///       .schema_warnings(true)
+    ///       .schema_report("schema_report.txt")
+    ///       .compile_protos(&["src/frontend.proto", "src/backend.proto"], &["src"])?;
+    ///   Ok(())
+    /// }
+    /// ```
+    pub fn schema_report<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.schema_report = Some(path.into());
+        self
+    }
+
     // IMPROVEMENT: https://github.com/tokio-rs/prost/pull/1022/files#r1563818651
     /// Configures the code generator to format the output code via `prettyplease`.
     ///
+    /// Formatting is done in-process on the parsed code, so no `rustfmt` executable is needed.
+    /// Generated code which does not parse, for example because of an invalid custom attribute,
+    /// is reported as an error.
+    ///
     /// By default, this is enabled but if the `format` feature is not enabled this does
     /// nothing.
     #[cfg(feature = "format")]
@@ -812,28 +2520,57 @@ impl Config {
     /// }
     /// ```
     pub fn compile_fds(&mut self, fds: FileDescriptorSet) -> Result<()> {
+        self.compile_fds_with_modules(fds).map(|_| ())
+    }
+
+    /// Compile a [`FileDescriptorSet`] into Rust files like [`Config::compile_fds`], returning
+    /// the path of the file written for each generated module.
+    ///
+    /// Modules without any generated code have no file, and are not returned. This allows build
+    /// scripts to post-process the generated files without deriving their names from the
+    /// packages.
+    ///
+    /// # Example `build.rs`
+    ///
+    /// ```rust,no_run
+    /// # use prost_types::FileDescriptorSet;
+    /// # fn fds() -> FileDescriptorSet { todo!() }
+    /// fn main() -> std::io::Result<()> {
+    ///   let modules = prost_build::Config::new().compile_fds_with_modules(fds())?;
+    ///   for (module, path) in &modules {
+    ///       println!("{} is generated in {}", module.to_rust_path(), path.display());
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    pub fn compile_fds_with_modules(
+        &mut self,
+        fds: FileDescriptorSet,
+    ) -> Result<BTreeMap<Module, PathBuf>> {
         let mut target_is_env = false;
-        let target: PathBuf = self.out_dir.clone().map(Ok).unwrap_or_else(|| {
-            env::var_os("OUT_DIR")
-                .ok_or_else(|| {
-                    Error::new(ErrorKind::Other, "OUT_DIR environment variable is not set")
-                })
-                .map(|val| {
+        let target: PathBuf = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => match self.read_env("OUT_DIR", || env::var_os("OUT_DIR")) {
+                Some(Some(val)) => {
                     target_is_env = true;
-                    Into::into(val)
-                })
-        })?;
+                    val.into()
+                }
+                Some(None) => {
+                    return Err(Error::new(
+                        ErrorKind::Other,
+                        "OUT_DIR environment variable is not set",
+                    ))
+                }
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::Other,
+                        "out_dir is required in hermetic mode",
+                    ))
+                }
+            },
+        };
 
-        let requests = fds
-            .file
-            .into_iter()
-            .map(|descriptor| {
-                (
-                    Module::from_protobuf_package_name(descriptor.package()),
-                    descriptor,
-                )
-            })
-            .collect::<Vec<_>>();
+        let requests = self.requests(fds);
 
         let file_names = requests
             .iter()
@@ -845,7 +2582,50 @@ impl Config {
             })
             .collect::<HashMap<Module, String>>();
 
+        let (package_docs, reexports) = if self.include_file.is_some() {
+            (
+                self.package_docs(&requests),
+                self.crate_remap_reexports(&requests),
+            )
+        } else {
+            (HashMap::new(), BTreeMap::new())
+        };
+
+        let prelude = if self.generate_prelude {
+            if let Some(file_name) = file_names.iter().find_map(|(module, file_name)| {
+                let conflicts = file_name == PRELUDE_FILE_NAME
+                    || (self.include_file.is_some() && module.parts().next() == Some("prelude"));
+                conflicts.then_some(file_name)
+            }) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "the prelude of generate_prelude conflicts with the module generated in {}",
+                        file_name
+                    ),
+                ));
+            }
+            Some(self.prelude_types(&requests)?)
+        } else {
+            None
+        };
+
+        if self.schema_warnings || self.schema_report.is_some() {
+            let report = SchemaReport::new(self, &requests);
+            if self.schema_warnings {
+                for warning in &report.warnings {
+                    println!("cargo:warning={}", warning);
+                }
+            }
+            if let Some(ref schema_report) = self.schema_report {
+                let path = target.join(schema_report);
+                trace!("Writing schema report: {}", path.display());
+                write_file_if_changed(&path, report.to_string().as_bytes())?;
+            }
+        }
+
         let modules = self.generate(requests)?;
+        let mut output_paths = BTreeMap::new();
         for (module, content) in &modules {
             let file_name = file_names
                 .get(module)
@@ -853,6 +2633,17 @@ impl Config {
             let output_path = target.join(file_name);
 
             write_file_if_changed(&output_path, content.as_bytes())?;
+            output_paths.insert(module.clone(), output_path);
+        }
+
+        if let Some(ref prelude) = prelude {
+            let path = target.join(PRELUDE_FILE_NAME);
+            trace!("Writing prelude file: {}", path.display());
+            let mut buffer = Vec::new();
+            self.write_line(&mut buffer, 0, "// This file is @generated by prost-build.")?;
+            self.write_prelude(prelude, &modules, &mut buffer)?;
+
+            write_file_if_changed(&path, &buffer)?;
         }
 
         if let Some(ref include_file) = self.include_file {
@@ -865,12 +2656,39 @@ impl Config {
                 &mut buffer,
                 if target_is_env { None } else { Some(&target) },
                 &file_names,
+                &package_docs,
+                &reexports,
             )?;
+            if prelude.is_some() {
+                self.write_line(
+                    &mut buffer,
+                    0,
+                    "/// The top-level messages and enums of all packages.",
+                )?;
+                self.write_line(&mut buffer, 0, "pub mod prelude {")?;
+                if target_is_env {
+                    self.write_line(
+                        &mut buffer,
+                        1,
+                        &format!(
+                            "include!(concat!(env!(\"OUT_DIR\"), \"/{}\"));",
+                            PRELUDE_FILE_NAME
+                        ),
+                    )?;
+                } else {
+                    self.write_line(
+                        &mut buffer,
+                        1,
+                        &format!("include!(\"{}\");", PRELUDE_FILE_NAME),
+                    )?;
+                }
+                self.write_line(&mut buffer, 0, "}")?;
+            }
 
             write_file_if_changed(&path, &buffer)?;
         }
 
-        Ok(())
+        Ok(output_paths)
     }
 
     /// Loads `.proto` files as a [`FileDescriptorSet`]. This allows inspection of the descriptors
@@ -909,6 +2727,7 @@ impl Config {
         includes: &[impl AsRef<Path>],
     ) -> Result<FileDescriptorSet> {
         let tmp;
+        let mut from_stdout = false;
         let file_descriptor_set_path = if let Some(path) = &self.file_descriptor_set_path {
             path.clone()
         } else {
@@ -918,21 +2737,36 @@ impl Config {
                     "file_descriptor_set_path is required with skip_protoc_run",
                 ));
             }
-            tmp = tempfile::Builder::new().prefix("prost-build").tempdir()?;
-            tmp.path().join("prost-descriptor-set")
+            match stdout_path().filter(|_| self.descriptor_set_from_stdout) {
+                Some(path) => {
+                    from_stdout = true;
+                    path.to_path_buf()
+                }
+                None => {
+                    tmp = tempfile::Builder::new().prefix("prost-build").tempdir()?;
+                    tmp.path().join("prost-descriptor-set")
+                }
+            }
         };
 
+        let mut stdout = None;
+
         if !self.skip_protoc_run {
-            let mut cmd = Command::new(&self.protoc_executable);
+            for arg in &self.protoc_args {
+                check_protoc_arg(arg)?;
+            }
+
+            let protoc_executable = self.resolve_protoc_executable()?;
+            let mut cmd = Command::new(&protoc_executable);
             cmd.arg("--include_imports");
             if !self.skip_source_info {
                 cmd.arg("--include_source_info");
             }
-            cmd.arg("-o").arg(&file_descriptor_set_path);
+            cmd.arg("-o").arg(protoc_path(&file_descriptor_set_path));
 
             for include in includes {
                 if include.as_ref().exists() {
-                    cmd.arg("-I").arg(include.as_ref());
+                    cmd.arg("-I").arg(protoc_path(include.as_ref()));
                 } else {
                     debug!(
                         "ignoring {} since it does not exist.",
@@ -943,60 +2777,103 @@ impl Config {
 
             // Set the protoc include after the user includes in case the user wants to
             // override one of the built-in .protos.
-            if let Some(protoc_include) = protoc_include_from_env() {
-                cmd.arg("-I").arg(protoc_include);
+            if let Some(protoc_include) = self
+                .read_env("PROTOC_INCLUDE", protoc_include_from_env)
+                .flatten()
+            {
+                cmd.arg("-I").arg(protoc_path(&protoc_include));
             }
 
+            if self.experimental_editions {
+                cmd.arg("--experimental_editions");
+            }
+            if self.fatal_warnings {
+                cmd.arg("--fatal_warnings");
+            }
             for arg in &self.protoc_args {
                 cmd.arg(arg);
             }
 
             for proto in protos {
-                cmd.arg(proto.as_ref());
+                cmd.arg(protoc_path(proto.as_ref()));
             }
 
-            debug!("Running: {:?}", cmd);
-
-            let output = match cmd.output() {
-            Err(err) if ErrorKind::NotFound == err.kind() => return Err(Error::new(
-                err.kind(),
-                error_message_protoc_not_found()
-            )),
-            Err(err) => return Err(Error::new(
-                err.kind(),
-                format!("failed to invoke protoc (hint: https://docs.rs/prost-build/#sourcing-protoc): (path: {}): {}", &self.protoc_executable.display(), err),
-            )),
-            Ok(output) => output,
-        };
-
-            if !output.status.success() {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    format!("protoc failed: {}", String::from_utf8_lossy(&output.stderr)),
-                ));
+            let output = run_protoc(&mut cmd, &protoc_executable)?;
+            if from_stdout {
+                stdout = Some(output.stdout);
             }
         }
 
-        let buf = fs::read(&file_descriptor_set_path).map_err(|e| {
-            Error::new(
-                e.kind(),
-                format!(
-                    "unable to open file_descriptor_set_path: {}, OS: {}",
-                    file_descriptor_set_path.display(),
-                    e
-                ),
-            )
-        })?;
+        let buf = match stdout {
+            Some(buf) => buf,
+            None => fs::read(&file_descriptor_set_path).map_err(|e| {
+                Error::new(
+                    e.kind(),
+                    format!(
+                        "unable to open file_descriptor_set_path: {}, OS: {}",
+                        file_descriptor_set_path.display(),
+                        e
+                    ),
+                )
+            })?,
+        };
         let file_descriptor_set = FileDescriptorSet::decode(buf.as_slice()).map_err(|error| {
             Error::new(
                 ErrorKind::InvalidInput,
                 format!("invalid FileDescriptorSet: {}", error),
             )
         })?;
+        self.raw_method_options = raw_method_options(&buf);
 
         Ok(file_descriptor_set)
     }
 
+    /// Returns the version of the `protoc` executable used by this configuration.
+    ///
+    /// This runs `protoc --version`, so build scripts can check which Protobuf features are
+    /// available before compiling.
+    ///
+    /// # Example `build.rs`
+    ///
+    /// ```rust,no_run
+    /// # use std::io::Result;
+    /// fn main() -> Result<()> {
+    ///   let mut prost_build = prost_build::Config::new();
+    ///   let version = prost_build.protoc_version()?;
+    ///   if version.supports_proto3_optional() && !version.supports_proto3_optional_by_default() {
+    ///       prost_build.protoc_arg("--experimental_allow_proto3_optional");
+    ///   }
+    ///   prost_build.compile_protos(&["src/frontend.proto", "src/backend.proto"], &["src"])?;
+    ///   Ok(())
+    /// }
+    /// ```
+    pub fn protoc_version(&self) -> Result<ProtocVersion> {
+        let protoc_executable = self.resolve_protoc_executable()?;
+        let output = run_protoc(
+            Command::new(&protoc_executable).arg("--version"),
+            &protoc_executable,
+        )?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout.parse().map_err(|_| {
+            Error::new(
+                ErrorKind::Other,
+                format!("unexpected protoc --version output: {}", stdout.trim()),
+            )
+        })
+    }
+
+    fn resolve_protoc_executable(&self) -> Result<PathBuf> {
+        match &self.protoc_executable {
+            Some(protoc_executable) => Ok(protoc_executable.clone()),
+            None => self.read_env("PROTOC", protoc_from_env).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Other,
+                    "protoc_executable is required in hermetic mode",
+                )
+            }),
+        }
+    }
+
     /// Compile `.proto` files into Rust files during a Cargo build with additional code generator
     /// configuration options.
     ///
@@ -1017,30 +2894,65 @@ impl Config {
     ///   Ok(())
     /// }
     /// ```
-    pub fn compile_protos(
+    pub fn compile_protos(
+        &mut self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<()> {
+        // TODO: This should probably emit 'rerun-if-changed=PATH' directives for cargo, however
+        // according to [1] if any are output then those paths replace the default crate root,
+        // which is undesirable. Figure out how to do it in an additive way; perhaps gcc-rs has
+        // this figured out.
+        // [1]: http://doc.crates.io/build-script.html#outputs-of-the-build-script
+
+        self.compile_protos_with_modules(protos, includes)
+            .map(|_| ())
+    }
+
+    /// Compile `.proto` files into Rust files like [`Config::compile_protos`], returning the path
+    /// of the file written for each generated module.
+    ///
+    /// See [`Config::compile_fds_with_modules`] for more information.
+    ///
+    /// # Example `build.rs`
+    ///
+    /// ```rust,no_run
+    /// # use std::io::Result;
+    /// fn main() -> Result<()> {
+    ///   let modules = prost_build::Config::new()
+    ///       .compile_protos_with_modules(&["src/frontend.proto", "src/backend.proto"], &["src"])?;
+    ///   for path in modules.values() {
+    ///       println!("cargo:warning=generated {}", path.display());
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    pub fn compile_protos_with_modules(
         &mut self,
         protos: &[impl AsRef<Path>],
         includes: &[impl AsRef<Path>],
-    ) -> Result<()> {
-        // TODO: This should probably emit 'rerun-if-changed=PATH' directives for cargo, however
-        // according to [1] if any are output then those paths replace the default crate root,
-        // which is undesirable. Figure out how to do it in an additive way; perhaps gcc-rs has
-        // this figured out.
-        // [1]: http://doc.crates.io/build-script.html#outputs-of-the-build-script
-
+    ) -> Result<BTreeMap<Module, PathBuf>> {
         let file_descriptor_set = self.load_fds(protos, includes)?;
 
-        self.compile_fds(file_descriptor_set)
+        self.compile_fds_with_modules(file_descriptor_set)
     }
 
     pub(crate) fn write_includes(
         &self,
-        mut modules: Vec<&Module>,
+        modules: Vec<&Module>,
         outfile: &mut impl Write,
         basepath: Option<&PathBuf>,
         file_names: &HashMap<Module, String>,
+        package_docs: &HashMap<Module, Vec<Comments>>,
+        reexports: &BTreeMap<Module, Vec<String>>,
     ) -> Result<()> {
-        modules.sort();
+        let included = modules.iter().copied().collect::<HashSet<_>>();
+        let modules = modules
+            .into_iter()
+            .chain(reexports.keys())
+            .sorted()
+            .dedup()
+            .collect::<Vec<_>>();
 
         let mut stack = Vec::new();
 
@@ -1050,12 +2962,32 @@ impl Config {
                 self.write_line(outfile, stack.len(), "}")?;
             }
             while stack.len() < module.len() {
+                if let Some(attributes) = &self.include_module_attributes {
+                    let opened = Module::from_parts(module.parts().take(stack.len() + 1));
+                    for line in attributes(&opened) {
+                        self.write_line(outfile, stack.len(), &line)?;
+                    }
+                }
                 self.write_line(
                     outfile,
                     stack.len(),
                     &format!("pub mod {} {{", module.part(stack.len())),
                 )?;
                 stack.push(module.part(stack.len()).to_owned());
+                if stack.len() == module.len() {
+                    if let Some(docs) = package_docs.get(module) {
+                        let mut buf = String::new();
+                        append_package_docs(docs, stack.len() as u8, &mut buf);
+                        outfile.write_all(buf.as_bytes())?;
+                    }
+                }
+            }
+
+            for line in reexports.get(module).into_iter().flatten() {
+                self.write_line(outfile, stack.len(), line)?;
+            }
+            if !included.contains(module) {
+                continue;
             }
 
             let file_name = file_names
@@ -1084,6 +3016,42 @@ impl Config {
         Ok(())
     }
 
+    /// Writes a `pub use` item for each top-level type of the generated modules, and a comment
+    /// for each name defined in several modules.
+    fn write_prelude(
+        &self,
+        types: &BTreeMap<String, Vec<Module>>,
+        modules: &HashMap<Module, String>,
+        outfile: &mut impl Write,
+    ) -> Result<()> {
+        for (name, type_modules) in types {
+            let paths = type_modules
+                .iter()
+                .filter(|module| modules.contains_key(*module))
+                .map(|module| {
+                    iter::once("super")
+                        .chain(module.parts())
+                        .chain(iter::once(name.as_str()))
+                        .join("::")
+                })
+                .collect::<Vec<_>>();
+            match paths.as_slice() {
+                [] => {}
+                [path] => self.write_line(outfile, 0, &format!("pub use {};", path))?,
+                paths => self.write_line(
+                    outfile,
+                    0,
+                    &format!(
+                        "// `{}` is not re-exported, as it is defined in several packages: {}",
+                        name,
+                        paths.join(", ")
+                    ),
+                )?,
+            }
+        }
+        Ok(())
+    }
+
     fn write_line(&self, outfile: &mut impl Write, depth: usize, line: &str) -> Result<()> {
         outfile.write_all(format!("{}{}\n", ("    ").to_owned().repeat(depth), line).as_bytes())
     }
@@ -1100,10 +3068,68 @@ impl Config {
     ) -> Result<HashMap<Module, String>> {
         let mut modules = HashMap::new();
         let mut packages = HashMap::new();
+        let mut newtypes: HashMap<Module, BTreeMap<String, String>> = HashMap::new();
+
+        let requests = if self.merged_packages.is_empty() {
+            requests
+        } else {
+            requests
+                .into_iter()
+                .map(|(module, file)| {
+                    if self.merged_packages.target(file.package()) == file.package() {
+                        (module, file)
+                    } else {
+                        (
+                            self.merged_packages
+                                .module(file.package(), self.ident_policy.as_ref()),
+                            file,
+                        )
+                    }
+                })
+                .collect()
+        };
+
+        if self.no_std {
+            if let Some((matcher, _)) = self
+                .map_type
+                .matchers
+                .iter()
+                .find(|(_, map_type)| *map_type == MapType::IndexMap)
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "IndexMap fields require the std feature of prost, and can not be generated for {} with no_std",
+                        matcher
+                    ),
+                ));
+            }
+        }
 
-        let message_graph = MessageGraph::new(requests.iter().map(|x| &x.1), self.boxed.clone());
-        let extern_paths = ExternPaths::new(&self.extern_paths, self.prost_types)
+        self.check_newtypes(&requests)?;
+
+        let message_graph = MessageGraph::new(
+            requests.iter().map(|x| &x.1),
+            self.boxed.clone(),
+            self.json_unknown_fields.clone(),
+        );
+        let extern_paths = self.extern_paths()?;
+        let duplicates = self
+            .merged_packages
+            .duplicates(&requests, |fq_name| {
+                extern_paths.resolve_ident(fq_name).is_some()
+            })
             .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+        let package_docs = if self.inner_package_docs {
+            self.package_docs(&requests)
+        } else {
+            HashMap::new()
+        };
+        let package_constants = if self.package_constants {
+            self.package_files(&requests)
+        } else {
+            HashMap::new()
+        };
 
         for (request_module, request_fd) in requests {
             // Only record packages that have services
@@ -1113,13 +3139,34 @@ impl Config {
             let buf = modules
                 .entry(request_module.clone())
                 .or_insert_with(String::new);
-            CodeGenerator::generate(self, &message_graph, &extern_paths, request_fd, buf);
+            let module_newtypes = newtypes.entry(request_module.clone()).or_default();
+            CodeGenerator::generate(
+                self,
+                &message_graph,
+                &extern_paths,
+                &duplicates,
+                request_fd,
+                buf,
+                module_newtypes,
+            );
             if buf.is_empty() {
                 // Did not generate any code, remove from list to avoid inclusion in include file or output file list
                 modules.remove(&request_module);
             }
         }
 
+        for (module, module_newtypes) in newtypes {
+            if let Some(buf) = modules.get_mut(&module) {
+                append_newtypes(self, &module_newtypes, buf);
+            }
+        }
+
+        for (module, (package, files)) in package_constants {
+            if let Some(buf) = modules.get_mut(&module) {
+                append_package_constants(&package, &files, buf);
+            }
+        }
+
         if let Some(ref mut service_generator) = self.service_generator {
             for (module, package) in packages {
                 let buf = modules.get_mut(&module).unwrap();
@@ -1129,10 +3176,22 @@ impl Config {
 
         #[cfg(feature = "format")]
         if self.fmt {
-            for buf in modules.values_mut() {
-                let file = syn::parse_file(buf).unwrap();
-                let formatted = prettyplease::unparse(&file);
-                *buf = formatted;
+            for (module, buf) in modules.iter_mut() {
+                let file = syn::parse_file(buf).map_err(|error| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("generated code for {} is not valid Rust: {}", module, error),
+                    )
+                })?;
+                *buf = prettyplease::unparse(&file);
+            }
+        }
+
+        for (module, docs) in package_docs {
+            if let Some(buf) = modules.get_mut(&module) {
+                let mut header = String::new();
+                append_package_docs(&docs, 0, &mut header);
+                buf.insert_str(0, &header);
             }
         }
 
@@ -1141,6 +3200,276 @@ impl Config {
         Ok(modules)
     }
 
+    /// Compiles a [`FileDescriptorSet`] into a [`TokenStream`] for each module, instead of
+    /// writing files.
+    ///
+    /// This allows procedural macros and other build tools to post-process or embed the
+    /// generated code directly. Options which only affect the written files, such as
+    /// [`Config::include_file`], are ignored.
+    ///
+    /// This method is only available with the `tokens` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use prost_types::FileDescriptorSet;
+    /// # fn fds() -> FileDescriptorSet { todo!() }
+    /// fn main() -> std::io::Result<()> {
+    ///   let modules = prost_build::Config::new().compile_fds_to_tokens(fds())?;
+    ///   for (module, tokens) in modules {
+    ///       println!("{}: {}", module, tokens);
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`TokenStream`]: proc_macro2::TokenStream
+    #[cfg(feature = "tokens")]
+    pub fn compile_fds_to_tokens(
+        &mut self,
+        fds: FileDescriptorSet,
+    ) -> Result<HashMap<Module, proc_macro2::TokenStream>> {
+        let requests = self.requests(fds);
+        self.generate(requests)?
+            .into_iter()
+            .map(|(module, content)| {
+                let tokens = content.parse().map_err(|error| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("generated code for {} is not valid Rust: {}", module, error),
+                    )
+                })?;
+                Ok((module, tokens))
+            })
+            .collect()
+    }
+
+    /// Applies the descriptor transforms to `fds`, and pairs each file with its module.
+    fn requests(&mut self, mut fds: FileDescriptorSet) -> Vec<(Module, FileDescriptorProto)> {
+        for transform in &mut self.fds_transforms {
+            transform(&mut fds);
+        }
+
+        fds.file
+            .into_iter()
+            .map(|descriptor| {
+                (
+                    self.merged_packages
+                        .module(descriptor.package(), self.ident_policy.as_ref()),
+                    descriptor,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the name of the variant generated for a value of an enum, given the
+    /// fully-qualified name of the enum and the name of the value.
+    pub(crate) fn enum_variant_name(&self, fq_enum_name: &str, value_name: &str) -> String {
+        let policy = self.ident_policy.as_ref();
+        if let Some(ref renamer) = self.enum_variant_renamer {
+            return policy.escape(&renamer(fq_enum_name, value_name));
+        }
+        match self.enum_variant_naming {
+            EnumVariantNaming::StripEnumPrefix => {
+                let enum_name = fq_enum_name.rsplit('.').next().unwrap_or(fq_enum_name);
+                strip_enum_prefix(
+                    &to_upper_camel_with(policy, enum_name),
+                    &to_upper_camel_with(policy, value_name),
+                )
+            }
+            EnumVariantNaming::UpperCamel => to_upper_camel_with(policy, value_name),
+            EnumVariantNaming::Original => policy.escape(value_name),
+        }
+    }
+
+    /// Returns the documentation of the package of each module, from all files of the package.
+    fn package_docs(
+        &self,
+        requests: &[(Module, FileDescriptorProto)],
+    ) -> HashMap<Module, Vec<Comments>> {
+        let mut docs = HashMap::<Module, Vec<Comments>>::new();
+        for (module, file) in requests {
+            if let Some(comments) = package_comments(self, file) {
+                docs.entry(module.clone()).or_default().push(comments);
+            }
+        }
+        docs
+    }
+
+    /// Returns the extern paths, including the packages of the crate remaps.
+    fn extern_paths(&self) -> Result<ExternPaths> {
+        let mut paths = self.extern_paths.clone();
+        for (package, remap) in &self.crate_remaps {
+            paths.extend(
+                remap
+                    .extern_paths(package)
+                    .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?,
+            );
+        }
+        ExternPaths::new(&paths, self.prost_types, self.unwrap_well_known_wrappers)
+            .map_err(|error| Error::new(ErrorKind::InvalidInput, error))
+    }
+
+    /// Returns the re-exports of the crate modules of the remapped packages of the requests, by
+    /// module.
+    fn crate_remap_reexports(
+        &self,
+        requests: &[(Module, FileDescriptorProto)],
+    ) -> BTreeMap<Module, Vec<String>> {
+        let mut reexports = BTreeMap::new();
+        for (module, file) in requests {
+            if file.package().is_empty() || reexports.contains_key(module) {
+                continue;
+            }
+            let package = format!(".{}", file.package());
+            let remap = self
+                .crate_remaps
+                .iter()
+                .filter_map(|(remapped, remap)| {
+                    remap
+                        .package_path(remapped, &package)
+                        .map(|path| (remapped.len(), remap, path))
+                })
+                .max_by_key(|(len, _, _)| *len);
+            if let Some((_, remap, path)) = remap {
+                let mut lines = Vec::new();
+                if let Some(feature) = &remap.feature {
+                    lines.push(format!("#[cfg(feature = \"{}\")]", feature));
+                }
+                lines.push(format!("pub use {}::*;", path));
+                reexports.insert(module.clone(), lines);
+            }
+        }
+        reexports
+    }
+
+    /// Returns the Rust name of each top-level message and enum generated by the requests, with
+    /// the modules defining it, in order.
+    fn prelude_types(
+        &self,
+        requests: &[(Module, FileDescriptorProto)],
+    ) -> Result<BTreeMap<String, Vec<Module>>> {
+        let extern_paths = self.extern_paths()?;
+
+        let mut types = BTreeMap::<String, Vec<Module>>::new();
+        for (module, file) in requests {
+            let names = file
+                .message_type
+                .iter()
+                .map(|message| message.name())
+                .chain(file.enum_type.iter().map(|desc| desc.name()));
+            for name in names {
+                let fq_name = match file.package() {
+                    "" => format!(".{}", name),
+                    package => format!(".{}.{}", package, name),
+                };
+                if extern_paths.resolve_ident(&fq_name).is_some() {
+                    continue;
+                }
+                let modules = types
+                    .entry(to_upper_camel_with(self.ident_policy.as_ref(), name))
+                    .or_default();
+                if !modules.contains(module) {
+                    modules.push(module.clone());
+                }
+            }
+        }
+        for modules in types.values_mut() {
+            modules.sort();
+        }
+        Ok(types)
+    }
+
+    /// Returns the package of each module, and the name and syntax of its files.
+    fn package_files(
+        &self,
+        requests: &[(Module, FileDescriptorProto)],
+    ) -> HashMap<Module, (String, Vec<(String, String)>)> {
+        let mut packages = HashMap::<Module, (String, Vec<(String, String)>)>::new();
+        for (module, file) in requests {
+            let (_, files) = packages.entry(module.clone()).or_insert_with(|| {
+                let package = self.merged_packages.target(file.package());
+                (package.to_string(), Vec::new())
+            });
+            let syntax = match file.syntax() {
+                "" => "proto2",
+                syntax => syntax,
+            };
+            files.push((file.name().to_string(), syntax.to_string()));
+        }
+        packages
+    }
+
+    /// Checks that the fields wrapped in newtypes are singular string and integer fields, and that
+    /// the fields sharing a newtype in a module have the same Rust type.
+    fn check_newtypes(&self, requests: &[(Module, FileDescriptorProto)]) -> Result<()> {
+        let mut types = HashMap::<(&Module, &String), &str>::new();
+        for (module, file) in requests {
+            let package = match file.package() {
+                "" => String::new(),
+                package => format!(".{}", package),
+            };
+            let mut messages: Vec<(String, &DescriptorProto)> = file
+                .message_type
+                .iter()
+                .map(|message| (format!("{}.{}", package, message.name()), message))
+                .collect();
+            while let Some((fq_message_name, message)) = messages.pop() {
+                messages.extend(
+                    message
+                        .nested_type
+                        .iter()
+                        .filter(|nested| !nested.options.as_ref().is_some_and(|o| o.map_entry()))
+                        .map(|nested| (format!("{}.{}", fq_message_name, nested.name()), nested)),
+                );
+                for field in &message.field {
+                    // Fields of oneofs are not wrapped.
+                    if field.oneof_index.is_some() && !field.proto3_optional() {
+                        continue;
+                    }
+                    let Some(name) = self
+                        .newtypes
+                        .get_first_field(&fq_message_name, field.name())
+                    else {
+                        continue;
+                    };
+                    let ty = match field.r#type() {
+                        Type::String => "String",
+                        Type::Int32 | Type::Sint32 | Type::Sfixed32 => "i32",
+                        Type::Int64 | Type::Sint64 | Type::Sfixed64 => "i64",
+                        Type::Uint32 | Type::Fixed32 => "u32",
+                        Type::Uint64 | Type::Fixed64 => "u64",
+                        _ => "",
+                    };
+                    if ty.is_empty() || field.label() == Label::Repeated {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!(
+                                "newtype {} can not wrap field {}.{}: only singular string and integer fields are supported",
+                                name,
+                                fq_message_name,
+                                field.name()
+                            ),
+                        ));
+                    }
+                    match types.insert((module, name), ty) {
+                        Some(existing) if existing != ty => {
+                            return Err(Error::new(
+                                ErrorKind::InvalidInput,
+                                format!(
+                                    "newtype {} is used for fields of different types: {} and {}",
+                                    name, existing, ty
+                                ),
+                            ));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn add_generated_modules(&mut self, modules: &mut HashMap<Module, String>) {
         for buf in modules.values_mut() {
             let with_generated = "// This file is @generated by prost-build.\n".to_string() + buf;
@@ -1169,32 +3498,90 @@ fn write_file_if_changed(path: &Path, content: &[u8]) -> std::io::Result<()> {
     }
 }
 
+/// Appends the documentation of a package, made of the comments of each of its files.
+fn append_package_docs(docs: &[Comments], indent_level: u8, buf: &mut String) {
+    for (i, comments) in docs.iter().enumerate() {
+        if i > 0 {
+            buf.push_str(&"    ".repeat(indent_level.into()));
+            buf.push_str("//!\n");
+        }
+        comments.append_inner_with_indent(indent_level, buf);
+    }
+}
+
 impl default::Default for Config {
     fn default() -> Config {
         Config {
             file_descriptor_set_path: None,
             service_generator: None,
+            fds_transforms: Vec::new(),
             map_type: PathMap::default(),
+            encode_default_map_values: false,
+            convenience_conversions: false,
             bytes_type: PathMap::default(),
+            interned_strings: PathMap::default(),
+            wkt_as_std: false,
+            no_std: false,
+            time_range_policy: TimeRangePolicy::Reject,
             type_attributes: PathMap::default(),
             message_attributes: PathMap::default(),
             enum_attributes: PathMap::default(),
+            oneof_attributes: PathMap::default(),
+            method_attributes: PathMap::default(),
             field_attributes: PathMap::default(),
+            field_cfgs: PathMap::default(),
+            type_cfgs: PathMap::default(),
+            type_visibilities: PathMap::default(),
+            field_visibilities: PathMap::default(),
             boxed: PathMap::default(),
+            newtypes: PathMap::default(),
+            unknown_enum_values: PathMap::default(),
             prost_types: true,
-            strip_enum_prefix: true,
+            unwrap_well_known_wrappers: false,
+            custom_types: HashMap::new(),
+            enum_variant_naming: EnumVariantNaming::StripEnumPrefix,
+            enum_variant_renamer: None,
+            ident_policy: Box::new(DefaultIdentPolicy),
             out_dir: None,
             extern_paths: Vec::new(),
+            crate_remaps: Vec::new(),
+            merged_packages: MergedPackages::default(),
+            archive_derives: PathMap::default(),
+            archive_derive_feature: None,
             default_package_filename: "_".to_string(),
             enable_type_names: false,
             type_name_domains: PathMap::default(),
             protoc_args: Vec::new(),
-            protoc_executable: protoc_from_env(),
+            experimental_editions: false,
+            fatal_warnings: false,
+            protoc_executable: None,
             disable_comments: PathMap::default(),
             skip_debug: PathMap::default(),
+            serde_json_names: PathMap::default(),
+            json_use_proto_names: PathMap::default(),
+            oneof_from_impls: PathMap::default(),
+            serde_json_value_conversions: PathMap::default(),
+            json_unknown_fields: PathMap::default(),
+            field_values: PathMap::default(),
+            send_sync_assertions: PathMap::default(),
+            presence_bitsets: PathMap::default(),
+            display_fields: PathMap::default(),
+            enforce_reserved: false,
+            inner_package_docs: false,
+            package_constants: false,
+            service_descriptors: false,
+            reserved_field_callback: None,
             skip_protoc_run: false,
+            hermetic: false,
+            allowed_env_vars: Vec::new(),
             skip_source_info: false,
+            descriptor_set_from_stdout: false,
+            raw_method_options: HashMap::new(),
             include_file: None,
+            include_module_attributes: None,
+            generate_prelude: false,
+            schema_warnings: false,
+            schema_report: None,
             prost_path: None,
             #[cfg(feature = "format")]
             fmt: true,
@@ -1207,25 +3594,79 @@ impl fmt::Debug for Config {
         fmt.debug_struct("Config")
             .field("file_descriptor_set_path", &self.file_descriptor_set_path)
             .field("service_generator", &self.service_generator.is_some())
+            .field("fds_transforms", &self.fds_transforms.len())
             .field("map_type", &self.map_type)
+            .field("encode_default_map_values", &self.encode_default_map_values)
+            .field("convenience_conversions", &self.convenience_conversions)
             .field("bytes_type", &self.bytes_type)
+            .field("interned_strings", &self.interned_strings)
+            .field("wkt_as_std", &self.wkt_as_std)
+            .field("no_std", &self.no_std)
+            .field("time_range_policy", &self.time_range_policy)
             .field("type_attributes", &self.type_attributes)
             .field("field_attributes", &self.field_attributes)
+            .field("field_cfgs", &self.field_cfgs)
+            .field("type_cfgs", &self.type_cfgs)
+            .field("type_visibilities", &self.type_visibilities)
+            .field("field_visibilities", &self.field_visibilities)
+            .field("newtypes", &self.newtypes)
+            .field("unknown_enum_values", &self.unknown_enum_values)
             .field("prost_types", &self.prost_types)
-            .field("strip_enum_prefix", &self.strip_enum_prefix)
+            .field(
+                "unwrap_well_known_wrappers",
+                &self.unwrap_well_known_wrappers,
+            )
+            .field("custom_types", &self.custom_types)
+            .field("enum_variant_naming", &self.enum_variant_naming)
+            .field("enum_variant_renamer", &self.enum_variant_renamer.is_some())
+            .field(
+                "include_module_attributes",
+                &self.include_module_attributes.is_some(),
+            )
             .field("out_dir", &self.out_dir)
             .field("extern_paths", &self.extern_paths)
+            .field("crate_remaps", &self.crate_remaps)
+            .field("merged_packages", &self.merged_packages)
+            .field("archive_derives", &self.archive_derives)
+            .field("archive_derive_feature", &self.archive_derive_feature)
             .field("default_package_filename", &self.default_package_filename)
             .field("enable_type_names", &self.enable_type_names)
             .field("type_name_domains", &self.type_name_domains)
             .field("protoc_args", &self.protoc_args)
+            .field("experimental_editions", &self.experimental_editions)
+            .field("fatal_warnings", &self.fatal_warnings)
+            .field("protoc_executable", &self.protoc_executable)
             .field("disable_comments", &self.disable_comments)
             .field("skip_debug", &self.skip_debug)
+            .field("serde_json_names", &self.serde_json_names)
+            .field("json_use_proto_names", &self.json_use_proto_names)
+            .field("oneof_from_impls", &self.oneof_from_impls)
+            .field(
+                "serde_json_value_conversions",
+                &self.serde_json_value_conversions,
+            )
+            .field("json_unknown_fields", &self.json_unknown_fields)
+            .field("field_values", &self.field_values)
+            .field("send_sync_assertions", &self.send_sync_assertions)
+            .field("presence_bitsets", &self.presence_bitsets)
+            .field("display_fields", &self.display_fields)
+            .field("enforce_reserved", &self.enforce_reserved)
+            .field("inner_package_docs", &self.inner_package_docs)
+            .field("package_constants", &self.package_constants)
+            .field("service_descriptors", &self.service_descriptors)
+            .field("reserved_field_callback", &self.reserved_field_callback)
+            .field("hermetic", &self.hermetic)
+            .field("allowed_env_vars", &self.allowed_env_vars)
+            .field("generate_prelude", &self.generate_prelude)
+            .field("schema_warnings", &self.schema_warnings)
+            .field("schema_report", &self.schema_report)
             .field("prost_path", &self.prost_path)
             .finish()
     }
 }
 
+/// Returns the message of the error returned when the `protoc` executable is not found, including
+/// the [`diagnose_protoc()`](crate::diagnose_protoc) report of the current environment.
 pub fn error_message_protoc_not_found() -> String {
     let error_msg = "Could not find `protoc`. If `protoc` is installed, try setting the `PROTOC` environment variable to the path of the `protoc` binary.";
 
@@ -1240,8 +3681,11 @@ pub fn error_message_protoc_not_found() -> String {
         "It is also available at https://github.com/protocolbuffers/protobuf/releases";
 
     format!(
-        "{} {} {}  For more information: https://docs.rs/prost-build/#sourcing-protoc",
-        error_msg, os_specific_hint, download_msg
+        "{} {} {}  For more information: https://docs.rs/prost-build/#sourcing-protoc\n\n{}",
+        error_msg,
+        os_specific_hint,
+        download_msg,
+        crate::diagnose_protoc()
     )
 }
 
@@ -1272,6 +3716,153 @@ pub fn protoc_include_from_env() -> Option<PathBuf> {
     Some(protoc_include)
 }
 
+/// Flags which make `protoc` write something other than the file descriptor set, or write it
+/// somewhere else.
+const UNSUPPORTED_PROTOC_FLAGS: &[&str] = &[
+    "--descriptor_set_out",
+    "--encode",
+    "--decode",
+    "--decode_raw",
+    "--print_free_field_numbers",
+    "--version",
+    "--help",
+    "-h",
+];
+
+/// Checks that an extra `protoc` argument is a flag which doesn't conflict with the arguments
+/// passed by `prost-build`.
+fn check_protoc_arg(arg: &OsStr) -> Result<()> {
+    let arg_str = arg.to_string_lossy();
+    let message = if !arg_str.starts_with('-') {
+        "is not a flag; .proto files are passed to compile_protos"
+    } else if arg_str.starts_with("-o") {
+        "conflicts with file_descriptor_set_path"
+    } else if UNSUPPORTED_PROTOC_FLAGS.iter().any(|flag| {
+        arg_str
+            .strip_prefix(flag)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+    }) {
+        "stops protoc from writing the file descriptor set"
+    } else {
+        return Ok(());
+    };
+    Err(Error::new(
+        ErrorKind::InvalidInput,
+        format!("protoc_arg {} {}", arg_str, message),
+    ))
+}
+
+/// Runs `protoc`, returning its output if it succeeded.
+fn run_protoc(cmd: &mut Command, protoc_executable: &Path) -> Result<Output> {
+    debug!("Running: {:?}", cmd);
+
+    let output = match cmd.output() {
+        Err(err) if ErrorKind::NotFound == err.kind() => {
+            return Err(Error::new(err.kind(), error_message_protoc_not_found()))
+        }
+        Err(err) => return Err(Error::new(
+            err.kind(),
+            format!("failed to invoke protoc (hint: https://docs.rs/prost-build/#sourcing-protoc): (path: {}): {}", protoc_executable.display(), err),
+        )),
+        Ok(output) => output,
+    };
+
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("protoc failed: {}", String::from_utf8_lossy(&output.stderr)),
+        ));
+    }
+    Ok(output)
+}
+
+/// Returns the path through which `protoc` can write the file descriptor set to its standard
+/// output, if the platform has one.
+fn stdout_path() -> Option<&'static Path> {
+    let path = Path::new("/dev/stdout");
+    if cfg!(unix) && path.exists() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Returns the encoded options of the methods in an encoded file descriptor set, keyed by their
+/// fully qualified name.
+///
+/// Decoding the file descriptor set drops the custom options, which are extensions of
+/// `MethodOptions`, so they are taken from the encoding instead.
+fn raw_method_options(buf: &[u8]) -> HashMap<String, Vec<u8>> {
+    let mut options = HashMap::new();
+    for file in FieldIter::new(buf).flatten().filter(|field| field.tag == 1) {
+        let mut package = "";
+        let mut services = Vec::new();
+        for field in file.nested().flatten() {
+            match field.tag {
+                2 => package = std::str::from_utf8(field.value).unwrap_or_default(),
+                6 => services.push(field),
+                _ => (),
+            }
+        }
+        for service in services {
+            let mut name = "";
+            let mut methods = Vec::new();
+            for field in service.nested().flatten() {
+                match field.tag {
+                    1 => name = std::str::from_utf8(field.value).unwrap_or_default(),
+                    2 => methods.push(field),
+                    _ => (),
+                }
+            }
+            let service_name = match package {
+                "" => format!(".{}", name),
+                package => format!(".{}.{}", package, name),
+            };
+            for method in methods {
+                let mut name = "";
+                let mut method_options = Vec::new();
+                for field in method.nested().flatten() {
+                    match field.tag {
+                        1 => name = std::str::from_utf8(field.value).unwrap_or_default(),
+                        // Options fields are merged if repeated.
+                        4 => method_options.extend_from_slice(field.value),
+                        _ => (),
+                    }
+                }
+                options.insert(format!("{}.{}", service_name, name), method_options);
+            }
+        }
+    }
+    options
+}
+
+/// Converts a path to the form passed on the `protoc` command line.
+///
+/// `protoc` matches proto files against include paths textually, and its path canonicalization
+/// mangles the Windows verbatim prefix (`\\?\`) returned by `fs::canonicalize`, so the prefix is
+/// removed. `protoc` adds it back itself when opening paths longer than `MAX_PATH`.
+fn protoc_path(path: &Path) -> Cow<'_, OsStr> {
+    if cfg!(windows) {
+        if let Some(stripped) = path.to_str().and_then(strip_verbatim_prefix) {
+            return Cow::Owned(stripped.into());
+        }
+    }
+    Cow::Borrowed(path.as_os_str())
+}
+
+/// Strips a verbatim prefix from a Windows disk (`\\?\C:\`) or UNC (`\\?\UNC\server\share`)
+/// path.
+fn strip_verbatim_prefix(path: &str) -> Option<String> {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        return Some(format!(r"\\{}", unc));
+    }
+    let disk = path.strip_prefix(r"\\?\")?;
+    match disk.as_bytes() {
+        [drive, b':', b'\\', ..] if drive.is_ascii_alphabetic() => Some(disk.to_owned()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1358,6 +3949,214 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_error_invalid_protoc_args() {
+        for (arg, message) in [
+            (
+                "foo.proto",
+                "protoc_arg foo.proto is not a flag; .proto files are passed to compile_protos",
+            ),
+            (
+                "-ofoo.bin",
+                "protoc_arg -ofoo.bin conflicts with file_descriptor_set_path",
+            ),
+            (
+                "--descriptor_set_out=foo.bin",
+                "protoc_arg --descriptor_set_out=foo.bin stops protoc from writing the file descriptor set",
+            ),
+            (
+                "--decode=foo.Msg",
+                "protoc_arg --decode=foo.Msg stops protoc from writing the file descriptor set",
+            ),
+            (
+                "--version",
+                "protoc_arg --version stops protoc from writing the file descriptor set",
+            ),
+        ] {
+            let mut config = Config::new();
+            config
+                .protoc_executable("path-does-not-exist")
+                .protoc_args(["--experimental_allow_proto3_optional", arg]);
+
+            let err = config.load_fds(&[""], &[""]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+            assert_eq!(err.to_string(), message);
+        }
+
+        // Flags which only share a prefix with an unsupported flag are passed on.
+        let mut config = Config::new();
+        config
+            .protoc_executable("path-does-not-exist")
+            .protoc_args(["--decode_rawish", "-Ifoo", "--fatal_warnings"])
+            .experimental_editions();
+        let err = config.load_fds(&[""], &[""]).unwrap_err();
+        assert_eq!(err.to_string(), error_message_protoc_not_found());
+    }
+
+    #[test]
+    fn test_error_no_std_index_map() {
+        let mut config = Config::new();
+        config.no_std(true).index_map([".settings"]);
+
+        let err = config.generate(Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "IndexMap fields require the std feature of prost, and can not be generated for .settings with no_std"
+        );
+
+        config.no_std(false);
+        assert!(config.generate(Vec::new()).is_ok());
+    }
+
+    #[test]
+    fn test_error_newtype_unsupported_fields() {
+        use prost_types::{DescriptorProto, FieldDescriptorProto};
+
+        let field = |name: &str, number: i32, ty: Type, label: Label| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: Some(ty.into()),
+            label: Some(label.into()),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("ids.proto".to_string()),
+            package: Some("ids".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("User".to_string()),
+                field: vec![
+                    field("id", 1, Type::String, Label::Optional),
+                    field("number", 2, Type::Int64, Label::Optional),
+                    field("aliases", 3, Type::String, Label::Repeated),
+                    field("score", 4, Type::Double, Label::Optional),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let requests = || vec![(Module::from_protobuf_package_name("ids"), file.clone())];
+
+        let mut config = Config::new();
+        config.newtype(".ids.User.id", "UserId");
+        assert!(config.generate(requests()).is_ok());
+
+        for (path, error) in [
+            (
+                ".ids.User.aliases",
+                "newtype UserId can not wrap field .ids.User.aliases: only singular string and \
+                 integer fields are supported",
+            ),
+            (
+                ".ids.User.score",
+                "newtype UserId can not wrap field .ids.User.score: only singular string and \
+                 integer fields are supported",
+            ),
+            (
+                ".ids.User.number",
+                "newtype UserId is used for fields of different types: String and i64",
+            ),
+        ] {
+            let mut config = Config::new();
+            config
+                .newtype(".ids.User.id", "UserId")
+                .newtype(path, "UserId");
+            let err = config.generate(requests()).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+            assert_eq!(err.to_string(), error);
+        }
+    }
+
+    #[test]
+    fn test_strip_verbatim_prefix() {
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\C:\my protos\foo.proto").as_deref(),
+            Some(r"C:\my protos\foo.proto")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\server\share\foo.proto").as_deref(),
+            Some(r"\\server\share\foo.proto")
+        );
+        assert_eq!(strip_verbatim_prefix(r"C:\protos\foo.proto"), None);
+        assert_eq!(strip_verbatim_prefix(r"\\server\share\foo.proto"), None);
+        assert_eq!(strip_verbatim_prefix(r"\\?\Volume{1234}\foo.proto"), None);
+        assert_eq!(strip_verbatim_prefix("/usr/include/foo.proto"), None);
+    }
+
+    #[test]
+    fn test_long_paths_with_spaces() {
+        let tempdir = tempfile::Builder::new()
+            .prefix("prost build")
+            .tempdir()
+            .unwrap();
+        // Canonical paths are verbatim paths on Windows.
+        let mut verbatim = fs::canonicalize(tempdir.path()).unwrap();
+        for i in 0..4 {
+            verbatim.push(format!(
+                "a directory with a rather long name {} {}",
+                i,
+                "x".repeat(40)
+            ));
+        }
+        fs::create_dir_all(&verbatim).unwrap();
+        assert!(verbatim.as_os_str().len() > 260);
+        let plain = PathBuf::from(protoc_path(&verbatim).into_owned());
+
+        fs::copy(
+            "src/fixtures/smoke_test/smoke_test.proto",
+            plain.join("smoke test.proto"),
+        )
+        .unwrap();
+
+        for (include, dir) in [(&verbatim, &plain), (&plain, &verbatim)] {
+            let fds = Config::new()
+                .file_descriptor_set_path(dir.join("descriptor set.bin"))
+                .load_fds(&[dir.join("smoke test.proto")], &[include])
+                .unwrap();
+            assert_eq!(fds.file.len(), 1);
+            assert_eq!(fds.file[0].name(), "smoke test.proto");
+        }
+    }
+
+    #[test]
+    fn test_descriptor_set_from_stdout() {
+        let fds = Config::new()
+            .descriptor_set_from_stdout()
+            .load_fds(&["src/fixtures/smoke_test/smoke_test.proto"], &["src"])
+            .unwrap();
+        assert_eq!(fds.file.len(), 1);
+        assert_eq!(fds.file[0].name(), "fixtures/smoke_test/smoke_test.proto");
+    }
+
+    #[test]
+    fn test_protoc_version() {
+        let version = Config::new().protoc_version().unwrap();
+        assert!(version.major >= 3);
+    }
+
+    #[test]
+    fn test_error_hermetic_protoc_executable() {
+        let mut config = Config::new();
+        config.hermetic();
+
+        let err = config.load_fds(&[""], &[""]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "protoc_executable is required in hermetic mode"
+        )
+    }
+
+    #[test]
+    fn test_error_hermetic_out_dir() {
+        let mut config = Config::new();
+        config.hermetic().allow_env_var("PROTOC");
+
+        let err = config
+            .compile_fds(FileDescriptorSet::default())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "out_dir is required in hermetic mode")
+    }
+
     #[test]
     fn test_error_unset_out_dir() {
         let mut config = Config::new();
@@ -1367,4 +4166,51 @@ mod tests {
             .unwrap_err();
         assert_eq!(err.to_string(), "OUT_DIR environment variable is not set")
     }
+
+    #[test]
+    fn test_error_merged_packages_collision() {
+        use prost_types::{DescriptorProto, FieldDescriptorProto};
+
+        let file = |package: &str, field: &str| {
+            let descriptor = FileDescriptorProto {
+                name: Some(format!("{}.proto", package)),
+                package: Some(package.to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Msg".to_string()),
+                    field: vec![FieldDescriptorProto {
+                        name: Some(field.to_string()),
+                        number: Some(1),
+                        r#type: Some(prost_types::field_descriptor_proto::Type::String.into()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            (Module::from_protobuf_package_name(package), descriptor)
+        };
+
+        let mut config = Config::new();
+        config.merge_packages(["foo.v1", "foo.v1beta"], "foo");
+
+        let modules = config
+            .generate(vec![file("foo.v1", "id"), file("foo.v1beta", "id")])
+            .unwrap();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(
+            modules[&Module::from_protobuf_package_name("foo")]
+                .matches("pub struct Msg")
+                .count(),
+            1
+        );
+
+        let err = config
+            .generate(vec![file("foo.v1", "id"), file("foo.v1beta", "name")])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "packages foo.v1 and foo.v1beta define different types named Msg, and can not be \
+             merged into the same module"
+        );
+    }
 }
@@ -0,0 +1,429 @@
+//! Statistics and warnings about suspicious constructs of the compiled Protobuf schema.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+};
+
+use crate::ident::{to_snake_with, to_upper_camel_with};
+use crate::message_graph::MessageGraph;
+use crate::{Config, Module};
+
+/// Oneofs with more variants are reported as large.
+const LARGE_ONEOF_VARIANTS: usize = 64;
+
+/// A suspicious construct of the compiled Protobuf schema.
+///
+/// Names are fully-qualified, without the leading dot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SchemaWarning {
+    /// A oneof generated as a large enum.
+    LargeOneof { oneof: String, variants: usize },
+    /// A message field boxed automatically because its message is recursive.
+    RecursiveField { field: String },
+    /// A field declared with the deprecated group syntax.
+    Group { field: String },
+    /// A deprecated weak import.
+    WeakImport { file: String, import: String },
+    /// A file without package declaration.
+    MissingPackage { file: String },
+    /// Two names converted to the same Rust identifier in the same scope.
+    IdentCollision {
+        scope: String,
+        first: String,
+        second: String,
+        ident: String,
+    },
+}
+
+impl fmt::Display for SchemaWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaWarning::LargeOneof { oneof, variants } => write!(
+                f,
+                "oneof {} has {} variants, which makes its generated enum large",
+                oneof, variants
+            ),
+            SchemaWarning::RecursiveField { field } => write!(
+                f,
+                "field {} is boxed because its message type is recursive",
+                field
+            ),
+            SchemaWarning::Group { field } => {
+                write!(f, "field {} uses the deprecated group syntax", field)
+            }
+            SchemaWarning::WeakImport { file, import } => {
+                write!(f, "{} has a deprecated weak import of {}", file, import)
+            }
+            SchemaWarning::MissingPackage { file } => {
+                write!(f, "{} has no package declaration", file)
+            }
+            SchemaWarning::IdentCollision {
+                scope,
+                first,
+                second,
+                ident,
+            } => write!(
+                f,
+                "{} and {} in {} are both generated as `{}`",
+                first, second, scope, ident
+            ),
+        }
+    }
+}
+
+/// The statistics and warnings of [`Config::schema_warnings`] and [`Config::schema_report`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct SchemaReport {
+    pub files: usize,
+    pub messages: usize,
+    pub enums: usize,
+    pub fields: usize,
+    pub oneofs: usize,
+    pub services: usize,
+    pub warnings: Vec<SchemaWarning>,
+}
+
+impl SchemaReport {
+    pub fn new(config: &Config, requests: &[(Module, FileDescriptorProto)]) -> SchemaReport {
+        let mut checker = Checker {
+            config,
+            message_graph: MessageGraph::new(
+                requests.iter().map(|(_, file)| file),
+                config.boxed.clone(),
+                config.json_unknown_fields.clone(),
+            ),
+            report: SchemaReport::default(),
+        };
+
+        let mut packages: HashMap<&str, Vec<(String, String)>> = HashMap::new();
+        for (_, file) in requests {
+            checker.check_file(file);
+
+            let package = config.merged_packages.target(file.package());
+            let names = packages.entry(package).or_default();
+            let types = file
+                .message_type
+                .iter()
+                .map(|message| message.name())
+                .chain(file.enum_type.iter().map(|enum_type| enum_type.name()));
+            for name in types {
+                names.push((name.to_string(), checker.to_upper_camel(name)));
+            }
+        }
+
+        let mut packages = packages.into_iter().collect::<Vec<_>>();
+        packages.sort();
+        for (package, names) in packages {
+            let scope = if package.is_empty() {
+                "the default package"
+            } else {
+                package
+            };
+            checker.check_collisions(scope, names);
+        }
+
+        checker.report
+    }
+}
+
+impl fmt::Display for SchemaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# This file is @generated by prost-build.")?;
+        writeln!(f, "files: {}", self.files)?;
+        writeln!(f, "messages: {}", self.messages)?;
+        writeln!(f, "enums: {}", self.enums)?;
+        writeln!(f, "fields: {}", self.fields)?;
+        writeln!(f, "oneofs: {}", self.oneofs)?;
+        writeln!(f, "services: {}", self.services)?;
+        writeln!(f, "warnings: {}", self.warnings.len())?;
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+struct Checker<'a> {
+    config: &'a Config,
+    message_graph: MessageGraph,
+    report: SchemaReport,
+}
+
+impl Checker<'_> {
+    fn check_file(&mut self, file: &FileDescriptorProto) {
+        self.report.files += 1;
+        self.report.services += file.service.len();
+
+        if file.package.is_none() {
+            self.warn(SchemaWarning::MissingPackage {
+                file: file.name().to_string(),
+            });
+        }
+        for &index in &file.weak_dependency {
+            if let Some(import) = file.dependency.get(index as usize) {
+                self.warn(SchemaWarning::WeakImport {
+                    file: file.name().to_string(),
+                    import: import.clone(),
+                });
+            }
+        }
+
+        let package = file.package();
+        for message in &file.message_type {
+            self.check_message(package, message);
+        }
+        for enum_type in &file.enum_type {
+            self.check_enum(package, enum_type);
+        }
+    }
+
+    fn check_message(&mut self, scope: &str, message: &DescriptorProto) {
+        let fq_message_name = qualify(scope, message.name());
+        self.report.messages += 1;
+        self.report.fields += message.field.len();
+
+        // Fields of real oneofs, as opposed to the synthetic oneofs of proto3 optional fields.
+        let mut oneofs = vec![Vec::new(); message.oneof_decl.len()];
+        let mut struct_fields = Vec::new();
+        for field in &message.field {
+            let fq_field_name = qualify(&fq_message_name, field.name());
+            if field.r#type() == Type::Group {
+                self.warn(SchemaWarning::Group {
+                    field: fq_field_name.clone(),
+                });
+            }
+            if self.is_recursive(&fq_message_name, field) {
+                self.warn(SchemaWarning::RecursiveField {
+                    field: fq_field_name,
+                });
+            }
+
+            match field.oneof_index {
+                Some(index) if !field.proto3_optional() => {
+                    if let Some(fields) = oneofs.get_mut(index as usize) {
+                        fields.push(field);
+                    }
+                }
+                _ => struct_fields.push((field.name().to_string(), self.to_snake(field.name()))),
+            }
+        }
+
+        let mut nested_types = Vec::new();
+        for (oneof, fields) in message.oneof_decl.iter().zip(&oneofs) {
+            if fields.is_empty() {
+                continue;
+            }
+            let fq_oneof_name = qualify(&fq_message_name, oneof.name());
+            self.report.oneofs += 1;
+            if fields.len() > LARGE_ONEOF_VARIANTS {
+                self.warn(SchemaWarning::LargeOneof {
+                    oneof: fq_oneof_name.clone(),
+                    variants: fields.len(),
+                });
+            }
+            struct_fields.push((oneof.name().to_string(), self.to_snake(oneof.name())));
+            nested_types.push((oneof.name().to_string(), self.to_upper_camel(oneof.name())));
+            let variants = fields
+                .iter()
+                .map(|field| (field.name().to_string(), self.to_upper_camel(field.name())))
+                .collect();
+            self.check_collisions(&fq_oneof_name, variants);
+        }
+        self.check_collisions(&fq_message_name, struct_fields);
+
+        for nested in &message.nested_type {
+            if nested
+                .options
+                .as_ref()
+                .is_some_and(|options| options.map_entry())
+            {
+                continue;
+            }
+            nested_types.push((
+                nested.name().to_string(),
+                self.to_upper_camel(nested.name()),
+            ));
+            self.check_message(&fq_message_name, nested);
+        }
+        for enum_type in &message.enum_type {
+            nested_types.push((
+                enum_type.name().to_string(),
+                self.to_upper_camel(enum_type.name()),
+            ));
+            self.check_enum(&fq_message_name, enum_type);
+        }
+        let module = format!("{} module", fq_message_name);
+        self.check_collisions(&module, nested_types);
+    }
+
+    fn check_enum(&mut self, scope: &str, enum_type: &EnumDescriptorProto) {
+        let fq_enum_name = qualify(scope, enum_type.name());
+        self.report.enums += 1;
+
+        // Aliases of a value are not generated.
+        let mut numbers = Vec::new();
+        let mut variants = Vec::new();
+        for value in &enum_type.value {
+            if numbers.contains(&value.number()) {
+                continue;
+            }
+            numbers.push(value.number());
+            let variant = self
+                .config
+                .enum_variant_name(&format!(".{}", fq_enum_name), value.name());
+            variants.push((value.name().to_string(), variant));
+        }
+        self.check_collisions(&fq_enum_name, variants);
+    }
+
+    /// Returns `true` if the field is boxed because its message is nested in itself.
+    fn is_recursive(&self, fq_message_name: &str, field: &FieldDescriptorProto) -> bool {
+        field.label() != Label::Repeated
+            && matches!(field.r#type(), Type::Message | Type::Group)
+            && !self.config.custom_types.contains_key(field.type_name())
+            && self
+                .message_graph
+                .is_nested(field.type_name(), &format!(".{}", fq_message_name))
+    }
+
+    /// Warns about the names of a scope which are converted to the same identifier.
+    fn check_collisions(&mut self, scope: &str, names: Vec<(String, String)>) {
+        let mut idents: HashMap<String, String> = HashMap::new();
+        for (name, ident) in names {
+            match idents.get(&ident) {
+                // The same definition in several files of a merged package.
+                Some(first) if *first == name => {}
+                Some(first) => {
+                    let warning = SchemaWarning::IdentCollision {
+                        scope: scope.to_string(),
+                        first: first.clone(),
+                        second: name,
+                        ident,
+                    };
+                    self.warn(warning);
+                }
+                None => {
+                    idents.insert(ident, name);
+                }
+            }
+        }
+    }
+
+    fn warn(&mut self, warning: SchemaWarning) {
+        self.report.warnings.push(warning);
+    }
+
+    fn to_snake(&self, name: &str) -> String {
+        to_snake_with(self.config.ident_policy.as_ref(), name)
+    }
+
+    fn to_upper_camel(&self, name: &str) -> String {
+        to_upper_camel_with(self.config.ident_policy.as_ref(), name)
+    }
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost_types::{EnumValueDescriptorProto, OneofDescriptorProto};
+
+    #[test]
+    fn test_enum_variant_collision() {
+        let value = |name: &str, number| EnumValueDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            options: None,
+        };
+        let file = FileDescriptorProto {
+            name: Some("status.proto".to_string()),
+            package: Some("status".to_string()),
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Status".to_string()),
+                value: vec![
+                    value("STATUS_OK", 0),
+                    value("OK", 1),
+                    // Aliases are not generated, so they can't collide.
+                    value("STATUS_ALIAS", 0),
+                    value("ALIAS", 0),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let report = SchemaReport::new(
+            &Config::new(),
+            &[(Module::from_protobuf_package_name("status"), file.clone())],
+        );
+        assert_eq!(report.enums, 1);
+        assert_eq!(
+            report.warnings,
+            [SchemaWarning::IdentCollision {
+                scope: "status.Status".to_string(),
+                first: "STATUS_OK".to_string(),
+                second: "OK".to_string(),
+                ident: "Ok".to_string(),
+            }]
+        );
+
+        let mut config = Config::new();
+        config.retain_enum_prefix();
+        let report = SchemaReport::new(
+            &config,
+            &[(Module::from_protobuf_package_name("status"), file)],
+        );
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_large_oneof() {
+        let field = |number: i32| FieldDescriptorProto {
+            name: Some(format!("choice_{}", number)),
+            number: Some(number),
+            r#type: Some(Type::Bool as i32),
+            oneof_index: Some(0),
+            ..Default::default()
+        };
+        let message = |variants: i32| DescriptorProto {
+            name: Some(format!("Choice{}", variants)),
+            field: (1..=variants).map(field).collect(),
+            oneof_decl: vec![OneofDescriptorProto {
+                name: Some("choice".to_string()),
+                options: None,
+            }],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("choices.proto".to_string()),
+            package: Some("choices".to_string()),
+            message_type: vec![message(64), message(65)],
+            ..Default::default()
+        };
+
+        let report = SchemaReport::new(
+            &Config::new(),
+            &[(Module::from_protobuf_package_name("choices"), file)],
+        );
+        assert_eq!(report.oneofs, 2);
+        assert_eq!(
+            report.warnings,
+            [SchemaWarning::LargeOneof {
+                oneof: "choices.Choice65.choice".to_string(),
+                variants: 65,
+            }]
+        );
+    }
+}
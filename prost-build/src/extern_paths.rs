@@ -23,7 +23,14 @@ pub struct ExternPaths {
 }
 
 impl ExternPaths {
-    pub fn new(paths: &[(String, String)], prost_types: bool) -> Result<ExternPaths, String> {
+    /// Creates the extern paths, adding the well-known types of `prost_types` if `prost_types`
+    /// is set, and lowering the well-known wrapper types to the Rust types implementing their
+    /// encoding if either `prost_types` or `unwrap_wrappers` is set.
+    pub fn new(
+        paths: &[(String, String)],
+        prost_types: bool,
+        unwrap_wrappers: bool,
+    ) -> Result<ExternPaths, String> {
         let mut extern_paths = ExternPaths {
             extern_paths: HashMap::new(),
         };
@@ -34,6 +41,10 @@ impl ExternPaths {
 
         if prost_types {
             extern_paths.insert(".google.protobuf".to_string(), "::prost_types".to_string())?;
+            extern_paths.insert(".google.protobuf.Empty".to_string(), "()".to_string())?;
+        }
+
+        if prost_types || unwrap_wrappers {
             extern_paths.insert(".google.protobuf.BoolValue".to_string(), "bool".to_string())?;
             extern_paths.insert(
                 ".google.protobuf.BytesValue".to_string(),
@@ -43,7 +54,6 @@ impl ExternPaths {
                 ".google.protobuf.DoubleValue".to_string(),
                 "f64".to_string(),
             )?;
-            extern_paths.insert(".google.protobuf.Empty".to_string(), "()".to_string())?;
             extern_paths.insert(".google.protobuf.FloatValue".to_string(), "f32".to_string())?;
             extern_paths.insert(".google.protobuf.Int32Value".to_string(), "i32".to_string())?;
             extern_paths.insert(".google.protobuf.Int64Value".to_string(), "i64".to_string())?;
@@ -132,6 +142,7 @@ mod tests {
                 (".a.b.c.d.e.f".to_string(), "::abc::def".to_string()),
             ],
             false,
+            false,
         )
         .unwrap();
 
@@ -157,7 +168,7 @@ mod tests {
 
     #[test]
     fn test_well_known_types() {
-        let paths = ExternPaths::new(&[], true).unwrap();
+        let paths = ExternPaths::new(&[], true, false).unwrap();
 
         let case = |proto_ident: &str, resolved_ident: &str| {
             assert_eq!(paths.resolve_ident(proto_ident).unwrap(), resolved_ident);
@@ -166,12 +177,47 @@ mod tests {
         case(".google.protobuf.Value", "::prost_types::Value");
         case(".google.protobuf.Duration", "::prost_types::Duration");
         case(".google.protobuf.Empty", "()");
+        case(".google.protobuf.Int32Value", "i32");
+    }
+
+    #[test]
+    fn test_unwrap_wrappers() {
+        let paths = ExternPaths::new(
+            &[(".google.protobuf".to_string(), "::wkt".to_string())],
+            false,
+            true,
+        )
+        .unwrap();
+
+        let case = |proto_ident: &str, resolved_ident: &str| {
+            assert_eq!(paths.resolve_ident(proto_ident).unwrap(), resolved_ident);
+        };
+
+        case(".google.protobuf.BoolValue", "bool");
+        case(
+            ".google.protobuf.BytesValue",
+            "::prost::alloc::vec::Vec<u8>",
+        );
+        case(
+            ".google.protobuf.StringValue",
+            "::prost::alloc::string::String",
+        );
+        case(".google.protobuf.UInt64Value", "u64");
+        case(".google.protobuf.Empty", "::wkt::Empty");
+        case(".google.protobuf.Timestamp", "::wkt::Timestamp");
+
+        let paths = ExternPaths::new(&[], false, true).unwrap();
+        assert_eq!(
+            paths.resolve_ident(".google.protobuf.Int32Value").unwrap(),
+            "i32"
+        );
+        assert!(paths.resolve_ident(".google.protobuf.Timestamp").is_none());
     }
 
     #[test]
     fn test_error_fully_qualified() {
         let paths = [("foo".to_string(), "bar".to_string())];
-        let err = ExternPaths::new(&paths, false).unwrap_err();
+        let err = ExternPaths::new(&paths, false, false).unwrap_err();
         assert_eq!(
             err.to_string(),
             "Protobuf paths must be fully qualified (begin with a leading '.'): foo"
@@ -181,7 +227,7 @@ mod tests {
     #[test]
     fn test_error_invalid_path() {
         let paths = [(".foo.".to_string(), "bar".to_string())];
-        let err = ExternPaths::new(&paths, false).unwrap_err();
+        let err = ExternPaths::new(&paths, false, false).unwrap_err();
         assert_eq!(
             err.to_string(),
             "invalid fully-qualified Protobuf path: .foo."
@@ -194,7 +240,7 @@ mod tests {
             (".foo".to_string(), "bar".to_string()),
             (".foo".to_string(), "bar".to_string()),
         ];
-        let err = ExternPaths::new(&paths, false).unwrap_err();
+        let err = ExternPaths::new(&paths, false, false).unwrap_err();
         assert_eq!(err.to_string(), "duplicate extern Protobuf path: .foo")
     }
 }
@@ -0,0 +1,145 @@
+// This file is @generated by prost-build.
+/// Mapped directly from bytes with zerocopy.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(
+    feature = "archive",
+    derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)
+)]
+#[cfg_attr(
+    feature = "archive",
+    derive(
+        ::zerocopy::FromBytes,
+        ::zerocopy::IntoBytes,
+        ::zerocopy::Immutable,
+        ::zerocopy::KnownLayout
+    )
+)]
+#[cfg_attr(feature = "archive", repr(C))]
+pub struct Sample {
+    #[prost(fixed64, tag = "1")]
+    pub timestamp: u64,
+    #[prost(double, tag = "2")]
+    pub value: f64,
+    #[prost(sint32, tag = "3")]
+    pub delta: i32,
+    #[prost(enumeration = "Level", tag = "4")]
+    pub level: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(
+    feature = "archive",
+    derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)
+)]
+pub struct Batch {
+    #[prost(string, tag = "1")]
+    pub source: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub samples: ::prost::alloc::vec::Vec<Sample>,
+    #[prost(map = "string, message", tag = "3")]
+    pub latest: ::std::collections::HashMap<::prost::alloc::string::String, Sample>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub payload: ::prost::alloc::vec::Vec<u8>,
+    #[prost(oneof = "batch::Origin", tags = "5, 6")]
+    pub origin: ::core::option::Option<batch::Origin>,
+}
+/// Nested message and enum types in `Batch`.
+pub mod batch {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    #[cfg_attr(
+        feature = "archive",
+        derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)
+    )]
+    #[cfg_attr(
+        feature = "archive",
+        rkyv(
+            serialize_bounds(
+                __S:rkyv::ser::Writer+rkyv::ser::Allocator,
+                __S::Error:rkyv::rancor::Source
+            )
+        )
+    )]
+    #[cfg_attr(
+        feature = "archive",
+        rkyv(deserialize_bounds(__D::Error:rkyv::rancor::Source))
+    )]
+    #[cfg_attr(
+        feature = "archive",
+        rkyv(
+            bytecheck(
+                bounds(
+                    __C:rkyv::validation::ArchiveContext,
+                    __C::Error:rkyv::rancor::Source
+                )
+            )
+        )
+    )]
+    pub enum Origin {
+        #[prost(string, tag = "5")]
+        Host(::prost::alloc::string::String),
+        #[prost(message, tag = "6")]
+        #[cfg_attr(feature = "archive", rkyv(omit_bounds))]
+        Forwarded(::prost::alloc::boxed::Box<super::Batch>),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(
+    feature = "archive",
+    derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)
+)]
+#[cfg_attr(
+    feature = "archive",
+    rkyv(
+        serialize_bounds(
+            __S:rkyv::ser::Writer+rkyv::ser::Allocator,
+            __S::Error:rkyv::rancor::Source
+        )
+    )
+)]
+#[cfg_attr(
+    feature = "archive",
+    rkyv(deserialize_bounds(__D::Error:rkyv::rancor::Source))
+)]
+#[cfg_attr(
+    feature = "archive",
+    rkyv(
+        bytecheck(
+            bounds(__C:rkyv::validation::ArchiveContext, __C::Error:rkyv::rancor::Source)
+        )
+    )
+)]
+pub struct Tree {
+    #[prost(message, repeated, tag = "1")]
+    #[cfg_attr(feature = "archive", rkyv(omit_bounds))]
+    pub children: ::prost::alloc::vec::Vec<Tree>,
+    #[prost(message, optional, boxed, tag = "2")]
+    #[cfg_attr(feature = "archive", rkyv(omit_bounds))]
+    pub parent: ::core::option::Option<::prost::alloc::boxed::Box<Tree>>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Level {
+    #[doc(alias = "LEVEL_UNSPECIFIED")]
+    Unspecified = 0,
+    #[doc(alias = "LEVEL_HIGH")]
+    High = 1,
+}
+impl Level {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "LEVEL_UNSPECIFIED",
+            Self::High => "LEVEL_HIGH",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "LEVEL_UNSPECIFIED" => Some(Self::Unspecified),
+            "LEVEL_HIGH" => Some(Self::High),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,10 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Account {
+    #[prost(message, optional, tag = "1")]
+    pub last_invoice: ::core::option::Option<::acme_protos::billing::v1::Invoice>,
+    #[prost(message, optional, tag = "2")]
+    pub owner: ::core::option::Option<::acme_protos::identity::User>,
+    #[prost(message, optional, tag = "3")]
+    pub role: ::core::option::Option<::acme_protos::identity::admin::Role>,
+}
@@ -0,0 +1,22 @@
+// This file is @generated by prost-build.
+pub mod acme {
+    pub mod billing {
+        pub mod v1 {
+            #[cfg(feature = "acme")]
+            pub use ::acme_protos::billing::v1::*;
+        }
+    }
+    pub mod identity {
+        pub mod v2 {
+            #[cfg(feature = "acme")]
+            pub use ::acme_protos::identity::*;
+            pub mod admin {
+                #[cfg(feature = "acme")]
+                pub use ::acme_protos::identity::admin::*;
+            }
+        }
+    }
+}
+pub mod app {
+    include!("app.rs");
+}
@@ -19,6 +19,7 @@ pub struct Response {
 pub enum ServingStatus {
     Unknown = 0,
     Serving = 1,
+    #[doc(alias = "NOT_SERVING")]
     NotServing = 2,
 }
 impl ServingStatus {
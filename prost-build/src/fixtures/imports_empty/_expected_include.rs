@@ -3,6 +3,9 @@ pub mod com {
     pub mod prost_test {
         pub mod test {
             pub mod v1 {
+                //! ---
+                //!
+                //! 1. Package
                 include!("com.prost_test.test.v1.rs");
             }
         }
@@ -0,0 +1,32 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct User {
+    #[prost(string, tag="1")]
+    #[serde(rename = "userId")]
+    pub user_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    #[serde(rename = "name")]
+    pub display_name: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    #[serde(rename = "legacy_id")]
+    pub legacy_id: ::prost::alloc::string::String,
+    #[prost(map="string, string", tag="4")]
+    #[serde(rename = "extraLabels")]
+    pub extra_labels: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    #[prost(oneof="user::Contact", tags="5, 6")]
+    pub contact: ::core::option::Option<user::Contact>,
+}
+/// Nested message and enum types in `User`.
+pub mod user {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Contact {
+        #[doc(alias = "email_address")]
+        #[prost(string, tag="5")]
+        #[serde(rename = "emailAddress")]
+        EmailAddress(::prost::alloc::string::String),
+        #[doc(alias = "phone_number")]
+        #[prost(string, tag="6")]
+        #[serde(rename = "phone")]
+        PhoneNumber(::prost::alloc::string::String),
+    }
+}
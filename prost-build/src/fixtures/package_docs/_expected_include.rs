@@ -0,0 +1,16 @@
+// This file is @generated by prost-build.
+pub mod outer {
+    //! Messages of the outer package.
+    //!
+    //! See <https://protobuf.dev> for the language guide.
+    include!("outer.rs");
+    pub mod inner {
+        //! Messages of the inner package.
+        //!
+        //! More messages of the inner package.
+        include!("outer.inner.rs");
+    }
+}
+pub mod undocumented {
+    include!("undocumented.rs");
+}
@@ -0,0 +1,15 @@
+// This file is @generated by prost-build.
+pub mod billing {
+    pub mod v1 {
+        include!("billing.v1.rs");
+    }
+}
+pub mod shop {
+    pub mod v1 {
+        include!("shop.v1.rs");
+    }
+}
+/// The top-level messages and enums of all packages.
+pub mod prelude {
+    include!("prelude.rs");
+}
@@ -0,0 +1,5 @@
+// This file is @generated by prost-build.
+pub use super::billing::v1::Invoice;
+pub use super::shop::v1::Order;
+pub use super::shop::v1::OrderStatus;
+// `Request` is not re-exported, as it is defined in several packages: super::billing::v1::Request, super::shop::v1::Request
@@ -0,0 +1,85 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub(crate) struct Account {
+    #[prost(string, tag="1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(int64, tag="2")]
+    pub(super) balance: i64,
+    #[prost(map="string, string", tag="3")]
+    labels: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    #[prost(enumeration="account::Kind", tag="6")]
+    pub kind: i32,
+    #[prost(oneof="account::Owner", tags="4, 5")]
+    pub(crate) owner: ::core::option::Option<account::Owner>,
+}
+/// Nested message and enum types in `Account`.
+pub mod account {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub(crate) enum Kind {
+        #[doc(alias = "KIND_UNSPECIFIED")]
+        Unspecified = 0,
+        #[doc(alias = "KIND_PERSONAL")]
+        Personal = 1,
+    }
+    impl Kind {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unspecified => "KIND_UNSPECIFIED",
+                Self::Personal => "KIND_PERSONAL",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "KIND_UNSPECIFIED" => Some(Self::Unspecified),
+                "KIND_PERSONAL" => Some(Self::Personal),
+                _ => None,
+            }
+        }
+    }
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub(super) enum Owner {
+        #[prost(string, tag="4")]
+        User(::prost::alloc::string::String),
+        #[prost(string, tag="5")]
+        Team(::prost::alloc::string::String),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct Secret {
+    #[prost(bytes="vec", tag="1")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum Status {
+    #[doc(alias = "STATUS_UNSPECIFIED")]
+    Unspecified = 0,
+    #[doc(alias = "STATUS_ACTIVE")]
+    Active = 1,
+}
+impl Status {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "STATUS_UNSPECIFIED",
+            Self::Active => "STATUS_ACTIVE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "STATUS_UNSPECIFIED" => Some(Self::Unspecified),
+            "STATUS_ACTIVE" => Some(Self::Active),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+/// The foo packages.
+pub mod foo {
+    #[allow(clippy::all)]
+    pub mod bar {
+        include!(concat!(env!("OUT_DIR"), "/foo.bar.rs"));
+    }
+    #[cfg(feature = "baz")]
+    pub mod baz {
+        include!(concat!(env!("OUT_DIR"), "/foo.baz.rs"));
+    }
+}
+#[allow(clippy::all)]
+pub mod qux {
+    include!(concat!(env!("OUT_DIR"), "/qux.rs"));
+}
@@ -38,6 +38,76 @@ pub fn to_upper_camel(s: impl AsRef<str>) -> String {
     sanitize_identifier(s.as_ref().to_upper_camel_case())
 }
 
+/// Converts Protobuf names to Rust identifiers.
+///
+/// The policy is used for the names of generated modules, messages, fields, oneofs, enums,
+/// services and methods, and for the names of enum variants unless they are named with
+/// [`Config::enum_variant_renamer`](crate::Config::enum_variant_renamer). The default
+/// implementations of the methods produce the names `prost-build` generates by default, so a
+/// policy only needs to override the conversions it changes.
+///
+/// Paths of types generated by other crates, registered with
+/// [`Config::extern_path`](crate::Config::extern_path), are always resolved with the default
+/// conversions, as those crates are usually built with the default policy.
+///
+/// # Examples
+///
+/// ```rust
+/// use prost_build::IdentPolicy;
+///
+/// /// Keeps acronyms and version numbers of type names, so that `HTTPServer2` is not
+/// /// converted to `HttpServer2`.
+/// struct KeepTypeNames;
+///
+/// impl IdentPolicy for KeepTypeNames {
+///     fn to_upper_camel(&self, name: &str) -> String {
+///         name.to_string()
+///     }
+/// }
+///
+/// let mut config = prost_build::Config::new();
+/// config.ident_policy(KeepTypeNames);
+/// ```
+pub trait IdentPolicy {
+    /// Converts a `camelCase` or `SCREAMING_SNAKE_CASE` name to a `lower_snake` case Rust
+    /// module, field or method identifier.
+    fn to_snake(&self, name: &str) -> String {
+        name.to_snake_case()
+    }
+
+    /// Converts a `snake_case` name to an `UpperCamel` case Rust type or variant identifier.
+    fn to_upper_camel(&self, name: &str) -> String {
+        name.to_upper_camel_case()
+    }
+
+    /// Escapes a converted identifier which is not a valid Rust identifier.
+    ///
+    /// The default implementation uses raw identifiers for Rust keywords, appends an underscore
+    /// to keywords which can not be raw identifiers, such as `self`, and prefixes identifiers
+    /// starting with a digit with an underscore.
+    fn escape(&self, ident: &str) -> String {
+        sanitize_identifier(ident)
+    }
+}
+
+/// The identifier conversions used by default.
+///
+/// See [`IdentPolicy`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultIdentPolicy;
+
+impl IdentPolicy for DefaultIdentPolicy {}
+
+/// Converts a name to a `lower_snake` case Rust identifier with the given policy.
+pub(crate) fn to_snake_with(policy: &dyn IdentPolicy, s: &str) -> String {
+    policy.escape(&policy.to_snake(s))
+}
+
+/// Converts a name to an `UpperCamel` case Rust identifier with the given policy.
+pub(crate) fn to_upper_camel_with(policy: &dyn IdentPolicy, s: &str) -> String {
+    policy.escape(&policy.to_upper_camel(s))
+}
+
 /// Strip an enum's type name from the prefix of an enum value.
 ///
 /// This function assumes that both have been formatted to Rust's
@@ -66,6 +136,26 @@ pub fn strip_enum_prefix(prefix: &str, name: &str) -> String {
     sanitize_identifier(stripped)
 }
 
+/// Converts a `snake_case` field name to its default Protobuf JSON name.
+///
+/// This matches `protoc`: underscores are removed and the character following each underscore is
+/// uppercased.
+pub fn to_json_name(s: &str) -> String {
+    let mut json_name = String::with_capacity(s.len());
+    let mut capitalize_next = false;
+    for c in s.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            json_name.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,6 +333,37 @@ mod tests {
         assert_eq!("Self_", &to_upper_camel("self"));
     }
 
+    #[test]
+    fn test_ident_policy() {
+        struct KeepAcronyms;
+
+        impl IdentPolicy for KeepAcronyms {
+            fn to_snake(&self, name: &str) -> String {
+                name.to_ascii_lowercase()
+            }
+
+            fn to_upper_camel(&self, name: &str) -> String {
+                name.to_string()
+            }
+        }
+
+        assert_eq!(
+            to_upper_camel_with(&DefaultIdentPolicy, "HTTPServer2"),
+            "HttpServer2"
+        );
+        assert_eq!(
+            to_snake_with(&DefaultIdentPolicy, "HTTPServer2"),
+            "http_server2"
+        );
+        assert_eq!(
+            to_upper_camel_with(&KeepAcronyms, "HTTPServer2"),
+            "HTTPServer2"
+        );
+        assert_eq!(to_snake_with(&KeepAcronyms, "HTTPServer2"), "httpserver2");
+        assert_eq!(to_snake_with(&KeepAcronyms, "Type"), "r#type");
+        assert_eq!(to_upper_camel_with(&KeepAcronyms, "2xx"), "_2xx");
+    }
+
     #[test]
     fn test_strip_enum_prefix() {
         assert_eq!(strip_enum_prefix("Foo", "FooBar"), "Bar");
@@ -253,4 +374,15 @@ mod tests {
         assert_eq!(strip_enum_prefix("Foo", "FooBar"), "Bar");
         assert_eq!(strip_enum_prefix("Foo", "FooSelf"), "Self_");
     }
+
+    #[test]
+    fn test_to_json_name() {
+        assert_eq!(to_json_name("foo"), "foo");
+        assert_eq!(to_json_name("foo_bar"), "fooBar");
+        assert_eq!(to_json_name("foo_bar_baz"), "fooBarBaz");
+        assert_eq!(to_json_name("foo__bar"), "fooBar");
+        assert_eq!(to_json_name("_foo"), "Foo");
+        assert_eq!(to_json_name("foo_1"), "foo1");
+        assert_eq!(to_json_name("fooBar"), "fooBar");
+    }
 }
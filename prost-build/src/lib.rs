@@ -84,6 +84,7 @@
 //! ## Feature Flags
 //! - `format`: Format the generated output. This feature is enabled by default.
 //! - `cleanup-markdown`: Clean up Markdown in protobuf docs. Enable this to clean up protobuf files from third parties.
+//! - `tokens`: Enable `Config::compile_fds_to_tokens`, which generates a `TokenStream` for each module instead of writing files.
 //!
 //! ### Cleaning up Markdown in code docs
 //!
@@ -118,9 +119,15 @@
 //! Alternatively, the path to `protoc` execuatable can be explicitly set
 //! via [`Config::protoc_executable()`].
 //!
+//! Builds which must not depend on the environment, such as under Bazel or Nix, can use
+//! [`Config::hermetic()`] to ignore `PROTOC` and `PROTOC_INCLUDE` and require explicit paths.
+//!
 //! If `prost-build` can not find `protoc`
 //! via these methods the `compile_protos` method will fail.
 //!
+//! The error lists the `protoc` executables found in the environment, and why each of them was
+//! rejected. The same report is returned by [`diagnose_protoc()`].
+//!
 //! [`protoc` install instructions]: https://github.com/protocolbuffers/protobuf#protocol-compiler-installation
 //!
 //! ### Compiling `protoc` from source
@@ -141,20 +148,35 @@ use std::path::Path;
 use prost_types::FileDescriptorSet;
 
 mod ast;
-pub use crate::ast::{Comments, Method, Service};
+pub use crate::ast::{
+    Comments, MessageTypeExt, Method, MethodDescriptorExt, Service, ServiceDescriptorExt,
+    SERVICE_DESCRIPTOR_EXT_VERSION,
+};
 
 mod collections;
 pub(crate) use collections::{BytesType, MapType};
 
 mod code_generator;
+mod diagnostics;
 mod extern_paths;
 mod ident;
+pub use ident::{DefaultIdentPolicy, IdentPolicy};
+mod merged_packages;
 mod message_graph;
 mod path;
+pub mod plugin;
+mod protoc_discovery;
+pub use protoc_discovery::{
+    diagnose_protoc, ProtocCandidate, ProtocRejection, ProtocReport, ProtocSource,
+};
+mod protoc_version;
+pub use protoc_version::{ParseProtocVersionError, ProtocVersion};
+mod source_info;
 
 mod config;
 pub use config::{
-    error_message_protoc_not_found, protoc_from_env, protoc_include_from_env, Config,
+    error_message_protoc_not_found, protoc_from_env, protoc_include_from_env, ArchiveDerive,
+    Config, CrateRemap, CustomType, EnumVariantNaming, TimeRangePolicy,
 };
 
 mod module;
@@ -179,6 +201,22 @@ pub trait ServiceGenerator {
     /// result to `buf`.
     fn generate(&mut self, service: Service, buf: &mut String);
 
+    /// Generates a Rust interface or implementation for a service like
+    /// [`generate`](#method.generate), with the metadata of the service resolved by
+    /// `prost-build`.
+    ///
+    /// This is the method called by `prost-build`. The default implementation ignores the
+    /// descriptor and calls `generate`.
+    fn generate_with_descriptor(
+        &mut self,
+        service: Service,
+        descriptor: &ServiceDescriptorExt,
+        buf: &mut String,
+    ) {
+        let _ = descriptor;
+        self.generate(service, buf);
+    }
+
     /// Finalizes the generation process.
     ///
     /// In case there's something that needs to be output at the end of the generation process, it
@@ -352,6 +390,8 @@ mod tests {
     struct MockState {
         service_names: Vec<String>,
         package_names: Vec<String>,
+        descriptors: Vec<ServiceDescriptorExt>,
+        methods: Vec<Method>,
         finalized: u32,
     }
 
@@ -365,6 +405,17 @@ mod tests {
         fn generate(&mut self, service: Service, _buf: &mut String) {
             let mut state = self.state.borrow_mut();
             state.service_names.push(service.name);
+            state.methods.extend(service.methods);
+        }
+
+        fn generate_with_descriptor(
+            &mut self,
+            service: Service,
+            descriptor: &ServiceDescriptorExt,
+            buf: &mut String,
+        ) {
+            self.state.borrow_mut().descriptors.push(descriptor.clone());
+            self.generate(service, buf);
         }
 
         fn finalize(&mut self, _buf: &mut String) {
@@ -417,6 +468,90 @@ mod tests {
         assert_eq!(state.finalized, 3);
     }
 
+    #[test]
+    fn service_descriptor_ext() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let state = Rc::new(RefCell::new(MockState::default()));
+        let gen = MockServiceGenerator::new(Rc::clone(&state));
+
+        Config::new()
+            .service_generator(Box::new(gen))
+            .extern_path(".helloworld.Response", "::responses::Response")
+            .type_name_domain(["."], "type.example.com")
+            .method_attribute(".helloworld", "#[instrument]")
+            .method_attribute("Greeting.Hello", "#[cfg(feature = \"hello\")]")
+            .prost_path("::custom_prost")
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &[
+                    "src/fixtures/helloworld/hello.proto",
+                    "src/fixtures/helloworld/goodbye.proto",
+                ],
+                &["src/fixtures/helloworld"],
+            )
+            .unwrap();
+
+        let state = state.borrow();
+        assert_eq!(state.service_names, ["Greeting", "Farewell"]);
+        let [greeting, farewell] = &state.descriptors[..] else {
+            panic!("unexpected descriptors: {:?}", state.descriptors);
+        };
+        assert_eq!(greeting.version, SERVICE_DESCRIPTOR_EXT_VERSION);
+        assert_eq!(greeting.full_name, "helloworld.Greeting");
+        assert_eq!(greeting.prost_path, "::custom_prost");
+
+        let hello = &greeting.methods[0];
+        assert_eq!(hello.full_name, "helloworld.Greeting.Hello");
+        assert_eq!(hello.path, "/helloworld.Greeting/Hello");
+        assert_eq!(hello.input.proto_name, ".helloworld.Message");
+        assert_eq!(hello.input.rust_path, "Message");
+        assert!(!hello.input.is_extern);
+        assert_eq!(hello.input.type_url, "type.example.com/helloworld.Message");
+        assert_eq!(hello.output.rust_path, "::responses::Response");
+        assert!(hello.output.is_extern);
+        assert_eq!(
+            hello.attributes,
+            ["#[instrument]", "#[cfg(feature = \"hello\")]"]
+        );
+
+        let goodbye = &farewell.methods[0];
+        assert_eq!(goodbye.path, "/helloworld.Farewell/Goodbye");
+        assert_eq!(goodbye.attributes, ["#[instrument]"]);
+    }
+
+    #[test]
+    fn method_options() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let state = Rc::new(RefCell::new(MockState::default()));
+        let gen = MockServiceGenerator::new(Rc::clone(&state));
+
+        Config::new()
+            .service_generator(Box::new(gen))
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/method_options/method_options.proto"],
+                &["src/fixtures/method_options"],
+            )
+            .unwrap();
+
+        let state = state.borrow();
+        let [fetch, legacy] = &state.methods[..] else {
+            panic!("unexpected methods: {:?}", state.methods);
+        };
+        assert!(!fetch.deprecated);
+        // `max_retries` is field 50000, a varint.
+        assert_eq!(fetch.options_bytes, [0x80, 0xb5, 0x18, 3]);
+        assert_eq!(fetch.source_path, [6, 0, 2, 0]);
+        assert_eq!(fetch.span, [17, 4, 19, 5]);
+        assert!(legacy.deprecated);
+        assert_eq!(legacy.options_bytes, [0x88, 0x02, 1]);
+        assert_eq!(legacy.source_path, [6, 0, 2, 1]);
+    }
+
     #[test]
     fn test_generate_message_attributes() {
         let _ = env_logger::try_init();
@@ -462,6 +597,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_transform_fds() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .transform_fds(|fds| {
+                for file in &mut fds.file {
+                    file.package = Some("renamed".to_string());
+                }
+            })
+            .transform_fds(|fds| {
+                for file in &mut fds.file {
+                    let package = format!("{}.again", file.package());
+                    file.package = Some(package);
+                }
+            })
+            .compile_protos(&["src/fixtures/smoke_test/smoke_test.proto"], &["src"])
+            .unwrap();
+
+        assert!(tempdir.path().join("renamed.again.rs").exists());
+        assert!(!tempdir.path().join("smoke_test.rs").exists());
+    }
+
+    #[test]
+    #[cfg(feature = "tokens")]
+    fn test_compile_fds_to_tokens() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let mut config = Config::new();
+        config.out_dir(tempdir.path());
+        let fds = config
+            .load_fds(
+                &["src/fixtures/helloworld/hello.proto"],
+                &["src/fixtures/helloworld"],
+            )
+            .unwrap();
+        let modules = config.compile_fds_to_tokens(fds).unwrap();
+
+        assert_eq!(modules.len(), 1);
+        let tokens = modules[&Module::from_protobuf_package_name("helloworld")].to_string();
+        assert!(tokens.contains("pub struct Message"));
+        assert!(tokens.contains("pub struct Response"));
+        // No file is written.
+        assert!(!tempdir.path().join("helloworld.rs").exists());
+    }
+
+    #[test]
+    #[cfg(feature = "format")]
+    fn test_formatted_fixtures_are_stable() {
+        for fixture in [
+            "src/fixtures/helloworld/_expected_helloworld_formatted.rs",
+            "src/fixtures/field_attributes/_expected_field_attributes_formatted.rs",
+            "src/fixtures/json_name/_expected_json_name_formatted.rs",
+        ] {
+            let expected = std::fs::read_to_string(fixture)
+                .unwrap()
+                .replace("\r\n", "\n");
+            let (header, code) = expected.split_once('\n').unwrap();
+            let formatted = prettyplease::unparse(&syn::parse_file(code).unwrap());
+            assert_eq!(
+                format!("{}\n{}", header, formatted),
+                expected,
+                "{}",
+                fixture
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "format")]
+    fn test_error_unparsable_generated_code() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let err = Config::new()
+            .out_dir(tempdir.path())
+            .type_attribute(".", "#[derive(")
+            .compile_protos(&["src/fixtures/smoke_test/smoke_test.proto"], &["src"])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err
+            .to_string()
+            .starts_with("generated code for smoke_test is not valid Rust: "));
+    }
+
     #[test]
     fn test_generate_no_empty_outputs() {
         let _ = env_logger::try_init();
@@ -522,6 +744,122 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_generate_serde_json_names() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .serde_json_names(["."])
+            .json_use_proto_names(["User.legacy_id"])
+            .compile_protos(
+                &["src/fixtures/json_name/json_name.proto"],
+                &["src/fixtures/json_name"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            if cfg!(feature = "format") {
+                "src/fixtures/json_name/_expected_json_name_formatted.rs"
+            } else {
+                "src/fixtures/json_name/_expected_json_name.rs"
+            },
+            tempdir.path().join("json_name.rs")
+        );
+    }
+
+    #[test]
+    fn test_generate_visibility() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .type_visibility(".visibility", "pub(crate)")
+            .type_visibility(".visibility.Secret", "")
+            .type_visibility(".visibility.Account.owner", "pub(super)")
+            .field_visibility(".visibility.Account.balance", "pub(super)")
+            .field_visibility(".visibility.Account.labels", "")
+            .field_visibility("Account.owner", "pub(crate)")
+            .compile_protos(
+                &["src/fixtures/visibility/visibility.proto"],
+                &["src/fixtures/visibility"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            if cfg!(feature = "format") {
+                "src/fixtures/visibility/_expected_visibility_formatted.rs"
+            } else {
+                "src/fixtures/visibility/_expected_visibility.rs"
+            },
+            tempdir.path().join("visibility.rs")
+        );
+    }
+
+    #[test]
+    fn test_generate_archive_derives() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .archive_derive([".archive"], ArchiveDerive::Rkyv)
+            .archive_derive([".archive.Sample"], ArchiveDerive::Zerocopy)
+            .archive_derive_feature("archive")
+            .compile_protos(
+                &["src/fixtures/archive_derive/archive_derive.proto"],
+                &["src/fixtures/archive_derive"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            if cfg!(feature = "format") {
+                "src/fixtures/archive_derive/_expected_archive_derive_formatted.rs"
+            } else {
+                "src/fixtures/archive_derive/_expected_archive_derive.rs"
+            },
+            tempdir.path().join("archive.rs")
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "zerocopy traits can not be derived for field .archive.Batch.source: only \
+                    singular, non-optional fixed-width numbers and enums are supported"
+    )]
+    fn test_error_archive_derive_unsupported_field() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .archive_derive([".archive.Batch"], ArchiveDerive::Zerocopy)
+            .compile_protos(
+                &["src/fixtures/archive_derive/archive_derive.proto"],
+                &["src/fixtures/archive_derive"],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "rkyv traits can not be derived for field .archive.Batch.samples: message \
+                    .archive.Sample does not derive the rkyv traits"
+    )]
+    fn test_error_archive_derive_unarchived_message() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .archive_derive([".archive.Batch"], ArchiveDerive::Rkyv)
+            .compile_protos(
+                &["src/fixtures/archive_derive/archive_derive.proto"],
+                &["src/fixtures/archive_derive"],
+            )
+            .unwrap();
+    }
+
     #[test]
     fn deterministic_include_file() {
         let _ = env_logger::try_init();
@@ -556,6 +894,237 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_package_docs() {
+        let _ = env_logger::try_init();
+        let protos = [
+            "src/fixtures/package_docs/outer.proto",
+            "src/fixtures/package_docs/inner_a.proto",
+            "src/fixtures/package_docs/inner_b.proto",
+            "src/fixtures/package_docs/undocumented.proto",
+        ];
+        let include_file = "_include.rs";
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .include_file(include_file)
+            .out_dir(tempdir.path())
+            .compile_protos(&protos, &["src/fixtures/package_docs"])
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            "src/fixtures/package_docs/_expected_include.rs",
+            tempdir.path().join(include_file)
+        );
+        // Inner doc comments are not allowed in files included with `include!`.
+        let outer = std::fs::read_to_string(tempdir.path().join("outer.rs")).unwrap();
+        assert!(!outer.contains("//!"));
+
+        Config::new()
+            .inner_package_docs(true)
+            .out_dir(tempdir.path())
+            .compile_protos(&protos, &["src/fixtures/package_docs"])
+            .unwrap();
+
+        let inner = std::fs::read_to_string(tempdir.path().join("outer.inner.rs")).unwrap();
+        assert!(inner.starts_with(
+            "// This file is @generated by prost-build.\n\
+             //! Messages of the inner package.\n\
+             //!\n\
+             //! More messages of the inner package.\n"
+        ));
+        let undocumented = std::fs::read_to_string(tempdir.path().join("undocumented.rs")).unwrap();
+        assert!(!undocumented.contains("//!"));
+    }
+
+    #[test]
+    fn test_package_constants() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .package_constants(true)
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &[
+                    "src/fixtures/package_docs/inner_a.proto",
+                    "src/fixtures/package_docs/inner_b.proto",
+                    "src/fixtures/helloworld/types.proto",
+                ],
+                &["src/fixtures/package_docs", "src/fixtures/helloworld"],
+            )
+            .unwrap();
+
+        let inner = std::fs::read_to_string(tempdir.path().join("outer.inner.rs")).unwrap();
+        assert!(inner.contains("pub const PACKAGE: &str = \"outer.inner\";\n"));
+        assert!(inner.contains(
+            "pub const PROTO_FILES: &[&str] = &[\"inner_a.proto\", \"inner_b.proto\"];\n"
+        ));
+        assert!(inner.contains("pub const SYNTAX: &str = \"proto3\";\n"));
+        let helloworld = std::fs::read_to_string(tempdir.path().join("helloworld.rs")).unwrap();
+        assert!(helloworld.contains("pub const PROTO_FILES: &[&str] = &[\"types.proto\"];\n"));
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/package_docs/inner_a.proto"],
+                &["src/fixtures/package_docs"],
+            )
+            .unwrap();
+        let inner = std::fs::read_to_string(tempdir.path().join("outer.inner.rs")).unwrap();
+        assert!(!inner.contains("PACKAGE"));
+    }
+
+    #[test]
+    fn test_compile_protos_with_modules() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let modules = Config::new()
+            .out_dir(tempdir.path())
+            .compile_protos_with_modules(
+                &[
+                    "src/fixtures/package_docs/outer.proto",
+                    "src/fixtures/package_docs/inner_a.proto",
+                    "src/fixtures/package_docs/inner_b.proto",
+                ],
+                &["src/fixtures/package_docs"],
+            )
+            .unwrap();
+
+        let inner = Module::from_rust_path("outer::inner");
+        assert_eq!(
+            modules.keys().collect::<Vec<_>>(),
+            [inner.parent().as_ref().unwrap(), &inner]
+        );
+        assert_eq!(modules[&inner], tempdir.path().join("outer.inner.rs"));
+        assert!(modules.values().all(|path| path.exists()));
+    }
+
+    #[test]
+    fn test_generate_prelude() {
+        let _ = env_logger::try_init();
+        let include_file = "_include.rs";
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .generate_prelude(true)
+            .include_file(include_file)
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &[
+                    "src/fixtures/prelude/shop.proto",
+                    "src/fixtures/prelude/billing.proto",
+                ],
+                &["src/fixtures/prelude"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            "src/fixtures/prelude/_expected_prelude.rs",
+            tempdir.path().join("prelude.rs")
+        );
+        assert_eq_fixture_file!(
+            "src/fixtures/prelude/_expected_include.rs",
+            tempdir.path().join(include_file)
+        );
+    }
+
+    #[test]
+    fn test_error_generate_prelude_conflict() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let err = Config::new()
+            .generate_prelude(true)
+            .include_file("_include.rs")
+            .out_dir(tempdir.path())
+            .merge_packages(["shop.v1"], "prelude.shop")
+            .compile_protos(
+                &["src/fixtures/prelude/shop.proto"],
+                &["src/fixtures/prelude"],
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "the prelude of generate_prelude conflicts with the module generated in prelude.shop.rs"
+        );
+    }
+
+    #[test]
+    fn test_crate_remap() {
+        let _ = env_logger::try_init();
+        let include_file = "_include.rs";
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .crate_remap(
+                ".acme",
+                CrateRemap::new("::acme_protos")
+                    .module(".acme.identity.v2", "identity")
+                    .feature("acme"),
+            )
+            .include_file(include_file)
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/crate_remap/app.proto"],
+                &["src/fixtures/crate_remap"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            "src/fixtures/crate_remap/_expected_app.rs",
+            tempdir.path().join("app.rs")
+        );
+        assert_eq_fixture_file!(
+            "src/fixtures/crate_remap/_expected_include.rs",
+            tempdir.path().join(include_file)
+        );
+        assert!(!tempdir.path().join("acme.billing.v1.rs").exists());
+    }
+
+    #[test]
+    fn test_error_crate_remap_module_not_nested() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let err = Config::new()
+            .crate_remap(
+                ".acme",
+                CrateRemap::new("::acme_protos").module(".app", "app"),
+            )
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/crate_remap/app.proto"],
+                &["src/fixtures/crate_remap"],
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "the module package .app of the crate remap of .acme is not nested in it"
+        );
+    }
+
+    #[test]
+    fn test_schema_report() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .schema_report("report.txt")
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/schema_report/unpackaged.proto"],
+                &["src/fixtures/schema_report"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            "src/fixtures/schema_report/_expected_report.txt",
+            tempdir.path().join("report.txt")
+        );
+    }
+
     #[test]
     fn write_includes() {
         let modules = [
@@ -576,9 +1145,49 @@ mod tests {
         let mut buf = Vec::new();
         Config::new()
             .default_package_filename("_.default")
-            .write_includes(modules.iter().collect(), &mut buf, None, &file_names)
+            .write_includes(
+                modules.iter().collect(),
+                &mut buf,
+                None,
+                &file_names,
+                &std::collections::HashMap::new(),
+                &std::collections::BTreeMap::new(),
+            )
             .unwrap();
         let actual = String::from_utf8(buf).unwrap();
         assert_eq_fixture_contents!("src/fixtures/write_includes/_.includes.rs", actual);
     }
+
+    #[test]
+    fn write_includes_module_attributes() {
+        let modules = [
+            Module::from_protobuf_package_name("foo.bar"),
+            Module::from_protobuf_package_name("foo.baz"),
+            Module::from_protobuf_package_name("qux"),
+        ];
+
+        let file_names = modules
+            .iter()
+            .map(|m| (m.clone(), m.to_file_name_or("_.default")))
+            .collect();
+
+        let mut buf = Vec::new();
+        Config::new()
+            .include_module_attributes(|module| match module.to_rust_path().as_str() {
+                "foo" => vec!["/// The foo packages.".to_string()],
+                "foo::baz" => vec!["#[cfg(feature = \"baz\")]".to_string()],
+                _ => vec!["#[allow(clippy::all)]".to_string()],
+            })
+            .write_includes(
+                modules.iter().collect(),
+                &mut buf,
+                None,
+                &file_names,
+                &std::collections::HashMap::new(),
+                &std::collections::BTreeMap::new(),
+            )
+            .unwrap();
+        let actual = String::from_utf8(buf).unwrap();
+        assert_eq_fixture_contents!("src/fixtures/write_includes/_.module_attributes.rs", actual);
+    }
 }
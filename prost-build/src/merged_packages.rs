@@ -0,0 +1,162 @@
+//! Generation of several Protobuf packages into a single Rust module.
+
+use std::borrow::Cow;
+use std::collections::{hash_map, HashMap, HashSet};
+
+use prost_types::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto};
+
+use crate::ident::IdentPolicy;
+use crate::Module;
+
+/// The packages generated into the module of another package.
+#[derive(Debug, Default)]
+pub(crate) struct MergedPackages {
+    /// The target package of each merged package, without leading dots.
+    targets: HashMap<String, String>,
+}
+
+/// A top-level definition of a merged package.
+#[derive(PartialEq)]
+enum Definition {
+    Message(DescriptorProto),
+    Enum(EnumDescriptorProto),
+}
+
+impl MergedPackages {
+    pub fn insert(&mut self, package: &str, target: &str) {
+        self.targets.insert(
+            package.trim_start_matches('.').to_string(),
+            target.trim_start_matches('.').to_string(),
+        );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Returns the package whose module the given package is generated into.
+    pub fn target<'a>(&'a self, package: &'a str) -> &'a str {
+        self.targets.get(package).map_or(package, String::as_str)
+    }
+
+    /// Returns the module the given package is generated into.
+    pub fn module(&self, package: &str, policy: &dyn IdentPolicy) -> Module {
+        Module::from_protobuf_package_name_with(self.target(package), policy)
+    }
+
+    /// Rewrites a fully-qualified Protobuf name so that its package is the target package.
+    pub fn resolve<'a>(&self, fq_name: &'a str) -> Cow<'a, str> {
+        let merged = self
+            .targets
+            .iter()
+            .filter(|(package, _)| {
+                fq_name
+                    .strip_prefix('.')
+                    .and_then(|name| name.strip_prefix(package.as_str()))
+                    .is_some_and(|rest| rest.starts_with('.'))
+            })
+            .max_by_key(|(package, _)| package.len());
+        match merged {
+            Some((package, target)) => {
+                let rest = &fq_name[package.len() + 1..];
+                if target.is_empty() {
+                    Cow::Owned(rest.to_string())
+                } else {
+                    Cow::Owned(format!(".{}{}", target, rest))
+                }
+            }
+            None => Cow::Borrowed(fq_name),
+        }
+    }
+
+    /// Returns the fully-qualified names of the top-level messages and enums which are already
+    /// defined identically by another package merged into the same module, and should not be
+    /// generated again.
+    ///
+    /// Fails if packages merged into the same module define different types with the same
+    /// name. Types for which `is_extern` returns `true` are not generated, and are ignored.
+    pub fn duplicates(
+        &self,
+        files: &[(Module, FileDescriptorProto)],
+        is_extern: impl Fn(&str) -> bool,
+    ) -> Result<HashSet<String>, String> {
+        let mut definitions = HashMap::<String, (&str, Definition)>::new();
+        let mut duplicates = HashSet::new();
+
+        for (_, file) in files {
+            let package = file.package();
+            if !self.targets.contains_key(package) && !self.targets.values().any(|t| t == package) {
+                continue;
+            }
+
+            let messages = file.message_type.iter().map(|message| {
+                let mut message = message.clone();
+                self.normalize(&mut message);
+                (message.name().to_string(), Definition::Message(message))
+            });
+            let enums = file
+                .enum_type
+                .iter()
+                .map(|desc| (desc.name().to_string(), Definition::Enum(desc.clone())));
+
+            for (name, definition) in messages.chain(enums) {
+                let fq_name = format!(".{}.{}", package, name);
+                if is_extern(&fq_name) {
+                    continue;
+                }
+                match definitions.entry(self.resolve(&fq_name).into_owned()) {
+                    hash_map::Entry::Vacant(vacant) => {
+                        vacant.insert((package, definition));
+                    }
+                    hash_map::Entry::Occupied(occupied) => {
+                        let (first_package, first_definition) = occupied.get();
+                        if *first_definition != definition {
+                            return Err(format!(
+                                "packages {} and {} define different types named {}, and can not \
+                                 be merged into the same module",
+                                first_package, package, name
+                            ));
+                        }
+                        duplicates.insert(fq_name);
+                    }
+                }
+            }
+        }
+
+        Ok(duplicates)
+    }
+
+    /// Rewrites the type names of the fields of a message so that the definitions of merged
+    /// packages can be compared.
+    fn normalize(&self, message: &mut DescriptorProto) {
+        for field in &mut message.field {
+            if let Some(type_name) = field.type_name.as_mut() {
+                *type_name = self.resolve(type_name).into_owned();
+            }
+        }
+        for nested in &mut message.nested_type {
+            self.normalize(nested);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let mut merged = MergedPackages::default();
+        merged.insert("foo.v1", "foo");
+        merged.insert(".foo.v1beta", "foo");
+        merged.insert("bar", "");
+
+        assert_eq!(merged.resolve(".foo.v1.Msg"), ".foo.Msg");
+        assert_eq!(merged.resolve(".foo.v1beta.Msg.Nested"), ".foo.Msg.Nested");
+        assert_eq!(merged.resolve(".foo.v1betaX.Msg"), ".foo.v1betaX.Msg");
+        assert_eq!(merged.resolve(".foo.v2.Msg"), ".foo.v2.Msg");
+        assert_eq!(merged.resolve(".bar.Msg"), ".Msg");
+        assert_eq!(merged.target("foo.v1beta"), "foo");
+        assert_eq!(merged.target("foo.v2"), "foo.v2");
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use petgraph::algo::has_path_connecting;
 use petgraph::graph::NodeIndex;
@@ -6,7 +6,7 @@ use petgraph::Graph;
 
 use prost_types::{
     field_descriptor_proto::{Label, Type},
-    DescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
 };
 
 use crate::path::PathMap;
@@ -18,19 +18,24 @@ pub struct MessageGraph {
     index: HashMap<String, NodeIndex>,
     graph: Graph<String, ()>,
     messages: HashMap<String, DescriptorProto>,
+    closed_enums: HashSet<String>,
     boxed: PathMap<()>,
+    json_unknown_fields: PathMap<()>,
 }
 
 impl MessageGraph {
     pub(crate) fn new<'a>(
         files: impl Iterator<Item = &'a FileDescriptorProto>,
         boxed: PathMap<()>,
+        json_unknown_fields: PathMap<()>,
     ) -> MessageGraph {
         let mut msg_graph = MessageGraph {
             index: HashMap::new(),
             graph: Graph::new(),
             messages: HashMap::new(),
+            closed_enums: HashSet::new(),
             boxed,
+            json_unknown_fields,
         };
 
         for file in files {
@@ -42,6 +47,10 @@ impl MessageGraph {
             for msg in &file.message_type {
                 msg_graph.add_message(&package, msg);
             }
+            // The enums of proto2 files are closed, and those of proto3 files open.
+            if matches!(file.syntax.as_deref(), None | Some("proto2")) {
+                msg_graph.add_closed_enums(&package, &file.enum_type, &file.message_type);
+            }
         }
 
         msg_graph
@@ -81,6 +90,28 @@ impl MessageGraph {
         }
     }
 
+    fn add_closed_enums(
+        &mut self,
+        scope: &str,
+        enums: &[EnumDescriptorProto],
+        messages: &[DescriptorProto],
+    ) {
+        for enum_type in enums {
+            self.closed_enums
+                .insert(format!("{}.{}", scope, enum_type.name()));
+        }
+        for msg in messages {
+            let scope = format!("{}.{}", scope, msg.name());
+            self.add_closed_enums(&scope, &msg.enum_type, &msg.nested_type);
+        }
+    }
+
+    /// Returns `true` if the enum with the given fully-qualified name is closed, which is the case
+    /// for the enums of proto2 files.
+    pub fn is_closed_enum(&self, fq_enum_name: &str) -> bool {
+        self.closed_enums.contains(fq_enum_name)
+    }
+
     /// Try get a message descriptor from current message graph
     pub fn get_message(&self, message: &str) -> Option<&DescriptorProto> {
         self.messages.get(message)
@@ -103,11 +134,17 @@ impl MessageGraph {
     /// Returns `true` if this message can automatically derive Copy trait.
     pub fn can_message_derive_copy(&self, fq_message_name: &str) -> bool {
         assert_eq!(".", &fq_message_name[..1]);
-        self.get_message(fq_message_name)
-            .unwrap()
-            .field
-            .iter()
-            .all(|field| self.can_field_derive_copy(fq_message_name, field))
+        // The map of retained unknown JSON fields can not be copied.
+        self.json_unknown_fields
+            .get(fq_message_name)
+            .next()
+            .is_none()
+            && self
+                .get_message(fq_message_name)
+                .unwrap()
+                .field
+                .iter()
+                .all(|field| self.can_field_derive_copy(fq_message_name, field))
     }
 
     /// Returns `true` if the type of this field allows deriving the Copy trait.
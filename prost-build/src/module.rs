@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::ident::to_snake;
+use crate::ident::{to_snake_with, DefaultIdentPolicy, IdentPolicy};
 
 /// A Rust module path for a Protobuf package.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -25,11 +25,17 @@ impl Module {
     /// Constituent parts are automatically converted to snake case in order to follow
     /// Rust module naming conventions.
     pub fn from_protobuf_package_name(name: &str) -> Self {
+        Self::from_protobuf_package_name_with(name, &DefaultIdentPolicy)
+    }
+
+    /// Construct a module path from a Protobuf package name, converting the parts with the
+    /// given policy.
+    pub(crate) fn from_protobuf_package_name_with(name: &str, policy: &dyn IdentPolicy) -> Self {
         Self {
             components: name
                 .split('.')
                 .filter(|s| !s.is_empty())
-                .map(to_snake)
+                .map(|s| to_snake_with(policy, s))
                 .collect(),
         }
     }
@@ -76,18 +82,80 @@ impl Module {
     pub(crate) fn part(&self, idx: usize) -> &str {
         self.components[idx].as_str()
     }
+
+    /// Construct a module path from a Rust path relative to the root of the generated modules,
+    /// such as `foo::bar`.
+    ///
+    /// This is the inverse of [`Module::to_rust_path`]. The parts are not converted, so raw
+    /// identifiers such as `r#type` must be given as generated.
+    pub fn from_rust_path(path: &str) -> Self {
+        Self::from_parts(
+            path.split("::")
+                .map(str::trim)
+                .filter(|part| !part.is_empty()),
+        )
+    }
+
+    /// Format the module path as a Rust path relative to the root of the generated modules,
+    /// such as `foo::bar`.
+    ///
+    /// The path of the root module is empty.
+    pub fn to_rust_path(&self) -> String {
+        self.components.join("::")
+    }
+
+    /// The module containing this module, or `None` for the root module.
+    pub fn parent(&self) -> Option<Module> {
+        let (_, parent) = self.components.split_last()?;
+        Some(Self {
+            components: parent.to_vec(),
+        })
+    }
+
+    /// The module named `name` inside this module.
+    pub fn child(&self, name: impl Into<String>) -> Module {
+        let mut components = self.components.clone();
+        components.push(name.into());
+        Self { components }
+    }
 }
 
 impl fmt::Display for Module {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut parts = self.parts();
-        if let Some(first) = parts.next() {
-            f.write_str(first)?;
-        }
-        for part in parts {
-            f.write_str("::")?;
-            f.write_str(part)?;
-        }
-        Ok(())
+        f.write_str(&self.to_rust_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_path() {
+        let module = Module::from_protobuf_package_name("foo.bar_baz.type");
+        assert_eq!(module.to_rust_path(), "foo::bar_baz::r#type");
+        assert_eq!(module.to_string(), "foo::bar_baz::r#type");
+        assert_eq!(Module::from_rust_path(&module.to_rust_path()), module);
+        assert_eq!(
+            Module::from_rust_path("foo :: bar"),
+            Module::from_parts(["foo", "bar"])
+        );
+
+        let root = Module::from_rust_path("");
+        assert!(root.is_empty());
+        assert_eq!(root.to_rust_path(), "");
+    }
+
+    #[test]
+    fn test_navigation() {
+        let module = Module::from_protobuf_package_name("foo.bar");
+        let parent = module.parent().unwrap();
+        assert_eq!(parent, Module::from_protobuf_package_name("foo"));
+        assert_eq!(parent.child("bar"), module);
+        assert_eq!(
+            parent.parent(),
+            Some(Module::from_parts(Vec::<String>::new()))
+        );
+        assert_eq!(parent.parent().unwrap().parent(), None);
     }
 }
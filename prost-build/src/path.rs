@@ -3,13 +3,21 @@
 use std::iter;
 
 /// Maps a fully-qualified Protobuf path to a value using path matchers.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub(crate) struct PathMap<T> {
     // insertion order might actually matter (to avoid warning about legacy-derive-helpers)
     // see: https://doc.rust-lang.org/rustc/lints/listing/warn-by-default.html#legacy-derive-helpers
     pub(crate) matchers: Vec<(String, T)>,
 }
 
+impl<T> Default for PathMap<T> {
+    fn default() -> Self {
+        PathMap {
+            matchers: Vec::new(),
+        }
+    }
+}
+
 impl<T> PathMap<T> {
     /// Inserts a new matcher and associated value to the path map.
     pub(crate) fn insert(&mut self, matcher: String, value: T) {
@@ -44,6 +52,11 @@ impl<T> PathMap<T> {
         self.matchers.clear();
     }
 
+    /// Removes the matchers whose value does not satisfy the predicate.
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.matchers.retain(|(_, value)| f(value));
+    }
+
     /// Returns the first value found best matching the path
     /// See [sub_path_iter()] for paths test order
     fn find_best_matching(&self, full_path: &str) -> Option<&T> {
@@ -0,0 +1,270 @@
+//! Helpers to write `protoc` plugins.
+//!
+//! `protoc` runs a plugin named `protoc-gen-NAME` for each `--NAME_out` option it is given,
+//! writes an encoded [`CodeGeneratorRequest`] to its standard input, and reads an encoded
+//! [`CodeGeneratorResponse`] from its standard output. [`Plugin`] takes care of this exchange,
+//! of reporting errors to `protoc`, and of declaring the features the plugin supports, so that
+//! the plugin only has to turn a request into files.
+//!
+//! ## Example
+//!
+//! A `protoc-gen-prost` plugin generating a file for each package with `prost-build`:
+//!
+//! ```rust,no_run
+//! use prost_build::plugin::{self, Plugin};
+//!
+//! fn main() -> std::io::Result<()> {
+//!     Plugin::new()
+//!         .proto3_optional(true)
+//!         .run(|request| plugin::generate(&mut prost_build::Config::new(), request))
+//! }
+//! ```
+//!
+//! It is then run by `protoc` with:
+//!
+//! ```bash
+//! $ protoc --plugin=protoc-gen-prost=path/to/protoc-gen-prost --prost_out=src/gen items.proto
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+use prost::encoding::int32;
+use prost::Message;
+use prost_types::compiler::code_generator_response::File;
+use prost_types::compiler::{CodeGeneratorRequest, CodeGeneratorResponse, SupportedFeatures};
+
+use crate::Config;
+
+/// The tags of the `minimum_edition` and `maximum_edition` fields of `CodeGeneratorResponse`.
+const MINIMUM_EDITION_TAG: u32 = 3;
+const MAXIMUM_EDITION_TAG: u32 = 4;
+
+/// The `protoc` side of a plugin.
+///
+/// By default, a plugin declares no optional features, and `protoc` refuses to run it on files
+/// which need them.
+#[derive(Clone, Debug, Default)]
+pub struct Plugin {
+    proto3_optional: bool,
+    editions: Option<(i32, i32)>,
+}
+
+impl Plugin {
+    /// Creates a plugin which supports no optional features.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares whether the plugin supports `optional` fields in `proto3` files.
+    ///
+    /// `prost-build` supports them, and generates them as `Option` fields.
+    pub fn proto3_optional(&mut self, enabled: bool) -> &mut Self {
+        self.proto3_optional = enabled;
+        self
+    }
+
+    /// Declares that the plugin supports files using editions from `minimum` to `maximum`.
+    ///
+    /// Editions are given as values of the `google.protobuf.Edition` enum, for example
+    /// `1000` for edition 2023.
+    pub fn editions(&mut self, minimum: i32, maximum: i32) -> &mut Self {
+        self.editions = Some((minimum, maximum));
+        self
+    }
+
+    /// Reads the request from the standard input, and writes the response to the standard
+    /// output.
+    ///
+    /// See [`Plugin::run_with`].
+    pub fn run<F, E>(&self, generate: F) -> io::Result<()>
+    where
+        F: FnOnce(&CodeGeneratorRequest) -> Result<Vec<File>, E>,
+        E: fmt::Display,
+    {
+        self.run_with(io::stdin().lock(), io::stdout().lock(), generate)
+    }
+
+    /// Reads the request from `input`, generates files with `generate`, and writes the response
+    /// to `output`.
+    ///
+    /// An error returned by `generate`, such as an unsupported construct in a `.proto` file, is
+    /// reported in the response, and `protoc` prints it and fails. This function only fails if
+    /// the request can not be read or decoded, or if the response can not be written, in which
+    /// case the plugin should exit with a non-zero status.
+    pub fn run_with<R, W, F, E>(&self, mut input: R, mut output: W, generate: F) -> io::Result<()>
+    where
+        R: Read,
+        W: Write,
+        F: FnOnce(&CodeGeneratorRequest) -> Result<Vec<File>, E>,
+        E: fmt::Display,
+    {
+        let mut buf = Vec::new();
+        input.read_to_end(&mut buf)?;
+        let request = CodeGeneratorRequest::decode(buf.as_slice()).map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid code generator request: {}", error),
+            )
+        })?;
+
+        output.write_all(&self.encode_response(generate(&request)))?;
+        output.flush()
+    }
+
+    /// Encodes the response holding either the generated files or the error message.
+    fn encode_response<E: fmt::Display>(&self, result: Result<Vec<File>, E>) -> Vec<u8> {
+        let mut features = SupportedFeatures::NONE;
+        if self.proto3_optional {
+            features |= SupportedFeatures::PROTO3_OPTIONAL;
+        }
+        if self.editions.is_some() {
+            features |= SupportedFeatures::SUPPORTS_EDITIONS;
+        }
+
+        let mut response = CodeGeneratorResponse::default();
+        response.set_features(features);
+        match result {
+            Ok(files) => response.file = files,
+            Err(error) => response.error = Some(error.to_string()),
+        }
+
+        let mut buf = response.encode_to_vec();
+        // The edition range is not part of the vendored `plugin.proto` yet, so it is appended
+        // as unknown fields.
+        if let Some((minimum, maximum)) = self.editions {
+            int32::encode(MINIMUM_EDITION_TAG, &minimum, &mut buf);
+            int32::encode(MAXIMUM_EDITION_TAG, &maximum, &mut buf);
+        }
+        buf
+    }
+}
+
+/// Generates the files requested by `protoc` with `config`.
+///
+/// One file is generated for each package, named as with [`Config::compile_protos`]. Only the
+/// files listed in `file_to_generate` are generated: the other files of the request are their
+/// dependencies.
+pub fn generate(config: &mut Config, request: &CodeGeneratorRequest) -> io::Result<Vec<File>> {
+    let requests = request
+        .proto_file
+        .iter()
+        .filter(|file| {
+            request
+                .file_to_generate
+                .iter()
+                .any(|name| name == file.name())
+        })
+        .map(|file| {
+            (
+                config
+                    .merged_packages
+                    .module(file.package(), config.ident_policy.as_ref()),
+                file.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let file_names = requests
+        .iter()
+        .map(|(module, _)| {
+            (
+                module.clone(),
+                module.to_file_name_or(&config.default_package_filename),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut files = config
+        .generate(requests)?
+        .into_iter()
+        .map(|(module, content)| File {
+            name: Some(file_names[&module].clone()),
+            content: Some(content),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost_types::FileDescriptorProto;
+
+    fn request(files: &[(&str, &str)], file_to_generate: &[&str]) -> Vec<u8> {
+        CodeGeneratorRequest {
+            file_to_generate: file_to_generate.iter().map(|f| f.to_string()).collect(),
+            proto_file: files
+                .iter()
+                .map(|(name, package)| FileDescriptorProto {
+                    name: Some(name.to_string()),
+                    package: Some(package.to_string()),
+                    message_type: vec![prost_types::DescriptorProto {
+                        name: Some("Msg".to_string()),
+                        ..Default::default()
+                    }],
+                    syntax: Some("proto3".to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_run() {
+        let input = request(
+            &[("dep.proto", "dep"), ("foo.proto", "foo.bar")],
+            &["foo.proto"],
+        );
+        let mut output = Vec::new();
+        Plugin::new()
+            .proto3_optional(true)
+            .run_with(input.as_slice(), &mut output, |request| {
+                generate(&mut Config::new(), request)
+            })
+            .unwrap();
+
+        let response = CodeGeneratorResponse::decode(output.as_slice()).unwrap();
+        assert_eq!(response.error, None);
+        assert_eq!(response.supported_features, Some(1));
+        assert_eq!(response.file.len(), 1);
+        assert_eq!(response.file[0].name(), "foo.bar.rs");
+        assert!(response.file[0].content().contains("pub struct Msg"));
+    }
+
+    #[test]
+    fn test_run_error() {
+        let input = request(&[], &[]);
+        let mut output = Vec::new();
+        Plugin::new()
+            .editions(998, 1000)
+            .run_with(input.as_slice(), &mut output, |_| {
+                Err::<Vec<File>, _>("unsupported option")
+            })
+            .unwrap();
+
+        let response = CodeGeneratorResponse::decode(output.as_slice()).unwrap();
+        assert_eq!(response.error(), "unsupported option");
+        assert_eq!(response.supported_features, Some(2));
+        // The edition range follows the known fields.
+        let mut edition_range = Vec::new();
+        int32::encode(MINIMUM_EDITION_TAG, &998, &mut edition_range);
+        int32::encode(MAXIMUM_EDITION_TAG, &1000, &mut edition_range);
+        assert!(output.ends_with(&edition_range));
+    }
+
+    #[test]
+    fn test_run_invalid_request() {
+        let mut output = Vec::new();
+        let error = Plugin::new()
+            .run_with(&[0xFF][..], &mut output, |_| Ok::<_, String>(Vec::new()))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(output.is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+//! Diagnostics of how the `protoc` executable is found.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ProtocVersion;
+
+/// Where a `protoc` candidate was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtocSource {
+    /// The `PROTOC` environment variable.
+    Env,
+    /// A directory of the `PATH` environment variable.
+    Path,
+}
+
+impl fmt::Display for ProtocSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocSource::Env => f.write_str("PROTOC"),
+            ProtocSource::Path => f.write_str("PATH"),
+        }
+    }
+}
+
+/// Why a `protoc` candidate can not be used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProtocRejection {
+    /// There is no file at the path.
+    NotFound,
+    /// The file is not executable.
+    NotExecutable,
+    /// The file is an ELF executable whose interpreter, the dynamic loader, doesn't exist, such
+    /// as a glibc build on a musl system.
+    MissingInterpreter(PathBuf),
+    /// Running `protoc --version` failed.
+    Failed(String),
+    /// `protoc --version` printed an unrecognized version.
+    UnknownVersion(String),
+}
+
+impl fmt::Display for ProtocRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocRejection::NotFound => f.write_str("no such file"),
+            ProtocRejection::NotExecutable => f.write_str("not executable"),
+            ProtocRejection::MissingInterpreter(interpreter) => write!(
+                f,
+                "its ELF interpreter {} is missing",
+                interpreter.display()
+            ),
+            ProtocRejection::Failed(error) => write!(f, "`protoc --version` failed: {}", error),
+            ProtocRejection::UnknownVersion(output) => {
+                write!(f, "unknown `protoc --version` output: {}", output)
+            }
+        }
+    }
+}
+
+/// A `protoc` executable considered by [`diagnose_protoc()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtocCandidate {
+    /// The path of the executable.
+    pub path: PathBuf,
+    /// Where the path was found.
+    pub source: ProtocSource,
+    /// The version reported by the executable, or why it can not be used.
+    pub status: Result<ProtocVersion, ProtocRejection>,
+}
+
+/// How `protoc` is found in the current environment, as returned by [`diagnose_protoc()`].
+///
+/// The [`Display`](fmt::Display) implementation lists each step of the resolution, and is part
+/// of [`error_message_protoc_not_found()`](crate::error_message_protoc_not_found).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtocReport {
+    /// The value of the `PROTOC` environment variable, if set.
+    pub protoc_env: Option<PathBuf>,
+    /// The `PROTOC` executable, if set, then each `protoc` found in the `PATH`, in order.
+    pub candidates: Vec<ProtocCandidate>,
+}
+
+impl ProtocReport {
+    /// Returns the candidate run by `prost-build`, unless configured with
+    /// [`Config::protoc_executable()`](crate::Config::protoc_executable): the `PROTOC` executable
+    /// if set, and otherwise the first `protoc` of the `PATH`.
+    ///
+    /// The candidate may have been rejected.
+    pub fn selected(&self) -> Option<&ProtocCandidate> {
+        self.candidates.first()
+    }
+}
+
+impl fmt::Display for ProtocReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "protoc discovery:")?;
+        match &self.protoc_env {
+            Some(path) => writeln!(f, "  PROTOC: {}", path.display())?,
+            None => writeln!(f, "  PROTOC: not set")?,
+        }
+        if !self
+            .candidates
+            .iter()
+            .any(|candidate| candidate.source == ProtocSource::Path)
+        {
+            writeln!(f, "  PATH: no protoc found")?;
+        }
+        for candidate in &self.candidates {
+            write!(f, "  {} ({}): ", candidate.path.display(), candidate.source)?;
+            match &candidate.status {
+                Ok(version) => writeln!(f, "protoc {}", version)?,
+                Err(rejection) => writeln!(f, "rejected, {}", rejection)?,
+            }
+        }
+        write!(
+            f,
+            "  vendored: prost-build doesn't bundle protoc, see the `protobuf-src` crate"
+        )
+    }
+}
+
+/// Returns how `protoc` is found in the current environment, checking each candidate executable.
+///
+/// This runs `protoc --version` for each candidate, and is meant to investigate build failures.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// let report = prost_build::diagnose_protoc();
+/// println!("cargo:warning={}", report);
+/// ```
+pub fn diagnose_protoc() -> ProtocReport {
+    let protoc_env = env::var_os("PROTOC").map(PathBuf::from);
+    let mut candidates = Vec::new();
+    if let Some(path) = &protoc_env {
+        candidates.push(check_candidate(path.clone(), ProtocSource::Env));
+    }
+    if let Some(paths) = env::var_os("PATH") {
+        let name = format!("protoc{}", env::consts::EXE_SUFFIX);
+        candidates.extend(
+            env::split_paths(&paths)
+                .map(|dir| dir.join(&name))
+                .filter(|path| path.is_file())
+                .map(|path| check_candidate(path, ProtocSource::Path)),
+        );
+    }
+    ProtocReport {
+        protoc_env,
+        candidates,
+    }
+}
+
+fn check_candidate(path: PathBuf, source: ProtocSource) -> ProtocCandidate {
+    let status = check_executable(&path);
+    ProtocCandidate {
+        path,
+        source,
+        status,
+    }
+}
+
+fn check_executable(path: &Path) -> Result<ProtocVersion, ProtocRejection> {
+    let metadata = fs::metadata(path).map_err(|_| ProtocRejection::NotFound)?;
+    if !metadata.is_file() {
+        return Err(ProtocRejection::NotFound);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(ProtocRejection::NotExecutable);
+        }
+    }
+    if let Some(interpreter) = fs::read(path)
+        .ok()
+        .and_then(|bytes| elf_interpreter(&bytes))
+    {
+        if !interpreter.exists() {
+            return Err(ProtocRejection::MissingInterpreter(interpreter));
+        }
+    }
+
+    let output = Command::new(path)
+        .arg("--version")
+        .output()
+        .map_err(|err| ProtocRejection::Failed(err.to_string()))?;
+    if !output.status.success() {
+        return Err(ProtocRejection::Failed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .parse()
+        .map_err(|_| ProtocRejection::UnknownVersion(stdout.trim().to_string()))
+}
+
+/// Returns the interpreter requested by the `PT_INTERP` program header of an ELF executable.
+fn elf_interpreter(bytes: &[u8]) -> Option<PathBuf> {
+    const PT_INTERP: u64 = 3;
+
+    if bytes.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    let is_64 = match bytes.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let is_le = match bytes.get(5)? {
+        1 => true,
+        2 => false,
+        _ => return None,
+    };
+    let read = |offset: usize, len: usize| -> Option<u64> {
+        let field = bytes.get(offset..offset.checked_add(len)?)?;
+        let mut value = 0u64;
+        for i in 0..len {
+            let byte = if is_le { field[len - 1 - i] } else { field[i] };
+            value = value << 8 | u64::from(byte);
+        }
+        Some(value)
+    };
+
+    let (phoff, phentsize, phnum) = if is_64 {
+        (read(0x20, 8)?, read(0x36, 2)?, read(0x38, 2)?)
+    } else {
+        (read(0x1c, 4)?, read(0x2a, 2)?, read(0x2c, 2)?)
+    };
+    for i in 0..phnum {
+        let header = usize::try_from(phoff.checked_add(i * phentsize)?).ok()?;
+        if read(header, 4)? != PT_INTERP {
+            continue;
+        }
+        let (offset, size) = if is_64 {
+            (read(header + 0x08, 8)?, read(header + 0x20, 8)?)
+        } else {
+            (read(header + 0x04, 4)?, read(header + 0x10, 4)?)
+        };
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+        let interpreter = bytes.get(start..end)?;
+        let interpreter = interpreter.split(|&b| b == 0).next()?;
+        return Some(PathBuf::from(String::from_utf8_lossy(interpreter).as_ref()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a little-endian 64-bit ELF executable requesting the interpreter.
+    fn elf_64(interpreter: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0; 0x40 + 0x38];
+        bytes[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        bytes[0x20..0x28].copy_from_slice(&0x40u64.to_le_bytes());
+        bytes[0x36..0x38].copy_from_slice(&0x38u16.to_le_bytes());
+        bytes[0x38..0x3a].copy_from_slice(&1u16.to_le_bytes());
+        let header = 0x40;
+        let interpreter_offset = bytes.len() as u64;
+        bytes[header..header + 4].copy_from_slice(&3u32.to_le_bytes());
+        bytes[header + 0x08..header + 0x10].copy_from_slice(&interpreter_offset.to_le_bytes());
+        bytes[header + 0x20..header + 0x28]
+            .copy_from_slice(&(interpreter.len() as u64 + 1).to_le_bytes());
+        bytes.extend_from_slice(interpreter);
+        bytes.push(0);
+        bytes
+    }
+
+    #[test]
+    fn test_elf_interpreter() {
+        assert_eq!(
+            elf_interpreter(&elf_64(b"/lib/ld-musl-x86_64.so.1")),
+            Some(PathBuf::from("/lib/ld-musl-x86_64.so.1"))
+        );
+        assert_eq!(elf_interpreter(b"#!/bin/sh\n"), None);
+        // Truncated headers are ignored.
+        assert_eq!(elf_interpreter(&elf_64(b"/lib/ld.so")[..0x50]), None);
+    }
+
+    #[test]
+    fn test_rejected_candidates() {
+        let candidate = check_candidate("path-does-not-exist".into(), ProtocSource::Env);
+        assert_eq!(candidate.status, Err(ProtocRejection::NotFound));
+        #[cfg(unix)]
+        assert_eq!(
+            check_executable(Path::new("src/lib.rs")),
+            Err(ProtocRejection::NotExecutable)
+        );
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = ProtocReport {
+            protoc_env: Some("/opt/protoc".into()),
+            candidates: vec![
+                ProtocCandidate {
+                    path: "/opt/protoc".into(),
+                    source: ProtocSource::Env,
+                    status: Err(ProtocRejection::MissingInterpreter(
+                        "/lib64/ld-linux-x86-64.so.2".into(),
+                    )),
+                },
+                ProtocCandidate {
+                    path: "/usr/bin/protoc".into(),
+                    source: ProtocSource::Path,
+                    status: Ok("libprotoc 3.21.12".parse().unwrap()),
+                },
+            ],
+        };
+        assert_eq!(report.selected(), report.candidates.first());
+        assert_eq!(
+            report.to_string(),
+            "protoc discovery:\n  \
+             PROTOC: /opt/protoc\n  \
+             /opt/protoc (PROTOC): rejected, its ELF interpreter /lib64/ld-linux-x86-64.so.2 \
+             is missing\n  \
+             /usr/bin/protoc (PATH): protoc 3.21.12\n  \
+             vendored: prost-build doesn't bundle protoc, see the `protobuf-src` crate"
+        );
+    }
+}
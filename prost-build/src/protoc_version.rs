@@ -0,0 +1,155 @@
+//! Versions of the `protoc` executable.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The version of a `protoc` executable, as returned by [`Config::protoc_version()`].
+///
+/// Releases before 22 are numbered `3.x.y` (for example `3.21.12`), later releases `x.y` (for
+/// example `27.1`). Both are kept as reported by `protoc --version`.
+///
+/// [`Config::protoc_version()`]: crate::Config::protoc_version
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProtocVersion {
+    /// The major version.
+    pub major: u32,
+    /// The minor version.
+    pub minor: u32,
+    /// The patch version, if reported.
+    pub patch: Option<u32>,
+}
+
+impl ProtocVersion {
+    /// The release number, `21` for `3.21.12` and `27` for `27.1`.
+    fn release(&self) -> u32 {
+        if self.major == 3 {
+            self.minor
+        } else {
+            self.major
+        }
+    }
+
+    /// Returns `true` if `optional` fields are supported in `proto3` files.
+    ///
+    /// Releases before 3.15 require the `--experimental_allow_proto3_optional` argument, see
+    /// [`Self::supports_proto3_optional_by_default()`].
+    pub fn supports_proto3_optional(&self) -> bool {
+        self.major > 3 || (self.major == 3 && self.minor >= 12)
+    }
+
+    /// Returns `true` if `optional` fields are supported in `proto3` files without passing
+    /// `--experimental_allow_proto3_optional`.
+    pub fn supports_proto3_optional_by_default(&self) -> bool {
+        self.major > 3 || (self.major == 3 && self.minor >= 15)
+    }
+
+    /// Returns `true` if files using `edition` instead of `syntax` are supported.
+    pub fn supports_editions(&self) -> bool {
+        self.release() >= 27
+    }
+}
+
+impl fmt::Display for ProtocVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if let Some(patch) = self.patch {
+            write!(f, ".{}", patch)?;
+        }
+        Ok(())
+    }
+}
+
+/// An error parsing a [`ProtocVersion`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseProtocVersionError(());
+
+impl fmt::Display for ParseProtocVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid protoc version")
+    }
+}
+
+impl std::error::Error for ParseProtocVersionError {}
+
+impl FromStr for ProtocVersion {
+    type Err = ParseProtocVersionError;
+
+    /// Parses a version such as `3.21.12`, optionally prefixed by `libprotoc` as printed by
+    /// `protoc --version`. Pre-release suffixes such as `-rc1` are ignored.
+    fn from_str(s: &str) -> Result<ProtocVersion, ParseProtocVersionError> {
+        let s = s.trim();
+        let s = s.strip_prefix("libprotoc ").unwrap_or(s);
+        let s = s
+            .split_once('-')
+            .map_or(s, |(version, _pre_release)| version);
+
+        let mut parts = s.split('.').map(|part| part.parse::<u32>());
+        let (Some(Ok(major)), Some(Ok(minor))) = (parts.next(), parts.next()) else {
+            return Err(ParseProtocVersionError(()));
+        };
+        let patch = match parts.next() {
+            None => None,
+            Some(Ok(patch)) => Some(patch),
+            Some(Err(_)) => return Err(ParseProtocVersionError(())),
+        };
+        if parts.next().is_some() {
+            return Err(ParseProtocVersionError(()));
+        }
+
+        Ok(ProtocVersion {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> ProtocVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            version("libprotoc 3.21.12\n"),
+            ProtocVersion {
+                major: 3,
+                minor: 21,
+                patch: Some(12)
+            }
+        );
+        assert_eq!(
+            version("libprotoc 27.1"),
+            ProtocVersion {
+                major: 27,
+                minor: 1,
+                patch: None
+            }
+        );
+        assert_eq!(version("libprotoc 3.20.0-rc2"), version("3.20.0"));
+        assert_eq!(version("28.0").to_string(), "28.0");
+        assert_eq!(version("3.6.1").to_string(), "3.6.1");
+
+        assert!("libprotoc".parse::<ProtocVersion>().is_err());
+        assert!("libprotoc 3".parse::<ProtocVersion>().is_err());
+        assert!("libprotoc 3.x".parse::<ProtocVersion>().is_err());
+        assert!("3.21.12.1".parse::<ProtocVersion>().is_err());
+    }
+
+    #[test]
+    fn features() {
+        assert!(!version("3.11.4").supports_proto3_optional());
+        assert!(version("3.12.0").supports_proto3_optional());
+        assert!(!version("3.12.0").supports_proto3_optional_by_default());
+        assert!(version("3.15.0").supports_proto3_optional_by_default());
+        assert!(version("25.1").supports_proto3_optional_by_default());
+
+        assert!(!version("3.21.12").supports_editions());
+        assert!(!version("26.1").supports_editions());
+        assert!(version("27.0").supports_editions());
+    }
+}
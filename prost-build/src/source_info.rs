@@ -0,0 +1,110 @@
+//! The source code info of file descriptors, which locates their items in the `.proto` files.
+//!
+//! `SourceCodeInfo` and the `source_code_info` field of `FileDescriptorProto` are removed by the
+//! `no-source-code-info` feature of `prost-types`, which may be enabled by any crate of the
+//! dependency graph. The locations are therefore decoded from the encoding of the file
+//! descriptor, and are missing if the feature removed them.
+
+use prost::encoding::decode_varint;
+use prost::{FieldIter, Message, RawField};
+use prost_types::FileDescriptorProto;
+
+/// The field number of `source_code_info` in `FileDescriptorProto`.
+const SOURCE_CODE_INFO: u32 = 9;
+
+/// The field number of `location` in `SourceCodeInfo`.
+const LOCATION: u32 = 1;
+
+/// The location of an item of a `.proto` file, with its comments.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Location {
+    pub(crate) path: Vec<i32>,
+    pub(crate) span: Vec<i32>,
+    pub(crate) leading_comments: Option<String>,
+    pub(crate) trailing_comments: Option<String>,
+    pub(crate) leading_detached_comments: Vec<String>,
+}
+
+impl Location {
+    /// Returns the locations of the source code info of `file`, if it has any.
+    pub(crate) fn of_file(file: &FileDescriptorProto) -> Option<Vec<Location>> {
+        Location::of_encoded_file(&file.encode_to_vec())
+    }
+
+    fn of_encoded_file(buf: &[u8]) -> Option<Vec<Location>> {
+        let source_code_info = FieldIter::new(buf)
+            .flatten()
+            .filter(|field| field.tag == SOURCE_CODE_INFO)
+            .last()?;
+        Some(
+            source_code_info
+                .nested()
+                .flatten()
+                .filter(|field| field.tag == LOCATION)
+                .map(Location::decode)
+                .collect(),
+        )
+    }
+
+    fn decode(field: RawField<'_>) -> Location {
+        let string = |value: &[u8]| String::from_utf8_lossy(value).into_owned();
+        let mut location = Location::default();
+        for field in field.nested().flatten() {
+            match field.tag {
+                // `path` and `span` are packed, but may also be encoded unpacked.
+                1 => decode_int32s(field.value, &mut location.path),
+                2 => decode_int32s(field.value, &mut location.span),
+                3 => location.leading_comments = Some(string(field.value)),
+                4 => location.trailing_comments = Some(string(field.value)),
+                6 => location.leading_detached_comments.push(string(field.value)),
+                _ => (),
+            }
+        }
+        location
+    }
+}
+
+/// Decodes the values of a packed `int32` field, or the value of an unpacked one.
+fn decode_int32s(mut value: &[u8], values: &mut Vec<i32>) {
+    while let Ok(n) = decode_varint(&mut value) {
+        values.push(n as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of_encoded_file() {
+        // A `source_code_info` with a location of path `[4, 0]` with a span and comments, and a
+        // location of path `[4, 1]` encoded unpacked, with a trailing comment.
+        let mut encoded = vec![0x4a, 0x1d];
+        encoded.extend_from_slice(&[
+            0x0a, 0x12, 0x0a, 0x02, 0x04, 0x00, 0x12, 0x03, 0x02, 0x00, 0x0b,
+        ]);
+        encoded.extend_from_slice(&[0x1a, 0x02, b' ', b'a', 0x32, 0x03, b' ', b'b', b'\n']);
+        encoded.extend_from_slice(&[0x0a, 0x07, 0x08, 0x04, 0x08, 0x01, 0x22, 0x01, b'c']);
+
+        assert_eq!(
+            Location::of_encoded_file(&encoded),
+            Some(vec![
+                Location {
+                    path: vec![4, 0],
+                    span: vec![2, 0, 11],
+                    leading_comments: Some(" a".to_string()),
+                    trailing_comments: None,
+                    leading_detached_comments: vec![" b\n".to_string()],
+                },
+                Location {
+                    path: vec![4, 1],
+                    span: Vec::new(),
+                    leading_comments: None,
+                    trailing_comments: Some("c".to_string()),
+                    leading_detached_comments: Vec::new(),
+                },
+            ])
+        );
+        assert_eq!(Location::of_file(&FileDescriptorProto::default()), None);
+    }
+}
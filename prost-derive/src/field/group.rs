@@ -3,12 +3,12 @@ use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 use syn::Meta;
 
-use crate::field::{set_bool, set_option, tag_attr, word_attr, Label};
+use crate::field::{message_field_values, set_bool, set_option, tag_attr, word_attr, Label, Tag};
 
 #[derive(Clone)]
 pub struct Field {
     pub label: Label,
-    pub tag: u32,
+    pub tag: Tag,
 }
 
 impl Field {
@@ -45,7 +45,7 @@ impl Field {
             );
         }
 
-        let tag = match tag.or(inferred_tag) {
+        let tag = match tag.or(inferred_tag.map(Tag::Number)) {
             Some(tag) => tag,
             None => bail!("group field is missing a tag attribute"),
         };
@@ -72,7 +72,7 @@ impl Field {
     }
 
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
-        let tag = self.tag;
+        let tag = &self.tag;
         match self.label {
             Label::Optional => quote! {
                 if let Some(ref msg) = #ident {
@@ -111,7 +111,7 @@ impl Field {
     }
 
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
-        let tag = self.tag;
+        let tag = &self.tag;
         match self.label {
             Label::Optional => quote! {
                 #ident.as_ref().map_or(0, |msg| ::prost::encoding::group::encoded_len(#tag, msg))
@@ -125,6 +125,23 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to whether the fields `a` and `b` are equal, with
+    /// `NaN` floats equal to each other.
+    pub fn eq_semantically(&self, a: TokenStream, b: TokenStream) -> TokenStream {
+        let eq = quote!(::prost::encoding::semantic_eq::message);
+        match self.label {
+            Label::Optional => quote!(::prost::encoding::semantic_eq::optional(&#a, &#b, #eq)),
+            Label::Required => quote!(#eq(&#a, &#b)),
+            Label::Repeated => quote!(::prost::encoding::semantic_eq::repeated(&#a, &#b, #eq)),
+        }
+    }
+
+    /// Returns statements which push the field to the `fields` vector of
+    /// `MessageFields::field_values`, with its description `META`, if it holds a value.
+    pub fn field_values(&self, ident: TokenStream) -> TokenStream {
+        message_field_values(&self.label, ident)
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
@@ -4,12 +4,14 @@ use quote::quote;
 use syn::punctuated::Punctuated;
 use syn::{Expr, ExprLit, Ident, Lit, Meta, MetaNameValue, Token};
 
-use crate::field::{scalar, set_option, tag_attr};
+use crate::field::{scalar, set_bool, set_option, tag_attr, word_attr, Tag};
 
 #[derive(Clone, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum MapTy {
     HashMap,
     BTreeMap,
+    IndexMap,
 }
 
 impl MapTy {
@@ -17,6 +19,7 @@ impl MapTy {
         match s {
             "map" | "hash_map" => Some(MapTy::HashMap),
             "btree_map" => Some(MapTy::BTreeMap),
+            "index_map" => Some(MapTy::IndexMap),
             _ => None,
         }
     }
@@ -25,13 +28,15 @@ impl MapTy {
         match *self {
             MapTy::HashMap => Ident::new("hash_map", Span::call_site()),
             MapTy::BTreeMap => Ident::new("btree_map", Span::call_site()),
+            MapTy::IndexMap => Ident::new("index_map", Span::call_site()),
         }
     }
 
-    fn lib(&self) -> TokenStream {
+    fn rust_type(&self) -> TokenStream {
         match self {
-            MapTy::HashMap => quote! { std },
-            MapTy::BTreeMap => quote! { prost::alloc },
+            MapTy::HashMap => quote! { ::std::collections::HashMap },
+            MapTy::BTreeMap => quote! { ::prost::alloc::collections::BTreeMap },
+            MapTy::IndexMap => quote! { ::prost::indexmap::IndexMap },
         }
     }
 }
@@ -41,7 +46,11 @@ fn fake_scalar(ty: scalar::Ty) -> scalar::Field {
     scalar::Field {
         ty,
         kind,
-        tag: 0, // Not used here
+        tag: Tag::Number(0), // Not used here
+        newtype: false,
+        max_len: None,
+        closed: false,
+        presence: None,
     }
 }
 
@@ -50,17 +59,25 @@ pub struct Field {
     pub map_ty: MapTy,
     pub key_ty: scalar::Ty,
     pub value_ty: ValueTy,
-    pub tag: u32,
+    pub tag: Tag,
+    /// Whether default values are encoded in the map entries rather than omitted.
+    pub encode_default_values: bool,
 }
 
 impl Field {
     pub fn new(attrs: &[Meta], inferred_tag: Option<u32>) -> Result<Option<Field>, Error> {
         let mut types = None;
         let mut tag = None;
+        let mut encode_default_values = false;
 
         for attr in attrs {
             if let Some(t) = tag_attr(attr)? {
                 set_option(&mut tag, t, "duplicate tag attributes")?;
+            } else if word_attr("encode_default_values", attr) {
+                set_bool(
+                    &mut encode_default_values,
+                    "duplicate encode_default_values attribute",
+                )?;
             } else if let Some(map_ty) = attr
                 .path()
                 .get_ident()
@@ -108,12 +125,13 @@ impl Field {
             }
         }
 
-        Ok(match (types, tag.or(inferred_tag)) {
+        Ok(match (types, tag.or(inferred_tag.map(Tag::Number))) {
             (Some((map_ty, key_ty, value_ty)), Some(tag)) => Some(Field {
                 map_ty,
                 key_ty,
                 value_ty,
                 tag,
+                encode_default_values,
             }),
             _ => None,
         })
@@ -125,15 +143,15 @@ impl Field {
 
     /// Returns a statement which encodes the map field.
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
-        let tag = self.tag;
+        let tag = &self.tag;
         let key_mod = self.key_ty.module();
         let ke = quote!(::prost::encoding::#key_mod::encode);
         let kl = quote!(::prost::encoding::#key_mod::encoded_len);
         let module = self.map_ty.module();
-        match &self.value_ty {
-            ValueTy::Scalar(scalar::Ty::Enumeration(ty)) => {
+        let (ve, vl) = match &self.value_ty {
+            ValueTy::Scalar(scalar::Ty::Enumeration(ty)) if !self.encode_default_values => {
                 let default = quote!(#ty::default() as i32);
-                quote! {
+                return quote! {
                     ::prost::encoding::#module::encode_with_default(
                         #ke,
                         #kl,
@@ -144,35 +162,35 @@ impl Field {
                         &#ident,
                         buf,
                     );
-                }
+                };
             }
             ValueTy::Scalar(value_ty) => {
                 let val_mod = value_ty.module();
-                let ve = quote!(::prost::encoding::#val_mod::encode);
-                let vl = quote!(::prost::encoding::#val_mod::encoded_len);
-                quote! {
-                    ::prost::encoding::#module::encode(
-                        #ke,
-                        #kl,
-                        #ve,
-                        #vl,
-                        #tag,
-                        &#ident,
-                        buf,
-                    );
-                }
+                (
+                    quote!(::prost::encoding::#val_mod::encode),
+                    quote!(::prost::encoding::#val_mod::encoded_len),
+                )
             }
-            ValueTy::Message => quote! {
-                ::prost::encoding::#module::encode(
-                    #ke,
-                    #kl,
-                    ::prost::encoding::message::encode,
-                    ::prost::encoding::message::encoded_len,
-                    #tag,
-                    &#ident,
-                    buf,
-                );
-            },
+            ValueTy::Message => (
+                quote!(::prost::encoding::message::encode),
+                quote!(::prost::encoding::message::encoded_len),
+            ),
+        };
+        let encode = if self.encode_default_values {
+            quote!(encode_all_values)
+        } else {
+            quote!(encode)
+        };
+        quote! {
+            ::prost::encoding::#module::#encode(
+                #ke,
+                #kl,
+                #ve,
+                #vl,
+                #tag,
+                &#ident,
+                buf,
+            );
         }
     }
 
@@ -215,14 +233,14 @@ impl Field {
 
     /// Returns an expression which evaluates to the encoded length of the map.
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
-        let tag = self.tag;
+        let tag = &self.tag;
         let key_mod = self.key_ty.module();
         let kl = quote!(::prost::encoding::#key_mod::encoded_len);
         let module = self.map_ty.module();
-        match &self.value_ty {
-            ValueTy::Scalar(scalar::Ty::Enumeration(ty)) => {
+        let vl = match &self.value_ty {
+            ValueTy::Scalar(scalar::Ty::Enumeration(ty)) if !self.encode_default_values => {
                 let default = quote!(#ty::default() as i32);
-                quote! {
+                return quote! {
                     ::prost::encoding::#module::encoded_len_with_default(
                         #kl,
                         ::prost::encoding::int32::encoded_len,
@@ -230,22 +248,33 @@ impl Field {
                         #tag,
                         &#ident,
                     )
-                }
+                };
             }
             ValueTy::Scalar(value_ty) => {
                 let val_mod = value_ty.module();
-                let vl = quote!(::prost::encoding::#val_mod::encoded_len);
-                quote!(::prost::encoding::#module::encoded_len(#kl, #vl, #tag, &#ident))
+                quote!(::prost::encoding::#val_mod::encoded_len)
             }
-            ValueTy::Message => quote! {
-                ::prost::encoding::#module::encoded_len(
-                    #kl,
-                    ::prost::encoding::message::encoded_len,
-                    #tag,
-                    &#ident,
-                )
-            },
-        }
+            ValueTy::Message => quote!(::prost::encoding::message::encoded_len),
+        };
+        let encoded_len = if self.encode_default_values {
+            quote!(encoded_len_all_values)
+        } else {
+            quote!(encoded_len)
+        };
+        quote!(::prost::encoding::#module::#encoded_len(#kl, #vl, #tag, &#ident))
+    }
+
+    /// Returns an expression which evaluates to whether the fields `a` and `b` are equal, with
+    /// `NaN` floats equal to each other. The order of the entries is ignored.
+    pub fn eq_semantically(&self, a: TokenStream, b: TokenStream) -> TokenStream {
+        let val_eq = match self.value_ty {
+            ValueTy::Scalar(scalar::Ty::Float) => quote!(::prost::encoding::semantic_eq::float),
+            ValueTy::Scalar(scalar::Ty::Double) => quote!(::prost::encoding::semantic_eq::double),
+            ValueTy::Scalar(..) => return quote!(#a == #b),
+            ValueTy::Message => quote!(::prost::encoding::semantic_eq::message),
+        };
+        let module = self.map_ty.module();
+        quote!(::prost::encoding::#module::eq_semantically(#val_eq, &#a, &#b))
     }
 
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
@@ -297,17 +326,29 @@ impl Field {
     ///
     /// The Debug tries to convert any enumerations met into the variants if possible, instead of
     /// outputting the raw numbers.
-    pub fn debug(&self, wrapper_name: TokenStream) -> TokenStream {
-        let type_name = match self.map_ty {
-            MapTy::HashMap => Ident::new("HashMap", Span::call_site()),
-            MapTy::BTreeMap => Ident::new("BTreeMap", Span::call_site()),
+    /// Returns statements which push the field to the `fields` vector of
+    /// `MessageFields::field_values`, with its description `META`.
+    pub fn field_values(&self, ident: TokenStream) -> TokenStream {
+        let key = self.key_ty.field_value(quote!(key));
+        let value = match self.value_ty {
+            ValueTy::Scalar(ref ty) => ty.field_value(quote!(value)),
+            ValueTy::Message => quote!(::prost::FieldValueRef::Message(value)),
         };
+        quote! {
+            fields.push((
+                &META,
+                ::prost::FieldValueRef::Map(#ident.iter().map(|(key, value)| (#key, #value)).collect()),
+            ));
+        }
+    }
+
+    pub fn debug(&self, wrapper_name: TokenStream) -> TokenStream {
+        let map_type = self.map_ty.rust_type();
 
         // A fake field for generating the debug wrapper
         let key_wrapper = fake_scalar(self.key_ty.clone()).debug(quote!(KeyWrapper));
         let key = self.key_ty.rust_type();
         let value_wrapper = self.value_ty.debug();
-        let libname = self.map_ty.lib();
         let fmt = quote! {
             fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                 #key_wrapper
@@ -334,14 +375,14 @@ impl Field {
 
                 let value = ty.rust_type();
                 quote! {
-                    struct #wrapper_name<'a>(&'a ::#libname::collections::#type_name<#key, #value>);
+                    struct #wrapper_name<'a>(&'a #map_type<#key, #value>);
                     impl<'a> ::core::fmt::Debug for #wrapper_name<'a> {
                         #fmt
                     }
                 }
             }
             ValueTy::Message => quote! {
-                struct #wrapper_name<'a, V: 'a>(&'a ::#libname::collections::#type_name<#key, V>);
+                struct #wrapper_name<'a, V: 'a>(&'a #map_type<#key, V>);
                 impl<'a, V> ::core::fmt::Debug for #wrapper_name<'a, V>
                 where
                     V: ::core::fmt::Debug + 'a,
@@ -1,14 +1,16 @@
 use anyhow::{bail, Error};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
-use syn::Meta;
+use syn::{Expr, ExprLit, Lit, Meta, MetaNameValue, Path};
 
-use crate::field::{set_bool, set_option, tag_attr, word_attr, Label};
+use crate::field::{message_field_values, set_bool, set_option, tag_attr, word_attr, Label, Tag};
 
 #[derive(Clone)]
 pub struct Field {
     pub label: Label,
-    pub tag: u32,
+    pub tag: Tag,
+    /// The message the field's type is encoded as, if the field is not a message itself.
+    pub proxy: Option<Path>,
 }
 
 impl Field {
@@ -17,6 +19,7 @@ impl Field {
         let mut label = None;
         let mut tag = None;
         let mut boxed = false;
+        let mut proxy = None;
 
         let mut unknown_attrs = Vec::new();
 
@@ -29,6 +32,8 @@ impl Field {
                 set_option(&mut tag, t, "duplicate tag attributes")?;
             } else if let Some(l) = Label::from_attr(attr) {
                 set_option(&mut label, l, "duplicate label attributes")?;
+            } else if let Some(p) = proxy_attr(attr)? {
+                set_option(&mut proxy, p, "duplicate proxy attributes")?;
             } else {
                 unknown_attrs.push(attr);
             }
@@ -38,6 +43,10 @@ impl Field {
             return Ok(None);
         }
 
+        if boxed && proxy.is_some() {
+            bail!("proxy attribute may not be applied to boxed message fields");
+        }
+
         if !unknown_attrs.is_empty() {
             bail!(
                 "unknown attribute(s) for message field: #[prost({})]",
@@ -45,7 +54,7 @@ impl Field {
             );
         }
 
-        let tag = match tag.or(inferred_tag) {
+        let tag = match tag.or(inferred_tag.map(Tag::Number)) {
             Some(tag) => tag,
             None => bail!("message field is missing a tag attribute"),
         };
@@ -53,6 +62,7 @@ impl Field {
         Ok(Some(Field {
             label: label.unwrap_or(Label::Optional),
             tag,
+            proxy,
         }))
     }
 
@@ -72,7 +82,24 @@ impl Field {
     }
 
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
-        let tag = self.tag;
+        let tag = &self.tag;
+        if let Some(ref proxy) = self.proxy {
+            let encode_fn = match self.label {
+                Label::Optional | Label::Required => quote!(encode),
+                Label::Repeated => quote!(encode_repeated),
+            };
+            let encode_fn = quote!(::prost::encoding::proxy::#encode_fn::<#proxy, _>);
+            return match self.label {
+                Label::Optional => quote! {
+                    if let Some(ref value) = #ident {
+                        #encode_fn(#tag, value, buf);
+                    }
+                },
+                Label::Required | Label::Repeated => quote! {
+                    #encode_fn(#tag, &#ident, buf);
+                },
+            };
+        }
         match self.label {
             Label::Optional => quote! {
                 if let Some(ref msg) = #ident {
@@ -91,6 +118,16 @@ impl Field {
     }
 
     pub fn merge(&self, ident: TokenStream) -> TokenStream {
+        if let Some(ref proxy) = self.proxy {
+            let merge_fn = match self.label {
+                Label::Optional => quote!(merge_optional),
+                Label::Required => quote!(merge),
+                Label::Repeated => quote!(merge_repeated),
+            };
+            return quote! {
+                ::prost::encoding::proxy::#merge_fn::<#proxy, _>(wire_type, #ident, buf, ctx)
+            };
+        }
         match self.label {
             Label::Optional => quote! {
                 ::prost::encoding::message::merge(wire_type,
@@ -108,7 +145,22 @@ impl Field {
     }
 
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
-        let tag = self.tag;
+        let tag = &self.tag;
+        if let Some(ref proxy) = self.proxy {
+            return match self.label {
+                Label::Optional => quote! {
+                    #ident.as_ref().map_or(0, |value| {
+                        ::prost::encoding::proxy::encoded_len::<#proxy, _>(#tag, value)
+                    })
+                },
+                Label::Required => quote! {
+                    ::prost::encoding::proxy::encoded_len::<#proxy, _>(#tag, &#ident)
+                },
+                Label::Repeated => quote! {
+                    ::prost::encoding::proxy::encoded_len_repeated::<#proxy, _>(#tag, &#ident)
+                },
+            };
+        }
         match self.label {
             Label::Optional => quote! {
                 #ident.as_ref().map_or(0, |msg| ::prost::encoding::message::encoded_len(#tag, msg))
@@ -122,6 +174,106 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to an upper bound of the encoded length of the
+    /// field, using the upper bound of singular nested messages.
+    pub fn encoded_len_upper_bound(&self, ident: TokenStream) -> TokenStream {
+        // Bounding proxied values would convert them anyway.
+        if self.proxy.is_some() {
+            return self.encoded_len(ident);
+        }
+        let tag = &self.tag;
+        let bound = |msg: TokenStream| {
+            quote! {{
+                let len = ::prost::Message::encoded_len_upper_bound(#msg);
+                ::prost::encoding::key_len(#tag) + ::prost::encoding::encoded_len_varint(len as u64) + len
+            }}
+        };
+        match self.label {
+            Label::Optional => {
+                let bound = bound(quote!(msg));
+                quote! {
+                    #ident.as_ref().map_or(0, |msg| #bound)
+                }
+            }
+            Label::Required => bound(quote!(&#ident)),
+            Label::Repeated => self.encoded_len(ident),
+        }
+    }
+
+    /// Returns a statement which encodes the optional field as a message set item.
+    pub fn encode_item(&self, ident: TokenStream) -> TokenStream {
+        let tag = &self.tag;
+        quote! {
+            if let Some(ref msg) = #ident {
+                ::prost::encoding::message_set::encode(#tag, msg, buf);
+            }
+        }
+    }
+
+    /// Returns a match arm which merges the message of a message set item into the field, given
+    /// the item's type id.
+    pub fn merge_item(&self, ident: TokenStream, name: TokenStream) -> TokenStream {
+        let tag = &self.tag;
+        quote! {
+            #tag => ::prost::encoding::message::merge(
+                ::prost::encoding::wire_type::WireType::LengthDelimited,
+                #ident.get_or_insert_with(::core::default::Default::default),
+                &mut buf,
+                ctx,
+            )
+            .map(|()| true)
+            .map_err(|mut error| {
+                error.push(STRUCT_NAME, stringify!(#name));
+                error
+            }),
+        }
+    }
+
+    /// Returns an expression which evaluates to the encoded length of the field as a message set
+    /// item.
+    pub fn encoded_len_item(&self, ident: TokenStream) -> TokenStream {
+        let tag = &self.tag;
+        quote! {
+            #ident.as_ref().map_or(0, |msg| ::prost::encoding::message_set::encoded_len(#tag, msg))
+        }
+    }
+
+    /// Returns an expression which evaluates to an upper bound of the encoded length of the field
+    /// as a message set item.
+    pub fn encoded_len_upper_bound_item(&self, ident: TokenStream) -> TokenStream {
+        let tag = &self.tag;
+        quote! {
+            #ident.as_ref().map_or(0, |msg| {
+                let len = ::prost::Message::encoded_len_upper_bound(msg);
+                ::prost::encoding::message_set::item_len(#tag, len)
+            })
+        }
+    }
+
+    /// Returns an expression which evaluates to whether the fields `a` and `b` are equal, with
+    /// `NaN` floats equal to each other.
+    pub fn eq_semantically(&self, a: TokenStream, b: TokenStream) -> TokenStream {
+        // Custom types are compared with `PartialEq`.
+        if self.proxy.is_some() {
+            return quote!(#a == #b);
+        }
+        let eq = quote!(::prost::encoding::semantic_eq::message);
+        match self.label {
+            Label::Optional => quote!(::prost::encoding::semantic_eq::optional(&#a, &#b, #eq)),
+            Label::Required => quote!(#eq(&#a, &#b)),
+            Label::Repeated => quote!(::prost::encoding::semantic_eq::repeated(&#a, &#b, #eq)),
+        }
+    }
+
+    /// Returns statements which push the field to the `fields` vector of
+    /// `MessageFields::field_values`, with its description `META`, if it holds a value.
+    pub fn field_values(&self, ident: TokenStream) -> Result<TokenStream, Error> {
+        if self.proxy.is_some() {
+            bail!("field_values is not supported for proxied message fields");
+        }
+        Ok(message_field_values(&self.label, ident))
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
@@ -130,3 +282,20 @@ impl Field {
         }
     }
 }
+
+fn proxy_attr(attr: &Meta) -> Result<Option<Path>, Error> {
+    if !attr.path().is_ident("proxy") {
+        return Ok(None);
+    }
+    match *attr {
+        Meta::NameValue(MetaNameValue {
+            value:
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(ref lit),
+                    ..
+                }),
+            ..
+        }) => Ok(Some(lit.parse()?)),
+        _ => bail!("invalid proxy attribute: {:?}", attr),
+    }
+}
@@ -9,9 +9,12 @@ use std::slice;
 
 use anyhow::{bail, Error};
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{quote, ToTokens};
 use syn::punctuated::Punctuated;
-use syn::{Attribute, Expr, ExprLit, Lit, LitBool, LitInt, Meta, MetaNameValue, Token};
+use syn::{
+    Attribute, Expr, ExprLit, ExprPath, Lit, LitBool, LitInt, Meta, MetaNameValue, Path, Token,
+    Type,
+};
 
 #[derive(Clone)]
 pub enum Field {
@@ -78,13 +81,13 @@ impl Field {
         Ok(Some(field))
     }
 
-    pub fn tags(&self) -> Vec<u32> {
+    pub fn tags(&self) -> Vec<Tag> {
         match *self {
-            Field::Scalar(ref scalar) => vec![scalar.tag],
-            Field::Message(ref message) => vec![message.tag],
-            Field::Map(ref map) => vec![map.tag],
+            Field::Scalar(ref scalar) => vec![scalar.tag.clone()],
+            Field::Message(ref message) => vec![message.tag.clone()],
+            Field::Map(ref map) => vec![map.tag.clone()],
             Field::Oneof(ref oneof) => oneof.tags.clone(),
-            Field::Group(ref group) => vec![group.tag],
+            Field::Group(ref group) => vec![group.tag.clone()],
         }
     }
 
@@ -100,10 +103,10 @@ impl Field {
     }
 
     /// Returns an expression which evaluates to the result of merging a decoded
-    /// value into the field.
-    pub fn merge(&self, ident: TokenStream) -> TokenStream {
+    /// value into the field, given the name of the field.
+    pub fn merge(&self, ident: TokenStream, name: TokenStream) -> TokenStream {
         match *self {
-            Field::Scalar(ref scalar) => scalar.merge(ident),
+            Field::Scalar(ref scalar) => scalar.merge(ident, name),
             Field::Message(ref message) => message.merge(ident),
             Field::Map(ref map) => map.merge(ident),
             Field::Oneof(ref oneof) => oneof.merge(ident),
@@ -122,6 +125,24 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to an upper bound of the encoded length of the field.
+    pub fn encoded_len_upper_bound(&self, ident: TokenStream) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.encoded_len_upper_bound(ident),
+            Field::Message(ref msg) => msg.encoded_len_upper_bound(ident),
+            _ => self.encoded_len(ident),
+        }
+    }
+
+    /// Returns a constant expression which evaluates to the maximum encoded length of the field,
+    /// or `None` if it depends on the value.
+    pub fn max_encoded_len(&self) -> Option<TokenStream> {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.max_encoded_len(),
+            _ => None,
+        }
+    }
+
     /// Returns a statement which clears the field.
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match *self {
@@ -133,6 +154,32 @@ impl Field {
         }
     }
 
+    /// Returns `true` if the field holds a single value, which occurrences on the wire replace or
+    /// merge into, rather than append to.
+    pub fn is_singular(&self) -> bool {
+        match *self {
+            Field::Scalar(ref scalar) => {
+                !matches!(scalar.kind, scalar::Kind::Repeated | scalar::Kind::Packed)
+            }
+            Field::Message(ref message) => message.label != Label::Repeated,
+            Field::Map(_) => false,
+            Field::Oneof(_) => true,
+            Field::Group(ref group) => group.label != Label::Repeated,
+        }
+    }
+
+    /// Returns an expression which evaluates to whether the fields `a` and `b` are equal, with
+    /// `NaN` floats equal to each other.
+    pub fn eq_semantically(&self, a: TokenStream, b: TokenStream) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.eq_semantically(a, b),
+            Field::Message(ref message) => message.eq_semantically(a, b),
+            Field::Map(ref map) => map.eq_semantically(a, b),
+            Field::Oneof(ref oneof) => oneof.eq_semantically(a, b),
+            Field::Group(ref group) => group.eq_semantically(a, b),
+        }
+    }
+
     pub fn default(&self) -> TokenStream {
         match *self {
             Field::Scalar(ref scalar) => scalar.default(),
@@ -140,10 +187,41 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to the result of decoding a value of the field, or
+    /// `None` if the field is not of a closed enum type.
+    pub fn decode_closed_enum(&self) -> Option<TokenStream> {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.decode_closed_enum(),
+            _ => None,
+        }
+    }
+
+    /// Returns an expression which evaluates to an empty value of the field's type, to merge into.
+    pub fn empty(&self) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.empty(),
+            _ => quote!(::core::default::Default::default()),
+        }
+    }
+
     /// Produces the fragment implementing debug for the given field.
     pub fn debug(&self, ident: TokenStream) -> TokenStream {
+        // Fields whose presence is tracked by the bitset are formatted as an `Option`.
+        if let Field::Scalar(ref scalar) = *self {
+            if let Some(debug) = scalar.debug_presence(ident.clone()) {
+                return debug;
+            }
+        }
         match *self {
-            Field::Scalar(ref scalar) => {
+            // Only enumerations and floats need a wrapper, other scalars are formatted as they
+            // are, whatever their container type.
+            Field::Scalar(ref scalar)
+                if !scalar.newtype
+                    && matches!(
+                        scalar.ty,
+                        scalar::Ty::Enumeration(..) | scalar::Ty::Float | scalar::Ty::Double
+                    ) =>
+            {
                 let wrapper = scalar.debug(quote!(ScalarWrapper));
                 quote! {
                     {
@@ -165,13 +243,81 @@ impl Field {
         }
     }
 
-    pub fn methods(&self, ident: &TokenStream) -> Option<TokenStream> {
+    /// Sets the presence bitset of the message, which tracks the presence of optional scalar fields
+    /// stored inline.
+    pub fn set_presence(&mut self, presence: Option<&TokenStream>) -> Result<(), Error> {
         match *self {
-            Field::Scalar(ref scalar) => scalar.methods(ident),
+            Field::Scalar(ref mut scalar) if matches!(scalar.kind, scalar::Kind::Presence(..)) => {
+                match presence {
+                    Some(presence) => scalar.presence = Some(presence.clone()),
+                    None => bail!("presence attribute requires a #[prost(presence)] field"),
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Returns methods to embed in the message, given the field's identifier and Rust type.
+    pub fn methods(&self, ident: &TokenStream, ty: &Type) -> Option<TokenStream> {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.methods(ident, ty),
             Field::Map(ref map) => map.methods(ident),
             _ => None,
         }
     }
+
+    /// Returns the field as an extension of a `message_set_wire_format` message, which is
+    /// encoded as an item whose type id is the tag of the field.
+    /// Returns statements which push the description and the value of the field to the `fields`
+    /// vector of `MessageFields::field_values`, if the field holds a value.
+    pub fn field_values(&self, ident: TokenStream, name: &str) -> Result<TokenStream, Error> {
+        let values = match *self {
+            Field::Scalar(ref scalar) => scalar.field_values(ident),
+            Field::Message(ref message) => message.field_values(ident)?,
+            Field::Map(ref map) => map.field_values(ident),
+            Field::Group(ref group) => group.field_values(ident),
+            // The variants of oneofs have their own descriptions.
+            Field::Oneof(ref oneof) => return Ok(oneof.field_values(ident)),
+        };
+        let tag = &self.tags()[0];
+        Ok(quote! {
+            {
+                const META: ::prost::FieldDescriptorMeta =
+                    ::prost::FieldDescriptorMeta::new(#name, #tag);
+                #values
+            }
+        })
+    }
+
+    /// Returns an expression which evaluates to the `FieldValueRef` of a reference to the value
+    /// of a oneof variant.
+    pub fn field_value(&self, value: TokenStream) -> Result<TokenStream, Error> {
+        match *self {
+            Field::Scalar(ref scalar) => Ok(scalar.field_value(value)),
+            Field::Message(ref message) if message.proxy.is_some() => {
+                bail!("field_values is not supported for proxied message fields")
+            }
+            Field::Message(..) | Field::Group(..) => {
+                Ok(quote!(::prost::FieldValueRef::Message(#value)))
+            }
+            Field::Map(..) | Field::Oneof(..) => bail!("invalid oneof variant"),
+        }
+    }
+
+    pub fn message_set_extension(&self) -> Result<&message::Field, Error> {
+        match *self {
+            Field::Message(ref message)
+                if message.label == Label::Optional && message.proxy.is_none() =>
+            {
+                if message.tag == Tag::Number(1) {
+                    bail!("message set extensions can not use tag 1 of the item group");
+                }
+                Ok(message)
+            }
+            _ => bail!("message set fields must be optional message fields"),
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -303,29 +449,116 @@ fn word_attr(key: &str, attr: &Meta) -> bool {
     }
 }
 
-pub(super) fn tag_attr(attr: &Meta) -> Result<Option<u32>, Error> {
+/// Returns statements which push a message or group field to the `fields` vector of
+/// `MessageFields::field_values`, with its description `META`, if it holds a value.
+fn message_field_values(label: &Label, ident: TokenStream) -> TokenStream {
+    match label {
+        Label::Optional => quote! {
+            if let ::core::option::Option::Some(ref value) = #ident {
+                fields.push((&META, ::prost::FieldValueRef::Message(value)));
+            }
+        },
+        Label::Required => quote! {
+            fields.push((&META, ::prost::FieldValueRef::Message(&#ident)));
+        },
+        Label::Repeated => quote! {
+            fields.push((
+                &META,
+                ::prost::FieldValueRef::Repeated(
+                    #ident.iter().map(|value| ::prost::FieldValueRef::Message(value)).collect(),
+                ),
+            ));
+        },
+    }
+}
+
+/// The tag of a field: either a field number, or the path of a `u32` constant holding it.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Tag {
+    Number(u32),
+    Path(Path),
+}
+
+impl Tag {
+    /// Returns the field number, if it is known when deriving.
+    pub fn number(&self) -> Option<u32> {
+        match *self {
+            Tag::Number(number) => Some(number),
+            Tag::Path(_) => None,
+        }
+    }
+
+    fn parse(value: &str) -> Result<Tag, Error> {
+        match value.parse::<u32>() {
+            Ok(number) => Ok(Tag::Number(number)),
+            Err(_) if !value.starts_with(|c: char| c.is_ascii_digit()) => {
+                Ok(Tag::Path(syn::parse_str(value)?))
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl ToTokens for Tag {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match *self {
+            Tag::Number(ref number) => number.to_tokens(tokens),
+            Tag::Path(ref path) => path.to_tokens(tokens),
+        }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Tag::Number(number) => write!(f, "{}", number),
+            Tag::Path(ref path) => {
+                if path.leading_colon.is_some() {
+                    f.write_str("::")?;
+                }
+                for (i, segment) in path.segments.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("::")?;
+                    }
+                    write!(f, "{}", segment.ident)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+pub(super) fn tag_attr(attr: &Meta) -> Result<Option<Tag>, Error> {
     if !attr.path().is_ident("tag") {
         return Ok(None);
     }
     match *attr {
-        Meta::List(ref meta_list) => Ok(Some(meta_list.parse_args::<LitInt>()?.base10_parse()?)),
+        Meta::List(ref meta_list) => Ok(Some(Tag::Number(
+            meta_list.parse_args::<LitInt>()?.base10_parse()?,
+        ))),
         Meta::NameValue(MetaNameValue {
             value: Expr::Lit(ref expr),
             ..
         }) => match expr.lit {
-            Lit::Str(ref lit) => lit
-                .value()
-                .parse::<u32>()
-                .map_err(Error::from)
-                .map(Option::Some),
-            Lit::Int(ref lit) => Ok(Some(lit.base10_parse()?)),
+            Lit::Str(ref lit) => Tag::parse(lit.value().trim()).map(Some),
+            Lit::Int(ref lit) => Ok(Some(Tag::Number(lit.base10_parse()?))),
             _ => bail!("invalid tag attribute: {:?}", attr),
         },
+        Meta::NameValue(MetaNameValue {
+            value: Expr::Path(ExprPath { ref path, .. }),
+            ..
+        }) => Ok(Some(Tag::Path(path.clone()))),
         _ => bail!("invalid tag attribute: {:?}", attr),
     }
 }
 
-fn tags_attr(attr: &Meta) -> Result<Option<Vec<u32>>, Error> {
+fn tags_attr(attr: &Meta) -> Result<Option<Vec<Tag>>, Error> {
     if !attr.path().is_ident("tags") {
         return Ok(None);
     }
@@ -334,7 +567,7 @@ fn tags_attr(attr: &Meta) -> Result<Option<Vec<u32>>, Error> {
             meta_list
                 .parse_args_with(Punctuated::<LitInt, Token![,]>::parse_terminated)?
                 .iter()
-                .map(LitInt::base10_parse)
+                .map(|lit| lit.base10_parse().map(Tag::Number))
                 .collect::<Result<Vec<_>, _>>()?,
         )),
         Meta::NameValue(MetaNameValue {
@@ -347,8 +580,8 @@ fn tags_attr(attr: &Meta) -> Result<Option<Vec<u32>>, Error> {
         }) => lit
             .value()
             .split(',')
-            .map(|s| s.trim().parse::<u32>().map_err(Error::from))
-            .collect::<Result<Vec<u32>, _>>()
+            .map(|s| Tag::parse(s.trim()))
+            .collect::<Result<Vec<Tag>, _>>()
             .map(Some),
         _ => bail!("invalid tag attribute: {:?}", attr),
     }
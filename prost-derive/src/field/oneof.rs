@@ -3,12 +3,12 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{parse_str, Expr, ExprLit, Ident, Lit, Meta, MetaNameValue, Path};
 
-use crate::field::{set_option, tags_attr};
+use crate::field::{set_option, tags_attr, Tag};
 
 #[derive(Clone)]
 pub struct Field {
     pub ty: Path,
-    pub tags: Vec<u32>,
+    pub tags: Vec<Tag>,
 }
 
 impl Field {
@@ -33,7 +33,9 @@ impl Field {
                 };
                 set_option(&mut ty, t, "duplicate oneof attribute")?;
             } else if let Some(t) = tags_attr(attr)? {
-                set_option(&mut tags, t, "duplicate tags attributes")?;
+                // Tags may be split across several attributes, for example to declare the tags
+                // of variants behind `#[cfg]` with `#[cfg_attr(..., prost(tags = "..."))]`.
+                tags.get_or_insert_with(Vec::new).extend(t);
             } else {
                 unknown_attrs.push(attr);
             }
@@ -84,6 +86,25 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to whether the oneof fields `a` and `b` are equal,
+    /// with `NaN` floats equal to each other.
+    pub fn eq_semantically(&self, a: TokenStream, b: TokenStream) -> TokenStream {
+        let ty = &self.ty;
+        quote! {
+            ::prost::encoding::semantic_eq::optional(&#a, &#b, #ty::eq_semantically)
+        }
+    }
+
+    /// Returns a statement which pushes the field held by the oneof to the `fields` vector of
+    /// `MessageFields::field_values`, if it is set.
+    pub fn field_values(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            if let ::core::option::Option::Some(ref oneof) = #ident {
+                fields.push(oneof.field_value());
+            }
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         quote!(#ident = ::core::option::Option::None)
     }
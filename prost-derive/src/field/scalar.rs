@@ -3,16 +3,27 @@ use std::fmt;
 use anyhow::{anyhow, bail, Error};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{parse_str, Expr, ExprLit, Ident, Index, Lit, LitByteStr, Meta, MetaNameValue, Path};
+use syn::{
+    parse_str, Expr, ExprLit, GenericArgument, Ident, Index, Lit, LitByteStr, Meta, MetaNameValue,
+    Path, PathArguments, Type,
+};
 
-use crate::field::{bool_attr, set_option, tag_attr, Label};
+use crate::field::{bool_attr, set_bool, set_option, tag_attr, word_attr, Label, Tag};
 
 /// A scalar protobuf field.
 #[derive(Clone)]
 pub struct Field {
     pub ty: Ty,
     pub kind: Kind,
-    pub tag: u32,
+    pub tag: Tag,
+    /// Whether the field is a single-element tuple struct wrapping the scalar value.
+    pub newtype: bool,
+    /// The maximum number of values of a repeated field, if it is bounded.
+    pub max_len: Option<usize>,
+    /// Whether the field is of a closed enum type, whose undeclared values are skipped.
+    pub closed: bool,
+    /// The presence bitset of the message, for a field of kind `Presence`.
+    pub presence: Option<TokenStream>,
 }
 
 impl Field {
@@ -22,12 +33,20 @@ impl Field {
         let mut packed = None;
         let mut default = None;
         let mut tag = None;
+        let mut newtype = false;
+        let mut max_len = None;
+        let mut closed = false;
+        let mut presence = None;
 
         let mut unknown_attrs = Vec::new();
 
         for attr in attrs {
             if let Some(t) = Ty::from_attr(attr)? {
                 set_option(&mut ty, t, "duplicate type attributes")?;
+            } else if word_attr("newtype", attr) {
+                set_bool(&mut newtype, "duplicate newtype attribute")?;
+            } else if word_attr("closed", attr) {
+                set_bool(&mut closed, "duplicate closed attribute")?;
             } else if let Some(p) = bool_attr("packed", attr)? {
                 set_option(&mut packed, p, "duplicate packed attributes")?;
             } else if let Some(t) = tag_attr(attr)? {
@@ -36,6 +55,10 @@ impl Field {
                 set_option(&mut label, l, "duplicate label attributes")?;
             } else if let Some(d) = DefaultValue::from_attr(attr)? {
                 set_option(&mut default, d, "duplicate default attributes")?;
+            } else if let Some(m) = max_len_attr(attr)? {
+                set_option(&mut max_len, m, "duplicate max_len attributes")?;
+            } else if let Some(p) = presence_attr(attr)? {
+                set_option(&mut presence, p, "duplicate presence attributes")?;
             } else {
                 unknown_attrs.push(attr);
             }
@@ -53,7 +76,7 @@ impl Field {
             );
         }
 
-        let tag = match tag.or(inferred_tag) {
+        let tag = match tag.or(inferred_tag.map(Tag::Number)) {
             Some(tag) => tag,
             None => bail!("missing tag attribute"),
         };
@@ -76,6 +99,18 @@ impl Field {
             (Some(Label::Repeated), _, true) => {
                 bail!("repeated fields may not have a default value");
             }
+            (Some(Label::Optional), _, _) if presence.is_some() => {
+                if !ty.is_numeric() {
+                    bail!("presence attribute may only be applied to numeric fields");
+                }
+                if newtype {
+                    bail!("presence attribute may not be applied to newtype fields");
+                }
+                Kind::Presence(default, presence.unwrap())
+            }
+            _ if presence.is_some() => {
+                bail!("presence attribute may only be applied to optional fields");
+            }
 
             (None, _, _) => Kind::Plain(default),
             (Some(Label::Optional), _, _) => Kind::Optional(default),
@@ -86,7 +121,45 @@ impl Field {
             (Some(Label::Repeated), _, false) => Kind::Repeated,
         };
 
-        Ok(Some(Field { ty, kind, tag }))
+        if newtype {
+            if matches!(kind, Kind::Repeated | Kind::Packed) {
+                bail!("newtype attribute may not be applied to repeated fields");
+            }
+            if matches!(
+                ty,
+                Ty::Bytes(..) | Ty::Enumeration(..) | Ty::Timestamp(..) | Ty::Duration(..)
+            ) {
+                bail!("newtype attribute may only be applied to string and numeric fields");
+            }
+        }
+
+        if max_len.is_some() {
+            if !matches!(kind, Kind::Repeated | Kind::Packed) {
+                bail!("max_len attribute may only be applied to repeated fields");
+            }
+            if matches!(ty, Ty::Timestamp(..) | Ty::Duration(..)) {
+                bail!("max_len attribute may not be applied to time fields");
+            }
+        }
+
+        if closed {
+            if !matches!(ty, Ty::Enumeration(..)) {
+                bail!("closed attribute may only be applied to enumeration fields");
+            }
+            if max_len.is_some() {
+                bail!("closed attribute may not be applied to fields with a max_len");
+            }
+        }
+
+        Ok(Some(Field {
+            ty,
+            kind,
+            tag,
+            newtype,
+            max_len,
+            closed,
+            presence: None,
+        }))
     }
 
     pub fn new_oneof(attrs: &[Meta]) -> Result<Option<Field>, Error> {
@@ -96,7 +169,9 @@ impl Field {
                     field.kind = Kind::Required(default);
                     Ok(Some(field))
                 }
-                Kind::Optional(..) => bail!("invalid optional attribute on oneof field"),
+                Kind::Optional(..) | Kind::Presence(..) => {
+                    bail!("invalid optional attribute on oneof field")
+                }
                 Kind::Required(..) => bail!("invalid required attribute on oneof field"),
                 Kind::Packed | Kind::Repeated => bail!("invalid repeated attribute on oneof field"),
             }
@@ -108,53 +183,197 @@ impl Field {
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
         let module = self.ty.module();
         let encode_fn = match self.kind {
-            Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) => quote!(encode),
+            Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) | Kind::Presence(..) => {
+                quote!(encode)
+            }
             Kind::Repeated => quote!(encode_repeated),
             Kind::Packed => quote!(encode_packed),
         };
         let encode_fn = quote!(::prost::encoding::#module::#encode_fn);
-        let tag = self.tag;
+        let tag = &self.tag;
 
         match self.kind {
             Kind::Plain(ref default) => {
-                let default = default.typed();
+                let is_default = self.is_default(ident.clone(), default);
+                let ident = self.unwrap_newtype(ident);
                 quote! {
-                    if #ident != #default {
+                    if !(#is_default) {
                         #encode_fn(#tag, &#ident, buf);
                     }
                 }
             }
-            Kind::Optional(..) => quote! {
-                if let ::core::option::Option::Some(ref value) = #ident {
-                    #encode_fn(#tag, value, buf);
+            Kind::Optional(..) => {
+                let value = if self.newtype {
+                    quote!(&value.0)
+                } else {
+                    quote!(value)
+                };
+                quote! {
+                    if let ::core::option::Option::Some(ref value) = #ident {
+                        #encode_fn(#tag, #value, buf);
+                    }
                 }
-            },
-            Kind::Required(..) | Kind::Repeated | Kind::Packed => quote! {
-                #encode_fn(#tag, &#ident, buf);
-            },
+            }
+            Kind::Presence(_, bit) => {
+                let presence = self.presence();
+                quote! {
+                    if #presence.get(#bit) {
+                        #encode_fn(#tag, &#ident, buf);
+                    }
+                }
+            }
+            Kind::Required(..) | Kind::Repeated | Kind::Packed => {
+                let ident = self.unwrap_newtype(ident);
+                quote! {
+                    #encode_fn(#tag, &#ident, buf);
+                }
+            }
         }
     }
 
     /// Returns an expression which evaluates to the result of merging a decoded
     /// scalar value into the field.
-    pub fn merge(&self, ident: TokenStream) -> TokenStream {
+    ///
+    /// `name` is the name of the field, reported to the observer along with `STRUCT_NAME` for
+    /// undeclared values of open enums.
+    pub fn merge(&self, ident: TokenStream, name: TokenStream) -> TokenStream {
+        if let Some(path) = self.closed_enum() {
+            let merge_fn = match self.kind {
+                Kind::Plain(..) | Kind::Required(..) => quote!(merge),
+                Kind::Optional(..) => quote!(merge_optional),
+                Kind::Presence(_, bit) => {
+                    let presence = self.presence();
+                    return quote! {
+                        (match ::prost::encoding::closed_enum::decode::<#path>(wire_type, tag, buf, ctx) {
+                            ::core::result::Result::Ok(::core::option::Option::Some(decoded)) => {
+                                *#ident = decoded;
+                                #presence.set(#bit);
+                                ::core::result::Result::Ok(())
+                            }
+                            ::core::result::Result::Ok(::core::option::Option::None) => {
+                                ::core::result::Result::Ok(())
+                            }
+                            ::core::result::Result::Err(error) => ::core::result::Result::Err(error),
+                        })
+                    };
+                }
+                Kind::Repeated | Kind::Packed => {
+                    let packed = matches!(self.kind, Kind::Packed);
+                    return quote! {
+                        ({
+                            ::prost::encoding::observe_packed(#packed, wire_type, tag, buf, &ctx);
+                            ::prost::encoding::closed_enum::merge_repeated::<#path>(
+                                wire_type, tag, #ident, buf, ctx,
+                            )
+                        })
+                    };
+                }
+            };
+            return quote! {
+                ::prost::encoding::closed_enum::#merge_fn::<#path>(wire_type, tag, #ident, buf, ctx)
+            };
+        }
+
         let module = self.ty.module();
         let merge_fn = match self.kind {
-            Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) => quote!(merge),
+            Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) | Kind::Presence(..) => {
+                quote!(merge)
+            }
             Kind::Repeated | Kind::Packed => quote!(merge_repeated),
         };
-        let merge_fn = quote!(::prost::encoding::#module::#merge_fn);
+        // Undeclared values of open enums are reported to the observer.
+        let call = |merge_fn: TokenStream, value: TokenStream| match self.ty {
+            Ty::Enumeration(ref path) => quote! {
+                ::prost::encoding::open_enum::#merge_fn::<#path>(
+                    STRUCT_NAME, stringify!(#name), wire_type, #value, buf, ctx,
+                )
+            },
+            _ => quote!(::prost::encoding::#module::#merge_fn(wire_type, #value, buf, ctx)),
+        };
+
+        let merge_repeated = |ident| match self.max_len {
+            Some(max_len) => {
+                let value_wire_type = self.ty.wire_type();
+                let merge_value = call(quote!(merge), quote!(value));
+                quote! {
+                    ::prost::encoding::merge_repeated_bounded(
+                        wire_type,
+                        #ident,
+                        #max_len,
+                        ::prost::encoding::wire_type::WireType::#value_wire_type,
+                        |wire_type, value, buf, ctx| #merge_value,
+                        buf,
+                        ctx,
+                    )
+                }
+            }
+            None => call(merge_fn.clone(), ident),
+        };
+        let merge_fn = |value| call(merge_fn.clone(), value);
 
         match self.kind {
-            Kind::Plain(..) | Kind::Required(..) | Kind::Repeated | Kind::Packed => quote! {
-                #merge_fn(wire_type, #ident, buf, ctx)
-            },
-            Kind::Optional(..) => quote! {
-                #merge_fn(wire_type,
-                          #ident.get_or_insert_with(::core::default::Default::default),
-                          buf,
-                          ctx)
-            },
+            Kind::Repeated | Kind::Packed if self.ty.is_numeric() => {
+                let packed = matches!(self.kind, Kind::Packed);
+                let merge = merge_repeated(ident);
+                quote! {
+                    ({
+                        ::prost::encoding::observe_packed(#packed, wire_type, tag, buf, &ctx);
+                        #merge
+                    })
+                }
+            }
+            Kind::Repeated | Kind::Packed => merge_repeated(ident),
+            Kind::Plain(..) | Kind::Required(..) => {
+                let value = if self.newtype {
+                    quote!(&mut #ident.0)
+                } else {
+                    ident
+                };
+                merge_fn(value)
+            }
+            Kind::Optional(..) => {
+                let empty = self.empty();
+                let value = quote!(#ident.get_or_insert_with(|| #empty));
+                let value = if self.newtype {
+                    quote!(&mut #value.0)
+                } else {
+                    value
+                };
+                merge_fn(value)
+            }
+            Kind::Presence(_, bit) => {
+                let presence = self.presence();
+                let merge = merge_fn(ident);
+                quote! {
+                    ({
+                        let result = #merge;
+                        if result.is_ok() {
+                            #presence.set(#bit);
+                        }
+                        result
+                    })
+                }
+            }
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of decoding a value of the field, or
+    /// `None` if the field is not of a closed enum type.
+    ///
+    /// The result is `None` if the enum does not declare the value, in which case the field must be
+    /// left as is.
+    pub fn decode_closed_enum(&self) -> Option<TokenStream> {
+        let path = self.closed_enum()?;
+        Some(quote! {
+            ::prost::encoding::closed_enum::decode::<#path>(wire_type, tag, buf, ctx)
+        })
+    }
+
+    /// Returns the path of the enum type of the field, if it is closed.
+    fn closed_enum(&self) -> Option<&Path> {
+        match self.ty {
+            Ty::Enumeration(ref path) if self.closed => Some(path),
+            _ => None,
         }
     }
 
@@ -162,43 +381,180 @@ impl Field {
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
         let module = self.ty.module();
         let encoded_len_fn = match self.kind {
-            Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) => quote!(encoded_len),
+            Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) | Kind::Presence(..) => {
+                quote!(encoded_len)
+            }
             Kind::Repeated => quote!(encoded_len_repeated),
             Kind::Packed => quote!(encoded_len_packed),
         };
         let encoded_len_fn = quote!(::prost::encoding::#module::#encoded_len_fn);
-        let tag = self.tag;
+        let tag = &self.tag;
 
         match self.kind {
             Kind::Plain(ref default) => {
-                let default = default.typed();
+                let is_default = self.is_default(ident.clone(), default);
+                let ident = self.unwrap_newtype(ident);
                 quote! {
-                    if #ident != #default {
+                    if !(#is_default) {
                         #encoded_len_fn(#tag, &#ident)
                     } else {
                         0
                     }
                 }
             }
-            Kind::Optional(..) => quote! {
-                #ident.as_ref().map_or(0, |value| #encoded_len_fn(#tag, value))
-            },
-            Kind::Required(..) | Kind::Repeated | Kind::Packed => quote! {
-                #encoded_len_fn(#tag, &#ident)
-            },
+            Kind::Optional(..) => {
+                let value = if self.newtype {
+                    quote!(&value.0)
+                } else {
+                    quote!(value)
+                };
+                quote! {
+                    #ident.as_ref().map_or(0, |value| #encoded_len_fn(#tag, #value))
+                }
+            }
+            Kind::Presence(_, bit) => {
+                let presence = self.presence();
+                quote! {
+                    if #presence.get(#bit) {
+                        #encoded_len_fn(#tag, &#ident)
+                    } else {
+                        0
+                    }
+                }
+            }
+            Kind::Required(..) | Kind::Repeated | Kind::Packed => {
+                let ident = self.unwrap_newtype(ident);
+                quote! {
+                    #encoded_len_fn(#tag, &#ident)
+                }
+            }
+        }
+    }
+
+    /// Returns an expression which evaluates to an upper bound of the encoded length of the
+    /// field. Singular numeric fields use a constant bound instead of inspecting the value.
+    pub fn encoded_len_upper_bound(&self, ident: TokenStream) -> TokenStream {
+        match (&self.kind, self.ty.max_encoded_len()) {
+            (
+                Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) | Kind::Presence(..),
+                Some(max_len),
+            ) => {
+                let tag = &self.tag;
+                quote!(::prost::encoding::key_len(#tag) + #max_len)
+            }
+            _ => self.encoded_len(ident),
+        }
+    }
+
+    /// Returns a constant expression which evaluates to the maximum encoded length of the field,
+    /// or `None` if it is unbounded: length delimited values, and repeated fields without a
+    /// `max_len`.
+    pub fn max_encoded_len(&self) -> Option<TokenStream> {
+        let value_len = self.ty.max_encoded_len()?;
+        let tag = &self.tag;
+        match (&self.kind, self.max_len) {
+            (Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) | Kind::Presence(..), _) => {
+                Some(quote!(::prost::encoding::key_len(#tag) + #value_len))
+            }
+            (Kind::Repeated, Some(max_len)) => {
+                Some(quote!(#max_len * (::prost::encoding::key_len(#tag) + #value_len)))
+            }
+            (Kind::Packed, Some(max_len)) => Some(quote! {
+                ::prost::encoding::key_len(#tag)
+                    + ::prost::encoding::encoded_len_varint((#max_len * #value_len) as u64)
+                    + #max_len * #value_len
+            }),
+            (Kind::Repeated | Kind::Packed, None) => None,
+        }
+    }
+
+    /// Returns statements which push the field to the `fields` vector of
+    /// `MessageFields::field_values`, with its description `META`, if it holds a value.
+    pub fn field_values(&self, ident: TokenStream) -> TokenStream {
+        match self.kind {
+            Kind::Plain(..) | Kind::Required(..) => {
+                let value = self.field_value(quote!(&#ident));
+                quote!(fields.push((&META, #value));)
+            }
+            Kind::Presence(_, bit) => {
+                let presence = self.presence();
+                let value = self.field_value(quote!(&#ident));
+                quote! {
+                    if #presence.get(#bit) {
+                        fields.push((&META, #value));
+                    }
+                }
+            }
+            Kind::Optional(..) => {
+                let value = self.field_value(quote!(value));
+                quote! {
+                    if let ::core::option::Option::Some(ref value) = #ident {
+                        fields.push((&META, #value));
+                    }
+                }
+            }
+            Kind::Repeated | Kind::Packed => {
+                let value = self.ty.field_value(quote!(value));
+                quote! {
+                    fields.push((
+                        &META,
+                        ::prost::FieldValueRef::Repeated(#ident.iter().map(|value| #value).collect()),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Returns an expression which evaluates to the `FieldValueRef` of a reference to a single
+    /// value of the field.
+    pub fn field_value(&self, value: TokenStream) -> TokenStream {
+        if self.newtype {
+            self.ty.field_value(quote!(&(#value).0))
+        } else {
+            self.ty.field_value(value)
+        }
+    }
+
+    /// Returns an expression which evaluates to `true` if the field holds its default value.
+    fn is_default(&self, ident: TokenStream, default: &DefaultValue) -> TokenStream {
+        let default = default.typed();
+        let ident = self.unwrap_newtype(ident);
+        match self.ty {
+            // Compare through `Deref` so that any string or bytes container type can be used.
+            Ty::String | Ty::Bytes(..) => quote!(&*#ident == #default),
+            _ => quote!(#ident == #default),
         }
     }
 
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.kind {
             Kind::Plain(ref default) | Kind::Required(ref default) => {
-                let default = default.typed();
+                let ident = self.unwrap_newtype(ident);
                 match self.ty {
-                    Ty::String | Ty::Bytes(..) => quote!(#ident.clear()),
-                    _ => quote!(#ident = #default),
+                    Ty::String | Ty::Bytes(..) if default.is_empty() => {
+                        let module = self.ty.module();
+                        quote!(::prost::encoding::#module::clear(&mut #ident))
+                    }
+                    Ty::String | Ty::Bytes(..) => {
+                        let module = self.ty.module();
+                        let default = default.typed();
+                        quote!(::prost::encoding::#module::reset(&mut #ident, #default))
+                    }
+                    _ => {
+                        let default = default.typed();
+                        quote!(#ident = #default)
+                    }
                 }
             }
             Kind::Optional(_) => quote!(#ident = ::core::option::Option::None),
+            Kind::Presence(ref default, bit) => {
+                let default = default.typed();
+                let presence = self.presence();
+                quote!({
+                    #ident = #default;
+                    #presence.clear(#bit);
+                })
+            }
             Kind::Repeated | Kind::Packed => quote!(#ident.clear()),
         }
     }
@@ -206,13 +562,50 @@ impl Field {
     /// Returns an expression which evaluates to the default value of the field.
     pub fn default(&self) -> TokenStream {
         match self.kind {
-            Kind::Plain(ref value) | Kind::Required(ref value) => value.owned(),
+            Kind::Plain(ref value) | Kind::Required(ref value) if self.newtype => {
+                let value = match self.ty {
+                    Ty::String => {
+                        let value = value.typed();
+                        quote!(::prost::alloc::string::String::from(#value))
+                    }
+                    _ => value.typed(),
+                };
+                quote!(::core::convert::From::from(#value))
+            }
+            // Non-empty defaults of `Bytes` fields reference the literal instead of copying it.
+            Kind::Plain(ref value) | Kind::Required(ref value)
+                if self.ty == Ty::Bytes(BytesTy::Bytes) && !value.is_empty() =>
+            {
+                let value = value.typed();
+                quote!(::prost::bytes::Bytes::from_static(#value))
+            }
+            Kind::Plain(ref value) | Kind::Required(ref value) | Kind::Presence(ref value, _) => {
+                value.owned()
+            }
             Kind::Optional(_) => quote!(::core::option::Option::None),
+            // Bounded fields may use any storage, such as an `ArrayVec`.
+            Kind::Repeated | Kind::Packed if self.max_len.is_some() => {
+                quote!(::core::default::Default::default())
+            }
             Kind::Repeated | Kind::Packed => quote!(::prost::alloc::vec::Vec::new()),
         }
     }
 
+    /// Returns an expression which evaluates to an empty value of the field's type.
+    pub fn empty(&self) -> TokenStream {
+        match self.ty {
+            // Not all string types implement `Default` on the minimum supported Rust version.
+            Ty::String if !self.newtype => quote!("".into()),
+            // `SystemTime` does not implement `Default`.
+            Ty::Timestamp(..) | Ty::Duration(..) => DefaultValue::new(&self.ty).owned(),
+            _ => quote!(::core::default::Default::default()),
+        }
+    }
+
     /// An inner debug wrapper, around the base type.
+    ///
+    /// Enumerations are formatted as their variant if possible, and floats in a form which parses
+    /// back to the same bits.
     fn debug_inner(&self, wrap_name: TokenStream) -> TokenStream {
         if let Ty::Enumeration(ref ty) = self.ty {
             quote! {
@@ -227,6 +620,16 @@ impl Field {
                     }
                 }
             }
+        } else if let Ty::Float | Ty::Double = self.ty {
+            let ty = self.ty.rust_type();
+            quote! {
+                struct #wrap_name<'a>(&'a #ty);
+                impl<'a> ::core::fmt::Debug for #wrap_name<'a> {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                        ::core::fmt::Display::fmt(&::prost::float::RoundTrip(*self.0), f)
+                    }
+                }
+            }
         } else {
             quote! {
                 #[allow(non_snake_case)]
@@ -240,7 +643,9 @@ impl Field {
         let wrapper = self.debug_inner(quote!(Inner));
         let inner_ty = self.ty.rust_type();
         match self.kind {
-            Kind::Plain(_) | Kind::Required(_) => self.debug_inner(wrapper_name),
+            Kind::Plain(_) | Kind::Required(_) | Kind::Presence(..) => {
+                self.debug_inner(wrapper_name)
+            }
             Kind::Optional(_) => quote! {
                 struct #wrapper_name<'a>(&'a ::core::option::Option<#inner_ty>);
                 impl<'a> ::core::fmt::Debug for #wrapper_name<'a> {
@@ -252,7 +657,7 @@ impl Field {
             },
             Kind::Repeated | Kind::Packed => {
                 quote! {
-                    struct #wrapper_name<'a>(&'a ::prost::alloc::vec::Vec<#inner_ty>);
+                    struct #wrapper_name<'a>(&'a [#inner_ty]);
                     impl<'a> ::core::fmt::Debug for #wrapper_name<'a> {
                         fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                             let mut vec_builder = f.debug_list();
@@ -268,8 +673,54 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to whether the fields `a` and `b` are equal, with
+    /// `NaN` floats equal to each other.
+    pub fn eq_semantically(&self, a: TokenStream, b: TokenStream) -> TokenStream {
+        let eq = match self.ty {
+            Ty::Float => quote!(::prost::encoding::semantic_eq::float),
+            Ty::Double => quote!(::prost::encoding::semantic_eq::double),
+            _ if !self.newtype => return quote!(#a == #b),
+            // Newtypes don't have to implement `PartialEq`, so the values they wrap are compared.
+            _ => quote!(::core::cmp::PartialEq::eq),
+        };
+        match self.kind {
+            // Unset fields hold their default value, and the presence bitsets are compared with
+            // the whole message.
+            Kind::Plain(..) | Kind::Required(..) | Kind::Presence(..) => {
+                let a = self.unwrap_newtype(a);
+                let b = self.unwrap_newtype(b);
+                quote!(#eq(&#a, &#b))
+            }
+            Kind::Optional(..) => {
+                let eq = if self.newtype {
+                    quote!(|a, b| #eq(&a.0, &b.0))
+                } else {
+                    eq
+                };
+                quote!(::prost::encoding::semantic_eq::optional(&#a, &#b, #eq))
+            }
+            Kind::Repeated | Kind::Packed => {
+                quote!(::prost::encoding::semantic_eq::repeated(&#a, &#b, #eq))
+            }
+        }
+    }
+
+    /// Returns an expression which accesses the scalar value wrapped by a newtype field.
+    fn unwrap_newtype(&self, ident: TokenStream) -> TokenStream {
+        if self.newtype {
+            quote!((#ident).0)
+        } else {
+            ident
+        }
+    }
+
     /// Returns methods to embed in the message.
-    pub fn methods(&self, ident: &TokenStream) -> Option<TokenStream> {
+    pub fn methods(&self, ident: &TokenStream, field_ty: &Type) -> Option<TokenStream> {
+        // Unset times have no meaningful default, so they are only accessed through the `Option`.
+        if self.newtype || matches!(self.ty, Ty::Timestamp(..) | Ty::Duration(..)) {
+            return None;
+        }
+
         let mut ident_str = ident.to_string();
         if ident_str.starts_with("r#") {
             ident_str = ident_str.split_off(2);
@@ -284,6 +735,10 @@ impl Field {
             Err(_) => quote!(#ident),
         };
 
+        if let Kind::Presence(ref default, bit) = self.kind {
+            return Some(self.presence_methods(ident, &ident_str, get, default, bit));
+        }
+
         if let Ty::Enumeration(ref ty) = self.ty {
             let set = Ident::new(&format!("set_{}", ident_str), Span::call_site());
             let set_doc = format!("Sets `{}` to the provided enum value.", ident_str);
@@ -327,7 +782,7 @@ impl Field {
                         }
                     }
                 }
-                Kind::Repeated | Kind::Packed => {
+                Kind::Repeated | Kind::Packed | Kind::Presence(..) => {
                     let iter_doc = format!(
                         "Returns an iterator which yields the valid enum values contained in `{}`.",
                         ident_str,
@@ -352,6 +807,76 @@ impl Field {
                     }
                 }
             })
+        } else if let Ty::Bytes(ref bytes_ty) = self.ty {
+            // The field may use any container of bytes, such as a `Box<[u8]>`.
+            let ty = match self.kind {
+                Kind::Optional(_) => option_inner_type(field_ty)
+                    .map(ToTokens::to_token_stream)
+                    .unwrap_or_else(|| bytes_ty.rust_type()),
+                _ => field_ty.to_token_stream(),
+            };
+            let set = Ident::new(&format!("set_{}", ident_str), Span::call_site());
+            let set_doc = format!("Sets `{}` to the provided value.", ident_str);
+            let take = Ident::new(&format!("take_{}", ident_str), Span::call_site());
+            let get_doc = match self.kind {
+                Kind::Optional(_) => format!(
+                    "Returns the value of `{0}`, or the default value if `{0}` is unset.",
+                    ident_str,
+                ),
+                _ => format!("Returns the value of `{}`.", ident_str),
+            };
+
+            let (get_body, set_value, take_doc, take_body) = match self.kind {
+                Kind::Plain(_) | Kind::Required(_) => {
+                    let default = self.default();
+                    (
+                        quote!(&self.#ident[..]),
+                        quote!(value.into()),
+                        format!(
+                            "Takes the value of `{}`, leaving the default value in its place.",
+                            ident_str,
+                        ),
+                        quote!(::core::mem::replace(&mut self.#ident, #default)),
+                    )
+                }
+                Kind::Optional(ref default) => {
+                    let typed = default.typed();
+                    let owned = default.owned();
+                    (
+                        quote! {
+                            match self.#ident {
+                                ::core::option::Option::Some(ref val) => &val[..],
+                                ::core::option::Option::None => #typed,
+                            }
+                        },
+                        quote!(::core::option::Option::Some(value.into())),
+                        format!(
+                            "Takes the value of `{}`, or the default value if it is unset, \
+                             leaving it unset.",
+                            ident_str,
+                        ),
+                        quote!(self.#ident.take().unwrap_or_else(|| #owned)),
+                    )
+                }
+                Kind::Repeated | Kind::Packed | Kind::Presence(..) => return None,
+            };
+
+            Some(quote! {
+                #[doc=#get_doc]
+                pub fn #get(&self) -> &[u8] {
+                    #get_body
+                }
+
+                #[doc=#set_doc]
+                pub fn #set(&mut self, value: impl ::core::convert::Into<#ty>) {
+                    self.#ident = #set_value;
+                }
+
+                #[doc=#take_doc]
+                pub fn #take(&mut self) -> #ty {
+                    #take_body
+                }
+            })
         } else if let Kind::Optional(ref default) = self.kind {
             let ty = self.ty.rust_ref_type();
 
@@ -381,6 +906,117 @@ impl Field {
     }
 }
 
+impl Field {
+    /// Returns the accessor methods of a field whose presence is tracked by the bitset.
+    fn presence_methods(
+        &self,
+        ident: &TokenStream,
+        ident_str: &str,
+        get: TokenStream,
+        default: &DefaultValue,
+        bit: u32,
+    ) -> TokenStream {
+        let presence = self.presence();
+        let has = Ident::new(&format!("has_{}", ident_str), Span::call_site());
+        let set = Ident::new(&format!("set_{}", ident_str), Span::call_site());
+        let clear = Ident::new(&format!("clear_{}", ident_str), Span::call_site());
+        let has_doc = format!("Returns whether `{}` is set.", ident_str);
+        let set_doc = format!("Sets `{}` to the provided value.", ident_str);
+        let clear_doc = format!("Unsets `{}`, restoring its default value.", ident_str);
+        let get_doc = format!(
+            "Returns the value of `{0}`, or the default value if `{0}` is unset.",
+            ident_str,
+        );
+        let typed = default.typed();
+
+        let (ty, get_body, set_value) = match self.ty {
+            Ty::Enumeration(ref ty) => (
+                quote!(#ty),
+                quote!(::core::convert::TryFrom::try_from(self.#ident).unwrap_or(#default)),
+                quote!(value as i32),
+            ),
+            _ => (self.ty.rust_ref_type(), quote!(self.#ident), quote!(value)),
+        };
+
+        quote! {
+            #[doc=#has_doc]
+            pub fn #has(&self) -> bool {
+                #presence.get(#bit)
+            }
+
+            #[doc=#get_doc]
+            pub fn #get(&self) -> #ty {
+                #get_body
+            }
+
+            #[doc=#set_doc]
+            pub fn #set(&mut self, value: #ty) {
+                self.#ident = #set_value;
+                #presence.set(#bit);
+            }
+
+            #[doc=#clear_doc]
+            pub fn #clear(&mut self) {
+                self.#ident = #typed;
+                #presence.clear(#bit);
+            }
+        }
+    }
+
+    /// Returns an expression which formats a field whose presence is tracked by the bitset as an
+    /// `Option` in `Debug`.
+    pub fn debug_presence(&self, ident: TokenStream) -> Option<TokenStream> {
+        let bit = match self.kind {
+            Kind::Presence(_, bit) => bit,
+            _ => return None,
+        };
+        let presence = self.presence();
+        let value = match self.ty {
+            Ty::Enumeration(_) | Ty::Float | Ty::Double => {
+                let wrapper = self.debug_inner(quote!(Inner));
+                quote!({
+                    #wrapper
+                    Inner(&#ident)
+                })
+            }
+            _ => quote!(&#ident),
+        };
+        Some(quote! {
+            if #presence.get(#bit) {
+                ::core::option::Option::Some(#value)
+            } else {
+                ::core::option::Option::None
+            }
+        })
+    }
+
+    /// Returns the presence bitset of the message.
+    fn presence(&self) -> TokenStream {
+        let presence = self
+            .presence
+            .as_ref()
+            .expect("presence field is set for fields of kind `Presence`");
+        quote!(self.#presence)
+    }
+}
+
+/// Returns `T` for a field of type `Option<T>`.
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) if segment.ident == "Option" => {
+            match args.args.first()? {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 /// A scalar protobuf field type.
 #[derive(Clone, PartialEq, Eq)]
 pub enum Ty {
@@ -400,6 +1036,10 @@ pub enum Ty {
     String,
     Bytes(BytesTy),
     Enumeration(Path),
+    /// A `google.protobuf.Timestamp` message as a `std::time::SystemTime`.
+    Timestamp(RangePolicy),
+    /// A `google.protobuf.Duration` message as a `core::time::Duration`.
+    Duration(RangePolicy),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -425,6 +1065,26 @@ impl BytesTy {
     }
 }
 
+/// How values of well-known time types which can not be represented by the Rust type are
+/// decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangePolicy {
+    /// Fail to decode the message.
+    Reject,
+    /// Use the closest representable value.
+    Saturate,
+}
+
+impl RangePolicy {
+    fn try_from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "reject" => Ok(RangePolicy::Reject),
+            "saturate" => Ok(RangePolicy::Saturate),
+            _ => bail!("Invalid range policy: {}", s),
+        }
+    }
+}
+
 impl Ty {
     pub fn from_attr(attr: &Meta) -> Result<Option<Ty>, Error> {
         let ty = match *attr {
@@ -443,6 +1103,10 @@ impl Ty {
             Meta::Path(ref name) if name.is_ident("bool") => Ty::Bool,
             Meta::Path(ref name) if name.is_ident("string") => Ty::String,
             Meta::Path(ref name) if name.is_ident("bytes") => Ty::Bytes(BytesTy::Vec),
+            Meta::Path(ref name) if name.is_ident("timestamp") => {
+                Ty::Timestamp(RangePolicy::Reject)
+            }
+            Meta::Path(ref name) if name.is_ident("duration") => Ty::Duration(RangePolicy::Reject),
             Meta::NameValue(MetaNameValue {
                 ref path,
                 value:
@@ -452,6 +1116,26 @@ impl Ty {
                     }),
                 ..
             }) if path.is_ident("bytes") => Ty::Bytes(BytesTy::try_from_str(&l.value())?),
+            Meta::NameValue(MetaNameValue {
+                ref path,
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(ref l),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("timestamp") => {
+                Ty::Timestamp(RangePolicy::try_from_str(&l.value())?)
+            }
+            Meta::NameValue(MetaNameValue {
+                ref path,
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(ref l),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("duration") => Ty::Duration(RangePolicy::try_from_str(&l.value())?),
             Meta::NameValue(MetaNameValue {
                 ref path,
                 value:
@@ -525,6 +1209,8 @@ impl Ty {
             Ty::String => "string",
             Ty::Bytes(..) => "bytes",
             Ty::Enumeration(..) => "enum",
+            Ty::Timestamp(..) => "timestamp",
+            Ty::Duration(..) => "duration",
         }
     }
 
@@ -556,19 +1242,83 @@ impl Ty {
             Ty::String => quote!(&str),
             Ty::Bytes(..) => quote!(&[u8]),
             Ty::Enumeration(..) => quote!(i32),
+            Ty::Timestamp(..) => quote!(::std::time::SystemTime),
+            Ty::Duration(..) => quote!(::core::time::Duration),
         }
     }
 
     pub fn module(&self) -> Ident {
         match *self {
             Ty::Enumeration(..) => Ident::new("int32", Span::call_site()),
+            Ty::Timestamp(RangePolicy::Saturate) => {
+                Ident::new("saturating_timestamp", Span::call_site())
+            }
+            Ty::Duration(RangePolicy::Saturate) => {
+                Ident::new("saturating_duration", Span::call_site())
+            }
             _ => Ident::new(self.as_str(), Span::call_site()),
         }
     }
 
-    /// Returns false if the scalar type is length delimited (i.e., `string` or `bytes`).
+    /// Returns false if the scalar type is length delimited (i.e., `string`, `bytes` or a time
+    /// message).
     pub fn is_numeric(&self) -> bool {
-        !matches!(self, Ty::String | Ty::Bytes(..))
+        !matches!(
+            self,
+            Ty::String | Ty::Bytes(..) | Ty::Timestamp(..) | Ty::Duration(..)
+        )
+    }
+
+    /// Returns the name of the `WireType` variant used to encode a single value of the type.
+    pub fn wire_type(&self) -> Ident {
+        let wire_type = match self {
+            Ty::Float | Ty::Fixed32 | Ty::Sfixed32 => "ThirtyTwoBit",
+            Ty::Double | Ty::Fixed64 | Ty::Sfixed64 => "SixtyFourBit",
+            Ty::String | Ty::Bytes(..) | Ty::Timestamp(..) | Ty::Duration(..) => "LengthDelimited",
+            _ => "Varint",
+        };
+        Ident::new(wire_type, Span::call_site())
+    }
+
+    /// Returns an expression which evaluates to the `FieldValueRef` of a reference to a value of
+    /// the type.
+    pub fn field_value(&self, value: TokenStream) -> TokenStream {
+        let variant = match self {
+            Ty::Double => quote!(F64),
+            Ty::Float => quote!(F32),
+            Ty::Int32 | Ty::Sint32 | Ty::Sfixed32 => quote!(I32),
+            Ty::Int64 | Ty::Sint64 | Ty::Sfixed64 => quote!(I64),
+            Ty::Uint32 | Ty::Fixed32 => quote!(U32),
+            Ty::Uint64 | Ty::Fixed64 => quote!(U64),
+            Ty::Bool => quote!(Bool),
+            Ty::Enumeration(..) => quote!(Enum),
+            Ty::Timestamp(..) => quote!(Timestamp),
+            Ty::Duration(..) => quote!(Duration),
+            // Any string or bytes container type which dereferences to `str` or `[u8]`.
+            Ty::String => {
+                return quote!(::prost::FieldValueRef::String(::core::ops::Deref::deref(#value)))
+            }
+            Ty::Bytes(..) => {
+                return quote!(::prost::FieldValueRef::Bytes(::core::ops::Deref::deref(#value)))
+            }
+        };
+        quote!(::prost::FieldValueRef::#variant(*#value))
+    }
+
+    /// Returns the maximum encoded length of a value of the scalar type, not including the key,
+    /// or `None` if the type is length delimited.
+    pub fn max_encoded_len(&self) -> Option<usize> {
+        match self {
+            Ty::Bool => Some(1),
+            Ty::Float | Ty::Fixed32 | Ty::Sfixed32 => Some(4),
+            Ty::Uint32 | Ty::Sint32 => Some(5),
+            Ty::Double | Ty::Fixed64 | Ty::Sfixed64 => Some(8),
+            // Negative `int32` and enumeration values are sign extended to 10 bytes.
+            Ty::Int32 | Ty::Int64 | Ty::Uint64 | Ty::Sint64 | Ty::Enumeration(..) => Some(10),
+            // A length prefix and the keys and values of `seconds` and non-negative `nanos`.
+            Ty::Timestamp(..) | Ty::Duration(..) => Some(18),
+            Ty::String | Ty::Bytes(..) => None,
+        }
     }
 }
 
@@ -584,6 +1334,42 @@ impl fmt::Display for Ty {
     }
 }
 
+/// Parses a `max_len = "N"` or `max_len = N` attribute.
+fn max_len_attr(attr: &Meta) -> Result<Option<usize>, Error> {
+    if !attr.path().is_ident("max_len") {
+        return Ok(None);
+    }
+    match attr {
+        Meta::NameValue(MetaNameValue {
+            value: Expr::Lit(ExprLit { lit, .. }),
+            ..
+        }) => match lit {
+            Lit::Str(lit) => Ok(Some(lit.value().parse()?)),
+            Lit::Int(lit) => Ok(Some(lit.base10_parse()?)),
+            _ => bail!("invalid max_len attribute: {:?}", attr),
+        },
+        _ => bail!("invalid max_len attribute: {:?}", attr),
+    }
+}
+
+/// Parses a `presence = "N"` or `presence = N` attribute.
+fn presence_attr(attr: &Meta) -> Result<Option<u32>, Error> {
+    if !attr.path().is_ident("presence") {
+        return Ok(None);
+    }
+    match attr {
+        Meta::NameValue(MetaNameValue {
+            value: Expr::Lit(ExprLit { lit, .. }),
+            ..
+        }) => match lit {
+            Lit::Str(lit) => Ok(Some(lit.value().parse()?)),
+            Lit::Int(lit) => Ok(Some(lit.base10_parse()?)),
+            _ => bail!("invalid presence attribute: {:?}", attr),
+        },
+        _ => bail!("invalid presence attribute: {:?}", attr),
+    }
+}
+
 /// Scalar Protobuf field types.
 #[derive(Clone)]
 pub enum Kind {
@@ -597,6 +1383,9 @@ pub enum Kind {
     Repeated,
     /// A packed repeated scalar field.
     Packed,
+    /// An optional scalar field stored inline, whose presence is tracked by a bit of the
+    /// message's presence bitset.
+    Presence(DefaultValue, u32),
 }
 
 /// Scalar Protobuf field default value.
@@ -676,7 +1465,14 @@ impl DefaultValue {
                 let value = value.trim();
 
                 if let Ty::Enumeration(ref path) = *ty {
-                    let variant = Ident::new(value, Span::call_site());
+                    // The default is the name of a variant of the enum.
+                    let variant = match parse_str::<Ident>(value) {
+                        Ok(variant) => variant,
+                        Err(_) => bail!(
+                            "invalid enumeration default value: {:?} is not a variant name",
+                            value
+                        ),
+                    };
                     return Ok(DefaultValue::Enumeration(quote!(#path::#variant)));
                 }
 
@@ -776,14 +1572,24 @@ impl DefaultValue {
             Ty::String => DefaultValue::String(String::new()),
             Ty::Bytes(..) => DefaultValue::Bytes(Vec::new()),
             Ty::Enumeration(ref path) => DefaultValue::Enumeration(quote!(#path::default())),
+            Ty::Timestamp(..) => DefaultValue::Path(syn::parse_quote!(::std::time::UNIX_EPOCH)),
+            Ty::Duration(..) => DefaultValue::Path(syn::parse_quote!(::core::time::Duration::ZERO)),
+        }
+    }
+
+    /// Returns `true` for empty string and bytes values.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            DefaultValue::String(value) => value.is_empty(),
+            DefaultValue::Bytes(value) => value.is_empty(),
+            _ => false,
         }
     }
 
     pub fn owned(&self) -> TokenStream {
         match *self {
-            DefaultValue::String(ref value) if value.is_empty() => {
-                quote!(::prost::alloc::string::String::new())
-            }
+            // Not all string types implement `Default` on the minimum supported Rust version.
+            DefaultValue::String(ref value) if value.is_empty() => quote!("".into()),
             DefaultValue::String(ref value) => quote!(#value.into()),
             DefaultValue::Bytes(ref value) if value.is_empty() => {
                 quote!(::core::default::Default::default())
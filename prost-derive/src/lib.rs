@@ -5,29 +5,50 @@
 extern crate alloc;
 extern crate proc_macro;
 
-use anyhow::{bail, Error};
+use anyhow::{anyhow, bail, Error};
 use itertools::Itertools;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::{
-    punctuated::Punctuated, Data, DataEnum, DataStruct, DeriveInput, Expr, Fields, FieldsNamed,
-    FieldsUnnamed, Ident, Index, Variant,
+    punctuated::Punctuated, Attribute, Data, DataEnum, DataStruct, DeriveInput, Expr, ExprGroup,
+    ExprLit, ExprParen, ExprUnary, Fields, FieldsNamed, FieldsUnnamed, Ident, Index, Lit, Meta,
+    MetaNameValue, Path, Token, Type, TypePath, UnOp, Variant,
 };
 
 mod field;
-use crate::field::Field;
+use crate::field::{Field, Tag};
 
 fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     let input: DeriveInput = syn::parse2(input)?;
 
     let ident = input.ident;
 
+    let reserved = Reserved::from_attrs(&input.attrs)?;
+
     syn::custom_keyword!(skip_debug);
     let skip_debug = input
         .attrs
-        .into_iter()
+        .iter()
         .any(|a| a.path().is_ident("prost") && a.parse_args::<skip_debug>().is_ok());
 
+    syn::custom_keyword!(transparent);
+    let transparent = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<transparent>().is_ok());
+
+    syn::custom_keyword!(message_set_wire_format);
+    let message_set_wire_format = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<message_set_wire_format>().is_ok());
+
+    syn::custom_keyword!(field_values);
+    let field_values = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<field_values>().is_ok());
+
     let variant_data = match input.data {
         Data::Struct(variant_data) => variant_data,
         Data::Enum(..) => bail!("Message can not be derived for an enum"),
@@ -37,6 +58,13 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
+    if transparent && field_values {
+        bail!("field_values attribute may not be applied to transparent messages");
+    }
+    if transparent {
+        return try_transparent_message(&ident, generics, variant_data, skip_debug);
+    }
+
     let (is_struct, fields) = match variant_data {
         DataStruct {
             fields: Fields::Named(FieldsNamed { named: fields, .. }),
@@ -55,7 +83,12 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         } => (false, Vec::new()),
     };
 
-    let mut next_tag: u32 = 1;
+    // Tags are inferred from the previous field, unless its tag is a constant.
+    syn::custom_keyword!(presence);
+    syn::custom_keyword!(skip);
+    let mut next_tag = Some(1);
+    let mut presence_field = None;
+    let mut skipped_fields = Vec::new();
     let mut fields = fields
         .into_iter()
         .enumerate()
@@ -67,10 +100,48 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                 };
                 quote!(#index)
             });
-            match Field::new(field.attrs, Some(next_tag)) {
+            let ty = field.ty;
+            // Skipped fields, and `PhantomData` markers of generic messages whatever their
+            // attributes, are never encoded.
+            if is_phantom_data(&ty)
+                || field
+                    .attrs
+                    .iter()
+                    .any(|a| a.path().is_ident("prost") && a.parse_args::<skip>().is_ok())
+            {
+                skipped_fields.push(field_ident);
+                return None;
+            }
+            // The presence bitset of optional scalar fields isn't a Protobuf field itself.
+            if field
+                .attrs
+                .iter()
+                .any(|a| a.path().is_ident("prost") && a.parse_args::<presence>().is_ok())
+            {
+                return if !is_struct {
+                    Some(Err(anyhow!(
+                        "presence field {}.{} must be a named field",
+                        ident,
+                        field_ident
+                    )))
+                } else if presence_field.replace(field_ident).is_some() {
+                    Some(Err(anyhow!(
+                        "message {} has multiple presence fields",
+                        ident
+                    )))
+                } else {
+                    None
+                };
+            }
+            match Field::new(field.attrs, next_tag) {
                 Ok(Some(field)) => {
-                    next_tag = field.tags().iter().max().map(|t| t + 1).unwrap_or(next_tag);
-                    Some(Ok((field_ident, field)))
+                    let tags = field.tags();
+                    if tags.iter().any(|tag| tag.number().is_none()) {
+                        next_tag = None;
+                    } else if let Some(max) = tags.iter().filter_map(Tag::number).max() {
+                        next_tag = Some(max + 1);
+                    }
+                    Some(Ok((field_ident, ty, field)))
                 }
                 Ok(None) => None,
                 Err(err) => Some(Err(
@@ -80,6 +151,20 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    for (field_ident, _, field) in &mut fields {
+        field.set_presence(presence_field.as_ref()).map_err(|err| {
+            err.context(format!("invalid message field {}.{}", ident, field_ident))
+        })?;
+    }
+    let methods = fields
+        .iter()
+        .filter_map(|(field_ident, ty, field)| field.methods(field_ident, ty))
+        .collect::<Vec<_>>();
+    let mut fields = fields
+        .into_iter()
+        .map(|(field_ident, _, field)| (field_ident, field))
+        .collect::<Vec<_>>();
+
     // We want Debug to be in declaration order
     let unsorted_fields = fields.clone();
 
@@ -87,7 +172,18 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     // TODO: This encodes oneof fields in the position of their lowest tag,
     // regardless of the currently occupied variant, is that consequential?
     // See: https://developers.google.com/protocol-buffers/docs/encoding#order
-    fields.sort_by_key(|(_, field)| field.tags().into_iter().min().unwrap());
+    // Fields with constant tags are encoded last, in declaration order.
+    fields.sort_by_key(|(_, field)| {
+        let tags = field.tags();
+        if tags.iter().any(|tag| tag.number().is_none()) {
+            u32::MAX
+        } else {
+            tags.iter()
+                .filter_map(Tag::number)
+                .min()
+                .unwrap_or(u32::MAX)
+        }
+    });
     let fields = fields;
 
     if let Some(duplicate_tag) = fields
@@ -103,16 +199,90 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         )
     };
 
-    let encoded_len = fields
+    if let Some(reserved_tag) = fields
         .iter()
-        .map(|(field_ident, field)| field.encoded_len(quote!(self.#field_ident)));
+        .flat_map(|(_, field)| field.tags())
+        .find(|tag| tag.number().is_some_and(|tag| reserved.contains(tag)))
+    {
+        bail!(
+            "message {} has a field with reserved tag {}",
+            ident,
+            reserved_tag
+        )
+    }
 
-    let encode = fields
-        .iter()
-        .map(|(field_ident, field)| field.encode(quote!(self.#field_ident)));
+    // The fields of messages with the legacy MessageSet wire format are encoded as items of a
+    // group, and are also decoded from regular fields.
+    let (encoded_len, encoded_len_upper_bound, encode, merge_items) = if message_set_wire_format {
+        let extensions = fields
+            .iter()
+            .map(|(field_ident, field)| {
+                let extension = field.message_set_extension().map_err(|err| {
+                    err.context(format!("invalid message field {}.{}", ident, field_ident))
+                })?;
+                Ok((field_ident, extension))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let merge_item = if extensions.is_empty() {
+            quote!(|_, _, _| ::core::result::Result::Ok(false))
+        } else {
+            let arms = extensions.iter().map(|(field_ident, extension)| {
+                extension.merge_item(quote!(self.#field_ident), quote!(#field_ident))
+            });
+            quote! {
+                |type_id, mut buf, ctx| match type_id {
+                    #(#arms)*
+                    _ => ::core::result::Result::Ok(false),
+                }
+            }
+        };
+
+        (
+            extensions
+                .iter()
+                .map(|(field_ident, extension)| {
+                    extension.encoded_len_item(quote!(self.#field_ident))
+                })
+                .collect::<Vec<_>>(),
+            extensions
+                .iter()
+                .map(|(field_ident, extension)| {
+                    extension.encoded_len_upper_bound_item(quote!(self.#field_ident))
+                })
+                .collect::<Vec<_>>(),
+            extensions
+                .iter()
+                .map(|(field_ident, extension)| extension.encode_item(quote!(self.#field_ident)))
+                .collect::<Vec<_>>(),
+            quote! {
+                ::prost::encoding::message_set::ITEM_TAG => {
+                    ::prost::encoding::message_set::merge_item(wire_type, buf, ctx, #merge_item)
+                }
+            },
+        )
+    } else {
+        (
+            fields
+                .iter()
+                .map(|(field_ident, field)| field.encoded_len(quote!(self.#field_ident)))
+                .collect(),
+            fields
+                .iter()
+                .map(|(field_ident, field)| {
+                    field.encoded_len_upper_bound(quote!(self.#field_ident))
+                })
+                .collect(),
+            fields
+                .iter()
+                .map(|(field_ident, field)| field.encode(quote!(self.#field_ident)))
+                .collect(),
+            quote!(),
+        )
+    };
 
     let merge = fields.iter().map(|(field_ident, field)| {
-        let merge = field.merge(quote!(value));
+        let merge = field.merge(quote!(value), quote!(#field_ident));
         let tags = field.tags().into_iter().map(|tag| quote!(#tag));
         let tags = Itertools::intersperse(tags, quote!(|));
 
@@ -127,6 +297,34 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         }
     });
 
+    // The fields of message sets are also decoded from repeated items, so that their occurrences
+    // can't be told apart.
+    let singular_tags = fields
+        .iter()
+        .filter(|(_, field)| !message_set_wire_format && field.is_singular())
+        .flat_map(|(_, field)| field.tags())
+        .map(|tag| quote!(#tag))
+        .collect::<Vec<_>>();
+    let is_singular_field = if singular_tags.is_empty() {
+        quote!()
+    } else {
+        let singular_tags = Itertools::intersperse(singular_tags.into_iter(), quote!(|));
+        quote! {
+            fn is_singular_field(tag: u32) -> bool {
+                match tag {
+                    #(#singular_tags)* => true,
+                    _ => false,
+                }
+            }
+        }
+    };
+
+    let merge_reserved = reserved.merge(&ident);
+    let check_tags = check_tags(
+        fields.iter().flat_map(|(_, field)| field.tags()),
+        &reserved.ranges,
+    );
+
     let struct_name = if fields.is_empty() {
         quote!()
     } else {
@@ -137,35 +335,97 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
 
     let clear = fields
         .iter()
-        .map(|(field_ident, field)| field.clear(quote!(self.#field_ident)));
+        .map(|(field_ident, field)| field.clear(quote!(self.#field_ident)))
+        .chain(
+            skipped_fields
+                .iter()
+                .map(|skipped| quote!(self.#skipped = ::core::default::Default::default())),
+        );
 
-    let default = if is_struct {
-        let default = fields.iter().map(|(field_ident, field)| {
-            let value = field.default();
-            quote!(#field_ident: #value,)
-        });
-        quote! {#ident {
-            #(#default)*
-        }}
+    let eq_semantically = fields
+        .iter()
+        .map(|(field_ident, field)| {
+            field.eq_semantically(quote!(self.#field_ident), quote!(other.#field_ident))
+        })
+        .chain(
+            presence_field
+                .iter()
+                .map(|presence| quote!(self.#presence == other.#presence)),
+        );
+
+    // Tuple struct fields are initialized by index, since they are sorted by tag.
+    let default = fields.iter().map(|(field_ident, field)| {
+        let value = field.default();
+        quote!(#field_ident: #value,)
+    });
+    let presence = presence_field
+        .iter()
+        .map(|presence| quote!(#presence: ::core::default::Default::default(),));
+    let skipped = skipped_fields
+        .iter()
+        .map(|skipped| quote!(#skipped: ::core::default::Default::default(),));
+    let default = quote! {#ident {
+        #(#default)*
+        #(#presence)*
+        #(#skipped)*
+    }};
+
+    let field_values = if field_values {
+        let values = fields
+            .iter()
+            .map(|(field_ident, field)| {
+                let name = field_ident.to_string();
+                let name = name.trim_start_matches("r#");
+                field
+                    .field_values(quote!(self.#field_ident), name)
+                    .map_err(|err| {
+                        err.context(format!("invalid message field {}.{}", ident, field_ident))
+                    })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        quote! {
+            #[allow(deprecated)]
+            impl #impl_generics ::prost::MessageFields for #ident #ty_generics #where_clause {
+                #[allow(unused_mut)]
+                fn field_values(&self) -> ::prost::FieldValues<'_> {
+                    let mut fields = ::prost::alloc::vec::Vec::new();
+                    #(#values)*
+                    ::prost::FieldValues::new(fields)
+                }
+            }
+        }
     } else {
-        let default = fields.iter().map(|(_, field)| {
-            let value = field.default();
-            quote!(#value,)
-        });
-        quote! {#ident (
-            #(#default)*
-        )}
+        quote!()
     };
 
-    let methods = fields
-        .iter()
-        .flat_map(|(field_ident, field)| field.methods(field_ident))
-        .collect::<Vec<_>>();
+    // Messages whose fields all have a bounded encoding get a constant for sizing buffers.
+    let max_encoded_len = if message_set_wire_format {
+        None
+    } else {
+        fields
+            .iter()
+            .map(|(_, field)| field.max_encoded_len())
+            .collect::<Option<Vec<_>>>()
+    };
+    let max_encoded_len = max_encoded_len.map(|lens| {
+        let doc = format!(
+            "The maximum encoded length of a `{}` message, not including a length delimiter.",
+            ident
+        );
+        quote! {
+            #[allow(dead_code, deprecated)]
+            impl #impl_generics #ident #ty_generics #where_clause {
+                #[doc = #doc]
+                pub const MAX_ENCODED_LEN: usize = 0 #(+ #lens)*;
+            }
+        }
+    });
+
     let methods = if methods.is_empty() {
         quote!()
     } else {
         quote! {
-            #[allow(dead_code)]
+            #[allow(dead_code, deprecated)]
             impl #impl_generics #ident #ty_generics #where_clause {
                 #(#methods)*
             }
@@ -173,9 +433,10 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     };
 
     let expanded = quote! {
+        #[allow(deprecated)]
         impl #impl_generics ::prost::Message for #ident #ty_generics #where_clause {
             #[allow(unused_variables)]
-            fn encode_raw(&self, buf: &mut impl ::prost::bytes::BufMut) {
+            fn encode_raw(&self, buf: &mut impl ::prost::buf::BufMut) {
                 #(#encode)*
             }
 
@@ -184,27 +445,42 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                 &mut self,
                 tag: u32,
                 wire_type: ::prost::encoding::wire_type::WireType,
-                buf: &mut impl ::prost::bytes::Buf,
+                buf: &mut impl ::prost::buf::Buf,
                 ctx: ::prost::encoding::DecodeContext,
             ) -> ::core::result::Result<(), ::prost::DecodeError>
             {
                 #struct_name
                 match tag {
+                    #merge_items
                     #(#merge)*
+                    #merge_reserved
                     _ => ::prost::encoding::skip_field(wire_type, tag, buf, ctx),
                 }
             }
 
+            #is_singular_field
+
             #[inline]
             fn encoded_len(&self) -> usize {
                 0 #(+ #encoded_len)*
             }
 
+            #[inline]
+            fn encoded_len_upper_bound(&self) -> usize {
+                0 #(+ #encoded_len_upper_bound)*
+            }
+
+            #[allow(unused_variables)]
+            fn eq_semantically(&self, other: &Self) -> bool {
+                true #(&& #eq_semantically)*
+            }
+
             fn clear(&mut self) {
                 #(#clear;)*
             }
         }
 
+        #[allow(deprecated)]
         impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
             fn default() -> Self {
                 #default
@@ -236,6 +512,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         quote! {
             #expanded
 
+            #[allow(deprecated)]
             impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
                 fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                     let mut builder = #debug_builder;
@@ -250,16 +527,238 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         #expanded
 
         #methods
+
+        #max_encoded_len
+
+        #field_values
+
+        #check_tags
     };
 
     Ok(expanded)
 }
 
+/// Derives `Message` for a `#[prost(transparent)]` wrapper, a tuple struct with a single message
+/// field which is encoded exactly as that field.
+fn try_transparent_message(
+    ident: &Ident,
+    generics: &syn::Generics,
+    variant_data: DataStruct,
+    skip_debug: bool,
+) -> Result<TokenStream, Error> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let field_ty = match variant_data.fields {
+        Fields::Unnamed(FieldsUnnamed { unnamed, .. }) if unnamed.len() == 1 => {
+            if unnamed[0]
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("prost"))
+            {
+                bail!(
+                    "the field of transparent message {} can not have prost attributes",
+                    ident
+                );
+            }
+            unnamed[0].ty.clone()
+        }
+        _ => bail!(
+            "transparent message {} must be a tuple struct with a single field",
+            ident
+        ),
+    };
+
+    let expanded = quote! {
+        impl #impl_generics ::prost::Message for #ident #ty_generics #where_clause {
+            fn encode_raw(&self, buf: &mut impl ::prost::buf::BufMut) {
+                ::prost::Message::encode_raw(&self.0, buf)
+            }
+
+            fn merge_field(
+                &mut self,
+                tag: u32,
+                wire_type: ::prost::encoding::wire_type::WireType,
+                buf: &mut impl ::prost::buf::Buf,
+                ctx: ::prost::encoding::DecodeContext,
+            ) -> ::core::result::Result<(), ::prost::DecodeError>
+            {
+                ::prost::Message::merge_field(&mut self.0, tag, wire_type, buf, ctx)
+            }
+
+            fn is_singular_field(tag: u32) -> bool {
+                <#field_ty as ::prost::Message>::is_singular_field(tag)
+            }
+
+            #[inline]
+            fn encoded_len(&self) -> usize {
+                ::prost::Message::encoded_len(&self.0)
+            }
+
+            #[inline]
+            fn encoded_len_upper_bound(&self) -> usize {
+                ::prost::Message::encoded_len_upper_bound(&self.0)
+            }
+
+            fn eq_semantically(&self, other: &Self) -> bool {
+                ::prost::Message::eq_semantically(&self.0, &other.0)
+            }
+
+            fn clear(&mut self) {
+                ::prost::Message::clear(&mut self.0)
+            }
+        }
+
+        impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
+            fn default() -> Self {
+                #ident(::core::default::Default::default())
+            }
+        }
+    };
+
+    if skip_debug {
+        return Ok(expanded);
+    }
+    Ok(quote! {
+        #expanded
+
+        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.debug_tuple(stringify!(#ident)).field(&self.0).finish()
+            }
+        }
+    })
+}
+
+/// The reserved field numbers of a message, declared with `#[prost(reserved = "2, 9..=11")]`.
+///
+/// Decoding a reserved field fails, unless a `#[prost(reserved_callback = "path::to::fn")]`
+/// function is declared, in which case it is called with the message name and field number
+/// and the field is skipped.
+struct Reserved {
+    ranges: Vec<(u32, u32)>,
+    callback: Option<Path>,
+}
+
+impl Reserved {
+    fn from_attrs(attrs: &[Attribute]) -> Result<Reserved, Error> {
+        let mut reserved = Reserved {
+            ranges: Vec::new(),
+            callback: None,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("prost")) {
+            let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+            for meta in metas {
+                let path = meta.path();
+                if !path.is_ident("reserved") && !path.is_ident("reserved_callback") {
+                    continue;
+                }
+                let Meta::NameValue(MetaNameValue {
+                    value:
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(lit), ..
+                        }),
+                    ..
+                }) = &meta
+                else {
+                    bail!(syn::Error::new_spanned(
+                        &meta,
+                        format!(
+                            "invalid {} attribute: expected a string value",
+                            quote!(#path)
+                        ),
+                    ));
+                };
+                if path.is_ident("reserved") {
+                    for range in lit.value().split(',') {
+                        let range = range.trim();
+                        let (start, end) = range.split_once("..=").unwrap_or((range, range));
+                        let (Ok(start), Ok(end)) = (start.trim().parse(), end.trim().parse())
+                        else {
+                            bail!(syn::Error::new_spanned(
+                                lit,
+                                format!("invalid reserved range: {:?}", range),
+                            ));
+                        };
+                        reserved.ranges.push((start, end));
+                    }
+                } else {
+                    if reserved.callback.is_some() {
+                        bail!("duplicate reserved_callback attributes");
+                    }
+                    reserved.callback = Some(lit.parse()?);
+                }
+            }
+        }
+        Ok(reserved)
+    }
+
+    fn contains(&self, tag: u32) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&tag))
+    }
+
+    /// Returns the match arm handling reserved tags in `Message::merge_field`.
+    fn merge(&self, ident: &Ident) -> TokenStream {
+        if self.ranges.is_empty() {
+            return quote!();
+        }
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|&(start, end)| quote!(#start..=#end));
+        let ranges = Itertools::intersperse(ranges, quote!(|));
+        let handle = match self.callback {
+            Some(ref callback) => quote! {{
+                #callback(stringify!(#ident), tag);
+                ::prost::encoding::skip_field(wire_type, tag, buf, ctx)
+            }},
+            None => quote! {
+                ::core::result::Result::Err(::prost::DecodeError::new(
+                    concat!("reserved field number used in ", stringify!(#ident)),
+                ))
+            },
+        };
+        quote! {
+            #(#ranges)* => #handle,
+        }
+    }
+}
+
+/// Returns a constant item which checks that the tags are valid field numbers, distinct, and not
+/// reserved, if some of them are constants which can not be checked when deriving.
+fn check_tags(tags: impl Iterator<Item = Tag>, reserved: &[(u32, u32)]) -> TokenStream {
+    let tags = tags.collect::<Vec<_>>();
+    if tags.iter().all(|tag| tag.number().is_some()) {
+        return quote!();
+    }
+    let reserved = reserved.iter().map(|&(start, end)| quote!((#start, #end)));
+    quote! {
+        const _: () = ::prost::encoding::check_tags(&[#(#tags),*], &[#(#reserved),*]);
+    }
+}
+
+/// Returns whether the type is `PhantomData`, by any path.
+fn is_phantom_data(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PhantomData"),
+        Type::Group(group) => is_phantom_data(&group.elem),
+        Type::Paren(paren) => is_phantom_data(&paren.elem),
+        _ => false,
+    }
+}
+
 #[proc_macro_derive(Message, attributes(prost))]
 pub fn message(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     try_message(input.into()).unwrap().into()
 }
 
+/// The number of variants above which enumerations are converted from `i32` with a lookup table.
+const LOOKUP_TABLE_THRESHOLD: usize = 64;
+
 fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
     let input: DeriveInput = syn::parse2(input)?;
     let ident = input.ident;
@@ -301,14 +800,82 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
 
     let default = variants[0].0.clone();
 
-    let is_valid = variants.iter().map(|(_, value)| quote!(#value => true));
-    let from = variants
-        .iter()
-        .map(|(variant, value)| quote!(#value => ::core::option::Option::Some(#ident::#variant)));
+    // Check the values which are known at expansion time, so that out of range values are not
+    // truncated by the `as i32` conversion.
+    let mut values = Vec::with_capacity(variants.len());
+    for (variant, expr) in &variants {
+        match discriminant_value(expr) {
+            Some(value) => match i32::try_from(value) {
+                Ok(value) => values.push((value, variant)),
+                Err(_) => bail!(
+                    "value {} of enumeration variant {}::{} is out of range of i32",
+                    value,
+                    ident,
+                    variant
+                ),
+            },
+            None => break,
+        }
+    }
+    let known = values.len() == variants.len();
+    values.sort_by_key(|&(value, _)| value);
 
-    let try_from = variants
+    // Large enumerations are looked up in a table sorted by value, since a match with an arm per
+    // variant is slow to compile.
+    let lookup_table = known && variants.len() > LOOKUP_TABLE_THRESHOLD;
+
+    let try_from = if lookup_table {
+        let len = variants.len();
+        let table = values.iter().map(|(_, variant)| quote!(#ident::#variant));
+        quote! {
+            static VARIANTS: [#ident; #len] = [#(#table),*];
+            match VARIANTS.binary_search_by_key(&value, |variant| *variant as i32) {
+                ::core::result::Result::Ok(index) => ::core::result::Result::Ok(VARIANTS[index]),
+                ::core::result::Result::Err(_) => {
+                    ::core::result::Result::Err(::prost::UnknownEnumValue(value))
+                }
+            }
+        }
+    } else {
+        let arms = variants
+            .iter()
+            .map(|(variant, value)| quote!(#value => ::core::result::Result::Ok(#ident::#variant)));
+        quote! {
+            match value {
+                #(#arms,)*
+                _ => ::core::result::Result::Err(::prost::UnknownEnumValue(value)),
+            }
+        }
+    };
+
+    // Match ranges of consecutive values, so that large enumerations don't need a pattern per
+    // variant.
+    let ranges = values
         .iter()
-        .map(|(variant, value)| quote!(#value => ::core::result::Result::Ok(#ident::#variant)));
+        .map(|&(value, _)| (value, value))
+        .coalesce(|(start, end), (next, _)| {
+            if end.checked_add(1) == Some(next) {
+                Ok((start, next))
+            } else {
+                Err(((start, end), (next, next)))
+            }
+        })
+        .map(|(start, end)| {
+            if start == end {
+                quote!(#start)
+            } else {
+                quote!(#start..=#end)
+            }
+        })
+        .collect::<Vec<_>>();
+    let is_valid = if !known {
+        let values = variants.iter().map(|(_, value)| value);
+        quote!(::core::matches!(value, #(#values)|*))
+    } else if ranges.len() <= LOOKUP_TABLE_THRESHOLD {
+        quote!(::core::matches!(value, #(#ranges)|*))
+    } else {
+        quote!(<#ident as ::core::convert::TryFrom<i32>>::try_from(value).is_ok())
+    };
 
     let is_valid_doc = format!("Returns `true` if `value` is a variant of `{}`.", ident);
     let from_i32_doc = format!(
@@ -317,45 +884,40 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
     );
 
     let expanded = quote! {
+        #[allow(deprecated)]
         impl #impl_generics #ident #ty_generics #where_clause {
             #[doc=#is_valid_doc]
             pub fn is_valid(value: i32) -> bool {
-                match value {
-                    #(#is_valid,)*
-                    _ => false,
-                }
+                #is_valid
             }
 
             #[deprecated = "Use the TryFrom<i32> implementation instead"]
             #[doc=#from_i32_doc]
             pub fn from_i32(value: i32) -> ::core::option::Option<#ident> {
-                match value {
-                    #(#from,)*
-                    _ => ::core::option::Option::None,
-                }
+                ::core::convert::TryFrom::try_from(value).ok()
             }
         }
 
+        #[allow(deprecated)]
         impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
             fn default() -> #ident {
                 #ident::#default
             }
         }
 
+        #[allow(deprecated)]
         impl #impl_generics ::core::convert::From::<#ident> for i32 #ty_generics #where_clause {
             fn from(value: #ident) -> i32 {
                 value as i32
             }
         }
 
+        #[allow(deprecated)]
         impl #impl_generics ::core::convert::TryFrom::<i32> for #ident #ty_generics #where_clause {
             type Error = ::prost::UnknownEnumValue;
 
             fn try_from(value: i32) -> ::core::result::Result<#ident, ::prost::UnknownEnumValue> {
-                match value {
-                    #(#try_from,)*
-                    _ => ::core::result::Result::Err(::prost::UnknownEnumValue(value)),
-                }
+                #try_from
             }
         }
     };
@@ -363,6 +925,25 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
     Ok(expanded)
 }
 
+/// Returns the value of an enumeration discriminant which is an integer literal, or a negated
+/// one.
+fn discriminant_value(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse().ok(),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => discriminant_value(expr)?.checked_neg(),
+        Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+            discriminant_value(expr)
+        }
+        _ => None,
+    }
+}
+
 #[proc_macro_derive(Enumeration, attributes(prost))]
 pub fn enumeration(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     try_enumeration(input.into()).unwrap().into()
@@ -376,9 +957,15 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
     syn::custom_keyword!(skip_debug);
     let skip_debug = input
         .attrs
-        .into_iter()
+        .iter()
         .any(|a| a.path().is_ident("prost") && a.parse_args::<skip_debug>().is_ok());
 
+    syn::custom_keyword!(field_values);
+    let field_values = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<field_values>().is_ok());
+
     let variants = match input.data {
         Data::Enum(DataEnum { variants, .. }) => variants,
         Data::Struct(..) => bail!("Oneof can not be derived for a struct"),
@@ -436,8 +1023,19 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
     });
 
     let merge = fields.iter().map(|(variant_ident, field)| {
-        let tag = field.tags()[0];
-        let merge = field.merge(quote!(value));
+        let tag = field.tags().swap_remove(0);
+        // An undeclared value of a closed enum leaves the oneof as is.
+        if let Some(decode) = field.decode_closed_enum() {
+            return quote! {
+                #tag => #decode.map(|value| {
+                    if let ::core::option::Option::Some(value) = value {
+                        *field = ::core::option::Option::Some(#ident::#variant_ident(value));
+                    }
+                })
+            };
+        }
+        let merge = field.merge(quote!(value), quote!(#variant_ident));
+        let empty = field.empty();
         quote! {
             #tag => {
                 match field {
@@ -445,7 +1043,7 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                         #merge
                     },
                     _ => {
-                        let mut owned_value = ::core::default::Default::default();
+                        let mut owned_value = #empty;
                         let value = &mut owned_value;
                         #merge.map(|_| *field = ::core::option::Option::Some(#ident::#variant_ident(owned_value)))
                     },
@@ -459,10 +1057,16 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         quote!(#ident::#variant_ident(ref value) => #encoded_len)
     });
 
+    let eq_semantically = fields.iter().map(|(variant_ident, field)| {
+        let eq = field.eq_semantically(quote!(*a), quote!(*b));
+        quote!((#ident::#variant_ident(ref a), #ident::#variant_ident(ref b)) => #eq)
+    });
+
     let expanded = quote! {
+        #[allow(deprecated)]
         impl #impl_generics #ident #ty_generics #where_clause {
             /// Encodes the message to a buffer.
-            pub fn encode(&self, buf: &mut impl ::prost::bytes::BufMut) {
+            pub fn encode(&self, buf: &mut impl ::prost::buf::BufMut) {
                 match *self {
                     #(#encode,)*
                 }
@@ -473,13 +1077,19 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                 field: &mut ::core::option::Option<#ident #ty_generics>,
                 tag: u32,
                 wire_type: ::prost::encoding::wire_type::WireType,
-                buf: &mut impl ::prost::bytes::Buf,
+                buf: &mut impl ::prost::buf::Buf,
                 ctx: ::prost::encoding::DecodeContext,
             ) -> ::core::result::Result<(), ::prost::DecodeError>
             {
+                // Reported to the observer along with the variant of undeclared enum values.
+                #[allow(dead_code)]
+                const STRUCT_NAME: &'static str = stringify!(#ident);
                 match tag {
                     #(#merge,)*
-                    _ => unreachable!(concat!("invalid ", stringify!(#ident), " tag: {}"), tag),
+                    _ => ::core::result::Result::Err(::prost::DecodeError::new(::prost::alloc::format!(
+                        concat!("invalid ", stringify!(#ident), " tag: {}"),
+                        tag,
+                    ))),
                 }
             }
 
@@ -490,6 +1100,16 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                     #(#encoded_len,)*
                 }
             }
+
+            /// Returns `true` if the oneof is equal to `other`, treating `NaN` float and double
+            /// values as equal to each other.
+            #[allow(unreachable_patterns)]
+            pub fn eq_semantically(&self, other: &Self) -> bool {
+                match (self, other) {
+                    #(#eq_semantically,)*
+                    _ => false,
+                }
+            }
         }
 
     };
@@ -508,6 +1128,7 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         quote! {
             #expanded
 
+            #[allow(deprecated)]
             impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
                 fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                     match *self {
@@ -518,7 +1139,53 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         }
     };
 
-    Ok(expanded)
+    let field_value = if field_values {
+        let arms = fields
+            .iter()
+            .map(|(variant_ident, field)| {
+                let name = variant_ident.to_string();
+                let name = name.trim_start_matches("r#");
+                let tag = field.tags().swap_remove(0);
+                let value = field.field_value(quote!(value)).map_err(|err| {
+                    err.context(format!(
+                        "invalid oneof variant {}::{}",
+                        ident, variant_ident
+                    ))
+                })?;
+                Ok(quote! {
+                    #ident::#variant_ident(ref value) => {
+                        const META: ::prost::FieldDescriptorMeta =
+                            ::prost::FieldDescriptorMeta::new(#name, #tag);
+                        (&META, #value)
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        quote! {
+            #[allow(deprecated)]
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// Returns the description and the value of the field held by the oneof.
+                pub fn field_value(
+                    &self,
+                ) -> (&'static ::prost::FieldDescriptorMeta, ::prost::FieldValueRef<'_>) {
+                    match *self {
+                        #(#arms,)*
+                    }
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let check_tags = check_tags(fields.iter().flat_map(|(_, field)| field.tags()), &[]);
+    Ok(quote! {
+        #expanded
+
+        #field_value
+
+        #check_tags
+    })
 }
 
 #[proc_macro_derive(Oneof, attributes(prost))]
@@ -528,9 +1195,75 @@ pub fn oneof(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
 #[cfg(test)]
 mod test {
-    use crate::{try_message, try_oneof};
+    use crate::{try_enumeration, try_message, try_oneof};
     use quote::quote;
 
+    #[test]
+    fn test_rejects_out_of_range_enumeration_values() {
+        let output = try_enumeration(quote!(
+            enum Invalid {
+                Zero = 0,
+                Big = 2147483648,
+            }
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject out of range value")
+                .to_string(),
+            "value 2147483648 of enumeration variant Invalid::Big is out of range of i32"
+        );
+
+        let output = try_enumeration(quote!(
+            enum Valid {
+                Min = -2147483648,
+                Max = 2147483647,
+            }
+        ));
+        assert!(output.is_ok());
+    }
+
+    #[test]
+    fn test_rejects_malformed_reserved_attributes() {
+        let output = try_message(quote!(
+            #[prost(reserved = 5)]
+            struct Invalid {}
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject non-string reserved attribute")
+                .to_string(),
+            "invalid reserved attribute: expected a string value"
+        );
+
+        let output = try_message(quote!(
+            #[prost(reserved_callback)]
+            struct Invalid {}
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject reserved_callback without a value")
+                .to_string(),
+            "invalid reserved_callback attribute: expected a string value"
+        );
+
+        let output = try_message(quote!(
+            #[prost(reserved = "1, 4..=x")]
+            struct Invalid {}
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject invalid reserved range")
+                .to_string(),
+            "invalid reserved range: \"4..=x\""
+        );
+
+        let output = try_message(quote!(
+            #[prost(reserved = )]
+            struct Invalid {}
+        ));
+        assert!(output.is_err(), "did not reject unparsable attribute");
+    }
+
     #[test]
     fn test_rejects_colliding_message_fields() {
         let output = try_message(quote!(
@@ -549,6 +1282,73 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_rejects_colliding_path_tags() {
+        let output = try_message(quote!(
+            struct Invalid {
+                #[prost(bool, tag = "crate::tags::A")]
+                a: bool,
+                #[prost(bool, tag = "crate::tags::A")]
+                b: bool,
+            }
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject colliding path tags")
+                .to_string(),
+            "message Invalid has multiple fields with tag crate::tags::A"
+        );
+    }
+
+    #[test]
+    fn test_rejects_inferred_tag_after_path_tag() {
+        let output = try_message(quote!(
+            struct Invalid {
+                #[prost(bool, tag = "crate::tags::A")]
+                a: bool,
+                #[prost(bool)]
+                b: bool,
+            }
+        ));
+        assert!(output.is_err());
+    }
+
+    #[test]
+    fn test_rejects_closed_non_enumeration() {
+        let output = try_message(quote!(
+            struct Invalid {
+                #[prost(int32, closed, tag = "1")]
+                level: i32,
+            }
+        ));
+        assert_eq!(
+            format!(
+                "{:#}",
+                output.expect_err("did not reject closed int32 field")
+            ),
+            "invalid message field Invalid.level: \
+             closed attribute may only be applied to enumeration fields"
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_enumeration_default() {
+        let output = try_message(quote!(
+            struct Invalid {
+                #[prost(enumeration = "Level", default = "Level::High", tag = "1")]
+                level: i32,
+            }
+        ));
+        assert_eq!(
+            format!(
+                "{:#}",
+                output.expect_err("did not reject invalid enumeration default")
+            ),
+            "invalid message field Invalid.level: invalid enumeration default value: \
+             \"Level::High\" is not a variant name"
+        );
+    }
+
     #[test]
     fn test_rejects_colliding_oneof_variants() {
         let output = try_oneof(quote!(
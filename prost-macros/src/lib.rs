@@ -0,0 +1,165 @@
+#![doc(html_root_url = "https://docs.rs/prost-macros/0.13.4")]
+
+//! Generates `prost` message types from inline Protocol Buffers definitions.
+//!
+//! The [`proto!`] macro compiles `.proto` definitions with `prost-build` while the crate using it
+//! is compiled, so that tests and small tools can use generated types without a `build.rs`.
+//! Like `prost-build`, it runs `protoc`, which is found as described in the
+//! [`prost-build` documentation](https://docs.rs/prost-build/#sourcing-protoc).
+
+extern crate proc_macro;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token};
+
+/// Generates the message types of inline `.proto` definitions.
+///
+/// The definitions are either given as a string literal, or read from a file with
+/// `path = "..."`, relative to the directory of the crate's `Cargo.toml`. Inline definitions may
+/// import files relative to the same directory.
+///
+/// The generated code of each package is placed in nested modules named after the package, as
+/// with the include file of `prost-build`. The macro should be invoked in a module where these
+/// modules resolve each other, usually the crate root or a module of its own.
+///
+/// ```rust,ignore
+/// prost_macros::proto!(
+///     r#"
+///     syntax = "proto3";
+///     package shapes;
+///
+///     message Point {
+///       int32 x = 1;
+///       int32 y = 2;
+///     }
+///     "#
+/// );
+///
+/// let point = shapes::Point { x: 1, y: 2 };
+/// ```
+#[proc_macro]
+pub fn proto(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as Input);
+    let span = input.literal().span();
+    match input.generate() {
+        Ok(tokens) => tokens.into(),
+        Err(error) => syn::Error::new(
+            span,
+            format!("failed to compile protobuf definitions: {}", error),
+        )
+        .to_compile_error()
+        .into(),
+    }
+}
+
+/// The definitions given to [`proto!`].
+enum Input {
+    /// The contents of a `.proto` file.
+    Source(LitStr),
+    /// The path of a `.proto` file, relative to the manifest directory.
+    Path(LitStr),
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Ident) {
+            let ident = input.parse::<Ident>()?;
+            if ident != "path" {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "expected `path = \"...\"` or a string literal",
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            Ok(Input::Path(input.parse()?))
+        } else {
+            Ok(Input::Source(input.parse()?))
+        }
+    }
+}
+
+impl Input {
+    fn literal(&self) -> &LitStr {
+        match self {
+            Input::Source(literal) | Input::Path(literal) => literal,
+        }
+    }
+
+    fn generate(&self) -> io::Result<TokenStream> {
+        let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default());
+        let tempdir = tempfile::Builder::new().prefix("prost-macros").tempdir()?;
+
+        let (file, includes, dependency) = match self {
+            Input::Source(source) => {
+                let file = tempdir.path().join("inline.proto");
+                fs::write(&file, source.value())?;
+                (file, vec![tempdir.path().to_owned(), manifest_dir], None)
+            }
+            Input::Path(path) => {
+                let file = manifest_dir.join(path.value());
+                let include = file.parent().unwrap_or(Path::new(".")).to_owned();
+                let dependency = file.to_str().map(|file| quote!(include_bytes!(#file)));
+                (file, vec![include], dependency)
+            }
+        };
+
+        let mut config = prost_build::Config::new();
+        config.out_dir(tempdir.path());
+        let fds = config.load_fds(&[file], &includes)?;
+        let modules = config.compile_fds_to_tokens(fds)?;
+
+        let mut root = ModuleTree::default();
+        for (module, tokens) in modules {
+            root.insert(module.parts(), tokens);
+        }
+        let code = root.into_tokens();
+        // Rebuild when the file changes.
+        let dependency = dependency.map(|bytes| quote!(const _: &[u8] = #bytes;));
+        Ok(quote! {
+            #dependency
+            #code
+        })
+    }
+}
+
+/// The generated modules, nested by package.
+#[derive(Default)]
+struct ModuleTree {
+    tokens: TokenStream,
+    children: BTreeMap<String, ModuleTree>,
+}
+
+impl ModuleTree {
+    fn insert<'a>(&mut self, mut parts: impl Iterator<Item = &'a str>, tokens: TokenStream) {
+        match parts.next() {
+            Some(part) => self
+                .children
+                .entry(part.to_owned())
+                .or_default()
+                .insert(parts, tokens),
+            None => self.tokens.extend(tokens),
+        }
+    }
+
+    fn into_tokens(self) -> TokenStream {
+        let mut tokens = self.tokens;
+        for (name, child) in self.children {
+            let ident = format_ident!("{}", name);
+            let child = child.into_tokens();
+            tokens.extend(quote! {
+                pub mod #ident {
+                    #child
+                }
+            });
+        }
+        tokens
+    }
+}
@@ -12,23 +12,50 @@ impl Any {
         Ok(Any { type_url, value })
     }
 
+    /// Serialize the given message type `M` as [`Any`], with a type URL in the given domain,
+    /// e.g. `type.example.com/my.package.Message` for the `type.example.com` domain.
+    pub fn from_msg_with_domain<M>(domain: &str, msg: &M) -> Result<Self, EncodeError>
+    where
+        M: Name,
+    {
+        let type_url = format!("{}/{}", domain.trim_end_matches('/'), M::full_name());
+        let mut value = Vec::new();
+        Message::encode(msg, &mut value)?;
+        Ok(Any { type_url, value })
+    }
+
+    /// Returns the fully-qualified name of the type of the serialized message, parsed from the
+    /// last segment of the type URL, e.g. `google.protobuf.Duration`.
+    ///
+    /// Returns `None` if the type URL is not valid.
+    pub fn type_name(&self) -> core::option::Option<&str> {
+        TypeUrl::new(&self.type_url).map(|url| url.full_name)
+    }
+
+    /// Returns `true` if the serialized message is of type `M`, regardless of the domain of the
+    /// type URL.
+    pub fn is<M>(&self) -> bool
+    where
+        M: Name,
+    {
+        let expected_type_url = M::type_url();
+        match (TypeUrl::new(&expected_type_url), self.type_name()) {
+            (Some(expected), Some(actual)) => expected.full_name == actual,
+            _ => false,
+        }
+    }
+
     /// Decode the given message type `M` from [`Any`], validating that it has
     /// the expected type URL.
     pub fn to_msg<M>(&self) -> Result<M, DecodeError>
     where
         M: Default + Name + Sized,
     {
-        let expected_type_url = M::type_url();
-
-        if let (Some(expected), Some(actual)) = (
-            TypeUrl::new(&expected_type_url),
-            TypeUrl::new(&self.type_url),
-        ) {
-            if expected == actual {
-                return M::decode(self.value.as_slice());
-            }
+        if self.is::<M>() {
+            return M::decode(self.value.as_slice());
         }
 
+        let expected_type_url = M::type_url();
         let mut err = DecodeError::new(format!(
             "expected type URL: \"{}\" (got: \"{}\")",
             expected_type_url, &self.type_url
@@ -66,4 +93,22 @@ mod tests {
         // Wrong type URL
         assert!(any.to_msg::<Duration>().is_err());
     }
+
+    #[test]
+    fn check_any_type_name() {
+        let message = Timestamp::date(2000, 1, 1).unwrap();
+        let any = Any::from_msg_with_domain("type.example.com/", &message).unwrap();
+        assert_eq!(&any.type_url, "type.example.com/google.protobuf.Timestamp");
+        assert_eq!(any.type_name(), Some("google.protobuf.Timestamp"));
+        assert!(any.is::<Timestamp>());
+        assert!(!any.is::<Duration>());
+        assert_eq!(any.to_msg::<Timestamp>().unwrap(), message);
+
+        let invalid = Any {
+            type_url: String::from("google.protobuf.Timestamp"),
+            value: Vec::new(),
+        };
+        assert_eq!(invalid.type_name(), None);
+        assert!(!invalid.is::<Timestamp>());
+    }
 }
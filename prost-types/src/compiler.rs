@@ -132,6 +132,7 @@ pub mod code_generator_response {
         /// point is used, this information will be appropriately offset and inserted
         /// into the code generation metadata for the generated files.
         #[prost(message, optional, tag = "16")]
+        #[cfg(not(feature = "no-source-code-info"))]
         pub generated_code_info: ::core::option::Option<super::super::GeneratedCodeInfo>,
     }
     /// Sync with code_generator.h.
@@ -149,7 +150,9 @@ pub mod code_generator_response {
     )]
     #[repr(i32)]
     pub enum Feature {
+        #[doc(alias = "FEATURE_NONE")]
         None = 0,
+        #[doc(alias = "FEATURE_PROTO3_OPTIONAL")]
         Proto3Optional = 1,
     }
     impl Feature {
@@ -0,0 +1,310 @@
+//! Lookups and traversals of file descriptor sets.
+
+use core::fmt;
+
+use prost::alloc::collections::BTreeMap;
+use prost::alloc::string::{String, ToString};
+use prost::alloc::vec::Vec;
+
+use crate::{
+    DescriptorProto, EnumDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    ServiceDescriptorProto,
+};
+
+/// A file descriptor set traversal error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DescriptorSetError {
+    /// Indicates that a file is not part of the set.
+    UnknownFile(String),
+
+    /// Indicates that a file imports a file which is not part of the set.
+    MissingDependency {
+        /// The name of the importing file.
+        file: String,
+        /// The name of the missing file.
+        dependency: String,
+    },
+
+    /// Indicates that a file imports itself, directly or through its dependencies.
+    DependencyCycle(String),
+}
+
+impl fmt::Display for DescriptorSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptorSetError::UnknownFile(file) => write!(f, "unknown file: {}", file),
+            DescriptorSetError::MissingDependency { file, dependency } => {
+                write!(f, "missing dependency {} of file {}", dependency, file)
+            }
+            DescriptorSetError::DependencyCycle(file) => {
+                write!(f, "file {} depends on itself", file)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DescriptorSetError {}
+
+impl FileDescriptorSet {
+    /// Returns the file with the given name, such as `google/protobuf/any.proto`.
+    pub fn find_file(&self, name: &str) -> Option<&FileDescriptorProto> {
+        self.file.iter().find(|file| file.name() == name)
+    }
+
+    /// Returns the message with the given fully-qualified name, such as `.my.package.Msg` or
+    /// `.my.package.Msg.Nested`. The leading dot is optional.
+    pub fn find_message(&self, name: &str) -> Option<&DescriptorProto> {
+        self.file.iter().find_map(|file| file.find_message(name))
+    }
+
+    /// Returns the enum with the given fully-qualified name, such as `.my.package.Enum` or
+    /// `.my.package.Msg.Enum`. The leading dot is optional.
+    pub fn find_enum(&self, name: &str) -> Option<&EnumDescriptorProto> {
+        self.file.iter().find_map(|file| file.find_enum(name))
+    }
+
+    /// Returns the service with the given fully-qualified name, such as `.my.package.Service`.
+    /// The leading dot is optional.
+    pub fn find_service(&self, name: &str) -> Option<&ServiceDescriptorProto> {
+        self.file.iter().find_map(|file| file.find_service(name))
+    }
+
+    /// Returns the file with the given name and all the files it imports, directly or
+    /// indirectly, ordered so that every file comes after its dependencies.
+    ///
+    /// The result is a self-contained set of files, as required to build a descriptor pool or
+    /// to run a code generator for a single file.
+    pub fn dependency_closure(
+        &self,
+        name: &str,
+    ) -> Result<Vec<&FileDescriptorProto>, DescriptorSetError> {
+        let file = self
+            .find_file(name)
+            .ok_or_else(|| DescriptorSetError::UnknownFile(name.to_string()))?;
+        let mut sort = TopologicalSort::new(self);
+        sort.visit(file)?;
+        Ok(sort.sorted)
+    }
+
+    /// Returns all the files of the set, ordered so that every file comes after its
+    /// dependencies. Files which do not depend on each other keep their order in the set.
+    pub fn topological_sort(&self) -> Result<Vec<&FileDescriptorProto>, DescriptorSetError> {
+        let mut sort = TopologicalSort::new(self);
+        for file in &self.file {
+            sort.visit(file)?;
+        }
+        Ok(sort.sorted)
+    }
+}
+
+impl FileDescriptorProto {
+    /// Returns the message of this file with the given fully-qualified name, such as
+    /// `.my.package.Msg` or `.my.package.Msg.Nested`. The leading dot is optional.
+    pub fn find_message(&self, name: &str) -> Option<&DescriptorProto> {
+        let (path, name) = self.local_path(name)?;
+        match path {
+            Some(path) => self.find_nested(path)?.nested_type.iter(),
+            None => self.message_type.iter(),
+        }
+        .find(|message| message.name() == name)
+    }
+
+    /// Returns the enum of this file with the given fully-qualified name, such as
+    /// `.my.package.Enum` or `.my.package.Msg.Enum`. The leading dot is optional.
+    pub fn find_enum(&self, name: &str) -> Option<&EnumDescriptorProto> {
+        let (path, name) = self.local_path(name)?;
+        match path {
+            Some(path) => self.find_nested(path)?.enum_type.iter(),
+            None => self.enum_type.iter(),
+        }
+        .find(|desc| desc.name() == name)
+    }
+
+    /// Returns the service of this file with the given fully-qualified name, such as
+    /// `.my.package.Service`. The leading dot is optional.
+    pub fn find_service(&self, name: &str) -> Option<&ServiceDescriptorProto> {
+        match self.local_path(name)? {
+            (None, name) => self.service.iter().find(|service| service.name() == name),
+            (Some(_), _) => None,
+        }
+    }
+
+    /// Splits a fully-qualified name into the path of its parent message, if any, and its
+    /// simple name, if it belongs to the package of this file.
+    fn local_path<'a>(&self, name: &'a str) -> Option<(Option<&'a str>, &'a str)> {
+        let name = name.strip_prefix('.').unwrap_or(name);
+        let name = match self.package() {
+            "" => name,
+            package => name.strip_prefix(package)?.strip_prefix('.')?,
+        };
+        Some(match name.rsplit_once('.') {
+            Some((path, name)) => (Some(path), name),
+            None => (None, name),
+        })
+    }
+
+    /// Returns the message with the given dot-separated path relative to the package.
+    fn find_nested(&self, path: &str) -> Option<&DescriptorProto> {
+        let mut names = path.split('.');
+        let first = names.next()?;
+        let mut message = self.message_type.iter().find(|m| m.name() == first)?;
+        for name in names {
+            message = message.nested_type.iter().find(|m| m.name() == name)?;
+        }
+        Some(message)
+    }
+}
+
+/// A depth-first traversal of the dependencies of files.
+struct TopologicalSort<'a> {
+    set: &'a FileDescriptorSet,
+    /// Whether each visited file is done, or still being visited.
+    visited: BTreeMap<&'a str, bool>,
+    sorted: Vec<&'a FileDescriptorProto>,
+}
+
+impl<'a> TopologicalSort<'a> {
+    fn new(set: &'a FileDescriptorSet) -> Self {
+        TopologicalSort {
+            set,
+            visited: BTreeMap::new(),
+            sorted: Vec::new(),
+        }
+    }
+
+    fn visit(&mut self, file: &'a FileDescriptorProto) -> Result<(), DescriptorSetError> {
+        match self.visited.get(file.name()) {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                return Err(DescriptorSetError::DependencyCycle(file.name().to_string()))
+            }
+            None => {}
+        }
+        self.visited.insert(file.name(), false);
+        for dependency in &file.dependency {
+            let dependency = self.set.find_file(dependency).ok_or_else(|| {
+                DescriptorSetError::MissingDependency {
+                    file: file.name().to_string(),
+                    dependency: dependency.clone(),
+                }
+            })?;
+            self.visit(dependency)?;
+        }
+        self.visited.insert(file.name(), true);
+        self.sorted.push(file);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::alloc::vec;
+
+    fn file(name: &str, package: &str, dependencies: &[&str]) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_string()),
+            package: Some(package.to_string()),
+            dependency: dependencies.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn message(name: &str, nested: Vec<DescriptorProto>) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_string()),
+            nested_type: nested,
+            ..Default::default()
+        }
+    }
+
+    fn names<'a>(files: &[&'a FileDescriptorProto]) -> Vec<&'a str> {
+        files.iter().map(|file| file.name()).collect()
+    }
+
+    #[test]
+    fn test_find() {
+        let mut outer = file("outer.proto", "my.pkg", &[]);
+        outer.message_type = vec![message("Msg", vec![message("Nested", vec![])])];
+        outer.message_type[0].enum_type = vec![EnumDescriptorProto {
+            name: Some("Kind".to_string()),
+            ..Default::default()
+        }];
+        outer.service = vec![ServiceDescriptorProto {
+            name: Some("Service".to_string()),
+            ..Default::default()
+        }];
+        let mut root = file("root.proto", "", &[]);
+        root.message_type = vec![message("Root", vec![])];
+        let set = FileDescriptorSet {
+            file: vec![outer, root],
+        };
+
+        assert_eq!(set.find_file("root.proto").unwrap().name(), "root.proto");
+        assert!(set.find_file("missing.proto").is_none());
+        assert_eq!(set.find_message(".my.pkg.Msg").unwrap().name(), "Msg");
+        assert_eq!(
+            set.find_message("my.pkg.Msg.Nested").unwrap().name(),
+            "Nested"
+        );
+        assert_eq!(set.find_message(".Root").unwrap().name(), "Root");
+        assert!(set.find_message(".my.pkg.Nested").is_none());
+        assert!(set.find_message(".my.pkgMsg").is_none());
+        assert!(set.find_message(".my.pkg.Msg.Kind").is_none());
+        assert_eq!(set.find_enum(".my.pkg.Msg.Kind").unwrap().name(), "Kind");
+        assert_eq!(
+            set.find_service(".my.pkg.Service").unwrap().name(),
+            "Service"
+        );
+        assert!(set.find_service(".my.pkg.Msg.Service").is_none());
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        let set = FileDescriptorSet {
+            file: vec![
+                file("a.proto", "", &["b.proto", "c.proto"]),
+                file("b.proto", "", &["c.proto"]),
+                file("c.proto", "", &[]),
+                file("d.proto", "", &[]),
+            ],
+        };
+        assert_eq!(
+            names(&set.topological_sort().unwrap()),
+            ["c.proto", "b.proto", "a.proto", "d.proto"]
+        );
+        assert_eq!(
+            names(&set.dependency_closure("b.proto").unwrap()),
+            ["c.proto", "b.proto"]
+        );
+        assert_eq!(
+            set.dependency_closure("e.proto"),
+            Err(DescriptorSetError::UnknownFile("e.proto".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_dependencies() {
+        let set = FileDescriptorSet {
+            file: vec![file("a.proto", "", &["b.proto"])],
+        };
+        assert_eq!(
+            set.topological_sort().unwrap_err().to_string(),
+            "missing dependency b.proto of file a.proto"
+        );
+
+        let set = FileDescriptorSet {
+            file: vec![
+                file("a.proto", "", &["b.proto"]),
+                file("b.proto", "", &["a.proto"]),
+            ],
+        };
+        assert_eq!(
+            set.topological_sort(),
+            Err(DescriptorSetError::DependencyCycle("a.proto".to_string()))
+        );
+    }
+}
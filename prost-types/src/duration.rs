@@ -1,5 +1,8 @@
 use super::*;
 
+use core::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use core::option::Option;
+
 #[cfg(feature = "std")]
 impl std::hash::Hash for Duration {
     fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
@@ -69,6 +72,131 @@ impl Duration {
         result.normalize();
         result
     }
+
+    /// Adds two durations, returning `None` if the sum is out of range.
+    ///
+    /// The result is normalized.
+    pub fn checked_add(self, rhs: Duration) -> Option<Duration> {
+        Duration::from_nanos(self.total_nanos() + rhs.total_nanos())
+    }
+
+    /// Subtracts `rhs` from the duration, returning `None` if the difference is out of range.
+    ///
+    /// The result is normalized.
+    pub fn checked_sub(self, rhs: Duration) -> Option<Duration> {
+        Duration::from_nanos(self.total_nanos() - rhs.total_nanos())
+    }
+
+    /// Negates the duration, returning `None` if the negation is out of range.
+    ///
+    /// The result is normalized.
+    pub fn checked_neg(self) -> Option<Duration> {
+        Duration::from_nanos(-self.total_nanos())
+    }
+
+    /// Adds two durations, saturating at the bounds of the range of durations.
+    ///
+    /// The result is normalized.
+    pub fn saturating_add(self, rhs: Duration) -> Duration {
+        Duration::from_nanos_saturating(self.total_nanos() + rhs.total_nanos())
+    }
+
+    /// Subtracts `rhs` from the duration, saturating at the bounds of the range of durations.
+    ///
+    /// The result is normalized.
+    pub fn saturating_sub(self, rhs: Duration) -> Duration {
+        Duration::from_nanos_saturating(self.total_nanos() - rhs.total_nanos())
+    }
+
+    /// Negates the duration, saturating at the bounds of the range of durations.
+    ///
+    /// The result is normalized.
+    pub fn saturating_neg(self) -> Duration {
+        Duration::from_nanos_saturating(-self.total_nanos())
+    }
+
+    /// Returns the length of the duration in nanoseconds, which can not overflow for any
+    /// `seconds` and `nanos`, whether normalized or not.
+    pub(crate) fn total_nanos(&self) -> i128 {
+        i128::from(self.seconds) * i128::from(NANOS_PER_SECOND) + i128::from(self.nanos)
+    }
+
+    /// Creates a normalized duration from a number of nanoseconds, if it is in range.
+    pub(crate) fn from_nanos(nanos: i128) -> Option<Duration> {
+        let seconds = i64::try_from(nanos / i128::from(NANOS_PER_SECOND)).ok()?;
+        let nanos = (nanos % i128::from(NANOS_PER_SECOND)) as i32;
+        Some(Duration { seconds, nanos })
+    }
+
+    fn from_nanos_saturating(nanos: i128) -> Duration {
+        Duration::from_nanos(nanos).unwrap_or(if nanos < 0 {
+            Duration {
+                seconds: i64::MIN,
+                nanos: -NANOS_MAX,
+            }
+        } else {
+            Duration {
+                seconds: i64::MAX,
+                nanos: NANOS_MAX,
+            }
+        })
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    /// Adds two durations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sum is out of range. See [`Duration::checked_add`] for a version without
+    /// panics.
+    fn add(self, rhs: Duration) -> Duration {
+        self.checked_add(rhs)
+            .expect("overflow when adding durations")
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Duration {
+    type Output = Duration;
+
+    /// Subtracts two durations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the difference is out of range. See [`Duration::checked_sub`] for a version
+    /// without panics.
+    fn sub(self, rhs: Duration) -> Duration {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting durations")
+    }
+}
+
+impl SubAssign for Duration {
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Duration {
+    type Output = Duration;
+
+    /// Negates the duration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the negation is out of range. See [`Duration::checked_neg`] for a version
+    /// without panics.
+    fn neg(self) -> Duration {
+        self.checked_neg().expect("overflow when negating duration")
+    }
 }
 
 impl Name for Duration {
@@ -249,6 +377,52 @@ mod proofs {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_duration_arithmetic() {
+        let duration = |seconds, nanos| Duration { seconds, nanos };
+
+        assert_eq!(
+            duration(1, 600_000_000) + duration(2, 600_000_000),
+            duration(4, 200_000_000)
+        );
+        assert_eq!(
+            duration(1, 0) - duration(2, 500_000_000),
+            duration(-1, -500_000_000)
+        );
+        assert_eq!(-duration(1, 500_000_000), duration(-1, -500_000_000));
+        // Operands don't need to be normalized.
+        assert_eq!(duration(1, -1) + duration(0, 1), duration(1, 0));
+
+        let mut total = Duration::default();
+        total += duration(0, 700_000_000);
+        total += duration(0, 700_000_000);
+        total -= duration(3, 0);
+        assert_eq!(total, duration(-1, -600_000_000));
+
+        let max = duration(i64::MAX, NANOS_MAX);
+        let min = duration(i64::MIN, -NANOS_MAX);
+        assert_eq!(max.checked_add(duration(0, 1)), None);
+        assert_eq!(min.checked_sub(duration(0, 1)), None);
+        assert_eq!(duration(i64::MIN, 0).checked_neg(), None);
+        assert_eq!(duration(i64::MIN + 1, -NANOS_MAX).checked_neg(), Some(max));
+        assert_eq!(max.saturating_add(duration(1, 0)), max);
+        assert_eq!(min.saturating_sub(max), min);
+        assert_eq!(duration(i64::MIN, 0).saturating_neg(), max);
+        assert_eq!(max.saturating_sub(max), Duration::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow when adding durations")]
+    fn test_duration_add_overflow() {
+        let _ = Duration {
+            seconds: i64::MAX,
+            nanos: 0,
+        } + Duration {
+            seconds: 1,
+            nanos: 0,
+        };
+    }
+
     #[cfg(feature = "std")]
     #[test]
     fn test_duration_from_str() {
@@ -0,0 +1,396 @@
+//! Conversions of `FieldMask` paths between their Protobuf and JSON representations.
+
+use core::fmt;
+
+use prost::alloc::string::{String, ToString};
+use prost::alloc::vec::Vec;
+
+use crate::field_descriptor_proto::{Label, Type};
+use crate::{DescriptorProto, FieldDescriptorProto, FieldMask, FileDescriptorSet};
+
+/// A field mask path conversion error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FieldMaskError {
+    /// Indicates that a path can't be converted without losing information, such as a Protobuf
+    /// path with an uppercase letter, or a JSON path with an underscore.
+    InvalidPath(String),
+
+    /// Indicates that a message is not part of the descriptor set.
+    UnknownMessage(String),
+
+    /// Indicates that a path names a field which the message does not have.
+    UnknownField {
+        /// The fully-qualified name of the message.
+        message: String,
+        /// The name of the missing field, as it appears in the path.
+        field: String,
+    },
+
+    /// Indicates that a path continues past a field which is not a singular message field.
+    NotAMessage {
+        /// The fully-qualified name of the message.
+        message: String,
+        /// The name of the field, as it appears in the path.
+        field: String,
+    },
+}
+
+impl fmt::Display for FieldMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldMaskError::InvalidPath(path) => write!(f, "invalid field mask path: {}", path),
+            FieldMaskError::UnknownMessage(message) => write!(f, "unknown message: {}", message),
+            FieldMaskError::UnknownField { message, field } => {
+                write!(f, "message {} has no field {}", message, field)
+            }
+            FieldMaskError::NotAMessage { message, field } => {
+                write!(
+                    f,
+                    "field {} of message {} is not a singular message field",
+                    field, message
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FieldMaskError {}
+
+impl FieldMask {
+    /// Creates a field mask from paths in the JSON representation, such as `user.displayName`,
+    /// by converting them to snake case.
+    ///
+    /// Paths which contain an underscore are rejected, since they would not convert back to the
+    /// same JSON path.
+    pub fn from_json_paths<I, S>(paths: I) -> Result<FieldMask, FieldMaskError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let paths = paths
+            .into_iter()
+            .map(|path| snake_case(path.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok(FieldMask { paths })
+    }
+
+    /// Returns the paths of the field mask in the JSON representation, such as
+    /// `user.displayName`, by converting them to lower camel case.
+    ///
+    /// Paths which contain an uppercase letter, or an underscore which is not followed by a
+    /// lowercase letter, are rejected, since they would not convert back to the same path.
+    pub fn to_json_paths(&self) -> Result<Vec<String>, FieldMaskError> {
+        self.paths
+            .iter()
+            .map(|path| lower_camel_case(path))
+            .collect()
+    }
+
+    /// Creates a field mask for the message with the given fully-qualified name, such as
+    /// `.my.package.Msg`, from paths in the JSON representation.
+    ///
+    /// Unlike [`FieldMask::from_json_paths`], each path segment is resolved against the fields of
+    /// the message in `set`, so that fields with a custom `json_name` are converted correctly,
+    /// and paths which do not name a field are rejected.
+    pub fn from_json_paths_for<I, S>(
+        set: &FileDescriptorSet,
+        message: &str,
+        paths: I,
+    ) -> Result<FieldMask, FieldMaskError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let paths = paths
+            .into_iter()
+            .map(|path| {
+                resolve(set, message, path.as_ref(), |field, segment| {
+                    (json_name(field) == segment).then(|| field.name().to_string())
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FieldMask { paths })
+    }
+
+    /// Returns the paths of the field mask in the JSON representation, resolving each path
+    /// segment against the fields of the message with the given fully-qualified name in `set`.
+    ///
+    /// Unlike [`FieldMask::to_json_paths`], fields with a custom `json_name` are converted
+    /// correctly, and paths which do not name a field are rejected.
+    pub fn to_json_paths_for(
+        &self,
+        set: &FileDescriptorSet,
+        message: &str,
+    ) -> Result<Vec<String>, FieldMaskError> {
+        self.paths
+            .iter()
+            .map(|path| {
+                resolve(set, message, path, |field, segment| {
+                    (field.name() == segment).then(|| json_name(field))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Converts each segment of a path with the field for which `convert` returns a name, descending
+/// into the message type of all but the last field.
+fn resolve<F>(
+    set: &FileDescriptorSet,
+    message: &str,
+    path: &str,
+    convert: F,
+) -> Result<String, FieldMaskError>
+where
+    F: Fn(&FieldDescriptorProto, &str) -> Option<String>,
+{
+    let mut message = message.to_string();
+    let mut descriptor = find_message(set, &message)?;
+    let mut converted = String::with_capacity(path.len());
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        let (field, name) = descriptor
+            .field
+            .iter()
+            .find_map(|field| convert(field, segment).map(|name| (field, name)))
+            .ok_or_else(|| FieldMaskError::UnknownField {
+                message: message.clone(),
+                field: segment.to_string(),
+            })?;
+        if !converted.is_empty() {
+            converted.push('.');
+        }
+        converted.push_str(&name);
+
+        if segments.peek().is_some() {
+            let is_message = matches!(field.r#type(), Type::Message | Type::Group);
+            if !is_message || field.label() == Label::Repeated {
+                return Err(FieldMaskError::NotAMessage {
+                    message,
+                    field: segment.to_string(),
+                });
+            }
+            message = field.type_name().to_string();
+            descriptor = find_message(set, &message)?;
+        }
+    }
+    Ok(converted)
+}
+
+fn find_message<'a>(
+    set: &'a FileDescriptorSet,
+    message: &str,
+) -> Result<&'a DescriptorProto, FieldMaskError> {
+    set.find_message(message)
+        .ok_or_else(|| FieldMaskError::UnknownMessage(message.to_string()))
+}
+
+/// Returns the JSON name of a field, which defaults to its name in lower camel case.
+fn json_name(field: &FieldDescriptorProto) -> String {
+    match field.json_name {
+        Some(ref json_name) => json_name.clone(),
+        None => field
+            .name()
+            .split('_')
+            .enumerate()
+            .map(|(i, word)| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) if i > 0 => first.to_uppercase().chain(chars).collect(),
+                    _ => word.to_string(),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Converts a Protobuf path to lower camel case, as in `FieldMaskUtil::ToJsonString`.
+fn lower_camel_case(path: &str) -> Result<String, FieldMaskError> {
+    let invalid = || FieldMaskError::InvalidPath(path.to_string());
+    let mut converted = String::with_capacity(path.len());
+    let mut after_underscore = false;
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            return Err(invalid());
+        }
+        if after_underscore {
+            if !c.is_ascii_lowercase() {
+                return Err(invalid());
+            }
+            converted.push(c.to_ascii_uppercase());
+            after_underscore = false;
+        } else if c == '_' {
+            after_underscore = true;
+        } else {
+            converted.push(c);
+        }
+    }
+    if after_underscore {
+        return Err(invalid());
+    }
+    Ok(converted)
+}
+
+/// Converts a JSON path to snake case, as in `FieldMaskUtil::FromJsonString`.
+fn snake_case(path: &str) -> Result<String, FieldMaskError> {
+    let mut converted = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c == '_' {
+            return Err(FieldMaskError::InvalidPath(path.to_string()));
+        }
+        if c.is_ascii_uppercase() {
+            converted.push('_');
+            converted.push(c.to_ascii_lowercase());
+        } else {
+            converted.push(c);
+        }
+    }
+    Ok(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::alloc::vec;
+
+    use crate::FileDescriptorProto;
+
+    fn field(name: &str, number: i32, r#type: Type, type_name: &str) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(r#type as i32),
+            type_name: (!type_name.is_empty()).then(|| type_name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn set() -> FileDescriptorSet {
+        let mut display_name = field("display_name", 1, Type::String, "");
+        display_name.json_name = Some("displayName".to_string());
+        let mut legacy_id = field("legacy_id", 2, Type::Int64, "");
+        legacy_id.json_name = Some("LEGACY".to_string());
+        let mut friends = field("friends", 3, Type::Message, ".app.User");
+        friends.label = Some(Label::Repeated as i32);
+        let user = DescriptorProto {
+            name: Some("User".to_string()),
+            field: vec![display_name, legacy_id, friends],
+            ..Default::default()
+        };
+        let request = DescriptorProto {
+            name: Some("UpdateRequest".to_string()),
+            field: vec![
+                field("user", 1, Type::Message, ".app.User"),
+                field("request_id", 2, Type::String, ""),
+            ],
+            ..Default::default()
+        };
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("app.proto".to_string()),
+                package: Some("app".to_string()),
+                message_type: vec![user, request],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_json_paths() {
+        let mask = FieldMask::from_json_paths(["user.displayName", "requestId", "a1b"]).unwrap();
+        assert_eq!(mask.paths, ["user.display_name", "request_id", "a1b"]);
+        assert_eq!(
+            mask.to_json_paths().unwrap(),
+            ["user.displayName", "requestId", "a1b"]
+        );
+        assert_eq!(
+            FieldMask::from_json_paths(["user.display_name"]),
+            Err(FieldMaskError::InvalidPath("user.display_name".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_proto_paths() {
+        for path in ["userName", "user__name", "user_", "user_1", "user_.name"] {
+            let mask = FieldMask {
+                paths: vec![path.to_string()],
+            };
+            assert_eq!(
+                mask.to_json_paths(),
+                Err(FieldMaskError::InvalidPath(path.to_string())),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_paths_for() {
+        let set = set();
+        let mask = FieldMask::from_json_paths_for(
+            &set,
+            ".app.UpdateRequest",
+            [
+                "user.displayName",
+                "user.LEGACY",
+                "requestId",
+                "user.friends",
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            mask.paths,
+            [
+                "user.display_name",
+                "user.legacy_id",
+                "request_id",
+                "user.friends"
+            ]
+        );
+        assert_eq!(
+            mask.to_json_paths_for(&set, "app.UpdateRequest").unwrap(),
+            [
+                "user.displayName",
+                "user.LEGACY",
+                "requestId",
+                "user.friends"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_paths_for() {
+        let set = set();
+        assert_eq!(
+            FieldMask::from_json_paths_for(&set, ".app.UpdateRequest", ["user.legacyId"]),
+            Err(FieldMaskError::UnknownField {
+                message: ".app.User".to_string(),
+                field: "legacyId".to_string(),
+            })
+        );
+        assert_eq!(
+            FieldMask::from_json_paths_for(&set, ".app.UpdateRequest", ["requestId.value"])
+                .unwrap_err()
+                .to_string(),
+            "field requestId of message .app.UpdateRequest is not a singular message field"
+        );
+        assert_eq!(
+            FieldMask::from_json_paths_for(&set, ".app.User", ["friends.displayName"]),
+            Err(FieldMaskError::NotAMessage {
+                message: ".app.User".to_string(),
+                field: "friends".to_string(),
+            })
+        );
+        let mask = FieldMask {
+            paths: vec!["user.displayName".to_string()],
+        };
+        assert_eq!(
+            mask.to_json_paths_for(&set, ".app.Missing"),
+            Err(FieldMaskError::UnknownMessage(".app.Missing".to_string()))
+        );
+    }
+}
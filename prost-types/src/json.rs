@@ -0,0 +1,304 @@
+//! Formatting of `Struct`, `ListValue` and `Value` as JSON.
+
+use super::*;
+
+/// Formats the value as compact JSON.
+///
+/// Values which have no JSON representation are formatted as in the Protobuf JSON mapping of
+/// `double` fields: NaN and infinities as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+/// A value without a kind is formatted as `null`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self, false)
+    }
+}
+
+/// Formats the struct as a compact JSON object, with its fields in key order.
+impl fmt::Display for Struct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_struct(f, self, false)
+    }
+}
+
+/// Formats the list as a compact JSON array.
+impl fmt::Display for ListValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_list(f, self, false)
+    }
+}
+
+/// Formats a [`Value`], [`Struct`] or [`ListValue`] as canonical JSON.
+///
+/// The output follows the JSON Canonicalization Scheme of [RFC 8785], so that it is byte for byte
+/// the same across runs and versions, and can be signed or hashed:
+///
+/// - there is no whitespace between tokens,
+/// - object keys are sorted by their UTF-16 code units,
+/// - numbers are written in their shortest round-trip form, in the notation of ECMAScript's
+///   `Number.prototype.toString`: `1e+21`, `1e-7`, `0.000001` and `0` for negative zero,
+/// - strings escape only quotes, backslashes and control characters.
+///
+/// RFC 8785 has no representation of NaN and infinities. They are formatted as the strings
+/// `"NaN"`, `"Infinity"` and `"-Infinity"`, like by the [`Display`](fmt::Display) implementation
+/// of [`Value`].
+///
+/// ```rust
+/// use prost_types::Value;
+///
+/// let value = Value::from(vec![Value::from(1e21), Value::from(-0.0), Value::from(0.5)]);
+/// assert_eq!(value.canonical_json().to_string(), "[1e+21,0,0.5]");
+/// ```
+///
+/// [RFC 8785]: https://www.rfc-editor.org/rfc/rfc8785
+#[derive(Clone, Copy, Debug)]
+pub struct CanonicalJson<'a, T>(&'a T);
+
+impl Value {
+    /// Returns an object formatting the value as canonical JSON, see [`CanonicalJson`].
+    pub fn canonical_json(&self) -> CanonicalJson<'_, Value> {
+        CanonicalJson(self)
+    }
+}
+
+impl Struct {
+    /// Returns an object formatting the struct as a canonical JSON object, see
+    /// [`CanonicalJson`].
+    pub fn canonical_json(&self) -> CanonicalJson<'_, Struct> {
+        CanonicalJson(self)
+    }
+}
+
+impl ListValue {
+    /// Returns an object formatting the list as a canonical JSON array, see [`CanonicalJson`].
+    pub fn canonical_json(&self) -> CanonicalJson<'_, ListValue> {
+        CanonicalJson(self)
+    }
+}
+
+impl fmt::Display for CanonicalJson<'_, Value> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self.0, true)
+    }
+}
+
+impl fmt::Display for CanonicalJson<'_, Struct> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_struct(f, self.0, true)
+    }
+}
+
+impl fmt::Display for CanonicalJson<'_, ListValue> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_list(f, self.0, true)
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: &Value, canonical: bool) -> fmt::Result {
+    match value.kind {
+        None | Some(value::Kind::NullValue(_)) => f.write_str("null"),
+        Some(value::Kind::NumberValue(number)) => {
+            if number.is_nan() {
+                f.write_str("\"NaN\"")
+            } else if number.is_infinite() {
+                if number.is_sign_positive() {
+                    f.write_str("\"Infinity\"")
+                } else {
+                    f.write_str("\"-Infinity\"")
+                }
+            } else if canonical {
+                write_canonical_number(f, number)
+            } else {
+                write!(f, "{}", number)
+            }
+        }
+        Some(value::Kind::StringValue(ref string)) => write_string(f, string),
+        Some(value::Kind::BoolValue(boolean)) => write!(f, "{}", boolean),
+        Some(value::Kind::StructValue(ref object)) => write_struct(f, object, canonical),
+        Some(value::Kind::ListValue(ref list)) => write_list(f, list, canonical),
+    }
+}
+
+fn write_struct(f: &mut fmt::Formatter<'_>, object: &Struct, canonical: bool) -> fmt::Result {
+    let mut fields: Vec<(&String, &Value)> = object.fields.iter().collect();
+    if canonical {
+        // The map is sorted by UTF-8 bytes, which only differs from the UTF-16 order of RFC 8785
+        // for keys with characters from U+E000 on.
+        fields.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+    }
+    f.write_str("{")?;
+    for (i, (key, value)) in fields.into_iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        write_string(f, key)?;
+        f.write_str(":")?;
+        write_value(f, value, canonical)?;
+    }
+    f.write_str("}")
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, list: &ListValue, canonical: bool) -> fmt::Result {
+    f.write_str("[")?;
+    for (i, value) in list.values.iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        write_value(f, value, canonical)?;
+    }
+    f.write_str("]")
+}
+
+/// Writes a finite number as ECMAScript's `Number.prototype.toString` does.
+fn write_canonical_number(f: &mut fmt::Formatter<'_>, number: f64) -> fmt::Result {
+    if number == 0.0 {
+        return f.write_str("0");
+    }
+    if number < 0.0 {
+        f.write_str("-")?;
+    }
+    // The `LowerExp` format gives the shortest digits which round-trip, as `d.ddde-x`.
+    let formatted = format!("{:e}", number.abs());
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    let digits: String = mantissa.chars().filter(|&c| c != '.').collect();
+    let digits = digits.as_str();
+    let exponent: i32 = exponent.parse().unwrap();
+    // The position of the decimal point relative to the digits.
+    let point = exponent + 1;
+    let len = digits.len() as i32;
+    if len <= point && point <= 21 {
+        f.write_str(digits)?;
+        for _ in len..point {
+            f.write_str("0")?;
+        }
+        Ok(())
+    } else if 0 < point && point <= 21 {
+        let (integer, fraction) = digits.split_at(point as usize);
+        write!(f, "{}.{}", integer, fraction)
+    } else if -6 < point && point <= 0 {
+        f.write_str("0.")?;
+        for _ in point..0 {
+            f.write_str("0")?;
+        }
+        f.write_str(digits)
+    } else {
+        let (first, rest) = digits.split_at(1);
+        f.write_str(first)?;
+        if !rest.is_empty() {
+            write!(f, ".{}", rest)?;
+        }
+        let sign = if exponent < 0 { '-' } else { '+' };
+        write!(f, "e{}{}", sign, exponent.abs())
+    }
+}
+
+/// Writes a JSON string literal, escaping quotes, backslashes and control characters.
+fn write_string(f: &mut fmt::Formatter<'_>, string: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    let mut start = 0;
+    for (i, c) in string.char_indices() {
+        let escape = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{8}' => "\\b",
+            '\u{c}' => "\\f",
+            '\u{0}'..='\u{1f}' => "",
+            _ => continue,
+        };
+        f.write_str(&string[start..i])?;
+        if escape.is_empty() {
+            write!(f, "\\u{:04x}", c as u32)?;
+        } else {
+            f.write_str(escape)?;
+        }
+        start = i + c.len_utf8();
+    }
+    f.write_str(&string[start..])?;
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::alloc::collections::BTreeMap;
+    use prost::alloc::string::ToString;
+    use prost::alloc::vec;
+
+    #[test]
+    fn check_value_display() {
+        assert_eq!(Value::default().to_string(), "null");
+        assert_eq!(
+            Value::from(value::Kind::NullValue(NullValue::NullValue as i32)).to_string(),
+            "null"
+        );
+        assert_eq!(Value::from(1.5).to_string(), "1.5");
+        assert_eq!(Value::from(-2).to_string(), "-2");
+        assert_eq!(Value::from(f64::NAN).to_string(), "\"NaN\"");
+        assert_eq!(Value::from(f64::INFINITY).to_string(), "\"Infinity\"");
+        assert_eq!(Value::from(f64::NEG_INFINITY).to_string(), "\"-Infinity\"");
+        assert_eq!(Value::from(true).to_string(), "true");
+        assert_eq!(
+            Value::from("a \"quoted\"\\\n\u{1}é").to_string(),
+            "\"a \\\"quoted\\\"\\\\\\n\\u0001é\""
+        );
+    }
+
+    #[test]
+    fn check_struct_display() {
+        let mut fields = BTreeMap::new();
+        fields.insert("b".to_string(), Value::from(vec![true.into(), "x".into()]));
+        fields.insert("a".to_string(), Value::from(1));
+        fields.insert("c".to_string(), Value::from(BTreeMap::new()));
+        fields.insert("d".to_string(), Value::from(Vec::new()));
+        assert_eq!(
+            Struct { fields }.to_string(),
+            r#"{"a":1,"b":[true,"x"],"c":{},"d":[]}"#
+        );
+    }
+
+    #[test]
+    fn check_canonical_numbers() {
+        let canonical = |number: f64| Value::from(number).canonical_json().to_string();
+        assert_eq!(canonical(0.0), "0");
+        assert_eq!(canonical(-0.0), "0");
+        assert_eq!(canonical(1.0), "1");
+        assert_eq!(canonical(-1.5), "-1.5");
+        assert_eq!(canonical(123456789.0), "123456789");
+        assert_eq!(canonical(1e20), "100000000000000000000");
+        assert_eq!(canonical(1e21), "1e+21");
+        assert_eq!(canonical(1.2345e22), "1.2345e+22");
+        assert_eq!(canonical(0.000001), "0.000001");
+        assert_eq!(canonical(1e-7), "1e-7");
+        assert_eq!(canonical(-1.5e-7), "-1.5e-7");
+        assert_eq!(canonical(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(canonical(f64::MAX), "1.7976931348623157e+308");
+        assert_eq!(canonical(5e-324), "5e-324");
+        assert_eq!(canonical(f64::NAN), "\"NaN\"");
+        assert_eq!(canonical(f64::NEG_INFINITY), "\"-Infinity\"");
+    }
+
+    #[test]
+    fn check_canonical_struct() {
+        let mut inner = BTreeMap::new();
+        inner.insert("\u{20ac}".to_string(), Value::from(1e30));
+        inner.insert("\u{1f600}".to_string(), Value::from("\u{f}"));
+        inner.insert("\u{fb33}".to_string(), Value::from(true));
+        let mut fields = BTreeMap::new();
+        fields.insert("b".to_string(), Value::from(vec![Value::from(1e-7)]));
+        fields.insert("a".to_string(), Value::from(inner));
+        let object = Struct { fields };
+        // Keys are sorted by UTF-16 code units, which puts the surrogate pair of U+1F600 before
+        // U+FB33.
+        assert_eq!(
+            object.canonical_json().to_string(),
+            "{\"a\":{\"\u{20ac}\":1e+30,\"\u{1f600}\":\"\\u000f\",\"\u{fb33}\":true},\"b\":[1e-7]}"
+        );
+        assert_eq!(
+            object.to_string(),
+            "{\"a\":{\"\u{20ac}\":1000000000000000000000000000000,\"\u{fb33}\":true,\"\u{1f600}\":\"\\u000f\"},\"b\":[0.0000001]}"
+        );
+    }
+}
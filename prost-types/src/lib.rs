@@ -36,13 +36,17 @@
 //! ## Feature Flags
 //! - `std`: Enable integration with standard library. Disable this feature for `no_std` support. This feature is enabled by default.
 //! - `arbitrary`: Enable integration with crate `arbitrary`. All types on this crate will implement `trait Arbitrary`.
+//! - `macros`: Enable the `value!` and `struct_value!` macros, which construct [`Value`] and [`Struct`] trees from JSON-like literals.
+//! - `serde`: Implement `serde::Serialize` and `serde::Deserialize` for [`Timestamp`] and [`Duration`], in their Protobuf JSON forms, such as `"2000-01-01T00:00:00Z"` and `"1.5s"`.
+//! - `no-source-code-info`: Remove `SourceCodeInfo` and `GeneratedCodeInfo`, and the `source_code_info` and `generated_code_info` fields of `FileDescriptorProto` and `compiler::code_generator_response::File`, which reduces compile time and code size. Their data is skipped when decoding. Enabling this feature is a breaking change for crates using these items, even if they do not enable it themselves, as Cargo features are unified across the dependency graph.
 //!
 //! [1]: https://developers.google.com/protocol-buffers/docs/reference/google.protobuf
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[rustfmt::skip]
-pub mod compiler;
+#[path = "compiler.rs"]
+mod compiler_protos;
 mod datetime;
 #[rustfmt::skip]
 mod protobuf;
@@ -70,9 +74,29 @@ const PACKAGE: &str = "google.protobuf";
 
 mod any;
 
+mod descriptor;
+pub use descriptor::DescriptorSetError;
+
 mod duration;
 pub use duration::DurationError;
 
+mod field_mask;
+pub use field_mask::FieldMaskError;
+
+mod plugin;
+
+/// Types of the `protoc` plugin protocol.
+///
+/// `protoc` writes an encoded [`CodeGeneratorRequest`] to the standard input of a plugin, and
+/// reads an encoded [`CodeGeneratorResponse`] from its standard output.
+///
+/// [`CodeGeneratorRequest`]: compiler::CodeGeneratorRequest
+/// [`CodeGeneratorResponse`]: compiler::CodeGeneratorResponse
+pub mod compiler {
+    pub use crate::compiler_protos::*;
+    pub use crate::plugin::SupportedFeatures;
+}
+
 mod timestamp;
 pub use timestamp::TimestampError;
 
@@ -80,3 +104,12 @@ mod type_url;
 pub(crate) use type_url::{type_url_for, TypeUrl};
 
 mod conversions;
+
+mod json;
+pub use json::CanonicalJson;
+
+#[cfg(feature = "macros")]
+mod macros;
+
+#[cfg(feature = "serde")]
+mod serde;
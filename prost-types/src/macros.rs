@@ -0,0 +1,123 @@
+/// Constructs a [`Value`](crate::Value) from a JSON-like literal.
+///
+/// Objects become [`Struct`](crate::Struct) values, arrays become
+/// [`ListValue`](crate::ListValue) values, and `null` becomes a null value. Any other element is
+/// an expression which is converted with `Value::from`, so that numbers, booleans, strings and
+/// nested `Value`s can be interpolated. Object keys are string literals, or parenthesized
+/// expressions converted with `String::from`.
+///
+/// ```rust
+/// use prost_types::value;
+///
+/// let name = "prost";
+/// let value = value!({
+///     "name": name,
+///     "stars": 3.5,
+///     "tags": [true, "x", null],
+///     "nested": { "empty": {} },
+/// });
+/// assert_eq!(
+///     value.to_string(),
+///     r#"{"name":"prost","nested":{"empty":{}},"stars":3.5,"tags":[true,"x",null]}"#
+/// );
+/// ```
+///
+/// This macro is only available with the `macros` feature.
+#[macro_export]
+macro_rules! value {
+    // Munches the elements of an array, accumulating the converted values.
+    (@array [$($elems:expr,)*]) => {
+        [$($elems,)*]
+    };
+    (@array [$($elems:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::value!(@array [$($elems,)* $crate::value!(null),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::value!(@array [$($elems,)* $crate::value!([$($array)*]),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::value!(@array [$($elems,)* $crate::value!({$($object)*}),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::value!(@array [$($elems,)* $crate::value!($next),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::value!(@array [$($elems,)* $crate::value!($last),])
+    };
+
+    // Munches the entries of an object, accumulating the keys and converted values.
+    (@object [$($entries:expr,)*]) => {
+        [$($entries,)*]
+    };
+    (@object [$($entries:expr,)*] $key:tt : null $(, $($rest:tt)*)?) => {
+        $crate::value!(@object [$($entries,)* $crate::value!(@entry $key, null),] $($($rest)*)?)
+    };
+    (@object [$($entries:expr,)*] $key:tt : [$($array:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::value!(
+            @object [$($entries,)* $crate::value!(@entry $key, [$($array)*]),] $($($rest)*)?
+        )
+    };
+    (@object [$($entries:expr,)*] $key:tt : {$($object:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::value!(
+            @object [$($entries,)* $crate::value!(@entry $key, {$($object)*}),] $($($rest)*)?
+        )
+    };
+    (@object [$($entries:expr,)*] $key:tt : $next:expr, $($rest:tt)*) => {
+        $crate::value!(@object [$($entries,)* $crate::value!(@entry $key, $next),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : $last:expr) => {
+        $crate::value!(@object [$($entries,)* $crate::value!(@entry $key, $last),])
+    };
+    (@entry $key:tt, $($value:tt)+) => {
+        (::core::convert::From::from($key), $crate::value!($($value)+))
+    };
+
+    (null) => {
+        $crate::Value {
+            kind: ::core::option::Option::Some($crate::value::Kind::NullValue(
+                $crate::NullValue::NullValue as i32,
+            )),
+        }
+    };
+    ([$($array:tt)*]) => {
+        $crate::Value {
+            kind: ::core::option::Option::Some($crate::value::Kind::ListValue($crate::ListValue {
+                values: ::core::iter::IntoIterator::into_iter($crate::value!(@array [] $($array)*))
+                    .collect(),
+            })),
+        }
+    };
+    ({$($object:tt)*}) => {
+        $crate::Value {
+            kind: ::core::option::Option::Some($crate::value::Kind::StructValue($crate::Struct {
+                fields: ::core::iter::IntoIterator::into_iter($crate::value!(@object [] $($object)*))
+                    .collect(),
+            })),
+        }
+    };
+    ($other:expr) => {
+        <$crate::Value as ::core::convert::From<_>>::from($other)
+    };
+}
+
+/// Constructs a [`Struct`](crate::Struct) from a JSON-like object literal.
+///
+/// The fields are given as with [`value!`](crate::value!).
+///
+/// ```rust
+/// use prost_types::struct_value;
+///
+/// let labels = struct_value!({ "env": "prod", "replicas": 3 });
+/// assert_eq!(labels.fields.len(), 2);
+/// ```
+///
+/// This macro is only available with the `macros` feature.
+#[macro_export]
+macro_rules! struct_value {
+    ({$($object:tt)*}) => {
+        $crate::Struct {
+            fields: ::core::iter::IntoIterator::into_iter($crate::value!(@object [] $($object)*))
+                .collect(),
+        }
+    };
+}
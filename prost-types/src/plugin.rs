@@ -0,0 +1,197 @@
+//! Helpers for the `protoc` plugin protocol.
+
+use core::fmt;
+use core::ops::{BitOr, BitOrAssign};
+
+use crate::compiler::code_generator_response::Feature;
+use crate::compiler::{CodeGeneratorRequest, CodeGeneratorResponse, Version};
+
+impl Version {
+    /// Creates a mainline stable version.
+    pub fn new(major: i32, minor: i32, patch: i32) -> Version {
+        Version {
+            major: Some(major),
+            minor: Some(minor),
+            patch: Some(patch),
+            suffix: None,
+        }
+    }
+
+    /// Returns `true` if the version is `major.minor.patch` or newer.
+    ///
+    /// Missing version components count as zero, and the suffix is ignored, so that a release
+    /// candidate of a version is considered to be that version.
+    pub fn is_at_least(&self, major: i32, minor: i32, patch: i32) -> bool {
+        (self.major(), self.minor(), self.patch()) >= (major, minor, patch)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major(), self.minor(), self.patch())?;
+        match self.suffix() {
+            "" => Ok(()),
+            suffix => write!(f, "-{}", suffix),
+        }
+    }
+}
+
+impl CodeGeneratorRequest {
+    /// Returns `true` if the request was sent by `protoc` version `major.minor.patch` or newer.
+    ///
+    /// `protoc` releases older than 3.0 do not send their version, and are always considered
+    /// older.
+    pub fn compiler_version_at_least(&self, major: i32, minor: i32, patch: i32) -> bool {
+        self.compiler_version
+            .as_ref()
+            .is_some_and(|version| version.is_at_least(major, minor, patch))
+    }
+}
+
+/// The set of optional features that a plugin declares in
+/// [`CodeGeneratorResponse::supported_features`].
+///
+/// `protoc` refuses to run a plugin on files which use a feature it doesn't declare. The values of
+/// [`Feature`] are already bit flags, so that features are combined with `|`:
+///
+/// ```rust
+/// use prost_types::compiler::{CodeGeneratorResponse, SupportedFeatures};
+///
+/// let mut response = CodeGeneratorResponse::default();
+/// response.set_features(SupportedFeatures::PROTO3_OPTIONAL | SupportedFeatures::SUPPORTS_EDITIONS);
+/// assert_eq!(response.supported_features, Some(3));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SupportedFeatures(u64);
+
+impl SupportedFeatures {
+    /// No optional features.
+    pub const NONE: SupportedFeatures = SupportedFeatures(Feature::None as u64);
+
+    /// `optional` fields in `proto3` files, see [`Feature::Proto3Optional`].
+    pub const PROTO3_OPTIONAL: SupportedFeatures =
+        SupportedFeatures(Feature::Proto3Optional as u64);
+
+    /// Files using editions, `FEATURE_SUPPORTS_EDITIONS`.
+    ///
+    /// `protoc` also requires the response to declare the range of supported editions in its
+    /// `minimum_edition` and `maximum_edition` fields. Those fields are newer than the
+    /// `plugin.proto` these types are generated from, so they have to be appended to the encoded
+    /// response as unknown fields.
+    pub const SUPPORTS_EDITIONS: SupportedFeatures = SupportedFeatures(2);
+
+    /// Creates a set from the raw bit flags, keeping any flag this version doesn't know about.
+    pub const fn from_bits(bits: u64) -> SupportedFeatures {
+        SupportedFeatures(bits)
+    }
+
+    /// Returns the raw bit flags.
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Returns `true` if all the features of `other` are in the set.
+    pub const fn contains(self, other: SupportedFeatures) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the set with the features of `other` added.
+    pub const fn with(self, other: SupportedFeatures) -> SupportedFeatures {
+        SupportedFeatures(self.0 | other.0)
+    }
+}
+
+impl From<Feature> for SupportedFeatures {
+    fn from(feature: Feature) -> SupportedFeatures {
+        SupportedFeatures(feature as u64)
+    }
+}
+
+impl BitOr for SupportedFeatures {
+    type Output = SupportedFeatures;
+
+    fn bitor(self, other: SupportedFeatures) -> SupportedFeatures {
+        self.with(other)
+    }
+}
+
+impl BitOrAssign for SupportedFeatures {
+    fn bitor_assign(&mut self, other: SupportedFeatures) {
+        *self = self.with(other);
+    }
+}
+
+impl CodeGeneratorResponse {
+    /// Returns the features declared by the plugin.
+    pub fn features(&self) -> SupportedFeatures {
+        SupportedFeatures::from_bits(self.supported_features())
+    }
+
+    /// Declares the features supported by the plugin.
+    pub fn set_features(&mut self, features: SupportedFeatures) {
+        self.supported_features = Some(features.bits());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::alloc::string::ToString;
+
+    #[test]
+    fn test_version() {
+        let mut version = Version::new(3, 21, 12);
+        assert_eq!(version.to_string(), "3.21.12");
+        assert!(version.is_at_least(3, 21, 12));
+        assert!(version.is_at_least(3, 15, 20));
+        assert!(!version.is_at_least(3, 21, 13));
+        assert!(!version.is_at_least(4, 0, 0));
+
+        version.suffix = Some("rc2".to_string());
+        assert_eq!(version.to_string(), "3.21.12-rc2");
+        assert!(version.is_at_least(3, 21, 12));
+
+        let partial = Version {
+            major: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(partial.to_string(), "3.0.0");
+        assert!(partial.is_at_least(3, 0, 0));
+    }
+
+    #[test]
+    fn test_compiler_version_at_least() {
+        let mut request = CodeGeneratorRequest::default();
+        assert!(!request.compiler_version_at_least(0, 0, 0));
+        request.compiler_version = Some(Version::new(3, 12, 0));
+        assert!(request.compiler_version_at_least(3, 12, 0));
+        assert!(!request.compiler_version_at_least(3, 15, 0));
+    }
+
+    #[test]
+    fn test_supported_features() {
+        let mut features = SupportedFeatures::NONE;
+        assert_eq!(features.bits(), 0);
+        assert!(!features.contains(SupportedFeatures::PROTO3_OPTIONAL));
+
+        features |= Feature::Proto3Optional.into();
+        assert_eq!(features.bits(), 1);
+        assert!(features.contains(SupportedFeatures::PROTO3_OPTIONAL));
+        assert!(!features.contains(SupportedFeatures::SUPPORTS_EDITIONS));
+
+        let features = features.with(SupportedFeatures::SUPPORTS_EDITIONS);
+        assert_eq!(features.bits(), 3);
+        assert!(features.contains(SupportedFeatures::PROTO3_OPTIONAL | SupportedFeatures::NONE));
+
+        let mut response = CodeGeneratorResponse::default();
+        assert_eq!(response.features(), SupportedFeatures::NONE);
+        response.supported_features = Some(1 << 10 | 2);
+        assert!(response
+            .features()
+            .contains(SupportedFeatures::SUPPORTS_EDITIONS));
+        assert_eq!(response.features().bits(), 1 << 10 | 2);
+        response.set_features(features);
+        assert_eq!(response.supported_features, Some(3));
+    }
+}
@@ -43,6 +43,7 @@ pub struct FileDescriptorProto {
     /// functionality of the descriptors -- the information is needed only by
     /// development tools.
     #[prost(message, optional, tag = "9")]
+    #[cfg(not(feature = "no-source-code-info"))]
     pub source_code_info: ::core::option::Option<SourceCodeInfo>,
     /// The syntax of the proto file.
     /// The supported values are "proto2" and "proto3".
@@ -119,11 +120,11 @@ pub struct FieldDescriptorProto {
     pub name: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(int32, optional, tag = "3")]
     pub number: ::core::option::Option<i32>,
-    #[prost(enumeration = "field_descriptor_proto::Label", optional, tag = "4")]
+    #[prost(enumeration = "field_descriptor_proto::Label", optional, closed, tag = "4")]
     pub label: ::core::option::Option<i32>,
     /// If type_name is set, this need not be set.  If both this and type_name
     /// are set, this must be one of TYPE_ENUM, TYPE_MESSAGE or TYPE_GROUP.
-    #[prost(enumeration = "field_descriptor_proto::Type", optional, tag = "5")]
+    #[prost(enumeration = "field_descriptor_proto::Type", optional, closed, tag = "5")]
     pub r#type: ::core::option::Option<i32>,
     /// For message and enum types, this is the name of the type.  If the name
     /// starts with a '.', it is fully-qualified.  Otherwise, C++-like scoping
@@ -197,35 +198,53 @@ pub mod field_descriptor_proto {
     pub enum Type {
         /// 0 is reserved for errors.
         /// Order is weird for historical reasons.
+        #[doc(alias = "TYPE_DOUBLE")]
         Double = 1,
+        #[doc(alias = "TYPE_FLOAT")]
         Float = 2,
         /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT64 if
         /// negative values are likely.
+        #[doc(alias = "TYPE_INT64")]
         Int64 = 3,
+        #[doc(alias = "TYPE_UINT64")]
         Uint64 = 4,
         /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT32 if
         /// negative values are likely.
+        #[doc(alias = "TYPE_INT32")]
         Int32 = 5,
+        #[doc(alias = "TYPE_FIXED64")]
         Fixed64 = 6,
+        #[doc(alias = "TYPE_FIXED32")]
         Fixed32 = 7,
+        #[doc(alias = "TYPE_BOOL")]
         Bool = 8,
+        #[doc(alias = "TYPE_STRING")]
         String = 9,
         /// Tag-delimited aggregate.
         /// Group type is deprecated and not supported in proto3. However, Proto3
         /// implementations should still be able to parse the group wire format and
         /// treat group fields as unknown fields.
+        #[doc(alias = "TYPE_GROUP")]
         Group = 10,
         /// Length-delimited aggregate.
+        #[doc(alias = "TYPE_MESSAGE")]
         Message = 11,
         /// New in version 2.
+        #[doc(alias = "TYPE_BYTES")]
         Bytes = 12,
+        #[doc(alias = "TYPE_UINT32")]
         Uint32 = 13,
+        #[doc(alias = "TYPE_ENUM")]
         Enum = 14,
+        #[doc(alias = "TYPE_SFIXED32")]
         Sfixed32 = 15,
+        #[doc(alias = "TYPE_SFIXED64")]
         Sfixed64 = 16,
         /// Uses ZigZag encoding.
+        #[doc(alias = "TYPE_SINT32")]
         Sint32 = 17,
         /// Uses ZigZag encoding.
+        #[doc(alias = "TYPE_SINT64")]
         Sint64 = 18,
     }
     impl Type {
@@ -295,8 +314,11 @@ pub mod field_descriptor_proto {
     #[repr(i32)]
     pub enum Label {
         /// 0 is reserved for errors
+        #[doc(alias = "LABEL_OPTIONAL")]
         Optional = 1,
+        #[doc(alias = "LABEL_REQUIRED")]
         Required = 2,
+        #[doc(alias = "LABEL_REPEATED")]
         Repeated = 3,
     }
     impl Label {
@@ -469,7 +491,7 @@ pub struct FileOptions {
     #[prost(bool, optional, tag = "10", default = "false")]
     pub java_multiple_files: ::core::option::Option<bool>,
     /// This option does nothing.
-    #[deprecated]
+    #[deprecated(note = "marked as deprecated in the Protobuf definition")]
     #[prost(bool, optional, tag = "20")]
     pub java_generate_equals_and_hash: ::core::option::Option<bool>,
     /// If set true, then the Java2 code generator will generate code that
@@ -483,6 +505,7 @@ pub struct FileOptions {
     #[prost(
         enumeration = "file_options::OptimizeMode",
         optional,
+        closed,
         tag = "9",
         default = "Speed"
     )]
@@ -582,8 +605,10 @@ pub mod file_options {
         /// etc.
         ///
         /// Use ReflectionOps to implement these methods.
+        #[doc(alias = "CODE_SIZE")]
         CodeSize = 2,
         /// Generate code using MessageLite and the lite runtime.
+        #[doc(alias = "LITE_RUNTIME")]
         LiteRuntime = 3,
     }
     impl OptimizeMode {
@@ -680,6 +705,7 @@ pub struct FieldOptions {
     #[prost(
         enumeration = "field_options::CType",
         optional,
+        closed,
         tag = "1",
         default = "String"
     )]
@@ -705,6 +731,7 @@ pub struct FieldOptions {
     #[prost(
         enumeration = "field_options::JsType",
         optional,
+        closed,
         tag = "6",
         default = "JsNormal"
     )]
@@ -770,6 +797,7 @@ pub mod field_options {
         /// Default mode.
         String = 0,
         Cord = 1,
+        #[doc(alias = "STRING_PIECE")]
         StringPiece = 2,
     }
     impl CType {
@@ -809,10 +837,13 @@ pub mod field_options {
     #[repr(i32)]
     pub enum JsType {
         /// Use the default type.
+        #[doc(alias = "JS_NORMAL")]
         JsNormal = 0,
         /// Use JavaScript strings.
+        #[doc(alias = "JS_STRING")]
         JsString = 1,
         /// Use JavaScript numbers.
+        #[doc(alias = "JS_NUMBER")]
         JsNumber = 2,
     }
     impl JsType {
@@ -900,6 +931,7 @@ pub struct MethodOptions {
     #[prost(
         enumeration = "method_options::IdempotencyLevel",
         optional,
+        closed,
         tag = "34",
         default = "IdempotencyUnknown"
     )]
@@ -927,8 +959,10 @@ pub mod method_options {
     )]
     #[repr(i32)]
     pub enum IdempotencyLevel {
+        #[doc(alias = "IDEMPOTENCY_UNKNOWN")]
         IdempotencyUnknown = 0,
         /// implies idempotent
+        #[doc(alias = "NO_SIDE_EFFECTS")]
         NoSideEffects = 1,
         /// idempotent, but may have side effects
         Idempotent = 2,
@@ -1000,6 +1034,7 @@ pub mod uninterpreted_option {
 }
 /// Encapsulates information about the original source file from which a
 /// FileDescriptorProto was generated.
+#[cfg(not(feature = "no-source-code-info"))]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SourceCodeInfo {
@@ -1051,7 +1086,9 @@ pub struct SourceCodeInfo {
     pub location: ::prost::alloc::vec::Vec<source_code_info::Location>,
 }
 /// Nested message and enum types in `SourceCodeInfo`.
+#[cfg(not(feature = "no-source-code-info"))]
 pub mod source_code_info {
+    #[cfg(not(feature = "no-source-code-info"))]
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct Location {
@@ -1147,6 +1184,7 @@ pub mod source_code_info {
 /// Describes the relationship between generated code and its original source
 /// file. A GeneratedCodeInfo message is associated with only one generated
 /// source file, but may contain references to different source .proto files.
+#[cfg(not(feature = "no-source-code-info"))]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeneratedCodeInfo {
@@ -1156,7 +1194,9 @@ pub struct GeneratedCodeInfo {
     pub annotation: ::prost::alloc::vec::Vec<generated_code_info::Annotation>,
 }
 /// Nested message and enum types in `GeneratedCodeInfo`.
+#[cfg(not(feature = "no-source-code-info"))]
 pub mod generated_code_info {
+    #[cfg(not(feature = "no-source-code-info"))]
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct Annotation {
@@ -1273,6 +1313,7 @@ pub mod generated_code_info {
 /// ```
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Any {
     /// A URL/resource name that uniquely identifies the type of the serialized
     /// protocol buffer message. This string must contain at least
@@ -1311,6 +1352,7 @@ pub struct Any {
 /// protobuf element, like the file in which it is defined.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct SourceContext {
     /// The path-qualified name of the .proto file that contained the associated
     /// protobuf element.  For example: `"google/protobuf/source_context.proto"`.
@@ -1320,6 +1362,7 @@ pub struct SourceContext {
 /// A protocol buffer message type.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Type {
     /// The fully qualified message name.
     #[prost(string, tag = "1")]
@@ -1343,6 +1386,7 @@ pub struct Type {
 /// A single field of a message type.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Field {
     /// The field type.
     #[prost(enumeration = "field::Kind", tag = "1")]
@@ -1395,42 +1439,61 @@ pub mod field {
     #[repr(i32)]
     pub enum Kind {
         /// Field type unknown.
+        #[doc(alias = "TYPE_UNKNOWN")]
         TypeUnknown = 0,
         /// Field type double.
+        #[doc(alias = "TYPE_DOUBLE")]
         TypeDouble = 1,
         /// Field type float.
+        #[doc(alias = "TYPE_FLOAT")]
         TypeFloat = 2,
         /// Field type int64.
+        #[doc(alias = "TYPE_INT64")]
         TypeInt64 = 3,
         /// Field type uint64.
+        #[doc(alias = "TYPE_UINT64")]
         TypeUint64 = 4,
         /// Field type int32.
+        #[doc(alias = "TYPE_INT32")]
         TypeInt32 = 5,
         /// Field type fixed64.
+        #[doc(alias = "TYPE_FIXED64")]
         TypeFixed64 = 6,
         /// Field type fixed32.
+        #[doc(alias = "TYPE_FIXED32")]
         TypeFixed32 = 7,
         /// Field type bool.
+        #[doc(alias = "TYPE_BOOL")]
         TypeBool = 8,
         /// Field type string.
+        #[doc(alias = "TYPE_STRING")]
         TypeString = 9,
         /// Field type group. Proto2 syntax only, and deprecated.
+        #[doc(alias = "TYPE_GROUP")]
         TypeGroup = 10,
         /// Field type message.
+        #[doc(alias = "TYPE_MESSAGE")]
         TypeMessage = 11,
         /// Field type bytes.
+        #[doc(alias = "TYPE_BYTES")]
         TypeBytes = 12,
         /// Field type uint32.
+        #[doc(alias = "TYPE_UINT32")]
         TypeUint32 = 13,
         /// Field type enum.
+        #[doc(alias = "TYPE_ENUM")]
         TypeEnum = 14,
         /// Field type sfixed32.
+        #[doc(alias = "TYPE_SFIXED32")]
         TypeSfixed32 = 15,
         /// Field type sfixed64.
+        #[doc(alias = "TYPE_SFIXED64")]
         TypeSfixed64 = 16,
         /// Field type sint32.
+        #[doc(alias = "TYPE_SINT32")]
         TypeSint32 = 17,
         /// Field type sint64.
+        #[doc(alias = "TYPE_SINT64")]
         TypeSint64 = 18,
     }
     impl Kind {
@@ -1503,12 +1566,16 @@ pub mod field {
     #[repr(i32)]
     pub enum Cardinality {
         /// For fields with unknown cardinality.
+        #[doc(alias = "CARDINALITY_UNKNOWN")]
         Unknown = 0,
         /// For optional fields.
+        #[doc(alias = "CARDINALITY_OPTIONAL")]
         Optional = 1,
         /// For required fields. Proto2 syntax only.
+        #[doc(alias = "CARDINALITY_REQUIRED")]
         Required = 2,
         /// For repeated fields.
+        #[doc(alias = "CARDINALITY_REPEATED")]
         Repeated = 3,
     }
     impl Cardinality {
@@ -1539,6 +1606,7 @@ pub mod field {
 /// Enum type definition.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Enum {
     /// Enum type name.
     #[prost(string, tag = "1")]
@@ -1559,6 +1627,7 @@ pub struct Enum {
 /// Enum value definition.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct EnumValue {
     /// Enum value name.
     #[prost(string, tag = "1")]
@@ -1574,6 +1643,7 @@ pub struct EnumValue {
 /// enumeration, etc.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Option {
     /// The option's name. For protobuf built-in options (options defined in
     /// descriptor.proto), this is the short name. For example, `"map_entry"`.
@@ -1594,8 +1664,10 @@ pub struct Option {
 #[repr(i32)]
 pub enum Syntax {
     /// Syntax `proto2`.
+    #[doc(alias = "SYNTAX_PROTO2")]
     Proto2 = 0,
     /// Syntax `proto3`.
+    #[doc(alias = "SYNTAX_PROTO3")]
     Proto3 = 1,
 }
 impl Syntax {
@@ -1629,6 +1701,7 @@ impl Syntax {
 /// detailed terminology.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Api {
     /// The fully qualified name of this interface, including package name
     /// followed by the interface's simple name.
@@ -1675,6 +1748,7 @@ pub struct Api {
 /// Method represents a method of an API interface.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Method {
     /// The simple name of this method.
     #[prost(string, tag = "1")]
@@ -1788,6 +1862,7 @@ pub struct Method {
 /// ```
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Mixin {
     /// The fully qualified name of the interface which is included.
     #[prost(string, tag = "1")]
@@ -1863,6 +1938,7 @@ pub struct Mixin {
 /// microsecond should be expressed in JSON format as "3.000001s".
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Duration {
     /// Signed seconds of the span of time. Must be from -315,576,000,000
     /// to +315,576,000,000 inclusive. Note: these bounds are computed from:
@@ -2102,6 +2178,7 @@ pub struct Duration {
 /// `INVALID_ARGUMENT` error if any path is unmappable.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct FieldMask {
     /// The set of field mask paths.
     #[prost(string, repeated, tag = "1")]
@@ -2117,6 +2194,7 @@ pub struct FieldMask {
 /// The JSON representation for `Struct` is JSON object.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Struct {
     /// Unordered map of dynamically typed values.
     #[prost(btree_map = "string, message", tag = "1")]
@@ -2133,6 +2211,7 @@ pub struct Struct {
 /// The JSON representation for `Value` is JSON value.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Value {
     /// The kind of value.
     #[prost(oneof = "value::Kind", tags = "1, 2, 3, 4, 5, 6")]
@@ -2143,23 +2222,30 @@ pub mod value {
     /// The kind of value.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
+    #[prost(field_values)]
     pub enum Kind {
         /// Represents a null value.
+        #[doc(alias = "null_value")]
         #[prost(enumeration = "super::NullValue", tag = "1")]
         NullValue(i32),
         /// Represents a double value.
+        #[doc(alias = "number_value")]
         #[prost(double, tag = "2")]
         NumberValue(f64),
         /// Represents a string value.
+        #[doc(alias = "string_value")]
         #[prost(string, tag = "3")]
         StringValue(::prost::alloc::string::String),
         /// Represents a boolean value.
+        #[doc(alias = "bool_value")]
         #[prost(bool, tag = "4")]
         BoolValue(bool),
         /// Represents a structured value.
+        #[doc(alias = "struct_value")]
         #[prost(message, tag = "5")]
         StructValue(super::Struct),
         /// Represents a repeated `Value`.
+        #[doc(alias = "list_value")]
         #[prost(message, tag = "6")]
         ListValue(super::ListValue),
     }
@@ -2169,6 +2255,7 @@ pub mod value {
 /// The JSON representation for `ListValue` is JSON array.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct ListValue {
     /// Repeated field of dynamically typed values.
     #[prost(message, repeated, tag = "1")]
@@ -2183,6 +2270,7 @@ pub struct ListValue {
 #[repr(i32)]
 pub enum NullValue {
     /// Null value.
+    #[doc(alias = "NULL_VALUE")]
     NullValue = 0,
 }
 impl NullValue {
@@ -2304,6 +2392,7 @@ impl NullValue {
 /// the Joda Time's [`ISODateTimeFormat.dateTime()`](<http://www.joda.org/joda-time/apidocs/org/joda/time/format/ISODateTimeFormat.html#dateTime%2D%2D>) to obtain a formatter capable of generating timestamps in this format.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[prost(field_values)]
 pub struct Timestamp {
     /// Represents seconds of UTC time since Unix epoch
     /// 1970-01-01T00:00:00Z. Must be from 0001-01-01T00:00:00Z to
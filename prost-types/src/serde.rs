@@ -0,0 +1,102 @@
+//! `serde` implementations of `Timestamp` and `Duration`, in their Protobuf JSON forms.
+//!
+//! Both are serialized as strings: timestamps in RFC 3339 with a `Z` suffix, such as
+//! `"1972-01-01T10:00:20.021Z"`, and durations as seconds with an `s` suffix, such as `"1.5s"`.
+//! Fractional seconds use 0, 3, 6 or 9 digits. Values outside of the ranges of the JSON mapping
+//! fail to serialize and deserialize.
+
+use core::fmt;
+
+use ::serde::de::{self, Unexpected, Visitor};
+use ::serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Duration, Timestamp};
+
+/// The seconds of `0001-01-01T00:00:00Z`, the earliest timestamp of the JSON mapping.
+const MIN_TIMESTAMP_SECONDS: i64 = -62_135_596_800;
+
+/// The seconds of `9999-12-31T23:59:59Z`, the latest timestamp of the JSON mapping.
+const MAX_TIMESTAMP_SECONDS: i64 = 253_402_300_799;
+
+/// The seconds of the longest duration of the JSON mapping, about 10,000 years.
+const MAX_DURATION_SECONDS: i64 = 315_576_000_000;
+
+fn timestamp_in_range(timestamp: &Timestamp) -> bool {
+    (MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS).contains(&timestamp.seconds)
+}
+
+fn duration_in_range(duration: &Duration) -> bool {
+    (-MAX_DURATION_SECONDS..=MAX_DURATION_SECONDS).contains(&duration.seconds)
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let timestamp = self.normalized();
+        if !timestamp_in_range(&timestamp) {
+            return Err(ser::Error::custom(format_args!(
+                "timestamp out of range: {:?}",
+                self
+            )));
+        }
+        serializer.collect_str(&timestamp)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TimestampVisitor;
+
+        impl Visitor<'_> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an RFC 3339 timestamp between years 1 and 9999")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Timestamp, E> {
+                match value.parse::<Timestamp>() {
+                    Ok(timestamp) if timestamp_in_range(&timestamp) => Ok(timestamp),
+                    _ => Err(E::invalid_value(Unexpected::Str(value), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_str(TimestampVisitor)
+    }
+}
+
+impl Serialize for Duration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let duration = self.normalized();
+        if !duration_in_range(&duration) {
+            return Err(ser::Error::custom(format_args!(
+                "duration out of range: {:?}",
+                self
+            )));
+        }
+        serializer.collect_str(&duration)
+    }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DurationVisitor;
+
+        impl Visitor<'_> for DurationVisitor {
+            type Value = Duration;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a duration in seconds with an `s` suffix, of at most 10,000 years")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Duration, E> {
+                match value.parse::<Duration>() {
+                    Ok(duration) if duration_in_range(&duration) => Ok(duration),
+                    _ => Err(E::invalid_value(Unexpected::Str(value), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_str(DurationVisitor)
+    }
+}
@@ -1,5 +1,8 @@
 use super::*;
 
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+use core::option::Option;
+
 impl Timestamp {
     /// Normalizes the timestamp to a canonical format.
     ///
@@ -112,6 +115,128 @@ impl Timestamp {
 
         Timestamp::try_from(date_time)
     }
+
+    /// Adds a duration to the timestamp, returning `None` if the result is out of range.
+    ///
+    /// The result is normalized.
+    pub fn checked_add(self, duration: Duration) -> Option<Timestamp> {
+        Timestamp::from_nanos(self.total_nanos() + duration.total_nanos())
+    }
+
+    /// Subtracts a duration from the timestamp, returning `None` if the result is out of range.
+    ///
+    /// The result is normalized.
+    pub fn checked_sub(self, duration: Duration) -> Option<Timestamp> {
+        Timestamp::from_nanos(self.total_nanos() - duration.total_nanos())
+    }
+
+    /// Adds a duration to the timestamp, saturating at the bounds of the range of timestamps.
+    ///
+    /// The result is normalized.
+    pub fn saturating_add(self, duration: Duration) -> Timestamp {
+        Timestamp::from_nanos_saturating(self.total_nanos() + duration.total_nanos())
+    }
+
+    /// Subtracts a duration from the timestamp, saturating at the bounds of the range of
+    /// timestamps.
+    ///
+    /// The result is normalized.
+    pub fn saturating_sub(self, duration: Duration) -> Timestamp {
+        Timestamp::from_nanos_saturating(self.total_nanos() - duration.total_nanos())
+    }
+
+    /// Returns the duration from `earlier` to the timestamp, which is negative if `earlier` is
+    /// later, or `None` if the duration is out of range.
+    ///
+    /// The result is normalized.
+    pub fn checked_duration_since(self, earlier: Timestamp) -> Option<Duration> {
+        Duration::from_nanos(self.total_nanos() - earlier.total_nanos())
+    }
+
+    /// Returns the number of nanoseconds since the Unix epoch, which can not overflow for any
+    /// `seconds` and `nanos`, whether normalized or not.
+    fn total_nanos(&self) -> i128 {
+        i128::from(self.seconds) * i128::from(NANOS_PER_SECOND) + i128::from(self.nanos)
+    }
+
+    /// Creates a normalized timestamp from a number of nanoseconds since the Unix epoch, if it is
+    /// in range.
+    fn from_nanos(nanos: i128) -> Option<Timestamp> {
+        let seconds = i64::try_from(nanos.div_euclid(i128::from(NANOS_PER_SECOND))).ok()?;
+        let nanos = nanos.rem_euclid(i128::from(NANOS_PER_SECOND)) as i32;
+        Some(Timestamp { seconds, nanos })
+    }
+
+    fn from_nanos_saturating(nanos: i128) -> Timestamp {
+        Timestamp::from_nanos(nanos).unwrap_or(if nanos < 0 {
+            Timestamp {
+                seconds: i64::MIN,
+                nanos: 0,
+            }
+        } else {
+            Timestamp {
+                seconds: i64::MAX,
+                nanos: NANOS_MAX,
+            }
+        })
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    /// Adds a duration to the timestamp.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result is out of range. See [`Timestamp::checked_add`] for a version
+    /// without panics.
+    fn add(self, duration: Duration) -> Timestamp {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to timestamp")
+    }
+}
+
+impl AddAssign<Duration> for Timestamp {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    /// Subtracts a duration from the timestamp.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result is out of range. See [`Timestamp::checked_sub`] for a version
+    /// without panics.
+    fn sub(self, duration: Duration) -> Timestamp {
+        self.checked_sub(duration)
+            .expect("overflow when subtracting duration from timestamp")
+    }
+}
+
+impl SubAssign<Duration> for Timestamp {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+impl Sub for Timestamp {
+    type Output = Duration;
+
+    /// Returns the duration between two timestamps, which is negative if `rhs` is later.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the duration is out of range. See [`Timestamp::checked_duration_since`] for a
+    /// version without panics.
+    fn sub(self, rhs: Timestamp) -> Duration {
+        self.checked_duration_since(rhs)
+            .expect("overflow when subtracting timestamps")
+    }
 }
 
 impl Name for Timestamp {
@@ -272,6 +397,60 @@ mod tests {
     #[cfg(feature = "std")]
     use std::time::{self, SystemTime, UNIX_EPOCH};
 
+    #[test]
+    fn check_timestamp_arithmetic() {
+        let timestamp = |seconds, nanos| Timestamp { seconds, nanos };
+        let duration = |seconds, nanos| Duration { seconds, nanos };
+
+        let start = timestamp(100, 800_000_000);
+        assert_eq!(
+            start + duration(1, 300_000_000),
+            timestamp(102, 100_000_000)
+        );
+        assert_eq!(start - duration(101, 0), timestamp(-1, 800_000_000));
+        assert_eq!(
+            start + duration(-1, -900_000_000),
+            timestamp(98, 900_000_000)
+        );
+        assert_eq!(
+            start - timestamp(102, 100_000_000),
+            duration(-1, -300_000_000)
+        );
+
+        let mut deadline = start;
+        deadline += duration(0, 300_000_000);
+        deadline -= duration(2, 0);
+        assert_eq!(deadline, timestamp(99, 100_000_000));
+
+        let max = timestamp(i64::MAX, NANOS_MAX);
+        let min = timestamp(i64::MIN, 0);
+        assert_eq!(max.checked_add(duration(0, 1)), None);
+        assert_eq!(min.checked_sub(duration(0, 1)), None);
+        assert_eq!(max.checked_duration_since(min), None);
+        assert_eq!(
+            max.checked_duration_since(timestamp(0, 0)),
+            Some(duration(i64::MAX, NANOS_MAX))
+        );
+        assert_eq!(max.saturating_add(duration(1, 0)), max);
+        assert_eq!(min.saturating_sub(duration(1, 0)), min);
+        assert_eq!(
+            min.saturating_add(duration(1, 0)),
+            timestamp(i64::MIN + 1, 0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow when subtracting timestamps")]
+    fn check_timestamp_sub_overflow() {
+        let _ = Timestamp {
+            seconds: i64::MAX,
+            nanos: 0,
+        } - Timestamp {
+            seconds: i64::MIN,
+            nanos: 0,
+        };
+    }
+
     #[cfg(feature = "std")]
     proptest! {
         #[test]
@@ -1,7 +1,7 @@
 use std::mem;
 
-use bytes::Buf;
 use criterion::{Criterion, Throughput};
+use prost::buf::Buf;
 use prost::encoding::varint::{decode_varint, encode_varint, encoded_len_varint};
 use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 
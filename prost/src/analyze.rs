@@ -0,0 +1,88 @@
+//! Inspection of the size of encoded messages.
+//!
+//! ```rust
+//! use prost::{analyze, Message};
+//!
+//! #[derive(Clone, PartialEq, Message)]
+//! struct Upload {
+//!     #[prost(string, tag = "1")]
+//!     name: String,
+//!     #[prost(bytes = "vec", tag = "2")]
+//!     payload: Vec<u8>,
+//! }
+//!
+//! let upload = Upload {
+//!     name: "log".to_string(),
+//!     payload: vec![0; 1000],
+//! };
+//! let buf = upload.encode_to_vec();
+//!
+//! let profile = analyze::size_profile::<Upload>(&buf).unwrap();
+//! assert_eq!(profile.get(1).unwrap().len, 5);
+//! assert_eq!(profile.get(2).unwrap().len, 1003);
+//! assert_eq!(profile.len(), buf.len());
+//! ```
+
+use alloc::collections::BTreeMap;
+
+use crate::{DecodeError, FieldIter, Message};
+
+/// The size of the occurrences of a field in an encoded message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FieldSize {
+    /// The number of occurrences of the field. Packed repeated fields usually occur once.
+    pub occurrences: usize,
+    /// The number of bytes of all occurrences, including their keys and length prefixes.
+    pub len: usize,
+}
+
+/// The size breakdown of an encoded message by top-level field.
+///
+/// Unknown fields are included, since they take up space on the wire as well.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SizeProfile {
+    fields: BTreeMap<u32, FieldSize>,
+}
+
+impl SizeProfile {
+    /// Returns the size of the field with the given tag, or `None` if the message does not
+    /// contain the field.
+    pub fn get(&self, tag: u32) -> Option<FieldSize> {
+        self.fields.get(&tag).copied()
+    }
+
+    /// Returns an iterator over the tags and sizes of the fields, in tag order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, FieldSize)> + '_ {
+        self.fields.iter().map(|(&tag, &size)| (tag, size))
+    }
+
+    /// Returns the number of bytes of the encoded message.
+    pub fn len(&self) -> usize {
+        self.fields.values().map(|size| size.len).sum()
+    }
+
+    /// Returns `true` if the encoded message is empty.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// Returns the number of bytes taken by each top-level field of an encoded message of type `M`.
+///
+/// The buffer is decoded as an `M` first, so that only valid messages are profiled. The fields of
+/// nested messages are accounted to their parent field; they can be profiled by passing the
+/// value of the parent field, as returned by [`FieldIter`], to this function.
+pub fn size_profile<M>(buf: &[u8]) -> Result<SizeProfile, DecodeError>
+where
+    M: Message + Default,
+{
+    M::decode(buf)?;
+    let mut profile = SizeProfile::default();
+    for field in FieldIter::new(buf) {
+        let field = field?;
+        let size = profile.fields.entry(field.tag).or_default();
+        size.occurrences += 1;
+        size.len += field.encoded.len();
+    }
+    Ok(profile)
+}
@@ -0,0 +1,177 @@
+//! Encoding and decoding of sequences of length-delimited messages.
+
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+use crate::buf::{Buf, BufMut};
+use crate::encoding::{encode_varint, encoded_len_varint, message, DecodeContext, WireType};
+use crate::{DecodeError, EncodeError, Message};
+
+/// Encodes the messages to the buffer as consecutive length-delimited records, in the format of
+/// [`Message::encode_length_delimited`].
+///
+/// The length of each message is computed once, and the buffer is checked to have enough
+/// capacity for all of them before anything is written. An error is returned if it does not,
+/// in which case the buffer is left unchanged.
+///
+/// ```rust
+/// let values = [1u32, 2, 3];
+/// let mut buf = Vec::new();
+/// prost::encode_many(&values, &mut buf).unwrap();
+/// assert_eq!(prost::decode_many::<u32>(buf.as_slice()).unwrap(), values);
+/// ```
+pub fn encode_many<M: Message>(messages: &[M], buf: &mut impl BufMut) -> Result<(), EncodeError> {
+    let (lens, required) = encoded_lens(messages);
+    let remaining = buf.remaining_mut();
+    if required > remaining {
+        return Err(EncodeError::new(required, remaining));
+    }
+    for (message, len) in messages.iter().zip(lens) {
+        encode_varint(len as u64, buf);
+        message.encode_raw(buf);
+    }
+    Ok(())
+}
+
+/// Encodes the messages to a new `Vec<u8>` as consecutive length-delimited records, allocated
+/// with the exact capacity needed.
+pub fn encode_many_to_vec<M: Message>(messages: &[M]) -> Vec<u8> {
+    let (lens, required) = encoded_lens(messages);
+    let mut buf = Vec::with_capacity(required);
+    for (message, len) in messages.iter().zip(lens) {
+        encode_varint(len as u64, &mut buf);
+        message.encode_raw(&mut buf);
+    }
+    buf
+}
+
+/// Returns the encoded length of each message, and the total length of the length-delimited
+/// records.
+fn encoded_lens<M: Message>(messages: &[M]) -> (Vec<usize>, usize) {
+    let lens = messages
+        .iter()
+        .map(Message::encoded_len)
+        .collect::<Vec<_>>();
+    let required = lens
+        .iter()
+        .map(|len| len + encoded_len_varint(*len as u64))
+        .sum();
+    (lens, required)
+}
+
+/// Decodes all the length-delimited messages of the buffer, until its end.
+///
+/// The decode fails if any message is invalid, or if the buffer ends within a record.
+pub fn decode_many<M: Message + Default>(buf: impl Buf) -> Result<Vec<M>, DecodeError> {
+    decode_many_iter(buf).collect()
+}
+
+/// Returns an iterator decoding the length-delimited messages of the buffer one at a time, until
+/// its end.
+///
+/// The iterator yields an error if a message is invalid, or if the buffer ends within a record,
+/// and then ends, since the start of the next record is unknown.
+///
+/// ```rust
+/// let buf = prost::encode_many_to_vec(&[String::from("a"), String::from("b")]);
+/// for value in prost::decode_many_iter::<String, _>(buf.as_slice()) {
+///     println!("{}", value.unwrap());
+/// }
+/// ```
+pub fn decode_many_iter<M: Message + Default, B: Buf>(buf: B) -> DecodeManyIter<M, B> {
+    DecodeManyIter {
+        buf,
+        failed: false,
+        message: PhantomData,
+    }
+}
+
+/// An iterator decoding length-delimited messages, returned by [`decode_many_iter`].
+#[derive(Debug)]
+pub struct DecodeManyIter<M, B> {
+    buf: B,
+    failed: bool,
+    message: PhantomData<fn() -> M>,
+}
+
+impl<M, B> DecodeManyIter<M, B> {
+    /// Returns the buffer, positioned after the last decoded record.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+impl<M: Message + Default, B: Buf> Iterator for DecodeManyIter<M, B> {
+    type Item = Result<M, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || !self.buf.has_remaining() {
+            return None;
+        }
+        let mut value = M::default();
+        let result = message::merge(
+            WireType::LengthDelimited,
+            &mut value,
+            &mut self.buf,
+            DecodeContext::default(),
+        );
+        self.failed = result.is_err();
+        Some(result.map(|()| value))
+    }
+}
+
+impl<M: Message + Default, B: Buf> FusedIterator for DecodeManyIter<M, B> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+
+    #[test]
+    fn encode_and_decode_many() {
+        let values = vec!["a".to_string(), String::new(), "ccc".to_string()];
+        let buf = encode_many_to_vec(&values);
+        assert_eq!(buf.len(), buf.capacity());
+
+        let mut expected = Vec::new();
+        for value in &values {
+            value.encode_length_delimited(&mut expected).unwrap();
+        }
+        assert_eq!(buf, expected);
+
+        let mut encoded = Vec::new();
+        encode_many(&values, &mut encoded).unwrap();
+        assert_eq!(encoded, expected);
+
+        assert_eq!(decode_many::<String>(buf.as_slice()).unwrap(), values);
+        assert!(decode_many::<String>(&[][..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn encode_many_checks_capacity() {
+        let values = [1u32, 2, 3];
+        let mut storage = [0u8; 5];
+        let mut buf = &mut storage[..];
+        let error = encode_many(&values, &mut buf).unwrap_err();
+        assert_eq!(error.required_capacity(), 9);
+        assert_eq!(error.remaining(), 5);
+        assert_eq!(storage, [0; 5]);
+    }
+
+    #[test]
+    fn decode_many_iter_stops_after_error() {
+        let mut buf = encode_many_to_vec(&[1u32, 2]);
+        // A record whose length exceeds the rest of the buffer.
+        buf.extend_from_slice(&[5, 8]);
+
+        let mut iter = decode_many_iter::<u32, _>(buf.as_slice());
+        assert_eq!(iter.next(), Some(Ok(1)));
+        assert_eq!(iter.next(), Some(Ok(2)));
+        assert!(matches!(iter.next(), Some(Err(_))));
+        assert_eq!(iter.next(), None);
+        assert!(decode_many::<u32>(buf.as_slice()).is_err());
+    }
+}
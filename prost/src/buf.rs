@@ -0,0 +1,154 @@
+//! The buffer traits messages are encoded to and decoded from.
+//!
+//! Messages are decoded from a [`Buf`] and encoded to a [`BufMut`], the traits of the same name
+//! in `bytes` 1.x, re-exported.
+//!
+//! Buffers don't need to be contiguous: fields which are split across chunks are decoded without
+//! first copying them into a contiguous buffer. [`Chunks`] is a buffer made of a sequence of byte
+//! slices, such as the segments of a rope.
+//!
+//! ```rust
+//! use prost::buf::{Buf, BufMut};
+//!
+//! let mut buf = Vec::new();
+//! buf.put_u32_le(7);
+//! buf.put_slice(b"hi");
+//!
+//! let mut slice = buf.as_slice();
+//! assert_eq!(slice.get_u32_le(), 7);
+//! assert_eq!(slice.chunk(), b"hi");
+//! ```
+
+pub use bytes::{Buf, BufMut};
+
+/// A buffer made of a sequence of byte slices, which are decoded as if they were concatenated.
+///
+/// This is the buffer used by [`Message::decode_from_chunks`](crate::Message::decode_from_chunks).
+///
+/// ```rust
+/// use prost::buf::{Buf, Chunks};
+///
+/// let segments: [&[u8]; 3] = [b"he", b"", b"llo"];
+/// let mut buf = Chunks::new(&segments);
+/// assert_eq!(buf.remaining(), 5);
+/// assert_eq!(buf.chunk(), b"he");
+/// buf.advance(3);
+/// assert_eq!(buf.chunk(), b"lo");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Chunks<'a, T> {
+    chunks: &'a [T],
+    /// The position in the first chunk.
+    offset: usize,
+    remaining: usize,
+}
+
+impl<'a, T: AsRef<[u8]>> Chunks<'a, T> {
+    /// Creates a buffer of the concatenation of `chunks`.
+    pub fn new(chunks: &'a [T]) -> Self {
+        let mut buf = Chunks {
+            chunks,
+            offset: 0,
+            remaining: chunks.iter().map(|chunk| chunk.as_ref().len()).sum(),
+        };
+        buf.skip_empty();
+        buf
+    }
+
+    fn current(&self) -> &[u8] {
+        match self.chunks.first() {
+            Some(chunk) => &chunk.as_ref()[self.offset..],
+            None => &[],
+        }
+    }
+
+    fn advance_by(&mut self, mut cnt: usize) {
+        assert!(
+            cnt <= self.remaining,
+            "cannot advance past the end of the buffer"
+        );
+        self.remaining -= cnt;
+        while cnt > 0 {
+            let len = self.current().len();
+            if cnt < len {
+                self.offset += cnt;
+                return;
+            }
+            cnt -= len;
+            self.chunks = &self.chunks[1..];
+            self.offset = 0;
+        }
+        self.skip_empty();
+    }
+
+    /// Moves past exhausted and empty chunks, so that `current` is empty only at the end of the
+    /// buffer.
+    fn skip_empty(&mut self) {
+        while let Some((chunk, rest)) = self.chunks.split_first() {
+            if self.offset < chunk.as_ref().len() {
+                break;
+            }
+            self.chunks = rest;
+            self.offset = 0;
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> Buf for Chunks<'_, T> {
+    fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.current()
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.advance_by(cnt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use crate::encoding::{decode_varint, encode_varint};
+
+    #[test]
+    fn chunks() {
+        let chunks: [&[u8]; 5] = [b"", b"ab", b"", b"cde", b"f"];
+        let mut buf = Chunks::new(&chunks);
+        assert_eq!(buf.remaining(), 6);
+        assert_eq!(buf.chunk(), b"ab");
+        buf.advance(1);
+        assert_eq!(buf.chunk(), b"b");
+        buf.advance(1);
+        assert_eq!(buf.chunk(), b"cde");
+
+        let mut bytes = [0; 3];
+        buf.copy_to_slice(&mut bytes);
+        assert_eq!(&bytes, b"cde");
+        assert_eq!(buf.chunk(), b"f");
+        assert_eq!(buf.get_u8(), b'f');
+        assert!(!buf.has_remaining());
+        assert_eq!(buf.chunk(), b"");
+
+        let empty: [&[u8]; 2] = [b"", b""];
+        assert!(!Chunks::new(&empty).has_remaining());
+    }
+
+    #[test]
+    fn chunks_split_varint() {
+        let mut encoded = Vec::new();
+        encode_varint(u64::MAX, &mut encoded);
+        for split in 0..=encoded.len() {
+            let (head, tail) = encoded.split_at(split);
+            let chunks = [head, tail];
+            let mut buf = Chunks::new(&chunks);
+            assert_eq!(decode_varint(&mut buf).unwrap(), u64::MAX);
+            assert!(!buf.has_remaining());
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Support for reusing encoding buffers across [`Message`](crate::Message) encodes.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+
+/// A pool of reusable byte buffers.
+///
+/// Used with [`Message::encode_to_vec_in`](crate::Message::encode_to_vec_in) to avoid
+/// allocating a new `Vec` for every encoded message.
+pub trait BufferPool {
+    /// Takes an empty buffer from the pool, with a capacity of at least `capacity` bytes if
+    /// possible.
+    fn acquire(&self, capacity: usize) -> Vec<u8>;
+
+    /// Returns a buffer to the pool so it can be reused.
+    fn release(&self, buf: Vec<u8>);
+}
+
+impl<P> BufferPool for &P
+where
+    P: BufferPool + ?Sized,
+{
+    fn acquire(&self, capacity: usize) -> Vec<u8> {
+        (**self).acquire(capacity)
+    }
+
+    fn release(&self, buf: Vec<u8>) {
+        (**self).release(buf)
+    }
+}
+
+/// A buffer borrowed from a [`BufferPool`].
+///
+/// The buffer is returned to the pool when dropped. Use [`PooledBuf::into_inner`] to keep the
+/// buffer instead.
+pub struct PooledBuf<'a, P>
+where
+    P: BufferPool + ?Sized,
+{
+    buf: Vec<u8>,
+    pool: &'a P,
+}
+
+impl<'a, P> PooledBuf<'a, P>
+where
+    P: BufferPool + ?Sized,
+{
+    /// Takes an empty buffer with at least `capacity` bytes of capacity from the pool.
+    pub fn new(pool: &'a P, capacity: usize) -> Self {
+        let mut buf = pool.acquire(capacity);
+        buf.clear();
+        buf.reserve(capacity);
+        PooledBuf { buf, pool }
+    }
+
+    /// Detaches the buffer from the pool, returning it.
+    pub fn into_inner(mut self) -> Vec<u8> {
+        mem::take(&mut self.buf)
+    }
+}
+
+impl<P> Deref for PooledBuf<'_, P>
+where
+    P: BufferPool + ?Sized,
+{
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+impl<P> DerefMut for PooledBuf<'_, P>
+where
+    P: BufferPool + ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+}
+
+impl<P> AsRef<[u8]> for PooledBuf<'_, P>
+where
+    P: BufferPool + ?Sized,
+{
+    fn as_ref(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl<P> fmt::Debug for PooledBuf<'_, P>
+where
+    P: BufferPool + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PooledBuf").field(&self.buf).finish()
+    }
+}
+
+impl<P> Drop for PooledBuf<'_, P>
+where
+    P: BufferPool + ?Sized,
+{
+    fn drop(&mut self) {
+        // Buffers detached by `into_inner` have no allocation worth keeping.
+        if self.buf.capacity() > 0 {
+            let mut buf = mem::take(&mut self.buf);
+            buf.clear();
+            self.pool.release(buf);
+        }
+    }
+}
+
+/// A thread-safe [`BufferPool`] keeping up to a fixed number of buffers.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct VecPool {
+    bufs: std::sync::Mutex<Vec<Vec<u8>>>,
+    max_bufs: usize,
+}
+
+#[cfg(feature = "std")]
+impl VecPool {
+    /// Creates a pool retaining at most `max_bufs` released buffers.
+    pub fn new(max_bufs: usize) -> Self {
+        VecPool {
+            bufs: std::sync::Mutex::new(Vec::with_capacity(max_bufs)),
+            max_bufs,
+        }
+    }
+
+    /// Returns the number of buffers currently available in the pool.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if the pool has no buffers available.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Vec<u8>>> {
+        // A panic while holding the lock can not leave the list of buffers inconsistent.
+        self.bufs
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl BufferPool for VecPool {
+    fn acquire(&self, capacity: usize) -> Vec<u8> {
+        self.lock()
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(capacity))
+    }
+
+    fn release(&self, buf: Vec<u8>) {
+        let mut bufs = self.lock();
+        if bufs.len() < self.max_bufs {
+            bufs.push(buf);
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pooled_buf_is_reused() {
+        let pool = VecPool::new(1);
+        let ptr = {
+            let mut buf = PooledBuf::new(&pool, 16);
+            buf.extend_from_slice(b"hello");
+            buf.as_ptr()
+        };
+        assert_eq!(pool.len(), 1);
+
+        let buf = PooledBuf::new(&pool, 4);
+        assert!(buf.is_empty());
+        assert_eq!(buf.as_ptr(), ptr);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn into_inner_detaches_buf() {
+        let pool = VecPool::new(1);
+        let mut buf = PooledBuf::new(&pool, 16);
+        buf.push(1);
+        assert_eq!(buf.into_inner(), [1]);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn pool_is_bounded() {
+        let pool = VecPool::new(1);
+        let a = PooledBuf::new(&pool, 16);
+        let b = PooledBuf::new(&pool, 16);
+        drop(a);
+        drop(b);
+        assert_eq!(pool.len(), 1);
+    }
+}
@@ -4,16 +4,29 @@
 
 #![allow(clippy::implicit_hasher, clippy::ptr_arg)]
 
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::String;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::fmt;
 use core::mem;
 use core::str;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
-use ::bytes::{Buf, BufMut, Bytes};
+use ::bytes::Bytes;
 
+use crate::buf::{Buf, BufMut};
+
+use crate::trace;
 use crate::DecodeError;
+use crate::DecodeErrorKind;
+use crate::DecodeObserver;
+use crate::Extensions;
+#[cfg(target_has_atomic = "ptr")]
+use crate::Interner;
 use crate::Message;
 
 pub mod varint;
@@ -27,13 +40,24 @@ pub use length_delimiter::{
 pub mod wire_type;
 pub use wire_type::{check_wire_type, WireType};
 
+mod time;
+pub use time::{duration, saturating_duration};
+#[cfg(feature = "std")]
+pub use time::{saturating_timestamp, timestamp};
+
 /// Additional information passed to every decode/merge function.
 ///
 /// The context should be passed by value and can be freely cloned. When passing
 /// to a function which is decoding a nested object, then use `enter_recursion`.
+///
+/// A context with [`Extensions`] can be passed to [`Message::merge_with_context`] to share
+/// state with custom `Message` implementations for the duration of a decode, such as a pool of
+/// interned strings.
+///
+/// [`Message::merge_with_context`]: crate::Message::merge_with_context
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "no-recursion-limit", derive(Default))]
-pub struct DecodeContext {
+pub struct DecodeContext<'a> {
     /// How many times we can recurse in the current decode stack before we hit
     /// the recursion limit.
     ///
@@ -42,19 +66,240 @@ pub struct DecodeContext {
     /// crate with the `no-recursion-limit` feature.
     #[cfg(not(feature = "no-recursion-limit"))]
     recurse_count: u32,
+
+    /// The observer notified about decode anomalies, if any.
+    observer: Option<Observer<'a>>,
+
+    /// The extensions shared with every decode function, if any.
+    extensions: Option<&'a Extensions>,
+
+    /// How repeated occurrences of singular fields are handled.
+    duplicate_field_policy: DuplicateFieldPolicy,
+
+    /// The limits on skipped unknown fields, if any.
+    skip_limits: Option<&'a SkipLimits>,
+
+    /// How many skipped groups enclose the current position.
+    skipped_group_depth: u32,
+}
+
+// Contexts may be moved to, and shared with, other threads along with the decode using them.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<DecodeContext<'static>>();
+};
+
+/// How the decoder handles a singular field occurring several times in the same message.
+///
+/// The Protobuf specification requires the last occurrence of a scalar field to win, and the
+/// occurrences of a message field to be merged. Parsers which disagree about this can be made to
+/// see different values in the same input, so security-sensitive decoders may prefer to keep the
+/// first occurrence, or to reject such input altogether.
+///
+/// The policy applies to the non-repeated fields of messages, groups and oneofs of derived
+/// [`Message`] implementations, each field number separately: different fields of a oneof still
+/// replace each other. Repeated and map fields are not affected.
+///
+/// # Examples
+///
+/// ```
+/// use prost::{DecodeContext, DuplicateFieldPolicy, Message};
+///
+/// #[derive(Clone, PartialEq, Message)]
+/// struct Transfer {
+///     #[prost(uint64, tag = "1")]
+///     amount: u64,
+/// }
+///
+/// // `amount` is set to 1, then to 1000.
+/// let buf = [0x08, 0x01, 0x08, 0xe8, 0x07];
+/// assert_eq!(Transfer::decode(&buf[..]).unwrap().amount, 1000);
+///
+/// let ctx = DecodeContext::new().with_duplicate_field_policy(DuplicateFieldPolicy::FirstWins);
+/// assert_eq!(Transfer::decode_with_context(&buf[..], ctx).unwrap().amount, 1);
+///
+/// let ctx = DecodeContext::new().with_duplicate_field_policy(DuplicateFieldPolicy::Error);
+/// assert!(Transfer::decode_with_context(&buf[..], ctx).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DuplicateFieldPolicy {
+    /// The last occurrence of a scalar field wins, and the occurrences of a message field are
+    /// merged, as the Protobuf specification requires.
+    #[default]
+    LastWins,
+    /// The first occurrence of a field wins, and later occurrences are skipped.
+    FirstWins,
+    /// A field occurring several times fails the decode.
+    Error,
+}
+
+/// Limits on the unknown fields skipped while decoding, set with
+/// [`DecodeContext::with_skip_limits`].
+///
+/// Skipping an unknown group requires decoding the keys of all its fields, down to the innermost
+/// nested group, so that input made of deeply nested or long unknown groups costs CPU time even
+/// though none of it is kept. The nesting of skipped groups is otherwise only bounded by the
+/// recursion limit, which is removed by the `no-recursion-limit` feature.
+///
+/// Exceeding a limit fails the decode with a [`DecodeErrorKind::SkipLimitExceeded`] error, as
+/// soon as it is detected: a length-delimited value is rejected before it is skipped.
+///
+/// The skipped bytes are counted across all the decodes using the same limits, until
+/// [`reset`](SkipLimits::reset). The limits can be shared by decodes running on several threads,
+/// although the count is then approximate, as concurrent skips may overwrite each other's count.
+///
+/// # Examples
+///
+/// ```
+/// use prost::{DecodeContext, DecodeErrorKind, Message, SkipLimits};
+///
+/// // An unknown group with tag 1, nested three times.
+/// let buf = [0x0b, 0x0b, 0x0b, 0x0c, 0x0c, 0x0c];
+/// let limits = SkipLimits::new().max_group_depth(2);
+/// let ctx = DecodeContext::new().with_skip_limits(&limits);
+/// let error = <()>::decode_with_context(&buf[..], ctx).unwrap_err();
+/// assert_eq!(error.kind(), DecodeErrorKind::SkipLimitExceeded);
+/// ```
+#[derive(Debug)]
+pub struct SkipLimits {
+    max_group_depth: u32,
+    max_skipped_bytes: usize,
+    skipped_bytes: AtomicUsize,
+}
+
+impl SkipLimits {
+    /// Creates limits which accept any unknown fields.
+    pub const fn new() -> Self {
+        SkipLimits {
+            max_group_depth: u32::MAX,
+            max_skipped_bytes: usize::MAX,
+            skipped_bytes: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sets the maximum number of nested unknown groups. Zero rejects any unknown group.
+    pub const fn max_group_depth(mut self, depth: u32) -> Self {
+        self.max_group_depth = depth;
+        self
+    }
+
+    /// Sets the maximum number of bytes of unknown field values to skip, including the keys of the
+    /// fields of skipped groups.
+    pub const fn max_skipped_bytes(mut self, bytes: usize) -> Self {
+        self.max_skipped_bytes = bytes;
+        self
+    }
+
+    /// Returns the number of bytes skipped since the limits were created or reset.
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Resets the number of skipped bytes to zero, to reuse the limits for another decode.
+    pub fn reset(&self) {
+        self.skipped_bytes.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for SkipLimits {
+    fn default() -> Self {
+        SkipLimits::new()
+    }
+}
+
+/// A [`DecodeObserver`] along with the length of the decoded buffer, used to turn the remaining
+/// length of the buffer into an offset.
+#[derive(Clone, Copy)]
+struct Observer<'a> {
+    observer: &'a dyn DecodeObserver,
+    len: usize,
+}
+
+impl fmt::Debug for Observer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observer")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
 }
 
 #[cfg(not(feature = "no-recursion-limit"))]
-impl Default for DecodeContext {
+impl Default for DecodeContext<'_> {
     #[inline]
-    fn default() -> DecodeContext {
+    fn default() -> Self {
         DecodeContext {
             recurse_count: crate::RECURSION_LIMIT,
+            observer: None,
+            extensions: None,
+            duplicate_field_policy: DuplicateFieldPolicy::LastWins,
+            skip_limits: None,
+            skipped_group_depth: 0,
         }
     }
 }
 
-impl DecodeContext {
+impl<'a> DecodeContext<'a> {
+    /// Creates a context with no observer and no extensions.
+    pub fn new() -> Self {
+        DecodeContext::default()
+    }
+
+    /// Sets the observer notified about anomalies tolerated by the decoder.
+    ///
+    /// Offsets are reported from the start of the buffer passed to
+    /// [`Message::merge_with_context`].
+    ///
+    /// [`Message::merge_with_context`]: crate::Message::merge_with_context
+    pub fn with_observer(mut self, observer: &'a dyn DecodeObserver) -> Self {
+        self.observer = Some(Observer { observer, len: 0 });
+        self
+    }
+
+    /// Sets the extensions returned by [`DecodeContext::extensions`].
+    pub fn with_extensions(mut self, extensions: &'a Extensions) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// Sets how singular fields occurring several times in the same message are handled.
+    pub fn with_duplicate_field_policy(mut self, policy: DuplicateFieldPolicy) -> Self {
+        self.duplicate_field_policy = policy;
+        self
+    }
+
+    /// Returns the policy set with [`DecodeContext::with_duplicate_field_policy`].
+    pub fn duplicate_field_policy(&self) -> DuplicateFieldPolicy {
+        self.duplicate_field_policy
+    }
+
+    /// Sets the limits on the unknown fields skipped by the decode.
+    pub fn with_skip_limits(mut self, limits: &'a SkipLimits) -> Self {
+        self.skip_limits = Some(limits);
+        self
+    }
+
+    /// Returns the extensions set with [`DecodeContext::with_extensions`], or empty extensions if
+    /// none were set.
+    pub fn extensions(&self) -> &'a Extensions {
+        self.extensions.unwrap_or(&crate::extensions::EMPTY)
+    }
+
+    /// Returns the interner set with [`Extensions::set_interner`], if any.
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn interner(&self) -> Option<&'a dyn Interner> {
+        self.extensions().interner()
+    }
+
+    /// Marks the start of the decode of `buf`, from which the offsets reported to the observer
+    /// are computed.
+    pub(crate) fn start(mut self, buf: &impl Buf) -> Self {
+        if let Some(observer) = &mut self.observer {
+            observer.len = buf.remaining();
+        }
+        self
+    }
+
     /// Call this function before recursively decoding.
     ///
     /// There is no `exit` function since this function creates a new `DecodeContext`
@@ -62,16 +307,17 @@ impl DecodeContext {
     // at the previous level of recursion.
     #[cfg(not(feature = "no-recursion-limit"))]
     #[inline]
-    pub(crate) fn enter_recursion(&self) -> DecodeContext {
+    pub fn enter_recursion(&self) -> DecodeContext<'a> {
         DecodeContext {
             recurse_count: self.recurse_count - 1,
+            ..self.clone()
         }
     }
 
     #[cfg(feature = "no-recursion-limit")]
     #[inline]
-    pub(crate) fn enter_recursion(&self) -> DecodeContext {
-        DecodeContext {}
+    pub fn enter_recursion(&self) -> DecodeContext<'a> {
+        self.clone()
     }
 
     /// Checks whether the recursion limit has been reached in the stack of
@@ -81,9 +327,12 @@ impl DecodeContext {
     /// Returns `Err<DecodeError>` if the recursion limit has been reached.
     #[cfg(not(feature = "no-recursion-limit"))]
     #[inline]
-    pub(crate) fn limit_reached(&self) -> Result<(), DecodeError> {
+    pub fn limit_reached(&self) -> Result<(), DecodeError> {
         if self.recurse_count == 0 {
-            Err(DecodeError::new("recursion limit reached"))
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::RecursionLimitReached,
+                "recursion limit reached",
+            ))
         } else {
             Ok(())
         }
@@ -92,16 +341,89 @@ impl DecodeContext {
     #[cfg(feature = "no-recursion-limit")]
     #[inline]
     #[allow(clippy::unnecessary_wraps)] // needed in other features
-    pub(crate) fn limit_reached(&self) -> Result<(), DecodeError> {
+    pub fn limit_reached(&self) -> Result<(), DecodeError> {
+        Ok(())
+    }
+
+    /// Returns the context to skip the fields of an unknown group with, checking the maximum
+    /// group depth of the skip limits, if any.
+    fn enter_skipped_group(&self) -> Result<DecodeContext<'a>, DecodeError> {
+        let depth = self.skipped_group_depth.saturating_add(1);
+        if let Some(limits) = self.skip_limits {
+            if depth > limits.max_group_depth {
+                return Err(DecodeError::with_kind(
+                    DecodeErrorKind::SkipLimitExceeded,
+                    format!(
+                        "skipped groups exceed the maximum depth of {}",
+                        limits.max_group_depth
+                    ),
+                ));
+            }
+        }
+        Ok(DecodeContext {
+            skipped_group_depth: depth,
+            ..self.clone()
+        })
+    }
+
+    /// Counts `bytes` more skipped bytes against the skip limits, if any.
+    #[inline]
+    fn count_skipped(&self, bytes: u64) -> Result<(), DecodeError> {
+        if let Some(limits) = self.skip_limits {
+            let skipped =
+                (limits.skipped_bytes.load(Ordering::Relaxed) as u64).saturating_add(bytes);
+            if skipped > limits.max_skipped_bytes as u64 {
+                return Err(DecodeError::with_kind(
+                    DecodeErrorKind::SkipLimitExceeded,
+                    format!(
+                        "skipped fields exceed the maximum of {} bytes",
+                        limits.max_skipped_bytes
+                    ),
+                ));
+            }
+            limits
+                .skipped_bytes
+                .store(skipped as usize, Ordering::Relaxed);
+        }
         Ok(())
     }
+
+    /// Calls `f` with the observer and the offset of the current position of `buf`, if an
+    /// observer is set.
+    #[inline]
+    fn observe(&self, buf: &impl Buf, f: impl FnOnce(&dyn DecodeObserver, usize)) {
+        if let Some(Observer { observer, len }) = self.observer {
+            f(observer, len.saturating_sub(buf.remaining()));
+        }
+    }
 }
 
+/// The smallest valid field tag.
 pub const MIN_TAG: u32 = 1;
+/// The largest valid field tag, `2^29 - 1`: keys are 32-bit varints, and the low three bits hold
+/// the wire type.
 pub const MAX_TAG: u32 = (1 << 29) - 1;
 
 /// Encodes a Protobuf field key, which consists of a wire type designator and
 /// the field tag.
+///
+/// The key is the varint `tag << 3 | wire_type`, and is followed by the value of the field,
+/// encoded as its wire type requires.
+///
+/// `tag` must be between [`MIN_TAG`] and [`MAX_TAG`]: this is checked in debug builds only,
+/// and other tags produce keys which do not decode to the same tag.
+///
+/// # Examples
+///
+/// ```
+/// use prost::{decode_key, encode_key, key_len, WireType};
+///
+/// let mut buf = Vec::new();
+/// encode_key(16, WireType::LengthDelimited, &mut buf);
+/// assert_eq!(buf, [0x82, 0x01]);
+/// assert_eq!(key_len(16), buf.len());
+/// assert_eq!(decode_key(&mut buf.as_slice()).unwrap(), (16, WireType::LengthDelimited));
+/// ```
 #[inline]
 pub fn encode_key(tag: u32, wire_type: WireType, buf: &mut impl BufMut) {
     debug_assert!((MIN_TAG..=MAX_TAG).contains(&tag));
@@ -111,6 +433,10 @@ pub fn encode_key(tag: u32, wire_type: WireType, buf: &mut impl BufMut) {
 
 /// Decodes a Protobuf field key, which consists of a wire type designator and
 /// the field tag.
+///
+/// On success, the returned tag is between [`MIN_TAG`] and [`MAX_TAG`], and the key has been
+/// consumed from `buf`. Keys which do not fit in 32 bits, have a tag of 0 or an unknown wire
+/// type are rejected.
 #[inline(always)]
 pub fn decode_key(buf: &mut impl Buf) -> Result<(u32, WireType), DecodeError> {
     let key = decode_varint(buf)?;
@@ -129,11 +455,43 @@ pub fn decode_key(buf: &mut impl Buf) -> Result<(u32, WireType), DecodeError> {
 
 /// Returns the width of an encoded Protobuf field key with the given tag.
 /// The returned width will be between 1 and 5 bytes (inclusive).
+///
+/// The width does not depend on the wire type.
 #[inline]
 pub const fn key_len(tag: u32) -> usize {
     encoded_len_varint((tag << 3) as u64)
 }
 
+/// Checks that the tags of a message are between [`MIN_TAG`] and [`MAX_TAG`], distinct, and not in
+/// one of the `reserved` ranges.
+///
+/// `prost-derive` evaluates it in a constant item for messages whose tags are constants, so that
+/// invalid tags fail to compile.
+pub const fn check_tags(tags: &[u32], reserved: &[(u32, u32)]) {
+    let mut i = 0;
+    while i < tags.len() {
+        let tag = tags[i];
+        if tag < MIN_TAG || tag > MAX_TAG {
+            panic!("invalid field tag");
+        }
+        let mut j = i + 1;
+        while j < tags.len() {
+            if tags[j] == tag {
+                panic!("multiple fields have the same tag");
+            }
+            j += 1;
+        }
+        let mut j = 0;
+        while j < reserved.len() {
+            if reserved[j].0 <= tag && tag <= reserved[j].1 {
+                panic!("a field has a reserved tag");
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
 /// Helper function which abstracts reading a length delimiter prefix followed
 /// by decoding values until the length of bytes is exhausted.
 pub fn merge_loop<T, M, B>(
@@ -163,32 +521,221 @@ where
     Ok(())
 }
 
+/// Merges the packed values of a fixed width numeric type into `values`.
+///
+/// The values are written directly into the spare capacity of `values`, instead of being pushed
+/// one at a time, so that large packed fields are decoded without initializing their memory
+/// twice.
+#[inline]
+pub(crate) fn merge_packed_fixed<T, B>(
+    values: &mut Vec<T>,
+    buf: &mut B,
+    width: usize,
+    get: impl Fn(&mut B) -> T,
+) -> Result<(), DecodeError>
+where
+    T: Copy,
+    B: Buf,
+{
+    let len = decode_varint(buf)?;
+    if len > buf.remaining() as u64 {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    let len = len as usize;
+    if len % width != 0 {
+        return Err(DecodeError::new("delimited length exceeded"));
+    }
+
+    let count = len / width;
+    values.reserve(count);
+    for slot in &mut values.spare_capacity_mut()[..count] {
+        slot.write(get(buf));
+    }
+    // SAFETY: `reserve` made room for `count` more values, and the first `count` values of the
+    // spare capacity were initialized above. If `get` panics, the length is left unchanged, and
+    // since `T` is `Copy`, no value needs to be dropped.
+    unsafe { values.set_len(values.len() + count) };
+    Ok(())
+}
+
+/// Merges the field with the given key into `msg`, applying the duplicate field policy of `ctx`.
+///
+/// `seen` holds the singular fields of `msg` merged so far in the current decode of the message,
+/// and stays empty with the default policy.
+#[inline]
+pub(crate) fn merge_message_field<M>(
+    msg: &mut M,
+    tag: u32,
+    wire_type: WireType,
+    buf: &mut impl Buf,
+    ctx: DecodeContext,
+    seen: &mut Vec<u32>,
+) -> Result<(), DecodeError>
+where
+    M: Message,
+{
+    if ctx.duplicate_field_policy != DuplicateFieldPolicy::LastWins && M::is_singular_field(tag) {
+        if seen.contains(&tag) {
+            return match ctx.duplicate_field_policy {
+                DuplicateFieldPolicy::FirstWins => skip_value(wire_type, tag, buf, ctx),
+                _ => Err(DecodeError::new(format!("duplicate field: {}", tag))),
+            };
+        }
+        seen.push(tag);
+    }
+    msg.merge_field(tag, wire_type, buf, ctx)
+}
+
 pub fn skip_field(
     wire_type: WireType,
     tag: u32,
     buf: &mut impl Buf,
     ctx: DecodeContext,
+) -> Result<(), DecodeError> {
+    #[cfg(feature = "instrument")]
+    crate::instrument::record_unknown_field();
+    ctx.observe(buf, |observer, offset| {
+        observer.unknown_field(tag, wire_type, offset)
+    });
+    skip_value(wire_type, tag, buf, ctx)
+}
+
+/// Notifies the observer, if any, when a repeated scalar field declared as `packed` is decoded
+/// from its unpacked encoding, or the other way around.
+///
+/// Both encodings are accepted by `merge_repeated`, as required by the Protobuf specification.
+#[inline]
+pub fn observe_packed(
+    packed: bool,
+    wire_type: WireType,
+    tag: u32,
+    buf: &impl Buf,
+    ctx: &DecodeContext,
+) {
+    if packed != (wire_type == WireType::LengthDelimited) {
+        ctx.observe(buf, |observer, offset| {
+            observer.unexpected_wire_type(tag, wire_type, offset)
+        });
+    }
+}
+
+/// The storage of a repeated scalar field declared with `max_len`, which holds at most that
+/// many values.
+///
+/// It is implemented for `Vec`, and for `arrayvec::ArrayVec` with the `arrayvec` feature, which
+/// keeps the values inline instead of allocating them.
+pub trait BoundedRepeated<T> {
+    /// Returns the number of values.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there are no values.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends a value, or returns it if the storage is full.
+    fn try_push(&mut self, value: T) -> Result<(), T>;
+}
+
+impl<T> BoundedRepeated<T> for Vec<T> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn try_push(&mut self, value: T) -> Result<(), T> {
+        self.push(value);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<T, const N: usize> BoundedRepeated<T> for arrayvec::ArrayVec<T, N> {
+    fn len(&self) -> usize {
+        arrayvec::ArrayVec::len(self)
+    }
+
+    fn try_push(&mut self, value: T) -> Result<(), T> {
+        arrayvec::ArrayVec::try_push(self, value).map_err(|error| error.element())
+    }
+}
+
+/// Merges the values of a repeated scalar field into storage holding at most `max_len` values.
+///
+/// `merge` decodes a single value, encoded with `value_wire_type`. Numeric values are accepted
+/// both packed and unpacked, as with `merge_repeated`. A value beyond `max_len`, or beyond the
+/// capacity of the storage, is a decode error.
+pub fn merge_repeated_bounded<T, C, B, M>(
+    wire_type: WireType,
+    values: &mut C,
+    max_len: usize,
+    value_wire_type: WireType,
+    merge: M,
+    buf: &mut B,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError>
+where
+    T: Default,
+    C: BoundedRepeated<T>,
+    B: Buf,
+    M: Fn(WireType, &mut T, &mut B, DecodeContext) -> Result<(), DecodeError>,
+{
+    let merge_value = |values: &mut C, buf: &mut B, ctx: DecodeContext| {
+        if values.len() >= max_len {
+            return Err(DecodeError::new(format!(
+                "repeated field exceeds its maximum length of {}",
+                max_len
+            )));
+        }
+        let mut value = T::default();
+        merge(value_wire_type, &mut value, buf, ctx)?;
+        values.try_push(value).map_err(|_| {
+            DecodeError::new(format!(
+                "repeated field exceeds its capacity of {} values",
+                values.len()
+            ))
+        })
+    };
+
+    if wire_type == WireType::LengthDelimited && value_wire_type != WireType::LengthDelimited {
+        // Packed.
+        merge_loop(values, buf, ctx, merge_value)
+    } else {
+        // Unpacked.
+        check_wire_type(value_wire_type, wire_type)?;
+        merge_value(values, buf, ctx)
+    }
+}
+
+/// Decodes the key of the next field of the group with the given tag, notifying the observer, if
+/// any, when the buffer ends before the group does.
+fn decode_group_key(
+    tag: u32,
+    buf: &mut impl Buf,
+    ctx: &DecodeContext,
+) -> Result<(u32, WireType), DecodeError> {
+    if !buf.has_remaining() {
+        ctx.observe(buf, |observer, offset| observer.group_eof(tag, offset));
+    }
+    decode_key(buf)
+}
+
+pub(crate) fn skip_value(
+    wire_type: WireType,
+    tag: u32,
+    buf: &mut impl Buf,
+    ctx: DecodeContext,
 ) -> Result<(), DecodeError> {
     ctx.limit_reached()?;
+    let remaining = buf.remaining();
     let len = match wire_type {
         WireType::Varint => decode_varint(buf).map(|_| 0)?,
         WireType::ThirtyTwoBit => 4,
         WireType::SixtyFourBit => 8,
         WireType::LengthDelimited => decode_varint(buf)?,
-        WireType::StartGroup => loop {
-            let (inner_tag, inner_wire_type) = decode_key(buf)?;
-            match inner_wire_type {
-                WireType::EndGroup => {
-                    if inner_tag != tag {
-                        return Err(DecodeError::new("unexpected end group tag"));
-                    }
-                    break 0;
-                }
-                _ => skip_field(inner_wire_type, inner_tag, buf, ctx.enter_recursion())?,
-            }
-        },
+        WireType::StartGroup => return skip_group(tag, buf, ctx),
         WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
     };
+    ctx.count_skipped(len.saturating_add((remaining - buf.remaining()) as u64))?;
 
     if len > buf.remaining() as u64 {
         return Err(DecodeError::new("buffer underflow"));
@@ -198,6 +745,25 @@ pub fn skip_field(
     Ok(())
 }
 
+/// Skips the fields of the unknown group with the given tag, up to its end group key.
+fn skip_group(tag: u32, buf: &mut impl Buf, ctx: DecodeContext) -> Result<(), DecodeError> {
+    let ctx = ctx.enter_skipped_group()?;
+    loop {
+        let remaining = buf.remaining();
+        let (inner_tag, inner_wire_type) = decode_group_key(tag, buf, &ctx)?;
+        ctx.count_skipped((remaining - buf.remaining()) as u64)?;
+        match inner_wire_type {
+            WireType::EndGroup => {
+                if inner_tag != tag {
+                    return Err(DecodeError::new("unexpected end group tag"));
+                }
+                return Ok(());
+            }
+            _ => skip_value(inner_wire_type, inner_tag, buf, ctx.enter_recursion())?,
+        }
+    }
+}
+
 /// Helper macro which emits an `encode_repeated` function for the type.
 macro_rules! encode_repeated {
     ($ty:ty) => {
@@ -371,38 +937,194 @@ from_uint64(value) {
     ((value >> 1) as i64) ^ (-((value & 1) as i64))
 });
 
-/// Macro which emits a module containing a set of encoding functions for a
-/// fixed width numeric type.
-macro_rules! fixed_width {
-    ($ty:ty,
-     $width:expr,
-     $wire_type:expr,
-     $proto_ty:ident,
-     $put:ident,
-     $get:ident) => {
-        pub mod $proto_ty {
-            use crate::encoding::*;
+/// Decoding of fields of closed enum types, the enums of `proto2` files.
+///
+/// A value which is not declared by a closed enum is not stored in its field, but treated as an
+/// unknown field: it is skipped, and reported to the observer as such. Fields of closed enums are
+/// encoded as `int32` fields.
+pub mod closed_enum {
+    use super::*;
 
-            pub fn encode(tag: u32, value: &$ty, buf: &mut impl BufMut) {
-                encode_key(tag, $wire_type, buf);
-                buf.$put(*value);
-            }
+    /// Decodes a value of the enum `E`, returning `None` if `E` does not declare it.
+    pub fn decode<E>(
+        wire_type: WireType,
+        tag: u32,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<Option<i32>, DecodeError>
+    where
+        E: TryFrom<i32>,
+    {
+        let remaining = buf.remaining();
+        let mut value = 0;
+        int32::merge(wire_type, &mut value, buf, ctx.clone())?;
+        if E::try_from(value).is_ok() {
+            return Ok(Some(value));
+        }
+        #[cfg(feature = "instrument")]
+        crate::instrument::record_unknown_field();
+        if let Some(Observer { observer, len }) = ctx.observer {
+            observer.unknown_field(tag, wire_type, len.saturating_sub(remaining));
+        }
+        Ok(None)
+    }
 
-            pub fn merge(
-                wire_type: WireType,
-                value: &mut $ty,
-                buf: &mut impl Buf,
-                _ctx: DecodeContext,
-            ) -> Result<(), DecodeError> {
-                check_wire_type($wire_type, wire_type)?;
-                if buf.remaining() < $width {
-                    return Err(DecodeError::new("buffer underflow"));
-                }
-                *value = buf.$get();
-                Ok(())
-            }
+    /// Merges a value of the enum `E` into the field, unless `E` does not declare it.
+    pub fn merge<E>(
+        wire_type: WireType,
+        tag: u32,
+        value: &mut i32,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        E: TryFrom<i32>,
+    {
+        if let Some(decoded) = decode::<E>(wire_type, tag, buf, ctx)? {
+            *value = decoded;
+        }
+        Ok(())
+    }
 
-            encode_repeated!($ty);
+    /// Merges a value of the enum `E` into the optional field, which is left unset if `E` does
+    /// not declare the value.
+    pub fn merge_optional<E>(
+        wire_type: WireType,
+        tag: u32,
+        value: &mut Option<i32>,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        E: TryFrom<i32>,
+    {
+        if let Some(decoded) = decode::<E>(wire_type, tag, buf, ctx)? {
+            *value = Some(decoded);
+        }
+        Ok(())
+    }
+
+    /// Merges packed or unpacked values of the enum `E` into the repeated field, leaving out the
+    /// values `E` does not declare.
+    pub fn merge_repeated<E>(
+        wire_type: WireType,
+        tag: u32,
+        values: &mut Vec<i32>,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        E: TryFrom<i32>,
+    {
+        if wire_type == WireType::LengthDelimited {
+            // Packed.
+            merge_loop(values, buf, ctx, |values, buf, ctx| {
+                values.extend(decode::<E>(WireType::Varint, tag, buf, ctx)?);
+                Ok(())
+            })
+        } else {
+            // Unpacked.
+            values.extend(decode::<E>(wire_type, tag, buf, ctx)?);
+            Ok(())
+        }
+    }
+}
+
+/// Decoding of fields of open enum types, the enums of `proto3` files.
+///
+/// A value which is not declared by an open enum is stored in its field like any other, and
+/// reported to the observer, if any. Fields of open enums are encoded as `int32` fields.
+pub mod open_enum {
+    use super::*;
+
+    /// Merges a value of the enum `E` into the field `field` of the message `message`.
+    pub fn merge<E>(
+        message: &'static str,
+        field: &'static str,
+        wire_type: WireType,
+        value: &mut i32,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        E: TryFrom<i32>,
+    {
+        let remaining = buf.remaining();
+        let observer = ctx.observer;
+        int32::merge(wire_type, value, buf, ctx)?;
+        if let Some(Observer { observer, len }) = observer {
+            if E::try_from(*value).is_err() {
+                observer.unknown_enum_value(message, field, *value, len.saturating_sub(remaining));
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges packed or unpacked values of the enum `E` into the repeated field `field` of the
+    /// message `message`.
+    pub fn merge_repeated<E>(
+        message: &'static str,
+        field: &'static str,
+        wire_type: WireType,
+        values: &mut Vec<i32>,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        E: TryFrom<i32>,
+    {
+        let remaining = buf.remaining();
+        let observer = ctx.observer;
+        let start = values.len();
+        int32::merge_repeated(wire_type, values, buf, ctx)?;
+        if let Some(Observer { observer, len }) = observer {
+            for value in &values[start..] {
+                if E::try_from(*value).is_err() {
+                    observer.unknown_enum_value(
+                        message,
+                        field,
+                        *value,
+                        len.saturating_sub(remaining),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Macro which emits a module containing a set of encoding functions for a
+/// fixed width numeric type.
+macro_rules! fixed_width {
+    ($ty:ty,
+     $width:expr,
+     $wire_type:expr,
+     $proto_ty:ident,
+     $put:ident,
+     $get:ident) => {
+        pub mod $proto_ty {
+            use crate::encoding::*;
+
+            pub fn encode(tag: u32, value: &$ty, buf: &mut impl BufMut) {
+                encode_key(tag, $wire_type, buf);
+                buf.$put(*value);
+            }
+
+            pub fn merge(
+                wire_type: WireType,
+                value: &mut $ty,
+                buf: &mut impl Buf,
+                _ctx: DecodeContext,
+            ) -> Result<(), DecodeError> {
+                check_wire_type($wire_type, wire_type)?;
+                if buf.remaining() < $width {
+                    return Err(DecodeError::new("buffer underflow"));
+                }
+                *value = buf.$get();
+                Ok(())
+            }
+
+            encode_repeated!($ty);
 
             pub fn encode_packed(tag: u32, values: &[$ty], buf: &mut impl BufMut) {
                 if values.is_empty() {
@@ -418,7 +1140,24 @@ macro_rules! fixed_width {
                 }
             }
 
-            merge_repeated_numeric!($ty, $wire_type, merge, merge_repeated);
+            pub fn merge_repeated(
+                wire_type: WireType,
+                values: &mut Vec<$ty>,
+                buf: &mut impl Buf,
+                ctx: DecodeContext,
+            ) -> Result<(), DecodeError> {
+                if wire_type == WireType::LengthDelimited {
+                    // Packed.
+                    merge_packed_fixed(values, buf, $width, |buf| buf.$get())
+                } else {
+                    // Unpacked.
+                    check_wire_type($wire_type, wire_type)?;
+                    let mut value = Default::default();
+                    merge(wire_type, &mut value, buf, ctx)?;
+                    values.push(value);
+                    Ok(())
+                }
+            }
 
             #[inline]
             pub fn encoded_len(tag: u32, _: &$ty) -> usize {
@@ -521,7 +1260,7 @@ fixed_width!(
 
 /// Macro which emits encoding functions for a length-delimited type.
 macro_rules! length_delimited {
-    ($ty:ty) => {
+    ($ty:ty, $empty:expr) => {
         encode_repeated!($ty);
 
         pub fn merge_repeated(
@@ -531,7 +1270,7 @@ macro_rules! length_delimited {
             ctx: DecodeContext,
         ) -> Result<(), DecodeError> {
             check_wire_type(WireType::LengthDelimited, wire_type)?;
-            let mut value = Default::default();
+            let mut value = $empty;
             merge(wire_type, &mut value, buf, ctx)?;
             values.push(value);
             Ok(())
@@ -553,16 +1292,137 @@ macro_rules! length_delimited {
     };
 }
 
+pub trait StringAdapter: sealed::StringAdapter {}
+
+impl StringAdapter for String {}
+
+impl sealed::StringAdapter for String {
+    fn empty() -> Self {
+        String::new()
+    }
+
+    fn as_str(&self) -> &str {
+        self
+    }
+
+    fn merge_from(
+        &mut self,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        string::merge_string(wire_type, self, buf, ctx)
+    }
+
+    fn clear(&mut self) {
+        String::clear(self)
+    }
+
+    fn set(&mut self, value: &str) {
+        String::clear(self);
+        self.push_str(value);
+    }
+}
+
+impl StringAdapter for Box<str> {}
+
+impl sealed::StringAdapter for Box<str> {
+    fn empty() -> Self {
+        Box::default()
+    }
+
+    fn as_str(&self) -> &str {
+        self
+    }
+
+    fn merge_from(
+        &mut self,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        let mut value = mem::take(self).into_string();
+        let result = string::merge_string(wire_type, &mut value, buf, ctx);
+        *self = value.into_boxed_str();
+        result
+    }
+
+    fn clear(&mut self) {
+        *self = Box::default();
+    }
+
+    fn set(&mut self, value: &str) {
+        *self = value.into();
+    }
+}
+
+/// Decoded through the [`Interner`](crate::Interner) of the context, if any.
+#[cfg(target_has_atomic = "ptr")]
+impl StringAdapter for Arc<str> {}
+
+#[cfg(target_has_atomic = "ptr")]
+impl sealed::StringAdapter for Arc<str> {
+    fn empty() -> Self {
+        Arc::from("")
+    }
+
+    fn as_str(&self) -> &str {
+        self
+    }
+
+    fn merge_from(
+        &mut self,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        let interner = ctx.interner();
+        let mut value = String::new();
+        let result = string::merge_string(wire_type, &mut value, buf, ctx);
+        *self = match interner {
+            Some(interner) if result.is_ok() => interner.intern(&value),
+            _ => Arc::from(value),
+        };
+        result
+    }
+
+    fn clear(&mut self) {
+        *self = Self::empty();
+    }
+
+    fn set(&mut self, value: &str) {
+        *self = Arc::from(value);
+    }
+}
+
 pub mod string {
     use super::*;
 
-    pub fn encode(tag: u32, value: &String, buf: &mut impl BufMut) {
+    pub fn encode(tag: u32, value: &impl StringAdapter, buf: &mut impl BufMut) {
         encode_key(tag, WireType::LengthDelimited, buf);
         encode_varint(value.len() as u64, buf);
-        buf.put_slice(value.as_bytes());
+        buf.put_slice(value.as_str().as_bytes());
     }
 
     pub fn merge(
+        wire_type: WireType,
+        value: &mut impl StringAdapter,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        value.merge_from(wire_type, buf, ctx)
+    }
+
+    pub fn clear(value: &mut impl StringAdapter) {
+        sealed::StringAdapter::clear(value)
+    }
+
+    /// Resets a string to a non-empty default value, reusing its allocation if possible.
+    pub fn reset(value: &mut impl StringAdapter, default: &str) {
+        sealed::StringAdapter::set(value, default)
+    }
+
+    pub(super) fn merge_string(
         wire_type: WireType,
         value: &mut String,
         buf: &mut impl Buf,
@@ -591,7 +1451,7 @@ pub mod string {
             }
 
             let drop_guard = DropGuard(value.as_mut_vec());
-            bytes::merge_one_copy(wire_type, drop_guard.0, buf, ctx)?;
+            bytes::merge(wire_type, drop_guard.0, buf, ctx)?;
             match str::from_utf8(drop_guard.0) {
                 Ok(_) => {
                     // Success; do not clear the bytes.
@@ -605,7 +1465,7 @@ pub mod string {
         }
     }
 
-    length_delimited!(String);
+    length_delimited!(impl StringAdapter, sealed::StringAdapter::empty());
 
     #[cfg(test)]
     mod test {
@@ -617,8 +1477,14 @@ pub mod string {
         proptest! {
             #[test]
             fn check(value: String, tag in MIN_TAG..=MAX_TAG) {
-                super::test::check_type(value, tag, WireType::LengthDelimited,
-                                        encode, merge, encoded_len)?;
+                super::test::check_type::<String, String>(value, tag, WireType::LengthDelimited,
+                                                          encode, merge, encoded_len)?;
+            }
+            #[test]
+            fn check_boxed(value: String, tag in MIN_TAG..=MAX_TAG) {
+                let value = value.into_boxed_str();
+                super::test::check_type::<Box<str>, Box<str>>(value, tag, WireType::LengthDelimited,
+                                                              encode, merge, encoded_len)?;
             }
             #[test]
             fn check_repeated(value: Vec<String>, tag in MIN_TAG..=MAX_TAG) {
@@ -633,36 +1499,81 @@ pub mod string {
 pub trait BytesAdapter: sealed::BytesAdapter {}
 
 mod sealed {
-    use super::{Buf, BufMut};
+    use super::{Buf, BufMut, DecodeContext, DecodeError, WireType};
 
     pub trait BytesAdapter: Default + Sized + 'static {
         fn len(&self) -> usize;
 
-        /// Replace contents of this buffer with the contents of another buffer.
-        fn replace_with(&mut self, buf: impl Buf);
+        /// Replaces the contents of this buffer with the next `len` bytes of another buffer.
+        fn replace_with(&mut self, buf: &mut impl Buf, len: usize);
 
         /// Appends this buffer to the (contents of) other buffer.
         fn append_to(&self, buf: &mut impl BufMut);
 
+        /// Empties this buffer.
+        fn clear(&mut self);
+
+        /// Replaces the contents of this buffer with a static value, reusing its allocation or
+        /// referencing the value if possible.
+        fn set_static(&mut self, mut value: &'static [u8]) {
+            let len = value.len();
+            self.replace_with(&mut value, len);
+        }
+
         fn is_empty(&self) -> bool {
             self.len() == 0
         }
     }
+
+    pub trait StringAdapter: Sized + 'static {
+        /// Returns an empty string.
+        ///
+        /// Not all string types implement `Default` on the minimum supported Rust version.
+        fn empty() -> Self;
+
+        fn as_str(&self) -> &str;
+
+        /// Replaces the contents of this string with a length-delimited string read from `buf`.
+        fn merge_from(
+            &mut self,
+            wire_type: WireType,
+            buf: &mut impl Buf,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>;
+
+        /// Empties this string.
+        fn clear(&mut self);
+
+        /// Replaces the contents of this string, reusing its allocation if possible.
+        fn set(&mut self, value: &str);
+
+        fn len(&self) -> usize {
+            self.as_str().len()
+        }
+    }
 }
 
 impl BytesAdapter for Bytes {}
 
 impl sealed::BytesAdapter for Bytes {
     fn len(&self) -> usize {
-        Buf::remaining(self)
+        Bytes::len(self)
     }
 
-    fn replace_with(&mut self, mut buf: impl Buf) {
-        *self = buf.copy_to_bytes(buf.remaining());
+    fn replace_with(&mut self, buf: &mut impl Buf, len: usize) {
+        *self = buf.copy_to_bytes(len);
     }
 
     fn append_to(&self, buf: &mut impl BufMut) {
-        buf.put(self.clone())
+        buf.put_slice(self)
+    }
+
+    fn clear(&mut self) {
+        Bytes::clear(self)
+    }
+
+    fn set_static(&mut self, value: &'static [u8]) {
+        *self = Bytes::from_static(value);
     }
 }
 
@@ -673,14 +1584,51 @@ impl sealed::BytesAdapter for Vec<u8> {
         Vec::len(self)
     }
 
-    fn replace_with(&mut self, buf: impl Buf) {
+    fn replace_with(&mut self, buf: &mut impl Buf, len: usize) {
         self.clear();
-        self.reserve(buf.remaining());
-        self.put(buf);
+        self.reserve(len);
+        extend_from_buf(self, buf, len);
     }
 
     fn append_to(&self, buf: &mut impl BufMut) {
-        buf.put(self.as_slice())
+        buf.put_slice(self)
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+}
+
+impl BytesAdapter for Box<[u8]> {}
+
+impl sealed::BytesAdapter for Box<[u8]> {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    fn replace_with(&mut self, buf: &mut impl Buf, len: usize) {
+        let mut value = Vec::new();
+        sealed::BytesAdapter::replace_with(&mut value, buf, len);
+        *self = value.into_boxed_slice();
+    }
+
+    fn append_to(&self, buf: &mut impl BufMut) {
+        buf.put_slice(self)
+    }
+
+    fn clear(&mut self) {
+        *self = Box::default();
+    }
+}
+
+/// Appends the next `len` bytes of `buf` to `vec`.
+fn extend_from_buf(vec: &mut Vec<u8>, buf: &mut impl Buf, mut len: usize) {
+    while len > 0 {
+        let chunk = buf.chunk();
+        let n = chunk.len().min(len);
+        vec.extend_from_slice(&chunk[..n]);
+        buf.advance(n);
+        len -= n;
     }
 }
 
@@ -715,32 +1663,22 @@ pub mod bytes {
         //
         // [1]: https://developers.google.com/protocol-buffers/docs/encoding#optional
         //
-        // This is intended for A and B both being Bytes so it is zero-copy.
-        // Some combinations of A and B types may cause a double-copy,
-        // in which case merge_one_copy() should be used instead.
-        value.replace_with(buf.copy_to_bytes(len));
+        // The value is copied at most once, and not at all from a `Bytes` buffer into a `Bytes`.
+        value.replace_with(buf, len);
         Ok(())
     }
 
-    pub(super) fn merge_one_copy(
-        wire_type: WireType,
-        value: &mut impl BytesAdapter,
-        buf: &mut impl Buf,
-        _ctx: DecodeContext,
-    ) -> Result<(), DecodeError> {
-        check_wire_type(WireType::LengthDelimited, wire_type)?;
-        let len = decode_varint(buf)?;
-        if len > buf.remaining() as u64 {
-            return Err(DecodeError::new("buffer underflow"));
-        }
-        let len = len as usize;
+    pub fn clear(value: &mut impl BytesAdapter) {
+        sealed::BytesAdapter::clear(value)
+    }
 
-        // If we must copy, make sure to copy only once.
-        value.replace_with(buf.take(len));
-        Ok(())
+    /// Resets a buffer to a non-empty default value, without allocating for `Bytes`, and reusing
+    /// the allocation of a `Vec<u8>`.
+    pub fn reset(value: &mut impl BytesAdapter, default: &'static [u8]) {
+        sealed::BytesAdapter::set_static(value, default)
     }
 
-    length_delimited!(impl BytesAdapter);
+    length_delimited!(impl BytesAdapter, Default::default());
 
     #[cfg(test)]
     mod test {
@@ -763,6 +1701,13 @@ pub mod bytes {
                                                         encode, merge, encoded_len)?;
             }
 
+            #[test]
+            fn check_boxed(value: Vec<u8>, tag in MIN_TAG..=MAX_TAG) {
+                let value = value.into_boxed_slice();
+                super::test::check_type::<Box<[u8]>, Box<[u8]>>(value, tag, WireType::LengthDelimited,
+                                                                encode, merge, encoded_len)?;
+            }
+
             #[test]
             fn check_repeated_vec(value: Vec<Vec<u8>>, tag in MIN_TAG..=MAX_TAG) {
                 super::test::check_collection_type(value, tag, WireType::LengthDelimited,
@@ -781,6 +1726,41 @@ pub mod bytes {
     }
 }
 
+/// Comparisons of field values for the derived implementations of
+/// [`Message::eq_semantically`].
+pub mod semantic_eq {
+    use super::*;
+
+    /// Returns `true` if the values are equal, or both `NaN`.
+    pub fn float(a: &f32, b: &f32) -> bool {
+        a == b || (a.is_nan() && b.is_nan())
+    }
+
+    /// Returns `true` if the values are equal, or both `NaN`.
+    pub fn double(a: &f64, b: &f64) -> bool {
+        a == b || (a.is_nan() && b.is_nan())
+    }
+
+    pub fn message<M>(a: &M, b: &M) -> bool
+    where
+        M: Message,
+    {
+        a.eq_semantically(b)
+    }
+
+    pub fn optional<T>(a: &Option<T>, b: &Option<T>, eq: impl Fn(&T, &T) -> bool) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    pub fn repeated<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| eq(a, b))
+    }
+}
+
 pub mod message {
     use super::*;
 
@@ -789,8 +1769,11 @@ pub mod message {
         M: Message,
     {
         encode_key(tag, WireType::LengthDelimited, buf);
-        encode_varint(msg.encoded_len() as u64, buf);
-        msg.encode_raw(buf);
+        let len = msg.encoded_len();
+        trace::encode::<M, _>(len + encoded_len_varint(len as u64), || {
+            encode_varint(len as u64, buf);
+            msg.encode_raw(buf);
+        });
     }
 
     pub fn merge<M, B>(
@@ -805,15 +1788,18 @@ pub mod message {
     {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
         ctx.limit_reached()?;
-        merge_loop(
-            msg,
-            buf,
-            ctx.enter_recursion(),
-            |msg: &mut M, buf: &mut B, ctx| {
-                let (tag, wire_type) = decode_key(buf)?;
-                msg.merge_field(tag, wire_type, buf, ctx)
-            },
-        )
+        let mut seen = Vec::new();
+        trace::decode::<M, _, _>(buf, |buf| {
+            merge_loop(
+                msg,
+                buf,
+                ctx.enter_recursion(),
+                |msg: &mut M, buf: &mut B, ctx| {
+                    let (tag, wire_type) = decode_key(buf)?;
+                    merge_message_field(msg, tag, wire_type, buf, ctx, &mut seen)
+                },
+            )
+        })
     }
 
     pub fn encode_repeated<M>(tag: u32, messages: &[M], buf: &mut impl BufMut)
@@ -842,111 +1828,440 @@ pub mod message {
     }
 
     #[inline]
-    pub fn encoded_len<M>(tag: u32, msg: &M) -> usize
+    pub fn encoded_len<M>(tag: u32, msg: &M) -> usize
+    where
+        M: Message,
+    {
+        let len = msg.encoded_len();
+        key_len(tag) + encoded_len_varint(len as u64) + len
+    }
+
+    #[inline]
+    pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
+    where
+        M: Message,
+    {
+        key_len(tag) * messages.len()
+            + messages
+                .iter()
+                .map(Message::encoded_len)
+                .map(|len| len + encoded_len_varint(len as u64))
+                .sum::<usize>()
+    }
+}
+
+pub mod group {
+    use super::*;
+
+    pub fn encode<M>(tag: u32, msg: &M, buf: &mut impl BufMut)
+    where
+        M: Message,
+    {
+        encode_key(tag, WireType::StartGroup, buf);
+        trace::encode::<M, _>(msg.encoded_len() + key_len(tag), || {
+            msg.encode_raw(buf);
+            encode_key(tag, WireType::EndGroup, buf);
+        });
+    }
+
+    pub fn merge<M>(
+        tag: u32,
+        wire_type: WireType,
+        msg: &mut M,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: Message,
+    {
+        check_wire_type(WireType::StartGroup, wire_type)?;
+
+        ctx.limit_reached()?;
+        let mut seen = Vec::new();
+        trace::decode::<M, _, _>(buf, |buf| loop {
+            let (field_tag, field_wire_type) = decode_group_key(tag, buf, &ctx)?;
+            if field_wire_type == WireType::EndGroup {
+                if field_tag != tag {
+                    return Err(DecodeError::new("unexpected end group tag"));
+                }
+                return Ok(());
+            }
+
+            merge_message_field(
+                msg,
+                field_tag,
+                field_wire_type,
+                buf,
+                ctx.enter_recursion(),
+                &mut seen,
+            )?;
+        })
+    }
+
+    pub fn encode_repeated<M>(tag: u32, messages: &[M], buf: &mut impl BufMut)
+    where
+        M: Message,
+    {
+        for msg in messages {
+            encode(tag, msg, buf);
+        }
+    }
+
+    pub fn merge_repeated<M>(
+        tag: u32,
+        wire_type: WireType,
+        messages: &mut Vec<M>,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: Message + Default,
+    {
+        check_wire_type(WireType::StartGroup, wire_type)?;
+        let mut msg = M::default();
+        merge(tag, WireType::StartGroup, &mut msg, buf, ctx)?;
+        messages.push(msg);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn encoded_len<M>(tag: u32, msg: &M) -> usize
+    where
+        M: Message,
+    {
+        2 * key_len(tag) + msg.encoded_len()
+    }
+
+    #[inline]
+    pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
+    where
+        M: Message,
+    {
+        2 * key_len(tag) * messages.len() + messages.iter().map(Message::encoded_len).sum::<usize>()
+    }
+}
+
+/// Rust types encoded as the Protobuf message they convert to and from, such as a decimal type
+/// encoded as a `Decimal` message. These functions are used by message fields with the
+/// `proxy` attribute.
+///
+/// A type `T` proxied by the message `P` is encoded by converting it with `P::from(&T)`, and
+/// decoded with `T::try_from(P)`, whose error fails the decode. A value which is already set is
+/// converted to its message before the decoded message is merged into it.
+pub mod proxy {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    pub fn encode<P, T>(tag: u32, value: &T, buf: &mut impl BufMut)
+    where
+        P: Message + for<'a> From<&'a T>,
+    {
+        message::encode(tag, &P::from(value), buf);
+    }
+
+    pub fn merge<P, T>(
+        wire_type: WireType,
+        value: &mut T,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        P: Message + for<'a> From<&'a T>,
+        T: TryFrom<P>,
+        T::Error: fmt::Display,
+    {
+        let mut msg = P::from(value);
+        message::merge(wire_type, &mut msg, buf, ctx)?;
+        *value = convert(msg)?;
+        Ok(())
+    }
+
+    pub fn merge_optional<P, T>(
+        wire_type: WireType,
+        value: &mut Option<T>,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        P: Message + Default + for<'a> From<&'a T>,
+        T: TryFrom<P>,
+        T::Error: fmt::Display,
+    {
+        let mut msg = value.as_ref().map_or_else(P::default, P::from);
+        message::merge(wire_type, &mut msg, buf, ctx)?;
+        *value = Some(convert(msg)?);
+        Ok(())
+    }
+
+    pub fn encode_repeated<P, T>(tag: u32, values: &[T], buf: &mut impl BufMut)
+    where
+        P: Message + for<'a> From<&'a T>,
+    {
+        for value in values {
+            encode::<P, T>(tag, value, buf);
+        }
+    }
+
+    pub fn merge_repeated<P, T>(
+        wire_type: WireType,
+        values: &mut Vec<T>,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        P: Message + Default,
+        T: TryFrom<P>,
+        T::Error: fmt::Display,
+    {
+        let mut msg = P::default();
+        message::merge(wire_type, &mut msg, buf, ctx)?;
+        values.push(convert(msg)?);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn encoded_len<P, T>(tag: u32, value: &T) -> usize
+    where
+        P: Message + for<'a> From<&'a T>,
+    {
+        message::encoded_len(tag, &P::from(value))
+    }
+
+    #[inline]
+    pub fn encoded_len_repeated<P, T>(tag: u32, values: &[T]) -> usize
     where
-        M: Message,
+        P: Message + for<'a> From<&'a T>,
     {
-        let len = msg.encoded_len();
-        key_len(tag) + encoded_len_varint(len as u64) + len
+        values
+            .iter()
+            .map(|value| encoded_len::<P, T>(tag, value))
+            .sum()
     }
 
-    #[inline]
-    pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
+    fn convert<P, T>(msg: P) -> Result<T, DecodeError>
     where
-        M: Message,
+        T: TryFrom<P>,
+        T::Error: fmt::Display,
     {
-        key_len(tag) * messages.len()
-            + messages
-                .iter()
-                .map(Message::encoded_len)
-                .map(|len| len + encoded_len_varint(len as u64))
-                .sum::<usize>()
+        T::try_from(msg).map_err(|error| DecodeError::new(error.to_string()))
     }
 }
 
-pub mod group {
+/// The legacy MessageSet wire format, used by messages with the `message_set_wire_format`
+/// option.
+///
+/// Such a message only has extensions, which are all messages, and encodes each of them as an
+/// item of a repeated group:
+///
+/// ```protobuf
+/// repeated group Item = 1 {
+///   required int32 type_id = 2;
+///   required bytes message = 3;
+/// }
+/// ```
+///
+/// where `type_id` is the field number of the extension, and `message` the encoded extension.
+pub mod message_set {
     use super::*;
 
-    pub fn encode<M>(tag: u32, msg: &M, buf: &mut impl BufMut)
+    /// The tag of the item group.
+    pub const ITEM_TAG: u32 = 1;
+    const TYPE_ID_TAG: u32 = 2;
+    const MESSAGE_TAG: u32 = 3;
+
+    /// Encodes `msg` as an item with the given type id.
+    pub fn encode<M>(type_id: u32, msg: &M, buf: &mut impl BufMut)
     where
         M: Message,
     {
-        encode_key(tag, WireType::StartGroup, buf);
-        msg.encode_raw(buf);
-        encode_key(tag, WireType::EndGroup, buf);
+        encode_key(ITEM_TAG, WireType::StartGroup, buf);
+        encode_key(TYPE_ID_TAG, WireType::Varint, buf);
+        encode_varint(u64::from(type_id), buf);
+        message::encode(MESSAGE_TAG, msg, buf);
+        encode_key(ITEM_TAG, WireType::EndGroup, buf);
     }
 
-    pub fn merge<M>(
-        tag: u32,
+    /// Merges an item into the extension with its type id.
+    ///
+    /// `merge` is called with the type id and the buffer positioned at the length-delimited
+    /// message, which it must consume, or returns `false` if the type id is unknown, in which
+    /// case the message is skipped. The message may precede the type id in the item, and is
+    /// then buffered until the type id is decoded.
+    pub fn merge_item<B, F>(
         wire_type: WireType,
-        msg: &mut M,
-        buf: &mut impl Buf,
+        buf: &mut B,
         ctx: DecodeContext,
+        mut merge: F,
     ) -> Result<(), DecodeError>
     where
-        M: Message,
+        B: Buf,
+        F: FnMut(u32, &mut dyn Buf, DecodeContext) -> Result<bool, DecodeError>,
     {
         check_wire_type(WireType::StartGroup, wire_type)?;
-
         ctx.limit_reached()?;
+
+        let mut type_id = None;
+        let mut buffered: Option<Vec<u8>> = None;
         loop {
-            let (field_tag, field_wire_type) = decode_key(buf)?;
-            if field_wire_type == WireType::EndGroup {
-                if field_tag != tag {
-                    return Err(DecodeError::new("unexpected end group tag"));
+            let (tag, wire_type) = decode_group_key(ITEM_TAG, buf, &ctx)?;
+            match (tag, wire_type) {
+                (ITEM_TAG, WireType::EndGroup) => break,
+                (_, WireType::EndGroup) => {
+                    return Err(DecodeError::new("unexpected end group tag"))
                 }
-                return Ok(());
+                (TYPE_ID_TAG, WireType::Varint) => type_id = Some(decode_type_id(buf)?),
+                (MESSAGE_TAG, WireType::LengthDelimited) => match type_id {
+                    Some(type_id) => {
+                        merge_message(type_id, buf, ctx.enter_recursion(), &mut merge)?
+                    }
+                    None => {
+                        let len = decode_varint(buf)?;
+                        if len > buf.remaining() as u64 {
+                            return Err(DecodeError::new("buffer underflow"));
+                        }
+                        // Merging the concatenation of encoded messages is merging each of them.
+                        let buffered = buffered.get_or_insert_with(Vec::new);
+                        extend_from_buf(buffered, buf, len as usize);
+                    }
+                },
+                _ => skip_field(wire_type, tag, buf, ctx.enter_recursion())?,
             }
+        }
 
-            M::merge_field(msg, field_tag, field_wire_type, buf, ctx.enter_recursion())?;
+        if let Some(message) = buffered {
+            let type_id =
+                type_id.ok_or_else(|| DecodeError::new("MessageSet item without a type id"))?;
+            let mut delimited = Vec::with_capacity(10 + message.len());
+            encode_varint(message.len() as u64, &mut delimited);
+            delimited.extend_from_slice(&message);
+            merge_message(
+                type_id,
+                &mut delimited.as_slice(),
+                ctx.enter_recursion(),
+                &mut merge,
+            )?;
         }
+        Ok(())
     }
 
-    pub fn encode_repeated<M>(tag: u32, messages: &[M], buf: &mut impl BufMut)
-    where
-        M: Message,
-    {
-        for msg in messages {
-            encode(tag, msg, buf);
+    fn decode_type_id(buf: &mut impl Buf) -> Result<u32, DecodeError> {
+        let type_id = decode_varint(buf)?;
+        match u32::try_from(type_id) {
+            Ok(type_id) if (MIN_TAG..=MAX_TAG).contains(&type_id) => Ok(type_id),
+            _ => Err(DecodeError::new(format!(
+                "invalid MessageSet type id: {}",
+                type_id
+            ))),
         }
     }
 
-    pub fn merge_repeated<M>(
-        tag: u32,
-        wire_type: WireType,
-        messages: &mut Vec<M>,
-        buf: &mut impl Buf,
+    fn merge_message<B, F>(
+        type_id: u32,
+        buf: &mut B,
         ctx: DecodeContext,
+        merge: &mut F,
     ) -> Result<(), DecodeError>
     where
-        M: Message + Default,
+        B: Buf,
+        F: FnMut(u32, &mut dyn Buf, DecodeContext) -> Result<bool, DecodeError>,
     {
-        check_wire_type(WireType::StartGroup, wire_type)?;
-        let mut msg = M::default();
-        merge(tag, WireType::StartGroup, &mut msg, buf, ctx)?;
-        messages.push(msg);
-        Ok(())
+        if merge(type_id, buf, ctx.clone())? {
+            Ok(())
+        } else {
+            skip_field(WireType::LengthDelimited, type_id, buf, ctx)
+        }
     }
 
+    /// Returns the encoded length of `msg` as an item with the given type id.
     #[inline]
-    pub fn encoded_len<M>(tag: u32, msg: &M) -> usize
+    pub fn encoded_len<M>(type_id: u32, msg: &M) -> usize
     where
         M: Message,
     {
-        2 * key_len(tag) + msg.encoded_len()
+        item_len(type_id, msg.encoded_len())
     }
 
+    /// Returns the encoded length of an item with the given type id, holding a message of `len`
+    /// bytes.
     #[inline]
-    pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
-    where
-        M: Message,
-    {
-        2 * key_len(tag) * messages.len() + messages.iter().map(Message::encoded_len).sum::<usize>()
+    pub fn item_len(type_id: u32, len: usize) -> usize {
+        2 * key_len(ITEM_TAG)
+            + key_len(TYPE_ID_TAG)
+            + encoded_len_varint(u64::from(type_id))
+            + key_len(MESSAGE_TAG)
+            + encoded_len_varint(len as u64)
+            + len
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn merge_items() {
+            let mut buf = Vec::new();
+            encode(100, &1u32, &mut buf);
+            assert_eq!(buf.len(), encoded_len(100, &1u32));
+
+            // An item with the message before the type id, and an unknown field.
+            encode_key(ITEM_TAG, WireType::StartGroup, &mut buf);
+            message::encode(MESSAGE_TAG, &2u32, &mut buf);
+            encode_key(4, WireType::Varint, &mut buf);
+            encode_varint(0, &mut buf);
+            encode_key(TYPE_ID_TAG, WireType::Varint, &mut buf);
+            encode_varint(100, &mut buf);
+            encode_key(ITEM_TAG, WireType::EndGroup, &mut buf);
+
+            // An item with an unknown type id.
+            encode(200, &3u32, &mut buf);
+
+            let mut values = Vec::new();
+            let mut buf = buf.as_slice();
+            while buf.has_remaining() {
+                let (tag, wire_type) = decode_key(&mut buf).unwrap();
+                assert_eq!(tag, ITEM_TAG);
+                merge_item(
+                    wire_type,
+                    &mut buf,
+                    DecodeContext::default(),
+                    |type_id, buf, ctx| {
+                        if type_id != 100 {
+                            return Ok(false);
+                        }
+                        let mut value = 0u32;
+                        message::merge(WireType::LengthDelimited, &mut value, &mut &mut *buf, ctx)?;
+                        values.push(value);
+                        Ok(true)
+                    },
+                )
+                .unwrap();
+            }
+            assert_eq!(values, [1, 2]);
+        }
+
+        #[test]
+        fn reject_items_without_type_id() {
+            let mut buf = Vec::new();
+            message::encode(MESSAGE_TAG, &1u32, &mut buf);
+            encode_key(ITEM_TAG, WireType::EndGroup, &mut buf);
+            let error = merge_item(
+                WireType::StartGroup,
+                &mut buf.as_slice(),
+                DecodeContext::default(),
+                |_, _, _| Ok(true),
+            )
+            .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "failed to decode Protobuf message: MessageSet item without a type id"
+            );
+        }
     }
 }
 
 /// Rust doesn't have a `Map` trait, so macros are currently the best way to be
-/// generic over `HashMap` and `BTreeMap`.
+/// generic over `HashMap`, `BTreeMap` and `IndexMap`.
 macro_rules! map {
     ($map_ty:ident) => {
         use crate::encoding::*;
@@ -1037,10 +2352,76 @@ macro_rules! map {
             KL: Fn(u32, &K) -> usize,
             VE: Fn(u32, &V, &mut B),
             VL: Fn(u32, &V) -> usize,
+        {
+            encode_entries(
+                key_encode,
+                key_encoded_len,
+                val_encode,
+                val_encoded_len,
+                Some(val_default),
+                tag,
+                values,
+                buf,
+            )
+        }
+
+        /// Generic protobuf map encode function which encodes every value, including the
+        /// default values which the other encode functions omit from map entries.
+        ///
+        /// Both encodings decode to the same map, but some decoders expect the value of every
+        /// entry to be present, for example for message values.
+        pub fn encode_all_values<K, V, B, KE, KL, VE, VL>(
+            key_encode: KE,
+            key_encoded_len: KL,
+            val_encode: VE,
+            val_encoded_len: VL,
+            tag: u32,
+            values: &$map_ty<K, V>,
+            buf: &mut B,
+        ) where
+            K: Default + Eq + Hash + Ord,
+            V: PartialEq,
+            B: BufMut,
+            KE: Fn(u32, &K, &mut B),
+            KL: Fn(u32, &K) -> usize,
+            VE: Fn(u32, &V, &mut B),
+            VL: Fn(u32, &V) -> usize,
+        {
+            encode_entries(
+                key_encode,
+                key_encoded_len,
+                val_encode,
+                val_encoded_len,
+                None,
+                tag,
+                values,
+                buf,
+            )
+        }
+
+        /// Encodes the entries of a map, omitting default keys, and the values equal to
+        /// `val_default` if any.
+        fn encode_entries<K, V, B, KE, KL, VE, VL>(
+            key_encode: KE,
+            key_encoded_len: KL,
+            val_encode: VE,
+            val_encoded_len: VL,
+            val_default: Option<&V>,
+            tag: u32,
+            values: &$map_ty<K, V>,
+            buf: &mut B,
+        ) where
+            K: Default + Eq + Hash + Ord,
+            V: PartialEq,
+            B: BufMut,
+            KE: Fn(u32, &K, &mut B),
+            KL: Fn(u32, &K) -> usize,
+            VE: Fn(u32, &V, &mut B),
+            VL: Fn(u32, &V) -> usize,
         {
             for (key, val) in values.iter() {
                 let skip_key = key == &K::default();
-                let skip_val = val == val_default;
+                let skip_val = val_default == Some(val);
 
                 let len = (if skip_key { 0 } else { key_encoded_len(1, key) })
                     + (if skip_val { 0 } else { val_encoded_len(2, val) });
@@ -1106,6 +2487,59 @@ macro_rules! map {
             tag: u32,
             values: &$map_ty<K, V>,
         ) -> usize
+        where
+            K: Default + Eq + Hash + Ord,
+            V: PartialEq,
+            KL: Fn(u32, &K) -> usize,
+            VL: Fn(u32, &V) -> usize,
+        {
+            entries_encoded_len(
+                key_encoded_len,
+                val_encoded_len,
+                Some(val_default),
+                tag,
+                values,
+            )
+        }
+
+        /// Generic protobuf map encoded length function for maps encoded with
+        /// `encode_all_values`.
+        pub fn encoded_len_all_values<K, V, KL, VL>(
+            key_encoded_len: KL,
+            val_encoded_len: VL,
+            tag: u32,
+            values: &$map_ty<K, V>,
+        ) -> usize
+        where
+            K: Default + Eq + Hash + Ord,
+            V: PartialEq,
+            KL: Fn(u32, &K) -> usize,
+            VL: Fn(u32, &V) -> usize,
+        {
+            entries_encoded_len(key_encoded_len, val_encoded_len, None, tag, values)
+        }
+
+        /// Returns `true` if the maps have the same keys, with values equal according to
+        /// `val_eq`, whatever the order of their entries.
+        pub fn eq_semantically<K, V, VE>(val_eq: VE, a: &$map_ty<K, V>, b: &$map_ty<K, V>) -> bool
+        where
+            K: Eq + Hash + Ord,
+            VE: Fn(&V, &V) -> bool,
+        {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, val)| b.get(key).is_some_and(|other| val_eq(val, other)))
+        }
+
+        /// Returns the encoded length of the entries of a map, omitting default keys, and the
+        /// values equal to `val_default` if any.
+        fn entries_encoded_len<K, V, KL, VL>(
+            key_encoded_len: KL,
+            val_encoded_len: VL,
+            val_default: Option<&V>,
+            tag: u32,
+            values: &$map_ty<K, V>,
+        ) -> usize
         where
             K: Default + Eq + Hash + Ord,
             V: PartialEq,
@@ -1120,7 +2554,7 @@ macro_rules! map {
                             0
                         } else {
                             key_encoded_len(1, key)
-                        }) + (if val == val_default {
+                        }) + (if val_default == Some(val) {
                             0
                         } else {
                             val_encoded_len(2, val)
@@ -1142,6 +2576,12 @@ pub mod btree_map {
     map!(BTreeMap);
 }
 
+#[cfg(feature = "indexmap")]
+pub mod index_map {
+    use indexmap::IndexMap;
+    map!(IndexMap);
+}
+
 #[cfg(test)]
 mod test {
     #[cfg(not(feature = "std"))]
@@ -1149,7 +2589,7 @@ mod test {
     use core::borrow::Borrow;
     use core::fmt::Debug;
 
-    use ::bytes::BytesMut;
+    use ::bytes::{Bytes, BytesMut};
     use proptest::{prelude::*, test_runner::TestCaseResult};
 
     use super::*;
@@ -1322,6 +2762,301 @@ mod test {
         assert!(s.is_empty());
     }
 
+    #[test]
+    fn skip_limits_group_depth() {
+        // Unknown fields: a varint, then a group with tag 2 holding a group with tag 3.
+        let buf = [0x08, 0x01, 0x13, 0x1b, 0x08, 0x01, 0x1c, 0x14];
+        let skip = |ctx: DecodeContext| {
+            let mut buf = &buf[..];
+            while buf.has_remaining() {
+                let (tag, wire_type) = decode_key(&mut buf)?;
+                skip_field(wire_type, tag, &mut buf, ctx.clone())?;
+            }
+            Ok::<_, DecodeError>(())
+        };
+
+        skip(DecodeContext::default()).unwrap();
+        let limits = SkipLimits::new().max_group_depth(2);
+        skip(DecodeContext::default().with_skip_limits(&limits)).unwrap();
+        // The keys of the group fields are counted, but not the key of the group itself.
+        assert_eq!(limits.skipped_bytes(), 6);
+
+        let limits = SkipLimits::new().max_group_depth(1);
+        let error = skip(DecodeContext::default().with_skip_limits(&limits)).unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::SkipLimitExceeded);
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: skipped groups exceed the maximum depth of 1"
+        );
+    }
+
+    #[test]
+    fn skip_limits_skipped_bytes() {
+        let limits = SkipLimits::new().max_skipped_bytes(8);
+        let ctx = DecodeContext::default().with_skip_limits(&limits);
+
+        // A length-delimited value of 3 bytes, and its length.
+        let mut buf = &[0x03, 0x01, 0x02, 0x03][..];
+        skip_value(WireType::LengthDelimited, 1, &mut buf, ctx.clone()).unwrap();
+        assert_eq!(limits.skipped_bytes(), 4);
+        skip_value(WireType::ThirtyTwoBit, 1, &mut &[0; 4][..], ctx.clone()).unwrap();
+        assert_eq!(limits.skipped_bytes(), 8);
+
+        // Length-delimited values are rejected before they are skipped.
+        let mut buf = &[0x00][..];
+        let error = skip_value(WireType::Varint, 1, &mut buf, ctx.clone()).unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::SkipLimitExceeded);
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: skipped fields exceed the maximum of 8 bytes"
+        );
+        let error = skip_value(
+            WireType::LengthDelimited,
+            1,
+            &mut &[0xff, 0xff, 0xff, 0xff, 0x0f][..],
+            ctx.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::SkipLimitExceeded);
+        assert_eq!(limits.skipped_bytes(), 8);
+
+        limits.reset();
+        skip_value(WireType::SixtyFourBit, 1, &mut &[0; 8][..], ctx).unwrap();
+    }
+
+    #[test]
+    fn decode_error_kind() {
+        let error = skip_value(
+            WireType::EndGroup,
+            1,
+            &mut &[][..],
+            DecodeContext::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::Other);
+
+        #[cfg(not(feature = "no-recursion-limit"))]
+        {
+            let mut ctx = DecodeContext::default();
+            for _ in 0..crate::RECURSION_LIMIT {
+                ctx = ctx.enter_recursion();
+            }
+            let error = ctx.limit_reached().unwrap_err();
+            assert_eq!(error.kind(), DecodeErrorKind::RecursionLimitReached);
+        }
+    }
+
+    #[test]
+    fn merge_packed_fixed_values() {
+        let ctx = DecodeContext::default;
+
+        let mut values = vec![7u32];
+        let buf = [8, 1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 2];
+        let mut slice = &buf[..];
+        fixed32::merge_repeated(WireType::LengthDelimited, &mut values, &mut slice, ctx()).unwrap();
+        assert_eq!(values, [7, 1, u32::MAX]);
+        assert_eq!(slice, [2]);
+
+        // Appends to the values of an earlier occurrence of the field.
+        let mut values = vec![-1i64];
+        let mut buf = Vec::new();
+        sfixed64::encode_packed(1, &[i64::MIN, 0, 5], &mut buf);
+        let mut slice = &buf[1..];
+        sfixed64::merge_repeated(WireType::LengthDelimited, &mut values, &mut slice, ctx())
+            .unwrap();
+        assert_eq!(values, [-1, i64::MIN, 0, 5]);
+        assert!(slice.is_empty());
+
+        let mut values = Vec::<f32>::new();
+        double::merge_repeated(
+            WireType::LengthDelimited,
+            &mut Vec::new(),
+            &mut &[0][..],
+            ctx(),
+        )
+        .unwrap();
+        let error = float::merge_repeated(
+            WireType::LengthDelimited,
+            &mut values,
+            &mut &[6, 0, 0, 0, 0, 0, 0][..],
+            ctx(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: delimited length exceeded"
+        );
+        let error = float::merge_repeated(
+            WireType::LengthDelimited,
+            &mut values,
+            &mut &[8, 0, 0, 0, 0][..],
+            ctx(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: buffer underflow"
+        );
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn merge_repeated_bounded_values() {
+        let merge = |wire_type, values: &mut Vec<u32>, buf: &mut &[u8]| {
+            merge_repeated_bounded(
+                wire_type,
+                values,
+                3,
+                WireType::Varint,
+                uint32::merge,
+                buf,
+                DecodeContext::default(),
+            )
+        };
+
+        // Packed values.
+        let mut values = Vec::new();
+        merge(
+            WireType::LengthDelimited,
+            &mut values,
+            &mut &[0x02, 0x01, 0x02][..],
+        )
+        .unwrap();
+        // Unpacked values.
+        merge(WireType::Varint, &mut values, &mut &[0x03][..]).unwrap();
+        assert_eq!(values, [1, 2, 3]);
+
+        let error = merge(WireType::Varint, &mut values, &mut &[0x04][..]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: repeated field exceeds its maximum length of 3"
+        );
+        let mut values = vec![1];
+        merge(
+            WireType::LengthDelimited,
+            &mut values,
+            &mut &[0x03, 0x02, 0x03, 0x04][..],
+        )
+        .unwrap_err();
+        assert!(merge(WireType::SixtyFourBit, &mut Vec::new(), &mut &[0; 8][..]).is_err());
+    }
+
+    #[cfg(feature = "arrayvec")]
+    #[test]
+    fn merge_repeated_bounded_array_vec() {
+        let mut values = arrayvec::ArrayVec::<u32, 2>::new();
+        let error = merge_repeated_bounded(
+            WireType::LengthDelimited,
+            &mut values,
+            usize::MAX,
+            WireType::Varint,
+            uint32::merge,
+            &mut &[0x03, 0x01, 0x02, 0x03][..],
+            DecodeContext::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: repeated field exceeds its capacity of 2 values"
+        );
+        assert_eq!(values.as_slice(), [1, 2]);
+    }
+
+    #[test]
+    fn map_encode_all_values() {
+        let values = BTreeMap::from([(1u32, String::new()), (2, "b".to_string())]);
+
+        let mut omitted = Vec::new();
+        crate::encoding::btree_map::encode(
+            uint32::encode,
+            uint32::encoded_len,
+            string::encode,
+            string::encoded_len,
+            1,
+            &values,
+            &mut omitted,
+        );
+        assert_eq!(omitted, b"\x0a\x02\x08\x01\x0a\x05\x08\x02\x12\x01b");
+
+        let mut all = Vec::new();
+        crate::encoding::btree_map::encode_all_values(
+            uint32::encode,
+            uint32::encoded_len,
+            string::encode,
+            string::encoded_len,
+            1,
+            &values,
+            &mut all,
+        );
+        assert_eq!(all, b"\x0a\x04\x08\x01\x12\x00\x0a\x05\x08\x02\x12\x01b");
+        assert_eq!(
+            crate::encoding::btree_map::encoded_len_all_values(
+                uint32::encoded_len,
+                string::encoded_len,
+                1,
+                &values
+            ),
+            all.len()
+        );
+
+        let mut buf = &all[..];
+        let mut decoded = BTreeMap::new();
+        while !buf.is_empty() {
+            decode_key(&mut buf).unwrap();
+            crate::encoding::btree_map::merge(
+                uint32::merge,
+                string::merge,
+                &mut decoded,
+                &mut buf,
+                DecodeContext::default(),
+            )
+            .unwrap();
+        }
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    #[cfg(feature = "indexmap")]
+    fn index_map_keeps_insertion_order() {
+        let values = indexmap::IndexMap::from([(3u32, "c".to_string()), (1, "a".to_string())]);
+
+        let mut buf = Vec::new();
+        crate::encoding::index_map::encode(
+            uint32::encode,
+            uint32::encoded_len,
+            string::encode,
+            string::encoded_len,
+            1,
+            &values,
+            &mut buf,
+        );
+        assert_eq!(buf, b"\x0a\x05\x08\x03\x12\x01c\x0a\x05\x08\x01\x12\x01a");
+        assert_eq!(
+            crate::encoding::index_map::encoded_len(
+                uint32::encoded_len,
+                string::encoded_len,
+                1,
+                &values
+            ),
+            buf.len()
+        );
+
+        let mut buf = &buf[..];
+        let mut decoded = indexmap::IndexMap::<u32, String>::new();
+        while !buf.is_empty() {
+            decode_key(&mut buf).unwrap();
+            crate::encoding::index_map::merge(
+                uint32::merge,
+                string::merge,
+                &mut decoded,
+                &mut buf,
+                DecodeContext::default(),
+            )
+            .unwrap();
+        }
+        assert!(decoded.keys().eq([3, 1].iter()));
+    }
+
     /// This big bowl o' macro soup generates an encoding property test for each combination of map
     /// type, scalar map key, and value type.
     /// TODO: these tests take a long time to compile, can this be improved?
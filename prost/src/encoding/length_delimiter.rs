@@ -2,7 +2,7 @@ pub use crate::error::{DecodeError, EncodeError, UnknownEnumValue};
 pub use crate::message::Message;
 pub use crate::name::Name;
 
-use bytes::{Buf, BufMut};
+use crate::buf::{Buf, BufMut};
 
 use crate::encoding::varint::{decode_varint, encode_varint, encoded_len_varint};
 
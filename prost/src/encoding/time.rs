@@ -0,0 +1,343 @@
+//! Encoding of `google.protobuf.Timestamp` and `google.protobuf.Duration` fields as
+//! [`SystemTime`] and [`Duration`] values.
+//!
+//! Both messages hold a `seconds` and a `nanos` field. Values which can not be represented by
+//! the std types, such as negative durations, are either rejected with a [`DecodeError`] or
+//! saturated to the closest representable value, depending on the module used.
+//!
+//! `SystemTime` is only part of `std`, so the timestamp modules require the `std` feature.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::buf::{Buf, BufMut};
+
+use super::{
+    check_wire_type, decode_key, encode_key, encode_varint, encoded_len_varint, int32, int64,
+    key_len, merge_loop, skip_field, DecodeContext, WireType,
+};
+use crate::DecodeError;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// The fields of a `Timestamp` or `Duration` message.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Parts {
+    seconds: i64,
+    nanos: i32,
+}
+
+impl Parts {
+    /// Splits a number of nanoseconds, saturating the seconds to the range of an `i64`.
+    fn from_nanos(nanos: i128) -> Parts {
+        let seconds = nanos.div_euclid(NANOS_PER_SECOND);
+        Parts {
+            seconds: seconds.clamp(i64::MIN.into(), i64::MAX.into()) as i64,
+            nanos: nanos.rem_euclid(NANOS_PER_SECOND) as i32,
+        }
+    }
+
+    fn total_nanos(self) -> i128 {
+        i128::from(self.seconds) * NANOS_PER_SECOND + i128::from(self.nanos)
+    }
+
+    /// Converts normalized, non-negative parts to a `Duration`.
+    fn unsigned_duration(self) -> Duration {
+        Duration::new(self.seconds as u64, self.nanos as u32)
+    }
+
+    fn body_len(self) -> usize {
+        (if self.seconds != 0 {
+            int64::encoded_len(1, &self.seconds)
+        } else {
+            0
+        }) + (if self.nanos != 0 {
+            int32::encoded_len(2, &self.nanos)
+        } else {
+            0
+        })
+    }
+
+    fn encode(self, tag: u32, buf: &mut impl BufMut) {
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(self.body_len() as u64, buf);
+        if self.seconds != 0 {
+            int64::encode(1, &self.seconds, buf);
+        }
+        if self.nanos != 0 {
+            int32::encode(2, &self.nanos, buf);
+        }
+    }
+
+    fn encoded_len(self, tag: u32) -> usize {
+        let len = self.body_len();
+        key_len(tag) + encoded_len_varint(len as u64) + len
+    }
+
+    fn merge<B>(
+        &mut self,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.limit_reached()?;
+        merge_loop(
+            self,
+            buf,
+            ctx.enter_recursion(),
+            |parts: &mut Parts, buf: &mut B, ctx| {
+                let (tag, wire_type) = decode_key(buf)?;
+                match tag {
+                    1 => int64::merge(wire_type, &mut parts.seconds, buf, ctx),
+                    2 => int32::merge(wire_type, &mut parts.nanos, buf, ctx),
+                    _ => skip_field(wire_type, tag, buf, ctx),
+                }
+            },
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+fn timestamp_parts(value: &SystemTime) -> Parts {
+    let nanos = match value.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_nanos() as i128,
+        Err(before) => -(before.duration().as_nanos() as i128),
+    };
+    Parts::from_nanos(nanos)
+}
+
+fn duration_parts(value: &Duration) -> Parts {
+    Parts::from_nanos(value.as_nanos() as i128)
+}
+
+/// Returns the time at the given number of nanoseconds from the Unix epoch, if representable.
+#[cfg(feature = "std")]
+fn system_time(nanos: i128) -> Option<SystemTime> {
+    let magnitude = nanos.unsigned_abs();
+    let duration = Duration::new(
+        (magnitude / NANOS_PER_SECOND as u128) as u64,
+        (magnitude % NANOS_PER_SECOND as u128) as u32,
+    );
+    if nanos < 0 {
+        UNIX_EPOCH.checked_sub(duration)
+    } else {
+        UNIX_EPOCH.checked_add(duration)
+    }
+}
+
+#[cfg(feature = "std")]
+fn to_system_time(parts: Parts) -> Result<SystemTime, DecodeError> {
+    system_time(parts.total_nanos()).ok_or_else(|| DecodeError::new("timestamp out of range"))
+}
+
+#[cfg(feature = "std")]
+fn to_system_time_saturating(parts: Parts) -> Result<SystemTime, DecodeError> {
+    let nanos = parts.total_nanos();
+    if let Some(time) = system_time(nanos) {
+        return Ok(time);
+    }
+    // The range of `SystemTime` depends on the platform, so search for its bound between the
+    // epoch, which is always representable, and the decoded time.
+    let (mut valid, mut invalid) = (0, nanos);
+    while (invalid - valid).abs() > 1 {
+        let middle = valid + (invalid - valid) / 2;
+        if system_time(middle).is_some() {
+            valid = middle;
+        } else {
+            invalid = middle;
+        }
+    }
+    Ok(system_time(valid).unwrap_or(UNIX_EPOCH))
+}
+
+fn to_duration(parts: Parts) -> Result<Duration, DecodeError> {
+    let nanos = parts.total_nanos();
+    if nanos < 0 {
+        return Err(DecodeError::new("negative duration"));
+    }
+    Ok(Parts::from_nanos(nanos).unsigned_duration())
+}
+
+fn to_duration_saturating(parts: Parts) -> Result<Duration, DecodeError> {
+    Ok(Parts::from_nanos(parts.total_nanos().max(0)).unsigned_duration())
+}
+
+macro_rules! time {
+    ($(#[$doc:meta])* $module:ident, $ty:ty, $to_parts:ident, $from_parts:ident) => {
+        $(#[$doc])*
+        pub mod $module {
+            use super::*;
+
+            pub fn encode(tag: u32, value: &$ty, buf: &mut impl BufMut) {
+                $to_parts(value).encode(tag, buf);
+            }
+
+            pub fn merge(
+                wire_type: WireType,
+                value: &mut $ty,
+                buf: &mut impl Buf,
+                ctx: DecodeContext,
+            ) -> Result<(), DecodeError> {
+                let mut parts = $to_parts(value);
+                parts.merge(wire_type, buf, ctx)?;
+                *value = $from_parts(parts)?;
+                Ok(())
+            }
+
+            pub fn encode_repeated(tag: u32, values: &[$ty], buf: &mut impl BufMut) {
+                for value in values {
+                    encode(tag, value, buf);
+                }
+            }
+
+            pub fn merge_repeated(
+                wire_type: WireType,
+                values: &mut Vec<$ty>,
+                buf: &mut impl Buf,
+                ctx: DecodeContext,
+            ) -> Result<(), DecodeError> {
+                let mut parts = Parts::default();
+                parts.merge(wire_type, buf, ctx)?;
+                values.push($from_parts(parts)?);
+                Ok(())
+            }
+
+            #[inline]
+            pub fn encoded_len(tag: u32, value: &$ty) -> usize {
+                $to_parts(value).encoded_len(tag)
+            }
+
+            #[inline]
+            pub fn encoded_len_repeated(tag: u32, values: &[$ty]) -> usize {
+                values.iter().map(|value| encoded_len(tag, value)).sum()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+time!(
+    /// `google.protobuf.Timestamp` fields as `SystemTime`, rejecting times the platform can not
+    /// represent.
+    timestamp,
+    SystemTime,
+    timestamp_parts,
+    to_system_time
+);
+#[cfg(feature = "std")]
+time!(
+    /// `google.protobuf.Timestamp` fields as `SystemTime`, saturating times the platform can not
+    /// represent to the closest representable time.
+    saturating_timestamp,
+    SystemTime,
+    timestamp_parts,
+    to_system_time_saturating
+);
+time!(
+    /// `google.protobuf.Duration` fields as `Duration`, rejecting negative durations.
+    duration,
+    Duration,
+    duration_parts,
+    to_duration
+);
+time!(
+    /// `google.protobuf.Duration` fields as `Duration`, saturating negative durations to zero.
+    saturating_duration,
+    Duration,
+    duration_parts,
+    to_duration_saturating
+);
+
+#[cfg(test)]
+mod test {
+    use ::bytes::Bytes;
+
+    use super::*;
+
+    fn decode<T>(
+        merge: fn(WireType, &mut T, &mut Bytes, DecodeContext) -> Result<(), DecodeError>,
+        mut value: T,
+        parts: Parts,
+    ) -> Result<T, DecodeError> {
+        let mut buf = Vec::new();
+        parts.encode(1, &mut buf);
+        let mut buf = Bytes::from(buf).split_off(key_len(1));
+        merge(
+            WireType::LengthDelimited,
+            &mut value,
+            &mut buf,
+            DecodeContext::default(),
+        )?;
+        Ok(value)
+    }
+
+    #[test]
+    fn timestamps_round_trip() {
+        for time in [
+            UNIX_EPOCH,
+            UNIX_EPOCH + Duration::new(1_700_000_000, 123),
+            UNIX_EPOCH - Duration::new(1, 1),
+        ] {
+            let mut buf = Vec::new();
+            timestamp::encode(3, &time, &mut buf);
+            assert_eq!(buf.len(), timestamp::encoded_len(3, &time));
+            let parts = timestamp_parts(&time);
+            assert!((0..1_000_000_000).contains(&parts.nanos));
+            assert_eq!(decode(timestamp::merge, UNIX_EPOCH, parts), Ok(time));
+        }
+        assert_eq!(
+            timestamp_parts(&(UNIX_EPOCH - Duration::new(1, 1))),
+            Parts {
+                seconds: -2,
+                nanos: 999_999_999
+            }
+        );
+    }
+
+    #[test]
+    fn unrepresentable_timestamps() {
+        // Before the earliest time of all platforms, once `nanos` is normalized.
+        let parts = Parts {
+            seconds: i64::MIN,
+            nanos: -1,
+        };
+        assert_eq!(
+            decode(timestamp::merge, UNIX_EPOCH, parts),
+            Err(DecodeError::new("timestamp out of range"))
+        );
+        let min = decode(saturating_timestamp::merge, UNIX_EPOCH, parts).unwrap();
+        assert!(min < UNIX_EPOCH);
+        assert_eq!(min.checked_sub(Duration::from_nanos(1)), None);
+    }
+
+    #[test]
+    fn durations() {
+        let duration = Duration::new(5, 500);
+        let mut buf = Vec::new();
+        duration::encode(1, &duration, &mut buf);
+        assert_eq!(buf, [10, 5, 8, 5, 16, 244, 3]);
+        assert_eq!(
+            decode(duration::merge, Duration::ZERO, duration_parts(&duration)),
+            Ok(duration)
+        );
+
+        let negative = Parts {
+            seconds: -1,
+            nanos: 0,
+        };
+        assert_eq!(
+            decode(duration::merge, Duration::ZERO, negative),
+            Err(DecodeError::new("negative duration"))
+        );
+        assert_eq!(
+            decode(saturating_duration::merge, duration, negative),
+            Ok(Duration::ZERO)
+        );
+    }
+}
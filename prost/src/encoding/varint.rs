@@ -1,7 +1,7 @@
 use core::cmp::min;
 use core::num::NonZeroU64;
 
-use ::bytes::{Buf, BufMut};
+use crate::buf::{Buf, BufMut};
 
 use crate::DecodeError;
 
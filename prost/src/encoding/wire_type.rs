@@ -1,20 +1,55 @@
 use crate::DecodeError;
 use alloc::format;
 
-/// Represent the wire type for protobuf encoding.
+/// The wire type of an encoded field, which tells how its value is encoded.
 ///
-/// The integer value is equvilant with the encoded value.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The wire type makes up the three low bits of the key of each field, see
+/// [`encode_key`](crate::encode_key). Its discriminant is the value encoded in the key, so
+/// that `WireType::LengthDelimited as u8 == 2`.
+///
+/// Decoders use it to skip fields they do not know, and to check that a field is encoded as
+/// its declared type expects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum WireType {
+    /// A varint: `int32`, `int64`, `uint32`, `uint64`, `sint32`, `sint64`, `bool` and `enum`.
     Varint = 0,
+    /// Eight little-endian bytes: `fixed64`, `sfixed64` and `double`.
     SixtyFourBit = 1,
+    /// A varint length followed by as many bytes: `string`, `bytes`, embedded messages and
+    /// packed repeated fields.
     LengthDelimited = 2,
+    /// The start of a group, ended by a field with the same tag and [`WireType::EndGroup`].
     StartGroup = 3,
+    /// The end of a group.
     EndGroup = 4,
+    /// Four little-endian bytes: `fixed32`, `sfixed32` and `float`.
     ThirtyTwoBit = 5,
 }
 
+impl From<WireType> for u8 {
+    #[inline]
+    fn from(value: WireType) -> Self {
+        value as u8
+    }
+}
+
+impl From<WireType> for u64 {
+    #[inline]
+    fn from(value: WireType) -> Self {
+        value as u64
+    }
+}
+
+impl TryFrom<u8> for WireType {
+    type Error = DecodeError;
+
+    #[inline]
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        WireType::try_from(u64::from(value))
+    }
+}
+
 impl TryFrom<u64> for WireType {
     type Error = DecodeError;
 
@@ -47,3 +82,20 @@ pub fn check_wire_type(expected: WireType, actual: WireType) -> Result<(), Decod
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        for value in 0u8..=5 {
+            let wire_type = WireType::try_from(value).unwrap();
+            assert_eq!(u8::from(wire_type), value);
+            assert_eq!(u64::from(wire_type), u64::from(value));
+            assert_eq!(WireType::try_from(u64::from(value)).unwrap(), wire_type);
+        }
+        assert!(WireType::try_from(6u8).is_err());
+        assert!(WireType::try_from(u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Type-erased messages, usable as trait objects.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt::Debug;
+
+use crate::{DecodeError, EncodeError, Message, Name};
+
+/// A dyn-compatible view of a [`Message`], for heterogeneous collections of messages such as
+/// `Vec<Box<dyn ErasedMessage>>`.
+///
+/// `ErasedMessage` is implemented for all messages which implement [`Name`] and `Clone`. Its
+/// methods share the names of the [`Message`] and [`Name`] methods they delegate to, so calls
+/// on concrete message types are ambiguous when both traits are in scope; call them on
+/// `dyn ErasedMessage` instead, or import only one of the traits.
+///
+/// ```rust
+/// use prost::{ErasedMessage, Message, Name};
+///
+/// #[derive(Clone, PartialEq, Message)]
+/// struct Ping {
+///     #[prost(uint64, tag = "1")]
+///     id: u64,
+/// }
+///
+/// impl Name for Ping {
+///     const NAME: &'static str = "Ping";
+///     const PACKAGE: &'static str = "demo";
+/// }
+///
+/// let messages: Vec<Box<dyn ErasedMessage>> = vec![Box::new(Ping { id: 1 })];
+/// for message in &messages {
+///     assert_eq!(message.type_url(), "/demo.Ping");
+///     assert_eq!(message.encode_to_vec(), [0x08, 0x01]);
+/// }
+/// assert_eq!(messages[0].downcast_ref::<Ping>(), Some(&Ping { id: 1 }));
+/// ```
+pub trait ErasedMessage: Debug + Send + Sync + 'static {
+    /// Encodes the message to a newly allocated buffer.
+    ///
+    /// See [`Message::encode_to_vec`].
+    fn encode_to_vec(&self) -> Vec<u8>;
+
+    /// Encodes the message to the start of a byte slice, and returns the number of bytes
+    /// written.
+    ///
+    /// See [`Message::encode_into_slice`].
+    fn encode_into_slice(&self, buf: &mut [u8]) -> Result<usize, EncodeError>;
+
+    /// Returns the encoded length of the message without a length delimiter.
+    ///
+    /// See [`Message::encoded_len`].
+    fn encoded_len(&self) -> usize;
+
+    /// Decodes an instance of the message from `buf`, and merges it into `self`.
+    ///
+    /// See [`Message::merge`].
+    fn merge_from_slice(&mut self, buf: &[u8]) -> Result<(), DecodeError>;
+
+    /// Clears the message, resetting all fields to their default.
+    ///
+    /// See [`Message::clear`].
+    fn clear(&mut self);
+
+    /// Returns a boxed copy of the message.
+    fn clone_box(&self) -> Box<dyn ErasedMessage>;
+
+    /// Returns the type URL of the message.
+    ///
+    /// See [`Name::type_url`].
+    fn type_url(&self) -> String;
+
+    /// Returns the message as [`Any`], to downcast it to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the message as mutable [`Any`], to downcast it to its concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<M> ErasedMessage for M
+where
+    M: Message + Name + Clone + 'static,
+{
+    fn encode_to_vec(&self) -> Vec<u8> {
+        Message::encode_to_vec(self)
+    }
+
+    fn encode_into_slice(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        Message::encode_into_slice(self, buf)
+    }
+
+    fn encoded_len(&self) -> usize {
+        Message::encoded_len(self)
+    }
+
+    fn merge_from_slice(&mut self, buf: &[u8]) -> Result<(), DecodeError> {
+        Message::merge(self, buf)
+    }
+
+    fn clear(&mut self) {
+        Message::clear(self)
+    }
+
+    fn clone_box(&self) -> Box<dyn ErasedMessage> {
+        Box::new(self.clone())
+    }
+
+    fn type_url(&self) -> String {
+        M::type_url()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl dyn ErasedMessage {
+    /// Returns `true` if the message is of type `M`.
+    pub fn is<M: ErasedMessage>(&self) -> bool {
+        self.as_any().is::<M>()
+    }
+
+    /// Returns a reference to the message if it is of type `M`.
+    pub fn downcast_ref<M: ErasedMessage>(&self) -> Option<&M> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Returns a mutable reference to the message if it is of type `M`.
+    pub fn downcast_mut<M: ErasedMessage>(&mut self) -> Option<&mut M> {
+        self.as_any_mut().downcast_mut()
+    }
+}
+
+impl Clone for Box<dyn ErasedMessage> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
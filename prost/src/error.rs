@@ -20,6 +20,8 @@ pub struct DecodeError {
 
 #[derive(Clone, PartialEq, Eq)]
 struct Inner {
+    /// The kind of error.
+    kind: DecodeErrorKind,
     /// A 'best effort' root cause description.
     description: Cow<'static, str>,
     /// A stack of (message, field) name pairs, which identify the specific
@@ -35,14 +37,29 @@ impl DecodeError {
     #[doc(hidden)]
     #[cold]
     pub fn new(description: impl Into<Cow<'static, str>>) -> DecodeError {
+        DecodeError::with_kind(DecodeErrorKind::Other, description)
+    }
+
+    /// Creates a new `DecodeError` of the given kind.
+    #[cold]
+    pub(crate) fn with_kind(
+        kind: DecodeErrorKind,
+        description: impl Into<Cow<'static, str>>,
+    ) -> DecodeError {
         DecodeError {
             inner: Box::new(Inner {
+                kind,
                 description: description.into(),
                 stack: Vec::new(),
             }),
         }
     }
 
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> DecodeErrorKind {
+        self.inner.kind
+    }
+
     /// Pushes a (message, field) name location pair on to the location stack.
     ///
     /// Meant to be used only by `Message` implementations.
@@ -55,6 +72,7 @@ impl DecodeError {
 impl fmt::Debug for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("DecodeError")
+            .field("kind", &self.inner.kind)
             .field("description", &self.inner.description)
             .field("stack", &self.inner.stack)
             .finish()
@@ -81,6 +99,21 @@ impl From<DecodeError> for std::io::Error {
     }
 }
 
+/// The kind of a [`DecodeError`], telling errors caused by the limits of the decoder apart from
+/// invalid input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodeErrorKind {
+    /// An error without a more specific kind, such as input which is not a valid encoding of
+    /// the message.
+    Other,
+    /// Nested messages exceed the recursion limit of the decoder.
+    RecursionLimitReached,
+    /// The unknown fields skipped by the decode exceed the [`SkipLimits`](crate::SkipLimits) of
+    /// the [`DecodeContext`](crate::DecodeContext).
+    SkipLimitExceeded,
+}
+
 /// A Protobuf message encoding error.
 ///
 /// `EncodeError` always indicates that a message failed to encode because the
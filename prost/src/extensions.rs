@@ -0,0 +1,124 @@
+//! State shared with decode functions through a [`DecodeContext`](crate::DecodeContext).
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::any::{Any, TypeId};
+use core::fmt;
+
+#[cfg(target_has_atomic = "ptr")]
+use crate::Interner;
+
+/// A map of values keyed by their type, shared with every decode function through
+/// [`DecodeContext::extensions`](crate::DecodeContext::extensions).
+///
+/// Values are only borrowed immutably during a decode, so state updated while decoding, such as
+/// a pool of interned strings, should use interior mutability.
+#[derive(Default)]
+pub struct Extensions {
+    map: BTreeMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+/// Returned by contexts without extensions.
+pub(crate) static EMPTY: Extensions = Extensions::new();
+
+impl Extensions {
+    /// Creates empty extensions.
+    pub const fn new() -> Self {
+        Extensions {
+            map: BTreeMap::new(),
+        }
+    }
+
+    /// Inserts a value, returning the previous value of the same type, if any.
+    pub fn insert<T>(&mut self, value: T) -> Option<T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast().ok())
+            .map(|previous| *previous)
+    }
+
+    /// Returns a reference to the value of type `T`, if any.
+    pub fn get<T>(&self) -> Option<&T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Returns a mutable reference to the value of type `T`, if any.
+    pub fn get_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut())
+    }
+
+    /// Removes the value of type `T`, returning it.
+    pub fn remove<T>(&mut self) -> Option<T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+
+    /// Sets the interner used to decode `Arc<str>` string fields, replacing any previous one.
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn set_interner(&mut self, interner: impl Interner + 'static) {
+        self.insert::<Box<dyn Interner>>(Box::new(interner));
+    }
+
+    /// Returns the interner set with [`Extensions::set_interner`], if any.
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn interner(&self) -> Option<&dyn Interner> {
+        self.get::<Box<dyn Interner>>().map(|interner| &**interner)
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_keyed_by_type() {
+        let mut extensions = Extensions::new();
+        assert!(extensions.is_empty());
+        assert_eq!(extensions.insert(1u32), None);
+        assert_eq!(extensions.insert("limit"), None);
+        assert_eq!(extensions.insert(2u32), Some(1));
+        assert_eq!(extensions.len(), 2);
+
+        *extensions.get_mut::<u32>().unwrap() += 1;
+        assert_eq!(extensions.get::<u32>(), Some(&3));
+        assert_eq!(extensions.get::<u64>(), None);
+        assert_eq!(extensions.remove::<&str>(), Some("limit"));
+        assert_eq!(extensions.len(), 1);
+    }
+}
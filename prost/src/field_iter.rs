@@ -0,0 +1,136 @@
+//! Iteration over the raw fields of an encoded message.
+
+use crate::encoding::{decode_key, decode_varint, skip_value, DecodeContext, WireType};
+use crate::DecodeError;
+
+/// A field of an encoded message, borrowed from the encoded buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawField<'a> {
+    /// The tag of the field.
+    pub tag: u32,
+    /// The wire type of the field.
+    pub wire_type: WireType,
+    /// The value of the field: the encoded varint or fixed width value, the payload of a length
+    /// delimited value without its length prefix, or the fields of a group without its end key.
+    pub value: &'a [u8],
+    /// The whole encoded field, from its key to the end of its value.
+    pub encoded: &'a [u8],
+    /// The position of the field in the iterated buffer.
+    pub offset: usize,
+}
+
+impl<'a> RawField<'a> {
+    /// Returns an iterator over the fields nested in this field, either the fields of a group,
+    /// or the fields of a length delimited value holding an encoded message.
+    ///
+    /// Whether a length delimited value holds a message, rather than a string, bytes, or packed
+    /// repeated values, depends on the message definition; iterating other values fails or
+    /// yields meaningless fields.
+    pub fn nested(&self) -> FieldIter<'a> {
+        FieldIter::new(self.value)
+    }
+}
+
+/// An iterator over the top-level fields of an encoded message, in encoded order, without
+/// decoding their values.
+///
+/// The fields of nested messages and groups are skipped along with their parent field, and can
+/// be iterated with [`RawField::nested`]. Iteration stops after the first error.
+///
+/// ```rust
+/// use prost::{FieldIter, WireType};
+///
+/// // A message with a `string` field 1 and an `int32` field 2.
+/// let buf = [0x0a, 0x02, b'h', b'i', 0x10, 0x2a];
+///
+/// let fields = FieldIter::new(&buf).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(fields.len(), 2);
+/// assert_eq!(fields[0].tag, 1);
+/// assert_eq!(fields[0].value, b"hi");
+/// assert_eq!(fields[1].wire_type, WireType::Varint);
+/// assert_eq!(fields[1].offset, 4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FieldIter<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> FieldIter<'a> {
+    /// Creates an iterator over the fields of the encoded message in `buf`.
+    pub fn new(buf: &'a [u8]) -> FieldIter<'a> {
+        FieldIter {
+            buf,
+            offset: 0,
+            failed: false,
+        }
+    }
+
+    fn read(&mut self) -> Result<RawField<'a>, DecodeError> {
+        let field = self.buf;
+        let mut rest = field;
+        let (tag, wire_type) = decode_key(&mut rest)?;
+        let value_start = field.len() - rest.len();
+        let value = match wire_type {
+            WireType::LengthDelimited => {
+                let len = decode_varint(&mut rest)?;
+                if len > rest.len() as u64 {
+                    return Err(DecodeError::new("buffer underflow"));
+                }
+                let (value, after) = rest.split_at(len as usize);
+                rest = after;
+                value
+            }
+            WireType::StartGroup => {
+                let ctx = DecodeContext::default();
+                ctx.limit_reached()?;
+                // The end key may be encoded with any length, so the end of the group fields is
+                // only known once the end key is found.
+                let value = rest;
+                loop {
+                    let len = value.len() - rest.len();
+                    let (inner_tag, inner_wire_type) = decode_key(&mut rest)?;
+                    if inner_wire_type == WireType::EndGroup {
+                        if inner_tag != tag {
+                            return Err(DecodeError::new("unexpected end group tag"));
+                        }
+                        break &value[..len];
+                    }
+                    skip_value(inner_wire_type, inner_tag, &mut rest, ctx.enter_recursion())?;
+                }
+            }
+            _ => {
+                skip_value(wire_type, tag, &mut rest, DecodeContext::default())?;
+                &field[value_start..field.len() - rest.len()]
+            }
+        };
+
+        let len = field.len() - rest.len();
+        let raw = RawField {
+            tag,
+            wire_type,
+            value,
+            encoded: &field[..len],
+            offset: self.offset,
+        };
+        self.buf = rest;
+        self.offset += len;
+        Ok(raw)
+    }
+}
+
+impl<'a> Iterator for FieldIter<'a> {
+    type Item = Result<RawField<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() || self.failed {
+            return None;
+        }
+        let field = self.read();
+        self.failed = field.is_err();
+        Some(field)
+    }
+}
+
+impl core::iter::FusedIterator for FieldIter<'_> {}
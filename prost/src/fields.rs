@@ -0,0 +1,229 @@
+//! Traversal of the fields of messages, for generic algorithms such as masking, redaction or
+//! size accounting which don't need full reflection.
+
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use alloc::vec;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::iter::FusedIterator;
+
+use ::bytes::Bytes;
+
+/// The static description of a message field, as yielded by [`MessageFields::field_values`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct FieldDescriptorMeta {
+    /// The name of the Rust field, or of the oneof variant for fields of a oneof.
+    pub name: &'static str,
+    /// The field number.
+    pub tag: u32,
+}
+
+impl FieldDescriptorMeta {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, tag: u32) -> Self {
+        FieldDescriptorMeta { name, tag }
+    }
+}
+
+/// A reference to the value of a message field.
+///
+/// Scalar values are copied, and `sint32`, `sfixed32` and `int32` fields are all represented as
+/// [`FieldValueRef::I32`], and likewise for the other integer types.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum FieldValueRef<'a> {
+    /// A `bool` value.
+    Bool(bool),
+    /// An `int32`, `sint32` or `sfixed32` value.
+    I32(i32),
+    /// An `int64`, `sint64` or `sfixed64` value.
+    I64(i64),
+    /// A `uint32` or `fixed32` value.
+    U32(u32),
+    /// A `uint64` or `fixed64` value.
+    U64(u64),
+    /// A `float` value.
+    F32(f32),
+    /// A `double` value.
+    F64(f64),
+    /// A `string` value.
+    String(&'a str),
+    /// A `bytes` value.
+    Bytes(&'a [u8]),
+    /// The value of an enumeration field, which may not be a declared variant of the enum.
+    Enum(i32),
+    /// A `google.protobuf.Timestamp` value represented as a `SystemTime`.
+    #[cfg(feature = "std")]
+    Timestamp(std::time::SystemTime),
+    /// A `google.protobuf.Duration` value represented as a `Duration`.
+    Duration(core::time::Duration),
+    /// A message or group, whose fields can be traversed in turn.
+    Message(&'a dyn MessageFields),
+    /// The values of a repeated field.
+    Repeated(Vec<FieldValueRef<'a>>),
+    /// The entries of a map field.
+    Map(Vec<(FieldValueRef<'a>, FieldValueRef<'a>)>),
+}
+
+/// Traversal of the fields of a message.
+///
+/// This is implemented by messages derived with `#[prost(field_values)]`, which `prost-build`
+/// generates for the messages selected with `Config::field_values`, and by the well-known
+/// wrapper types implemented in `prost`. The fields of nested messages must implement it as
+/// well.
+///
+/// ```rust
+/// use prost::{FieldValueRef, MessageFields};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// #[prost(field_values)]
+/// struct Login {
+///     #[prost(string, tag = "1")]
+///     user: String,
+///     #[prost(string, tag = "2")]
+///     password: String,
+/// }
+///
+/// let login = Login {
+///     user: "root".to_string(),
+///     password: "hunter2".to_string(),
+/// };
+/// let strings = login
+///     .field_values()
+///     .filter_map(|(field, value)| match value {
+///         FieldValueRef::String(value) => Some((field.name, value)),
+///         _ => None,
+///     })
+///     .collect::<Vec<_>>();
+/// assert_eq!(strings, [("user", "root"), ("password", "hunter2")]);
+/// ```
+pub trait MessageFields: Debug {
+    /// Returns an iterator over the fields of the message which hold a value, in field number
+    /// order.
+    ///
+    /// Singular fields without presence are always included, and unset optional fields,
+    /// optional messages and oneofs are skipped. Repeated and map fields are included even if
+    /// empty.
+    fn field_values(&self) -> FieldValues<'_>;
+}
+
+/// An iterator over the fields of a message, returned by [`MessageFields::field_values`].
+#[derive(Debug)]
+pub struct FieldValues<'a> {
+    fields: vec::IntoIter<(&'static FieldDescriptorMeta, FieldValueRef<'a>)>,
+}
+
+impl<'a> FieldValues<'a> {
+    #[doc(hidden)]
+    pub fn new(fields: Vec<(&'static FieldDescriptorMeta, FieldValueRef<'a>)>) -> Self {
+        FieldValues {
+            fields: fields.into_iter(),
+        }
+    }
+}
+
+impl<'a> Iterator for FieldValues<'a> {
+    type Item = (&'static FieldDescriptorMeta, FieldValueRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.fields.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.fields.size_hint()
+    }
+}
+
+impl ExactSizeIterator for FieldValues<'_> {}
+
+impl FusedIterator for FieldValues<'_> {}
+
+impl<M> MessageFields for Box<M>
+where
+    M: MessageFields,
+{
+    fn field_values(&self) -> FieldValues<'_> {
+        (**self).field_values()
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<M> MessageFields for Arc<M>
+where
+    M: MessageFields,
+{
+    fn field_values(&self) -> FieldValues<'_> {
+        (**self).field_values()
+    }
+}
+
+impl<M> MessageFields for Cow<'static, M>
+where
+    M: MessageFields + Clone,
+{
+    fn field_values(&self) -> FieldValues<'_> {
+        (**self).field_values()
+    }
+}
+
+/// Implements `MessageFields` for a well-known wrapper type, whose `value` field is only
+/// encoded if it is not the default.
+macro_rules! wrapper_fields {
+    ($ty:ty, $value:ident => $field_value:expr) => {
+        impl MessageFields for $ty {
+            fn field_values(&self) -> FieldValues<'_> {
+                const VALUE: FieldDescriptorMeta = FieldDescriptorMeta::new("value", 1);
+                let $value = self;
+                if *self == <$ty>::default() {
+                    FieldValues::new(Vec::new())
+                } else {
+                    FieldValues::new(vec![(&VALUE, $field_value)])
+                }
+            }
+        }
+    };
+}
+
+wrapper_fields!(bool, value => FieldValueRef::Bool(*value));
+wrapper_fields!(u32, value => FieldValueRef::U32(*value));
+wrapper_fields!(u64, value => FieldValueRef::U64(*value));
+wrapper_fields!(i32, value => FieldValueRef::I32(*value));
+wrapper_fields!(i64, value => FieldValueRef::I64(*value));
+wrapper_fields!(f32, value => FieldValueRef::F32(*value));
+wrapper_fields!(f64, value => FieldValueRef::F64(*value));
+wrapper_fields!(String, value => FieldValueRef::String(value));
+wrapper_fields!(Vec<u8>, value => FieldValueRef::Bytes(value));
+wrapper_fields!(Bytes, value => FieldValueRef::Bytes(value));
+
+/// `google.protobuf.Empty`
+impl MessageFields for () {
+    fn field_values(&self) -> FieldValues<'_> {
+        FieldValues::new(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    #[test]
+    fn wrapper_field_values() {
+        assert_eq!(0u32.field_values().len(), 0);
+        let text = "text".to_string();
+        let values = text.field_values().collect::<Vec<_>>();
+        assert_eq!(values.len(), 1);
+        assert_eq!(*values[0].0, FieldDescriptorMeta::new("value", 1));
+        assert!(matches!(values[0].1, FieldValueRef::String("text")));
+        assert_eq!(Box::new(()).field_values().len(), 0);
+    }
+}
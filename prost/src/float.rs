@@ -0,0 +1,132 @@
+//! Formatting of floating point values which preserves their exact bits.
+//!
+//! The `Debug` implementations of derived messages format `float` and `double` fields with
+//! [`RoundTrip`], so that the output is the same on all platforms, and parsing it back yields
+//! the original value.
+//!
+//! ```rust
+//! use prost::float::RoundTrip;
+//!
+//! let value = f64::from_bits(0x7ff8_0000_0000_0001);
+//! let formatted = RoundTrip(value).to_string();
+//! assert_eq!(formatted, "NaN(0x7ff8000000000001)");
+//!
+//! let RoundTrip(parsed) = formatted.parse::<RoundTrip<f64>>().unwrap();
+//! assert_eq!(parsed.to_bits(), value.to_bits());
+//! ```
+
+use core::fmt;
+use core::num::ParseFloatError;
+use core::str::FromStr;
+
+/// A floating point value which is formatted with the shortest representation that parses back
+/// to the same bits.
+///
+/// Finite values and infinities are formatted as with `Debug`, which keeps the sign of `-0.0`.
+/// The canonical quiet NaN is formatted as `NaN`, and other NaNs as `NaN(0x...)` with their bits
+/// in hexadecimal, since they would all print as `NaN` otherwise.
+///
+/// Formatted values can be parsed back with [`FromStr`], which accepts everything accepted by
+/// `f32::from_str` and `f64::from_str` as well.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct RoundTrip<T>(pub T);
+
+macro_rules! round_trip {
+    ($ty:ty, $bits:ty, $nan:expr, $width:expr) => {
+        impl fmt::Display for RoundTrip<$ty> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let bits = self.0.to_bits();
+                if !self.0.is_nan() {
+                    write!(f, "{:?}", self.0)
+                } else if bits == $nan {
+                    f.write_str("NaN")
+                } else {
+                    write!(f, "NaN({:#0width$x})", bits, width = $width + 2)
+                }
+            }
+        }
+
+        impl FromStr for RoundTrip<$ty> {
+            type Err = ParseFloatError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let nan = s
+                    .strip_prefix("NaN(0x")
+                    .and_then(|s| s.strip_suffix(')'))
+                    .and_then(|bits| <$bits>::from_str_radix(bits, 16).ok())
+                    .map(<$ty>::from_bits)
+                    .filter(|value| value.is_nan());
+                match nan {
+                    Some(value) => Ok(RoundTrip(value)),
+                    // A NaN is always parsed as the canonical NaN.
+                    None if s == "NaN" => Ok(RoundTrip(<$ty>::from_bits($nan))),
+                    None => s.parse().map(RoundTrip),
+                }
+            }
+        }
+    };
+}
+
+round_trip!(f32, u32, 0x7fc0_0000, 8);
+round_trip!(f64, u64, 0x7ff8_0000_0000_0000, 16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    fn check_f64(value: f64, formatted: &str) {
+        assert_eq!(RoundTrip(value).to_string(), formatted);
+        let RoundTrip(parsed) = formatted.parse::<RoundTrip<f64>>().unwrap();
+        assert_eq!(parsed.to_bits(), value.to_bits(), "{}", formatted);
+    }
+
+    fn check_f32(value: f32, formatted: &str) {
+        assert_eq!(RoundTrip(value).to_string(), formatted);
+        let RoundTrip(parsed) = formatted.parse::<RoundTrip<f32>>().unwrap();
+        assert_eq!(parsed.to_bits(), value.to_bits(), "{}", formatted);
+    }
+
+    #[test]
+    fn round_trip_f64() {
+        check_f64(0.0, "0.0");
+        check_f64(-0.0, "-0.0");
+        check_f64(0.1, "0.1");
+        check_f64(1.0 / 3.0, "0.3333333333333333");
+        check_f64(1e300, "1e300");
+        check_f64(f64::MIN_POSITIVE, "2.2250738585072014e-308");
+        check_f64(f64::from_bits(1), "5e-324");
+        check_f64(f64::INFINITY, "inf");
+        check_f64(f64::NEG_INFINITY, "-inf");
+        check_f64(f64::from_bits(0x7ff8_0000_0000_0000), "NaN");
+        check_f64(
+            f64::from_bits(0xfff8_0000_0000_0000),
+            "NaN(0xfff8000000000000)",
+        );
+        check_f64(
+            f64::from_bits(0x7ff0_0000_0000_0001),
+            "NaN(0x7ff0000000000001)",
+        );
+    }
+
+    #[test]
+    fn round_trip_f32() {
+        check_f32(-0.0, "-0.0");
+        check_f32(0.1, "0.1");
+        check_f32(16777217.0, "16777216.0");
+        check_f32(f32::MAX, "3.4028235e38");
+        check_f32(f32::from_bits(0x7fc0_0000), "NaN");
+        check_f32(f32::from_bits(0x7fc0_0001), "NaN(0x7fc00001)");
+        check_f32(f32::from_bits(0x0000_0001), "1e-45");
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!("NaN(0x3ff0000000000000)".parse::<RoundTrip<f64>>().is_err());
+        assert!("NaN(0xfffffffff)".parse::<RoundTrip<f32>>().is_err());
+        assert!("NaN(7fc00001)".parse::<RoundTrip<f32>>().is_err());
+        assert!("".parse::<RoundTrip<f64>>().is_err());
+        assert_eq!("1.5".parse::<RoundTrip<f64>>(), Ok(RoundTrip(1.5)));
+    }
+}
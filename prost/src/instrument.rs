@@ -0,0 +1,190 @@
+//! Instrumentation of message encoding and decoding.
+//!
+//! A process-wide hook installed with [`set_hook`] receives an [`Event`] for every message encoded
+//! or decoded through the [`Message`](crate::Message) methods, which makes it possible to collect
+//! per-type serialization metrics without wrapping every call site.
+//!
+//! Only top level operations are reported: nested messages are accounted for in the event of the
+//! message containing them.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+type Hook = Box<dyn Fn(&Event) + Send + Sync + 'static>;
+
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+// Checked before doing any work, so that uninstrumented encodes and decodes only pay for a
+// relaxed load.
+static HOOKED: AtomicBool = AtomicBool::new(false);
+
+std::thread_local! {
+    static UNKNOWN_FIELDS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The kind of operation an [`Event`] reports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// The message was encoded.
+    Encode,
+    /// The message was decoded or merged.
+    Decode,
+}
+
+/// An encode or decode of a message, reported to the installed hook.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Event {
+    /// The operation performed.
+    pub operation: Operation,
+    /// The Rust type name of the message, as returned by [`core::any::type_name`].
+    pub type_name: &'static str,
+    /// The number of bytes written or consumed, including any length delimiter.
+    pub encoded_len: usize,
+    /// The time taken by the operation.
+    pub duration: Duration,
+    /// The number of unknown fields skipped while decoding, including those of nested messages.
+    ///
+    /// Always zero for encodes.
+    pub unknown_fields: usize,
+    /// Whether the operation completed successfully.
+    pub success: bool,
+}
+
+/// Installs a hook receiving an [`Event`] for every message encode and decode, replacing any
+/// previously installed hook.
+///
+/// The hook is called on the thread performing the operation, so it should be cheap. It must not
+/// call [`set_hook`] or [`take_hook`] itself.
+pub fn set_hook(hook: Hook) {
+    let mut slot = HOOK.write().unwrap_or_else(PoisonError::into_inner);
+    *slot = Some(hook);
+    HOOKED.store(true, Ordering::Release);
+}
+
+/// Removes the installed hook, returning it.
+pub fn take_hook() -> Option<Hook> {
+    let mut slot = HOOK.write().unwrap_or_else(PoisonError::into_inner);
+    HOOKED.store(false, Ordering::Release);
+    slot.take()
+}
+
+fn is_hooked() -> bool {
+    HOOKED.load(Ordering::Relaxed)
+}
+
+/// Counts an unknown field skipped by the decoder on the current thread.
+pub(crate) fn record_unknown_field() {
+    if is_hooked() {
+        UNKNOWN_FIELDS.with(|count| count.set(count.get().wrapping_add(1)));
+    }
+}
+
+fn unknown_fields() -> usize {
+    UNKNOWN_FIELDS.with(Cell::get)
+}
+
+/// An operation in progress, reported to the hook by [`Span::finish`].
+pub(crate) struct Span {
+    started: Option<Started>,
+}
+
+struct Started {
+    operation: Operation,
+    type_name: &'static str,
+    at: Instant,
+    unknown_fields: usize,
+}
+
+impl Span {
+    /// Starts timing an encode of `M`.
+    pub(crate) fn encode<M>() -> Span {
+        Span::start::<M>(Operation::Encode)
+    }
+
+    /// Starts timing a decode of `M`.
+    pub(crate) fn decode<M>() -> Span {
+        Span::start::<M>(Operation::Decode)
+    }
+
+    fn start<M>(operation: Operation) -> Span {
+        let started = is_hooked().then(|| Started {
+            operation,
+            type_name: core::any::type_name::<M>(),
+            at: Instant::now(),
+            unknown_fields: unknown_fields(),
+        });
+        Span { started }
+    }
+
+    /// Reports the operation to the hook, if one is installed.
+    pub(crate) fn finish(self, encoded_len: usize, success: bool) {
+        let Some(started) = self.started else {
+            return;
+        };
+        let event = Event {
+            operation: started.operation,
+            type_name: started.type_name,
+            encoded_len,
+            duration: started.at.elapsed(),
+            unknown_fields: match started.operation {
+                Operation::Encode => 0,
+                Operation::Decode => unknown_fields().wrapping_sub(started.unknown_fields),
+            },
+            success,
+        };
+        let hook = HOOK.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(hook) = &*hook {
+            hook(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn hook_receives_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let thread = std::thread::current().id();
+        set_hook({
+            let events = Arc::clone(&events);
+            // Other tests may encode concurrently, only keep events from this thread.
+            Box::new(move |event| {
+                if std::thread::current().id() == thread {
+                    events.lock().unwrap().push(event.clone());
+                }
+            })
+        });
+
+        let encoded = String::from("hello").encode_to_vec();
+        // An unknown field 2, followed by the string field 1.
+        let mut buf = vec![0x10, 0x01];
+        buf.extend_from_slice(&encoded);
+        let decoded = String::decode(&buf[..]).unwrap();
+        assert!(take_hook().is_some());
+        String::decode(&buf[..]).unwrap();
+
+        assert_eq!(decoded, "hello");
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+
+        assert_eq!(events[0].operation, Operation::Encode);
+        assert_eq!(events[0].type_name, "alloc::string::String");
+        assert_eq!(events[0].encoded_len, encoded.len());
+        assert_eq!(events[0].unknown_fields, 0);
+        assert!(events[0].success);
+
+        assert_eq!(events[1].operation, Operation::Decode);
+        assert_eq!(events[1].type_name, "alloc::string::String");
+        assert_eq!(events[1].encoded_len, buf.len());
+        assert_eq!(events[1].unknown_fields, 1);
+        assert!(events[1].success);
+    }
+}
@@ -0,0 +1,89 @@
+//! Deduplication of decoded strings.
+
+use alloc::sync::Arc;
+
+/// Deduplicates the strings decoded into `Arc<str>` fields.
+///
+/// An interner is set for a decode with [`Extensions::set_interner`], and used for every
+/// `Arc<str>` string field decoded with a [`DecodeContext`] carrying these extensions, so that
+/// repeated values share a single allocation.
+///
+/// [`Extensions::set_interner`]: crate::Extensions::set_interner
+/// [`DecodeContext`]: crate::DecodeContext
+pub trait Interner: Send + Sync {
+    /// Returns a string equal to `value`, reusing a previously returned one if possible.
+    fn intern(&self, value: &str) -> Arc<str>;
+}
+
+/// A thread-safe [`Interner`] keeping every string it returns.
+///
+/// Strings are only released when the interner is dropped or [`StringInterner::clear`] is called.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct StringInterner {
+    strings: std::sync::Mutex<std::collections::HashSet<Arc<str>>>,
+}
+
+#[cfg(feature = "std")]
+impl StringInterner {
+    /// Creates an empty interner.
+    pub fn new() -> Self {
+        StringInterner::default()
+    }
+
+    /// Returns the number of distinct strings in the interner.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if the interner holds no strings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Releases all strings held by the interner.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, std::collections::HashSet<Arc<str>>> {
+        // A panic while holding the lock can not leave the set of strings inconsistent.
+        self.strings
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Interner for StringInterner {
+    fn intern(&self, value: &str) -> Arc<str> {
+        let mut strings = self.lock();
+        if let Some(interned) = strings.get(value) {
+            return Arc::clone(interned);
+        }
+        let interned = Arc::<str>::from(value);
+        strings.insert(Arc::clone(&interned));
+        interned
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_deduplicated() {
+        let interner = StringInterner::new();
+        let a = interner.intern("label");
+        let b = interner.intern("label");
+        let c = interner.intern("other");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(&*c, "other");
+        assert_eq!(interner.len(), 2);
+
+        interner.clear();
+        assert!(interner.is_empty());
+        assert!(!Arc::ptr_eq(&a, &interner.intern("label")));
+    }
+}
@@ -0,0 +1,161 @@
+//! Reading length-delimited messages from [`std::io::Read`] streams.
+//!
+//! Streams of messages, such as those written by
+//! [`Message::encode_length_delimited`](crate::Message::encode_length_delimited), prefix each
+//! message with its length. These functions read one message at a time, without reading past
+//! its end, and reject length prefixes larger than a given maximum before allocating a buffer
+//! for the message.
+//!
+//! ```rust
+//! use prost::Message;
+//!
+//! let mut stream = Vec::new();
+//! 1u32.encode_length_delimited(&mut stream).unwrap();
+//! 2u32.encode_length_delimited(&mut stream).unwrap();
+//!
+//! let mut reader = stream.as_slice();
+//! let mut values = Vec::new();
+//! while let Some(value) = u32::decode_length_delimited_from_reader(&mut reader, 1024).unwrap() {
+//!     values.push(value);
+//! }
+//! assert_eq!(values, [1, 2]);
+//! ```
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::encoding::decode_varint;
+
+/// Reads a length delimiter from the reader.
+///
+/// Returns `None` if the reader is at its end, or an error of kind
+/// [`UnexpectedEof`](ErrorKind::UnexpectedEof) if it ends within the delimiter. An invalid
+/// delimiter is an error of kind [`InvalidData`](ErrorKind::InvalidData).
+///
+/// The reader is read one byte at a time, so it should be buffered.
+pub fn read_length_delimiter(reader: &mut impl Read) -> io::Result<Option<usize>> {
+    let mut bytes = Vec::with_capacity(10);
+    loop {
+        let mut byte = 0;
+        match reader.read(core::slice::from_mut(&mut byte)) {
+            Ok(0) if bytes.is_empty() => return Ok(None),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "stream ended within a length delimiter",
+                ))
+            }
+            Ok(_) => bytes.push(byte),
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+        if byte < 0x80 || bytes.len() == 10 {
+            break;
+        }
+    }
+
+    let len = decode_varint(&mut bytes.as_slice())?;
+    usize::try_from(len).map(Some).map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            "length delimiter exceeds maximum usize value",
+        )
+    })
+}
+
+/// Reads a length delimiter and the bytes of the message following it from the reader.
+///
+/// Returns `None` if the reader is at its end, or an error of kind
+/// [`UnexpectedEof`](ErrorKind::UnexpectedEof) if it ends within the delimiter or the message.
+/// Messages longer than `max_len` bytes are rejected with an error of kind
+/// [`InvalidData`](ErrorKind::InvalidData), without reading them.
+pub fn read_length_delimited(
+    reader: &mut impl Read,
+    max_len: usize,
+) -> io::Result<Option<Vec<u8>>> {
+    let len = match read_length_delimiter(reader)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+    if len > max_len {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "message length {} exceeds the maximum of {} bytes",
+                len, max_len
+            ),
+        ));
+    }
+
+    let mut buf = Vec::with_capacity(len);
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "stream ended within a length-delimited message",
+        ));
+    }
+    Ok(Some(buf))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_messages() {
+        let stream = [0x02, b'h', b'i', 0x00, 0x01, b'!'];
+        let mut reader = &stream[..];
+        assert_eq!(
+            read_length_delimited(&mut reader, 2).unwrap(),
+            Some(b"hi".to_vec())
+        );
+        assert_eq!(
+            read_length_delimited(&mut reader, 2).unwrap(),
+            Some(Vec::new())
+        );
+        assert_eq!(
+            read_length_delimited(&mut reader, 2).unwrap(),
+            Some(b"!".to_vec())
+        );
+        assert_eq!(read_length_delimited(&mut reader, 2).unwrap(), None);
+    }
+
+    #[test]
+    fn read_multi_byte_delimiter() {
+        let mut stream = vec![0xAC, 0x02];
+        stream.extend_from_slice(&[7; 300]);
+        let mut reader = &stream[..];
+        assert_eq!(read_length_delimiter(&mut reader).unwrap(), Some(300));
+        assert_eq!(reader.len(), 300);
+    }
+
+    #[test]
+    fn reject_truncated_streams() {
+        let error = read_length_delimited(&mut &[0x80][..], 10).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        let error = read_length_delimited(&mut &[0x03, 1, 2][..], 10).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn reject_large_messages() {
+        // A length prefix of 2^63, which must not be allocated.
+        let stream = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+        let error = read_length_delimited(&mut &stream[..], 1 << 20).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let mut reader = &[0x03, 1, 2, 3][..];
+        let error = read_length_delimited(&mut reader, 2).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "message length 3 exceeds the maximum of 2 bytes"
+        );
+        // The message is not read.
+        assert_eq!(reader, [1, 2, 3]);
+
+        let invalid = [0xFF; 10];
+        let error = read_length_delimiter(&mut &invalid[..]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
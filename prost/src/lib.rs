@@ -9,20 +9,67 @@ pub extern crate alloc;
 // Re-export the bytes crate for use within derived code.
 pub use bytes;
 
+// Re-export the indexmap crate for use within derived code.
+#[cfg(feature = "indexmap")]
+pub use indexmap;
+
+mod batch;
+mod buffer_pool;
+mod erased;
 mod error;
+mod extensions;
+mod field_iter;
+mod fields;
+#[cfg(target_has_atomic = "ptr")]
+mod interner;
 mod message;
 mod name;
+mod observer;
+mod presence;
+mod trace;
 mod types;
 
+pub mod analyze;
+pub mod buf;
 #[doc(hidden)]
 pub mod encoding;
+pub mod float;
+#[cfg(feature = "instrument")]
+pub mod instrument;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod surgery;
+pub mod testing;
+#[cfg(feature = "text-encoding")]
+pub mod text_encoding;
 
+pub use crate::batch::{
+    decode_many, decode_many_iter, encode_many, encode_many_to_vec, DecodeManyIter,
+};
+#[cfg(feature = "std")]
+pub use crate::buffer_pool::VecPool;
+pub use crate::buffer_pool::{BufferPool, PooledBuf};
 pub use crate::encoding::length_delimiter::{
     decode_length_delimiter, encode_length_delimiter, length_delimiter_len,
 };
-pub use crate::error::{DecodeError, EncodeError, UnknownEnumValue};
+pub use crate::encoding::wire_type::WireType;
+pub use crate::encoding::DecodeContext;
+pub use crate::encoding::DuplicateFieldPolicy;
+pub use crate::encoding::SkipLimits;
+pub use crate::encoding::{decode_key, encode_key, key_len, MAX_TAG, MIN_TAG};
+pub use crate::erased::ErasedMessage;
+pub use crate::error::{DecodeError, DecodeErrorKind, EncodeError, UnknownEnumValue};
+pub use crate::extensions::Extensions;
+pub use crate::field_iter::{FieldIter, RawField};
+pub use crate::fields::{FieldDescriptorMeta, FieldValueRef, FieldValues, MessageFields};
+#[cfg(target_has_atomic = "ptr")]
+pub use crate::interner::Interner;
+#[cfg(all(feature = "std", target_has_atomic = "ptr"))]
+pub use crate::interner::StringInterner;
 pub use crate::message::Message;
 pub use crate::name::Name;
+pub use crate::observer::DecodeObserver;
+pub use crate::presence::PresenceBits;
 
 // See `encoding::DecodeContext` for more info.
 // 100 is the default recursion limit in the C++ implementation.
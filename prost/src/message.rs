@@ -1,25 +1,37 @@
+use alloc::borrow::Cow;
 #[cfg(not(feature = "std"))]
 use alloc::boxed::Box;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
 use core::fmt::Debug;
 
-use bytes::{Buf, BufMut};
+use crate::buf::{Buf, BufMut, Chunks};
 
+use crate::buffer_pool::{BufferPool, PooledBuf};
+use crate::encoding::varint::decode_varint;
 use crate::encoding::varint::{encode_varint, encoded_len_varint};
 use crate::encoding::wire_type::WireType;
-use crate::encoding::{decode_key, message, DecodeContext};
+use crate::encoding::{decode_key, merge_message_field, message, DecodeContext};
+#[cfg(feature = "instrument")]
+use crate::instrument::Span;
+use crate::trace;
 use crate::DecodeError;
+use crate::DecodeObserver;
 use crate::EncodeError;
 
 /// A Protocol Buffers message.
 pub trait Message: Debug + Send + Sync {
     /// Encodes the message to a buffer.
     ///
-    /// This method will panic if the buffer has insufficient capacity.
+    /// This method will panic if the buffer has insufficient capacity. The encoding methods
+    /// check the capacity of the buffer first and return an [`EncodeError`] instead, so they
+    /// should be used outside of `Message` implementations.
     ///
-    /// Meant to be used only by `Message` implementations.
+    /// Meant to be used only by `Message` implementations, which must write exactly
+    /// [`encoded_len`](Message::encoded_len) bytes.
     #[doc(hidden)]
     fn encode_raw(&self, buf: &mut impl BufMut)
     where
@@ -39,9 +51,52 @@ pub trait Message: Debug + Send + Sync {
     where
         Self: Sized;
 
+    /// Returns `true` if the field with the given tag is a singular field of the message, to which
+    /// the [`DuplicateFieldPolicy`](crate::DuplicateFieldPolicy) of the decode context applies.
+    ///
+    /// Meant to be used only by `Message` implementations. The default implementation returns
+    /// `false`, so that every occurrence of a field is merged.
+    #[doc(hidden)]
+    fn is_singular_field(tag: u32) -> bool
+    where
+        Self: Sized,
+    {
+        let _ = tag;
+        false
+    }
+
     /// Returns the encoded length of the message without a length delimiter.
     fn encoded_len(&self) -> usize;
 
+    /// Returns an upper bound of the encoded length of the message without a length delimiter.
+    ///
+    /// Derived implementations use a constant bound for singular numeric fields, and only
+    /// compute the exact length of variable length fields such as strings, bytes and repeated
+    /// fields. This makes the bound cheaper to compute than [`encoded_len`](Message::encoded_len)
+    /// when pre-allocating buffers.
+    ///
+    /// The default implementation returns the exact encoded length.
+    fn encoded_len_upper_bound(&self) -> usize {
+        self.encoded_len()
+    }
+
+    /// Returns `true` if the message is equal to `other`, treating `NaN` float and double values
+    /// as equal to each other.
+    ///
+    /// Unlike `PartialEq`, this makes messages containing `NaN` values equal to themselves, for
+    /// example to check that they round-trip in tests, or to deduplicate them. Map fields are
+    /// equal if they have the same entries, whatever their order. Other values are compared as
+    /// with `PartialEq`, so `0.0` and `-0.0` are equal.
+    ///
+    /// The default implementation compares the encodings of the messages, which is only
+    /// equivalent for messages without map fields. Derived implementations compare the fields.
+    fn eq_semantically(&self, other: &Self) -> bool
+    where
+        Self: Sized,
+    {
+        self.encode_to_vec() == other.encode_to_vec()
+    }
+
     /// Encodes the message to a buffer.
     ///
     /// An error will be returned if the buffer does not have sufficient capacity.
@@ -49,24 +104,70 @@ pub trait Message: Debug + Send + Sync {
     where
         Self: Sized,
     {
+        #[cfg(feature = "instrument")]
+        let span = Span::encode::<Self>();
         let required = self.encoded_len();
         let remaining = buf.remaining_mut();
         if required > remaining {
+            #[cfg(feature = "instrument")]
+            span.finish(0, false);
             return Err(EncodeError::new(required, remaining));
         }
 
-        self.encode_raw(buf);
+        trace::encode::<Self, _>(required, || self.encode_raw(buf));
+        #[cfg(feature = "instrument")]
+        span.finish(required, true);
         Ok(())
     }
 
+    /// Encodes the message to the start of a byte slice, and returns the number of bytes
+    /// written.
+    ///
+    /// This is meant for fixed-size buffers, such as on embedded targets. An error is returned
+    /// and the slice is left unchanged if it is too short for the message.
+    fn encode_into_slice(&self, buf: &mut [u8]) -> Result<usize, EncodeError>
+    where
+        Self: Sized,
+    {
+        let len = buf.len();
+        let mut slice = buf;
+        self.encode(&mut slice)?;
+        Ok(len - slice.len())
+    }
+
     /// Encodes the message to a newly allocated buffer.
     fn encode_to_vec(&self) -> Vec<u8>
     where
         Self: Sized,
     {
-        let mut buf = Vec::with_capacity(self.encoded_len());
+        #[cfg(feature = "instrument")]
+        let span = Span::encode::<Self>();
+        let len = self.encoded_len();
+        let mut buf = Vec::with_capacity(len);
 
-        self.encode_raw(&mut buf);
+        trace::encode::<Self, _>(len, || self.encode_raw(&mut buf));
+        #[cfg(feature = "instrument")]
+        span.finish(buf.len(), true);
+        buf
+    }
+
+    /// Encodes the message to a buffer taken from `pool`.
+    ///
+    /// The buffer is returned to the pool when the [`PooledBuf`] is dropped, so high throughput
+    /// encoders can reuse allocations instead of allocating a new `Vec` per message.
+    fn encode_to_vec_in<'a, P>(&self, pool: &'a P) -> PooledBuf<'a, P>
+    where
+        Self: Sized,
+        P: BufferPool + ?Sized,
+    {
+        #[cfg(feature = "instrument")]
+        let span = Span::encode::<Self>();
+        let len = self.encoded_len();
+        let mut buf = PooledBuf::new(pool, len);
+
+        trace::encode::<Self, _>(len, || self.encode_raw(&mut *buf));
+        #[cfg(feature = "instrument")]
+        span.finish(buf.len(), true);
         buf
     }
 
@@ -77,14 +178,22 @@ pub trait Message: Debug + Send + Sync {
     where
         Self: Sized,
     {
+        #[cfg(feature = "instrument")]
+        let span = Span::encode::<Self>();
         let len = self.encoded_len();
         let required = len + encoded_len_varint(len as u64);
         let remaining = buf.remaining_mut();
         if required > remaining {
+            #[cfg(feature = "instrument")]
+            span.finish(0, false);
             return Err(EncodeError::new(required, remaining));
         }
-        encode_varint(len as u64, buf);
-        self.encode_raw(buf);
+        trace::encode::<Self, _>(required, || {
+            encode_varint(len as u64, buf);
+            self.encode_raw(buf);
+        });
+        #[cfg(feature = "instrument")]
+        span.finish(required, true);
         Ok(())
     }
 
@@ -93,23 +202,43 @@ pub trait Message: Debug + Send + Sync {
     where
         Self: Sized,
     {
+        #[cfg(feature = "instrument")]
+        let span = Span::encode::<Self>();
         let len = self.encoded_len();
-        let mut buf = Vec::with_capacity(len + encoded_len_varint(len as u64));
+        let required = len + encoded_len_varint(len as u64);
+        let mut buf = Vec::with_capacity(required);
 
-        encode_varint(len as u64, &mut buf);
-        self.encode_raw(&mut buf);
+        trace::encode::<Self, _>(required, || {
+            encode_varint(len as u64, &mut buf);
+            self.encode_raw(&mut buf);
+        });
+        #[cfg(feature = "instrument")]
+        span.finish(buf.len(), true);
         buf
     }
 
     /// Decodes an instance of the message from a buffer.
     ///
     /// The entire buffer will be consumed.
-    fn decode(mut buf: impl Buf) -> Result<Self, DecodeError>
+    fn decode(buf: impl Buf) -> Result<Self, DecodeError>
     where
         Self: Default,
     {
         let mut message = Self::default();
-        Self::merge(&mut message, &mut buf).map(|_| message)
+        Self::merge(&mut message, buf).map(|_| message)
+    }
+
+    /// Decodes an instance of the message from a sequence of byte slices, such as the segments
+    /// of a rope buffer, as if they were concatenated.
+    ///
+    /// The slices are not copied into a contiguous buffer first; fields which are split across
+    /// slices are decoded directly from them.
+    fn decode_from_chunks<T>(chunks: &[T]) -> Result<Self, DecodeError>
+    where
+        T: AsRef<[u8]>,
+        Self: Default,
+    {
+        Self::decode(Chunks::new(chunks))
     }
 
     /// Decodes a length-delimited instance of the message from the buffer.
@@ -122,6 +251,25 @@ pub trait Message: Debug + Send + Sync {
         Ok(message)
     }
 
+    /// Reads and decodes one length-delimited instance of the message from the reader.
+    ///
+    /// Returns `None` if the reader is at its end. Messages longer than `max_len` bytes are
+    /// rejected without being read, and the reader is not read past the end of the message.
+    /// See [`io::read_length_delimited`](crate::io::read_length_delimited) for the errors.
+    #[cfg(feature = "std")]
+    fn decode_length_delimited_from_reader(
+        reader: &mut impl std::io::Read,
+        max_len: usize,
+    ) -> std::io::Result<Option<Self>>
+    where
+        Self: Default,
+    {
+        match crate::io::read_length_delimited(reader, max_len)? {
+            Some(buf) => Ok(Some(Self::decode(buf.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
     /// Decodes an instance of the message from a buffer, and merges it into `self`.
     ///
     /// The entire buffer will be consumed.
@@ -129,12 +277,29 @@ pub trait Message: Debug + Send + Sync {
     where
         Self: Sized,
     {
-        let ctx = DecodeContext::default();
-        while buf.has_remaining() {
-            let (tag, wire_type) = decode_key(&mut buf)?;
-            self.merge_field(tag, wire_type, &mut buf, ctx.clone())?;
-        }
-        Ok(())
+        #[cfg(feature = "instrument")]
+        let (span, remaining) = (Span::decode::<Self>(), buf.remaining());
+        let result = trace::decode::<Self, _, _>(&mut buf, |buf| {
+            merge_fields(self, buf, DecodeContext::default())
+        });
+        #[cfg(feature = "instrument")]
+        span.finish(remaining - buf.remaining(), result.is_ok());
+        result
+    }
+
+    /// Clears the message, and decodes an instance of the message from a buffer into it.
+    ///
+    /// This is equivalent to [`Message::decode`], but reuses the allocations of `self`, which
+    /// makes it the preferred way to decode many messages of the same type. See
+    /// [`Message::clear`] for the allocations which are kept.
+    ///
+    /// If decoding fails, `self` contains the fields which were decoded before the error.
+    fn clear_and_merge(&mut self, buf: impl Buf) -> Result<(), DecodeError>
+    where
+        Self: Sized,
+    {
+        self.clear();
+        self.merge(buf)
     }
 
     /// Decodes a length-delimited instance of the message from buffer, and
@@ -143,18 +308,221 @@ pub trait Message: Debug + Send + Sync {
     where
         Self: Sized,
     {
-        message::merge(
+        #[cfg(feature = "instrument")]
+        let (span, remaining) = (Span::decode::<Self>(), buf.remaining());
+        let result = message::merge(
             WireType::LengthDelimited,
             self,
             &mut buf,
             DecodeContext::default(),
-        )
+        );
+        #[cfg(feature = "instrument")]
+        span.finish(remaining - buf.remaining(), result.is_ok());
+        result
+    }
+
+    /// Decodes an instance of the message from a buffer, with the given context.
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_with_context(buf: impl Buf, ctx: DecodeContext) -> Result<Self, DecodeError>
+    where
+        Self: Default,
+    {
+        let mut message = Self::default();
+        message.merge_with_context(buf, ctx).map(|_| message)
+    }
+
+    /// Decodes an instance of the message from a buffer, and merges it into `self`, with the given
+    /// context.
+    ///
+    /// The context can carry an observer and [`Extensions`](crate::Extensions) shared with the
+    /// decode functions of all fields, including those of nested messages.
+    ///
+    /// The entire buffer will be consumed.
+    fn merge_with_context(
+        &mut self,
+        mut buf: impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        Self: Sized,
+    {
+        #[cfg(feature = "instrument")]
+        let (span, remaining) = (Span::decode::<Self>(), buf.remaining());
+        let ctx = ctx.start(&buf);
+        let result = trace::decode::<Self, _, _>(&mut buf, |buf| merge_fields(self, buf, ctx));
+        #[cfg(feature = "instrument")]
+        span.finish(remaining - buf.remaining(), result.is_ok());
+        result
+    }
+
+    /// Decodes an instance of the message from a buffer, notifying `observer` about anomalies
+    /// tolerated by the decoder.
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_with_observer(
+        buf: impl Buf,
+        observer: &dyn DecodeObserver,
+    ) -> Result<Self, DecodeError>
+    where
+        Self: Default,
+    {
+        Self::decode_with_context(buf, DecodeContext::new().with_observer(observer))
+    }
+
+    /// Decodes an instance of the message from a buffer, and merges it into `self`, notifying
+    /// `observer` about anomalies tolerated by the decoder.
+    ///
+    /// The entire buffer will be consumed.
+    fn merge_with_observer(
+        &mut self,
+        buf: impl Buf,
+        observer: &dyn DecodeObserver,
+    ) -> Result<(), DecodeError>
+    where
+        Self: Sized,
+    {
+        self.merge_with_context(buf, DecodeContext::new().with_observer(observer))
+    }
+
+    /// Decodes an instance of the message from the complete fields at the start of a buffer,
+    /// returning it with the number of bytes consumed.
+    ///
+    /// Decoding stops at the first field which is cut off by the end of the buffer, such as the
+    /// last record of an append-only log which is still being written, instead of failing. The
+    /// fields of the returned message are those which fit in the consumed bytes; the rest of the
+    /// buffer can be decoded again once more data is available. Invalid fields are errors, as
+    /// with [`Message::decode`].
+    ///
+    /// ```rust
+    /// # use prost::Message;
+    /// let encoded = String::from("hello").encode_to_vec();
+    /// let (value, consumed) = String::decode_partial(&encoded[..4]).unwrap();
+    /// assert_eq!((value.as_str(), consumed), ("", 0));
+    /// let (value, consumed) = String::decode_partial(&encoded).unwrap();
+    /// assert_eq!((value.as_str(), consumed), ("hello", encoded.len()));
+    /// ```
+    fn decode_partial(buf: &[u8]) -> Result<(Self, usize), DecodeError>
+    where
+        Self: Default,
+    {
+        let mut message = Self::default();
+        let mut seen = Vec::new();
+        let mut consumed = 0;
+        while let Some(len) = complete_field_len(&buf[consumed..], GROUP_DEPTH_LIMIT)? {
+            let mut field = &buf[consumed..consumed + len];
+            let (tag, wire_type) = decode_key(&mut field)?;
+            merge_message_field(
+                &mut message,
+                tag,
+                wire_type,
+                &mut field,
+                DecodeContext::default(),
+                &mut seen,
+            )?;
+            consumed += len;
+        }
+        Ok((message, consumed))
     }
 
     /// Clears the message, resetting all fields to their default.
+    ///
+    /// Derived implementations keep the capacity of strings, bytes, repeated fields and
+    /// `HashMap` fields, so that the message can be reused with [`Message::clear_and_merge`]
+    /// without allocating again. Optional, oneof and `BTreeMap` fields release their contents.
     fn clear(&mut self);
 }
 
+fn merge_fields<M>(
+    message: &mut M,
+    buf: &mut impl Buf,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError>
+where
+    M: Message,
+{
+    let mut seen = Vec::new();
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(buf)?;
+        merge_message_field(message, tag, wire_type, buf, ctx.clone(), &mut seen)?;
+    }
+    Ok(())
+}
+
+/// The nesting limit of the groups measured by `complete_field_len`, which matches the recursion
+/// limit of decoding.
+#[cfg(not(feature = "no-recursion-limit"))]
+const GROUP_DEPTH_LIMIT: u32 = crate::RECURSION_LIMIT;
+#[cfg(feature = "no-recursion-limit")]
+const GROUP_DEPTH_LIMIT: u32 = u32::MAX;
+
+/// Returns the encoded length of the field at the start of the buffer, or `None` if the buffer
+/// is empty or ends within the field.
+fn complete_field_len(buf: &[u8], depth: u32) -> Result<Option<usize>, DecodeError> {
+    let Some(key_len) = varint_len(buf) else {
+        return Ok(None);
+    };
+    let (tag, wire_type) = decode_key(&mut &buf[..key_len])?;
+    let value = &buf[key_len..];
+    let value_len = match wire_type {
+        WireType::Varint => varint_len(value),
+        WireType::ThirtyTwoBit => Some(4),
+        WireType::SixtyFourBit => Some(8),
+        WireType::LengthDelimited => match varint_len(value) {
+            Some(prefix_len) => {
+                let len = decode_varint(&mut &value[..prefix_len])?;
+                Some(usize::try_from(len).map_or(usize::MAX, |len| len.saturating_add(prefix_len)))
+            }
+            None => None,
+        },
+        WireType::StartGroup => {
+            let depth = depth
+                .checked_sub(1)
+                .ok_or_else(|| DecodeError::new("recursion limit reached"))?;
+            complete_group_len(tag, value, depth)?
+        }
+        WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+    };
+    Ok(value_len
+        .map(|len| len.saturating_add(key_len))
+        .filter(|&len| len <= buf.len()))
+}
+
+/// Returns the encoded length of the fields of a group and its end group key, or `None` if the
+/// buffer ends within them.
+fn complete_group_len(tag: u32, buf: &[u8], depth: u32) -> Result<Option<usize>, DecodeError> {
+    let mut len = 0;
+    loop {
+        let rest = &buf[len..];
+        let Some(key_len) = varint_len(rest) else {
+            return Ok(None);
+        };
+        let (inner_tag, inner_wire_type) = decode_key(&mut &rest[..key_len])?;
+        if inner_wire_type == WireType::EndGroup {
+            if inner_tag != tag {
+                return Err(DecodeError::new("unexpected end group tag"));
+            }
+            return Ok(Some(len + key_len));
+        }
+        match complete_field_len(rest, depth)? {
+            Some(field_len) => len += field_len,
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Returns the length of the varint at the start of the buffer, or `None` if the buffer ends
+/// within it.
+///
+/// Varints longer than ten bytes are reported as ten bytes long, and rejected when decoded.
+fn varint_len(buf: &[u8]) -> Option<usize> {
+    match buf.iter().take(10).position(|&byte| byte < 0x80) {
+        Some(i) => Some(i + 1),
+        None if buf.len() < 10 => None,
+        None => Some(10),
+    }
+}
+
 impl<M> Message for Box<M>
 where
     M: Message,
@@ -171,17 +539,111 @@ where
     ) -> Result<(), DecodeError> {
         (**self).merge_field(tag, wire_type, buf, ctx)
     }
+    fn is_singular_field(tag: u32) -> bool {
+        M::is_singular_field(tag)
+    }
     fn encoded_len(&self) -> usize {
         (**self).encoded_len()
     }
+    fn encoded_len_upper_bound(&self) -> usize {
+        (**self).encoded_len_upper_bound()
+    }
+    fn eq_semantically(&self, other: &Self) -> bool {
+        (**self).eq_semantically(other)
+    }
     fn clear(&mut self) {
         (**self).clear()
     }
 }
 
+#[cfg(target_has_atomic = "ptr")]
+impl<M> Message for Arc<M>
+where
+    M: Message + Clone,
+{
+    fn encode_raw(&self, buf: &mut impl BufMut) {
+        (**self).encode_raw(buf)
+    }
+    fn merge_field(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        Arc::make_mut(self).merge_field(tag, wire_type, buf, ctx)
+    }
+    fn is_singular_field(tag: u32) -> bool {
+        M::is_singular_field(tag)
+    }
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+    fn encoded_len_upper_bound(&self) -> usize {
+        (**self).encoded_len_upper_bound()
+    }
+    fn eq_semantically(&self, other: &Self) -> bool {
+        (**self).eq_semantically(other)
+    }
+    fn clear(&mut self) {
+        Arc::make_mut(self).clear()
+    }
+}
+
+impl<M> Message for Cow<'static, M>
+where
+    M: Message + Clone,
+{
+    fn encode_raw(&self, buf: &mut impl BufMut) {
+        (**self).encode_raw(buf)
+    }
+    fn merge_field(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        self.to_mut().merge_field(tag, wire_type, buf, ctx)
+    }
+    fn is_singular_field(tag: u32) -> bool {
+        M::is_singular_field(tag)
+    }
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+    fn encoded_len_upper_bound(&self) -> usize {
+        (**self).encoded_len_upper_bound()
+    }
+    fn eq_semantically(&self, other: &Self) -> bool {
+        (**self).eq_semantically(other)
+    }
+    fn clear(&mut self) {
+        self.to_mut().clear()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     const _MESSAGE_IS_OBJECT_SAFE: Option<&dyn Message> = None;
+
+    #[test]
+    fn complete_field_len_of_truncated_fields() {
+        // Field 1, varint 300.
+        assert_eq!(complete_field_len(&[0x08, 0xac, 0x02], 1).unwrap(), Some(3));
+        assert_eq!(complete_field_len(&[0x08, 0xac], 1).unwrap(), None);
+        assert_eq!(complete_field_len(&[], 1).unwrap(), None);
+        // Field 1, length-delimited "ab".
+        assert_eq!(complete_field_len(b"\x0a\x02ab", 1).unwrap(), Some(4));
+        assert_eq!(complete_field_len(b"\x0a\x02a", 1).unwrap(), None);
+        // Group 1 holding field 2, fixed32.
+        let group = [0x0b, 0x15, 1, 2, 3, 4, 0x0c];
+        assert_eq!(complete_field_len(&group, 1).unwrap(), Some(7));
+        assert_eq!(complete_field_len(&group[..6], 1).unwrap(), None);
+        assert!(complete_field_len(&group, 0).is_err());
+        // Invalid wire type.
+        assert!(complete_field_len(&[0x0e], 1).is_err());
+    }
 }
@@ -0,0 +1,91 @@
+//! Observation of anomalies tolerated while decoding.
+
+use crate::encoding::WireType;
+
+/// Receives notifications about input accepted by the decoder despite not matching the message
+/// definition exactly.
+///
+/// Such input is not an error, so it is silently tolerated by [`Message::decode`]. Decoding with
+/// [`Message::decode_with_observer`] or [`Message::merge_with_observer`] instead reports it to an
+/// observer, for example to monitor the quality of data produced by other services.
+///
+/// Offsets are in bytes from the start of the buffer passed to the decode method. All methods do
+/// nothing by default.
+///
+/// Observers are `Send` and `Sync`, like the [`DecodeContext`] holding them, so observers which
+/// record what they are notified about need a lock or atomics rather than a `RefCell`.
+///
+/// [`Message::decode`]: crate::Message::decode
+/// [`Message::decode_with_observer`]: crate::Message::decode_with_observer
+/// [`Message::merge_with_observer`]: crate::Message::merge_with_observer
+/// [`DecodeContext`]: crate::DecodeContext
+pub trait DecodeObserver: Send + Sync {
+    /// Called when a field with a tag unknown to the message is skipped.
+    ///
+    /// `offset` is the position of the field's value, just after its key. Fields of unknown groups
+    /// are skipped along with the group, and are not reported separately.
+    fn unknown_field(&self, tag: u32, wire_type: WireType, offset: usize) {
+        let _ = (tag, wire_type, offset);
+    }
+
+    /// Called when a known field is decoded from a wire type other than the one of its
+    /// declaration, which currently happens for repeated scalar fields using the packed encoding
+    /// when declared unpacked, or the other way around.
+    ///
+    /// `offset` is the position of the field's value, just after its key.
+    fn unexpected_wire_type(&self, tag: u32, wire_type: WireType, offset: usize) {
+        let _ = (tag, wire_type, offset);
+    }
+
+    /// Called when a field of an open enum type is decoded with a value the enum does not
+    /// declare, such as a value added to the enum by a newer version of the sender.
+    ///
+    /// The value is stored in the field as usual. `message` and `field` are the Rust names of the
+    /// message and of the field, or of the oneof and of its variant. `offset` is the position of
+    /// the field's value, just after its key, which may be the start of all the values of a packed
+    /// field. Values of closed enums are reported as unknown fields instead, and map values are
+    /// not reported.
+    fn unknown_enum_value(
+        &self,
+        message: &'static str,
+        field: &'static str,
+        value: i32,
+        offset: usize,
+    ) {
+        let _ = (message, field, value, offset);
+    }
+
+    /// Called when the buffer ends inside the group with the given tag.
+    ///
+    /// The decode fails right after this notification.
+    fn group_eof(&self, tag: u32, offset: usize) {
+        let _ = (tag, offset);
+    }
+}
+
+impl<O> DecodeObserver for &O
+where
+    O: DecodeObserver + ?Sized,
+{
+    fn unknown_field(&self, tag: u32, wire_type: WireType, offset: usize) {
+        (**self).unknown_field(tag, wire_type, offset)
+    }
+
+    fn unexpected_wire_type(&self, tag: u32, wire_type: WireType, offset: usize) {
+        (**self).unexpected_wire_type(tag, wire_type, offset)
+    }
+
+    fn unknown_enum_value(
+        &self,
+        message: &'static str,
+        field: &'static str,
+        value: i32,
+        offset: usize,
+    ) {
+        (**self).unknown_enum_value(message, field, value, offset)
+    }
+
+    fn group_eof(&self, tag: u32, offset: usize) {
+        (**self).group_eof(tag, offset)
+    }
+}
@@ -0,0 +1,72 @@
+//! A compact bitset tracking the presence of optional scalar fields.
+
+/// The presence bits of the optional scalar fields of a message which are stored inline, rather
+/// than as an `Option`.
+///
+/// Each field is assigned a bit, which is set when the field is set and cleared when it is
+/// unset. The values of unset fields are their defaults. `WORDS` is the number of 32-bit words
+/// needed to hold a bit for each field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PresenceBits<const WORDS: usize>([u32; WORDS]);
+
+impl<const WORDS: usize> PresenceBits<WORDS> {
+    /// Creates a bitset with no bits set.
+    pub const fn new() -> Self {
+        Self([0; WORDS])
+    }
+
+    /// Returns whether the bit is set.
+    #[inline]
+    pub fn get(&self, bit: u32) -> bool {
+        self.0[(bit / 32) as usize] & (1 << (bit % 32)) != 0
+    }
+
+    /// Sets the bit.
+    #[inline]
+    pub fn set(&mut self, bit: u32) {
+        self.0[(bit / 32) as usize] |= 1 << (bit % 32);
+    }
+
+    /// Clears the bit.
+    #[inline]
+    pub fn clear(&mut self, bit: u32) {
+        self.0[(bit / 32) as usize] &= !(1 << (bit % 32));
+    }
+
+    /// Returns whether no bits are set.
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+}
+
+impl<const WORDS: usize> Default for PresenceBits<WORDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_clear_bits() {
+        let mut bits = PresenceBits::<2>::new();
+        assert!(bits.is_empty());
+
+        bits.set(3);
+        bits.set(40);
+        assert!(bits.get(3));
+        assert!(bits.get(40));
+        assert!(!bits.get(8));
+        assert!(!bits.get(35));
+
+        bits.clear(3);
+        assert!(!bits.get(3));
+        assert!(bits.get(40));
+        assert!(!bits.is_empty());
+
+        bits.clear(40);
+        assert_eq!(bits, PresenceBits::default());
+    }
+}
@@ -0,0 +1,104 @@
+//! Reading and rewriting individual top-level fields of encoded messages.
+//!
+//! These functions locate fields by scanning the keys of an encoded message and skipping over
+//! their values, without decoding the message. They are useful when only a few fields of a large
+//! message are of interest, for example to rewrite a routing field in a proxy.
+//!
+//! Values are exchanged in their encoded form, and can be produced and consumed with the
+//! functions of the [`encoding`](crate::encoding) module, or with the [`Message`] trait for
+//! nested messages.
+//!
+//! ```rust
+//! use prost::encoding::{string, DecodeContext};
+//! use prost::surgery;
+//!
+//! // A message with a `string` field 1 and an `int32` field 2.
+//! let mut buf = vec![0x0a, 0x03, b'o', b'l', b'd', 0x10, 0x2a];
+//!
+//! let mut field = Vec::new();
+//! string::encode(1, &String::from("new"), &mut field);
+//! surgery::replace_field(&mut buf, 1, &field).unwrap();
+//! assert_eq!(buf, [0x0a, 0x03, b'n', b'e', b'w', 0x10, 0x2a]);
+//!
+//! let (wire_type, mut value) = surgery::read_field(&buf, 1).unwrap().unwrap();
+//! let mut route = String::new();
+//! string::merge(wire_type, &mut route, &mut value, DecodeContext::default()).unwrap();
+//! assert_eq!(route, "new");
+//! ```
+//!
+//! [`Message`]: crate::Message
+
+use alloc::vec::Vec;
+
+use crate::encoding::{decode_key, WireType};
+use crate::{DecodeError, FieldIter};
+
+/// Returns the wire type and the encoded value of the last occurrence of the field with the
+/// given tag, or `None` if the message does not contain the field.
+///
+/// The value starts just after the key of the field: it includes the length prefix of length
+/// delimited values, and the end key of groups, so that it can be passed to the `merge`
+/// functions of the [`encoding`](crate::encoding) module. See [`FieldIter`] to iterate over all
+/// fields with their decoded value slices instead.
+///
+/// Decoders keep the last occurrence of scalar fields, but merge all occurrences of message
+/// fields, and append the values of all occurrences of repeated fields.
+pub fn read_field(buf: &[u8], tag: u32) -> Result<Option<(WireType, &[u8])>, DecodeError> {
+    let mut last = None;
+    for field in FieldIter::new(buf) {
+        let field = field?;
+        if field.tag == tag {
+            last = Some(field);
+        }
+    }
+    last.map(|field| {
+        let mut value = field.encoded;
+        decode_key(&mut value)?;
+        Ok((field.wire_type, value))
+    })
+    .transpose()
+}
+
+/// Replaces all occurrences of the field with the given tag by `field`.
+///
+/// `field` holds the encoded replacement, keys included, as written by the `encode` functions
+/// of the [`encoding`](crate::encoding) module. It may hold several occurrences, for example the
+/// elements of a repeated field, but all must have the given tag. It is written at the position
+/// of the first occurrence of the field, or appended if the message does not contain the field.
+/// Other fields are left untouched.
+///
+/// Fails without modifying the message if either `buf` or `field` can not be scanned.
+pub fn replace_field(buf: &mut Vec<u8>, tag: u32, field: &[u8]) -> Result<(), DecodeError> {
+    for replacement in FieldIter::new(field) {
+        if replacement?.tag != tag {
+            return Err(DecodeError::new("replacement field has a different tag"));
+        }
+    }
+
+    let mut occurrences = Vec::new();
+    for existing in FieldIter::new(buf) {
+        let existing = existing?;
+        if existing.tag == tag {
+            occurrences.push(existing.offset..existing.offset + existing.encoded.len());
+        }
+    }
+
+    match occurrences.split_first() {
+        None => buf.extend_from_slice(field),
+        Some((first, others)) => {
+            // Remove from the end, so that the ranges of the earlier occurrences stay valid.
+            for range in others.iter().rev() {
+                buf.drain(range.clone());
+            }
+            buf.splice(first.clone(), field.iter().copied());
+        }
+    }
+    Ok(())
+}
+
+/// Removes all occurrences of the field with the given tag.
+///
+/// Fails without modifying the message if `buf` can not be scanned.
+pub fn remove_field(buf: &mut Vec<u8>, tag: u32) -> Result<(), DecodeError> {
+    replace_field(buf, tag, &[])
+}
@@ -0,0 +1,165 @@
+//! Assertions for the test suites of applications using generated messages.
+//!
+//! [`assert_roundtrip!`](crate::assert_roundtrip) checks that a message encodes and decodes
+//! consistently, and [`assert_wire_compat!`](crate::assert_wire_compat) that a new version of a
+//! message type keeps the data written by an old version, so that schema changes such as
+//! renumbered fields or incompatible type changes are caught by tests rather than in production.
+
+use alloc::vec::Vec;
+
+use crate::Message;
+
+/// Asserts that the message encodes and decodes to itself.
+///
+/// The encoding must have the length returned by [`Message::encoded_len`], at most
+/// [`Message::encoded_len_upper_bound`], and decode to a message equal to the original, with the
+/// same encoded length. The encoding of the decoded message is not compared to the original one,
+/// as `HashMap` fields encode their entries in an unspecified order.
+///
+/// See [`assert_roundtrip!`](crate::assert_roundtrip).
+#[track_caller]
+pub fn assert_roundtrip<M>(message: &M)
+where
+    M: Message + Default + PartialEq,
+{
+    let encoded = message.encode_to_vec();
+    assert_eq!(
+        message.encoded_len(),
+        encoded.len(),
+        "encoded length of {:?} does not match its encoding",
+        message
+    );
+    assert!(
+        encoded.len() <= message.encoded_len_upper_bound(),
+        "encoded length of {:?} ({}) exceeds its upper bound ({})",
+        message,
+        encoded.len(),
+        message.encoded_len_upper_bound()
+    );
+    let decoded = match M::decode(encoded.as_slice()) {
+        Ok(decoded) => decoded,
+        Err(error) => panic!("failed to decode {:?}: {}", message, error),
+    };
+    assert_eq!(&decoded, message, "message does not roundtrip");
+    assert_eq!(
+        decoded.encoded_len(),
+        encoded.len(),
+        "decoded message {:?} does not have the encoded length of the original",
+        decoded
+    );
+}
+
+/// Asserts that the `New` message type decodes the encoding of the `Old` message, and keeps all
+/// its data.
+///
+/// The sample is encoded, decoded as `New`, encoded again, and decoded as `Old`, which must give
+/// back the sample. This fails if a field set in the sample was removed or renumbered in `New`,
+/// or changed to a type with another wire type, such as `string` to `int32`. Changes between
+/// types which read the same encoding differently, such as `int32` to `sint32`, are not detected.
+///
+/// See [`assert_wire_compat!`](crate::assert_wire_compat).
+#[track_caller]
+pub fn assert_wire_compat<Old, New>(sample: &Old)
+where
+    Old: Message + Default + PartialEq,
+    New: Message + Default,
+{
+    let encoded = sample.encode_to_vec();
+    let new = match New::decode(encoded.as_slice()) {
+        Ok(new) => new,
+        Err(error) => panic!("new message type fails to decode {:?}: {}", sample, error),
+    };
+    let reencoded: Vec<u8> = new.encode_to_vec();
+    let old = match Old::decode(reencoded.as_slice()) {
+        Ok(old) => old,
+        Err(error) => panic!(
+            "old message type fails to decode {:?}, decoded from {:?}: {}",
+            new, sample, error
+        ),
+    };
+    assert_eq!(
+        &old, sample,
+        "new message type {:?} does not keep the data of the old one",
+        new
+    );
+}
+
+/// Asserts that messages encode and decode to themselves.
+///
+/// See [`testing::assert_roundtrip`](crate::testing::assert_roundtrip) for the checks.
+///
+/// ```rust
+/// prost::assert_roundtrip!(String::from("hello"), 42u32);
+/// ```
+#[macro_export]
+macro_rules! assert_roundtrip {
+    ($($message:expr),+ $(,)?) => {
+        $($crate::testing::assert_roundtrip(&$message);)+
+    };
+}
+
+/// Asserts that a new version of a message type keeps the data of an old version.
+///
+/// Each sample of the old type must be kept by the new type, as checked by
+/// [`testing::assert_wire_compat`](crate::testing::assert_wire_compat). Samples should set the
+/// fields whose compatibility matters, as default values are not encoded. Without samples, the
+/// default value of the old type is checked.
+///
+/// ```rust
+/// // `uint32` fields can be widened to `uint64`.
+/// prost::assert_wire_compat!(u32, u64, 7, u32::MAX);
+/// ```
+///
+/// ```rust,should_panic
+/// // The data of renumbered fields is lost.
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Old {
+///     #[prost(string, tag = "1")]
+///     name: String,
+/// }
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct New {
+///     #[prost(string, tag = "2")]
+///     name: String,
+/// }
+///
+/// prost::assert_wire_compat!(Old, New, Old { name: "a".to_string() });
+/// ```
+#[macro_export]
+macro_rules! assert_wire_compat {
+    ($old:ty, $new:ty $(,)?) => {
+        $crate::testing::assert_wire_compat::<$old, $new>(
+            &<$old as ::core::default::Default>::default(),
+        );
+    };
+    ($old:ty, $new:ty, $($sample:expr),+ $(,)?) => {
+        $($crate::testing::assert_wire_compat::<$old, $new>(&$sample);)+
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn roundtrip() {
+        assert_roundtrip!(String::from("hello"), 0u64, u64::MAX, vec![1u8, 2]);
+    }
+
+    #[test]
+    fn wire_compat() {
+        assert_wire_compat!(u32, u64);
+        assert_wire_compat!(u32, u64, 7, u32::MAX);
+        assert_wire_compat!(String, Vec<u8>, String::from("hello"));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not keep the data of the old one")]
+    fn wire_incompat() {
+        // Values above `u32::MAX` are truncated.
+        assert_wire_compat!(u64, u32, u64::MAX);
+    }
+}
@@ -0,0 +1,205 @@
+//! Base64 and hex encodings of messages, for config files, command line arguments and debugging
+//! tools.
+//!
+//! Base64 uses the standard alphabet of RFC 4648, with padding. Hex uses lowercase digits, and
+//! both cases are accepted when decoding.
+//!
+//! The message is encoded to a buffer as by [`Message::encode_to_vec`], and its text is written
+//! to a [`fmt::Write`] in small chunks, without building an intermediate string.
+//!
+//! ```rust
+//! use prost::text_encoding;
+//!
+//! let encoded = text_encoding::to_base64(&String::from("hello"));
+//! assert_eq!(encoded, "CgVoZWxsbw==");
+//! let decoded: String = text_encoding::decode_from_base64(&encoded).unwrap();
+//! assert_eq!(decoded, "hello");
+//!
+//! assert_eq!(text_encoding::to_hex(&150u32), "089601");
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{DecodeError, Message};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// The number of bytes encoded before each write to the output.
+const CHUNK_LEN: usize = 96;
+
+/// Encodes the message as base64 to `out`.
+pub fn encode_to_base64<M, W>(message: &M, out: &mut W) -> fmt::Result
+where
+    M: Message,
+    W: fmt::Write + ?Sized,
+{
+    let buf = message.encode_to_vec();
+    let mut text = [0; CHUNK_LEN / 3 * 4];
+    for chunk in buf.chunks(CHUNK_LEN) {
+        let mut len = 0;
+        for group in chunk.chunks(3) {
+            let bits = group.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+                bits | u32::from(byte) << (16 - 8 * i)
+            });
+            for i in 0..4 {
+                text[len + i] = if i <= group.len() {
+                    BASE64_ALPHABET[(bits >> (18 - 6 * i)) as usize & 0x3f]
+                } else {
+                    b'='
+                };
+            }
+            len += 4;
+        }
+        out.write_str(ascii(&text[..len]))?;
+    }
+    Ok(())
+}
+
+/// Returns the message encoded as base64.
+pub fn to_base64<M>(message: &M) -> String
+where
+    M: Message,
+{
+    let mut out = String::with_capacity((message.encoded_len() + 2) / 3 * 4);
+    encode_to_base64(message, &mut out).expect("writing to a String does not fail");
+    out
+}
+
+/// Decodes a message from its base64 encoding.
+///
+/// The padding is optional. Whitespace and the characters of the URL-safe alphabet are not
+/// accepted.
+pub fn decode_from_base64<M>(text: &str) -> Result<M, DecodeError>
+where
+    M: Message + Default,
+{
+    let text = text.as_bytes();
+    let unpadded = match text {
+        [rest @ .., b'=', b'='] | [rest @ .., b'='] if text.len() % 4 == 0 => rest,
+        _ => text,
+    };
+    if unpadded.len() % 4 == 1 {
+        return Err(DecodeError::new("invalid base64 length"));
+    }
+
+    let mut buf = Vec::with_capacity(unpadded.len() / 4 * 3 + 2);
+    for group in unpadded.chunks(4) {
+        let mut bits = 0u32;
+        for (i, &c) in group.iter().enumerate() {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return Err(DecodeError::new("invalid base64 character")),
+            };
+            bits |= u32::from(value) << (18 - 6 * i);
+        }
+        buf.extend_from_slice(&bits.to_be_bytes()[1..group.len()]);
+    }
+    M::decode(buf.as_slice())
+}
+
+/// Encodes the message as lowercase hex to `out`.
+pub fn encode_to_hex<M, W>(message: &M, out: &mut W) -> fmt::Result
+where
+    M: Message,
+    W: fmt::Write + ?Sized,
+{
+    let buf = message.encode_to_vec();
+    let mut text = [0; CHUNK_LEN * 2];
+    for chunk in buf.chunks(CHUNK_LEN) {
+        for (i, &byte) in chunk.iter().enumerate() {
+            text[2 * i] = HEX_DIGITS[usize::from(byte >> 4)];
+            text[2 * i + 1] = HEX_DIGITS[usize::from(byte & 0xf)];
+        }
+        out.write_str(ascii(&text[..2 * chunk.len()]))?;
+    }
+    Ok(())
+}
+
+/// Returns the message encoded as lowercase hex.
+pub fn to_hex<M>(message: &M) -> String
+where
+    M: Message,
+{
+    let mut out = String::with_capacity(message.encoded_len() * 2);
+    encode_to_hex(message, &mut out).expect("writing to a String does not fail");
+    out
+}
+
+/// Decodes a message from its hex encoding, in either case.
+pub fn decode_from_hex<M>(text: &str) -> Result<M, DecodeError>
+where
+    M: Message + Default,
+{
+    let text = text.as_bytes();
+    if text.len() % 2 != 0 {
+        return Err(DecodeError::new("invalid hex length"));
+    }
+
+    let digit = |c: u8| match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(DecodeError::new("invalid hex character")),
+    };
+    let buf = text
+        .chunks(2)
+        .map(|pair| Ok(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect::<Result<Vec<u8>, DecodeError>>()?;
+    M::decode(buf.as_slice())
+}
+
+fn ascii(text: &[u8]) -> &str {
+    core::str::from_utf8(text).expect("encoded text is ASCII")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    #[test]
+    fn base64() {
+        // Messages of every length modulo 3, so with every amount of padding.
+        for len in 0..8 {
+            let bytes = (0..len).map(|i| 0xf0 + i).collect::<Vec<u8>>();
+            let encoded = to_base64(&bytes);
+            assert_eq!(encoded.len(), (bytes.encoded_len() + 2) / 3 * 4);
+            assert_eq!(decode_from_base64::<Vec<u8>>(&encoded).unwrap(), bytes);
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(decode_from_base64::<Vec<u8>>(unpadded).unwrap(), bytes);
+        }
+        assert_eq!(to_base64(&vec![0xfb, 0xff]), "CgL7/w==");
+    }
+
+    #[test]
+    fn base64_errors() {
+        for text in ["C", "CgVoZ", "Cg-o", "Cg==Cg==", "Cg= ", "Cg==="] {
+            assert!(decode_from_base64::<Vec<u8>>(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn hex() {
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        let encoded = to_hex(&bytes);
+        assert!(encoded.starts_with("0a8002000102"));
+        assert_eq!(decode_from_hex::<Vec<u8>>(&encoded).unwrap(), bytes);
+        assert_eq!(
+            decode_from_hex::<Vec<u8>>(&encoded.to_uppercase()).unwrap(),
+            bytes
+        );
+        for text in ["0", "0g", "0a 1"] {
+            assert!(decode_from_hex::<Vec<u8>>(text).is_err(), "{}", text);
+        }
+    }
+}
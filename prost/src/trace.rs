@@ -0,0 +1,242 @@
+//! Spans of the `tracing` crate around message encoding and decoding.
+//!
+//! Every message encoded or decoded, including nested messages, runs in a `TRACE` level span
+//! named `encode` or `decode`, with the type name of the message in the `message` field and the
+//! number of bytes written or read in the `len` field. The spans nest like the messages, so that
+//! the time spent in each nested type can be told apart.
+//!
+//! Without the `tracing` feature, the functions of this module only run the operation.
+
+use crate::buf::Buf;
+
+/// Runs `f`, which encodes a message of type `M` to `len` bytes, in an `encode` span.
+#[inline(always)]
+#[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+pub(crate) fn encode<M, R>(len: usize, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "tracing")]
+    let _span =
+        tracing::trace_span!("encode", message = core::any::type_name::<M>(), len).entered();
+    #[cfg(not(feature = "tracing"))]
+    let _ = len;
+    f()
+}
+
+/// Runs `f`, which decodes a message of type `M` from `buf`, in a `decode` span recording the
+/// number of bytes read.
+#[inline(always)]
+#[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+pub(crate) fn decode<M, B, R>(buf: &mut B, f: impl FnOnce(&mut B) -> R) -> R
+where
+    B: Buf + ?Sized,
+{
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::trace_span!(
+            "decode",
+            message = core::any::type_name::<M>(),
+            len = tracing::field::Empty,
+        );
+        if !span.is_disabled() {
+            let _entered = span.enter();
+            let remaining = buf.remaining();
+            let result = f(buf);
+            span.record("len", remaining - buf.remaining());
+            return result;
+        }
+    }
+    f(buf)
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use core::fmt;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::encoding::{message, string, DecodeContext};
+    use crate::{DecodeError, Message, WireType};
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Inner {
+        name: String,
+    }
+
+    impl Message for Inner {
+        fn encode_raw(&self, buf: &mut impl crate::buf::BufMut) {
+            string::encode(1, &self.name, buf);
+        }
+
+        fn merge_field(
+            &mut self,
+            tag: u32,
+            wire_type: WireType,
+            buf: &mut impl Buf,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            match tag {
+                1 => string::merge(wire_type, &mut self.name, buf, ctx),
+                _ => crate::encoding::skip_field(wire_type, tag, buf, ctx),
+            }
+        }
+
+        fn encoded_len(&self) -> usize {
+            string::encoded_len(1, &self.name)
+        }
+
+        fn clear(&mut self) {
+            self.name.clear();
+        }
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Outer {
+        inner: Option<Inner>,
+    }
+
+    impl Message for Outer {
+        fn encode_raw(&self, buf: &mut impl crate::buf::BufMut) {
+            if let Some(inner) = &self.inner {
+                message::encode(1, inner, buf);
+            }
+        }
+
+        fn merge_field(
+            &mut self,
+            tag: u32,
+            wire_type: WireType,
+            buf: &mut impl Buf,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            match tag {
+                1 => message::merge(
+                    wire_type,
+                    self.inner.get_or_insert_with(Default::default),
+                    buf,
+                    ctx,
+                ),
+                _ => crate::encoding::skip_field(wire_type, tag, buf, ctx),
+            }
+        }
+
+        fn encoded_len(&self) -> usize {
+            self.inner
+                .as_ref()
+                .map_or(0, |inner| message::encoded_len(1, inner))
+        }
+
+        fn clear(&mut self) {
+            self.inner = None;
+        }
+    }
+
+    /// A span as recorded by [`Recorder`]: its name, fields, and the index of its parent.
+    #[derive(Debug, PartialEq)]
+    struct RecordedSpan {
+        name: &'static str,
+        message: String,
+        len: Option<u64>,
+        parent: Option<usize>,
+    }
+
+    impl Visit for RecordedSpan {
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            if field.name() == "len" {
+                self.len = Some(value);
+            }
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.message = value.to_string();
+            }
+        }
+
+        fn record_debug(&mut self, _: &Field, _: &dyn fmt::Debug) {}
+    }
+
+    /// Records the spans created on the current thread, and the stack of entered spans.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+        stack: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut span = RecordedSpan {
+                name: attributes.metadata().name(),
+                message: String::new(),
+                len: None,
+                parent: self.stack.lock().unwrap().last().copied(),
+            };
+            attributes.record(&mut span);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span);
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &Id, values: &Record<'_>) {
+            values.record(&mut self.spans.lock().unwrap()[id.into_u64() as usize - 1]);
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, id: &Id) {
+            self.stack.lock().unwrap().push(id.into_u64() as usize - 1);
+        }
+
+        fn exit(&self, _: &Id) {
+            self.stack.lock().unwrap().pop();
+        }
+    }
+
+    fn span(name: &'static str, message: &str, len: u64, parent: Option<usize>) -> RecordedSpan {
+        RecordedSpan {
+            name,
+            message: message.to_string(),
+            len: Some(len),
+            parent,
+        }
+    }
+
+    #[test]
+    fn nested_spans() {
+        let outer = Outer {
+            inner: Some(Inner {
+                name: "hello".to_string(),
+            }),
+        };
+        let inner = core::any::type_name::<Inner>();
+        let outer_name = core::any::type_name::<Outer>();
+
+        let recorder = Recorder::default();
+        let decoded = tracing::subscriber::with_default(recorder.clone(), || {
+            let encoded = outer.encode_to_vec();
+            Outer::decode(encoded.as_slice()).unwrap()
+        });
+        assert_eq!(decoded, outer);
+
+        assert_eq!(
+            *recorder.spans.lock().unwrap(),
+            [
+                span("encode", outer_name, 9, None),
+                span("encode", inner, 8, Some(0)),
+                span("decode", outer_name, 9, None),
+                span("decode", inner, 8, Some(2)),
+            ]
+        );
+    }
+}
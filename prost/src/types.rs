@@ -9,12 +9,15 @@ use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 
-use ::bytes::{Buf, BufMut, Bytes};
+use ::bytes::Bytes;
+
+use crate::buf::{Buf, BufMut};
 
 use crate::encoding::wire_type::WireType;
 use crate::{
     encoding::{
-        bool, bytes, double, float, int32, int64, skip_field, string, uint32, uint64, DecodeContext,
+        bool, bytes, double, float, int32, int64, semantic_eq, skip_field, string, uint32, uint64,
+        DecodeContext,
     },
     DecodeError, Message, Name,
 };
@@ -39,6 +42,9 @@ impl Message for bool {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if *self {
             2
@@ -81,6 +87,9 @@ impl Message for u32 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if *self != 0 {
             uint32::encoded_len(1, self)
@@ -123,6 +132,9 @@ impl Message for u64 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if *self != 0 {
             uint64::encoded_len(1, self)
@@ -165,6 +177,9 @@ impl Message for i32 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if *self != 0 {
             int32::encoded_len(1, self)
@@ -207,6 +222,9 @@ impl Message for i64 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if *self != 0 {
             int64::encoded_len(1, self)
@@ -249,6 +267,9 @@ impl Message for f32 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if *self != 0.0 {
             float::encoded_len(1, self)
@@ -256,6 +277,9 @@ impl Message for f32 {
             0
         }
     }
+    fn eq_semantically(&self, other: &Self) -> bool {
+        semantic_eq::float(self, other)
+    }
     fn clear(&mut self) {
         *self = 0.0;
     }
@@ -291,6 +315,9 @@ impl Message for f64 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if *self != 0.0 {
             double::encoded_len(1, self)
@@ -298,6 +325,9 @@ impl Message for f64 {
             0
         }
     }
+    fn eq_semantically(&self, other: &Self) -> bool {
+        semantic_eq::double(self, other)
+    }
     fn clear(&mut self) {
         *self = 0.0;
     }
@@ -333,6 +363,9 @@ impl Message for String {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if !self.is_empty() {
             string::encoded_len(1, self)
@@ -375,6 +408,9 @@ impl Message for Vec<u8> {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if !self.is_empty() {
             bytes::encoded_len(1, self)
@@ -417,6 +453,9 @@ impl Message for Bytes {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
+    }
     fn encoded_len(&self) -> usize {
         if !self.is_empty() {
             bytes::encoded_len(1, self)
@@ -477,6 +516,17 @@ fn googleapis_type_url_for<T: Name>() -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_eq_semantically() {
+        assert!(f32::NAN.eq_semantically(&f32::from_bits(f32::NAN.to_bits() | 1)));
+        assert!(!f32::NAN.eq_semantically(&0.0));
+        assert!(f64::NAN.eq_semantically(&f64::NAN));
+        assert!((-0.0f64).eq_semantically(&0.0));
+        assert!(!1.0f64.eq_semantically(&f64::INFINITY));
+        assert!(Message::eq_semantically(&7u32, &7));
+        assert!(!Message::eq_semantically(&7u32, &8));
+    }
+
     #[test]
     fn test_impl_name() {
         assert_eq!("BoolValue", bool::NAME);
@@ -1,5 +1,12 @@
 #![allow(clippy::large_enum_variant, clippy::unreadable_literal)]
 
+use std::path::Path;
+
+/// Returns the path of the `protoc` built from the vendored protobuf sources.
+pub fn protoc() -> &'static Path {
+    Path::new(concat!(env!("PROTOBUF"), "/bin/protoc"))
+}
+
 pub mod conformance {
     use std::path::Path;
 
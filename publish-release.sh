@@ -17,6 +17,7 @@ CRATES=( \
   "prost" \
   "prost-types" \
   "prost-build" \
+  "prost-build-test-utils" \
 )
 
 for CRATE in "${CRATES[@]}"; do
@@ -172,6 +172,210 @@ fn main() {
         .compile_protos(&[src.join("boxed_field.proto")], includes)
         .unwrap();
 
+    prost_build::Config::new()
+        .enforce_reserved(true)
+        .compile_protos(&[src.join("reserved.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .reserved_field_callback("crate::reserved::count_reserved_field")
+        .compile_protos(&[src.join("reserved_callback.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .oneof_from_impls(["."])
+        .compile_protos(&[src.join("oneof_from.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .newtype("newtype.User.id", "UserId")
+        .newtype("newtype.User.Session.user_id", "UserId")
+        .newtype("account_id", "AccountId")
+        .newtype("referrer_id", "AccountId")
+        .compile_protos(&[src.join("newtype.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .interned_strings([".interned_strings"])
+        .compile_protos(&[src.join("interned_strings.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .wkt_as_std(true)
+        .compile_protos(&[src.join("wkt_as_std.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .no_std(true)
+        .wkt_as_std(true)
+        .type_attribute(
+            ".no_std.Device",
+            "#[derive(::serde::Serialize, ::serde::Deserialize)]",
+        )
+        .serde_json_names([".no_std.Device"])
+        .compile_protos(&[src.join("no_std.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .type_attribute(
+            ".json_value",
+            "#[derive(::serde::Serialize, ::serde::Deserialize)]",
+        )
+        .btree_map([".json_value"])
+        .serde_json_names([".json_value"])
+        .serde_json_value_conversions([".json_value.Settings"])
+        .compile_protos(&[src.join("json_value.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .compile_well_known_types()
+        .unwrap_well_known_wrappers(true)
+        .compile_protos(&[src.join("unwrapped_wrappers.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .enum_variant_naming(prost_build::EnumVariantNaming::Original)
+        .compile_protos(&[src.join("enum_naming_original.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .enum_variant_renamer(|_, value_name| {
+            value_name
+                .strip_prefix('k')
+                .unwrap_or(value_name)
+                .to_string()
+        })
+        .compile_protos(&[src.join("enum_naming_renamed.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .package_constants(true)
+        .compile_protos(&[src.join("package_constants.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .service_descriptors(true)
+        .type_name_domain(["."], "type.googleapis.com")
+        .compile_protos(&[src.join("service_descriptors.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .encode_default_map_values(true)
+        .compile_protos(&[src.join("map_default_values.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .compile_protos(&[src.join("message_set.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .oneof_attribute(".oneof_attribute", "#[derive(Eq, Ord)]")
+        .oneof_attribute("oneof_attribute.Shape.kind", "#[derive(PartialOrd)]")
+        .oneof_attribute(".oneof_attribute.Shape.Fill", "#[derive(PartialOrd)]")
+        .compile_protos(&[src.join("oneof_attribute.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .accept_unknown_enum_values(["closed_enum.Paint.legacy"])
+        .compile_protos(&[src.join("closed_enum.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .index_map(["."])
+        .compile_protos(&[src.join("index_map.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .custom_type(
+            ".custom_types.Decimal",
+            prost_build::CustomType::new("crate::custom_types::Fixed"),
+        )
+        .send_sync_assertions(["."])
+        .compile_protos(&[src.join("custom_types.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .presence_bitsets([".presence_bitsets.Reading"])
+        .compile_protos(&[src.join("presence_bitsets.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .display_from_field(".display_from_field.User", "name")
+        .display_from_field(".display_from_field.Score", "points")
+        .compile_protos(&[src.join("display_from_field.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .type_attribute(
+            ".json_unknown_fields",
+            "#[derive(::serde::Serialize, ::serde::Deserialize)]",
+        )
+        .type_attribute(".json_unknown_fields", "#[serde(default)]")
+        .serde_json_names([".json_unknown_fields"])
+        .json_unknown_fields([".json_unknown_fields.Event", ".json_unknown_fields.Detail"])
+        .compile_protos(&[src.join("json_unknown_fields.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .convenience_conversions(true)
+        .compile_protos(&[src.join("convenience_conversions.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map([".field_values"])
+        .field_values([".field_values"])
+        .compile_protos(&[src.join("field_values.proto")], includes)
+        .unwrap();
+
+    // `any()` never holds, so that the internal fields of `Account` are not compiled, and `all()`
+    // always holds.
+    prost_build::Config::new()
+        .field_cfg(".field_cfg.Account.audit_log", "any()")
+        .field_cfg(".field_cfg.Account.internal_labels", "any()")
+        .field_cfg(".field_cfg.Account.owner.service_account", "any()")
+        .field_cfg(".field_cfg.Account.internal_state.migration", "any()")
+        .field_cfg(".field_cfg.Account.internal_state.shard", "any()")
+        .field_cfg(".field_cfg.FullAccount.internal_state.shard", "all()")
+        .compile_protos(&[src.join("field_cfg.proto")], includes)
+        .unwrap();
+
+    // Keeps the acronyms of type names, so that `HTTPServer2` is not renamed `HttpServer2`.
+    struct KeepAcronyms;
+
+    impl prost_build::IdentPolicy for KeepAcronyms {
+        fn to_upper_camel(&self, name: &str) -> String {
+            name.split('_')
+                .flat_map(|part| {
+                    let mut chars = part.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_ascii_uppercase())
+                        .into_iter()
+                        .chain(chars)
+                })
+                .collect()
+        }
+    }
+
+    prost_build::Config::new()
+        .ident_policy(KeepAcronyms)
+        .compile_protos(&[src.join("ident_policy.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .merge_packages(
+            ["merged_packages.v1", "merged_packages.v1beta"],
+            "merged_packages",
+        )
+        .compile_protos(
+            &[
+                src.join("merged_packages_v1.proto"),
+                src.join("merged_packages_v1beta.proto"),
+            ],
+            includes,
+        )
+        .unwrap();
+
     // Check that attempting to compile a .proto without a package declaration does not result in an error.
     config
         .compile_protos(&[src.join("no_package.proto")], includes)
@@ -209,6 +413,40 @@ fn main() {
 
     assert!(no_root_packages_with_default.join("_.rs").exists());
 
+    // Check that the prelude re-exports the types of nested packages and of files without a
+    // package.
+    let prelude = out_dir.as_path().join("prelude");
+    fs::create_dir_all(&prelude).expect("failed to create prefix directory");
+    prost_build::Config::new()
+        .out_dir(&prelude)
+        .include_file("_include.rs")
+        .generate_prelude(true)
+        .compile_protos(
+            &[src.join("no_root_packages/widget_factory.proto")],
+            &[src.join("no_root_packages")],
+        )
+        .unwrap();
+
+    // Compile the nested packages with a remapped package, re-exported by the include file.
+    let crate_remap = out_dir.as_path().join("crate_remap");
+    fs::create_dir_all(&crate_remap).expect("failed to create prefix directory");
+    cfg_if! {
+        if #[cfg(feature = "edition-2015")] {
+            const CRATE_REMAP_PATH: &str = "::packages::gizmo";
+        } else {
+            const CRATE_REMAP_PATH: &str = "crate::packages::gizmo";
+        }
+    };
+    prost_build::Config::new()
+        .out_dir(&crate_remap)
+        .include_file("_include.rs")
+        .crate_remap(".packages.gizmo", CRATE_REMAP_PATH)
+        .compile_protos(
+            &[src.join("packages").join("widget_factory.proto")],
+            &[src.join("packages")],
+        )
+        .unwrap();
+
     let extern_paths = out_dir.as_path().join("extern_paths");
     fs::create_dir_all(&extern_paths).expect("failed to create prefix directory");
 
@@ -0,0 +1,92 @@
+//! Decodes adversarial inputs derived from the fuzzing corpus, checking that malformed input is
+//! rejected with an error rather than a panic.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::Message;
+
+use crate::groups::{NestedGroup2, OneofGroup, Test2};
+use crate::recursive_oneof::A;
+
+/// The seeds of the afl corpus, and encoded messages collected from failed fuzz runs.
+fn corpus() -> Vec<Vec<u8>> {
+    let mut corpus = vec![
+        include_bytes!("../../fuzz/afl/proto3/in/testmessage").to_vec(),
+        include_bytes!("../../fuzz/afl/proto3/in/empty").to_vec(),
+        vec![0x28, 0x28, 0x28, 0xFF, 0xFF, 0xFF, 0xFF, 0x68],
+        vec![0x92, 0x01, 0x00, 0x92, 0xF4, 0x01, 0x02, 0x00, 0x00],
+        vec![0x5d, 0xff, 0xff, 0xff, 0xff, 0x28, 0xff, 0xff, 0x21],
+        vec![0x98, 0x04, 0x02, 0x08, 0x0B, 0x98, 0x04, 0x02, 0x08, 0x02],
+        // A `groups.OneofGroup` with a group variant.
+        vec![0x08, 0x01, 0x13, 0x08, 0x02, 0x12, 0x01, 0x61, 0x14],
+        // A `google.protobuf.Struct` with a nested list value.
+        vec![
+            0x0A, 0x09, 0x0A, 0x01, 0x61, 0x12, 0x04, 0x32, 0x02, 0x20, 0x01,
+        ],
+    ];
+
+    // Deterministic pseudo-random inputs.
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    for len in 1..64 {
+        corpus.push(
+            (0..len)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect(),
+        );
+    }
+    corpus
+}
+
+/// Mutations of an input: all its truncations, and the inputs with one byte replaced by values
+/// which change the meaning of varints, keys and lengths.
+fn mutations(input: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let truncations = (0..input.len()).map(|len| input[..len].to_vec());
+    let replacements = (0..input.len()).flat_map(move |i| {
+        [0x00, 0x01, 0x7F, 0x80, 0xFF].iter().map(move |&byte| {
+            let mut mutated = input.to_vec();
+            mutated[i] = byte;
+            mutated
+        })
+    });
+    truncations.chain(replacements)
+}
+
+fn decode_all<M>(input: &[u8])
+where
+    M: Message + Default,
+{
+    if let Ok(msg) = M::decode(input) {
+        // Messages decoded from malformed input must be encodable.
+        assert_eq!(msg.encode_to_vec().len(), msg.encoded_len());
+    }
+    let _ = M::decode_length_delimited(input);
+    let mut msg = M::default();
+    let _ = msg.merge(input);
+    let _ = msg.merge(input);
+}
+
+#[test]
+fn adversarial_inputs_do_not_panic() {
+    for seed in corpus() {
+        for input in core::iter::once(seed.clone()).chain(mutations(&seed)) {
+            decode_all::<Test2>(&input);
+            decode_all::<OneofGroup>(&input);
+            decode_all::<NestedGroup2>(&input);
+            decode_all::<A>(&input);
+            decode_all::<prost_types::Struct>(&input);
+            decode_all::<prost_types::Any>(&input);
+            decode_all::<prost_types::FileDescriptorSet>(&input);
+            let _ = prost::decode_length_delimiter(input.as_slice());
+            for field in prost::FieldIter::new(&input) {
+                let Ok(field) = field else { break };
+                prost::FieldIter::new(field.value).for_each(drop);
+            }
+        }
+    }
+}
@@ -20,6 +20,31 @@ macro_rules! assert_eq_bootstrapped_file {
     }};
 }
 
+/// The predicate compiling the types which describe the source code of `.proto` files and of
+/// generated files, unless the `no-source-code-info` feature of `prost-types` is enabled.
+const SOURCE_CODE_INFO: &str = r#"not(feature = "no-source-code-info")"#;
+
+/// The well-known types, which implement `MessageFields` so that the messages of other crates
+/// can be traversed.
+const WELL_KNOWN_TYPES: &[&str] = &[
+    ".google.protobuf.Any",
+    ".google.protobuf.Api",
+    ".google.protobuf.Method",
+    ".google.protobuf.Mixin",
+    ".google.protobuf.Duration",
+    ".google.protobuf.FieldMask",
+    ".google.protobuf.SourceContext",
+    ".google.protobuf.Struct",
+    ".google.protobuf.Value",
+    ".google.protobuf.ListValue",
+    ".google.protobuf.Timestamp",
+    ".google.protobuf.Type",
+    ".google.protobuf.Field",
+    ".google.protobuf.Enum",
+    ".google.protobuf.EnumValue",
+    ".google.protobuf.Option",
+];
+
 /// Test which bootstraps protobuf.rs and compiler.rs from the .proto definitions in the Protobuf
 /// repo. Ensures that the checked-in compiled versions are up-to-date.
 #[test]
@@ -44,6 +69,17 @@ fn bootstrap() {
             ".",
             r#"#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]"#,
         )
+        .field_values(WELL_KNOWN_TYPES)
+        .type_cfg(".google.protobuf.SourceCodeInfo", SOURCE_CODE_INFO)
+        .type_cfg(".google.protobuf.GeneratedCodeInfo", SOURCE_CODE_INFO)
+        .field_cfg(
+            ".google.protobuf.FileDescriptorProto.source_code_info",
+            SOURCE_CODE_INFO,
+        )
+        .field_cfg(
+            ".google.protobuf.compiler.CodeGeneratorResponse.File.generated_code_info",
+            SOURCE_CODE_INFO,
+        )
         .out_dir(tempdir.path())
         .compile_protos(
             &[
@@ -0,0 +1,129 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use arrayvec::ArrayVec;
+use prost::{Enumeration, Message};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enumeration)]
+enum Level {
+    Low = 0,
+    High = 1,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Bounded {
+    #[prost(uint32, repeated, max_len = "4", tag = "1")]
+    readings: ArrayVec<u32, 4>,
+    #[prost(fixed64, repeated, packed = "false", max_len = 2, tag = "2")]
+    timestamps: ArrayVec<u64, 2>,
+    #[prost(enumeration = "Level", repeated, max_len = "2", tag = "3")]
+    levels: ArrayVec<i32, 2>,
+    #[prost(string, repeated, max_len = "2", tag = "4")]
+    names: Vec<String>,
+}
+
+/// The same message without bounds.
+#[derive(Clone, PartialEq, Message)]
+struct Unbounded {
+    #[prost(uint32, repeated, tag = "1")]
+    readings: Vec<u32>,
+    #[prost(fixed64, repeated, packed = "false", tag = "2")]
+    timestamps: Vec<u64>,
+    #[prost(enumeration = "Level", repeated, tag = "3")]
+    levels: Vec<i32>,
+    #[prost(string, repeated, tag = "4")]
+    names: Vec<String>,
+}
+
+#[test]
+fn test_bounded_repeated_roundtrip() {
+    let mut bounded = Bounded::default();
+    bounded.readings.extend([1, 2, 3, 4].iter().copied());
+    bounded.timestamps.push(5);
+    bounded.levels.push(Level::High as i32);
+    bounded.names.push("a".to_string());
+
+    let encoded = bounded.encode_to_vec();
+    assert_eq!(encoded.len(), bounded.encoded_len());
+    assert_eq!(Bounded::decode(encoded.as_slice()).as_ref(), Ok(&bounded));
+
+    let unbounded = Unbounded {
+        readings: [1, 2, 3, 4].to_vec(),
+        timestamps: [5].to_vec(),
+        levels: [Level::High as i32].to_vec(),
+        names: ["a".to_string()].to_vec(),
+    };
+    assert_eq!(encoded, unbounded.encode_to_vec());
+    assert_eq!(
+        format!("{:?}", bounded),
+        "Bounded { readings: [1, 2, 3, 4], timestamps: [5], levels: [High], names: [\"a\"] }"
+    );
+
+    bounded.clear();
+    assert_eq!(bounded, Bounded::default());
+}
+
+#[test]
+fn test_bounded_repeated_overflow() {
+    let mut unbounded = Unbounded {
+        readings: [1, 2, 3, 4, 5].to_vec(),
+        ..Default::default()
+    };
+    assert_eq!(
+        Bounded::decode(unbounded.encode_to_vec().as_slice())
+            .unwrap_err()
+            .to_string(),
+        "failed to decode Protobuf message: Bounded.readings: \
+         repeated field exceeds its maximum length of 4"
+    );
+
+    unbounded.readings.clear();
+    unbounded.timestamps = [1, 2, 3].to_vec();
+    assert!(Bounded::decode(unbounded.encode_to_vec().as_slice()).is_err());
+
+    unbounded.timestamps.clear();
+    unbounded.names = ["a", "b", "c"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    assert!(Bounded::decode(unbounded.encode_to_vec().as_slice()).is_err());
+
+    unbounded.names.pop();
+    assert!(Bounded::decode(unbounded.encode_to_vec().as_slice()).is_ok());
+}
+
+/// A message whose fields all have a bounded encoding.
+#[derive(Clone, PartialEq, Message)]
+struct Sensor {
+    #[prost(fixed32, tag = "1")]
+    id: u32,
+    #[prost(bool, tag = "2")]
+    active: bool,
+    #[prost(enumeration = "Level", tag = "3")]
+    level: i32,
+    #[prost(double, optional, tag = "4")]
+    calibration: Option<f64>,
+    #[prost(uint32, repeated, max_len = "4", tag = "5")]
+    readings: ArrayVec<u32, 4>,
+    #[prost(fixed64, repeated, packed = "false", max_len = "2", tag = "16")]
+    timestamps: ArrayVec<u64, 2>,
+}
+
+#[test]
+fn test_max_encoded_len() {
+    let sensor = Sensor {
+        id: u32::MAX,
+        active: true,
+        // Negative values are sign extended to 10 bytes.
+        level: -1,
+        calibration: Some(1.5),
+        readings: [u32::MAX; 4].into(),
+        timestamps: [u64::MAX; 2].into(),
+    };
+    assert_eq!(sensor.encoded_len(), Sensor::MAX_ENCODED_LEN);
+
+    let mut buf = [0; Sensor::MAX_ENCODED_LEN];
+    sensor.encode(&mut &mut buf[..]).unwrap();
+    assert_eq!(Sensor::decode(&buf[..]).unwrap(), sensor);
+}
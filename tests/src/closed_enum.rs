@@ -0,0 +1,101 @@
+extern crate std;
+
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use std::sync::Mutex;
+
+use prost::encoding::{int32, string};
+use prost::{DecodeObserver, Message, WireType};
+
+include!(concat!(env!("OUT_DIR"), "/closed_enum.rs"));
+
+const RED: i32 = Color::Red as i32;
+const GREEN: i32 = Color::Green as i32;
+const UNKNOWN: i32 = 3;
+
+#[test]
+fn skips_unknown_values() {
+    let mut buf = Vec::new();
+    int32::encode(1, &UNKNOWN, &mut buf);
+    int32::encode(2, &UNKNOWN, &mut buf);
+    int32::encode_repeated(3, &[RED, UNKNOWN, GREEN], &mut buf);
+    int32::encode_packed(4, &[UNKNOWN, RED], &mut buf);
+    string::encode(6, &"gloss".to_string(), &mut buf);
+    int32::encode(5, &UNKNOWN, &mut buf);
+
+    let paint = Paint::decode(buf.as_slice()).unwrap();
+    assert_eq!(paint.color, None);
+    assert_eq!(paint.base, GREEN);
+    assert_eq!(paint.layers, [RED, GREEN]);
+    assert_eq!(paint.packed_layers, [RED]);
+    assert_eq!(paint.finish, Some(paint::Finish::Name("gloss".to_string())));
+
+    // The skipped values are not encoded again.
+    let mut expected = Vec::new();
+    int32::encode(2, &GREEN, &mut expected);
+    int32::encode_repeated(3, &[RED, GREEN], &mut expected);
+    int32::encode_packed(4, &[RED], &mut expected);
+    string::encode(6, &"gloss".to_string(), &mut expected);
+    assert_eq!(paint.encode_to_vec(), expected);
+}
+
+#[test]
+fn keeps_previous_value() {
+    let mut buf = Vec::new();
+    int32::encode(1, &RED, &mut buf);
+    int32::encode(1, &UNKNOWN, &mut buf);
+    int32::encode(5, &GREEN, &mut buf);
+    int32::encode(5, &UNKNOWN, &mut buf);
+
+    let paint = Paint::decode(buf.as_slice()).unwrap();
+    assert_eq!(paint.color(), Color::Red);
+    assert_eq!(paint.finish, Some(paint::Finish::Tint(GREEN)));
+}
+
+#[test]
+fn accepts_unknown_values_when_configured() {
+    let mut buf = Vec::new();
+    int32::encode(8, &UNKNOWN, &mut buf);
+    let paint = Paint::decode(buf.as_slice()).unwrap();
+    assert_eq!(paint.legacy, Some(UNKNOWN));
+
+    let mut expected = Vec::new();
+    int32::encode(2, &GREEN, &mut expected);
+    expected.extend_from_slice(&buf);
+    assert_eq!(paint.encode_to_vec(), expected);
+}
+
+#[test]
+fn map_values_are_accepted() {
+    let mut paint = Paint::default();
+    paint.palette.insert(1, UNKNOWN);
+    let decoded = Paint::decode(paint.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded.palette, paint.palette);
+}
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<(u32, WireType, usize)>>);
+
+impl DecodeObserver for Recorder {
+    fn unknown_field(&self, tag: u32, wire_type: WireType, offset: usize) {
+        self.0.lock().unwrap().push((tag, wire_type, offset));
+    }
+}
+
+#[test]
+fn observes_unknown_values() {
+    let mut buf = Vec::new();
+    // Value at offset 1.
+    int32::encode(1, &UNKNOWN, &mut buf);
+    // Values at offsets 4 and 5.
+    int32::encode_packed(4, &[RED, UNKNOWN], &mut buf);
+
+    let recorder = Recorder::default();
+    let paint = Paint::decode_with_observer(buf.as_slice(), &recorder).unwrap();
+    assert_eq!(paint.packed_layers, [RED]);
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        vec![(1, WireType::Varint, 1), (4, WireType::Varint, 5)]
+    );
+}
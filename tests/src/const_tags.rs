@@ -0,0 +1,103 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{Message, Oneof};
+
+pub mod tags {
+    pub const ID: u32 = 1;
+    pub const NAME: u32 = 2;
+    pub const LABELS: u32 = 3;
+    pub const EMAIL: u32 = 4;
+    pub const PHONE: u32 = 5;
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct User {
+    #[prost(uint32, tag = "crate::const_tags::tags::ID")]
+    id: u32,
+    #[prost(string, tag = "crate::const_tags::tags::NAME")]
+    name: String,
+    #[prost(string, repeated, tag = "crate::const_tags::tags::LABELS")]
+    labels: Vec<String>,
+    #[prost(
+        oneof = "Contact",
+        tags = "crate::const_tags::tags::EMAIL, crate::const_tags::tags::PHONE"
+    )]
+    contact: Option<Contact>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+enum Contact {
+    #[prost(string, tag = "crate::const_tags::tags::EMAIL")]
+    Email(String),
+    #[prost(uint64, tag = "crate::const_tags::tags::PHONE")]
+    Phone(u64),
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct NumberedUser {
+    #[prost(uint32, tag = "1")]
+    id: u32,
+    #[prost(string, tag = "2")]
+    name: String,
+    #[prost(string, repeated, tag = "3")]
+    labels: Vec<String>,
+    #[prost(oneof = "NumberedContact", tags = "4, 5")]
+    contact: Option<NumberedContact>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+enum NumberedContact {
+    #[prost(string, tag = "4")]
+    Email(String),
+    #[prost(uint64, tag = "5")]
+    Phone(u64),
+}
+
+/// Path tags may be mixed with literal tags, given without quotes.
+#[derive(Clone, PartialEq, Message)]
+struct Mixed {
+    #[prost(uint32, tag = 1)]
+    id: u32,
+    #[prost(string, tag = crate::const_tags::tags::NAME)]
+    name: String,
+}
+
+#[test]
+fn test_const_tags_encode_like_literal_tags() {
+    let user = User {
+        id: 7,
+        name: "ferris".to_string(),
+        labels: vec!["a".to_string(), "b".to_string()],
+        contact: Some(Contact::Phone(42)),
+    };
+    let numbered = NumberedUser {
+        id: 7,
+        name: "ferris".to_string(),
+        labels: vec!["a".to_string(), "b".to_string()],
+        contact: Some(NumberedContact::Phone(42)),
+    };
+    let buf = user.encode_to_vec();
+    assert_eq!(buf, numbered.encode_to_vec());
+    assert_eq!(user.encoded_len(), buf.len());
+    assert_eq!(User::decode(buf.as_slice()).unwrap(), user);
+}
+
+#[test]
+fn test_const_tags_decode() {
+    let numbered = NumberedUser {
+        id: 1,
+        name: String::new(),
+        labels: Vec::new(),
+        contact: Some(NumberedContact::Email("ferris@example.com".to_string())),
+    };
+    let user = User::decode(numbered.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(
+        user.contact,
+        Some(Contact::Email("ferris@example.com".to_string()))
+    );
+
+    let mixed = Mixed::decode(numbered.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(mixed.id, 1);
+}
@@ -0,0 +1,19 @@
+include!(concat!(env!("OUT_DIR"), "/convenience_conversions.rs"));
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+
+#[test]
+fn converts_to_and_from_bytes() {
+    let user = User {
+        name: "alice".to_string(),
+        age: 30,
+    };
+    let bytes: Vec<u8> = (&user).into();
+    assert_eq!(bytes, prost::Message::encode_to_vec(&user));
+
+    let decoded: User = bytes.as_slice().try_into().unwrap();
+    assert_eq!(decoded, user);
+    assert!(User::try_from(&[0x0a, 0x05][..]).is_err());
+}
@@ -0,0 +1,15 @@
+//! Tests nested packages with `crate_remap`, whose include file re-exports the remapped package.
+
+include!(concat!(env!("OUT_DIR"), "/crate_remap/_include.rs"));
+
+#[test]
+fn test() {
+    use prost::Message;
+
+    let gizmo: self::packages::gizmo::Gizmo = crate::packages::gizmo::Gizmo {};
+    let widget_factory = self::packages::widget::factory::WidgetFactory {
+        gizmo: Some(gizmo),
+        ..Default::default()
+    };
+    assert_eq!(2, widget_factory.encoded_len());
+}
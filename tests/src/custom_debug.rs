@@ -7,6 +7,8 @@ use alloc::string::String;
 use alloc::string::ToString;
 use core::fmt;
 
+use super::message_encoding::BasicEnumeration;
+
 impl fmt::Debug for Msg {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("Msg {..}")
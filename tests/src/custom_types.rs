@@ -0,0 +1,78 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/custom_types.rs"));
+
+/// A fixed-point number with nine decimal places, stored as a `Decimal` message.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fixed(i128);
+
+impl<'a> From<&'a Fixed> for Decimal {
+    fn from(value: &'a Fixed) -> Decimal {
+        Decimal {
+            units: (value.0 / 1_000_000_000) as i64,
+            nanos: (value.0 % 1_000_000_000) as i32,
+        }
+    }
+}
+
+impl TryFrom<Decimal> for Fixed {
+    type Error = &'static str;
+
+    fn try_from(value: Decimal) -> Result<Fixed, Self::Error> {
+        if value.nanos.abs() >= 1_000_000_000 {
+            return Err("nanos out of range");
+        }
+        Ok(Fixed(
+            i128::from(value.units) * 1_000_000_000 + i128::from(value.nanos),
+        ))
+    }
+}
+
+#[test]
+fn roundtrip_custom_type_fields() {
+    let order = Order {
+        price: Some(Fixed(12_500_000_000)),
+        fees: vec![Fixed(1), Fixed(-2_000_000_001)],
+        discounts: [("loyalty".into(), Decimal { units: 1, nanos: 0 })]
+            .into_iter()
+            .collect(),
+        total: Some(order::Total::Amount(Fixed(99))),
+    };
+
+    let buf = order.encode_to_vec();
+    assert_eq!(buf.len(), order.encoded_len());
+    assert_eq!(Order::decode(buf.as_slice()).unwrap(), order);
+}
+
+#[test]
+fn custom_type_is_encoded_as_message() {
+    let order = Order {
+        price: Some(Fixed(3_000_000_004)),
+        ..Default::default()
+    };
+    let mut expected = Vec::new();
+    prost::encoding::message::encode(1, &Decimal { units: 3, nanos: 4 }, &mut expected);
+    assert_eq!(order.encode_to_vec(), expected);
+}
+
+#[test]
+fn custom_type_conversion_error() {
+    let mut buf = Vec::new();
+    prost::encoding::message::encode(
+        1,
+        &Decimal {
+            units: 0,
+            nanos: 1_000_000_000,
+        },
+        &mut buf,
+    );
+    let error = Order::decode(buf.as_slice()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: Order.price: nanos out of range"
+    );
+}
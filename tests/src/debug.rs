@@ -97,3 +97,36 @@ fn oneof_with_enum() {
         "MessageWithOneof { of: Some(Enumeration(TWO)) }"
     );
 }
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Floats {
+    #[prost(double, tag = "1")]
+    double: f64,
+    #[prost(float, optional, tag = "2")]
+    float: Option<f32>,
+    #[prost(double, repeated, tag = "3")]
+    doubles: prost::alloc::vec::Vec<f64>,
+    #[prost(btree_map = "string, float", tag = "4")]
+    float_map: prost::alloc::collections::BTreeMap<String, f32>,
+}
+
+/// Floats are formatted so that they parse back to the same bits
+#[test]
+fn floats() {
+    let mut msg = Floats {
+        double: -0.0,
+        float: Some(f32::from_bits(0x7fc0_0001)),
+        doubles: prost::alloc::vec![0.1, 1e300, f64::NAN, f64::NEG_INFINITY],
+        float_map: Default::default(),
+    };
+    msg.float_map.insert("a".into(), 16777216.0);
+    assert_eq!(
+        format!("{:?}", msg),
+        "Floats { \
+         double: -0.0, \
+         float: Some(NaN(0x7fc00001)), \
+         doubles: [0.1, 1e300, NaN, -inf], \
+         float_map: {\"a\": 16777216.0} \
+         }"
+    );
+}
@@ -0,0 +1,263 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use prost::buf::{Buf, BufMut};
+use prost::encoding::{self, skip_field};
+use prost::{DecodeContext, DecodeError, DuplicateFieldPolicy, Extensions, Message, WireType};
+
+/// Limits the total length of the labels of a decode.
+struct LabelBudget(AtomicUsize);
+
+/// A label, decoded within the budget found in the context extensions, if any.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Label(String);
+
+impl Message for Label {
+    fn encode_raw(&self, buf: &mut impl BufMut) {
+        encoding::string::encode(1, &self.0, buf);
+    }
+
+    fn merge_field(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        if tag != 1 {
+            return skip_field(wire_type, tag, buf, ctx);
+        }
+        let extensions = ctx.extensions();
+        encoding::string::merge(wire_type, &mut self.0, buf, ctx)?;
+        if let Some(budget) = extensions.get::<LabelBudget>() {
+            let len = self.0.len();
+            budget
+                .0
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |available| {
+                    available.checked_sub(len)
+                })
+                .map_err(|_| DecodeError::new("label budget exceeded"))?;
+        }
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        encoding::string::encoded_len(1, &self.0)
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Event {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+}
+
+#[test]
+fn extensions_are_shared_with_nested_messages() {
+    let event = Event {
+        labels: ["a", "bc", "def"]
+            .into_iter()
+            .map(|label| Label(label.into()))
+            .collect(),
+    };
+    let buf = event.encode_to_vec();
+
+    // Without extensions, `Label` decodes without a budget.
+    assert_eq!(Event::decode(&buf[..]).unwrap(), event);
+    assert!(DecodeContext::new().extensions().is_empty());
+
+    let mut extensions = Extensions::new();
+    extensions.insert(LabelBudget(AtomicUsize::new(6)));
+    let ctx = DecodeContext::new().with_extensions(&extensions);
+    assert_eq!(Event::decode_with_context(&buf[..], ctx).unwrap(), event);
+    let budget = extensions.get::<LabelBudget>().unwrap();
+    assert_eq!(budget.0.load(Ordering::Relaxed), 0);
+
+    budget.0.store(5, Ordering::Relaxed);
+    let ctx = DecodeContext::new().with_extensions(&extensions);
+    assert_eq!(
+        Event::decode_with_context(&buf[..], ctx)
+            .unwrap_err()
+            .to_string(),
+        "failed to decode Protobuf message: Event.labels: label budget exceeded"
+    );
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Limits {
+    #[prost(uint64, tag = "1")]
+    max: u64,
+    #[prost(string, tag = "2")]
+    unit: String,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum Recipient {
+    #[prost(string, tag = "4")]
+    Account(String),
+    #[prost(uint64, tag = "5")]
+    Id(u64),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Transfer {
+    #[prost(uint64, tag = "1")]
+    amount: u64,
+    #[prost(message, optional, tag = "2")]
+    limits: Option<Limits>,
+    #[prost(string, repeated, tag = "3")]
+    notes: Vec<String>,
+    #[prost(oneof = "Recipient", tags = "4, 5")]
+    recipient: Option<Recipient>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Envelope {
+    #[prost(bytes = "vec", tag = "1")]
+    transfer: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct DecodedEnvelope {
+    #[prost(message, optional, tag = "1")]
+    transfer: Option<Transfer>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Batch {
+    #[prost(message, repeated, tag = "1")]
+    transfers: Vec<Transfer>,
+}
+
+/// Returns the concatenation of two encoded transfers, in which every field occurs twice.
+fn duplicated_transfer() -> Vec<u8> {
+    let first = Transfer {
+        amount: 1,
+        limits: Some(Limits {
+            max: 10,
+            unit: String::new(),
+        }),
+        notes: vec!["a".to_string()],
+        recipient: Some(Recipient::Account("x".to_string())),
+    };
+    let second = Transfer {
+        amount: 2,
+        limits: Some(Limits {
+            max: 0,
+            unit: "kb".to_string(),
+        }),
+        notes: vec!["b".to_string()],
+        recipient: Some(Recipient::Account("y".to_string())),
+    };
+    let mut buf = first.encode_to_vec();
+    second.encode(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn duplicate_fields_last_wins() {
+    let buf = duplicated_transfer();
+    let expected = Transfer {
+        amount: 2,
+        limits: Some(Limits {
+            max: 10,
+            unit: "kb".to_string(),
+        }),
+        notes: vec!["a".to_string(), "b".to_string()],
+        recipient: Some(Recipient::Account("y".to_string())),
+    };
+    assert_eq!(Transfer::decode(&buf[..]).unwrap(), expected);
+    let ctx = DecodeContext::new().with_duplicate_field_policy(DuplicateFieldPolicy::LastWins);
+    assert_eq!(ctx.duplicate_field_policy(), DuplicateFieldPolicy::LastWins);
+    assert_eq!(
+        Transfer::decode_with_context(&buf[..], ctx).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn duplicate_fields_first_wins() {
+    let buf = duplicated_transfer();
+    let ctx = DecodeContext::new().with_duplicate_field_policy(DuplicateFieldPolicy::FirstWins);
+    let expected = Transfer {
+        amount: 1,
+        limits: Some(Limits {
+            max: 10,
+            unit: String::new(),
+        }),
+        notes: vec!["a".to_string(), "b".to_string()],
+        recipient: Some(Recipient::Account("x".to_string())),
+    };
+    assert_eq!(
+        Transfer::decode_with_context(&buf[..], ctx.clone()).unwrap(),
+        expected
+    );
+
+    // The policy applies to each field of a oneof separately.
+    let mut buf = buf;
+    Transfer {
+        recipient: Some(Recipient::Id(7)),
+        ..Default::default()
+    }
+    .encode(&mut buf)
+    .unwrap();
+    assert_eq!(
+        Transfer::decode_with_context(&buf[..], ctx)
+            .unwrap()
+            .recipient,
+        Some(Recipient::Id(7))
+    );
+}
+
+#[test]
+fn duplicate_fields_error() {
+    let buf = duplicated_transfer();
+    let ctx = DecodeContext::new().with_duplicate_field_policy(DuplicateFieldPolicy::Error);
+    assert_eq!(
+        Transfer::decode_with_context(&buf[..], ctx.clone())
+            .unwrap_err()
+            .to_string(),
+        "failed to decode Protobuf message: duplicate field: 1"
+    );
+
+    // Repeated fields and fields occurring once are accepted.
+    let transfer = Transfer {
+        amount: 1,
+        notes: vec!["a".to_string(), "b".to_string()],
+        ..Default::default()
+    };
+    let single = transfer.encode_to_vec();
+    assert_eq!(
+        Transfer::decode_with_context(&single[..], ctx.clone()).unwrap(),
+        transfer
+    );
+
+    // Duplicates are detected in nested messages, and separately for each of them.
+    let envelope = Envelope { transfer: buf }.encode_to_vec();
+    assert_eq!(
+        DecodedEnvelope::decode_with_context(&envelope[..], ctx.clone())
+            .unwrap_err()
+            .to_string(),
+        "failed to decode Protobuf message: DecodedEnvelope.transfer: duplicate field: 1"
+    );
+    let mut envelopes = Envelope { transfer: single }.encode_to_vec();
+    envelopes.extend_from_slice(&envelopes.clone());
+    assert_eq!(
+        Batch::decode_with_context(&envelopes[..], ctx.clone())
+            .unwrap()
+            .transfers,
+        vec![transfer.clone(), transfer]
+    );
+    assert_eq!(
+        DecodedEnvelope::decode_with_context(&envelopes[..], ctx)
+            .unwrap_err()
+            .to_string(),
+        "failed to decode Protobuf message: duplicate field: 1"
+    );
+}
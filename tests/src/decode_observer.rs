@@ -0,0 +1,190 @@
+extern crate std;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use std::sync::Mutex;
+
+use prost::encoding::{encode_key, encode_varint};
+use prost::{DecodeObserver, Message, WireType};
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Observed {
+    #[prost(int32, tag = "1")]
+    a: i32,
+    #[prost(int32, repeated, packed = "false", tag = "2")]
+    unpacked: Vec<i32>,
+    #[prost(int32, repeated, tag = "3")]
+    packed: Vec<i32>,
+    #[prost(group, optional, tag = "4")]
+    group: Option<Group>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Group {
+    #[prost(int32, tag = "5")]
+    b: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+enum Color {
+    Red = 0,
+    Green = 1,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Paint {
+    #[prost(enumeration = "Color", tag = "1")]
+    color: i32,
+    #[prost(enumeration = "Color", optional, tag = "2")]
+    accent: Option<i32>,
+    #[prost(enumeration = "Color", repeated, tag = "3")]
+    layers: Vec<i32>,
+    #[prost(oneof = "Finish", tags = "4")]
+    finish: Option<Finish>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum Finish {
+    #[prost(enumeration = "Color", tag = "4")]
+    Tint(i32),
+}
+
+#[derive(Debug, PartialEq)]
+enum Anomaly {
+    UnknownField(u32, WireType, usize),
+    UnexpectedWireType(u32, WireType, usize),
+    UnknownEnumValue(&'static str, &'static str, i32, usize),
+    GroupEof(u32, usize),
+}
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<Anomaly>>);
+
+impl DecodeObserver for Recorder {
+    fn unknown_field(&self, tag: u32, wire_type: WireType, offset: usize) {
+        let anomaly = Anomaly::UnknownField(tag, wire_type, offset);
+        self.0.lock().unwrap().push(anomaly);
+    }
+
+    fn unexpected_wire_type(&self, tag: u32, wire_type: WireType, offset: usize) {
+        let anomaly = Anomaly::UnexpectedWireType(tag, wire_type, offset);
+        self.0.lock().unwrap().push(anomaly);
+    }
+
+    fn unknown_enum_value(
+        &self,
+        message: &'static str,
+        field: &'static str,
+        value: i32,
+        offset: usize,
+    ) {
+        let anomaly = Anomaly::UnknownEnumValue(message, field, value, offset);
+        self.0.lock().unwrap().push(anomaly);
+    }
+
+    fn group_eof(&self, tag: u32, offset: usize) {
+        self.0.lock().unwrap().push(Anomaly::GroupEof(tag, offset));
+    }
+}
+
+#[test]
+fn observes_anomalies() {
+    let mut buf = Vec::new();
+    encode_key(1, WireType::Varint, &mut buf);
+    encode_varint(1, &mut buf);
+    // Unknown field, with its value at offset 3.
+    encode_key(10, WireType::Varint, &mut buf);
+    encode_varint(2, &mut buf);
+    // Packed encoding of the unpacked field, with its value at offset 5.
+    prost::encoding::int32::encode_packed(2, &[3, 4], &mut buf);
+    // Unpacked encoding of the packed field, with its value at offset 9.
+    prost::encoding::int32::encode_repeated(3, &[5], &mut buf);
+    // Well-formed encodings.
+    prost::encoding::int32::encode_repeated(2, &[6], &mut buf);
+    prost::encoding::int32::encode_packed(3, &[7], &mut buf);
+
+    let recorder = Recorder::default();
+    let msg = Observed::decode_with_observer(&buf[..], &recorder).unwrap();
+    assert_eq!(
+        msg,
+        Observed {
+            a: 1,
+            unpacked: vec![3, 4, 6],
+            packed: vec![5, 7],
+            group: None,
+        }
+    );
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            Anomaly::UnknownField(10, WireType::Varint, 3),
+            Anomaly::UnexpectedWireType(2, WireType::LengthDelimited, 5),
+            Anomaly::UnexpectedWireType(3, WireType::Varint, 9),
+        ]
+    );
+
+    // Without an observer, the same message is decoded.
+    assert_eq!(Observed::decode(&buf[..]).unwrap(), msg);
+}
+
+#[test]
+fn observes_group_eof() {
+    let mut buf = Vec::new();
+    encode_key(4, WireType::StartGroup, &mut buf);
+    encode_key(5, WireType::Varint, &mut buf);
+    encode_varint(1, &mut buf);
+
+    let recorder = Recorder::default();
+    assert!(Observed::decode_with_observer(&buf[..], &recorder).is_err());
+    assert_eq!(*recorder.0.lock().unwrap(), [Anomaly::GroupEof(4, 3)]);
+
+    // Unknown groups are skipped as a whole.
+    let mut buf = Vec::new();
+    encode_key(6, WireType::StartGroup, &mut buf);
+    encode_key(7, WireType::Varint, &mut buf);
+    encode_varint(1, &mut buf);
+
+    let recorder = Recorder::default();
+    assert!(Observed::decode_with_observer(&buf[..], &recorder).is_err());
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            Anomaly::UnknownField(6, WireType::StartGroup, 1),
+            Anomaly::GroupEof(6, 3),
+        ]
+    );
+}
+
+#[test]
+fn observes_unknown_enum_values() {
+    let mut buf = Vec::new();
+    // Undeclared values, at offsets 1, 3, 5 and 10.
+    prost::encoding::int32::encode(1, &2, &mut buf);
+    prost::encoding::int32::encode(2, &3, &mut buf);
+    prost::encoding::int32::encode_packed(3, &[1, 4, 5], &mut buf);
+    prost::encoding::int32::encode(4, &6, &mut buf);
+    // Declared values are not reported.
+    prost::encoding::int32::encode(2, &1, &mut buf);
+
+    let recorder = Recorder::default();
+    let paint = Paint::decode_with_observer(&buf[..], &recorder).unwrap();
+    assert_eq!(
+        paint,
+        Paint {
+            color: 2,
+            accent: Some(1),
+            layers: vec![1, 4, 5],
+            finish: Some(Finish::Tint(6)),
+        }
+    );
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            Anomaly::UnknownEnumValue("Paint", "color", 2, 1),
+            Anomaly::UnknownEnumValue("Paint", "accent", 3, 3),
+            Anomaly::UnknownEnumValue("Paint", "layers", 4, 5),
+            Anomaly::UnknownEnumValue("Paint", "layers", 5, 5),
+            Anomaly::UnknownEnumValue("Finish", "Tint", 6, 10),
+        ]
+    );
+}
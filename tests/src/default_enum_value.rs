@@ -4,6 +4,8 @@
 //! must ensure that the appropriate name conventions are used.
 #![allow(clippy::enum_variant_names)]
 
+use prost::Message;
+
 include!(concat!(env!("OUT_DIR"), "/default_enum_value.rs"));
 
 #[test]
@@ -23,6 +25,59 @@ fn test_default_enum() {
     );
 }
 
+#[test]
+fn test_default_enum_fallback() {
+    // Field 2 set to a value which is not a `PrivacyLevel`.
+    let msg = Test {
+        privacy_level_3: Some(9),
+        ..Test::default()
+    };
+    assert_eq!(msg.privacy_level_3(), PrivacyLevel::PrivacyLevelThree);
+
+    // `PrivacyLevel` is a closed proto2 enum, so such a value is not decoded.
+    let msg = Test::decode(&[0x10, 0x09][..]).unwrap();
+    assert_eq!(msg.privacy_level_3, None);
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Derived {
+    #[prost(enumeration = "PrivacyLevel", default = "Two", tag = "1")]
+    plain: i32,
+    #[prost(
+        enumeration = "PrivacyLevel",
+        optional,
+        default = "PrivacyLevelThree",
+        tag = "2"
+    )]
+    optional: Option<i32>,
+    #[prost(enumeration = "PrivacyLevel", required, default = "Two", tag = "3")]
+    required: i32,
+}
+
+#[test]
+fn test_derived_default_enum() {
+    let msg = Derived::default();
+    assert_eq!(msg.plain, PrivacyLevel::Two as i32);
+    assert_eq!(msg.plain(), PrivacyLevel::Two);
+    assert_eq!(msg.optional(), PrivacyLevel::PrivacyLevelThree);
+    assert_eq!(msg.required(), PrivacyLevel::Two);
+
+    // Plain fields holding their default are not encoded.
+    assert_eq!(
+        Derived {
+            optional: Some(PrivacyLevel::One as i32),
+            ..Derived::default()
+        }
+        .encode_to_vec(),
+        [0x10, 0x01, 0x18, 0x02]
+    );
+
+    let msg = Derived::decode(&[0x08, 0x09, 0x10, 0x09, 0x18, 0x09][..]).unwrap();
+    assert_eq!(msg.plain(), PrivacyLevel::Two);
+    assert_eq!(msg.optional(), PrivacyLevel::PrivacyLevelThree);
+    assert_eq!(msg.required(), PrivacyLevel::Two);
+}
+
 #[test]
 fn test_enum_to_string() {
     assert_eq!(PrivacyLevel::One.as_str_name(), "PRIVACY_LEVEL_ONE");
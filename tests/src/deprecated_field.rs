@@ -3,6 +3,8 @@ use alloc::string::ToString;
 
 mod deprecated_field {
     // #![deny(unused_results)]
+    // Generated code must not trigger deprecation warnings by itself.
+    #![deny(deprecated)]
     include!(concat!(env!("OUT_DIR"), "/deprecated_field.rs"));
 }
 
@@ -12,6 +14,7 @@ fn test_warns_when_using_fields_with_deprecated_field() {
     let message = deprecated_field::Test {
         not_outdated: ".ogg".to_string(),
         outdated: ".wav".to_string(),
+        ..Default::default()
     };
     // This test relies on the `#[allow(deprecated)]` attribute to ignore the warning that should
     // be raised by the compiler.
@@ -28,3 +31,24 @@ fn test_warns_when_using_fields_with_deprecated_field() {
     //       = note: `#[warn(deprecated)]` on by default
     drop(message);
 }
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_items_are_usable() {
+    let message = deprecated_field::Test {
+        outdated_message: Some(deprecated_field::OutdatedMessage {
+            value: "old".to_string(),
+        }),
+        outdated_enum: deprecated_field::OutdatedEnum::Unknown as i32,
+        status: deprecated_field::Status::Outdated as i32,
+        choice: Some(deprecated_field::test::Choice::OutdatedChoice(
+            "old".to_string(),
+        )),
+        ..Default::default()
+    };
+    assert_eq!(message.status(), deprecated_field::Status::Outdated);
+    assert_eq!(
+        deprecated_field::Status::Outdated.as_str_name(),
+        "STATUS_OUTDATED"
+    );
+}
@@ -0,0 +1,22 @@
+use alloc::format;
+use alloc::string::ToString;
+
+include!(concat!(env!("OUT_DIR"), "/display_from_field.rs"));
+
+#[test]
+fn displays_field() {
+    let user = User {
+        name: "ferris".to_string(),
+        id: 7,
+    };
+    assert_eq!(user.to_string(), "ferris");
+    assert_eq!(format!("[{:>8}]", user), "[  ferris]");
+}
+
+#[test]
+fn displays_default_of_unset_field() {
+    let mut score = Score::default();
+    assert_eq!(score.to_string(), "0");
+    score.points = Some(1.5);
+    assert_eq!(score.to_string(), "1.5");
+}
@@ -0,0 +1,26 @@
+mod original {
+    include!(concat!(env!("OUT_DIR"), "/enum_naming_original.rs"));
+}
+
+mod renamed {
+    include!(concat!(env!("OUT_DIR"), "/enum_naming_renamed.rs"));
+}
+
+#[test]
+fn test_original_names() {
+    use original::{Color, Paint};
+
+    assert_eq!(Color::COLOR_RED as i32, 1);
+    assert_eq!(Color::COLOR_RED.as_str_name(), "COLOR_RED");
+    assert_eq!(Paint::default().color(), Color::COLOR_RED);
+}
+
+#[test]
+fn test_renamed() {
+    use renamed::{Color, Paint};
+
+    assert_eq!(Color::Red as i32, 1);
+    assert_eq!(Color::Self_ as i32, 2);
+    assert_eq!(Color::from_str_name("kRed"), Some(Color::Red));
+    assert_eq!(Paint::default().color(), Color::Red);
+}
@@ -0,0 +1,125 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{Message, Oneof};
+
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(double, tag = "2")]
+    value: f64,
+    #[prost(float, optional, tag = "3")]
+    weight: Option<f32>,
+    #[prost(double, repeated, tag = "4")]
+    history: Vec<f64>,
+    #[prost(btree_map = "string, double", tag = "5")]
+    bounds: BTreeMap<String, f64>,
+    #[prost(message, optional, tag = "6")]
+    nested: Option<Box<Sample>>,
+    #[prost(message, repeated, tag = "7")]
+    children: Vec<Sample>,
+    #[prost(oneof = "Reading", tags = "8, 9")]
+    reading: Option<Reading>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+enum Reading {
+    #[prost(double, tag = "8")]
+    Celsius(f64),
+    #[prost(float, tag = "9")]
+    Fahrenheit(f32),
+}
+
+fn sample() -> Sample {
+    Sample {
+        name: "probe".to_string(),
+        value: f64::NAN,
+        weight: Some(f32::NAN),
+        history: vec![1.0, f64::NAN],
+        bounds: vec![("min".to_string(), f64::NAN), ("max".to_string(), 1.0)]
+            .into_iter()
+            .collect(),
+        nested: Some(Box::new(Sample {
+            value: f64::NAN,
+            ..Default::default()
+        })),
+        children: vec![Sample {
+            reading: Some(Reading::Fahrenheit(f32::NAN)),
+            ..Default::default()
+        }],
+        reading: Some(Reading::Celsius(f64::NAN)),
+    }
+}
+
+#[test]
+fn test_nan_values_are_equal() {
+    let sample = sample();
+    assert_ne!(sample.clone(), sample);
+    assert!(sample.eq_semantically(&sample));
+
+    let decoded = Sample::decode(sample.encode_to_vec().as_slice()).unwrap();
+    assert!(decoded.eq_semantically(&sample));
+
+    // NaNs with different payloads are equal too.
+    let mut other = sample.clone();
+    other.value = f64::from_bits(f64::NAN.to_bits() | 1);
+    assert_ne!(other.encode_to_vec(), sample.encode_to_vec());
+    assert!(other.eq_semantically(&sample));
+}
+
+#[test]
+fn test_different_values_are_not_equal() {
+    let sample = sample();
+    let changes: [fn(&mut Sample); 8] = [
+        |sample| sample.name.clear(),
+        |sample| sample.value = 0.0,
+        |sample| sample.weight = None,
+        |sample| sample.history.push(0.0),
+        |sample| {
+            sample.bounds.insert("max".to_string(), f64::NAN);
+        },
+        |sample| sample.nested.as_mut().unwrap().value = 1.0,
+        |sample| sample.children[0].reading = Some(Reading::Celsius(f64::NAN)),
+        |sample| sample.reading = None,
+    ];
+    for change in changes {
+        let mut other = sample.clone();
+        change(&mut other);
+        assert!(!other.eq_semantically(&sample));
+        assert!(!sample.eq_semantically(&other));
+    }
+
+    // Signed zeros are equal, as with `PartialEq`.
+    let mut other = sample.clone();
+    other.history[0] = 1.0;
+    other.nested.as_mut().unwrap().history = vec![-0.0];
+    let mut sample = sample;
+    sample.nested.as_mut().unwrap().history = vec![0.0];
+    assert!(other.eq_semantically(&sample));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_map_order_is_ignored() {
+    use std::collections::HashMap;
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Histogram {
+        #[prost(map = "string, float", tag = "1")]
+        buckets: HashMap<String, f32>,
+    }
+
+    let buckets = (0..32).map(|i| (i.to_string(), f32::NAN));
+    let a = Histogram {
+        buckets: buckets.clone().collect(),
+    };
+    let b = Histogram {
+        buckets: buckets.rev().collect(),
+    };
+    assert!(a.eq_semantically(&b));
+    assert!(!a.eq_semantically(&Histogram::default()));
+}
@@ -0,0 +1,63 @@
+use prost::ErasedMessage;
+use prost_types::{Duration, Timestamp};
+
+fn messages() -> Vec<Box<dyn ErasedMessage>> {
+    vec![
+        Box::new(Timestamp {
+            seconds: 1,
+            nanos: 2,
+        }),
+        Box::new(Duration {
+            seconds: 3,
+            nanos: 0,
+        }),
+    ]
+}
+
+#[test]
+fn erased_messages_encode() {
+    let messages = messages();
+    assert_eq!(
+        messages[0].type_url(),
+        "type.googleapis.com/google.protobuf.Timestamp"
+    );
+    assert_eq!(
+        messages[1].type_url(),
+        "type.googleapis.com/google.protobuf.Duration"
+    );
+    assert_eq!(messages[0].encode_to_vec(), [0x08, 0x01, 0x10, 0x02]);
+    assert_eq!(messages[1].encoded_len(), 2);
+}
+
+#[test]
+fn erased_messages_merge_and_clear() {
+    let mut messages = messages();
+    let encoded = messages[1].encode_to_vec();
+
+    messages[0].merge_from_slice(&encoded).unwrap();
+    assert_eq!(
+        messages[0].downcast_ref::<Timestamp>(),
+        Some(&Timestamp {
+            seconds: 3,
+            nanos: 2,
+        })
+    );
+    assert!(messages[0].merge_from_slice(&[0x08]).is_err());
+
+    messages[1].clear();
+    assert_eq!(messages[1].encoded_len(), 0);
+}
+
+#[test]
+fn erased_messages_clone_and_downcast() {
+    let messages = messages();
+    let mut cloned = messages.clone();
+
+    cloned[1].downcast_mut::<Duration>().unwrap().seconds = 4;
+    assert_eq!(messages[1].downcast_ref::<Duration>().unwrap().seconds, 3);
+    assert_eq!(cloned[1].downcast_ref::<Duration>().unwrap().seconds, 4);
+
+    assert!(cloned[0].is::<Timestamp>());
+    assert!(!cloned[0].is::<Duration>());
+    assert_eq!(cloned[0].downcast_ref::<Duration>(), None);
+}
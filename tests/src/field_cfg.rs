@@ -0,0 +1,49 @@
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/field_cfg.rs"));
+
+#[test]
+fn test_conditional_fields_are_skipped() {
+    // Only the unconditional fields and variants exist.
+    let account = Account {
+        name: "alice".to_string(),
+        owner: Some(account::Owner::User("alice".to_string())),
+    };
+    // The oneof of a conditional field is still generated, without its conditional variants.
+    let _: Option<account::InternalState> = None;
+
+    let mut full = FullAccount {
+        name: "alice".to_string(),
+        audit_log: "created".to_string(),
+        internal_labels: [("tier".to_string(), "gold".to_string())]
+            .iter()
+            .cloned()
+            .collect(),
+        owner: Some(full_account::Owner::User("alice".to_string())),
+        internal_state: Some(full_account::InternalState::Shard(3)),
+    };
+    assert_eq!(
+        Account::decode(full.encode_to_vec().as_slice()),
+        Ok(account)
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            Account::decode(full.encode_to_vec().as_slice()).unwrap()
+        ),
+        "Account { name: \"alice\", owner: Some(User(\"alice\")) }"
+    );
+
+    // Conditional variants are skipped like unknown fields, and leave the oneof empty.
+    full.owner = Some(full_account::Owner::ServiceAccount("builder".to_string()));
+    let decoded = Account::decode(full.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded.owner, None);
+    assert_eq!(
+        decoded.encode_to_vec(),
+        FullAccount {
+            name: "alice".to_string(),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    );
+}
@@ -0,0 +1,111 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::encoding::encode_key;
+use prost::{FieldIter, Message, RawField, WireType};
+
+#[derive(Clone, PartialEq, Message)]
+struct Event {
+    #[prost(uint32, tag = "1")]
+    kind: u32,
+    #[prost(string, tag = "2")]
+    source: String,
+    #[prost(message, optional, tag = "3")]
+    inner: Option<Inner>,
+    #[prost(group, optional, tag = "4")]
+    group: Option<Inner>,
+    #[prost(fixed64, tag = "5")]
+    id: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Inner {
+    #[prost(string, tag = "1")]
+    name: String,
+}
+
+fn event() -> Event {
+    Event {
+        kind: 300,
+        source: "host".to_string(),
+        inner: Some(Inner {
+            name: "a".to_string(),
+        }),
+        group: Some(Inner {
+            name: "b".to_string(),
+        }),
+        id: 7,
+    }
+}
+
+fn fields(buf: &[u8]) -> Vec<RawField<'_>> {
+    FieldIter::new(buf).collect::<Result<_, _>>().unwrap()
+}
+
+#[test]
+fn test_field_iter() {
+    let buf = event().encode_to_vec();
+    let fields = fields(&buf);
+
+    let tags: Vec<_> = fields.iter().map(|f| (f.tag, f.wire_type)).collect();
+    assert_eq!(
+        tags,
+        [
+            (1, WireType::Varint),
+            (2, WireType::LengthDelimited),
+            (3, WireType::LengthDelimited),
+            (4, WireType::StartGroup),
+            (5, WireType::SixtyFourBit),
+        ]
+    );
+    assert_eq!(fields[0].value, [0xac, 0x02]);
+    assert_eq!(fields[1].value, b"host");
+    assert_eq!(fields[4].value, 7u64.to_le_bytes());
+
+    // Fields are contiguous and cover the whole buffer.
+    let mut offset = 0;
+    for field in &fields {
+        assert_eq!(field.offset, offset);
+        assert_eq!(field.encoded, &buf[offset..offset + field.encoded.len()]);
+        offset += field.encoded.len();
+    }
+    assert_eq!(offset, buf.len());
+
+    // Nested messages and groups.
+    let inner = fields[2].nested().next().unwrap().unwrap();
+    assert_eq!((inner.tag, inner.value), (1, &b"a"[..]));
+    let group: Vec<_> = fields[3].nested().collect::<Result<_, _>>().unwrap();
+    assert_eq!(group.len(), 1);
+    assert_eq!((group[0].tag, group[0].value), (1, &b"b"[..]));
+    assert_eq!(fields[3].encoded.last(), Some(&0x24));
+}
+
+#[test]
+fn test_field_iter_errors() {
+    let buf = event().encode_to_vec();
+    let mut iter = FieldIter::new(&buf[..buf.len() - 1]);
+    assert_eq!(iter.by_ref().filter(Result::is_ok).count(), 4);
+    assert_eq!(iter.next(), None);
+
+    let mut iter = FieldIter::new(&buf[..buf.len() - 1]);
+    assert!(iter.nth(4).unwrap().is_err());
+    assert!(iter.next().is_none());
+
+    // Mismatched and stray end group keys.
+    let mut buf = Vec::new();
+    encode_key(4, WireType::StartGroup, &mut buf);
+    encode_key(5, WireType::EndGroup, &mut buf);
+    assert!(FieldIter::new(&buf).next().unwrap().is_err());
+    assert!(FieldIter::new(&buf[1..]).next().unwrap().is_err());
+
+    // Groups nested beyond the recursion limit.
+    let mut buf = vec![];
+    for _ in 0..200 {
+        encode_key(1, WireType::StartGroup, &mut buf);
+    }
+    for _ in 0..200 {
+        encode_key(1, WireType::EndGroup, &mut buf);
+    }
+    assert!(FieldIter::new(&buf).next().unwrap().is_err());
+}
@@ -0,0 +1,102 @@
+include!(concat!(env!("OUT_DIR"), "/field_values.rs"));
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{FieldValueRef, MessageFields};
+
+/// Collects the strings of the message and its nested messages, with the path of their fields.
+fn strings(message: &dyn MessageFields, prefix: &str, out: &mut Vec<(String, String)>) {
+    fn visit(value: &FieldValueRef<'_>, path: String, out: &mut Vec<(String, String)>) {
+        match value {
+            FieldValueRef::String(value) => out.push((path, value.to_string())),
+            FieldValueRef::Message(message) => strings(*message, &format!("{}.", path), out),
+            FieldValueRef::Repeated(values) => {
+                for value in values {
+                    visit(value, path.clone(), out);
+                }
+            }
+            FieldValueRef::Map(entries) => {
+                for (_, value) in entries {
+                    visit(value, path.clone(), out);
+                }
+            }
+            _ => {}
+        }
+    }
+    for (field, value) in message.field_values() {
+        visit(&value, format!("{}{}", prefix, field.name), out);
+    }
+}
+
+#[test]
+fn traverses_fields() {
+    let user = User {
+        name: "alice".to_string(),
+        age: Some(30),
+        role: Role::Admin as i32,
+        emails: vec!["alice@example.com".to_string()],
+        credentials: BTreeMap::from([(
+            "work".to_string(),
+            Credentials {
+                password: "hunter2".to_string(),
+                token: vec![1, 2],
+            },
+        )]),
+        primary: None,
+        created: Some(prost_types::Timestamp {
+            seconds: 1,
+            nanos: 0,
+        }),
+        nickname: Some("al".to_string()),
+        contact: Some(user::Contact::Phone("555".to_string())),
+    };
+
+    let fields = user
+        .field_values()
+        .map(|(field, _)| (field.name, field.tag))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fields,
+        [
+            ("name", 1),
+            ("age", 2),
+            ("role", 3),
+            ("emails", 4),
+            ("credentials", 5),
+            ("created", 7),
+            ("nickname", 8),
+            ("Phone", 9),
+        ]
+    );
+    let values = user
+        .field_values()
+        .map(|(_, value)| value)
+        .collect::<Vec<_>>();
+    assert!(matches!(values[1], FieldValueRef::U32(30)));
+    assert!(matches!(values[2], FieldValueRef::Enum(1)));
+
+    let mut out = Vec::new();
+    strings(&user, "", &mut out);
+    let out = out
+        .iter()
+        .map(|(path, value)| (path.as_str(), value.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        out,
+        [
+            ("name", "alice"),
+            ("emails", "alice@example.com"),
+            ("credentials.password", "hunter2"),
+            ("nickname.value", "al"),
+            ("Phone", "555"),
+        ]
+    );
+
+    // Unset optional fields and oneofs are skipped.
+    let empty = User::default();
+    assert_eq!(empty.field_values().len(), 4);
+}
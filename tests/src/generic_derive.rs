@@ -1,3 +1,12 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use prost::encoding::{double, string};
+use prost::Message;
+
 pub trait CustomType: prost::Message + Default {}
 
 impl CustomType for u64 {}
@@ -26,3 +35,70 @@ fn generic_enum() {
         GenericEnum::Number(_) => panic!("Not supposed to reach"),
     }
 }
+
+/// A marker type which is neither a message nor `Debug`.
+struct Celsius;
+
+#[derive(prost::Message)]
+struct Measurement<U: Send + Sync> {
+    #[prost(double, tag = "1")]
+    value: f64,
+    // Attributes of `PhantomData` fields are ignored.
+    #[prost(uint32, tag = "1")]
+    unit: PhantomData<U>,
+    #[prost(string, tag = "2")]
+    label: String,
+}
+
+#[derive(prost::Message)]
+struct Marked<U: Send + Sync>(
+    ::core::marker::PhantomData<U>,
+    #[prost(uint64, tag = "1")] u64,
+);
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct FixedArray<const N: usize> {
+    #[prost(int32, repeated, tag = "1")]
+    values: Vec<i32>,
+}
+
+impl<const N: usize> FixedArray<N> {
+    fn is_full(&self) -> bool {
+        self.values.len() == N
+    }
+}
+
+#[test]
+fn phantom_data_fields_are_skipped() {
+    let measurement = Measurement::<Celsius> {
+        value: 21.5,
+        unit: PhantomData,
+        label: "outside".to_string(),
+    };
+    let mut expected = Vec::new();
+    double::encode(1, &21.5, &mut expected);
+    string::encode(2, &"outside".to_string(), &mut expected);
+    assert_eq!(measurement.encode_to_vec(), expected);
+    let decoded = Measurement::<Celsius>::decode(expected.as_slice()).unwrap();
+    assert_eq!(decoded.value, 21.5);
+    assert_eq!(decoded.label, "outside");
+    assert_eq!(
+        format!("{:?}", measurement),
+        "Measurement { value: 21.5, label: \"outside\" }"
+    );
+    assert_eq!(Measurement::<Celsius>::default().label, "");
+
+    let marked = Marked::<Celsius>(PhantomData, 7);
+    assert_eq!(marked.encode_to_vec(), [8, 7]);
+    assert_eq!(Marked::<Celsius>::decode(&[8, 7][..]).unwrap().1, 7);
+    assert_eq!(Marked::<Celsius>::default().1, 0);
+}
+
+#[test]
+fn const_generic_messages() {
+    let array = FixedArray::<2> { values: vec![1, 2] };
+    assert!(array.is_full());
+    let decoded = FixedArray::<3>::decode(array.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded.values, array.values);
+    assert!(!decoded.is_full());
+}
@@ -0,0 +1,17 @@
+include!(concat!(env!("OUT_DIR"), "/ident_policy.rs"));
+
+use prost::Message;
+
+#[test]
+fn ident_policy_keeps_acronyms() {
+    let gateway = GRPCGateway {
+        upstream: Some(HTTPServer2 {
+            tls_config: Some(http_server2::TLSConfig {
+                cert_pem: "cert".to_string(),
+            }),
+            fallback_tls_configs: Vec::new(),
+        }),
+    };
+    let decoded = GRPCGateway::decode(gateway.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded, gateway);
+}
@@ -0,0 +1,34 @@
+use alloc::format;
+use alloc::string::ToString;
+
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/index_map.rs"));
+
+#[test]
+fn index_map_keeps_insertion_order() {
+    let mut settings = Settings::default();
+    settings.values.insert("z".to_string(), "last".to_string());
+    settings.values.insert("a".to_string(), "first".to_string());
+    settings.levels.insert(2, Level::High as i32);
+    settings.levels.insert(1, Level::Unspecified as i32);
+    settings
+        .sections
+        .insert("nested".to_string(), Settings::default());
+
+    let decoded = Settings::decode(settings.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded, settings);
+    assert!(decoded.values.keys().eq(["z", "a"].iter()));
+    assert!(decoded.levels.keys().eq([2, 1].iter()));
+}
+
+#[test]
+fn index_map_debug() {
+    let mut settings = Settings::default();
+    settings.levels.insert(2, Level::High as i32);
+    settings.levels.insert(1, 5);
+    assert_eq!(
+        format!("{:?}", settings),
+        "Settings { values: {}, levels: {2: High, 1: 5}, sections: {} }"
+    );
+}
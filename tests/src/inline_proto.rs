@@ -0,0 +1,49 @@
+use alloc::vec;
+
+use prost::Message;
+
+prost_macros::proto!(
+    r#"
+    syntax = "proto3";
+
+    package inline.shapes;
+
+    message Point {
+      int32 x = 1;
+      int32 y = 2;
+    }
+
+    message Shape {
+      repeated Point points = 1;
+      Kind kind = 2;
+
+      enum Kind {
+        KIND_UNSPECIFIED = 0;
+        KIND_POLYGON = 1;
+      }
+    }
+    "#
+);
+
+// The path is relative to the manifest of each of the test crates.
+prost_macros::proto!(path = "../tests/src/inline_proto.proto");
+
+#[test]
+fn inline_definitions() {
+    let shape = inline::shapes::Shape {
+        points: vec![inline::shapes::Point { x: 1, y: 2 }],
+        kind: inline::shapes::shape::Kind::Polygon as i32,
+    };
+    let buf = shape.encode_to_vec();
+    assert_eq!(
+        inline::shapes::Shape::decode(buf.as_slice()).unwrap(),
+        shape
+    );
+}
+
+#[test]
+fn definitions_from_file() {
+    let line = inline_file::Line { points: vec![3, 4] };
+    let buf = line.encode_to_vec();
+    assert_eq!(inline_file::Line::decode(buf.as_slice()).unwrap(), line);
+}
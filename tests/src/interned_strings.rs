@@ -0,0 +1,57 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+
+use prost::{DecodeContext, Extensions, Message, StringInterner};
+
+include!(concat!(env!("OUT_DIR"), "/interned_strings.rs"));
+
+fn event(label: &str, host: &str) -> LogEvent {
+    LogEvent {
+        label: label.into(),
+        tags: vec!["http".into(), label.into()],
+        note: None,
+        attributes: [(String::from("path"), String::from("/"))]
+            .into_iter()
+            .collect(),
+        source: Some(log_event::Source::Host(host.into())),
+    }
+}
+
+#[test]
+fn test_defaults() {
+    let event = LogEvent::default();
+    assert_eq!(&*event.label, "");
+    assert_eq!(event.note(), "none");
+
+    let event = LogEvent::decode(&[0x1a, 0x01, b'a'][..]).unwrap();
+    assert_eq!(event.note.as_deref(), Some("a"));
+}
+
+#[test]
+fn test_interned_strings() {
+    let first = event("request", "a").encode_to_vec();
+    let second = event("request", "a").encode_to_vec();
+
+    // Without an interner, every string is allocated separately.
+    let a = LogEvent::decode(&first[..]).unwrap();
+    let b = LogEvent::decode(&second[..]).unwrap();
+    assert_eq!(a, event("request", "a"));
+    assert!(!Arc::ptr_eq(&a.label, &b.label));
+
+    let mut extensions = Extensions::new();
+    extensions.set_interner(StringInterner::new());
+    let ctx = DecodeContext::new().with_extensions(&extensions);
+    let a = LogEvent::decode_with_context(&first[..], ctx.clone()).unwrap();
+    let b = LogEvent::decode_with_context(&second[..], ctx).unwrap();
+    assert_eq!(a, b);
+    assert!(Arc::ptr_eq(&a.label, &b.label));
+    assert!(Arc::ptr_eq(&a.label, &a.tags[1]));
+    assert!(Arc::ptr_eq(&a.tags[0], &b.tags[0]));
+    let (Some(log_event::Source::Host(a_host)), Some(log_event::Source::Host(b_host))) =
+        (&a.source, &b.source)
+    else {
+        panic!("missing host");
+    };
+    assert!(Arc::ptr_eq(a_host, b_host));
+}
@@ -0,0 +1,49 @@
+include!(concat!(env!("OUT_DIR"), "/json_unknown_fields.rs"));
+
+use prost::Message;
+
+const JSON: &str = r#"{
+    "name": "deploy",
+    "detail": {"level": 2, "color": "red"},
+    "legacy": {"level": 3, "color": "blue"},
+    "payload": {"@type": "type.googleapis.com/gateway.Payload", "id": 7},
+    "tags": ["a", "b"]
+}"#;
+
+#[test]
+fn retains_unknown_fields() {
+    let event = serde_json::from_str::<Event>(JSON).unwrap();
+    assert_eq!(event.name, "deploy");
+    assert_eq!(
+        serde_json::Value::Object(event.json_unknown_fields.clone()),
+        serde_json::json!({
+            "payload": {"@type": "type.googleapis.com/gateway.Payload", "id": 7},
+            "tags": ["a", "b"],
+        })
+    );
+    let detail = event.detail.as_ref().unwrap();
+    assert_eq!(detail.level, 2);
+    assert_eq!(detail.json_unknown_fields["color"], "red");
+
+    // Unknown fields of messages which don't retain them are dropped.
+    let mut expected = serde_json::from_str::<serde_json::Value>(JSON).unwrap();
+    expected["legacy"]
+        .as_object_mut()
+        .unwrap()
+        .remove("color")
+        .unwrap();
+    assert_eq!(serde_json::to_value(&event).unwrap(), expected);
+}
+
+#[test]
+fn unknown_fields_are_not_encoded() {
+    let mut event = serde_json::from_str::<Event>(JSON).unwrap();
+    let decoded = Event::decode(event.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded.name, "deploy");
+    assert!(decoded.json_unknown_fields.is_empty());
+    assert!(decoded.eq_semantically(&event));
+    assert_ne!(decoded, event);
+
+    event.clear();
+    assert_eq!(event, Event::default());
+}
@@ -0,0 +1,32 @@
+include!(concat!(env!("OUT_DIR"), "/json_value.rs"));
+
+use alloc::string::ToString;
+use alloc::vec;
+use core::convert::TryFrom;
+
+#[test]
+fn test_message_to_json_value() {
+    let mut settings = Settings {
+        name: "quota".to_string(),
+        limits: vec![settings::Limit {
+            resource: "cpu".to_string(),
+            max: 4,
+        }],
+        legacy_id: 7,
+        ..Default::default()
+    };
+    settings.labels.insert(2, "two".to_string());
+
+    let value: serde_json::Value = settings.clone().into();
+    assert_eq!(value["name"], "quota");
+    assert_eq!(value["labels"]["2"], "two");
+    assert_eq!(value["limits"][0]["max"], 4);
+    assert_eq!(value["LEGACY"], 7);
+    assert_eq!(Settings::try_from(value).unwrap(), settings);
+}
+
+#[test]
+fn test_message_from_invalid_json_value() {
+    let value = serde_json::from_str::<serde_json::Value>(r#"{"name":1}"#).unwrap();
+    assert!(Settings::try_from(value).is_err());
+}
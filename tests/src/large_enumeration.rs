@@ -0,0 +1,257 @@
+//! Tests for enumerations with enough variants to be converted with a lookup table.
+
+use core::convert::TryFrom;
+
+use prost::{Enumeration, UnknownEnumValue};
+
+#[derive(Clone, Copy, Debug, PartialEq, Enumeration)]
+#[repr(i32)]
+pub enum Sparse {
+    Sparse0 = -1813,
+    Sparse1 = -1776,
+    Sparse2 = -1739,
+    Sparse3 = -1702,
+    Sparse4 = -1665,
+    Sparse5 = -1628,
+    Sparse6 = -1591,
+    Sparse7 = -1554,
+    Sparse8 = -1517,
+    Sparse9 = -1480,
+    Sparse10 = -1443,
+    Sparse11 = -1406,
+    Sparse12 = -1369,
+    Sparse13 = -1332,
+    Sparse14 = -1295,
+    Sparse15 = -1258,
+    Sparse16 = -1221,
+    Sparse17 = -1184,
+    Sparse18 = -1147,
+    Sparse19 = -1110,
+    Sparse20 = -1073,
+    Sparse21 = -1036,
+    Sparse22 = -999,
+    Sparse23 = -962,
+    Sparse24 = -925,
+    Sparse25 = -888,
+    Sparse26 = -851,
+    Sparse27 = -814,
+    Sparse28 = -777,
+    Sparse29 = -740,
+    Sparse30 = -703,
+    Sparse31 = -666,
+    Sparse32 = -629,
+    Sparse33 = -592,
+    Sparse34 = -555,
+    Sparse35 = -518,
+    Sparse36 = -481,
+    Sparse37 = -444,
+    Sparse38 = -407,
+    Sparse39 = -370,
+    Sparse40 = -333,
+    Sparse41 = -296,
+    Sparse42 = -259,
+    Sparse43 = -222,
+    Sparse44 = -185,
+    Sparse45 = -148,
+    Sparse46 = -111,
+    Sparse47 = -74,
+    Sparse48 = -37,
+    Sparse49 = 0,
+    Sparse50 = 37,
+    Sparse51 = 74,
+    Sparse52 = 111,
+    Sparse53 = 148,
+    Sparse54 = 185,
+    Sparse55 = 222,
+    Sparse56 = 259,
+    Sparse57 = 296,
+    Sparse58 = 333,
+    Sparse59 = 370,
+    Sparse60 = 407,
+    Sparse61 = 444,
+    Sparse62 = 481,
+    Sparse63 = 518,
+    Sparse64 = 555,
+    Sparse65 = 592,
+    Sparse66 = 629,
+    Sparse67 = 666,
+    Sparse68 = 703,
+    Sparse69 = 740,
+    Sparse70 = 777,
+    Sparse71 = 814,
+    Sparse72 = 851,
+    Sparse73 = 888,
+    Sparse74 = 925,
+    Sparse75 = 962,
+    Sparse76 = 999,
+    Sparse77 = 1036,
+    Sparse78 = 1073,
+    Sparse79 = 1110,
+    Sparse80 = 1147,
+    Sparse81 = 1184,
+    Sparse82 = 1221,
+    Sparse83 = 1258,
+    Sparse84 = 1295,
+    Sparse85 = 1332,
+    Sparse86 = 1369,
+    Sparse87 = 1406,
+    Sparse88 = 1443,
+    Sparse89 = 1480,
+    Sparse90 = 1517,
+    Sparse91 = 1554,
+    Sparse92 = 1591,
+    Sparse93 = 1628,
+    Sparse94 = 1665,
+    Sparse95 = 1702,
+    Sparse96 = 1739,
+    Sparse97 = 1776,
+    Min = -2147483648,
+    Max = 2147483647,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Enumeration)]
+#[repr(i32)]
+pub enum Dense {
+    Dense0 = 0,
+    Dense1 = 1,
+    Dense2 = 2,
+    Dense3 = 3,
+    Dense4 = 4,
+    Dense5 = 5,
+    Dense6 = 6,
+    Dense7 = 7,
+    Dense8 = 8,
+    Dense9 = 9,
+    Dense10 = 10,
+    Dense11 = 11,
+    Dense12 = 12,
+    Dense13 = 13,
+    Dense14 = 14,
+    Dense15 = 15,
+    Dense16 = 16,
+    Dense17 = 17,
+    Dense18 = 18,
+    Dense19 = 19,
+    Dense20 = 20,
+    Dense21 = 21,
+    Dense22 = 22,
+    Dense23 = 23,
+    Dense24 = 24,
+    Dense25 = 25,
+    Dense26 = 26,
+    Dense27 = 27,
+    Dense28 = 28,
+    Dense29 = 29,
+    Dense30 = 30,
+    Dense31 = 31,
+    Dense32 = 32,
+    Dense33 = 33,
+    Dense34 = 34,
+    Dense35 = 35,
+    Dense36 = 36,
+    Dense37 = 37,
+    Dense38 = 38,
+    Dense39 = 39,
+    Dense40 = 40,
+    Dense41 = 41,
+    Dense42 = 42,
+    Dense43 = 43,
+    Dense44 = 44,
+    Dense45 = 45,
+    Dense46 = 46,
+    Dense47 = 47,
+    Dense48 = 48,
+    Dense49 = 49,
+    Dense50 = 50,
+    Dense51 = 51,
+    Dense52 = 52,
+    Dense53 = 53,
+    Dense54 = 54,
+    Dense55 = 55,
+    Dense56 = 56,
+    Dense57 = 57,
+    Dense58 = 58,
+    Dense59 = 59,
+    Dense60 = 60,
+    Dense61 = 61,
+    Dense62 = 62,
+    Dense63 = 63,
+    Dense64 = 64,
+    Dense65 = 65,
+    Dense66 = 66,
+    Dense67 = 67,
+    Dense68 = 68,
+    Dense69 = 69,
+    Hundreds1 = 100,
+    Hundreds2 = 200,
+    Hundreds3 = 300,
+    Hundreds4 = 400,
+    Hundreds5 = 500,
+    Hundreds6 = 600,
+    Hundreds7 = 700,
+    Hundreds8 = 800,
+    Hundreds9 = 900,
+    Hundreds10 = 1000,
+    Hundreds11 = 1100,
+    Hundreds12 = 1200,
+    Hundreds13 = 1300,
+    Hundreds14 = 1400,
+    Hundreds15 = 1500,
+    Hundreds16 = 1600,
+    Hundreds17 = 1700,
+    Hundreds18 = 1800,
+    Hundreds19 = 1900,
+    Hundreds20 = 2000,
+    Hundreds21 = 2100,
+    Hundreds22 = 2200,
+    Hundreds23 = 2300,
+    Hundreds24 = 2400,
+    Hundreds25 = 2500,
+    Hundreds26 = 2600,
+    Hundreds27 = 2700,
+    Hundreds28 = 2800,
+    Hundreds29 = 2900,
+    Hundreds30 = 3000,
+}
+
+fn check<E>(value: i32, valid: bool, is_valid: fn(i32) -> bool)
+where
+    E: TryFrom<i32, Error = UnknownEnumValue> + Into<i32> + core::fmt::Debug,
+{
+    assert_eq!(is_valid(value), valid, "{}", value);
+    match E::try_from(value) {
+        Ok(variant) => {
+            assert!(valid, "{}", value);
+            assert_eq!(variant.into(), value);
+        }
+        Err(err) => {
+            assert!(!valid, "{}", value);
+            assert_eq!(err, UnknownEnumValue(value));
+        }
+    }
+}
+
+#[test]
+fn sparse_lookup() {
+    for value in -2000..2000 {
+        let valid = value % 37 == 0 && (-49 * 37..=48 * 37).contains(&value);
+        check::<Sparse>(value, valid, Sparse::is_valid);
+    }
+    for value in [i32::MIN, i32::MIN + 1, i32::MAX - 1, i32::MAX] {
+        check::<Sparse>(
+            value,
+            value == i32::MIN || value == i32::MAX,
+            Sparse::is_valid,
+        );
+    }
+    assert_eq!(Sparse::default(), Sparse::Sparse0);
+}
+
+#[test]
+fn dense_lookup() {
+    for value in -10..4000 {
+        let valid = (0..70).contains(&value) || (value % 100 == 0 && (100..=3000).contains(&value));
+        check::<Dense>(value, valid, Dense::is_valid);
+    }
+    assert_eq!(Dense::try_from(2500), Ok(Dense::Hundreds25));
+}
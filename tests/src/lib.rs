@@ -17,9 +17,16 @@ cfg_if! {
         extern crate prost;
         extern crate prost_types;
         extern crate protobuf;
+        extern crate serde;
+        #[cfg(test)]
+        extern crate arrayvec;
         #[cfg(test)]
         extern crate prost_build;
         #[cfg(test)]
+        extern crate prost_macros;
+        #[cfg(test)]
+        extern crate serde_json;
+        #[cfg(test)]
         extern crate tempfile;
     }
 }
@@ -27,6 +34,7 @@ cfg_if! {
 pub mod decode_error;
 pub mod extern_paths;
 pub mod no_root_packages;
+pub mod no_std;
 pub mod packages;
 pub mod unittest;
 
@@ -56,9 +64,132 @@ mod type_names;
 #[cfg(test)]
 mod boxed_field;
 
+#[cfg(test)]
+mod newtype;
+
+#[cfg(test)]
+mod oneof_from;
+
+#[cfg(test)]
+mod oneof_merge;
+
+#[cfg(test)]
+mod interned_strings;
+
+#[cfg(all(test, feature = "std"))]
+mod wkt_as_std;
+
+#[cfg(test)]
+mod unwrapped_wrappers;
+
+#[cfg(test)]
+mod surgery;
+
+#[cfg(test)]
+mod field_iter;
+
+#[cfg(test)]
+mod size_profile;
+
+#[cfg(test)]
+mod enum_naming;
+
+#[cfg(test)]
+mod merged_packages;
+
+#[cfg(test)]
+mod ident_policy;
+
+#[cfg(test)]
+mod package_constants;
+
+#[cfg(test)]
+mod service_descriptors;
+
+#[cfg(test)]
+mod field_cfg;
+
+#[cfg(test)]
+mod map_default_values;
+
+#[cfg(test)]
+mod bounded_repeated;
+
+#[cfg(test)]
+mod message_set;
+
+#[cfg(test)]
+mod json_value;
+
+#[cfg(test)]
+mod crate_remap;
+
+#[cfg(test)]
+mod custom_types;
+
+#[cfg(test)]
+mod value_macros;
+
+#[cfg(test)]
+mod inline_proto;
+
+#[cfg(all(test, feature = "std"))]
+mod index_map;
+
+#[cfg(test)]
+mod const_tags;
+
+#[cfg(test)]
+mod oneof_attribute;
+
+#[cfg(test)]
+mod closed_enum;
+
+#[cfg(test)]
+mod prelude;
+
+#[cfg(test)]
+mod eq_semantically;
+
+#[cfg(test)]
+mod erased;
+
+#[cfg(test)]
+mod reserved;
+
 #[cfg(test)]
 mod custom_debug;
 
+#[cfg(test)]
+mod decode_context;
+
+#[cfg(test)]
+mod decode_observer;
+
+#[cfg(test)]
+mod adversarial;
+
+#[cfg(test)]
+mod large_enumeration;
+
+#[cfg(test)]
+mod presence_bitsets;
+
+#[cfg(test)]
+mod well_known_types_serde;
+
+#[cfg(test)]
+mod display_from_field;
+
+#[cfg(test)]
+mod json_unknown_fields;
+
+#[cfg(test)]
+mod convenience_conversions;
+
+#[cfg(test)]
+mod field_values;
+
 // Must be `pub` as doc tests are only executed on public types.
 pub mod disable_comments;
 
@@ -126,7 +257,7 @@ pub mod default_string_escape {
 use alloc::vec::Vec;
 
 use anyhow::anyhow;
-use prost::bytes::Buf;
+use prost::buf::Buf;
 
 use prost::Message;
 
@@ -185,6 +316,13 @@ where
         return RoundtripResult::Error(anyhow!(error));
     }
     let buf1 = buf1;
+    if encoded_len > all_types.encoded_len_upper_bound() {
+        return RoundtripResult::Error(anyhow!(
+            "encoded len ({}) exceeds encoded len upper bound ({})",
+            encoded_len,
+            all_types.encoded_len_upper_bound()
+        ));
+    }
     if encoded_len != buf1.len() {
         return RoundtripResult::Error(anyhow!(
             "expected encoded len ({}) did not match actual encoded len ({})",
@@ -231,6 +369,7 @@ where
     M: Message + Default + PartialEq,
 {
     let expected_len = msg.encoded_len();
+    assert!(expected_len <= msg.encoded_len_upper_bound());
 
     let mut buf = Vec::with_capacity(18);
     msg.encode(&mut buf).unwrap();
@@ -245,6 +384,13 @@ where
         buf.remaining()
     );
     assert_eq!(msg, &roundtrip);
+
+    // Decoding must not depend on how the buffer is split into chunks.
+    let encoded = msg.encode_to_vec();
+    for size in [1, 3] {
+        let chunks = encoded.chunks(size).collect::<Vec<_>>();
+        assert_eq!(msg, &M::decode_from_chunks(&chunks).unwrap());
+    }
 }
 
 /// Serialize from A should equal Serialize from B
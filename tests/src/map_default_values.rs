@@ -0,0 +1,33 @@
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/map_default_values.rs"));
+
+#[test]
+fn test_default_map_values_are_encoded() {
+    let mut maps = Maps::default();
+    maps.messages.insert("a".to_string(), Value::default());
+    maps.numbers.insert(1, 0);
+    maps.colors.insert(2, Color::Red as i32);
+
+    let encoded = maps.encode_to_vec();
+    assert_eq!(encoded.len(), maps.encoded_len());
+    assert_eq!(
+        encoded,
+        [
+            // messages: {1: "a", 2: {}}
+            0x0a, 0x05, 0x0a, 0x01, b'a', 0x12, 0x00, //
+            // numbers: {1: 1, 2: 0}
+            0x12, 0x04, 0x08, 0x01, 0x10, 0x00, //
+            // colors: {1: 2, 2: RED}
+            0x1a, 0x04, 0x08, 0x02, 0x10, 0x00,
+        ]
+    );
+    assert_eq!(Maps::decode(encoded.as_slice()), Ok(maps));
+}
+
+#[test]
+fn test_default_map_keys_are_omitted() {
+    let mut maps = Maps::default();
+    maps.numbers.insert(0, 0);
+    assert_eq!(maps.encode_to_vec(), [0x12, 0x02, 0x10, 0x00]);
+}
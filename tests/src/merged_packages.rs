@@ -0,0 +1,16 @@
+include!(concat!(env!("OUT_DIR"), "/merged_packages.rs"));
+
+#[test]
+fn merged_packages_share_a_module() {
+    let resource = Resource {
+        id: "alice".to_string(),
+        kind: Kind::User.into(),
+    };
+    let response = Response {
+        request: Some(Request {
+            resource: Some(resource.clone()),
+        }),
+        resources: vec![resource],
+    };
+    assert_eq!(response.resources[0].kind(), Kind::User);
+}
@@ -316,8 +316,294 @@ fn check_default_values() {
     assert_eq!(&default.bytes_buf.as_ref(), b"foo\0bar");
     assert_eq!(default.enumeration, BasicEnumeration::ONE as i32);
     assert_eq!(default.optional_enumeration, None);
-    assert_eq!(&default.repeated_enumeration, &[]);
+    assert_eq!(&default.repeated_enumeration, &[0i32; 0]);
     assert_eq!(0, default.encoded_len());
+
+    // `Bytes` defaults reference the literal instead of allocating.
+    let other = DefaultValues::default();
+    assert_eq!(default.bytes_buf.as_ptr(), other.bytes_buf.as_ptr());
+}
+
+#[test]
+fn check_clear_default_values() {
+    let mut msg = DefaultValues {
+        int32: 1,
+        optional_int32: Some(2),
+        string: "a string longer than the default".to_owned(),
+        bytes_vec: b"a buffer longer than the default".to_vec(),
+        bytes_buf: Bytes::from_static(b"other"),
+        enumeration: BasicEnumeration::TWO as i32,
+        optional_enumeration: Some(BasicEnumeration::THREE as i32),
+        repeated_enumeration: vec![BasicEnumeration::ONE as i32; 16],
+    };
+    let string_capacity = msg.string.capacity();
+    let bytes_vec_capacity = msg.bytes_vec.capacity();
+    let repeated_capacity = msg.repeated_enumeration.capacity();
+
+    msg.clear();
+    assert_eq!(msg, DefaultValues::default());
+    assert_eq!(0, msg.encoded_len());
+    // Cleared fields keep their allocations.
+    assert_eq!(msg.string.capacity(), string_capacity);
+    assert_eq!(msg.bytes_vec.capacity(), bytes_vec_capacity);
+    assert_eq!(msg.repeated_enumeration.capacity(), repeated_capacity);
+}
+
+#[test]
+fn check_bytes_accessors() {
+    let mut msg = DefaultValues::default();
+    assert_eq!(msg.bytes_buf(), b"foo\0bar");
+    msg.set_bytes_buf(&b"buf"[..]);
+    msg.set_bytes_vec(b"vec".to_vec());
+    assert_eq!(msg.bytes_buf(), b"buf");
+    assert_eq!(msg.bytes_vec(), b"vec");
+    assert_eq!(msg.take_bytes_buf(), Bytes::from_static(b"buf"));
+    assert_eq!(msg.take_bytes_vec(), b"vec");
+    assert_eq!(msg, DefaultValues::default());
+
+    let mut msg = ScalarTypes::default();
+    assert_eq!(msg.optional_bytes_buf(), b"");
+    msg.set_optional_bytes_buf(String::from("buf"));
+    assert_eq!(msg.optional_bytes_buf, Some(Bytes::from_static(b"buf")));
+    assert_eq!(msg.take_optional_bytes_buf(), Bytes::from_static(b"buf"));
+    assert_eq!(msg.optional_bytes_buf, None);
+    assert_eq!(msg.take_optional_bytes_vec(), b"");
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct UpperBound {
+    #[prost(int32, tag = "1")]
+    pub int32: i32,
+    #[prost(fixed64, optional, tag = "2")]
+    pub fixed64: Option<u64>,
+    #[prost(string, tag = "3")]
+    pub string: String,
+    #[prost(message, optional, tag = "4")]
+    pub nested: Option<RepeatedFloats>,
+}
+
+#[test]
+fn check_encoded_len_upper_bound() {
+    let empty = UpperBound::default();
+    assert_eq!(empty.encoded_len(), 0);
+    // Singular numeric fields are bounded by their maximum length regardless of value.
+    assert_eq!(empty.encoded_len_upper_bound(), (1 + 10) + (1 + 8));
+
+    let msg = UpperBound {
+        int32: -1,
+        fixed64: Some(7),
+        string: "hello".to_owned(),
+        nested: Some(RepeatedFloats {
+            single_float: 1.0,
+            repeated_float: vec![1.0, 2.0],
+        }),
+    };
+    assert_eq!(
+        msg.encoded_len_upper_bound(),
+        (1 + 10) + (1 + 8) + (1 + 1 + 5) + (1 + 1 + (1 + 4) + (2 + 1 + 8))
+    );
+    assert!(msg.encoded_len() <= msg.encoded_len_upper_bound());
+    check_message(&msg);
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Containers {
+    #[prost(string, tag = "1")]
+    pub boxed_str: Box<str>,
+    #[prost(string, optional, tag = "2", default = "foo")]
+    pub optional_boxed_str: Option<Box<str>>,
+    #[prost(string, repeated, tag = "3")]
+    pub repeated_boxed_str: Vec<Box<str>>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub boxed_bytes: Box<[u8]>,
+    #[prost(bytes = "vec", repeated, tag = "5")]
+    pub repeated_boxed_bytes: Vec<Box<[u8]>>,
+    #[prost(btree_map = "string, bytes", tag = "6")]
+    pub map: prost::alloc::collections::BTreeMap<Box<str>, Box<[u8]>>,
+    #[prost(message, optional, tag = "7")]
+    pub arc: Option<prost::alloc::sync::Arc<RepeatedFloats>>,
+    #[prost(message, repeated, tag = "8")]
+    pub cow: Vec<prost::alloc::borrow::Cow<'static, RepeatedFloats>>,
+    #[prost(oneof = "ContainersOneof", tags = "9")]
+    pub oneof: Option<ContainersOneof>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+pub enum ContainersOneof {
+    #[prost(string, tag = "9")]
+    BoxedStr(Box<str>),
+}
+
+#[test]
+fn check_containers() {
+    use prost::alloc::borrow::Cow;
+    use prost::alloc::sync::Arc;
+
+    static FLOATS: RepeatedFloats = RepeatedFloats {
+        single_float: 1.0,
+        repeated_float: Vec::new(),
+    };
+
+    let mut msg = Containers {
+        boxed_str: "hello".into(),
+        optional_boxed_str: Some("".into()),
+        repeated_boxed_str: vec!["a".into(), "".into()],
+        boxed_bytes: b"world".as_ref().into(),
+        repeated_boxed_bytes: vec![b"b".as_ref().into()],
+        map: [("key".into(), b"value".as_ref().into())]
+            .into_iter()
+            .collect(),
+        arc: Some(Arc::new(FLOATS.clone())),
+        cow: vec![
+            Cow::Borrowed(&FLOATS),
+            Cow::Owned(RepeatedFloats::default()),
+        ],
+        oneof: Some(ContainersOneof::BoxedStr("oneof".into())),
+    };
+    assert_eq!(msg.optional_boxed_str(), "");
+    check_message(&msg);
+
+    msg.clear();
+    assert_eq!(msg, Containers::default());
+    assert_eq!(msg.optional_boxed_str(), "foo");
+    assert_eq!(msg.encoded_len(), 0);
+}
+
+#[test]
+fn check_encode_into_slice() {
+    let msg = RepeatedFloats {
+        single_float: 1.0,
+        repeated_float: vec![2.0, 3.0],
+    };
+    let encoded = msg.encode_to_vec();
+
+    let mut buf = [0xff; 32];
+    assert_eq!(msg.encode_into_slice(&mut buf), Ok(encoded.len()));
+    assert_eq!(&buf[..encoded.len()], encoded.as_slice());
+    assert!(buf[encoded.len()..].iter().all(|&b| b == 0xff));
+
+    let mut short = [0xff; 4];
+    let err = msg.encode_into_slice(&mut short).unwrap_err();
+    assert_eq!(err.required_capacity(), encoded.len());
+    assert_eq!(err.remaining(), 4);
+    assert_eq!(short, [0xff; 4]);
+
+    let mut exact = vec![0; encoded.len()];
+    assert_eq!(msg.encode_into_slice(&mut exact), Ok(encoded.len()));
+    assert_eq!(exact, encoded);
+}
+
+#[test]
+fn check_clear_and_merge() {
+    let first = ScalarTypes {
+        string: "a longer string".to_owned(),
+        bytes_vec: vec![1; 16],
+        repeated_string: vec!["a".to_owned(), "b".to_owned()],
+        repeated_int32: vec![1, 2, 3, 4],
+        ..ScalarTypes::default()
+    };
+    let second = ScalarTypes {
+        string: "short".to_owned(),
+        repeated_int32: vec![5],
+        ..ScalarTypes::default()
+    };
+
+    let mut msg = ScalarTypes::default();
+    msg.clear_and_merge(first.encode_to_vec().as_slice())
+        .unwrap();
+    assert_eq!(msg, first);
+    let capacities = (
+        msg.string.capacity(),
+        msg.bytes_vec.capacity(),
+        msg.repeated_string.capacity(),
+        msg.repeated_int32.capacity(),
+    );
+
+    msg.clear_and_merge(second.encode_to_vec().as_slice())
+        .unwrap();
+    assert_eq!(msg, second);
+    assert_eq!(
+        (
+            msg.string.capacity(),
+            msg.bytes_vec.capacity(),
+            msg.repeated_string.capacity(),
+            msg.repeated_int32.capacity(),
+        ),
+        capacities
+    );
+}
+
+#[test]
+fn check_decode_partial() {
+    let msg = ScalarTypes {
+        int32: 7,
+        string: "a string which is cut off".to_owned(),
+        repeated_int32: vec![1, 2, 3],
+        ..ScalarTypes::default()
+    };
+    let encoded = msg.encode_to_vec();
+    assert_eq!(
+        ScalarTypes::decode_partial(&encoded).unwrap(),
+        (msg.clone(), encoded.len())
+    );
+
+    // Each prefix decodes to the fields it holds entirely.
+    let mut last = (ScalarTypes::default(), 0);
+    for len in 0..=encoded.len() {
+        let (partial, consumed) = ScalarTypes::decode_partial(&encoded[..len]).unwrap();
+        assert!(consumed <= len && consumed >= last.1);
+        assert_eq!(partial, ScalarTypes::decode(&encoded[..consumed]).unwrap());
+        last = (partial, consumed);
+    }
+    assert_eq!(last, (msg, encoded.len()));
+
+    // Invalid fields are still errors.
+    assert!(ScalarTypes::decode_partial(&[0x0f]).is_err());
+}
+
+/// A strongly typed wrapper, encoded exactly as the wrapped message.
+#[derive(Clone, PartialEq, Message)]
+#[prost(transparent)]
+pub struct Floats(RepeatedFloats);
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(transparent)]
+pub struct Wrapper<M: Message + Default>(M);
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Transparent {
+    #[prost(message, optional, tag = "1")]
+    pub floats: Option<Floats>,
+    #[prost(message, repeated, tag = "2")]
+    pub wrapped: Vec<Wrapper<Floats>>,
+}
+
+#[test]
+fn check_transparent() {
+    let inner = RepeatedFloats {
+        single_float: 1.0,
+        repeated_float: vec![2.0, 3.0],
+    };
+    let floats = Floats(inner.clone());
+    assert_eq!(floats.encode_to_vec(), inner.encode_to_vec());
+    assert_eq!(floats.encoded_len(), inner.encoded_len());
+    assert_eq!(Floats::decode(&*inner.encode_to_vec()).unwrap(), floats);
+    assert_eq!(Floats::default(), Floats(RepeatedFloats::default()));
+    assert_eq!(
+        format!("{:?}", floats),
+        format!("Floats({:?})", inner).as_str()
+    );
+    check_message(&floats);
+
+    let wrapped = Wrapper(floats.clone());
+    assert_eq!(wrapped.encode_to_vec(), inner.encode_to_vec());
+    check_message(&wrapped);
+
+    let msg = Transparent {
+        floats: Some(floats.clone()),
+        wrapped: vec![wrapped, Wrapper::default()],
+    };
+    check_message(&msg);
 }
 
 /// A protobuf enum.
@@ -330,6 +616,49 @@ pub enum BasicEnumeration {
     THREE = 3,
 }
 
+/// An enum with negative values and values at the limits of `i32`.
+#[derive(Clone, Copy, Debug, PartialEq, Enumeration)]
+#[repr(i32)]
+pub enum SignedEnumeration {
+    Zero = 0,
+    Min = -2147483648,
+    Max = 2147483647,
+    MinusThree = -3,
+    MinusTwo = -2,
+    MinusOne = -1,
+    One = 1,
+    Hundred = 100,
+}
+
+#[test]
+fn check_signed_enumeration() {
+    use core::convert::TryFrom;
+
+    let variants = [
+        SignedEnumeration::Zero,
+        SignedEnumeration::Min,
+        SignedEnumeration::Max,
+        SignedEnumeration::MinusThree,
+        SignedEnumeration::MinusTwo,
+        SignedEnumeration::MinusOne,
+        SignedEnumeration::One,
+        SignedEnumeration::Hundred,
+    ];
+    for variant in variants {
+        let value = i32::from(variant);
+        assert!(SignedEnumeration::is_valid(value));
+        assert_eq!(SignedEnumeration::try_from(value), Ok(variant));
+    }
+    for value in [i32::MIN + 1, i32::MAX - 1, -4, 2, 99, 101] {
+        assert!(!SignedEnumeration::is_valid(value));
+        assert_eq!(
+            SignedEnumeration::try_from(value),
+            Err(prost::UnknownEnumValue(value))
+        );
+    }
+    assert_eq!(SignedEnumeration::default(), SignedEnumeration::Zero);
+}
+
 #[derive(Clone, PartialEq, Message)]
 pub struct Basic {
     #[prost(int32, tag = "1")]
@@ -395,3 +724,19 @@ pub enum BasicOneof {
     #[prost(string, tag = "9")]
     String(String),
 }
+
+#[test]
+#[cfg(feature = "std")]
+fn check_assert_roundtrip_hash_maps() {
+    // Decoded maps iterate, and so encode, in another order than the original ones.
+    let basic = Basic {
+        string_map: (0..32).map(|i| (i.to_string(), i.to_string())).collect(),
+        enumeration_map: (0..32).map(|i| (i, BasicEnumeration::TWO as i32)).collect(),
+        ..Basic::default()
+    };
+    let compound = Compound {
+        message_map: (0..32).map(|i| (i, basic.clone())).collect(),
+        ..Compound::default()
+    };
+    prost::assert_roundtrip!(basic, compound);
+}
@@ -0,0 +1,111 @@
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/message_set.rs"));
+
+/// A message set with the extensions it knows about.
+#[derive(Clone, PartialEq, Message)]
+#[prost(message_set_wire_format)]
+struct Extensions {
+    #[prost(message, optional, tag = "100")]
+    payload: Option<Payload>,
+    #[prost(message, optional, boxed, tag = "200")]
+    other: Option<Box<Payload>>,
+}
+
+/// The same message set, spelling out the items of its wire format.
+#[derive(Clone, PartialEq, Message)]
+struct Items {
+    #[prost(group, repeated, tag = "1")]
+    item: Vec<Item>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Item {
+    #[prost(int32, required, tag = "2")]
+    type_id: i32,
+    #[prost(bytes, required, tag = "3")]
+    message: Vec<u8>,
+}
+
+fn payload(name: &str) -> Payload {
+    Payload {
+        name: Some(name.to_string()),
+    }
+}
+
+fn item(type_id: i32, msg: &impl Message) -> Item {
+    Item {
+        type_id,
+        message: msg.encode_to_vec(),
+    }
+}
+
+#[test]
+fn test_message_set_items() {
+    let extensions = Extensions {
+        payload: Some(payload("a")),
+        other: Some(Box::new(payload("b"))),
+    };
+    let encoded = extensions.encode_to_vec();
+    assert_eq!(encoded.len(), extensions.encoded_len());
+    assert!(encoded.len() <= extensions.encoded_len_upper_bound());
+
+    let items = Items {
+        item: [item(100, &payload("a")), item(200, &payload("b"))].to_vec(),
+    };
+    assert_eq!(encoded, items.encode_to_vec());
+    assert_eq!(Extensions::decode(encoded.as_slice()), Ok(extensions));
+}
+
+#[test]
+fn test_message_set_merge() {
+    // Items of the same type are merged, and unknown items are skipped.
+    let items = Items {
+        item: [
+            item(100, &payload("a")),
+            item(300, &payload("unknown")),
+            item(100, &Payload { name: None }),
+        ]
+        .to_vec(),
+    };
+    let mut encoded = items.encode_to_vec();
+    // Extensions encoded as regular fields are accepted too.
+    ::prost::encoding::message::encode(200, &payload("b"), &mut encoded);
+
+    let extensions = Extensions::decode(encoded.as_slice()).unwrap();
+    assert_eq!(extensions.payload, Some(payload("a")));
+    assert_eq!(extensions.other, Some(Box::new(payload("b"))));
+
+    let items = Items {
+        item: [Item {
+            type_id: 100,
+            message: [0x0a, 0x01].to_vec(),
+        }]
+        .to_vec(),
+    };
+    assert_eq!(
+        Extensions::decode(items.encode_to_vec().as_slice())
+            .unwrap_err()
+            .to_string(),
+        "failed to decode Protobuf message: Extensions.payload: delimited length exceeded"
+    );
+}
+
+#[test]
+fn test_generated_message_set() {
+    let items = Items {
+        item: [item(100, &payload("a"))].to_vec(),
+    };
+    // Extensions are not generated, so all the items are unknown.
+    let message_set = MessageSet::decode(items.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(message_set, MessageSet::default());
+    assert_eq!(message_set.encoded_len(), 0);
+
+    // An item which is not a group is invalid.
+    let invalid = [0x0a, 0x00];
+    assert!(MessageSet::decode(&invalid[..]).is_err());
+}
@@ -0,0 +1,56 @@
+include!(concat!(env!("OUT_DIR"), "/newtype.rs"));
+
+use alloc::string::ToString;
+use prost::Message;
+
+#[test]
+fn test_newtype_fields() {
+    let user = User {
+        id: UserId("alice".to_string()),
+        name: "Alice".to_string(),
+        account_id: AccountId(42),
+        referrer_id: Some(AccountId(7)),
+    };
+    let session = user::Session {
+        user_id: user.id.clone(),
+    };
+    assert_eq!(session.user_id, user.id);
+}
+
+#[test]
+fn test_newtype_roundtrip() {
+    let user = User {
+        id: "alice".to_string().into(),
+        name: "Alice".to_string(),
+        account_id: 42.into(),
+        referrer_id: Some(AccountId(7)),
+    };
+    let encoded = user.encode_to_vec();
+    assert_eq!(User::decode(encoded.as_slice()).unwrap(), user);
+
+    // The wire format is the same as for the unwrapped fields.
+    #[derive(Clone, PartialEq, Message)]
+    struct PlainUser {
+        #[prost(string, tag = "1")]
+        id: alloc::string::String,
+        #[prost(string, tag = "2")]
+        name: alloc::string::String,
+        #[prost(uint64, tag = "3")]
+        account_id: u64,
+        #[prost(uint64, optional, tag = "4")]
+        referrer_id: Option<u64>,
+    }
+    let plain = PlainUser::decode(encoded.as_slice()).unwrap();
+    assert_eq!(plain.id, "alice");
+    assert_eq!(plain.account_id, 42);
+    assert_eq!(plain.referrer_id, Some(7));
+    assert_eq!(plain.encode_to_vec(), encoded);
+}
+
+#[test]
+fn test_newtype_default() {
+    let user = User::default();
+    assert_eq!(user.id, UserId::default());
+    assert_eq!(u64::from(user.account_id), 0);
+    assert_eq!(user.encoded_len(), 0);
+}
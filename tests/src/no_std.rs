@@ -0,0 +1,45 @@
+//! Tests code generated with `no_std`, which is also checked without the standard library by the
+//! `tests-no-std` crate.
+
+include!(concat!(env!("OUT_DIR"), "/no_std.rs"));
+
+#[test]
+fn test() {
+    use alloc::collections::BTreeMap;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use core::time::Duration;
+    use prost::Message;
+
+    let mut device = Device {
+        name: "sensor".to_string(),
+        uptime: Some(Duration::from_secs(5)),
+        firmware: vec![1, 2],
+        status: Status::Online as i32,
+        legacy_id: 7,
+        address: Some(device::Address::Port(8080)),
+        ..Default::default()
+    };
+    // Map fields are generated as `BTreeMap`, and timestamps keep the `prost_types` type.
+    let counters: &mut BTreeMap<String, i64> = &mut device.counters;
+    counters.insert("restarts".to_string(), 2);
+    counters.insert("errors".to_string(), 1);
+    let event = Event {
+        at: Some(prost_types::Timestamp {
+            seconds: 1,
+            nanos: 0,
+        }),
+        device: Some(device.clone()),
+    };
+    assert_eq!(
+        Event::decode(event.encode_to_vec().as_slice()).unwrap(),
+        event
+    );
+
+    let json = serde_json::to_string(&device).unwrap();
+    assert_eq!(
+        json,
+        r#"{"name":"sensor","counters":{"errors":1,"restarts":2},"uptime":{"secs":5,"nanos":0},"firmware":[1,2],"status":1,"LEGACY":7,"address":{"port":8080}}"#
+    );
+    assert_eq!(serde_json::from_str::<Device>(&json).unwrap(), device);
+}
@@ -0,0 +1,34 @@
+use alloc::collections::BTreeSet;
+use alloc::string::ToString;
+
+include!(concat!(env!("OUT_DIR"), "/oneof_attribute.rs"));
+
+#[test]
+fn test_oneof_attribute() {
+    // Deriving `Ord` on `Color` a second time would not compile, so the attributes of the package
+    // path are only added to the oneofs.
+    let mut kinds = BTreeSet::new();
+    kinds.insert(shape::Kind::Sides(4));
+    kinds.insert(shape::Kind::Name("square".to_string()));
+    kinds.insert(shape::Kind::Sides(4));
+    assert_eq!(kinds.len(), 2);
+
+    let mut values = BTreeSet::new();
+    values.insert(shape::fill::Value::Color(Color::Red as i32));
+    values.insert(shape::fill::Value::Pattern("dots".to_string()));
+    assert_eq!(values.len(), 2);
+
+    // The message containing the oneof is not affected.
+    let fill = shape::Fill {
+        value: values.into_iter().next(),
+    };
+    assert_eq!(
+        fill.value,
+        Some(shape::fill::Value::Color(Color::Red as i32))
+    );
+}
+
+#[test]
+fn test_oneof_attribute_path() {
+    assert!(shape::Kind::Name("square".to_string()) < shape::Kind::Sides(4));
+}
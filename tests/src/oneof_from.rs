@@ -0,0 +1,26 @@
+include!(concat!(env!("OUT_DIR"), "/oneof_from.rs"));
+
+#[test]
+fn test_oneof_from_variant() {
+    let circle = Circle { radius: 1.0 };
+    let kind: shape::Kind = circle.into();
+    assert_eq!(kind, shape::Kind::Circle(circle));
+
+    let kind = shape::Kind::from(Color::Red);
+    assert_eq!(kind, shape::Kind::Color(Color::Red as i32));
+}
+
+#[test]
+fn test_message_from_variant() {
+    let square = Square { side: 2.0 };
+    let shape = Shape::from(square);
+    assert_eq!(shape.kind, Some(shape::Kind::Square(square)));
+    assert_eq!(shape.name, "");
+}
+
+#[test]
+fn test_message_from_boxed_variant() {
+    let leaf = Recursive::from(3);
+    let node = recursive::Node::from(leaf.clone());
+    assert_eq!(node, recursive::Node::Child(alloc::boxed::Box::new(leaf)));
+}
@@ -0,0 +1,59 @@
+use alloc::string::{String, ToString};
+
+use prost::encoding::{DecodeContext, WireType};
+use prost::{Message, Oneof};
+
+#[derive(Clone, PartialEq, Oneof)]
+enum Kind {
+    #[prost(int32, tag = "1")]
+    Number(i32),
+    #[prost(string, tag = "2")]
+    Text(String),
+}
+
+/// A message whose `tags` attribute lists a tag which is not a variant of the oneof.
+#[derive(Clone, PartialEq, Message)]
+struct Mismatched {
+    #[prost(oneof = "Kind", tags = "1, 2, 3")]
+    kind: Option<Kind>,
+}
+
+#[test]
+fn test_oneof_merge_foreign_tag() {
+    let mut field = None;
+    let error = Kind::merge(
+        &mut field,
+        3,
+        WireType::Varint,
+        &mut [0x01].as_slice(),
+        DecodeContext::default(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: invalid Kind tag: 3"
+    );
+    assert_eq!(field, None);
+
+    Kind::merge(
+        &mut field,
+        1,
+        WireType::Varint,
+        &mut [0x2a].as_slice(),
+        DecodeContext::default(),
+    )
+    .unwrap();
+    assert_eq!(field, Some(Kind::Number(42)));
+}
+
+#[test]
+fn test_message_merge_foreign_oneof_tag() {
+    let error = Mismatched::decode([0x18, 0x01].as_slice()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: Mismatched.kind: invalid Kind tag: 3"
+    );
+
+    let msg = Mismatched::decode([0x12, 0x02, b'h', b'i'].as_slice()).unwrap();
+    assert_eq!(msg.kind, Some(Kind::Text("hi".to_string())));
+}
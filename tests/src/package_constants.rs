@@ -0,0 +1,9 @@
+include!(concat!(env!("OUT_DIR"), "/package_constants.rs"));
+
+#[test]
+fn test_package_constants() {
+    assert_eq!(PACKAGE, "package_constants");
+    assert_eq!(PROTO_FILES, ["package_constants.proto"]);
+    assert_eq!(SYNTAX, "proto3");
+    let _ = Schema::default();
+}
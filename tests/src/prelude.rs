@@ -0,0 +1,15 @@
+include!(concat!(env!("OUT_DIR"), "/prelude/_include.rs"));
+
+#[test]
+fn test_prelude() {
+    use self::prelude::*;
+    use prost::Message;
+
+    let widget_factory = WidgetFactory {
+        root: Some(Root {}),
+        widget: Some(Widget {}),
+        gizmo: Some(Gizmo {}),
+        ..Default::default()
+    };
+    assert_eq!(6, widget_factory.encoded_len());
+}
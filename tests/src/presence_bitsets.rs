@@ -0,0 +1,91 @@
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use prost::encoding::{double, int32};
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/presence_bitsets.rs"));
+
+#[test]
+fn accessors() {
+    let mut reading = Reading::default();
+    assert!(!reading.has_temperature());
+    assert_eq!(reading.temperature(), 0.0);
+    assert_eq!(reading.count(), 7);
+    assert_eq!(reading.level(), Level::High);
+
+    reading.set_temperature(21.5);
+    reading.set_level(Level::Low);
+    assert!(reading.has_temperature());
+    assert_eq!(reading.temperature(), 21.5);
+    assert!(reading.has_level());
+    assert_eq!(reading.level(), Level::Low);
+    assert!(!reading.has_count());
+
+    reading.clear_level();
+    assert!(!reading.has_level());
+    assert_eq!(reading.level(), Level::High);
+
+    // Messages without a presence bitset keep their `Option` fields.
+    let legacy = Legacy {
+        temperature: Some(21.5),
+    };
+    assert_eq!(legacy.temperature, Some(21.5));
+}
+
+#[test]
+fn encodes_set_fields() {
+    assert!(Reading::default().encode_to_vec().is_empty());
+
+    // Set fields are encoded, even with their default value.
+    let mut reading = Reading::default();
+    reading.set_count(7);
+    reading.set_temperature(21.5);
+    let mut expected = Vec::new();
+    double::encode(1, &21.5, &mut expected);
+    int32::encode(2, &7, &mut expected);
+    assert_eq!(reading.encode_to_vec(), expected);
+    assert_eq!(reading.encoded_len(), expected.len());
+
+    let decoded = Reading::decode(expected.as_slice()).unwrap();
+    assert_eq!(decoded, reading);
+    assert!(decoded.has_count());
+    assert!(!decoded.has_valid());
+
+    reading.clear();
+    assert_eq!(reading, Reading::default());
+}
+
+#[test]
+fn skips_unknown_enum_values() {
+    let mut buf = Vec::new();
+    int32::encode(4, &3, &mut buf);
+    let reading = Reading::decode(buf.as_slice()).unwrap();
+    assert!(!reading.has_level());
+    assert_eq!(reading.level(), Level::High);
+}
+
+#[test]
+fn presence_is_compared() {
+    let mut reading = Reading::default();
+    reading.set_count(7);
+    assert_eq!(reading.count(), Reading::default().count());
+    assert_ne!(reading, Reading::default());
+    assert!(!reading.eq_semantically(&Reading::default()));
+
+    reading.clear_count();
+    assert!(reading.eq_semantically(&Reading::default()));
+}
+
+#[test]
+fn debug_formats_options() {
+    let mut reading = Reading::default();
+    reading.set_level(Level::Low);
+    reading.label = Some("outside".to_string());
+    assert_eq!(
+        format!("{:?}", reading),
+        "Reading { temperature: None, count: None, valid: None, level: Some(Low), \
+         label: Some(\"outside\"), samples: [] }"
+    );
+}
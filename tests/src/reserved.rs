@@ -0,0 +1,57 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use prost::encoding::{encode_key, encode_varint, WireType};
+use prost::Message as _;
+
+mod reserved {
+    include!(concat!(env!("OUT_DIR"), "/reserved.rs"));
+}
+
+mod reserved_callback {
+    include!(concat!(env!("OUT_DIR"), "/reserved_callback.rs"));
+}
+
+static RESERVED_FIELDS: AtomicU32 = AtomicU32::new(0);
+
+pub fn count_reserved_field(message: &'static str, tag: u32) {
+    assert_eq!(message, "Message");
+    assert!(tag >= 2);
+    RESERVED_FIELDS.fetch_add(1, Ordering::SeqCst);
+}
+
+fn encode_varint_field(tag: u32) -> alloc::vec::Vec<u8> {
+    let mut buf = alloc::vec::Vec::new();
+    encode_key(tag, WireType::Varint, &mut buf);
+    encode_varint(42, &mut buf);
+    buf
+}
+
+#[test]
+fn test_reserved_field_is_rejected() {
+    for tag in [2, 9, 10, 11] {
+        let buf = encode_varint_field(tag);
+        assert!(reserved::Message::decode(buf.as_slice()).is_err());
+    }
+}
+
+#[test]
+fn test_unreserved_unknown_field_is_skipped() {
+    for tag in [3, 8, 12] {
+        let buf = encode_varint_field(tag);
+        assert_eq!(
+            reserved::Message::decode(buf.as_slice()).unwrap(),
+            reserved::Message::default()
+        );
+    }
+}
+
+#[test]
+fn test_reserved_field_callback() {
+    let before = RESERVED_FIELDS.load(Ordering::SeqCst);
+    let buf = encode_varint_field(1000);
+    assert_eq!(
+        reserved_callback::Message::decode(buf.as_slice()).unwrap(),
+        reserved_callback::Message::default()
+    );
+    assert_eq!(RESERVED_FIELDS.load(Ordering::SeqCst), before + 1);
+}
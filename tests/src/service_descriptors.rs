@@ -0,0 +1,68 @@
+include!(concat!(env!("OUT_DIR"), "/service_descriptors.rs"));
+
+#[test]
+fn test_service_descriptor() {
+    assert_eq!(gateway_descriptor::NAME, "service_descriptors.Gateway");
+    assert_eq!(
+        gateway_descriptor::METHOD_NAMES,
+        ["Get", "Watch", "Upload", "Type"]
+    );
+    assert_eq!(
+        gateway_descriptor::METHOD_PATHS,
+        [
+            "/service_descriptors.Gateway/Get",
+            "/service_descriptors.Gateway/Watch",
+            "/service_descriptors.Gateway/Upload",
+            "/service_descriptors.Gateway/Type",
+        ]
+    );
+}
+
+#[test]
+fn test_method_descriptors() {
+    use gateway_descriptor::{get, r#type, upload, watch};
+
+    assert_eq!(get::NAME, "Get");
+    assert_eq!(get::PATH, "/service_descriptors.Gateway/Get");
+    assert_eq!(
+        get::INPUT_TYPE_URL,
+        "type.googleapis.com/service_descriptors.Request"
+    );
+    assert_eq!(
+        get::OUTPUT_TYPE_URL,
+        "type.googleapis.com/service_descriptors.Response"
+    );
+    assert_eq!(
+        (get::CLIENT_STREAMING, get::SERVER_STREAMING),
+        (false, false)
+    );
+
+    assert_eq!(watch::NAME, "Watch");
+    assert_eq!(watch::PATH, "/service_descriptors.Gateway/Watch");
+    assert_eq!(watch::INPUT_TYPE_URL, get::INPUT_TYPE_URL);
+    assert_eq!(watch::OUTPUT_TYPE_URL, get::OUTPUT_TYPE_URL);
+    assert_eq!(
+        (watch::CLIENT_STREAMING, watch::SERVER_STREAMING),
+        (false, true)
+    );
+
+    assert_eq!(upload::NAME, "Upload");
+    assert_eq!(upload::PATH, "/service_descriptors.Gateway/Upload");
+    assert_eq!(upload::INPUT_TYPE_URL, get::INPUT_TYPE_URL);
+    assert_eq!(upload::OUTPUT_TYPE_URL, get::OUTPUT_TYPE_URL);
+    assert_eq!(
+        (upload::CLIENT_STREAMING, upload::SERVER_STREAMING),
+        (true, false)
+    );
+
+    // Methods named after Rust keywords get raw identifiers.
+    assert_eq!(r#type::NAME, "Type");
+    assert_eq!(r#type::PATH, "/service_descriptors.Gateway/Type");
+    assert_eq!(r#type::INPUT_TYPE_URL, get::INPUT_TYPE_URL);
+    assert_eq!(r#type::OUTPUT_TYPE_URL, get::OUTPUT_TYPE_URL);
+    assert_eq!(
+        (r#type::CLIENT_STREAMING, r#type::SERVER_STREAMING),
+        (false, false)
+    );
+    let _ = (Request::default(), Response::default());
+}
@@ -0,0 +1,114 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::analyze::{size_profile, FieldSize};
+use prost::encoding::{encode_key, encode_varint};
+use prost::{FieldIter, Message, WireType};
+
+#[derive(Clone, PartialEq, Message)]
+struct Batch {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(message, repeated, tag = "2")]
+    records: Vec<Record>,
+    #[prost(uint32, repeated, tag = "3")]
+    ids: Vec<u32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Record {
+    #[prost(string, tag = "1")]
+    key: String,
+    #[prost(bytes = "vec", tag = "2")]
+    value: Vec<u8>,
+}
+
+fn batch() -> Batch {
+    Batch {
+        name: "batch".to_string(),
+        records: vec![
+            Record {
+                key: "a".to_string(),
+                value: vec![0; 100],
+            },
+            Record {
+                key: "b".to_string(),
+                value: vec![0; 10],
+            },
+        ],
+        ids: vec![1, 300],
+    }
+}
+
+#[test]
+fn test_size_profile() {
+    let buf = batch().encode_to_vec();
+    let profile = size_profile::<Batch>(&buf).unwrap();
+
+    assert_eq!(
+        profile.get(1),
+        Some(FieldSize {
+            occurrences: 1,
+            len: 7,
+        })
+    );
+    // Each record has a key of 3 bytes, and a value of 2 + 100 and 2 + 10 bytes.
+    assert_eq!(
+        profile.get(2),
+        Some(FieldSize {
+            occurrences: 2,
+            len: 2 + 105 + 2 + 15,
+        })
+    );
+    assert_eq!(
+        profile.get(3),
+        Some(FieldSize {
+            occurrences: 1,
+            len: 5,
+        })
+    );
+    assert_eq!(profile.get(4), None);
+    assert_eq!(
+        profile.iter().map(|(tag, _)| tag).collect::<Vec<_>>(),
+        [1, 2, 3]
+    );
+    assert_eq!(profile.len(), buf.len());
+}
+
+#[test]
+fn test_size_profile_nested() {
+    let buf = batch().encode_to_vec();
+    let record = FieldIter::new(&buf)
+        .map(Result::unwrap)
+        .find(|field| field.tag == 2)
+        .unwrap();
+
+    let profile = size_profile::<Record>(record.value).unwrap();
+    assert_eq!(profile.get(1).unwrap().len, 3);
+    assert_eq!(profile.get(2).unwrap().len, 102);
+}
+
+#[test]
+fn test_size_profile_unknown_fields() {
+    let mut buf = batch().encode_to_vec();
+    encode_key(9, WireType::Varint, &mut buf);
+    encode_varint(1, &mut buf);
+
+    let profile = size_profile::<Batch>(&buf).unwrap();
+    assert_eq!(
+        profile.get(9),
+        Some(FieldSize {
+            occurrences: 1,
+            len: 2,
+        })
+    );
+    assert_eq!(profile.len(), buf.len());
+}
+
+#[test]
+fn test_size_profile_invalid() {
+    let buf = batch().encode_to_vec();
+    assert!(size_profile::<Batch>(&buf[..buf.len() - 1]).is_err());
+    assert!(size_profile::<Batch>(&[]).unwrap().is_empty());
+}
@@ -0,0 +1,112 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::encoding::{int32, message, string, DecodeContext, WireType};
+use prost::surgery::{read_field, remove_field, replace_field};
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+struct Request {
+    #[prost(string, tag = "1")]
+    route: String,
+    #[prost(int32, repeated, packed = "false", tag = "2")]
+    ids: Vec<i32>,
+    #[prost(message, optional, boxed, tag = "3")]
+    inner: Option<Box<Request>>,
+    #[prost(bytes = "vec", tag = "4")]
+    payload: Vec<u8>,
+}
+
+fn request() -> Request {
+    Request {
+        route: "a".to_string(),
+        ids: vec![1, 2],
+        inner: Some(Box::new(Request {
+            route: "b".to_string(),
+            ..Request::default()
+        })),
+        payload: vec![0; 64],
+    }
+}
+
+#[test]
+fn test_read_field() {
+    let buf = request().encode_to_vec();
+
+    let (wire_type, mut value) = read_field(&buf, 1).unwrap().unwrap();
+    let mut route = String::new();
+    string::merge(wire_type, &mut route, &mut value, DecodeContext::default()).unwrap();
+    assert_eq!(route, "a");
+
+    // The last occurrence of a repeated field.
+    let (wire_type, mut value) = read_field(&buf, 2).unwrap().unwrap();
+    let mut id = 0;
+    int32::merge(wire_type, &mut id, &mut value, DecodeContext::default()).unwrap();
+    assert_eq!(id, 2);
+
+    let (wire_type, mut value) = read_field(&buf, 3).unwrap().unwrap();
+    let mut inner = Request::default();
+    message::merge(wire_type, &mut inner, &mut value, DecodeContext::default()).unwrap();
+    assert_eq!(inner.route, "b");
+
+    assert_eq!(read_field(&buf, 5).unwrap(), None);
+    assert!(read_field(&buf[..buf.len() - 1], 1).is_err());
+}
+
+#[test]
+fn test_replace_field() {
+    let mut buf = request().encode_to_vec();
+    let mut field = Vec::new();
+    string::encode(1, &"route".to_string(), &mut field);
+    replace_field(&mut buf, 1, &field).unwrap();
+
+    let mut expected = request();
+    expected.route = "route".to_string();
+    assert_eq!(buf, expected.encode_to_vec());
+
+    // All occurrences are replaced.
+    let mut field = Vec::new();
+    int32::encode_repeated(2, &[7, 8, 9], &mut field);
+    replace_field(&mut buf, 2, &field).unwrap();
+    expected.ids = vec![7, 8, 9];
+    assert_eq!(buf, expected.encode_to_vec());
+
+    remove_field(&mut buf, 2).unwrap();
+    expected.ids.clear();
+    assert_eq!(buf, expected.encode_to_vec());
+
+    // Missing fields are appended.
+    let mut field = Vec::new();
+    int32::encode(2, &3, &mut field);
+    replace_field(&mut buf, 2, &field).unwrap();
+    expected.ids = vec![3];
+    assert_eq!(Request::decode(&buf[..]).unwrap(), expected);
+}
+
+#[test]
+fn test_replace_field_errors() {
+    let mut buf = request().encode_to_vec();
+    let original = buf.clone();
+
+    let mut field = Vec::new();
+    string::encode(5, &"route".to_string(), &mut field);
+    assert!(replace_field(&mut buf, 1, &field).is_err());
+    assert!(replace_field(&mut buf, 1, &[0x0a]).is_err());
+    assert_eq!(buf, original);
+
+    let mut truncated = original[..original.len() - 1].to_vec();
+    assert!(remove_field(&mut truncated, 1).is_err());
+    assert_eq!(truncated, &original[..original.len() - 1]);
+
+    // Groups are skipped as a whole, and stray end group keys are rejected.
+    let mut group = vec![0x0b, 0x08, 0x01, 0x0c, 0x10, 0x05];
+    remove_field(&mut group, 1).unwrap();
+    assert_eq!(group, [0x10, 0x05]);
+    assert!(read_field(&[0x0c], 1).is_err());
+    assert_eq!(
+        read_field(&[0x0b, 0x0c], 1).unwrap(),
+        Some((WireType::StartGroup, &[0x0c][..]))
+    );
+}
@@ -0,0 +1,39 @@
+use alloc::string::String;
+use alloc::vec;
+
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/unwrapped_wrappers.rs"));
+
+#[test]
+fn test_unwrapped_wrappers() {
+    let settings = Settings {
+        retries: Some(3),
+        enabled: None,
+        name: Some(String::from("a")),
+        ids: vec![0, 1],
+    };
+    let buf = settings.encode_to_vec();
+    assert_eq!(
+        buf,
+        [
+            0x0a, 0x02, 0x08, 0x03, // retries
+            0x1a, 0x03, 0x0a, 0x01, b'a', // name
+            0x22, 0x00, // ids
+            0x22, 0x02, 0x08, 0x01,
+        ]
+    );
+    assert_eq!(Settings::decode(&buf[..]).unwrap(), settings);
+}
+
+#[test]
+fn test_presence() {
+    // Default values are encoded as empty wrappers, and unset fields are not encoded.
+    let settings = Settings {
+        enabled: Some(false),
+        ..Settings::default()
+    };
+    assert_eq!(settings.encode_to_vec(), [0x12, 0x00]);
+    assert_eq!(Settings::decode(&[0x12, 0x00][..]).unwrap(), settings);
+    assert_eq!(Settings::default().encoded_len(), 0);
+}
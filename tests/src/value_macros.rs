@@ -0,0 +1,63 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+
+use prost_types::value::Kind;
+use prost_types::{struct_value, value, ListValue, NullValue, Struct, Value};
+
+#[test]
+fn value_macro_scalars() {
+    assert_eq!(
+        value!(null),
+        Value::from(Kind::NullValue(NullValue::NullValue as i32))
+    );
+    assert_eq!(value!(true), Value::from(true));
+    assert_eq!(value!(-1.5), Value::from(-1.5));
+    assert_eq!(value!("x"), Value::from("x"));
+
+    let nested = value!([1]);
+    assert_eq!(value!(nested.clone()), nested);
+}
+
+#[test]
+fn value_macro_trees() {
+    let name = String::from("b");
+    let value = value!({
+        "a": 1,
+        (name): [true, "x", null, [], {}, -2 + 1],
+        "c": { "d": null },
+    });
+
+    let mut c = BTreeMap::new();
+    c.insert("d".to_string(), value!(null));
+    let mut fields = BTreeMap::new();
+    fields.insert("a".to_string(), Value::from(1));
+    fields.insert(
+        "b".to_string(),
+        Value::from(vec![
+            Value::from(true),
+            Value::from("x"),
+            value!(null),
+            Value::from(Kind::ListValue(ListValue::default())),
+            Value::from(Kind::StructValue(Struct::default())),
+            Value::from(-1),
+        ]),
+    );
+    fields.insert("c".to_string(), Value::from(c));
+    assert_eq!(value, Value::from(fields));
+
+    assert_eq!(
+        value.to_string(),
+        r#"{"a":1,"b":[true,"x",null,[],{},-1],"c":{"d":null}}"#
+    );
+}
+
+#[test]
+fn struct_value_macro() {
+    let labels = struct_value!({ "env": "prod", "replicas": 3 });
+    assert_eq!(labels.fields.len(), 2);
+    assert_eq!(labels.fields["replicas"], Value::from(3));
+    assert_eq!(labels.to_string(), r#"{"env":"prod","replicas":3}"#);
+
+    assert_eq!(struct_value!({}), Struct::default());
+}
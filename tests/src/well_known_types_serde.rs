@@ -0,0 +1,63 @@
+use alloc::string::ToString;
+
+use prost_types::{Duration, Timestamp};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Event {
+    at: Timestamp,
+    timeout: Duration,
+    expires: Option<Timestamp>,
+}
+
+#[test]
+fn json_round_trip() {
+    let event = Event {
+        at: Timestamp::date_time_nanos(1972, 1, 1, 10, 0, 20, 21_000_000).unwrap(),
+        timeout: Duration {
+            seconds: -1,
+            nanos: -500_000_000,
+        },
+        expires: None,
+    };
+    let json = serde_json::to_string(&event).unwrap();
+    assert_eq!(
+        json,
+        r#"{"at":"1972-01-01T10:00:20.021Z","timeout":"-1.500s","expires":null}"#
+    );
+    assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), event);
+}
+
+#[test]
+fn json_normalizes_values() {
+    let timestamp = Timestamp {
+        seconds: 0,
+        nanos: 1_000_000_001,
+    };
+    assert_eq!(
+        serde_json::to_string(&timestamp).unwrap(),
+        r#""1970-01-01T00:00:01.000000001Z""#
+    );
+    let duration = Duration {
+        seconds: 1,
+        nanos: -1_000,
+    };
+    assert_eq!(serde_json::to_string(&duration).unwrap(), r#""0.999999s""#);
+}
+
+#[test]
+fn json_rejects_values_out_of_range() {
+    let timestamp = Timestamp::date(10000, 1, 1).unwrap();
+    assert!(serde_json::to_string(&timestamp).is_err());
+    let duration = Duration {
+        seconds: 315_576_000_001,
+        nanos: 0,
+    };
+    assert!(serde_json::to_string(&duration).is_err());
+
+    assert!(serde_json::from_str::<Timestamp>(r#""10000-01-01T00:00:00Z""#).is_err());
+    assert!(serde_json::from_str::<Duration>(r#""315576000001s""#).is_err());
+    let error = serde_json::from_str::<Duration>(r#""1.5""#).unwrap_err();
+    assert!(error.to_string().contains("invalid value"));
+    assert!(serde_json::from_str::<Timestamp>("0").is_err());
+}
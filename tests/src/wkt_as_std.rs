@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/wkt_as_std.rs"));
+
+/// The same message, with the `prost_types` messages.
+#[derive(Clone, PartialEq, Message)]
+struct RawJob {
+    #[prost(message, optional, tag = "1")]
+    created: Option<prost_types::Timestamp>,
+    #[prost(message, optional, tag = "2")]
+    timeout: Option<prost_types::Duration>,
+    #[prost(message, repeated, tag = "3")]
+    retries: Vec<prost_types::Timestamp>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct SaturatingJob {
+    #[prost(timestamp = "saturate", optional, tag = "1")]
+    created: Option<SystemTime>,
+    #[prost(duration = "saturate", optional, tag = "2")]
+    timeout: Option<Duration>,
+}
+
+#[test]
+fn test_std_types() {
+    let created = UNIX_EPOCH + Duration::new(1_700_000_000, 5);
+    let job = Job {
+        created: Some(created),
+        timeout: Some(Duration::from_millis(1500)),
+        retries: vec![UNIX_EPOCH - Duration::from_secs(1), UNIX_EPOCH],
+        phases: HashMap::from([(
+            "build".to_string(),
+            prost_types::Duration {
+                seconds: 3,
+                nanos: 0,
+            },
+        )]),
+        deadline: Some(job::Deadline::After(Duration::from_secs(60))),
+    };
+    let buf = job.encode_to_vec();
+    assert_eq!(buf.len(), job.encoded_len());
+    assert_eq!(Job::decode(&buf[..]).unwrap(), job);
+
+    let raw = RawJob::decode(&buf[..]).unwrap();
+    assert_eq!(
+        raw.created,
+        Some(prost_types::Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 5,
+        })
+    );
+    assert_eq!(
+        raw.timeout,
+        Some(prost_types::Duration {
+            seconds: 1,
+            nanos: 500_000_000,
+        })
+    );
+    assert_eq!(
+        raw.retries,
+        [
+            prost_types::Timestamp {
+                seconds: -1,
+                nanos: 0,
+            },
+            prost_types::Timestamp::default(),
+        ]
+    );
+
+    // Unset fields are not encoded, while the epoch and zero durations are.
+    assert_eq!(Job::default().encoded_len(), 0);
+    let zero = Job {
+        timeout: Some(Duration::ZERO),
+        ..Job::default()
+    };
+    assert_eq!(zero.encode_to_vec(), [0x12, 0x00]);
+    assert_eq!(Job::decode(&[0x12, 0x00][..]).unwrap(), zero);
+}
+
+#[test]
+fn test_out_of_range() {
+    let raw = RawJob {
+        created: Some(prost_types::Timestamp {
+            seconds: i64::MIN,
+            nanos: -1,
+        }),
+        timeout: Some(prost_types::Duration {
+            seconds: -5,
+            nanos: 0,
+        }),
+        retries: Vec::new(),
+    };
+    let buf = raw.encode_to_vec();
+
+    let error = Job::decode(&buf[..]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: Job.created: timestamp out of range"
+    );
+
+    let job = SaturatingJob::decode(&buf[..]).unwrap();
+    assert!(job.created.unwrap() < UNIX_EPOCH);
+    assert_eq!(job.timeout, Some(Duration::ZERO));
+}